@@ -16,6 +16,13 @@ public class DeviceTransferKey {
     this.keyMaterial = Native.DeviceTransfer_GeneratePrivateKey();
   }
 
+  public DeviceTransferKey(DeviceTransferKeyType keyType) {
+    // Always PKCS#8, like the default constructor.
+    this.keyMaterial =
+        filterExceptions(
+            () -> Native.DeviceTransfer_GeneratePrivateKeyWithType(keyType.getValue(), 0));
+  }
+
   public byte[] keyMaterial() {
     return this.keyMaterial;
   }
@@ -24,4 +31,18 @@ public class DeviceTransferKey {
     return filterExceptions(
         () -> Native.DeviceTransfer_GenerateCertificate(this.keyMaterial, name, daysTilExpires));
   }
+
+  /**
+   * Computes the SHA-256 fingerprint of a DER-encoded certificate, to be exchanged out of band so
+   * that the other device can pin it.
+   */
+  public static byte[] getCertificateFingerprint(byte[] certificate) {
+    return filterExceptions(() -> Native.DeviceTransfer_GetCertificateFingerprint(certificate));
+  }
+
+  /** Checks a peer's DER-encoded certificate against the fingerprint exchanged out of band. */
+  public static boolean certificateMatchesFingerprint(byte[] certificate, byte[] fingerprint) {
+    return filterExceptions(
+        () -> Native.DeviceTransfer_CertificateMatchesFingerprint(certificate, fingerprint));
+  }
 }
@@ -0,0 +1,24 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.devicetransfer;
+
+/** The algorithm of a {@link DeviceTransferKey}. */
+public enum DeviceTransferKeyType {
+  /** RSA, with the same key size as {@link DeviceTransferKey#DeviceTransferKey()}. */
+  RSA(0),
+  /** ECDSA over the NIST P-256 curve, much faster to generate than RSA. */
+  ECDSA_P256(1);
+
+  private final int value;
+
+  DeviceTransferKeyType(int value) {
+    this.value = value;
+  }
+
+  int getValue() {
+    return this.value;
+  }
+}
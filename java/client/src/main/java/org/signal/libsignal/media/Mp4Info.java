@@ -0,0 +1,110 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.media;
+
+/** Information about an MP4 input, as reported by {@link Mp4Sanitizer#sanitizeWithInfo}. */
+public class Mp4Info {
+
+  private Long durationMillis;
+  private int videoWidth;
+  private int videoHeight;
+  private int videoRotation;
+  private String videoCodec;
+  private Long videoDurationMillis;
+  private String audioCodec;
+  private Long audioDurationMillis;
+
+  public Mp4Info(
+      Long durationMillis,
+      int videoWidth,
+      int videoHeight,
+      int videoRotation,
+      String videoCodec,
+      Long videoDurationMillis,
+      String audioCodec,
+      Long audioDurationMillis) {
+    this.durationMillis = durationMillis;
+    this.videoWidth = videoWidth;
+    this.videoHeight = videoHeight;
+    this.videoRotation = videoRotation;
+    this.videoCodec = videoCodec;
+    this.videoDurationMillis = videoDurationMillis;
+    this.audioCodec = audioCodec;
+    this.audioDurationMillis = audioDurationMillis;
+  }
+
+  /**
+   * Get the presentation duration of the movie.
+   *
+   * @return The duration in milliseconds, or {@code null} if it wasn't specified.
+   */
+  public Long getDurationMillis() {
+    return durationMillis;
+  }
+
+  /**
+   * Get the display width of the first video track.
+   *
+   * @return The width in pixels, or 0 if there is no video track.
+   */
+  public int getVideoWidth() {
+    return videoWidth;
+  }
+
+  /**
+   * Get the display height of the first video track.
+   *
+   * @return The height in pixels, or 0 if there is no video track.
+   */
+  public int getVideoHeight() {
+    return videoHeight;
+  }
+
+  /**
+   * Get the clockwise rotation to apply when displaying the first video track.
+   *
+   * @return The rotation in degrees: 0, 90, 180, or 270.
+   */
+  public int getVideoRotation() {
+    return videoRotation;
+  }
+
+  /**
+   * Get the codec of the first video track, such as {@code avc1}.
+   *
+   * @return The codec, or {@code null} if there is no video track or its codec is unknown.
+   */
+  public String getVideoCodec() {
+    return videoCodec;
+  }
+
+  /**
+   * Get the media duration of the first video track.
+   *
+   * @return The duration in milliseconds, or {@code null} if it wasn't specified.
+   */
+  public Long getVideoDurationMillis() {
+    return videoDurationMillis;
+  }
+
+  /**
+   * Get the codec of the first audio track, such as {@code mp4a}.
+   *
+   * @return The codec, or {@code null} if there is no audio track or its codec is unknown.
+   */
+  public String getAudioCodec() {
+    return audioCodec;
+  }
+
+  /**
+   * Get the media duration of the first audio track.
+   *
+   * @return The duration in milliseconds, or {@code null} if it wasn't specified.
+   */
+  public Long getAudioDurationMillis() {
+    return audioDurationMillis;
+  }
+}
@@ -65,13 +65,7 @@ public class Mp4Sanitizer {
             ParseException.class,
             () -> Native.Mp4Sanitizer_Sanitize(TrustedSkipInputStream.makeTrusted(input), length));
     try {
-      byte[] sanitizedMetadata = Native.SanitizedMetadata_GetMetadata(sanitizedMetadataHandle);
-      if (sanitizedMetadata.length == 0) {
-        sanitizedMetadata = null;
-      }
-      long dataOffset = Native.SanitizedMetadata_GetDataOffset(sanitizedMetadataHandle);
-      long dataLength = Native.SanitizedMetadata_GetDataLen(sanitizedMetadataHandle);
-      return new SanitizedMetadata(sanitizedMetadata, dataOffset, dataLength);
+      return readSanitizedMetadata(sanitizedMetadataHandle, null);
     } finally {
       Native.SanitizedMetadata_Destroy(sanitizedMetadataHandle);
     }
@@ -102,15 +96,95 @@ public class Mp4Sanitizer {
                 Native.Mp4Sanitizer_Sanitize_File_With_Compounded_MDAT_Boxes(
                     TrustedSkipInputStream.makeTrusted(input), length, cumulativeMdatBoxSize));
     try {
-      byte[] sanitizedMetadata = Native.SanitizedMetadata_GetMetadata(sanitizedMetadataHandle);
-      if (sanitizedMetadata.length == 0) {
-        sanitizedMetadata = null;
-      }
-      long dataOffset = Native.SanitizedMetadata_GetDataOffset(sanitizedMetadataHandle);
-      long dataLength = Native.SanitizedMetadata_GetDataLen(sanitizedMetadataHandle);
-      return new SanitizedMetadata(sanitizedMetadata, dataOffset, dataLength);
+      return readSanitizedMetadata(sanitizedMetadataHandle, null);
     } finally {
       Native.SanitizedMetadata_Destroy(sanitizedMetadataHandle);
     }
   }
+
+  /**
+   * Sanitize an MP4 input, additionally reporting its duration, dimensions, and codecs.
+   *
+   * <p>This behaves like {@link #sanitize}, but the result's {@link SanitizedMetadata#getInfo} is
+   * populated as well.
+   *
+   * @param input An MP4 format input stream.
+   * @param length The exact length of the input stream.
+   * @return The sanitized metadata.
+   * @throws IOException If an IO error on the input occurs.
+   * @throws ParseException If the input could not be parsed.
+   */
+  public static SanitizedMetadata sanitizeWithInfo(InputStream input, long length)
+      throws IOException, ParseException {
+    long sanitizedMetadataHandle =
+        filterExceptions(
+            IOException.class,
+            ParseException.class,
+            () ->
+                Native.Mp4Sanitizer_SanitizeWithInfo(
+                    TrustedSkipInputStream.makeTrusted(input), length));
+    try {
+      return readSanitizedMetadata(sanitizedMetadataHandle, readInfo(sanitizedMetadataHandle));
+    } finally {
+      Native.SanitizedMetadata_Destroy(sanitizedMetadataHandle);
+    }
+  }
+
+  /**
+   * Sanitize an MP4 input featuring multiple MDAT boxes compounded to a single cumulative MDAT box,
+   * additionally reporting its duration, dimensions, and codecs.
+   *
+   * @param input An MP4 format input stream.
+   * @param length The exact length of the input stream.
+   * @param cumulativeMdatBoxSize The byte length of cumulative, compounded MDAT box
+   * @return The sanitized metadata.
+   * @throws IOException If an IO error on the input occurs.
+   * @throws ParseException If the input could not be parsed.
+   * @see #sanitizeFileWithCompoundedMdatBoxes
+   */
+  public static SanitizedMetadata sanitizeWithInfoFileWithCompoundedMdatBoxes(
+      InputStream input, long length, int cumulativeMdatBoxSize)
+      throws IOException, ParseException {
+    long sanitizedMetadataHandle =
+        filterExceptions(
+            IOException.class,
+            ParseException.class,
+            () ->
+                Native.Mp4Sanitizer_SanitizeWithInfo_File_With_Compounded_MDAT_Boxes(
+                    TrustedSkipInputStream.makeTrusted(input), length, cumulativeMdatBoxSize));
+    try {
+      return readSanitizedMetadata(sanitizedMetadataHandle, readInfo(sanitizedMetadataHandle));
+    } finally {
+      Native.SanitizedMetadata_Destroy(sanitizedMetadataHandle);
+    }
+  }
+
+  private static SanitizedMetadata readSanitizedMetadata(
+      long sanitizedMetadataHandle, Mp4Info info) {
+    byte[] sanitizedMetadata = Native.SanitizedMetadata_GetMetadata(sanitizedMetadataHandle);
+    if (sanitizedMetadata.length == 0) {
+      sanitizedMetadata = null;
+    }
+    long dataOffset = Native.SanitizedMetadata_GetDataOffset(sanitizedMetadataHandle);
+    long dataLength = Native.SanitizedMetadata_GetDataLen(sanitizedMetadataHandle);
+    return new SanitizedMetadata(sanitizedMetadata, dataOffset, dataLength, info);
+  }
+
+  private static Mp4Info readInfo(long sanitizedMetadataHandle) {
+    String videoCodec = Native.SanitizedMetadata_GetVideoCodec(sanitizedMetadataHandle);
+    String audioCodec = Native.SanitizedMetadata_GetAudioCodec(sanitizedMetadataHandle);
+    return new Mp4Info(
+        durationOrNull(Native.SanitizedMetadata_GetDurationMillis(sanitizedMetadataHandle)),
+        Native.SanitizedMetadata_GetVideoWidth(sanitizedMetadataHandle),
+        Native.SanitizedMetadata_GetVideoHeight(sanitizedMetadataHandle),
+        Native.SanitizedMetadata_GetVideoRotation(sanitizedMetadataHandle),
+        videoCodec.isEmpty() ? null : videoCodec,
+        durationOrNull(Native.SanitizedMetadata_GetVideoDurationMillis(sanitizedMetadataHandle)),
+        audioCodec.isEmpty() ? null : audioCodec,
+        durationOrNull(Native.SanitizedMetadata_GetAudioDurationMillis(sanitizedMetadataHandle)));
+  }
+
+  private static Long durationOrNull(long durationMillis) {
+    return durationMillis == -1 ? null : durationMillis;
+  }
 }
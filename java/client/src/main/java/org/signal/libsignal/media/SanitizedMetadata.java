@@ -10,11 +10,18 @@ public class SanitizedMetadata {
   private byte[] sanitizedMetadata;
   private long dataOffset;
   private long dataLength;
+  private Mp4Info info;
 
   public SanitizedMetadata(byte[] sanitizedMetadata, long dataOffset, long dataLength) {
+    this(sanitizedMetadata, dataOffset, dataLength, null);
+  }
+
+  public SanitizedMetadata(
+      byte[] sanitizedMetadata, long dataOffset, long dataLength, Mp4Info info) {
     this.sanitizedMetadata = sanitizedMetadata;
     this.dataOffset = dataOffset;
     this.dataLength = dataLength;
+    this.info = info;
   }
 
   /**
@@ -43,4 +50,14 @@ public class SanitizedMetadata {
   public long getDataLength() {
     return dataLength;
   }
+
+  /**
+   * Get the duration, dimensions, and codecs of the input.
+   *
+   * @return The media information, or {@code null} if it wasn't requested from the sanitizer.
+   * @see Mp4Sanitizer#sanitizeWithInfo
+   */
+  public Mp4Info getInfo() {
+    return info;
+  }
 }
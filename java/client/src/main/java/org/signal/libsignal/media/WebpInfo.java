@@ -0,0 +1,58 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.media;
+
+/** Information about a WebP input, as reported by {@link WebpSanitizer#sanitizeWithInfo}. */
+public class WebpInfo {
+
+  private int width;
+  private int height;
+  private boolean animated;
+  private int frameCount;
+
+  public WebpInfo(int width, int height, boolean animated, int frameCount) {
+    this.width = width;
+    this.height = height;
+    this.animated = animated;
+    this.frameCount = frameCount;
+  }
+
+  /**
+   * Get the canvas width of the image.
+   *
+   * @return The width in pixels, or 0 if it could not be determined.
+   */
+  public int getWidth() {
+    return width;
+  }
+
+  /**
+   * Get the canvas height of the image.
+   *
+   * @return The height in pixels, or 0 if it could not be determined.
+   */
+  public int getHeight() {
+    return height;
+  }
+
+  /**
+   * Whether the image is marked as animated.
+   *
+   * @return {@code true} if the image is animated.
+   */
+  public boolean isAnimated() {
+    return animated;
+  }
+
+  /**
+   * Get the number of frames in the image.
+   *
+   * @return The number of animation frames, or 1 for a still image.
+   */
+  public int getFrameCount() {
+    return frameCount;
+  }
+}
@@ -35,4 +35,29 @@ public class WebpSanitizer {
         ParseException.class,
         () -> Native.WebpSanitizer_Sanitize(TrustedSkipInputStream.makeTrusted(input)));
   }
+
+  /**
+   * Sanitize a WebP input, additionally reporting its dimensions and animation.
+   *
+   * @param input A WebP format input stream.
+   * @return The information found while sanitizing.
+   * @throws IOException If an IO error on the input occurs.
+   * @throws ParseException If the input could not be parsed.
+   */
+  public static WebpInfo sanitizeWithInfo(InputStream input) throws IOException, ParseException {
+    long sanitizedHandle =
+        filterExceptions(
+            IOException.class,
+            ParseException.class,
+            () -> Native.WebpSanitizer_SanitizeWithInfo(TrustedSkipInputStream.makeTrusted(input)));
+    try {
+      return new WebpInfo(
+          Native.SanitizedWebp_GetWidth(sanitizedHandle),
+          Native.SanitizedWebp_GetHeight(sanitizedHandle),
+          Native.SanitizedWebp_IsAnimated(sanitizedHandle),
+          Native.SanitizedWebp_GetFrameCount(sanitizedHandle));
+    } finally {
+      Native.SanitizedWebp_Destroy(sanitizedHandle);
+    }
+  }
 }
@@ -0,0 +1,150 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.messagebackup;
+
+import static org.signal.libsignal.internal.FilterExceptions.filterExceptions;
+
+import java.io.IOException;
+import java.io.InputStream;
+import java.util.Collections;
+import java.util.LinkedHashMap;
+import java.util.Map;
+import java.util.function.Supplier;
+import org.signal.libsignal.internal.CalledFromNative;
+import org.signal.libsignal.internal.Native;
+import org.signal.libsignal.internal.NativeHandleGuard;
+
+/**
+ * Summary statistics about the contents of a message backup.
+ *
+ * <p>Collecting statistics only checks that each frame parses; it does not validate the backup
+ * the way {@link MessageBackup#validate} does. This makes it suitable for showing a user what's in
+ * a backup before restoring it.
+ */
+public class MessageBackupStatistics extends NativeHandleGuard.SimpleOwner {
+  /**
+   * Reads an encrypted message backup bundle and summarizes its contents.
+   *
+   * @param key the key to use to decrypt the backup
+   * @param purpose whether the input was created for device-to-device transfer or remote backup
+   * @param streamFactory a factory for <code>InputStream</code>s that produce the input
+   * @param streamLength the number of bytes each <code>InputStream</code> will produce
+   * @throws ValidationError with an error message if the input could not be parsed
+   * @throws IOException if the input could not be read
+   */
+  public static MessageBackupStatistics collect(
+      MessageBackupKey key,
+      MessageBackup.Purpose purpose,
+      Supplier<InputStream> streamFactory,
+      long streamLength)
+      throws ValidationError, IOException {
+    try (InputStream first = streamFactory.get();
+        InputStream second = streamFactory.get();
+        NativeHandleGuard keyGuard = new NativeHandleGuard(key)) {
+      long handle =
+          filterExceptions(
+              IOException.class,
+              ValidationError.class,
+              () ->
+                  Native.MessageBackupStatistics_Collect(
+                      keyGuard.nativeHandle(), first, second, streamLength, purpose.ordinal()));
+      return new MessageBackupStatistics(handle);
+    }
+  }
+
+  @CalledFromNative
+  private MessageBackupStatistics(long nativeHandle) {
+    super(nativeHandle);
+  }
+
+  @Override
+  protected void release(long nativeHandle) {
+    Native.MessageBackupStatistics_Destroy(nativeHandle);
+  }
+
+  /** The kinds of frames present in the backup, such as {@code "ChatItem.StandardMessage"}. */
+  public String[] getFrameKinds() {
+    return (String[]) guardedMap(Native::MessageBackupStatistics_GetFrameKinds);
+  }
+
+  /** The number of frames of the given kind. */
+  public long getFrameCount(String kind) {
+    return guardedMap(handle -> Native.MessageBackupStatistics_GetFrameCount(handle, kind));
+  }
+
+  /** The total size of all frames of the given kind, once decrypted and decompressed. */
+  public long getFrameBytes(String kind) {
+    return guardedMap(handle -> Native.MessageBackupStatistics_GetFrameBytes(handle, kind));
+  }
+
+  /** The total size of all frames, once decrypted and decompressed. */
+  public long getTotalFrameBytes() {
+    return guardedMap(Native::MessageBackupStatistics_GetTotalFrameBytes);
+  }
+
+  /** The size of the backup file the statistics were collected from. */
+  public long getBackupFileBytes() {
+    return guardedMap(Native::MessageBackupStatistics_GetBackupFileBytes);
+  }
+
+  /**
+   * The ratio of the decompressed frame data to the size of the backup file.
+   *
+   * <p>Returns 0 if the backup file was empty.
+   */
+  public double getCompressionRatio() {
+    long backupFileBytes = getBackupFileBytes();
+    if (backupFileBytes == 0) {
+      return 0;
+    }
+    return (double) getTotalFrameBytes() / backupFileBytes;
+  }
+
+  /** The number of attachments referenced anywhere in the backup. */
+  public long getAttachmentCount() {
+    return guardedMap(Native::MessageBackupStatistics_GetAttachmentCount);
+  }
+
+  /** The sum of the sizes of all attachments, as recorded in the backup. */
+  public long getAttachmentBytes() {
+    return guardedMap(Native::MessageBackupStatistics_GetAttachmentBytes);
+  }
+
+  /** The number of chats that contain at least one message. */
+  public long getChatCount() {
+    return guardedMap(Native::MessageBackupStatistics_GetChatCount);
+  }
+
+  /** The total number of chat items across all chats. */
+  public long getMessageCount() {
+    return guardedMap(Native::MessageBackupStatistics_GetMessageCount);
+  }
+
+  /**
+   * The number of chat items in each chat, keyed by the chat's ID within the backup.
+   *
+   * <p>Chats are ordered by ascending ID.
+   */
+  public Map<Long, Long> getMessageCountsPerChat() {
+    return guardedMap(
+        handle -> {
+          Map<Long, Long> counts = new LinkedHashMap<>();
+          for (int i = 0; ; ++i) {
+            long chatId = Native.MessageBackupStatistics_GetChatIdAt(handle, i);
+            if (chatId == -1) {
+              break;
+            }
+            counts.put(chatId, Native.MessageBackupStatistics_GetMessageCountForChat(handle, chatId));
+          }
+          return Collections.unmodifiableMap(counts);
+        });
+  }
+
+  /** The number of unknown fields and enum values encountered while reading the backup. */
+  public long getUnknownFieldCount() {
+    return guardedMap(Native::MessageBackupStatistics_GetUnknownFieldCount);
+  }
+}
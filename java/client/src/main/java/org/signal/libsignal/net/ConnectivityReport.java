@@ -0,0 +1,94 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.net;
+
+import org.signal.libsignal.internal.Native;
+import org.signal.libsignal.internal.NativeHandleGuard;
+
+/**
+ * A snapshot of connection health since the last network change.
+ *
+ * <p>Routes are only grouped by kind, such as {@code "direct"} or {@code "fronted+socks"}; nothing
+ * here identifies a particular host, address, or proxy.
+ *
+ * @see Network#getConnectivityReport
+ */
+public class ConnectivityReport extends NativeHandleGuard.SimpleOwner {
+  ConnectivityReport(long nativeHandle) {
+    super(nativeHandle);
+  }
+
+  @Override
+  protected void release(long nativeHandle) {
+    Native.ConnectivityReport_Destroy(nativeHandle);
+  }
+
+  /**
+   * The number of buckets returned by {@link #getLatencyHistogram}.
+   *
+   * <p>Buckets are ordered from fastest to slowest; the last one has no upper bound.
+   */
+  public static int getLatencyBucketCount() {
+    return Native.ConnectivityReport_latency_bucket_count();
+  }
+
+  /** How long statistics have been collected for, in seconds. */
+  public long getCollectionPeriodSeconds() {
+    return guardedMap(Native::ConnectivityReport_collection_period_seconds);
+  }
+
+  /** The kinds of route that were attempted, in the order each was first tried. */
+  public String[] getRouteKinds() {
+    return (String[]) guardedMap(Native::ConnectivityReport_route_kinds);
+  }
+
+  /** The number of successful connection attempts over the given kind of route. */
+  public long getRouteSuccesses(String kind) {
+    return guardedMap(handle -> Native.ConnectivityReport_route_successes(handle, kind));
+  }
+
+  /** The number of failed connection attempts over the given kind of route. */
+  public long getRouteFailures(String kind) {
+    return guardedMap(handle -> Native.ConnectivityReport_route_failures(handle, kind));
+  }
+
+  /**
+   * The number of successful connections over the given kind of route that fell in each latency
+   * bucket.
+   *
+   * @see #getLatencyBucketCount
+   */
+  public long[] getLatencyHistogram(String kind) {
+    return guardedMap(
+        handle -> {
+          long[] counts = new long[getLatencyBucketCount()];
+          for (int i = 0; i < counts.length; ++i) {
+            counts[i] = Native.ConnectivityReport_latency_bucket(handle, kind, i);
+          }
+          return counts;
+        });
+  }
+
+  /** The number of hostname lookups answered by the system resolver. */
+  public long getDnsSystemLookups() {
+    return guardedMap(Native::ConnectivityReport_dns_system_lookups);
+  }
+
+  /** The number of hostname lookups answered over DNS-over-HTTPS. */
+  public long getDnsDohLookups() {
+    return guardedMap(Native::ConnectivityReport_dns_doh_lookups);
+  }
+
+  /** The number of hostname lookups answered over DNS-over-TLS. */
+  public long getDnsDotLookups() {
+    return guardedMap(Native::ConnectivityReport_dns_dot_lookups);
+  }
+
+  /** The number of hostname lookups answered from the built-in static fallback. */
+  public long getDnsStaticLookups() {
+    return guardedMap(Native::ConnectivityReport_dns_static_lookups);
+  }
+}
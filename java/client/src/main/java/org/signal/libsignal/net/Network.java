@@ -156,6 +156,29 @@ public class Network {
     this.connectionManager.setProxy(SIGNAL_TLS_PROXY_SCHEME, host, port, username, null);
   }
 
+  /**
+   * Sets a pluggable transport bridge to be used for all new connections (until overridden).
+   *
+   * <p>The app is responsible for running a Pluggable Transports client (such as an obfs4 library)
+   * that listens for SOCKS5 connections on {@code localPort}. New connections will ask that client
+   * to connect to the bridge at {@code bridgeHost}:{@code bridgePort}, passing along {@code
+   * bridgeArgs}: the bridge's space-separated {@code key=value} arguments, as found at the end of a
+   * bridge line. The proxy can be overridden by calling this method again or unset by calling
+   * {@link #clearProxy}.
+   *
+   * <p>Existing connections and services will continue with the setting they were created with. (In
+   * particular, changing this setting will not affect any existing {@link ChatConnection
+   * ChatConnections}.)
+   *
+   * @throws IOException if the ports are not valid, or if the bridge arguments are malformed or
+   *     too long
+   */
+  public void setPluggableTransportProxy(
+      int localPort, String bridgeHost, int bridgePort, String bridgeArgs) throws IOException {
+    this.connectionManager.setPluggableTransportProxy(
+        localPort, bridgeHost, bridgePort, bridgeArgs);
+  }
+
   /**
    * Refuses to make any new connections until a new proxy configuration is set or {@link
    * #clearProxy} is called.
@@ -247,6 +270,16 @@ public class Network {
     connectionManager.guardedRun(Native::ConnectionManager_on_network_change);
   }
 
+  /**
+   * Summarizes connection attempts made since the last network change.
+   *
+   * <p>The report contains only aggregate counts, so it's suitable for uploading.
+   */
+  public ConnectivityReport getConnectivityReport() {
+    return new ConnectivityReport(
+        connectionManager.guardedMap(Native::ConnectionManager_get_connectivity_report));
+  }
+
   public CompletableFuture<CdsiLookupResponse> cdsiLookup(
       String username, String password, CdsiLookupRequest request, Consumer<byte[]> tokenConsumer)
       throws IOException, InterruptedException, ExecutionException {
@@ -426,7 +459,27 @@ public class Network {
         setInvalidProxy();
         throw e;
       }
+      setProxyConfig(rawProxyConfig);
+    }
+
+    private void setPluggableTransportProxy(
+        int localPort, String bridgeHost, int bridgePort, String bridgeArgs) throws IOException {
+      long rawProxyConfig;
+      try {
+        rawProxyConfig =
+            filterExceptions(
+                IOException.class,
+                () ->
+                    Native.ConnectionProxyConfig_newPluggableTransport(
+                        localPort, bridgeHost, bridgePort, bridgeArgs));
+      } catch (IOException | RuntimeException | Error e) {
+        setInvalidProxy();
+        throw e;
+      }
+      setProxyConfig(rawProxyConfig);
+    }
 
+    private void setProxyConfig(long rawProxyConfig) {
       try {
         guardedRun(h -> Native.ConnectionManager_set_proxy(h, rawProxyConfig));
       } finally {
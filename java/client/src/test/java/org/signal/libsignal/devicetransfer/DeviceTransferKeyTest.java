@@ -5,6 +5,10 @@
 
 package org.signal.libsignal.devicetransfer;
 
+import static org.junit.Assert.assertEquals;
+import static org.junit.Assert.assertFalse;
+import static org.junit.Assert.assertTrue;
+
 import java.io.ByteArrayInputStream;
 import java.security.cert.CertificateFactory;
 import org.junit.Test;
@@ -18,4 +22,26 @@ public class DeviceTransferKeyTest {
     CertificateFactory cf = CertificateFactory.getInstance("X.509");
     cf.generateCertificate(new ByteArrayInputStream(certBytes));
   }
+
+  @Test
+  public void testEcdsaDeviceTransferKey() throws Exception {
+    DeviceTransferKey key = new DeviceTransferKey(DeviceTransferKeyType.ECDSA_P256);
+    byte[] certBytes = key.generateCertificate("name", 365);
+
+    CertificateFactory cf = CertificateFactory.getInstance("X.509");
+    cf.generateCertificate(new ByteArrayInputStream(certBytes));
+  }
+
+  @Test
+  public void testCertificateFingerprint() throws Exception {
+    byte[] certBytes =
+        new DeviceTransferKey(DeviceTransferKeyType.ECDSA_P256).generateCertificate("name", 365);
+    byte[] otherCertBytes =
+        new DeviceTransferKey(DeviceTransferKeyType.ECDSA_P256).generateCertificate("name", 365);
+
+    byte[] fingerprint = DeviceTransferKey.getCertificateFingerprint(certBytes);
+    assertEquals(32, fingerprint.length);
+    assertTrue(DeviceTransferKey.certificateMatchesFingerprint(certBytes, fingerprint));
+    assertFalse(DeviceTransferKey.certificateMatchesFingerprint(otherCertBytes, fingerprint));
+  }
 }
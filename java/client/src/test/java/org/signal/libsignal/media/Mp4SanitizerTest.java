@@ -72,6 +72,28 @@ public class Mp4SanitizerTest {
         sanitized, ftyp().length, mp4Data.length - metadata.length, metadata);
   }
 
+  @Test
+  public void testMinimalMp4WithInfo() throws Exception {
+    byte[] metadata = ByteUtil.combine(ftyp(), moov());
+    byte[] mp4Data = ByteUtil.combine(ftyp(), mdat(), moov());
+
+    Assert.assertNull(
+        Mp4Sanitizer.sanitize(new ByteArrayInputStream(mp4Data), mp4Data.length).getInfo());
+
+    SanitizedMetadata sanitized =
+        Mp4Sanitizer.sanitizeWithInfo(new ByteArrayInputStream(mp4Data), mp4Data.length);
+
+    assertSanitizedMetadataEquals(
+        sanitized, ftyp().length, mp4Data.length - metadata.length, metadata);
+    Mp4Info info = sanitized.getInfo();
+    Assert.assertNotNull(info);
+    Assert.assertNull(info.getDurationMillis());
+    Assert.assertEquals(0, info.getVideoWidth());
+    Assert.assertEquals(0, info.getVideoHeight());
+    Assert.assertNull(info.getVideoCodec());
+    Assert.assertNull(info.getAudioCodec());
+  }
+
   @Test
   public void testMinimalCompoundedMdatMp4() throws Exception {
     byte[] metadata = ByteUtil.combine(ftyp(), moov());
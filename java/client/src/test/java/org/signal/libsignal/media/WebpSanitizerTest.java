@@ -5,6 +5,8 @@
 
 package org.signal.libsignal.media;
 
+import static org.junit.Assert.assertEquals;
+import static org.junit.Assert.assertFalse;
 import static org.junit.Assert.assertThrows;
 
 import java.io.ByteArrayInputStream;
@@ -51,6 +53,16 @@ public class WebpSanitizerTest {
     WebpSanitizer.sanitize(new ByteArrayInputStream(data));
   }
 
+  @Test
+  public void testMinimalWebpWithInfo() throws Exception {
+    byte[] data = webp();
+    WebpInfo info = WebpSanitizer.sanitizeWithInfo(new ByteArrayInputStream(data));
+    assertEquals(1, info.getWidth());
+    assertEquals(1, info.getHeight());
+    assertFalse(info.isAnimated());
+    assertEquals(1, info.getFrameCount());
+  }
+
   @Test
   public void testWebpIoError() throws Exception {
     try (InputStream ioErrorStream = new IoErrorInputStream()) {
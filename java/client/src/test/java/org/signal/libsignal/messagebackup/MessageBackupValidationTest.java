@@ -91,6 +91,27 @@ public class MessageBackupValidationTest {
     assertArrayEquals(result2.unknownFieldMessages, new String[0]);
   }
 
+  @Test
+  public void collectStatistics() throws IOException, ValidationError {
+    Supplier<InputStream> factory =
+        () -> {
+          return MessageBackupValidationTest.class.getResourceAsStream(VALID_BACKUP_RESOURCE_NAME);
+        };
+    final long length;
+    try (InputStream input = factory.get()) {
+      length = ResourceReader.readAll(input).length;
+    }
+    MessageBackupStatistics stats =
+        MessageBackupStatistics.collect(makeMessageBackupKey(), BACKUP_PURPOSE, factory, length);
+    assertTrue(Arrays.asList(stats.getFrameKinds()).contains("BackupInfo"));
+    assertEquals(1, stats.getFrameCount("BackupInfo"));
+    assertEquals(0, stats.getFrameCount("not a frame kind"));
+    assertEquals(length, stats.getBackupFileBytes());
+    assertTrue(stats.getCompressionRatio() > 0);
+    assertEquals(stats.getChatCount(), stats.getMessageCountsPerChat().size());
+    assertEquals(0, stats.getUnknownFieldCount());
+  }
+
   @Test
   public void onlineValidation() throws IOException, ValidationError {
     final InputStream input = ComparableBackupTest.getCanonicalBackupInputStream();
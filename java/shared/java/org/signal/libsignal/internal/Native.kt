@@ -422,6 +422,8 @@ internal object Native {
   @JvmStatic
   public external fun ConnectionManager_clear_proxy(connectionManager: ObjectHandle): Unit
   @JvmStatic
+  public external fun ConnectionManager_get_connectivity_report(connectionManager: ObjectHandle): ObjectHandle
+  @JvmStatic
   public external fun ConnectionManager_new(environment: Int, userAgent: String, remoteConfig: ObjectHandle, buildVariant: Int): ObjectHandle
   @JvmStatic
   public external fun ConnectionManager_on_network_change(connectionManager: ObjectHandle): Unit
@@ -438,6 +440,31 @@ internal object Native {
   public external fun ConnectionProxyConfig_Destroy(handle: ObjectHandle): Unit
   @JvmStatic @Throws(Exception::class)
   public external fun ConnectionProxyConfig_new(scheme: String, host: String, port: Int, username: String?, password: String?): ObjectHandle
+  @JvmStatic @Throws(Exception::class)
+  public external fun ConnectionProxyConfig_newPluggableTransport(localPort: Int, bridgeHost: String, bridgePort: Int, bridgeArgs: String): ObjectHandle
+
+  @JvmStatic
+  public external fun ConnectivityReport_Destroy(handle: ObjectHandle): Unit
+  @JvmStatic
+  public external fun ConnectivityReport_collection_period_seconds(report: ObjectHandle): Long
+  @JvmStatic
+  public external fun ConnectivityReport_dns_doh_lookups(report: ObjectHandle): Long
+  @JvmStatic
+  public external fun ConnectivityReport_dns_dot_lookups(report: ObjectHandle): Long
+  @JvmStatic
+  public external fun ConnectivityReport_dns_static_lookups(report: ObjectHandle): Long
+  @JvmStatic
+  public external fun ConnectivityReport_dns_system_lookups(report: ObjectHandle): Long
+  @JvmStatic
+  public external fun ConnectivityReport_latency_bucket(report: ObjectHandle, kind: String, bucket: Int): Long
+  @JvmStatic
+  public external fun ConnectivityReport_latency_bucket_count(): Int
+  @JvmStatic
+  public external fun ConnectivityReport_route_failures(report: ObjectHandle, kind: String): Long
+  @JvmStatic
+  public external fun ConnectivityReport_route_kinds(report: ObjectHandle): Array<Object>
+  @JvmStatic
+  public external fun ConnectivityReport_route_successes(report: ObjectHandle, kind: String): Long
 
   @JvmStatic
   public external fun CopyBackupMediaStream_Destroy(handle: ObjectHandle): Unit
@@ -521,10 +548,16 @@ internal object Native {
   @JvmStatic
   public external fun DeleteBackupMediaStream_next(asyncRuntime: ObjectHandle, stream: SimpleOwner): CompletableFuture<Object>
 
+  @JvmStatic @Throws(Exception::class)
+  public external fun DeviceTransfer_CertificateMatchesFingerprint(certificate: ByteArray, fingerprint: ByteArray): Boolean
   @JvmStatic @Throws(Exception::class)
   public external fun DeviceTransfer_GenerateCertificate(privateKey: ByteArray, name: String, daysToExpire: Int): ByteArray
   @JvmStatic
   public external fun DeviceTransfer_GeneratePrivateKey(): ByteArray
+  @JvmStatic @Throws(Exception::class)
+  public external fun DeviceTransfer_GeneratePrivateKeyWithType(keyType: Int, keyFormat: Int): ByteArray
+  @JvmStatic @Throws(Exception::class)
+  public external fun DeviceTransfer_GetCertificateFingerprint(certificate: ByteArray): ByteArray
 
   @JvmStatic @Throws(Exception::class)
   public external fun DonationPermitDerivedKeyPair_CheckValidContents(buffer: ByteArray): Unit
@@ -825,12 +858,45 @@ internal object Native {
   @JvmStatic
   public external fun MessageBackupKey_GetHmacKey(key: ObjectHandle): ByteArray
 
+  @JvmStatic @Throws(Exception::class)
+  public external fun MessageBackupStatistics_Collect(key: ObjectHandle, firstStream: InputStream, secondStream: InputStream, len: Long, purpose: Int): ObjectHandle
+  @JvmStatic
+  public external fun MessageBackupStatistics_Destroy(handle: ObjectHandle): Unit
+  @JvmStatic
+  public external fun MessageBackupStatistics_GetAttachmentBytes(stats: ObjectHandle): Long
+  @JvmStatic
+  public external fun MessageBackupStatistics_GetAttachmentCount(stats: ObjectHandle): Long
+  @JvmStatic
+  public external fun MessageBackupStatistics_GetBackupFileBytes(stats: ObjectHandle): Long
+  @JvmStatic
+  public external fun MessageBackupStatistics_GetChatCount(stats: ObjectHandle): Long
+  @JvmStatic
+  public external fun MessageBackupStatistics_GetChatIdAt(stats: ObjectHandle, index: Int): Long
+  @JvmStatic
+  public external fun MessageBackupStatistics_GetFrameBytes(stats: ObjectHandle, kind: String): Long
+  @JvmStatic
+  public external fun MessageBackupStatistics_GetFrameCount(stats: ObjectHandle, kind: String): Long
+  @JvmStatic
+  public external fun MessageBackupStatistics_GetFrameKinds(stats: ObjectHandle): Array<Object>
+  @JvmStatic
+  public external fun MessageBackupStatistics_GetMessageCount(stats: ObjectHandle): Long
+  @JvmStatic
+  public external fun MessageBackupStatistics_GetMessageCountForChat(stats: ObjectHandle, chatId: Long): Long
+  @JvmStatic
+  public external fun MessageBackupStatistics_GetTotalFrameBytes(stats: ObjectHandle): Long
+  @JvmStatic
+  public external fun MessageBackupStatistics_GetUnknownFieldCount(stats: ObjectHandle): Long
+
   @JvmStatic @Throws(Exception::class)
   public external fun MessageBackupValidator_Validate(key: ObjectHandle, firstStream: InputStream, secondStream: InputStream, len: Long, purpose: Int): Object
 
   @JvmStatic @Throws(Exception::class)
   public external fun Mp4Sanitizer_Sanitize(input: InputStream, len: Long): ObjectHandle
   @JvmStatic @Throws(Exception::class)
+  public external fun Mp4Sanitizer_SanitizeWithInfo(input: InputStream, len: Long): ObjectHandle
+  @JvmStatic @Throws(Exception::class)
+  public external fun Mp4Sanitizer_SanitizeWithInfo_File_With_Compounded_MDAT_Boxes(input: InputStream, len: Long, cumulativeMdatBoxSize: Int): ObjectHandle
+  @JvmStatic @Throws(Exception::class)
   public external fun Mp4Sanitizer_Sanitize_File_With_Compounded_MDAT_Boxes(input: InputStream, len: Long, cumulativeMdatBoxSize: Int): ObjectHandle
 
   @JvmStatic
@@ -1110,11 +1176,38 @@ internal object Native {
   @JvmStatic
   public external fun SanitizedMetadata_Destroy(handle: ObjectHandle): Unit
   @JvmStatic
+  public external fun SanitizedMetadata_GetAudioCodec(sanitized: ObjectHandle): String
+  @JvmStatic
+  public external fun SanitizedMetadata_GetAudioDurationMillis(sanitized: ObjectHandle): Long
+  @JvmStatic
   public external fun SanitizedMetadata_GetDataLen(sanitized: ObjectHandle): Long
   @JvmStatic
   public external fun SanitizedMetadata_GetDataOffset(sanitized: ObjectHandle): Long
   @JvmStatic
+  public external fun SanitizedMetadata_GetDurationMillis(sanitized: ObjectHandle): Long
+  @JvmStatic
   public external fun SanitizedMetadata_GetMetadata(sanitized: ObjectHandle): ByteArray
+  @JvmStatic
+  public external fun SanitizedMetadata_GetVideoCodec(sanitized: ObjectHandle): String
+  @JvmStatic
+  public external fun SanitizedMetadata_GetVideoDurationMillis(sanitized: ObjectHandle): Long
+  @JvmStatic
+  public external fun SanitizedMetadata_GetVideoHeight(sanitized: ObjectHandle): Int
+  @JvmStatic
+  public external fun SanitizedMetadata_GetVideoRotation(sanitized: ObjectHandle): Int
+  @JvmStatic
+  public external fun SanitizedMetadata_GetVideoWidth(sanitized: ObjectHandle): Int
+
+  @JvmStatic
+  public external fun SanitizedWebp_Destroy(handle: ObjectHandle): Unit
+  @JvmStatic
+  public external fun SanitizedWebp_GetFrameCount(sanitized: ObjectHandle): Int
+  @JvmStatic
+  public external fun SanitizedWebp_GetHeight(sanitized: ObjectHandle): Int
+  @JvmStatic
+  public external fun SanitizedWebp_GetWidth(sanitized: ObjectHandle): Int
+  @JvmStatic
+  public external fun SanitizedWebp_IsAnimated(sanitized: ObjectHandle): Boolean
 
   @JvmStatic @Throws(Exception::class)
   public external fun ScannableFingerprint_Compare(fprint1: ByteArray, fprint2: ByteArray): Boolean
@@ -1505,6 +1598,8 @@ internal object Native {
 
   @JvmStatic @Throws(Exception::class)
   public external fun WebpSanitizer_Sanitize(input: InputStream): Unit
+  @JvmStatic @Throws(Exception::class)
+  public external fun WebpSanitizer_SanitizeWithInfo(input: InputStream): ObjectHandle
 
   @JvmStatic @Throws(Exception::class)
   public external fun ZkCredentialKeyPair_CheckValidContents(keyPairBytes: ByteArray): Unit
@@ -156,6 +156,143 @@ export async function validate(
   }
 }
 
+/**
+ * Summary statistics about the contents of a backup file.
+ *
+ * Collecting statistics only checks that each frame parses; it does not validate the backup the
+ * way {@link validate} does. This makes it suitable for showing a user what's in a backup before
+ * restoring it.
+ *
+ * @see {@link collectStatistics}
+ */
+export class MessageBackupStatistics {
+  readonly _nativeHandle: Native.MessageBackupStatistics;
+
+  /** @internal */
+  constructor(handle: Native.MessageBackupStatistics) {
+    this._nativeHandle = handle;
+  }
+
+  /** The kinds of frames present in the backup, such as `"ChatItem.StandardMessage"`. */
+  public get frameKinds(): string[] {
+    return Native.MessageBackupStatistics_GetFrameKinds(this);
+  }
+
+  /** The number of frames of the given kind. */
+  public frameCount(kind: string): bigint {
+    return Native.MessageBackupStatistics_GetFrameCount(this, kind);
+  }
+
+  /** The total size of all frames of the given kind, once decrypted and decompressed. */
+  public frameBytes(kind: string): bigint {
+    return Native.MessageBackupStatistics_GetFrameBytes(this, kind);
+  }
+
+  /** The total size of all frames, once decrypted and decompressed. */
+  public get totalFrameBytes(): bigint {
+    return Native.MessageBackupStatistics_GetTotalFrameBytes(this);
+  }
+
+  /** The size of the backup file the statistics were collected from. */
+  public get backupFileBytes(): bigint {
+    return Native.MessageBackupStatistics_GetBackupFileBytes(this);
+  }
+
+  /**
+   * The ratio of the decompressed frame data to the size of the backup file, or `null` if the file
+   * was empty.
+   */
+  public get compressionRatio(): number | null {
+    const backupFileBytes = this.backupFileBytes;
+    if (backupFileBytes === 0n) {
+      return null;
+    }
+    return Number(this.totalFrameBytes) / Number(backupFileBytes);
+  }
+
+  /** The number of attachments referenced anywhere in the backup. */
+  public get attachmentCount(): bigint {
+    return Native.MessageBackupStatistics_GetAttachmentCount(this);
+  }
+
+  /** The sum of the sizes of all attachments, as recorded in the backup. */
+  public get attachmentBytes(): bigint {
+    return Native.MessageBackupStatistics_GetAttachmentBytes(this);
+  }
+
+  /** The number of chats that contain at least one message. */
+  public get chatCount(): bigint {
+    return Native.MessageBackupStatistics_GetChatCount(this);
+  }
+
+  /** The total number of chat items across all chats. */
+  public get messageCount(): bigint {
+    return Native.MessageBackupStatistics_GetMessageCount(this);
+  }
+
+  /**
+   * The number of chat items in each chat, keyed by the chat's ID within the backup.
+   *
+   * Chats are ordered by ascending ID.
+   */
+  public get messageCountsPerChat(): Map<bigint, bigint> {
+    const counts = new Map<bigint, bigint>();
+    for (let index = 0; ; ++index) {
+      const chatId = Native.MessageBackupStatistics_GetChatIdAt(this, index);
+      if (chatId === null) {
+        break;
+      }
+      counts.set(
+        chatId,
+        Native.MessageBackupStatistics_GetMessageCountForChat(this, chatId)
+      );
+    }
+    return counts;
+  }
+
+  /** The number of unknown fields and enum values encountered while reading the backup. */
+  public get unknownFieldCount(): bigint {
+    return Native.MessageBackupStatistics_GetUnknownFieldCount(this);
+  }
+}
+
+/**
+ * Summarize the contents of a backup file.
+ *
+ * @param backupKey The key to use to decrypt the backup contents.
+ * @param purpose Whether the backup is intended for device-to-device transfer or remote storage.
+ * @param inputFactory A function that returns new input streams that read the backup contents.
+ * @param length The exact length of the input stream.
+ * @returns Statistics about the frames, chats, and attachments in the backup.
+ * @throws IoError If an IO error on the input occurs.
+ * @throws BackupValidationError If the input could not be parsed.
+ */
+export async function collectStatistics(
+  backupKey: MessageBackupKey,
+  purpose: Purpose,
+  inputFactory: InputStreamFactory,
+  length: bigint
+): Promise<MessageBackupStatistics> {
+  let firstStream: InputStream | undefined;
+  let secondStream: InputStream | undefined;
+  try {
+    firstStream = inputFactory();
+    secondStream = inputFactory();
+    return new MessageBackupStatistics(
+      await Native.MessageBackupStatistics_Collect(
+        backupKey,
+        _bridgeInputStream(firstStream),
+        _bridgeInputStream(secondStream),
+        length,
+        purpose
+      )
+    );
+  } finally {
+    await firstStream?.close();
+    await secondStream?.close();
+  }
+}
+
 /**
  * An alternative to {@link validate()} that validates a backup frame-by-frame.
  *
@@ -79,6 +79,72 @@ export class SanitizedMetadata {
   getDataLen(): bigint {
     return Native.SanitizedMetadata_GetDataLen(this);
   }
+
+  // The remaining accessors are only populated by {@link sanitizeWithInfo}.
+
+  /**
+   * Get the presentation duration of the movie, if it was specified.
+   * @returns The duration in milliseconds, or `null` if it is unknown.
+   */
+  getDurationMillis(): bigint | null {
+    return Native.SanitizedMetadata_GetDurationMillis(this);
+  }
+
+  /**
+   * Get the display width of the first video track.
+   * @returns The width in pixels, or 0 if there is no video track.
+   */
+  getVideoWidth(): number {
+    return Native.SanitizedMetadata_GetVideoWidth(this);
+  }
+
+  /**
+   * Get the display height of the first video track.
+   * @returns The height in pixels, or 0 if there is no video track.
+   */
+  getVideoHeight(): number {
+    return Native.SanitizedMetadata_GetVideoHeight(this);
+  }
+
+  /**
+   * Get the clockwise rotation to apply when displaying the first video track.
+   * @returns The rotation in degrees: 0, 90, 180, or 270.
+   */
+  getVideoRotation(): number {
+    return Native.SanitizedMetadata_GetVideoRotation(this);
+  }
+
+  /**
+   * Get the codec of the first video track, such as `avc1`.
+   * @returns The codec, or `null` if there is no video track or its codec is unknown.
+   */
+  getVideoCodec(): string | null {
+    return Native.SanitizedMetadata_GetVideoCodec(this) || null;
+  }
+
+  /**
+   * Get the media duration of the first video track, if it was specified.
+   * @returns The duration in milliseconds, or `null` if it is unknown.
+   */
+  getVideoDurationMillis(): bigint | null {
+    return Native.SanitizedMetadata_GetVideoDurationMillis(this);
+  }
+
+  /**
+   * Get the codec of the first audio track, such as `mp4a`.
+   * @returns The codec, or `null` if there is no audio track or its codec is unknown.
+   */
+  getAudioCodec(): string | null {
+    return Native.SanitizedMetadata_GetAudioCodec(this) || null;
+  }
+
+  /**
+   * Get the media duration of the first audio track, if it was specified.
+   * @returns The duration in milliseconds, or `null` if it is unknown.
+   */
+  getAudioDurationMillis(): bigint | null {
+    return Native.SanitizedMetadata_GetAudioDurationMillis(this);
+  }
 }
 
 /**
@@ -101,3 +167,28 @@ export async function sanitize(
   );
   return SanitizedMetadata._fromNativeHandle(sanitizedMetadataNativeHandle);
 }
+
+/**
+ * Sanitize an MP4 input, additionally reporting its duration, dimensions, and codecs.
+ *
+ * This behaves like {@link sanitize}, but the accessors for the media information on the result
+ * are populated as well.
+ *
+ * @param input An MP4 format input stream.
+ * @param len The exact length of the input stream.
+ * @returns The sanitized metadata.
+ * @throws {IoError} If an IO error on the input occurs.
+ * @throws {InvalidMediaInputError} If the input could not be parsed because it was invalid.
+ * @throws {UnsupportedMediaInputError} If the input could not be parsed because it's unsupported in some way.
+ */
+export async function sanitizeWithInfo(
+  input: InputStream,
+  len: bigint
+): Promise<SanitizedMetadata> {
+  const sanitizedMetadataNativeHandle =
+    await Native.Mp4Sanitizer_SanitizeWithInfo(
+      _bridgeInputStream(input),
+      len
+    );
+  return SanitizedMetadata._fromNativeHandle(sanitizedMetadataNativeHandle);
+}
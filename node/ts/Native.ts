@@ -468,6 +468,8 @@ export type ArgFfiMyTestStruct = {
 
 export const NetRemoteConfigKeys = [
   'chatRequestConnectionCheckTimeoutMillis',
+  'dnsOverTlsFallback',
+  'chatCertificatePins',
   'grpc.AccountsAnonymousLookupUsernameHash',
   'grpc.AccountsAnonymousLookupUsernameLink.2',
   'grpc.AccountsAnonymousCheckAccountExistence.2',
@@ -935,6 +937,9 @@ type NativeFunctions = {
   ConnectionManager_clear_proxy: (
     connection_manager: Wrapper<ConnectionManager>
   ) => void;
+  ConnectionManager_get_connectivity_report: (
+    connection_manager: Wrapper<ConnectionManager>
+  ) => ConnectivityReport;
   ConnectionManager_new: (
     environment: number,
     user_agent: string,
@@ -971,6 +976,44 @@ type NativeFunctions = {
     username: string | null,
     password: string | null
   ) => ConnectionProxyConfig;
+  ConnectionProxyConfig_newPluggableTransport: (
+    local_port: number,
+    bridge_host: string,
+    bridge_port: number,
+    bridge_args: string
+  ) => ConnectionProxyConfig;
+  ConnectivityReport_collection_period_seconds: (
+    report: Wrapper<ConnectivityReport>
+  ) => bigint;
+  ConnectivityReport_dns_doh_lookups: (
+    report: Wrapper<ConnectivityReport>
+  ) => bigint;
+  ConnectivityReport_dns_dot_lookups: (
+    report: Wrapper<ConnectivityReport>
+  ) => bigint;
+  ConnectivityReport_dns_static_lookups: (
+    report: Wrapper<ConnectivityReport>
+  ) => bigint;
+  ConnectivityReport_dns_system_lookups: (
+    report: Wrapper<ConnectivityReport>
+  ) => bigint;
+  ConnectivityReport_latency_bucket: (
+    report: Wrapper<ConnectivityReport>,
+    kind: string,
+    bucket: number
+  ) => bigint;
+  ConnectivityReport_latency_bucket_count: () => number;
+  ConnectivityReport_route_failures: (
+    report: Wrapper<ConnectivityReport>,
+    kind: string
+  ) => bigint;
+  ConnectivityReport_route_kinds: (
+    report: Wrapper<ConnectivityReport>
+  ) => Array<string>;
+  ConnectivityReport_route_successes: (
+    report: Wrapper<ConnectivityReport>,
+    kind: string
+  ) => bigint;
   CopyBackupMediaStream_cancel: (
     stream: Wrapper<CopyBackupMediaStream>
   ) => void;
@@ -1448,6 +1491,53 @@ type NativeFunctions = {
   MessageBackupKey_GetHmacKey: (
     key: Wrapper<MessageBackupKey>
   ) => Uint8Array<ArrayBuffer>;
+  MessageBackupStatistics_Collect: (
+    key: Wrapper<MessageBackupKey>,
+    first_stream: InputStream,
+    second_stream: InputStream,
+    len: bigint,
+    purpose: number
+  ) => Promise<MessageBackupStatistics>;
+  MessageBackupStatistics_GetAttachmentBytes: (
+    stats: Wrapper<MessageBackupStatistics>
+  ) => bigint;
+  MessageBackupStatistics_GetAttachmentCount: (
+    stats: Wrapper<MessageBackupStatistics>
+  ) => bigint;
+  MessageBackupStatistics_GetBackupFileBytes: (
+    stats: Wrapper<MessageBackupStatistics>
+  ) => bigint;
+  MessageBackupStatistics_GetChatCount: (
+    stats: Wrapper<MessageBackupStatistics>
+  ) => bigint;
+  MessageBackupStatistics_GetChatIdAt: (
+    stats: Wrapper<MessageBackupStatistics>,
+    index: number
+  ) => bigint | null;
+  MessageBackupStatistics_GetFrameBytes: (
+    stats: Wrapper<MessageBackupStatistics>,
+    kind: string
+  ) => bigint;
+  MessageBackupStatistics_GetFrameCount: (
+    stats: Wrapper<MessageBackupStatistics>,
+    kind: string
+  ) => bigint;
+  MessageBackupStatistics_GetFrameKinds: (
+    stats: Wrapper<MessageBackupStatistics>
+  ) => Array<string>;
+  MessageBackupStatistics_GetMessageCount: (
+    stats: Wrapper<MessageBackupStatistics>
+  ) => bigint;
+  MessageBackupStatistics_GetMessageCountForChat: (
+    stats: Wrapper<MessageBackupStatistics>,
+    chat_id: bigint
+  ) => bigint;
+  MessageBackupStatistics_GetTotalFrameBytes: (
+    stats: Wrapper<MessageBackupStatistics>
+  ) => bigint;
+  MessageBackupStatistics_GetUnknownFieldCount: (
+    stats: Wrapper<MessageBackupStatistics>
+  ) => bigint;
   MessageBackupValidator_Validate: (
     key: Wrapper<MessageBackupKey>,
     first_stream: InputStream,
@@ -1460,6 +1550,10 @@ type NativeFunctions = {
     input: InputStream,
     len: bigint
   ) => Promise<SanitizedMetadata>;
+  Mp4Sanitizer_SanitizeWithInfo: (
+    input: InputStream,
+    len: bigint
+  ) => Promise<SanitizedMetadata>;
   OnlineBackupValidator_AddFrame: (
     backup: Wrapper<OnlineBackupValidator>,
     frame: Uint8Array<ArrayBuffer>
@@ -1824,15 +1918,43 @@ type NativeFunctions = {
   RegistrationSession_GetVerified: (
     session: Wrapper<RegistrationSession>
   ) => boolean;
+  SanitizedMetadata_GetAudioCodec: (
+    sanitized: Wrapper<SanitizedMetadata>
+  ) => string;
+  SanitizedMetadata_GetAudioDurationMillis: (
+    sanitized: Wrapper<SanitizedMetadata>
+  ) => bigint | null;
   SanitizedMetadata_GetDataLen: (
     sanitized: Wrapper<SanitizedMetadata>
   ) => bigint;
   SanitizedMetadata_GetDataOffset: (
     sanitized: Wrapper<SanitizedMetadata>
   ) => bigint;
+  SanitizedMetadata_GetDurationMillis: (
+    sanitized: Wrapper<SanitizedMetadata>
+  ) => bigint | null;
   SanitizedMetadata_GetMetadata: (
     sanitized: Wrapper<SanitizedMetadata>
   ) => Uint8Array<ArrayBuffer>;
+  SanitizedMetadata_GetVideoCodec: (
+    sanitized: Wrapper<SanitizedMetadata>
+  ) => string;
+  SanitizedMetadata_GetVideoDurationMillis: (
+    sanitized: Wrapper<SanitizedMetadata>
+  ) => bigint | null;
+  SanitizedMetadata_GetVideoHeight: (
+    sanitized: Wrapper<SanitizedMetadata>
+  ) => number;
+  SanitizedMetadata_GetVideoRotation: (
+    sanitized: Wrapper<SanitizedMetadata>
+  ) => number;
+  SanitizedMetadata_GetVideoWidth: (
+    sanitized: Wrapper<SanitizedMetadata>
+  ) => number;
+  SanitizedWebp_GetFrameCount: (sanitized: Wrapper<SanitizedWebp>) => number;
+  SanitizedWebp_GetHeight: (sanitized: Wrapper<SanitizedWebp>) => number;
+  SanitizedWebp_GetWidth: (sanitized: Wrapper<SanitizedWebp>) => number;
+  SanitizedWebp_IsAnimated: (sanitized: Wrapper<SanitizedWebp>) => boolean;
   ScannableFingerprint_Compare: (
     fprint1: Uint8Array<ArrayBuffer>,
     fprint2: Uint8Array<ArrayBuffer>
@@ -3084,6 +3206,7 @@ type NativeFunctions = {
     length: number
   ) => number;
   WebpSanitizer_Sanitize: (input: SyncInputStream) => void;
+  WebpSanitizer_SanitizeWithInfo: (input: SyncInputStream) => SanitizedWebp;
   ZkCredentialKeyPair_CheckValidContents: (
     key_pair_bytes: Uint8Array<ArrayBuffer>
   ) => void;
@@ -3218,6 +3341,7 @@ const {
   ComparableBackup_GetUnknownFields,
   ComparableBackup_ReadUnencrypted,
   ConnectionManager_clear_proxy,
+  ConnectionManager_get_connectivity_report,
   ConnectionManager_new,
   ConnectionManager_on_network_change,
   ConnectionManager_set_censorship_circumvention_enabled,
@@ -3226,6 +3350,17 @@ const {
   ConnectionManager_set_proxy,
   ConnectionManager_set_remote_config,
   ConnectionProxyConfig_new,
+  ConnectionProxyConfig_newPluggableTransport,
+  ConnectivityReport_collection_period_seconds,
+  ConnectivityReport_dns_doh_lookups,
+  ConnectivityReport_dns_dot_lookups,
+  ConnectivityReport_dns_static_lookups,
+  ConnectivityReport_dns_system_lookups,
+  ConnectivityReport_latency_bucket,
+  ConnectivityReport_latency_bucket_count,
+  ConnectivityReport_route_failures,
+  ConnectivityReport_route_kinds,
+  ConnectivityReport_route_successes,
   CopyBackupMediaStream_cancel,
   CopyBackupMediaStream_next,
   CreateCallLinkCredentialPresentation_CheckValidContents,
@@ -3354,9 +3489,23 @@ const {
   MessageBackupKey_FromBackupKeyAndBackupId,
   MessageBackupKey_GetAesKey,
   MessageBackupKey_GetHmacKey,
+  MessageBackupStatistics_Collect,
+  MessageBackupStatistics_GetAttachmentBytes,
+  MessageBackupStatistics_GetAttachmentCount,
+  MessageBackupStatistics_GetBackupFileBytes,
+  MessageBackupStatistics_GetChatCount,
+  MessageBackupStatistics_GetChatIdAt,
+  MessageBackupStatistics_GetFrameBytes,
+  MessageBackupStatistics_GetFrameCount,
+  MessageBackupStatistics_GetFrameKinds,
+  MessageBackupStatistics_GetMessageCount,
+  MessageBackupStatistics_GetMessageCountForChat,
+  MessageBackupStatistics_GetTotalFrameBytes,
+  MessageBackupStatistics_GetUnknownFieldCount,
   MessageBackupValidator_Validate,
   MinidumpToJSONString,
   Mp4Sanitizer_Sanitize,
+  Mp4Sanitizer_SanitizeWithInfo,
   OnlineBackupValidator_AddFrame,
   OnlineBackupValidator_Finalize,
   OnlineBackupValidator_New,
@@ -3470,9 +3619,21 @@ const {
   RegistrationSession_GetNextVerificationAttemptSeconds,
   RegistrationSession_GetRequestedInformation,
   RegistrationSession_GetVerified,
+  SanitizedMetadata_GetAudioCodec,
+  SanitizedMetadata_GetAudioDurationMillis,
   SanitizedMetadata_GetDataLen,
   SanitizedMetadata_GetDataOffset,
+  SanitizedMetadata_GetDurationMillis,
   SanitizedMetadata_GetMetadata,
+  SanitizedMetadata_GetVideoCodec,
+  SanitizedMetadata_GetVideoDurationMillis,
+  SanitizedMetadata_GetVideoHeight,
+  SanitizedMetadata_GetVideoRotation,
+  SanitizedMetadata_GetVideoWidth,
+  SanitizedWebp_GetFrameCount,
+  SanitizedWebp_GetHeight,
+  SanitizedWebp_GetWidth,
+  SanitizedWebp_IsAnimated,
   ScannableFingerprint_Compare,
   SealedSenderDecryptionResult_GetDeviceId,
   SealedSenderDecryptionResult_GetSenderE164,
@@ -3828,6 +3989,7 @@ const {
   ValidatingMac_Initialize,
   ValidatingMac_Update,
   WebpSanitizer_Sanitize,
+  WebpSanitizer_SanitizeWithInfo,
   ZkCredentialKeyPair_CheckValidContents,
   ZkCredentialKeyPair_GenerateDeterministic,
   ZkCredentialKeyPair_GetPublicKey,
@@ -3953,6 +4115,7 @@ export {
   ComparableBackup_GetUnknownFields,
   ComparableBackup_ReadUnencrypted,
   ConnectionManager_clear_proxy,
+  ConnectionManager_get_connectivity_report,
   ConnectionManager_new,
   ConnectionManager_on_network_change,
   ConnectionManager_set_censorship_circumvention_enabled,
@@ -3961,6 +4124,17 @@ export {
   ConnectionManager_set_proxy,
   ConnectionManager_set_remote_config,
   ConnectionProxyConfig_new,
+  ConnectionProxyConfig_newPluggableTransport,
+  ConnectivityReport_collection_period_seconds,
+  ConnectivityReport_dns_doh_lookups,
+  ConnectivityReport_dns_dot_lookups,
+  ConnectivityReport_dns_static_lookups,
+  ConnectivityReport_dns_system_lookups,
+  ConnectivityReport_latency_bucket,
+  ConnectivityReport_latency_bucket_count,
+  ConnectivityReport_route_failures,
+  ConnectivityReport_route_kinds,
+  ConnectivityReport_route_successes,
   CopyBackupMediaStream_cancel,
   CopyBackupMediaStream_next,
   CreateCallLinkCredentialPresentation_CheckValidContents,
@@ -4089,9 +4263,23 @@ export {
   MessageBackupKey_FromBackupKeyAndBackupId,
   MessageBackupKey_GetAesKey,
   MessageBackupKey_GetHmacKey,
+  MessageBackupStatistics_Collect,
+  MessageBackupStatistics_GetAttachmentBytes,
+  MessageBackupStatistics_GetAttachmentCount,
+  MessageBackupStatistics_GetBackupFileBytes,
+  MessageBackupStatistics_GetChatCount,
+  MessageBackupStatistics_GetChatIdAt,
+  MessageBackupStatistics_GetFrameBytes,
+  MessageBackupStatistics_GetFrameCount,
+  MessageBackupStatistics_GetFrameKinds,
+  MessageBackupStatistics_GetMessageCount,
+  MessageBackupStatistics_GetMessageCountForChat,
+  MessageBackupStatistics_GetTotalFrameBytes,
+  MessageBackupStatistics_GetUnknownFieldCount,
   MessageBackupValidator_Validate,
   MinidumpToJSONString,
   Mp4Sanitizer_Sanitize,
+  Mp4Sanitizer_SanitizeWithInfo,
   OnlineBackupValidator_AddFrame,
   OnlineBackupValidator_Finalize,
   OnlineBackupValidator_New,
@@ -4205,9 +4393,21 @@ export {
   RegistrationSession_GetNextVerificationAttemptSeconds,
   RegistrationSession_GetRequestedInformation,
   RegistrationSession_GetVerified,
+  SanitizedMetadata_GetAudioCodec,
+  SanitizedMetadata_GetAudioDurationMillis,
   SanitizedMetadata_GetDataLen,
   SanitizedMetadata_GetDataOffset,
+  SanitizedMetadata_GetDurationMillis,
   SanitizedMetadata_GetMetadata,
+  SanitizedMetadata_GetVideoCodec,
+  SanitizedMetadata_GetVideoDurationMillis,
+  SanitizedMetadata_GetVideoHeight,
+  SanitizedMetadata_GetVideoRotation,
+  SanitizedMetadata_GetVideoWidth,
+  SanitizedWebp_GetFrameCount,
+  SanitizedWebp_GetHeight,
+  SanitizedWebp_GetWidth,
+  SanitizedWebp_IsAnimated,
   ScannableFingerprint_Compare,
   SealedSenderDecryptionResult_GetDeviceId,
   SealedSenderDecryptionResult_GetSenderE164,
@@ -4563,6 +4763,7 @@ export {
   ValidatingMac_Initialize,
   ValidatingMac_Update,
   WebpSanitizer_Sanitize,
+  WebpSanitizer_SanitizeWithInfo,
   ZkCredentialKeyPair_CheckValidContents,
   ZkCredentialKeyPair_GenerateDeterministic,
   ZkCredentialKeyPair_GetPublicKey,
@@ -4691,6 +4892,9 @@ export interface ConnectionManager {
 export interface ConnectionProxyConfig {
   readonly __type: unique symbol;
 }
+export interface ConnectivityReport {
+  readonly __type: unique symbol;
+}
 export interface CopyBackupMediaStream {
   readonly __type: unique symbol;
 }
@@ -4760,6 +4964,9 @@ export interface LookupRequest {
 export interface MessageBackupKey {
   readonly __type: unique symbol;
 }
+export interface MessageBackupStatistics {
+  readonly __type: unique symbol;
+}
 export interface NonSuspendingBackgroundThreadRuntime {
   readonly __type: unique symbol;
 }
@@ -4844,6 +5051,9 @@ export interface RegistrationSession {
 export interface SanitizedMetadata {
   readonly __type: unique symbol;
 }
+export interface SanitizedWebp {
+  readonly __type: unique symbol;
+}
 export interface SealedSenderDecryptionResult {
   readonly __type: unique symbol;
 }
@@ -19,6 +19,49 @@ import type {
   UnsupportedMediaInputError,
 } from './Errors.js';
 
+export class SanitizedWebp {
+  readonly _nativeHandle: Native.SanitizedWebp;
+
+  private constructor(handle: Native.SanitizedWebp) {
+    this._nativeHandle = handle;
+  }
+
+  static _fromNativeHandle(handle: Native.SanitizedWebp): SanitizedWebp {
+    return new SanitizedWebp(handle);
+  }
+
+  /**
+   * Get the canvas width of the image.
+   * @returns The width in pixels, or 0 if it could not be determined.
+   */
+  getWidth(): number {
+    return Native.SanitizedWebp_GetWidth(this);
+  }
+
+  /**
+   * Get the canvas height of the image.
+   * @returns The height in pixels, or 0 if it could not be determined.
+   */
+  getHeight(): number {
+    return Native.SanitizedWebp_GetHeight(this);
+  }
+
+  /**
+   * Whether the image is marked as animated.
+   */
+  isAnimated(): boolean {
+    return Native.SanitizedWebp_IsAnimated(this);
+  }
+
+  /**
+   * Get the number of frames in the image.
+   * @returns The number of animation frames, or 1 for a still image.
+   */
+  getFrameCount(): number {
+    return Native.SanitizedWebp_GetFrameCount(this);
+  }
+}
+
 /**
  * Sanitize a WebP input.
  *
@@ -30,3 +73,20 @@ import type {
 export function sanitize(input: Uint8Array<ArrayBuffer>): void {
   Native.WebpSanitizer_Sanitize(input);
 }
+
+/**
+ * Sanitize a WebP input, additionally reporting its dimensions and animation.
+ *
+ * @param input A WebP format input stream.
+ * @returns The information found while sanitizing.
+ * @throws {IoError} If an IO error on the input occurs.
+ * @throws {InvalidMediaInputError} If the input could not be parsed because it was invalid.
+ * @throws {UnsupportedMediaInputError} If the input could not be parsed because it's unsupported in some way.
+ */
+export function sanitizeWithInfo(
+  input: Uint8Array<ArrayBuffer>
+): SanitizedWebp {
+  return SanitizedWebp._fromNativeHandle(
+    Native.WebpSanitizer_SanitizeWithInfo(input)
+  );
+}
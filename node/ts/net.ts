@@ -19,6 +19,7 @@ import {
   ProvisioningConnection,
   ProvisioningConnectionListener,
 } from './net/Chat.js';
+import { ConnectivityReport } from './net/ConnectivityReport.js';
 import { RegistrationService } from './net/Registration.js';
 import { Svr2 } from './net/Svr2.js';
 import { SvrB } from './net/SvrB.js';
@@ -34,6 +35,7 @@ export * from './net/chat/UnauthKeysService.js';
 export * from './net/chat/UnauthMessagesService.js';
 export * from './net/chat/UnauthProfilesService.js';
 export * from './net/chat/UnauthUsernamesService.js';
+export * from './net/ConnectivityReport.js';
 export * from './net/Registration.js';
 export * from './net/Svr2.js';
 export * from './net/SvrB.js';
@@ -130,6 +132,16 @@ export type ProxyOptions = {
   password?: string;
 };
 
+/** See {@link Net.setPluggableTransportProxy()}. */
+export type PluggableTransportProxyOptions = {
+  /** The port the app's Pluggable Transports client listens on for SOCKS5 connections. */
+  localPort: number;
+  bridgeHost: string;
+  bridgePort: number;
+  /** The bridge's space-separated `key=value` arguments, as found at the end of a bridge line. */
+  bridgeArgs: string;
+};
+
 /** The "scheme" for Signal TLS proxies. See {@link Net.setProxy()}. */
 export const SIGNAL_TLS_PROXY_SCHEME = 'org.signal.tls';
 
@@ -475,6 +487,36 @@ export class Net {
     return { scheme, username, password, host, port };
   }
 
+  /**
+   * Sets a pluggable transport bridge to be used for all new connections (until overridden).
+   *
+   * The app is responsible for running a Pluggable Transports client (such as an obfs4 library)
+   * that listens for SOCKS5 connections on `localPort`. New connections will ask that client to
+   * connect to the bridge, passing along its arguments. The proxy can be overridden by calling this
+   * method again or unset by calling {@link #clearProxy}.
+   *
+   * Throws if the ports are not valid, or if the bridge arguments are malformed or too long.
+   */
+  setPluggableTransportProxy(
+    options: Readonly<PluggableTransportProxyOptions>
+  ): void {
+    const { localPort, bridgeHost, bridgePort, bridgeArgs } = options;
+    try {
+      const proxyConfig = newNativeHandle(
+        Native.ConnectionProxyConfig_newPluggableTransport(
+          localPort,
+          bridgeHost,
+          bridgePort,
+          bridgeArgs
+        )
+      );
+      Native.ConnectionManager_set_proxy(this._connectionManager, proxyConfig);
+    } catch (e) {
+      this.setInvalidProxy();
+      throw e;
+    }
+  }
+
   /**
    * Refuses to make any new connections until a new proxy configuration is set or
    * {@link #clearProxy} is called.
@@ -560,6 +602,17 @@ export class Net {
     Native.ConnectionManager_on_network_change(this._connectionManager);
   }
 
+  /**
+   * Summarizes connection attempts made since the last network change.
+   *
+   * The report contains only aggregate counts, so it's suitable for uploading.
+   */
+  connectivityReport(): ConnectivityReport {
+    return new ConnectivityReport(
+      Native.ConnectionManager_get_connectivity_report(this._connectionManager)
+    );
+  }
+
   async cdsiLookup(
     auth: Readonly<ServiceAuth>,
     options: ReadonlyDeep<CDSRequestOptionsType>
@@ -0,0 +1,87 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+import * as Native from '../Native.js';
+
+/**
+ * A snapshot of connection health since the last network change.
+ *
+ * Routes are only grouped by kind, such as `"direct"` or `"fronted+socks"`; nothing here
+ * identifies a particular host, address, or proxy.
+ *
+ * Obtained from `Net.connectivityReport()`.
+ */
+export class ConnectivityReport {
+  readonly _nativeHandle: Native.ConnectivityReport;
+
+  /** @internal */
+  constructor(handle: Native.ConnectivityReport) {
+    this._nativeHandle = handle;
+  }
+
+  /**
+   * The number of buckets returned by {@link ConnectivityReport.latencyHistogram}.
+   *
+   * Buckets are ordered from fastest to slowest; the last one has no upper bound.
+   */
+  public static get latencyBucketCount(): number {
+    return Native.ConnectivityReport_latency_bucket_count();
+  }
+
+  /** How long statistics have been collected for, in seconds. */
+  public get collectionPeriodSeconds(): bigint {
+    return Native.ConnectivityReport_collection_period_seconds(this);
+  }
+
+  /** The kinds of route that were attempted, in the order each was first tried. */
+  public get routeKinds(): string[] {
+    return Native.ConnectivityReport_route_kinds(this);
+  }
+
+  /** The number of successful connection attempts over the given kind of route. */
+  public routeSuccesses(kind: string): bigint {
+    return Native.ConnectivityReport_route_successes(this, kind);
+  }
+
+  /** The number of failed connection attempts over the given kind of route. */
+  public routeFailures(kind: string): bigint {
+    return Native.ConnectivityReport_route_failures(this, kind);
+  }
+
+  /**
+   * The number of successful connections over the given kind of route that fell in each latency
+   * bucket.
+   *
+   * @see ConnectivityReport.latencyBucketCount
+   */
+  public latencyHistogram(kind: string): bigint[] {
+    const bucketCount = ConnectivityReport.latencyBucketCount;
+    const counts: bigint[] = [];
+    for (let bucket = 0; bucket < bucketCount; ++bucket) {
+      counts.push(Native.ConnectivityReport_latency_bucket(this, kind, bucket));
+    }
+    return counts;
+  }
+
+  /** The number of hostname lookups answered by the system resolver. */
+  public get dnsSystemLookups(): bigint {
+    return Native.ConnectivityReport_dns_system_lookups(this);
+  }
+
+  /** The number of hostname lookups answered over DNS-over-HTTPS. */
+  public get dnsDohLookups(): bigint {
+    return Native.ConnectivityReport_dns_doh_lookups(this);
+  }
+
+  /** The number of hostname lookups answered over DNS-over-TLS. */
+  public get dnsDotLookups(): bigint {
+    return Native.ConnectivityReport_dns_dot_lookups(this);
+  }
+
+  /** The number of hostname lookups answered from the built-in static fallback. */
+  public get dnsStaticLookups(): bigint {
+    return Native.ConnectivityReport_dns_static_lookups(this);
+  }
+}
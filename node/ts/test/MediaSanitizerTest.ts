@@ -83,6 +83,28 @@ describe('Mp4Sanitizer', () => {
       }
     });
   });
+
+  describe('sanitizeWithInfo', () => {
+    it('reports no tracks for a minimal mp4', async () => {
+      const metadata = new Uint8Array(ftyp().concat(moov()));
+      const data = new Uint8Array(ftyp().concat(mdat(), moov()));
+      const sanitized = await Mp4Sanitizer.sanitizeWithInfo(
+        new Uint8ArrayInputStream(data),
+        BigInt(data.length)
+      );
+      assertSanitizedMetadataEqual(
+        sanitized,
+        ftyp().length,
+        data.length - metadata.length,
+        metadata
+      );
+      assert.isNull(sanitized.getDurationMillis());
+      assert.equal(sanitized.getVideoWidth(), 0);
+      assert.equal(sanitized.getVideoHeight(), 0);
+      assert.isNull(sanitized.getVideoCodec());
+      assert.isNull(sanitized.getAudioCodec());
+    });
+  });
 });
 
 describe('WebpSanitizer', () => {
@@ -114,6 +136,17 @@ describe('WebpSanitizer', () => {
       WebpSanitizer.sanitize(input);
     });
   });
+
+  describe('sanitizeWithInfo', () => {
+    it('reports the dimensions of a minimal webp', () => {
+      const input = new Uint8Array(webp());
+      const sanitized = WebpSanitizer.sanitizeWithInfo(input);
+      assert.equal(sanitized.getWidth(), 1);
+      assert.equal(sanitized.getHeight(), 1);
+      assert.isFalse(sanitized.isAnimated());
+      assert.equal(sanitized.getFrameCount(), 1);
+    });
+  });
 });
 
 function ftyp(): Array<number> {
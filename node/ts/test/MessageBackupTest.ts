@@ -123,6 +123,30 @@ describe('MessageBackup', () => {
       assert.equal(outcome2.errorMessage, null);
     });
 
+    it('collects statistics for a minimal backup', async () => {
+      const input = fs.readFileSync(
+        path.join(
+          import.meta.dirname,
+          '../../ts/test/new_account.binproto.encrypted'
+        )
+      );
+
+      const stats = await MessageBackup.collectStatistics(
+        testKey,
+        purpose,
+        () => new Uint8ArrayInputStream(input),
+        BigInt(input.length)
+      );
+      assert.include(stats.frameKinds, 'BackupInfo');
+      assert.equal(stats.frameCount('BackupInfo'), 1n);
+      assert.equal(stats.frameCount('not a frame kind'), 0n);
+      assert.equal(stats.backupFileBytes, BigInt(input.length));
+      assert.isAbove(Number(stats.totalFrameBytes), 0);
+      assert.isNotNull(stats.compressionRatio);
+      assert.equal(stats.messageCountsPerChat.size, Number(stats.chatCount));
+      assert.equal(stats.unknownFieldCount, 0n);
+    });
+
     it('throws on empty input', async () => {
       try {
         await MessageBackup.validate(
@@ -212,6 +212,23 @@ describe('chat service api', () => {
       net.setProxy({ scheme: 'socks+shoes', host: 'signalfoundation.org' })
     );
 
+    check(() =>
+      net.setPluggableTransportProxy({
+        localPort: 0,
+        bridgeHost: '192.0.2.1',
+        bridgePort: 443,
+        bridgeArgs: 'cert=abc',
+      })
+    );
+    check(() =>
+      net.setPluggableTransportProxy({
+        localPort: 9050,
+        bridgeHost: '192.0.2.1',
+        bridgePort: 443,
+        bridgeArgs: 'not-a-key-value-pair',
+      })
+    );
+
     check(() => net.setProxyFromUrl('not a url'));
     check(() => net.setProxyFromUrl('socks+shoes://signalfoundation.org'));
     check(() => net.setProxyFromUrl('https://signalfoundation.org:0x50'));
@@ -3,17 +3,19 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
-use ::device_transfer::{self, KeyFormat};
+use ::device_transfer::{self, KeyFormat, KeyType};
 use libsignal_bridge_macros::*;
+use libsignal_bridge_types::device_transfer::DeviceTransferKeyType;
 
 // Not used by the Java bridge.
 #[allow(unused_imports)]
 use crate::support::*;
 use crate::*;
 
+const DEVICE_TRANSFER_KEY_BITS: usize = 4096;
+
 #[bridge_fn(node = false)]
 fn DeviceTransfer_GeneratePrivateKey() -> Vec<u8> {
-    const DEVICE_TRANSFER_KEY_BITS: usize = 4096;
     device_transfer::create_rsa_private_key(DEVICE_TRANSFER_KEY_BITS, KeyFormat::Pkcs8)
         .expect("no internal failures")
 }
@@ -23,10 +25,23 @@ fn DeviceTransfer_GeneratePrivateKey() -> Vec<u8> {
 fn DeviceTransfer_GeneratePrivateKeyWithFormat(
     key_format: u8,
 ) -> Result<Vec<u8>, device_transfer::Error> {
-    const DEVICE_TRANSFER_KEY_BITS: usize = 4096;
     device_transfer::create_rsa_private_key(DEVICE_TRANSFER_KEY_BITS, KeyFormat::from(key_format))
 }
 
+#[bridge_fn(node = false)]
+fn DeviceTransfer_GeneratePrivateKeyWithType(
+    key_type: AsType<DeviceTransferKeyType, u8>,
+    key_format: u8,
+) -> Result<Vec<u8>, device_transfer::Error> {
+    let key_type = match key_type.into_inner() {
+        DeviceTransferKeyType::Rsa => KeyType::Rsa {
+            bits: DEVICE_TRANSFER_KEY_BITS,
+        },
+        DeviceTransferKeyType::EcdsaP256 => KeyType::EcdsaP256,
+    };
+    device_transfer::create_private_key(key_type, KeyFormat::from(key_format))
+}
+
 #[bridge_fn(node = false)]
 fn DeviceTransfer_GenerateCertificate(
     private_key: &[u8],
@@ -35,3 +50,18 @@ fn DeviceTransfer_GenerateCertificate(
 ) -> Result<Vec<u8>, device_transfer::Error> {
     device_transfer::create_self_signed_cert(private_key, &name, days_to_expire)
 }
+
+#[bridge_fn(node = false)]
+fn DeviceTransfer_GetCertificateFingerprint(
+    certificate: &[u8],
+) -> Result<[u8; 32], device_transfer::Error> {
+    device_transfer::certificate_fingerprint(certificate)
+}
+
+#[bridge_fn(node = false)]
+fn DeviceTransfer_CertificateMatchesFingerprint(
+    certificate: &[u8],
+    fingerprint: &[u8],
+) -> Result<bool, device_transfer::Error> {
+    device_transfer::certificate_matches_fingerprint(certificate, fingerprint)
+}
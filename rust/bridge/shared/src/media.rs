@@ -3,8 +3,10 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use std::time::Duration;
+
 use libsignal_bridge_macros::*;
-use libsignal_bridge_types::media::SanitizedMetadata;
+use libsignal_bridge_types::media::{SanitizedMetadata, SanitizedWebp};
 use signal_media::sanitize::{mp4, webp};
 
 use crate::io::{AsyncInput, InputStream, SyncInput, SyncInputStream};
@@ -14,6 +16,7 @@ use crate::support::*;
 use crate::*;
 
 bridge_handle_fns!(SanitizedMetadata);
+bridge_handle_fns!(SanitizedWebp);
 
 /// Exposed so that we have an easy method to invoke from Java to test whether libsignal was
 /// compiled with signal-media.
@@ -27,7 +30,7 @@ async fn Mp4Sanitizer_Sanitize(
 ) -> Result<SanitizedMetadata, mp4::Error> {
     let input = AsyncInput::new(input, len);
     let metadata = mp4::sanitize(input, None).await?;
-    Ok(SanitizedMetadata(metadata))
+    Ok(SanitizedMetadata(metadata, None))
 }
 
 #[bridge_fn(ffi = false, node = false)]
@@ -38,7 +41,28 @@ async fn Mp4Sanitizer_Sanitize_File_With_Compounded_MDAT_Boxes(
 ) -> Result<SanitizedMetadata, mp4::Error> {
     let input = AsyncInput::new(input, len);
     let metadata = mp4::sanitize(input, Some(cumulative_mdat_box_size)).await?;
-    Ok(SanitizedMetadata(metadata))
+    Ok(SanitizedMetadata(metadata, None))
+}
+
+#[bridge_fn]
+async fn Mp4Sanitizer_SanitizeWithInfo(
+    input: &mut dyn InputStream,
+    len: u64,
+) -> Result<SanitizedMetadata, mp4::Error> {
+    let input = AsyncInput::new(input, len);
+    let (metadata, info) = mp4::sanitize_with_info(input, None).await?;
+    Ok(SanitizedMetadata(metadata, info))
+}
+
+#[bridge_fn(ffi = false, node = false)]
+async fn Mp4Sanitizer_SanitizeWithInfo_File_With_Compounded_MDAT_Boxes(
+    input: &mut dyn InputStream,
+    len: u64,
+    cumulative_mdat_box_size: u32,
+) -> Result<SanitizedMetadata, mp4::Error> {
+    let input = AsyncInput::new(input, len);
+    let (metadata, info) = mp4::sanitize_with_info(input, Some(cumulative_mdat_box_size)).await?;
+    Ok(SanitizedMetadata(metadata, info))
 }
 
 #[bridge_fn]
@@ -48,6 +72,15 @@ fn WebpSanitizer_Sanitize(input: &mut dyn SyncInputStream) -> Result<(), webp::E
     Ok(())
 }
 
+#[bridge_fn]
+fn WebpSanitizer_SanitizeWithInfo(
+    input: &mut dyn SyncInputStream,
+) -> Result<SanitizedWebp, webp::Error> {
+    let input = SyncInput::new(input, None);
+    let info = webp::sanitize_with_info(input)?;
+    Ok(SanitizedWebp(info))
+}
+
 #[bridge_fn]
 fn SanitizedMetadata_GetMetadata(sanitized: &SanitizedMetadata) -> &[u8] {
     sanitized.0.metadata.as_deref().unwrap_or_default()
@@ -62,3 +95,79 @@ fn SanitizedMetadata_GetDataOffset(sanitized: &SanitizedMetadata) -> u64 {
 fn SanitizedMetadata_GetDataLen(sanitized: &SanitizedMetadata) -> u64 {
     sanitized.0.data.len
 }
+
+fn duration_millis(duration: Duration) -> u64 {
+    duration.as_millis().try_into().unwrap_or(u64::MAX)
+}
+
+fn video_track(sanitized: &SanitizedMetadata) -> Option<&mp4::TrackInfo> {
+    sanitized.1.as_ref()?.video_track()
+}
+
+fn audio_track(sanitized: &SanitizedMetadata) -> Option<&mp4::TrackInfo> {
+    sanitized.1.as_ref()?.audio_track()
+}
+
+#[bridge_fn]
+fn SanitizedMetadata_GetDurationMillis(sanitized: &SanitizedMetadata) -> Option<u64> {
+    sanitized.1.as_ref()?.duration.map(duration_millis)
+}
+
+#[bridge_fn]
+fn SanitizedMetadata_GetVideoWidth(sanitized: &SanitizedMetadata) -> u32 {
+    video_track(sanitized).map_or(0, |track| track.width)
+}
+
+#[bridge_fn]
+fn SanitizedMetadata_GetVideoHeight(sanitized: &SanitizedMetadata) -> u32 {
+    video_track(sanitized).map_or(0, |track| track.height)
+}
+
+#[bridge_fn]
+fn SanitizedMetadata_GetVideoRotation(sanitized: &SanitizedMetadata) -> u32 {
+    video_track(sanitized).map_or(0, |track| track.rotation.into())
+}
+
+#[bridge_fn]
+fn SanitizedMetadata_GetVideoCodec(sanitized: &SanitizedMetadata) -> String {
+    video_track(sanitized)
+        .and_then(|track| track.codec.clone())
+        .unwrap_or_default()
+}
+
+#[bridge_fn]
+fn SanitizedMetadata_GetVideoDurationMillis(sanitized: &SanitizedMetadata) -> Option<u64> {
+    video_track(sanitized)?.duration.map(duration_millis)
+}
+
+#[bridge_fn]
+fn SanitizedMetadata_GetAudioCodec(sanitized: &SanitizedMetadata) -> String {
+    audio_track(sanitized)
+        .and_then(|track| track.codec.clone())
+        .unwrap_or_default()
+}
+
+#[bridge_fn]
+fn SanitizedMetadata_GetAudioDurationMillis(sanitized: &SanitizedMetadata) -> Option<u64> {
+    audio_track(sanitized)?.duration.map(duration_millis)
+}
+
+#[bridge_fn]
+fn SanitizedWebp_GetWidth(sanitized: &SanitizedWebp) -> u32 {
+    sanitized.0.dimensions.map_or(0, |(width, _)| width)
+}
+
+#[bridge_fn]
+fn SanitizedWebp_GetHeight(sanitized: &SanitizedWebp) -> u32 {
+    sanitized.0.dimensions.map_or(0, |(_, height)| height)
+}
+
+#[bridge_fn]
+fn SanitizedWebp_IsAnimated(sanitized: &SanitizedWebp) -> bool {
+    sanitized.0.animated
+}
+
+#[bridge_fn]
+fn SanitizedWebp_GetFrameCount(sanitized: &SanitizedWebp) -> u32 {
+    sanitized.0.frame_count
+}
@@ -8,8 +8,10 @@ use libsignal_account_keys::{AccountEntropyPool, BACKUP_FORWARD_SECRECY_TOKEN_LE
 use libsignal_bridge_macros::*;
 use libsignal_bridge_types::message_backup::*;
 use libsignal_message_backup::backup::Purpose;
-use libsignal_message_backup::frame::LimitedReaderFactory;
-use libsignal_message_backup::{BackupReader, FoundUnknownField, ReadError, ReadResult};
+use libsignal_message_backup::frame::{
+    LimitedReaderFactory, ValidationError as FrameValidationError,
+};
+use libsignal_message_backup::{BackupReader, Error, FoundUnknownField, ReadError, ReadResult};
 use libsignal_protocol::Aci;
 
 use crate::io::{AsyncInput, InputStream};
@@ -116,6 +118,139 @@ async fn MessageBackupValidator_Validate(
     })
 }
 
+bridge_handle_fns!(MessageBackupStatistics, clone = false);
+
+#[bridge_fn]
+async fn MessageBackupStatistics_Collect(
+    key: &MessageBackupKey,
+    first_stream: &mut dyn InputStream,
+    second_stream: &mut dyn InputStream,
+    len: u64,
+    purpose: AsType<Purpose, u8>,
+) -> Result<MessageBackupStatistics, ReadError> {
+    let streams = [
+        // The first stream is read in bulk, so buffering doesn't gain us anything.
+        BufReader::with_capacity(0, AsyncInput::new(first_stream, len)),
+        BufReader::new(AsyncInput::new(second_stream, len)),
+    ];
+    let factory = LimitedReaderFactory::new(streams);
+
+    let reader = BackupReader::new_encrypted_compressed(&key.0, factory, purpose.into_inner())
+        .await
+        .map_err(|e| {
+            ReadError::with_error_only(match e {
+                FrameValidationError::Io(e) => Error::Parse(e),
+                FrameValidationError::InvalidHmac(e) => Error::HmacMismatch(e),
+                e @ (FrameValidationError::MissingMetadataField(_)
+                | FrameValidationError::InvalidLength { .. }
+                | FrameValidationError::TooManyForwardSecrecyPairs(_)) => {
+                    Error::Parse(std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+                }
+            })
+        })?;
+
+    let ReadResult {
+        result,
+        found_unknown_fields,
+    } = reader.collect_statistics().await;
+
+    match result {
+        Ok(stats) => Ok(MessageBackupStatistics::new(stats, len)),
+        Err(error) => Err(ReadError {
+            error,
+            found_unknown_fields,
+        }),
+    }
+}
+
+#[bridge_fn]
+fn MessageBackupStatistics_GetFrameKinds(stats: &MessageBackupStatistics) -> Box<[String]> {
+    stats
+        .inner
+        .frames
+        .keys()
+        .map(|&kind| kind.to_owned())
+        .collect()
+}
+
+#[bridge_fn]
+fn MessageBackupStatistics_GetFrameCount(stats: &MessageBackupStatistics, kind: String) -> u64 {
+    stats.inner.frames.get(kind.as_str()).map_or(0, |f| f.count)
+}
+
+#[bridge_fn]
+fn MessageBackupStatistics_GetFrameBytes(stats: &MessageBackupStatistics, kind: String) -> u64 {
+    stats
+        .inner
+        .frames
+        .get(kind.as_str())
+        .map_or(0, |f| f.total_len)
+}
+
+/// The total size of all frames once decrypted and decompressed.
+///
+/// Dividing this by [`MessageBackupStatistics_GetBackupFileBytes`] gives the compression ratio.
+#[bridge_fn]
+fn MessageBackupStatistics_GetTotalFrameBytes(stats: &MessageBackupStatistics) -> u64 {
+    stats.inner.total_frame_len()
+}
+
+#[bridge_fn]
+fn MessageBackupStatistics_GetBackupFileBytes(stats: &MessageBackupStatistics) -> u64 {
+    stats.backup_len
+}
+
+#[bridge_fn]
+fn MessageBackupStatistics_GetAttachmentCount(stats: &MessageBackupStatistics) -> u64 {
+    stats.inner.attachments.count
+}
+
+#[bridge_fn]
+fn MessageBackupStatistics_GetAttachmentBytes(stats: &MessageBackupStatistics) -> u64 {
+    stats.inner.attachments.total_size
+}
+
+#[bridge_fn]
+fn MessageBackupStatistics_GetChatCount(stats: &MessageBackupStatistics) -> u64 {
+    stats.chat_ids.len().try_into().expect("usize fits in u64")
+}
+
+/// Returns the ID of the chat at `index`, in ascending order of ID.
+///
+/// Returns `None` if `index` is not less than [`MessageBackupStatistics_GetChatCount`].
+#[bridge_fn]
+fn MessageBackupStatistics_GetChatIdAt(stats: &MessageBackupStatistics, index: u32) -> Option<u64> {
+    let index = usize::try_from(index).ok()?;
+    stats.chat_ids.get(index).copied()
+}
+
+#[bridge_fn]
+fn MessageBackupStatistics_GetMessageCount(stats: &MessageBackupStatistics) -> u64 {
+    stats.inner.messages_per_chat.values().sum()
+}
+
+#[bridge_fn]
+fn MessageBackupStatistics_GetMessageCountForChat(
+    stats: &MessageBackupStatistics,
+    chat_id: u64,
+) -> u64 {
+    stats
+        .inner
+        .messages_per_chat
+        .get(&chat_id)
+        .copied()
+        .unwrap_or_default()
+}
+
+#[bridge_fn]
+fn MessageBackupStatistics_GetUnknownFieldCount(stats: &MessageBackupStatistics) -> u64 {
+    stats
+        .inner
+        .unknown_field_count
+        .try_into()
+        .expect("usize fits in u64")
+}
+
 bridge_handle_fns!(OnlineBackupValidator, clone = false);
 bridge_handle_fns!(BackupJsonExporter, clone = false, ffi = false);
 
@@ -12,7 +12,9 @@ pub use libsignal_bridge_types::net::{
     BuildVariant, ConnectionManager, Environment, TokioAsyncContext,
 };
 use libsignal_core::LogSafeDisplay;
-use libsignal_net::connect_state::infer_proxy_mode_for_config;
+use libsignal_net::connect_state::{
+    ConnectivityReport, LATENCY_BUCKET_COUNT, RouteStats, infer_proxy_mode_for_config,
+};
 use libsignal_net::infra::route::ConnectionProxyConfig;
 
 use crate::support::*;
@@ -47,17 +49,7 @@ fn ConnectionProxyConfig_new(
     let port = if port == i32::MIN {
         None
     } else {
-        Some(
-            u16::try_from(port)
-                .ok()
-                .and_then(NonZeroU16::new)
-                .ok_or_else(|| {
-                    std::io::Error::new(
-                        std::io::ErrorKind::InvalidInput,
-                        format!("invalid port '{port}'"),
-                    )
-                })?,
-        )
+        Some(port_from_i32(port)?)
     };
 
     let auth = match (username, password) {
@@ -91,6 +83,62 @@ fn ConnectionProxyConfig_new(
     })
 }
 
+fn port_from_i32(port: i32) -> Result<NonZeroU16, std::io::Error> {
+    u16::try_from(port)
+        .ok()
+        .and_then(NonZeroU16::new)
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("invalid port '{port}'"),
+            )
+        })
+}
+
+/// Creates a proxy config for a bridge reached through a Pluggable Transports
+/// client (such as an obfs4 library) run by the app, which listens for SOCKS5
+/// connections on `local_port`.
+///
+/// `bridge_args` are the bridge's space-separated `key=value` arguments, as
+/// found at the end of a bridge line.
+#[bridge_fn]
+fn ConnectionProxyConfig_newPluggableTransport(
+    local_port: i32,
+    bridge_host: String,
+    bridge_port: i32,
+    bridge_args: String,
+) -> Result<ConnectionProxyConfig, std::io::Error> {
+    use libsignal_net::infra::host::Host;
+    use libsignal_net::infra::route::PluggableTransportProxy;
+    use libsignal_net::infra::tcp_ssl::proxy::pluggable::LocalSocksTransport;
+
+    let local_port = port_from_i32(local_port)?;
+    let bridge_port = port_from_i32(bridge_port)?;
+    if bridge_host.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "missing bridge host",
+        ));
+    }
+    // Don't include the arguments in any error; they're as sensitive as the
+    // bridge address.
+    let args = bridge_args
+        .split_ascii_whitespace()
+        .map(|arg| arg.split_once('='))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "bridge arguments must be key=value pairs",
+            )
+        })?;
+    let transport =
+        LocalSocksTransport::new(Host::parse_as_ip_or_domain(&bridge_host), bridge_port, args)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))?;
+
+    Ok(PluggableTransportProxy::via_local_socks(local_port, transport).into())
+}
+
 bridge_handle_fns!(ConnectionManager, clone = false);
 
 #[bridge_fn]
@@ -153,6 +201,87 @@ fn ConnectionManager_on_network_change(connection_manager: &ConnectionManager) {
     connection_manager.on_network_change(std::time::Instant::now())
 }
 
+bridge_handle_fns!(ConnectivityReport, clone = false);
+
+/// Summarizes connection attempts made since the last network change.
+///
+/// The report contains only aggregate counts, so it's suitable for uploading.
+#[bridge_fn]
+fn ConnectionManager_get_connectivity_report(
+    connection_manager: &ConnectionManager,
+) -> ConnectivityReport {
+    connection_manager.connectivity_report()
+}
+
+#[bridge_fn]
+fn ConnectivityReport_collection_period_seconds(report: &ConnectivityReport) -> u64 {
+    report.collection_period.as_secs()
+}
+
+/// The kinds of route that were attempted, such as "direct" or "fronted+socks".
+#[bridge_fn]
+fn ConnectivityReport_route_kinds(report: &ConnectivityReport) -> Box<[String]> {
+    report
+        .routes
+        .iter()
+        .map(|(kind, _)| kind.to_string())
+        .collect()
+}
+
+#[bridge_fn]
+fn ConnectivityReport_route_successes(report: &ConnectivityReport, kind: String) -> u64 {
+    report.route_stats(&kind).map_or(0, |stats| stats.successes)
+}
+
+#[bridge_fn]
+fn ConnectivityReport_route_failures(report: &ConnectivityReport, kind: String) -> u64 {
+    report.route_stats(&kind).map_or(0, |stats| stats.failures)
+}
+
+#[bridge_fn]
+fn ConnectivityReport_latency_bucket_count() -> u32 {
+    LATENCY_BUCKET_COUNT.try_into().expect("small")
+}
+
+/// The number of successful connections for `kind` that fell in latency bucket `bucket`.
+///
+/// Buckets are ordered from fastest to slowest; the last one has no upper bound.
+#[bridge_fn]
+fn ConnectivityReport_latency_bucket(
+    report: &ConnectivityReport,
+    kind: String,
+    bucket: u32,
+) -> u64 {
+    let Some(RouteStats { latency, .. }) = report.route_stats(&kind) else {
+        return 0;
+    };
+    usize::try_from(bucket)
+        .ok()
+        .and_then(|bucket| latency.counts().get(bucket))
+        .copied()
+        .unwrap_or(0)
+}
+
+#[bridge_fn]
+fn ConnectivityReport_dns_system_lookups(report: &ConnectivityReport) -> u64 {
+    report.dns_lookups.system
+}
+
+#[bridge_fn]
+fn ConnectivityReport_dns_doh_lookups(report: &ConnectivityReport) -> u64 {
+    report.dns_lookups.doh
+}
+
+#[bridge_fn]
+fn ConnectivityReport_dns_dot_lookups(report: &ConnectivityReport) -> u64 {
+    report.dns_lookups.dot
+}
+
+#[bridge_fn]
+fn ConnectivityReport_dns_static_lookups(report: &ConnectivityReport) -> u64 {
+    report.dns_lookups.static_fallback
+}
+
 #[cfg(any(feature = "node", feature = "jni", feature = "ffi"))]
 #[cfg(test)]
 mod test {
@@ -170,4 +299,24 @@ mod test {
         ConnectionProxyConfig_new(scheme.to_owned(), "host".to_owned(), 80, None, None)
             .expect("valid")
     }
+
+    #[test_case(9050, "192.0.2.1", 443, "cert=abc iat-mode=0" => matches Ok(ConnectionProxyConfig::Pluggable(_)); "valid")]
+    #[test_case(9050, "192.0.2.1", 443, "" => matches Ok(ConnectionProxyConfig::Pluggable(_)); "no arguments")]
+    #[test_case(0, "192.0.2.1", 443, "cert=abc" => matches Err(_); "invalid local port")]
+    #[test_case(9050, "192.0.2.1", 70000, "cert=abc" => matches Err(_); "invalid bridge port")]
+    #[test_case(9050, "", 443, "cert=abc" => matches Err(_); "missing bridge host")]
+    #[test_case(9050, "192.0.2.1", 443, "cert" => matches Err(_); "malformed arguments")]
+    fn connection_proxy_config_pluggable_transport(
+        local_port: i32,
+        bridge_host: &str,
+        bridge_port: i32,
+        bridge_args: &str,
+    ) -> Result<ConnectionProxyConfig, std::io::Error> {
+        ConnectionProxyConfig_newPluggableTransport(
+            local_port,
+            bridge_host.to_owned(),
+            bridge_port,
+            bridge_args.to_owned(),
+        )
+    }
 }
@@ -482,6 +482,7 @@ impl IntoFfiError for DeviceTransferError {
     fn into_ffi_error(self) -> impl Into<SignalFfiError> {
         let code = match self {
             Self::KeyDecodingFailed => SignalErrorCode::InvalidKey,
+            Self::CertificateDecodingFailed => SignalErrorCode::InvalidArgument,
             Self::InternalError(_) => SignalErrorCode::InternalError,
         };
         SimpleError::new(code, format!("Device transfer operation failed: {self}"))
@@ -881,9 +882,12 @@ impl IntoFfiError for KeyTransError {
     fn into_ffi_error(self) -> impl Into<SignalFfiError> {
         let message = self.to_string();
         let code = match self {
-            Self::VerificationFailed(libsignal_keytrans::Error::VerificationFailed(_)) => {
-                SignalErrorCode::KeyTransparencyVerificationFailed
-            }
+            Self::VerificationFailed(
+                libsignal_keytrans::Error::VerificationFailed(_)
+                | libsignal_keytrans::Error::SelfMonitorVersionChanged
+                | libsignal_keytrans::Error::VersionDecreased
+                | libsignal_keytrans::Error::UnexpectedSearchValue,
+            ) => SignalErrorCode::KeyTransparencyVerificationFailed,
             Self::VerificationFailed(_) | Self::InvalidResponse(_) | Self::InvalidRequest(_) => {
                 SignalErrorCode::KeyTransparencyError
             }
@@ -512,7 +512,9 @@ impl MessageOnlyExceptionJniError for SignalCryptoError {
 impl MessageOnlyExceptionJniError for DeviceTransferError {
     fn exception_class(&self) -> ClassName<'static> {
         match self {
-            DeviceTransferError::InternalError(_) | DeviceTransferError::KeyDecodingFailed => {
+            DeviceTransferError::InternalError(_)
+            | DeviceTransferError::KeyDecodingFailed
+            | DeviceTransferError::CertificateDecodingFailed => {
                 ClassName("java.lang.RuntimeException")
             }
         }
@@ -1036,9 +1038,12 @@ impl MessageOnlyExceptionJniError for libsignal_net_chat::api::DisconnectedError
 impl MessageOnlyExceptionJniError for libsignal_net_chat::api::keytrans::Error {
     fn exception_class(&self) -> ClassName<'static> {
         match self {
-            Self::VerificationFailed(libsignal_keytrans::Error::VerificationFailed(_)) => {
-                ClassName("org.signal.libsignal.keytrans.VerificationFailedException")
-            }
+            Self::VerificationFailed(
+                libsignal_keytrans::Error::VerificationFailed(_)
+                | libsignal_keytrans::Error::SelfMonitorVersionChanged
+                | libsignal_keytrans::Error::VersionDecreased
+                | libsignal_keytrans::Error::UnexpectedSearchValue,
+            ) => ClassName("org.signal.libsignal.keytrans.VerificationFailedException"),
             Self::VerificationFailed(_) | Self::InvalidResponse(_) | Self::InvalidRequest(_) => {
                 ClassName("org.signal.libsignal.keytrans.KeyTransparencyException")
             }
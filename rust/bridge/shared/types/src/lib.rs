@@ -42,6 +42,19 @@ mod pin {
     bridge_as_handle!(PinHash);
 }
 
+pub mod device_transfer {
+    /// The algorithm of a device transfer key, as selected by the app.
+    #[repr(u8)]
+    #[derive(Clone, Copy, derive_more::TryFrom)]
+    #[try_from(repr)]
+    pub enum DeviceTransferKeyType {
+        /// RSA, with the same key size as the other key generation functions.
+        Rsa = 0,
+        /// ECDSA over the NIST P-256 curve.
+        EcdsaP256 = 1,
+    }
+}
+
 pub mod incremental_mac;
 pub mod message_backup;
 
@@ -57,9 +70,17 @@ pub mod media {
         // type even when the feature is disabled. Having the type always
         // present works around this bug.
         #[cfg(feature = "signal-media")] pub signal_media::sanitize::mp4::SanitizedMetadata,
+        #[cfg(feature = "signal-media")] pub Option<signal_media::sanitize::mp4::Mp4Info>,
+    );
+
+    // Wrapper struct for cbindgen; see above.
+    #[derive(Clone, Debug)]
+    pub struct SanitizedWebp(
+        #[cfg(feature = "signal-media")] pub signal_media::sanitize::webp::WebpInfo,
     );
 
     use crate::*;
 
     bridge_as_handle!(SanitizedMetadata);
+    bridge_as_handle!(SanitizedWebp);
 }
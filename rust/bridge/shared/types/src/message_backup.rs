@@ -102,6 +102,26 @@ pub struct MessageBackupValidationOutcome {
 }
 bridge_as_handle!(MessageBackupValidationOutcome, jni = false, node = false);
 
+pub struct MessageBackupStatistics {
+    pub inner: libsignal_message_backup::stats::BackupStatistics,
+    /// The length of the backup file the statistics were collected from.
+    pub backup_len: u64,
+    /// The keys of `inner.messages_per_chat`, so that they can be looked up by index.
+    pub chat_ids: Box<[u64]>,
+}
+bridge_as_handle!(MessageBackupStatistics);
+
+impl MessageBackupStatistics {
+    pub fn new(inner: libsignal_message_backup::stats::BackupStatistics, backup_len: u64) -> Self {
+        let chat_ids = inner.messages_per_chat.keys().copied().collect();
+        Self {
+            inner,
+            backup_len,
+            chat_ids,
+        }
+    }
+}
+
 /// A (line, error_message) pair for a single exported frame.
 pub type JsonFrameExportResult = (Option<String>, Option<String>);
 
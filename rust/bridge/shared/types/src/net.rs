@@ -10,12 +10,13 @@ use std::time::{Duration, Instant};
 
 use libsignal_core::LogSafeDisplay as _;
 use libsignal_net::connect_state::{
-    ConnectState, ConnectionResources, DefaultConnectorFactory, PreconnectingFactory,
-    SUGGESTED_CONNECT_CONFIG, SUGGESTED_TLS_PRECONNECT_LIFETIME,
+    ConnectState, ConnectionResources, ConnectivityReport, DefaultConnectorFactory,
+    PreconnectingFactory, SUGGESTED_CONNECT_CONFIG, SUGGESTED_TLS_PRECONNECT_LIFETIME,
 };
 use libsignal_net::enclave::{EnclaveEndpoint, EnclaveKind};
 use libsignal_net::env::{Env, StaticIpOrder, UserAgent};
-use libsignal_net::infra::dns::DnsResolver;
+use libsignal_net::infra::certs::{CertificatePolicy, SpkiPins, SpkiSha256};
+use libsignal_net::infra::dns::{DnsResolver, EncryptedDnsFallback};
 use libsignal_net::infra::route::{
     ConnectionProxyConfig, DirectOrProxyMode, DirectOrProxyProvider, RouteProvider,
     RouteProviderExt as _, UnresolvedWebsocketServiceRoute,
@@ -25,7 +26,7 @@ use libsignal_net::infra::{AsHttpHeader as _, EnableDomainFronting, OverrideNagl
 use rand::TryRngCore as _;
 
 pub use self::remote_config::BuildVariant;
-use self::remote_config::RemoteConfig;
+use self::remote_config::{RemoteConfig, RemoteConfigKey};
 use crate::*;
 
 pub mod cdsi;
@@ -144,7 +145,7 @@ impl ConnectionManager {
     }
 
     pub fn new_from_static_environment(
-        env: Env<'static>,
+        mut env: Env<'static>,
         user_agent: &str,
         remote_config: HashMap<String, Arc<str>>,
         build_variant: BuildVariant,
@@ -152,13 +153,35 @@ impl ConnectionManager {
         let (network_change_event_tx, network_change_event_rx) = ::tokio::sync::watch::channel(());
         let user_agent = UserAgent::with_libsignal_version(user_agent);
 
-        let dns_resolver = DnsResolver::new_with_static_fallback(
+        let remote_config = RemoteConfig::new(remote_config, build_variant);
+        let encrypted_dns_fallback =
+            if remote_config.is_enabled(RemoteConfigKey::DnsOverTlsFallback) {
+                EncryptedDnsFallback::Tls
+            } else {
+                EncryptedDnsFallback::Https
+            };
+        let dns_resolver = DnsResolver::new_with_encrypted_fallback(
             env.static_fallback(StaticIpOrder::Shuffled(&mut rand::rngs::OsRng.unwrap_err())),
+            encrypted_dns_fallback,
             &network_change_event_rx,
         );
+        if let Some(pins) = remote_config
+            .get(RemoteConfigKey::ChatCertificatePins)
+            .as_option()
+        {
+            match parse_certificate_pins(pins) {
+                Some(policy) => {
+                    log::info!("enforcing certificate pins for chat");
+                    env.chat_domain_config.connect = env
+                        .chat_domain_config
+                        .connect
+                        .config_with_certificate_policy(policy);
+                }
+                None => log::warn!("ignoring invalid {}", RemoteConfigKey::ChatCertificatePins),
+            }
+        }
         let transport_connector =
             std::sync::Mutex::new(TcpSslConnector::new_direct(dns_resolver.clone()));
-        let remote_config = RemoteConfig::new(remote_config, build_variant);
         let endpoints = std::sync::Mutex::new(
             EndpointConnections::new(&env, false, EnforceMinimumTls::Yes).into(),
         );
@@ -285,6 +308,13 @@ impl ConnectionManager {
             .network_changed(now.into());
     }
 
+    pub fn connectivity_report(&self) -> ConnectivityReport {
+        self.connect
+            .lock()
+            .expect("not poisoned")
+            .connectivity_report(&self.dns_resolver)
+    }
+
     pub fn enclave_connection_resources(
         &self,
         enclave: &EnclaveEndpoint<impl EnclaveKind>,
@@ -332,8 +362,24 @@ impl ConnectionManager {
     }
 }
 
+/// Parses the value of [`RemoteConfigKey::ChatCertificatePins`].
+fn parse_certificate_pins(value: &str) -> Option<Arc<CertificatePolicy>> {
+    let mut pins = value.split(',').map(|pin| {
+        let mut hash = SpkiSha256::default();
+        hex::decode_to_slice(pin.trim(), &mut hash).ok()?;
+        Some(hash)
+    });
+    let primary = pins.next()??;
+    let backups = pins.collect::<Option<Vec<_>>>()?;
+    Some(Arc::new(CertificatePolicy {
+        pins: Some(SpkiPins::new(primary, backups).ok()?),
+        certificate_transparency: None,
+    }))
+}
+
 bridge_as_handle!(ConnectionManager);
 bridge_as_handle!(ConnectionProxyConfig);
+bridge_as_handle!(ConnectivityReport);
 
 #[cfg(test)]
 mod test {
@@ -356,6 +402,38 @@ mod test {
         );
     }
 
+    #[test]
+    fn chat_certificate_pins_from_remote_config() {
+        let primary = "aa".repeat(32);
+        let backup = "BB".repeat(32);
+        let cm = ConnectionManager::new(
+            Environment::Prod,
+            "test-user-agent",
+            HashMap::from([(
+                RemoteConfigKey::ChatCertificatePins.to_string(),
+                format!("{primary}, {backup}").into(),
+            )]),
+            BuildVariant::Production,
+        );
+        assert_matches!(
+            &cm.env.chat_domain_config.connect.cert,
+            libsignal_net::infra::certs::RootCertificates::WithPolicy { policy, .. }
+                if **policy == CertificatePolicy {
+                    pins: Some(SpkiPins::new([0xaa; 32], [[0xbb; 32]]).expect("valid")),
+                    certificate_transparency: None,
+                }
+        );
+    }
+
+    #[test_case(""; "empty")]
+    #[test_case(&"aa".repeat(32); "no backup")]
+    #[test_case(&format!("{},{}", "aa".repeat(32), "aa".repeat(32)); "backup same as primary")]
+    #[test_case(&format!("{},{}", "aa".repeat(32), "bb".repeat(31)); "short pin")]
+    #[test_case(&format!("{},{},", "aa".repeat(32), "bb".repeat(32)); "trailing comma")]
+    fn invalid_certificate_pins(value: &str) {
+        assert_matches!(parse_certificate_pins(value), None);
+    }
+
     #[test_case(
         Environment::Staging,
         "/tls-tunnel-staging",
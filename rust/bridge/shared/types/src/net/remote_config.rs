@@ -104,6 +104,16 @@ pub enum RemoteConfigKey {
     /// How long to wait for a response to a chat request before checking whether the connection is
     /// still active.
     ChatRequestConnectionCheckTimeoutMilliseconds => "chatRequestConnectionCheckTimeoutMillis",
+    /// Fall back to DNS-over-TLS instead of DNS-over-HTTPS when the system resolver fails.
+    ///
+    /// Only read when the connection manager is created.
+    DnsOverTlsFallback => "dnsOverTlsFallback",
+    /// Require the chat server's certificate chain to include one of these public keys.
+    ///
+    /// Comma-separated, hex-encoded SHA-256 hashes of SubjectPublicKeyInfos, starting with the key
+    /// currently in use; at least one backup is required. Only read when the connection manager is
+    /// created.
+    ChatCertificatePins => "chatCertificatePins",
 
     // Typed API keys, based on gRPC request names.
     // These should all start with "grpc." and optionally end with ".{digit}"
@@ -1124,7 +1124,10 @@ impl SignalNodeError for libsignal_net_chat::api::keytrans::Error {
         let message = self.to_string();
         let name = match self {
             libsignal_net_chat::api::keytrans::Error::VerificationFailed(
-                KtError::VerificationFailed(_),
+                KtError::VerificationFailed(_)
+                | KtError::SelfMonitorVersionChanged
+                | KtError::VersionDecreased
+                | KtError::UnexpectedSearchValue,
             ) => "KeyTransparencyVerificationFailed",
             libsignal_net_chat::api::keytrans::Error::VerificationFailed(_)
             | libsignal_net_chat::api::keytrans::Error::InvalidResponse(_)
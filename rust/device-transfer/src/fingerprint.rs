@@ -0,0 +1,33 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Checking the other device's certificate against the fingerprint exchanged out of band.
+
+use boring::hash::MessageDigest;
+use boring::x509::{X509, X509Ref};
+
+use crate::Error;
+
+/// Compute the SHA-256 fingerprint of a DER-encoded certificate.
+///
+/// This is the value exchanged out of band (e.g. in the QR code) so that each device can pin the
+/// other's self-signed certificate.
+pub fn certificate_fingerprint(cert_der: &[u8]) -> Result<[u8; 32], Error> {
+    let cert = X509::from_der(cert_der).map_err(|_| Error::CertificateDecodingFailed)?;
+    cert_fingerprint(&cert).ok_or(Error::InternalError("Hashing certificate failed"))
+}
+
+/// Check a peer's DER-encoded certificate against the fingerprint exchanged out of band.
+pub fn certificate_matches_fingerprint(
+    cert_der: &[u8],
+    expected_fingerprint: &[u8],
+) -> Result<bool, Error> {
+    Ok(certificate_fingerprint(cert_der)?[..] == *expected_fingerprint)
+}
+
+pub(crate) fn cert_fingerprint(cert: &X509Ref) -> Option<[u8; 32]> {
+    let digest = cert.digest(MessageDigest::sha256()).ok()?;
+    digest[..].try_into().ok()
+}
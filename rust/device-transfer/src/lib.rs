@@ -12,17 +12,26 @@ use std::fmt;
 use std::time::{Duration, SystemTime};
 
 use boring::asn1::Asn1Time;
+use boring::ec::{EcGroup, EcKey};
 use boring::error::ErrorStack;
 use boring::hash::MessageDigest;
+use boring::nid::Nid;
 use boring::pkey::{PKey, Private};
 use boring::rsa::Rsa;
 use boring::x509::{X509, X509Builder, X509Name, X509NameBuilder};
 
+mod fingerprint;
+pub use fingerprint::{certificate_fingerprint, certificate_matches_fingerprint};
+
+pub mod transfer;
+
 /// Error types for device transfer.
 #[derive(Copy, Clone, Debug)]
 pub enum Error {
-    /// Failure to decode some provided RSA private key.
+    /// Failure to decode some provided private key.
     KeyDecodingFailed,
+    /// Failure to decode some provided certificate.
+    CertificateDecodingFailed,
     /// Internal error in device transfer.
     InternalError(&'static str),
 }
@@ -30,7 +39,8 @@ pub enum Error {
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Error::KeyDecodingFailed => write!(f, "Decoding provided RSA private key failed"),
+            Error::KeyDecodingFailed => write!(f, "Decoding provided private key failed"),
+            Error::CertificateDecodingFailed => write!(f, "Decoding provided certificate failed"),
             Error::InternalError(s) => write!(f, "Internal error in device transfer ({s})"),
         }
     }
@@ -54,12 +64,41 @@ impl From<u8> for KeyFormat {
     }
 }
 
+/// Certificate key algorithm.
+#[derive(Copy, Clone, Debug)]
+pub enum KeyType {
+    /// RSA with a modulus of size `bits`
+    Rsa {
+        /// The size of the modulus in bits
+        bits: usize,
+    },
+    /// ECDSA over the NIST P-256 curve, much faster to generate than RSA
+    EcdsaP256,
+}
+
 /// Generate a private key of size `bits` and export to a specified format.
 pub fn create_rsa_private_key(bits: usize, key_format: KeyFormat) -> Result<Vec<u8>, Error> {
-    let bits = u32::try_from(bits).map_err(|_| Error::InternalError("invalid key size"))?;
-    let rsa = Rsa::generate(bits).map_err(|_| Error::InternalError("RSA key generation failed"))?;
-    let key =
-        PKey::from_rsa(rsa).map_err(|_| Error::InternalError("Private key generation failed"))?;
+    create_private_key(KeyType::Rsa { bits }, key_format)
+}
+
+/// Generate a private key of type `key_type` and export to a specified format.
+pub fn create_private_key(key_type: KeyType, key_format: KeyFormat) -> Result<Vec<u8>, Error> {
+    let key = match key_type {
+        KeyType::Rsa { bits } => {
+            let bits = u32::try_from(bits).map_err(|_| Error::InternalError("invalid key size"))?;
+            let rsa = Rsa::generate(bits)
+                .map_err(|_| Error::InternalError("RSA key generation failed"))?;
+            PKey::from_rsa(rsa)
+        }
+        KeyType::EcdsaP256 => {
+            let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)
+                .map_err(|_| Error::InternalError("Loading curve failed"))?;
+            let ec_key = EcKey::generate(&group)
+                .map_err(|_| Error::InternalError("EC key generation failed"))?;
+            PKey::from_ec_key(ec_key)
+        }
+    }
+    .map_err(|_| Error::InternalError("Private key generation failed"))?;
     private_key_to_der(key, key_format)
 }
 
@@ -76,14 +115,13 @@ fn private_key_to_der(key: PKey<Private>, format: KeyFormat) -> Result<Vec<u8>,
 
 /// Generate a self-signed certificate of name `name`, expiring in `days_to_expire`.
 ///
-/// `rsa_key_pkcs8` should be the output of [create_rsa_private_key].
+/// `private_key_der` should be the output of [create_private_key] or [create_rsa_private_key].
 pub fn create_self_signed_cert(
-    rsa_key_pkcs8: &[u8],
+    private_key_der: &[u8],
     name: &str,
     days_to_expire: u32,
 ) -> Result<Vec<u8>, Error> {
-    let rsa_key =
-        PKey::private_key_from_der(rsa_key_pkcs8).map_err(|_| Error::KeyDecodingFailed)?;
+    let key = PKey::private_key_from_der(private_key_der).map_err(|_| Error::KeyDecodingFailed)?;
 
     let valid_after_timestamp: libc::time_t = (SystemTime::now()
         - Duration::from_secs(60 * 60 * 24))
@@ -93,7 +131,7 @@ pub fn create_self_signed_cert(
     .try_into()
     .map_err(|_| Error::InternalError("Could not generate valid start timestamp"))?;
 
-    let cert = build_cert(rsa_key, name, valid_after_timestamp, days_to_expire)
+    let cert = build_cert(key, name, valid_after_timestamp, days_to_expire)
         .map_err(|_| Error::InternalError("Creating certificate failed"))?;
 
     cert.to_der()
@@ -101,7 +139,7 @@ pub fn create_self_signed_cert(
 }
 
 fn build_cert(
-    rsa_key: PKey<Private>,
+    key: PKey<Private>,
     name: &str,
     valid_after_timestamp: libc::time_t,
     days_to_expire: u32,
@@ -119,8 +157,8 @@ fn build_cert(
     cert_builder.set_not_before(&started_at)?;
     cert_builder.set_not_after(&ends_at)?;
 
-    cert_builder.set_pubkey(&rsa_key)?;
-    cert_builder.sign(&rsa_key, MessageDigest::sha256())?;
+    cert_builder.set_pubkey(&key)?;
+    cert_builder.sign(&key, MessageDigest::sha256())?;
 
     Ok(cert_builder.build())
 }
@@ -0,0 +1,544 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! The transfer session run between the old and new device.
+//!
+//! Both devices authenticate with the self-signed certificates from
+//! [`create_self_signed_cert`](crate::create_self_signed_cert), each pinning the fingerprint of the
+//! other's certificate (exchanged out of band, e.g. by scanning a QR code). After a version
+//! handshake, the sender lists the items it will transfer—the backup stream and each attachment
+//! file—and the receiver replies with how much of each it already has from an earlier attempt, so
+//! an interrupted transfer can pick up where it left off. Each item carries a generation, so that
+//! data kept from an attempt at a different version of the item is thrown away rather than
+//! resumed.
+//!
+//! Sessions work on blocking streams, normally a [`std::net::TcpStream`].
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use boring::error::ErrorStack;
+use boring::pkey::{PKey, Private};
+use boring::ssl::{
+    SslAcceptor, SslConnector, SslContextBuilder, SslMethod, SslStream, SslVerifyMode, SslVersion,
+};
+use boring::x509::X509;
+
+use crate::fingerprint::cert_fingerprint;
+
+mod frame;
+use frame::Frame;
+
+/// The version of the transfer protocol implemented here.
+///
+/// Both sides must use the same version.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// The maximum amount of item data sent in a single frame.
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Errors that end a transfer session.
+#[derive(Debug)]
+pub enum TransferError {
+    /// The provided private key or certificate could not be used.
+    InvalidIdentity,
+    /// The peer's certificate didn't match the pinned fingerprint.
+    PeerCertificateMismatch,
+    /// The TLS handshake failed for some other reason.
+    HandshakeFailed,
+    /// The peer uses a different version of the transfer protocol.
+    UnsupportedVersion(u32),
+    /// The peer sent something that doesn't follow the transfer protocol.
+    Protocol(&'static str),
+    /// The transfer was cancelled locally.
+    Cancelled,
+    /// The peer cancelled the transfer.
+    CancelledByPeer,
+    /// Reading or writing the connection, the source, or the sink failed.
+    Io(io::Error),
+    /// Internal error in device transfer.
+    InternalError(&'static str),
+}
+
+impl fmt::Display for TransferError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidIdentity => write!(f, "Decoding provided key or certificate failed"),
+            Self::PeerCertificateMismatch => {
+                write!(
+                    f,
+                    "Peer certificate does not match the expected fingerprint"
+                )
+            }
+            Self::HandshakeFailed => write!(f, "TLS handshake failed"),
+            Self::UnsupportedVersion(v) => write!(f, "Peer uses unsupported protocol version {v}"),
+            Self::Protocol(s) => write!(f, "Protocol error ({s})"),
+            Self::Cancelled => write!(f, "Transfer was cancelled"),
+            Self::CancelledByPeer => write!(f, "Transfer was cancelled by the other device"),
+            Self::Io(e) => write!(f, "IO error: {e}"),
+            Self::InternalError(s) => write!(f, "Internal error in device transfer ({s})"),
+        }
+    }
+}
+
+impl std::error::Error for TransferError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for TransferError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+/// The private key and certificate one device presents to the other.
+pub struct TransferIdentity {
+    key: PKey<Private>,
+    cert: X509,
+}
+
+impl TransferIdentity {
+    /// Loads an identity from a DER-encoded private key and the certificate made from it.
+    ///
+    /// These are normally the outputs of [`create_private_key`](crate::create_private_key) and
+    /// [`create_self_signed_cert`](crate::create_self_signed_cert).
+    pub fn new(private_key_der: &[u8], cert_der: &[u8]) -> Result<Self, TransferError> {
+        let key = PKey::private_key_from_der(private_key_der)
+            .map_err(|_| TransferError::InvalidIdentity)?;
+        let cert = X509::from_der(cert_der).map_err(|_| TransferError::InvalidIdentity)?;
+        let matches = cert
+            .public_key()
+            .map_err(|_| TransferError::InvalidIdentity)?
+            .public_eq(&key);
+        if !matches {
+            return Err(TransferError::InvalidIdentity);
+        }
+        Ok(Self { key, cert })
+    }
+
+    /// The fingerprint the peer should pin for this identity: the SHA-256 digest of its
+    /// DER-encoded certificate.
+    pub fn fingerprint(&self) -> Result<[u8; 32], TransferError> {
+        cert_fingerprint(&self.cert)
+            .ok_or(TransferError::InternalError("Hashing certificate failed"))
+    }
+}
+
+/// Something being transferred.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ItemId {
+    /// The backup stream (as produced for `message-backup`).
+    Backup,
+    /// An attachment file, identified by a name that's meaningful to both clients.
+    ///
+    /// The name must be a single path component: sessions refuse to send or accept names that are
+    /// empty, `.` or `..`, or that contain `/`, `\`, or NUL. It still comes from the other device,
+    /// though, so a [`TransferSink`] should treat it as untrusted.
+    Attachment(String),
+}
+
+/// An item listed for transfer, along with its total length in bytes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ItemInfo {
+    /// Which item this is.
+    pub id: ItemId,
+    /// The total length of the item's data.
+    pub len: u64,
+    /// Identifies this version of the item's data, such as a content hash or a modification
+    /// counter.
+    ///
+    /// Data the receiver kept from an earlier session is only resumed if it was received for the
+    /// same generation; otherwise the item is transferred again from the start.
+    pub generation: u64,
+}
+
+/// Provides the items to send.
+pub trait TransferSource {
+    /// Lists every item to be sent, in the order they should be sent.
+    fn items(&mut self) -> io::Result<Vec<ItemInfo>>;
+
+    /// Opens an item to read its data starting from `offset`.
+    ///
+    /// The reader must produce at least the rest of the item's listed length.
+    fn open(&mut self, item: &ItemInfo, offset: u64) -> io::Result<Box<dyn Read + '_>>;
+}
+
+/// Data stored for an item by an earlier, interrupted transfer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReceivedData {
+    /// How many bytes of the item are stored.
+    pub len: u64,
+    /// The [`ItemInfo::generation`] the data was received for.
+    pub generation: u64,
+}
+
+/// Stores received items.
+pub trait TransferSink {
+    /// What's already stored for the item `id` from an earlier, interrupted transfer, if anything.
+    ///
+    /// If it was received for the item's current generation, transfer of the item continues from
+    /// the end of the stored data. Otherwise the data is [discarded](Self::discard) and the item is
+    /// received again from the start.
+    fn received(&mut self, id: &ItemId) -> io::Result<Option<ReceivedData>>;
+
+    /// Throws away whatever is stored for `item`.
+    fn discard(&mut self, item: &ItemInfo) -> io::Result<()>;
+
+    /// Appends data to `item`.
+    ///
+    /// The sink needs to remember `item`'s generation along with the data, to report from
+    /// [`Self::received`] in a later session.
+    fn write(&mut self, item: &ItemInfo, data: &[u8]) -> io::Result<()>;
+
+    /// Called when all of `item`'s data has been received.
+    ///
+    /// This is called for every item in every session, including items whose data was all
+    /// received in an earlier session.
+    fn finish_item(&mut self, item: &ItemInfo) -> io::Result<()>;
+}
+
+/// The progress of a transfer, reported after every chunk and every completed item.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Progress {
+    /// Bytes the receiver has, including any kept from an earlier session.
+    pub bytes_transferred: u64,
+    /// The total length of all items.
+    pub total_bytes: u64,
+    /// Items whose data has all been received.
+    pub items_completed: u32,
+    /// The number of items in the transfer.
+    pub item_count: u32,
+}
+
+/// Lets a transfer be cancelled from another thread.
+///
+/// Cancellation is checked between frames; the peer is told the transfer is cancelled, and the
+/// session ends with [`TransferError::Cancelled`]. A session blocked waiting for the peer won't
+/// notice until the peer sends something, so a read timeout on the underlying stream is also
+/// recommended. Likewise, a sender that's in the middle of streaming data when the receiver
+/// cancels may see the connection close before it sees the cancellation.
+#[derive(Clone, Debug, Default)]
+pub struct Cancellation(Arc<AtomicBool>);
+
+impl Cancellation {
+    /// Creates a new, uncancelled handle.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests cancellation of any session using this handle.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether [`Self::cancel`] has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// An established, authenticated connection to the other device.
+pub struct TransferSession<S> {
+    stream: SslStream<S>,
+}
+
+impl<S: Read + Write> TransferSession<S> {
+    /// Connects to the device listening on the other end of `stream`.
+    pub fn connect(
+        stream: S,
+        identity: &TransferIdentity,
+        peer_fingerprint: &[u8; 32],
+    ) -> Result<Self, TransferError> {
+        let mismatch = Arc::new(AtomicBool::new(false));
+        let mut builder = SslConnector::builder(SslMethod::tls())
+            .map_err(|_| TransferError::InternalError("Creating TLS connector failed"))?;
+        configure(&mut builder, identity, *peer_fingerprint, &mismatch)
+            .map_err(|_| TransferError::InternalError("Configuring TLS connector failed"))?;
+        let config = builder
+            .build()
+            .configure()
+            .map_err(|_| TransferError::InternalError("Configuring TLS connection failed"))?
+            // There's no name to check; the certificate is pinned instead.
+            .use_server_name_indication(false)
+            .verify_hostname(false);
+        let stream = config
+            .connect("", stream)
+            .map_err(|_| handshake_error(&mismatch))?;
+        Self::start(stream)
+    }
+
+    /// Accepts a connection from the device on the other end of `stream`.
+    pub fn accept(
+        stream: S,
+        identity: &TransferIdentity,
+        peer_fingerprint: &[u8; 32],
+    ) -> Result<Self, TransferError> {
+        let mismatch = Arc::new(AtomicBool::new(false));
+        let mut builder = SslAcceptor::mozilla_modern(SslMethod::tls())
+            .map_err(|_| TransferError::InternalError("Creating TLS acceptor failed"))?;
+        configure(&mut builder, identity, *peer_fingerprint, &mismatch)
+            .map_err(|_| TransferError::InternalError("Configuring TLS acceptor failed"))?;
+        let stream = builder
+            .build()
+            .accept(stream)
+            .map_err(|_| handshake_error(&mismatch))?;
+        Self::start(stream)
+    }
+
+    fn start(stream: SslStream<S>) -> Result<Self, TransferError> {
+        let mut session = Self { stream };
+        session.write(&Frame::Hello {
+            version: PROTOCOL_VERSION,
+        })?;
+        match session.read()? {
+            Frame::Hello {
+                version: PROTOCOL_VERSION,
+            } => Ok(session),
+            Frame::Hello { version } => Err(TransferError::UnsupportedVersion(version)),
+            _ => Err(TransferError::Protocol("expected hello")),
+        }
+    }
+
+    /// Sends every item from `source`, skipping whatever the receiver already has.
+    pub fn send(
+        mut self,
+        source: &mut dyn TransferSource,
+        progress: &mut dyn FnMut(Progress),
+        cancellation: &Cancellation,
+    ) -> Result<(), TransferError> {
+        let items = source.items()?;
+        let total_bytes =
+            total_len(&items).ok_or(TransferError::InternalError("items are too large"))?;
+        let item_count = u32::try_from(items.len())
+            .map_err(|_| TransferError::InternalError("too many items"))?;
+
+        self.check_cancelled(cancellation)?;
+        self.write(&Frame::Manifest(items.clone()))?;
+        let offsets = match self.read()? {
+            Frame::Resume(offsets) => offsets,
+            Frame::Cancel => return Err(TransferError::CancelledByPeer),
+            _ => return Err(TransferError::Protocol("expected resume offsets")),
+        };
+        if offsets.len() != items.len()
+            || offsets
+                .iter()
+                .zip(&items)
+                .any(|(offset, item)| *offset > item.len)
+        {
+            return Err(TransferError::Protocol("invalid resume offsets"));
+        }
+
+        let mut status = Progress {
+            bytes_transferred: offsets.iter().sum(),
+            total_bytes,
+            items_completed: 0,
+            item_count,
+        };
+        progress(status);
+
+        let mut buf = vec![0; CHUNK_SIZE];
+        for ((index, item), offset) in (0..item_count).zip(&items).zip(offsets) {
+            let mut remaining = item.len - offset;
+            if remaining > 0 {
+                let mut reader = source.open(item, offset)?;
+                while remaining > 0 {
+                    self.check_cancelled(cancellation)?;
+                    let amount =
+                        usize::try_from(remaining).map_or(CHUNK_SIZE, |r| r.min(CHUNK_SIZE));
+                    reader.read_exact(&mut buf[..amount])?;
+                    self.write(&Frame::Chunk {
+                        item: index,
+                        data: buf[..amount].to_vec(),
+                    })?;
+                    remaining -= amount as u64;
+                    status.bytes_transferred += amount as u64;
+                    progress(status);
+                }
+            }
+            self.write(&Frame::ItemComplete { item: index })?;
+            status.items_completed += 1;
+            progress(status);
+        }
+
+        self.check_cancelled(cancellation)?;
+        self.write(&Frame::Done)?;
+        match self.read()? {
+            Frame::Complete => {
+                self.close();
+                Ok(())
+            }
+            Frame::Cancel => Err(TransferError::CancelledByPeer),
+            _ => Err(TransferError::Protocol("expected completion")),
+        }
+    }
+
+    /// Receives items into `sink` until the sender is done.
+    pub fn receive(
+        mut self,
+        sink: &mut dyn TransferSink,
+        progress: &mut dyn FnMut(Progress),
+        cancellation: &Cancellation,
+    ) -> Result<(), TransferError> {
+        let items = match self.read()? {
+            Frame::Manifest(items) => items,
+            Frame::Cancel => return Err(TransferError::CancelledByPeer),
+            _ => return Err(TransferError::Protocol("expected manifest")),
+        };
+        self.check_cancelled(cancellation)?;
+        let total_bytes =
+            total_len(&items).ok_or(TransferError::Protocol("items are too large"))?;
+        let item_count =
+            u32::try_from(items.len()).map_err(|_| TransferError::Protocol("too many items"))?;
+
+        let mut received = Vec::with_capacity(items.len());
+        for item in &items {
+            let offset = match sink.received(&item.id)? {
+                Some(ReceivedData { len, generation })
+                    if generation == item.generation && len <= item.len =>
+                {
+                    len
+                }
+                Some(_) => {
+                    sink.discard(item)?;
+                    0
+                }
+                None => 0,
+            };
+            received.push(offset);
+        }
+        let mut completed = vec![false; items.len()];
+        self.write(&Frame::Resume(received.clone()))?;
+
+        let mut status = Progress {
+            bytes_transferred: received.iter().sum(),
+            total_bytes,
+            items_completed: 0,
+            item_count,
+        };
+        progress(status);
+
+        loop {
+            self.check_cancelled(cancellation)?;
+            match self.read()? {
+                Frame::Chunk { item, data } => {
+                    let index = item_index(item, &items)?;
+                    let new_len = received[index] + data.len() as u64;
+                    if completed[index] || new_len > items[index].len {
+                        return Err(TransferError::Protocol(
+                            "chunk goes past the end of its item",
+                        ));
+                    }
+                    sink.write(&items[index], &data)?;
+                    received[index] = new_len;
+                    status.bytes_transferred += data.len() as u64;
+                    progress(status);
+                }
+                Frame::ItemComplete { item } => {
+                    let index = item_index(item, &items)?;
+                    if completed[index] || received[index] != items[index].len {
+                        return Err(TransferError::Protocol(
+                            "item completed before all its data",
+                        ));
+                    }
+                    sink.finish_item(&items[index])?;
+                    completed[index] = true;
+                    status.items_completed += 1;
+                    progress(status);
+                }
+                Frame::Done => {
+                    if completed.contains(&false) {
+                        return Err(TransferError::Protocol("transfer ended early"));
+                    }
+                    self.write(&Frame::Complete)?;
+                    self.close();
+                    return Ok(());
+                }
+                Frame::Cancel => return Err(TransferError::CancelledByPeer),
+                _ => return Err(TransferError::Protocol("unexpected frame during transfer")),
+            }
+        }
+    }
+
+    fn read(&mut self) -> Result<Frame, TransferError> {
+        Frame::read_from(&mut self.stream)
+    }
+
+    fn write(&mut self, frame: &Frame) -> Result<(), TransferError> {
+        frame.write_to(&mut self.stream)
+    }
+
+    fn check_cancelled(&mut self, cancellation: &Cancellation) -> Result<(), TransferError> {
+        if cancellation.is_cancelled() {
+            // Best effort; the session is over either way.
+            _ = self.write(&Frame::Cancel);
+            self.close();
+            return Err(TransferError::Cancelled);
+        }
+        Ok(())
+    }
+
+    fn close(&mut self) {
+        // The peer may have already closed the connection, and there's nothing left to send.
+        _ = self.stream.shutdown();
+    }
+}
+
+fn configure(
+    builder: &mut SslContextBuilder,
+    identity: &TransferIdentity,
+    peer_fingerprint: [u8; 32],
+    mismatch: &Arc<AtomicBool>,
+) -> Result<(), ErrorStack> {
+    builder.set_min_proto_version(Some(SslVersion::TLS1_3))?;
+    builder.set_certificate(&identity.cert)?;
+    builder.set_private_key(&identity.key)?;
+    builder.check_private_key()?;
+
+    let mismatch = Arc::clone(mismatch);
+    builder.set_verify_callback(
+        SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT,
+        // The peer's certificate is self-signed, so ordinary chain verification would always
+        // fail; the pinned fingerprint is the only thing that matters.
+        move |_preverify_ok, context| {
+            let matches = context
+                .current_cert()
+                .and_then(cert_fingerprint)
+                .is_some_and(|actual| actual == peer_fingerprint);
+            if !matches {
+                mismatch.store(true, Ordering::Relaxed);
+            }
+            matches
+        },
+    );
+    Ok(())
+}
+
+fn handshake_error(mismatch: &AtomicBool) -> TransferError {
+    if mismatch.load(Ordering::Relaxed) {
+        TransferError::PeerCertificateMismatch
+    } else {
+        TransferError::HandshakeFailed
+    }
+}
+
+fn total_len(items: &[ItemInfo]) -> Option<u64> {
+    items
+        .iter()
+        .try_fold(0u64, |total, item| total.checked_add(item.len))
+}
+
+fn item_index(item: u32, items: &[ItemInfo]) -> Result<usize, TransferError> {
+    usize::try_from(item)
+        .ok()
+        .filter(|index| *index < items.len())
+        .ok_or(TransferError::Protocol("unknown item"))
+}
@@ -0,0 +1,362 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! The messages exchanged once the TLS connection is established.
+//!
+//! Every frame is a one-byte type, a four-byte big-endian payload length, and the payload.
+
+use std::io::{Read, Write};
+
+use super::{ItemId, ItemInfo, TransferError};
+
+/// Identifies the protocol in the first frame, before any version negotiation.
+const MAGIC: [u8; 4] = *b"SGDT";
+
+/// Bounds the allocation for any single frame; manifests for large attachment sets are the
+/// biggest frames by far.
+const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+const HEADER_LEN: usize = 5;
+
+mod tag {
+    pub const HELLO: u8 = 1;
+    pub const MANIFEST: u8 = 2;
+    pub const RESUME: u8 = 3;
+    pub const CHUNK: u8 = 4;
+    pub const ITEM_COMPLETE: u8 = 5;
+    pub const DONE: u8 = 6;
+    pub const COMPLETE: u8 = 7;
+    pub const CANCEL: u8 = 8;
+}
+
+mod item_kind {
+    pub const BACKUP: u8 = 0;
+    pub const ATTACHMENT: u8 = 1;
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(super) enum Frame {
+    /// Sent by both sides immediately after the TLS handshake.
+    Hello { version: u32 },
+    /// Sent by the sender to list everything it's about to transfer.
+    Manifest(Vec<ItemInfo>),
+    /// The receiver's reply to a manifest: how many bytes of each item it already has.
+    Resume(Vec<u64>),
+    /// The next piece of an item's data.
+    Chunk { item: u32, data: Vec<u8> },
+    /// All of an item's data has been sent.
+    ItemComplete { item: u32 },
+    /// All items have been sent.
+    Done,
+    /// The receiver's reply to [`Frame::Done`], confirming it has stored everything.
+    Complete,
+    /// Either side is abandoning the transfer.
+    Cancel,
+}
+
+impl Frame {
+    pub(super) fn write_to(&self, output: &mut impl Write) -> Result<(), TransferError> {
+        // Assemble the whole frame first so it goes out as a single write.
+        let mut buf = vec![0; HEADER_LEN];
+        let tag = match self {
+            Self::Hello { version } => {
+                buf.extend_from_slice(&MAGIC);
+                buf.extend_from_slice(&version.to_be_bytes());
+                tag::HELLO
+            }
+            Self::Manifest(items) => {
+                let count = u32::try_from(items.len())
+                    .map_err(|_| TransferError::Protocol("too many items"))?;
+                buf.extend_from_slice(&count.to_be_bytes());
+                for item in items {
+                    match &item.id {
+                        ItemId::Backup => buf.push(item_kind::BACKUP),
+                        ItemId::Attachment(name) => {
+                            check_attachment_name(name)?;
+                            let name_len = u16::try_from(name.len())
+                                .map_err(|_| TransferError::Protocol("attachment name too long"))?;
+                            buf.push(item_kind::ATTACHMENT);
+                            buf.extend_from_slice(&name_len.to_be_bytes());
+                            buf.extend_from_slice(name.as_bytes());
+                        }
+                    }
+                    buf.extend_from_slice(&item.len.to_be_bytes());
+                    buf.extend_from_slice(&item.generation.to_be_bytes());
+                }
+                tag::MANIFEST
+            }
+            Self::Resume(offsets) => {
+                let count = u32::try_from(offsets.len())
+                    .map_err(|_| TransferError::Protocol("too many items"))?;
+                buf.extend_from_slice(&count.to_be_bytes());
+                for offset in offsets {
+                    buf.extend_from_slice(&offset.to_be_bytes());
+                }
+                tag::RESUME
+            }
+            Self::Chunk { item, data } => {
+                buf.extend_from_slice(&item.to_be_bytes());
+                buf.extend_from_slice(data);
+                tag::CHUNK
+            }
+            Self::ItemComplete { item } => {
+                buf.extend_from_slice(&item.to_be_bytes());
+                tag::ITEM_COMPLETE
+            }
+            Self::Done => tag::DONE,
+            Self::Complete => tag::COMPLETE,
+            Self::Cancel => tag::CANCEL,
+        };
+
+        buf[0] = tag;
+        let payload_len = buf.len() - HEADER_LEN;
+        if payload_len > MAX_FRAME_LEN {
+            return Err(TransferError::Protocol("frame too large"));
+        }
+        let payload_len = u32::try_from(payload_len).expect("checked against maximum");
+        buf[1..HEADER_LEN].copy_from_slice(&payload_len.to_be_bytes());
+
+        output.write_all(&buf)?;
+        output.flush()?;
+        Ok(())
+    }
+
+    pub(super) fn read_from(input: &mut impl Read) -> Result<Self, TransferError> {
+        let mut header = [0; HEADER_LEN];
+        input.read_exact(&mut header)?;
+        let payload_len = u32::from_be_bytes(header[1..].try_into().expect("correct length"));
+        let payload_len = usize::try_from(payload_len)
+            .ok()
+            .filter(|len| *len <= MAX_FRAME_LEN)
+            .ok_or(TransferError::Protocol("frame too large"))?;
+        let mut payload = vec![0; payload_len];
+        input.read_exact(&mut payload)?;
+
+        let mut payload = Payload(&payload);
+        let frame = match header[0] {
+            tag::HELLO => {
+                if payload.take(MAGIC.len())? != MAGIC {
+                    return Err(TransferError::Protocol(
+                        "peer is not a device transfer client",
+                    ));
+                }
+                Self::Hello {
+                    version: payload.u32()?,
+                }
+            }
+            tag::MANIFEST => {
+                let count = payload.u32()?;
+                // Don't preallocate based on the count; running out of payload bounds the loop.
+                let mut items = Vec::new();
+                for _ in 0..count {
+                    let id = match payload.u8()? {
+                        item_kind::BACKUP => ItemId::Backup,
+                        item_kind::ATTACHMENT => {
+                            let name_len = payload.u16()?;
+                            let name = payload.take(name_len.into())?;
+                            let name = String::from_utf8(name.to_vec())
+                                .map_err(|_| TransferError::Protocol("invalid attachment name"))?;
+                            check_attachment_name(&name)?;
+                            ItemId::Attachment(name)
+                        }
+                        _ => return Err(TransferError::Protocol("unknown item kind")),
+                    };
+                    items.push(ItemInfo {
+                        id,
+                        len: payload.u64()?,
+                        generation: payload.u64()?,
+                    });
+                }
+                Self::Manifest(items)
+            }
+            tag::RESUME => {
+                let count = payload.u32()?;
+                let mut offsets = Vec::new();
+                for _ in 0..count {
+                    offsets.push(payload.u64()?);
+                }
+                Self::Resume(offsets)
+            }
+            tag::CHUNK => {
+                let item = payload.u32()?;
+                let data = payload.take(payload.0.len())?.to_vec();
+                Self::Chunk { item, data }
+            }
+            tag::ITEM_COMPLETE => Self::ItemComplete {
+                item: payload.u32()?,
+            },
+            tag::DONE => Self::Done,
+            tag::COMPLETE => Self::Complete,
+            tag::CANCEL => Self::Cancel,
+            _ => return Err(TransferError::Protocol("unknown frame type")),
+        };
+
+        if !payload.0.is_empty() {
+            return Err(TransferError::Protocol("unexpected data at end of frame"));
+        }
+        Ok(frame)
+    }
+}
+
+/// Keeps attachment names to a single path component; see [`ItemId::Attachment`].
+fn check_attachment_name(name: &str) -> Result<(), TransferError> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\', '\0']) {
+        return Err(TransferError::Protocol("invalid attachment name"));
+    }
+    Ok(())
+}
+
+struct Payload<'a>(&'a [u8]);
+
+impl<'a> Payload<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], TransferError> {
+        let Some((result, rest)) = self.0.split_at_checked(len) else {
+            return Err(TransferError::Protocol("frame is truncated"));
+        };
+        self.0 = rest;
+        Ok(result)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], TransferError> {
+        Ok(self.take(N)?.try_into().expect("correct length"))
+    }
+
+    fn u8(&mut self) -> Result<u8, TransferError> {
+        let [byte] = self.array()?;
+        Ok(byte)
+    }
+
+    fn u16(&mut self) -> Result<u16, TransferError> {
+        self.array().map(u16::from_be_bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, TransferError> {
+        self.array().map(u32::from_be_bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64, TransferError> {
+        self.array().map(u64::from_be_bytes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use test_case::test_case;
+
+    use super::*;
+
+    fn round_trip(frame: Frame) {
+        let mut buf = Vec::new();
+        frame.write_to(&mut buf).expect("can write");
+        let decoded = Frame::read_from(&mut buf.as_slice()).expect("can read");
+        assert_eq!(decoded, frame);
+    }
+
+    #[test]
+    fn frames_round_trip() {
+        round_trip(Frame::Hello { version: 1 });
+        round_trip(Frame::Manifest(vec![
+            ItemInfo {
+                id: ItemId::Backup,
+                len: 12345,
+                generation: 7,
+            },
+            ItemInfo {
+                id: ItemId::Attachment("photo.jpg".to_owned()),
+                len: 0,
+                generation: u64::MAX,
+            },
+        ]));
+        round_trip(Frame::Resume(vec![0, 10, u64::MAX]));
+        round_trip(Frame::Chunk {
+            item: 3,
+            data: b"abcdef".to_vec(),
+        });
+        round_trip(Frame::Chunk {
+            item: 0,
+            data: vec![],
+        });
+        round_trip(Frame::ItemComplete { item: 7 });
+        round_trip(Frame::Done);
+        round_trip(Frame::Complete);
+        round_trip(Frame::Cancel);
+    }
+
+    #[test]
+    fn rejects_trailing_data() {
+        let mut buf = Vec::new();
+        Frame::Done.write_to(&mut buf).expect("can write");
+        buf[4] = 1;
+        buf.push(0);
+        assert_matches!(
+            Frame::read_from(&mut buf.as_slice()),
+            Err(TransferError::Protocol(_))
+        );
+    }
+
+    #[test]
+    fn rejects_oversized_frames() {
+        let mut header = vec![tag::CHUNK];
+        header.extend_from_slice(&u32::MAX.to_be_bytes());
+        assert_matches!(
+            Frame::read_from(&mut header.as_slice()),
+            Err(TransferError::Protocol("frame too large"))
+        );
+    }
+
+    #[test_case(""; "empty")]
+    #[test_case("."; "current directory")]
+    #[test_case(".."; "parent directory")]
+    #[test_case("../backup"; "relative path")]
+    #[test_case("/etc/passwd"; "absolute path")]
+    #[test_case("dir\\file"; "backslash")]
+    #[test_case("file\0.jpg"; "nul")]
+    fn rejects_attachment_names_with_paths(name: &str) {
+        let manifest = |name: &str| {
+            Frame::Manifest(vec![ItemInfo {
+                id: ItemId::Attachment(name.to_owned()),
+                len: 0,
+                generation: 0,
+            }])
+        };
+        assert_matches!(
+            manifest(name).write_to(&mut Vec::new()),
+            Err(TransferError::Protocol("invalid attachment name"))
+        );
+
+        // Encode the frame by hand to get past the sender's check.
+        let name_len = u16::try_from(name.len()).expect("short");
+        let mut payload = 1u32.to_be_bytes().to_vec();
+        payload.push(item_kind::ATTACHMENT);
+        payload.extend_from_slice(&name_len.to_be_bytes());
+        payload.extend_from_slice(name.as_bytes());
+        payload.extend_from_slice(&[0; 16]);
+        let payload_len = u32::try_from(payload.len()).expect("short");
+        let buf = [
+            &[tag::MANIFEST][..],
+            &payload_len.to_be_bytes(),
+            &payload[..],
+        ]
+        .concat();
+        assert_matches!(
+            Frame::read_from(&mut buf.as_slice()),
+            Err(TransferError::Protocol("invalid attachment name"))
+        );
+    }
+
+    #[test]
+    fn rejects_other_protocols() {
+        let mut buf = Vec::new();
+        Frame::Hello { version: 1 }
+            .write_to(&mut buf)
+            .expect("can write");
+        buf[HEADER_LEN..][..MAGIC.len()].copy_from_slice(b"HTTP");
+        assert_matches!(
+            Frame::read_from(&mut buf.as_slice()),
+            Err(TransferError::Protocol(_))
+        );
+    }
+}
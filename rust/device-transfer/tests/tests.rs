@@ -71,3 +71,43 @@ fn test_generate_and_parse() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+fn test_generate_ecdsa_and_parse() -> Result<(), Error> {
+    for key_format in [KeyFormat::KeySpecific, KeyFormat::Pkcs8] {
+        let key = create_private_key(KeyType::EcdsaP256, key_format)?;
+        let cert = create_self_signed_cert(&key, "test", 10)?;
+
+        let boring_key =
+            PKey::private_key_from_der(&key).expect("BoringSSL can parse our private key");
+        boring_key.ec_key().expect("This is an EC key");
+
+        let boring_cert = X509::from_der(&cert).expect("BoringSSL can parse our certificate");
+        let pubkey = boring_cert.public_key().expect("Can extract public key");
+        assert!(pubkey.public_eq(&boring_key));
+
+        // Self-signature verifies:
+        assert!(boring_cert.verify(&pubkey).unwrap());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_certificate_fingerprint() -> Result<(), Error> {
+    let key = create_private_key(KeyType::EcdsaP256, KeyFormat::Pkcs8)?;
+    let cert = create_self_signed_cert(&key, "test", 10)?;
+    let other_cert = create_self_signed_cert(&key, "other", 10)?;
+
+    let fingerprint = certificate_fingerprint(&cert)?;
+    assert!(certificate_matches_fingerprint(&cert, &fingerprint)?);
+    assert!(!certificate_matches_fingerprint(&other_cert, &fingerprint)?);
+    assert!(!certificate_matches_fingerprint(&cert, &fingerprint[..16])?);
+
+    assert!(matches!(
+        certificate_fingerprint(&key),
+        Err(Error::CertificateDecodingFailed)
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,385 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::net::{TcpListener, TcpStream};
+
+use assert_matches::assert_matches;
+use device_transfer::transfer::*;
+use device_transfer::{KeyFormat, KeyType, create_private_key, create_self_signed_cert};
+
+struct Device {
+    identity: TransferIdentity,
+    /// What the other device pins for this one.
+    fingerprint: [u8; 32],
+}
+
+impl Device {
+    fn new(name: &str) -> Self {
+        Self::with_key_type(name, KeyType::EcdsaP256)
+    }
+
+    fn with_key_type(name: &str, key_type: KeyType) -> Self {
+        let key = create_private_key(key_type, KeyFormat::Pkcs8).expect("can create key");
+        let cert = create_self_signed_cert(&key, name, 1).expect("can create cert");
+        let identity = TransferIdentity::new(&key, &cert).expect("valid identity");
+        let fingerprint = identity.fingerprint().expect("can hash");
+        Self {
+            identity,
+            fingerprint,
+        }
+    }
+}
+
+type Session = TransferSession<TcpStream>;
+
+/// Runs `send` on a session connected to a session running `receive`, over localhost.
+fn run(
+    sender: &Device,
+    receiver: &Device,
+    send: impl FnOnce(Session) -> Result<(), TransferError>,
+    receive: impl FnOnce(Session) -> Result<(), TransferError> + Send,
+) -> (Result<(), TransferError>, Result<(), TransferError>) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("can listen");
+    let address = listener.local_addr().expect("bound");
+
+    std::thread::scope(|scope| {
+        let receiving = scope.spawn(|| {
+            let (stream, _) = listener.accept()?;
+            receive(Session::accept(
+                stream,
+                &receiver.identity,
+                &sender.fingerprint,
+            )?)
+        });
+        let sent = TcpStream::connect(address)
+            .map_err(TransferError::from)
+            .and_then(|stream| Session::connect(stream, &sender.identity, &receiver.fingerprint))
+            .and_then(send);
+        (sent, receiving.join().expect("receiver didn't panic"))
+    })
+}
+
+struct MemorySource(Vec<(ItemInfo, Vec<u8>)>);
+
+impl MemorySource {
+    fn new(items: impl IntoIterator<Item = (ItemId, Vec<u8>)>) -> Self {
+        Self::with_generation(items, 0)
+    }
+
+    fn with_generation(
+        items: impl IntoIterator<Item = (ItemId, Vec<u8>)>,
+        generation: u64,
+    ) -> Self {
+        Self(
+            items
+                .into_iter()
+                .map(|(id, data)| {
+                    let len = u64::try_from(data.len()).expect("small enough");
+                    (
+                        ItemInfo {
+                            id,
+                            len,
+                            generation,
+                        },
+                        data,
+                    )
+                })
+                .collect(),
+        )
+    }
+}
+
+impl TransferSource for MemorySource {
+    fn items(&mut self) -> io::Result<Vec<ItemInfo>> {
+        Ok(self.0.iter().map(|(item, _)| item.clone()).collect())
+    }
+
+    fn open(&mut self, item: &ItemInfo, offset: u64) -> io::Result<Box<dyn Read + '_>> {
+        let (_, data) = self
+            .0
+            .iter()
+            .find(|(candidate, _)| candidate == item)
+            .ok_or(io::ErrorKind::NotFound)?;
+        let offset = usize::try_from(offset).expect("small enough");
+        Ok(Box::new(&data[offset..]))
+    }
+}
+
+#[derive(Default)]
+struct MemorySink {
+    items: HashMap<ItemId, Vec<u8>>,
+    generations: HashMap<ItemId, u64>,
+    finished: Vec<ItemId>,
+    discarded: Vec<ItemId>,
+    bytes_written: usize,
+}
+
+impl TransferSink for MemorySink {
+    fn received(&mut self, id: &ItemId) -> io::Result<Option<ReceivedData>> {
+        Ok(self.items.get(id).map(|data| ReceivedData {
+            len: u64::try_from(data.len()).expect("small enough"),
+            generation: self.generations[id],
+        }))
+    }
+
+    fn discard(&mut self, item: &ItemInfo) -> io::Result<()> {
+        self.items.remove(&item.id);
+        self.generations.remove(&item.id);
+        self.discarded.push(item.id.clone());
+        Ok(())
+    }
+
+    fn write(&mut self, item: &ItemInfo, data: &[u8]) -> io::Result<()> {
+        self.items
+            .entry(item.id.clone())
+            .or_default()
+            .extend_from_slice(data);
+        self.generations.insert(item.id.clone(), item.generation);
+        self.bytes_written += data.len();
+        Ok(())
+    }
+
+    fn finish_item(&mut self, item: &ItemInfo) -> io::Result<()> {
+        self.finished.push(item.id.clone());
+        Ok(())
+    }
+}
+
+fn test_items() -> Vec<(ItemId, Vec<u8>)> {
+    let backup = (0..=255).cycle().take(3 * CHUNK_SIZE + 17).collect();
+    vec![
+        (ItemId::Backup, backup),
+        (
+            ItemId::Attachment("small".to_owned()),
+            b"attachment".to_vec(),
+        ),
+        (ItemId::Attachment("empty".to_owned()), vec![]),
+    ]
+}
+
+fn total_len(items: &[(ItemId, Vec<u8>)]) -> usize {
+    items.iter().map(|(_, data)| data.len()).sum()
+}
+
+#[test]
+fn transfers_everything() {
+    // Mix key types to make sure either can be used on either side.
+    let old_device = Device::with_key_type("old", KeyType::Rsa { bits: 2048 });
+    let new_device = Device::new("new");
+    let items = test_items();
+    let mut source = MemorySource::new(items.clone());
+    let mut sink = MemorySink::default();
+    let mut last_progress = None;
+
+    let (sent, received) = run(
+        &old_device,
+        &new_device,
+        |session| session.send(&mut source, &mut |_| {}, &Cancellation::new()),
+        |session| {
+            session.receive(
+                &mut sink,
+                &mut |progress| last_progress = Some(progress),
+                &Cancellation::new(),
+            )
+        },
+    );
+    sent.expect("sent");
+    received.expect("received");
+
+    let total = u64::try_from(total_len(&items)).expect("small enough");
+    assert_eq!(
+        last_progress,
+        Some(Progress {
+            bytes_transferred: total,
+            total_bytes: total,
+            items_completed: 3,
+            item_count: 3,
+        })
+    );
+    for (id, data) in &items {
+        assert_eq!(
+            sink.items.get(id).map(Vec::as_slice).unwrap_or_default(),
+            data.as_slice()
+        );
+    }
+    assert_eq!(
+        sink.finished,
+        items.into_iter().map(|(id, _)| id).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn cancelled_transfer_can_resume() {
+    let (old_device, new_device) = (Device::new("old"), Device::new("new"));
+    let items = test_items();
+    let mut source = MemorySource::new(items.clone());
+    let mut sink = MemorySink::default();
+
+    let cancellation = Cancellation::new();
+    let (sent, received) = run(
+        &old_device,
+        &new_device,
+        |session| {
+            session.send(
+                &mut source,
+                &mut |progress| {
+                    if progress.bytes_transferred > 0 {
+                        cancellation.cancel();
+                    }
+                },
+                &cancellation,
+            )
+        },
+        |session| session.receive(&mut sink, &mut |_| {}, &Cancellation::new()),
+    );
+    assert_matches!(sent, Err(TransferError::Cancelled));
+    assert_matches!(received, Err(TransferError::CancelledByPeer));
+
+    let partial = sink.bytes_written;
+    assert_eq!(partial, CHUNK_SIZE);
+    assert!(sink.finished.is_empty());
+
+    let (sent, received) = run(
+        &old_device,
+        &new_device,
+        |session| session.send(&mut source, &mut |_| {}, &Cancellation::new()),
+        |session| session.receive(&mut sink, &mut |_| {}, &Cancellation::new()),
+    );
+    sent.expect("sent");
+    received.expect("received");
+
+    assert_eq!(sink.bytes_written, total_len(&items));
+    for (id, data) in &items {
+        assert_eq!(
+            sink.items.get(id).map(Vec::as_slice).unwrap_or_default(),
+            data.as_slice()
+        );
+    }
+}
+
+#[test]
+fn changed_item_starts_over() {
+    let (old_device, new_device) = (Device::new("old"), Device::new("new"));
+    let mut sink = MemorySink::default();
+
+    let cancellation = Cancellation::new();
+    let (sent, _received) = run(
+        &old_device,
+        &new_device,
+        |session| {
+            session.send(
+                &mut MemorySource::new(test_items()),
+                &mut |progress| {
+                    if progress.bytes_transferred > 0 {
+                        cancellation.cancel();
+                    }
+                },
+                &cancellation,
+            )
+        },
+        |session| session.receive(&mut sink, &mut |_| {}, &Cancellation::new()),
+    );
+    assert_matches!(sent, Err(TransferError::Cancelled));
+    assert_eq!(sink.bytes_written, CHUNK_SIZE);
+
+    // The backup was made again in the meantime, so the partial copy is no good.
+    let mut items = test_items();
+    items[0].1.reverse();
+    let mut source = MemorySource::with_generation(items.clone(), 1);
+    let (sent, received) = run(
+        &old_device,
+        &new_device,
+        |session| session.send(&mut source, &mut |_| {}, &Cancellation::new()),
+        |session| session.receive(&mut sink, &mut |_| {}, &Cancellation::new()),
+    );
+    sent.expect("sent");
+    received.expect("received");
+
+    assert_eq!(sink.discarded, [ItemId::Backup]);
+    assert_eq!(sink.bytes_written, CHUNK_SIZE + total_len(&items));
+    for (id, data) in &items {
+        assert_eq!(
+            sink.items.get(id).map(Vec::as_slice).unwrap_or_default(),
+            data.as_slice()
+        );
+    }
+}
+
+#[test]
+fn receiver_can_cancel() {
+    let (old_device, new_device) = (Device::new("old"), Device::new("new"));
+    let mut source = MemorySource::new(test_items());
+    let mut sink = MemorySink::default();
+
+    let cancellation = Cancellation::new();
+    cancellation.cancel();
+    let (sent, received) = run(
+        &old_device,
+        &new_device,
+        |session| session.send(&mut source, &mut |_| {}, &Cancellation::new()),
+        |session| session.receive(&mut sink, &mut |_| {}, &cancellation),
+    );
+    assert_matches!(sent, Err(TransferError::CancelledByPeer));
+    assert_matches!(received, Err(TransferError::Cancelled));
+    assert_eq!(sink.bytes_written, 0);
+}
+
+#[test]
+fn sender_rejects_unexpected_receiver() {
+    let old_device = Device::new("old");
+    let new_device = Device::new("new");
+    let impostor = Device {
+        fingerprint: new_device.fingerprint,
+        ..Device::new("impostor")
+    };
+
+    let (sent, received) = run(
+        &old_device,
+        &impostor,
+        |_| panic!("should not connect"),
+        |_| panic!("should not connect"),
+    );
+    assert_matches!(sent, Err(TransferError::PeerCertificateMismatch));
+    assert_matches!(received, Err(_));
+}
+
+#[test]
+fn receiver_rejects_unexpected_sender() {
+    let old_device = Device::new("old");
+    let new_device = Device::new("new");
+    let impostor = Device {
+        fingerprint: old_device.fingerprint,
+        ..Device::new("impostor")
+    };
+
+    let (sent, received) = run(
+        &impostor,
+        &new_device,
+        |session| {
+            session.send(
+                &mut MemorySource::new(test_items()),
+                &mut |_| {},
+                &Cancellation::new(),
+            )
+        },
+        |_| panic!("should not connect"),
+    );
+    assert_matches!(sent, Err(_));
+    assert_matches!(received, Err(TransferError::PeerCertificateMismatch));
+}
+
+#[test]
+fn rejects_mismatched_identity() {
+    let key = create_private_key(KeyType::EcdsaP256, KeyFormat::Pkcs8).expect("can create key");
+    let other_key =
+        create_private_key(KeyType::EcdsaP256, KeyFormat::Pkcs8).expect("can create key");
+    let cert = create_self_signed_cert(&key, "test", 1).expect("can create cert");
+    assert_matches!(
+        TransferIdentity::new(&other_key, &cert),
+        Err(TransferError::InvalidIdentity)
+    );
+}
@@ -0,0 +1,649 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+//! Implements a third-party auditor.
+//!
+//! An auditor is given every change made to the log, and independently tracks
+//! the state of both the prefix tree and the log tree. Once it has caught up
+//! with a tree head published by the service operator, it counter-signs it.
+//! Clients using [`DeploymentMode::ThirdPartyAuditing`] require such a
+//! signature (as part of a [`FullAuditorTreeHead`]) before they accept a tree
+//! head.
+//!
+//! [`FullAuditorTreeHead`]: crate::FullAuditorTreeHead
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ed25519_dalek::{Signer as _, SigningKey, VerifyingKey};
+
+use crate::log::FullLogTree;
+use crate::proto::{
+    AuditorTreeHead, AuditorUpdate, StoredAuditorKey, StoredAuditorState, TreeHead,
+};
+use crate::verify::{Error, MAX_TREE_SIZE, leaf_hash, verify_tree_head_signature};
+use crate::{
+    DeploymentMode, PublicConfig, SingleSignatureTreeHead, TreeRoot, VerifiableTreeHead, prefix,
+};
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// The state of a single search key in the prefix tree.
+#[derive(Clone, Copy, Debug)]
+struct KeyState {
+    /// Number of times the key has been updated since it was first added.
+    counter: u32,
+    /// Position in the log where the key was first added.
+    position: u64,
+}
+
+/// A third-party auditor for a key transparency log.
+///
+/// The auditor keeps every leaf of the log tree, and the position and update
+/// count of every index in the prefix tree, in memory.
+#[derive(Clone)]
+pub struct Auditor {
+    config: PublicConfig,
+    signing_key: SigningKey,
+    prefix_root: [u8; 32],
+    keys: HashMap<[u8; 32], KeyState>,
+    log: FullLogTree,
+    last_timestamp: Option<i64>,
+}
+
+impl Auditor {
+    /// Creates an auditor for an empty log.
+    ///
+    /// `config` must use [`DeploymentMode::ThirdPartyAuditing`], and the
+    /// public half of `signing_key` must be one of its auditor keys.
+    pub fn new(config: PublicConfig, signing_key: SigningKey) -> Result<Self> {
+        let DeploymentMode::ThirdPartyAuditing(auditor_keys) = &config.mode else {
+            return Err(Error::BadData(
+                "auditing requires third-party auditing deployment mode".to_string(),
+            ));
+        };
+        if !auditor_keys
+            .iter()
+            .any(|key| *key == signing_key.verifying_key())
+        {
+            return Err(Error::BadData(
+                "signing key is not one of the configured auditor keys".to_string(),
+            ));
+        }
+        Ok(Self {
+            config,
+            signing_key,
+            prefix_root: prefix::empty_root(),
+            keys: HashMap::new(),
+            log: FullLogTree::default(),
+            last_timestamp: None,
+        })
+    }
+
+    /// Restores an auditor from state previously saved with
+    /// [`to_stored`](Self::to_stored).
+    ///
+    /// `config` and `signing_key` are checked the same way as in
+    /// [`new`](Self::new).
+    pub fn from_stored(
+        config: PublicConfig,
+        signing_key: SigningKey,
+        stored: StoredAuditorState,
+    ) -> Result<Self> {
+        let StoredAuditorState {
+            prefix_root,
+            keys,
+            log_leaves,
+            last_timestamp,
+        } = stored;
+
+        let mut auditor = Self::new(config, signing_key)?;
+        auditor.prefix_root = prefix_root
+            .try_into()
+            .map_err(|_| Error::BadData("prefix root is wrong size".to_string()))?;
+        for leaf in log_leaves {
+            let leaf = leaf
+                .try_into()
+                .map_err(|_| Error::BadData("log leaf is wrong size".to_string()))?;
+            auditor.log.append(leaf);
+        }
+        if auditor.tree_size() > MAX_TREE_SIZE {
+            return Err(Error::BadData("log is too large".to_string()));
+        }
+        for StoredAuditorKey {
+            index,
+            counter,
+            position,
+        } in keys
+        {
+            let index = index
+                .try_into()
+                .map_err(|_| Error::BadData("index is wrong size".to_string()))?;
+            if position >= auditor.tree_size() {
+                return Err(Error::BadData(
+                    "index position is outside of the log".to_string(),
+                ));
+            }
+            if auditor
+                .keys
+                .insert(index, KeyState { counter, position })
+                .is_some()
+            {
+                return Err(Error::BadData("duplicate index".to_string()));
+            }
+        }
+        auditor.last_timestamp = last_timestamp;
+        Ok(auditor)
+    }
+
+    /// Returns the auditor's state, so that it can be saved and later restored
+    /// with [`from_stored`](Self::from_stored).
+    ///
+    /// The configuration and signing key are not included.
+    pub fn to_stored(&self) -> StoredAuditorState {
+        StoredAuditorState {
+            prefix_root: self.prefix_root.to_vec(),
+            keys: self
+                .keys
+                .iter()
+                .map(
+                    |(index, &KeyState { counter, position })| StoredAuditorKey {
+                        index: index.to_vec(),
+                        counter,
+                        position,
+                    },
+                )
+                .collect(),
+            log_leaves: self.log.leaves().map(|leaf| leaf.to_vec()).collect(),
+            last_timestamp: self.last_timestamp,
+        }
+    }
+
+    pub fn verifying_key(&self) -> VerifyingKey {
+        self.signing_key.verifying_key()
+    }
+
+    /// The number of updates ingested so far.
+    pub fn tree_size(&self) -> u64 {
+        self.log.len()
+    }
+
+    /// The root of the log tree, or `None` if no updates have been ingested.
+    pub fn tree_root(&self) -> Option<TreeRoot> {
+        self.log.root().ok()
+    }
+
+    /// Returns a consistency proof between the log as it was when it had
+    /// `tree_size` entries and the current log.
+    ///
+    /// Together with a tree head previously signed by this auditor, this is
+    /// what the service operator needs to produce a [`FullAuditorTreeHead`]
+    /// for a newer tree head.
+    ///
+    /// [`FullAuditorTreeHead`]: crate::FullAuditorTreeHead
+    pub fn consistency_proof(&self, tree_size: u64) -> Result<Vec<[u8; 32]>> {
+        Ok(self.log.consistency_proof(tree_size)?)
+    }
+
+    /// Applies the next change to the log.
+    ///
+    /// The copath in `update` must evaluate to the current prefix tree root
+    /// with the index's current leaf (or with an empty leaf, if the index is
+    /// new). If it doesn't, the update is rejected and the auditor's state is
+    /// left unchanged.
+    pub fn update(&mut self, update: &AuditorUpdate) -> Result<()> {
+        let AuditorUpdate {
+            index,
+            commitment,
+            copath,
+        } = update;
+        let index: &[u8; 32] = index
+            .as_slice()
+            .try_into()
+            .map_err(|_| Error::BadData("index is wrong size".to_string()))?;
+        let commitment: &[u8; 32] = commitment
+            .as_slice()
+            .try_into()
+            .map_err(|_| Error::BadData("commitment is wrong size".to_string()))?;
+
+        let position = self.tree_size();
+        if position >= MAX_TREE_SIZE {
+            return Err(Error::BadData("log is too large".to_string()));
+        }
+
+        let (old_prefix_root, new_state) = match self.keys.get(index) {
+            Some(&KeyState {
+                counter,
+                position: first_position,
+            }) => {
+                let old_root = prefix::evaluate_leaf(index, counter, first_position, copath)?;
+                let counter = counter
+                    .checked_add(1)
+                    .ok_or_else(|| Error::BadData("too many updates to index".to_string()))?;
+                (
+                    old_root,
+                    KeyState {
+                        counter,
+                        position: first_position,
+                    },
+                )
+            }
+            None => (
+                prefix::evaluate_absent(index, copath)?,
+                KeyState {
+                    counter: 0,
+                    position,
+                },
+            ),
+        };
+        if old_prefix_root != self.prefix_root {
+            return Err(Error::VerificationFailed(
+                "prefix tree proof does not match current root".to_string(),
+            ));
+        }
+        let new_prefix_root =
+            prefix::evaluate_leaf(index, new_state.counter, new_state.position, copath)?;
+
+        self.log.append(leaf_hash(&new_prefix_root, commitment));
+        self.prefix_root = new_prefix_root;
+        self.keys.insert(*index, new_state);
+        Ok(())
+    }
+
+    /// Verifies a tree head published by the service operator against the
+    /// audited log, and signs it.
+    ///
+    /// The tree head must be the same size as the audited log and carry the
+    /// service's signature for this auditor. `now` becomes the timestamp of
+    /// the returned auditor tree head.
+    pub fn sign_tree_head(
+        &mut self,
+        tree_head: &TreeHead,
+        now: SystemTime,
+    ) -> Result<AuditorTreeHead> {
+        let TreeHead {
+            tree_size,
+            timestamp,
+            signatures,
+        } = tree_head;
+        if *tree_size != self.tree_size() {
+            return Err(Error::BadData(
+                "tree head size does not match audited log".to_string(),
+            ));
+        }
+        let root = self.log.root()?;
+
+        let auditor_key = self.verifying_key();
+        let signature = signatures
+            .iter()
+            .find(|sig| sig.auditor_public_key.as_slice() == auditor_key.as_bytes().as_slice())
+            .ok_or_else(|| {
+                Error::BadData("tree head is not signed for this auditor".to_string())
+            })?;
+        let service_head = SingleSignatureTreeHead(TreeHead {
+            tree_size: *tree_size,
+            timestamp: *timestamp,
+            signatures: vec![signature.clone()],
+        });
+        verify_tree_head_signature(
+            &self.config,
+            &service_head,
+            &root,
+            &self.config.signature_key,
+            Some(&auditor_key),
+        )?;
+
+        if self.last_timestamp.is_some_and(|last| *timestamp < last) {
+            return Err(Error::VerificationFailed(
+                "tree head timestamp is older than the last one signed".to_string(),
+            ));
+        }
+        self.last_timestamp = Some(*timestamp);
+
+        let mut auditor_head = AuditorTreeHead {
+            tree_size: *tree_size,
+            timestamp: now
+                .duration_since(UNIX_EPOCH)
+                .expect("valid SystemTime")
+                .as_millis()
+                .try_into()
+                .expect("enough millis since UNIX_EPOCH"),
+            signature: vec![],
+        };
+        let to_be_signed = auditor_head.to_signable_header(&root, &self.config, Some(&auditor_key));
+        auditor_head.signature = self.signing_key.sign(&to_be_signed).to_bytes().to_vec();
+        Ok(auditor_head)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use assert_matches::assert_matches;
+    use const_str::hex;
+    use ed25519_dalek::Signer as _;
+    use prost::Message as _;
+
+    use super::*;
+    use crate::proto::{FullAuditorTreeHead, FullTreeHead, Signature};
+    use crate::verify::verify_full_tree_head;
+    use crate::vrf;
+
+    const SERVICE_KEY: [u8; 32] = [1; 32];
+    const AUDITOR_KEY: [u8; 32] = [2; 32];
+    const VRF_KEY: [u8; 32] =
+        hex!("ec3a268237cf5c47115cf222405d5f90cc633ebe05caf82c0dd5acf9d341dadb");
+
+    fn config() -> PublicConfig {
+        PublicConfig {
+            mode: DeploymentMode::ThirdPartyAuditing(
+                vec![SigningKey::from_bytes(&AUDITOR_KEY).verifying_key()].into(),
+            ),
+            signature_key: SigningKey::from_bytes(&SERVICE_KEY).verifying_key(),
+            vrf_key: vrf::PublicKey::try_from(VRF_KEY).expect("valid key"),
+        }
+    }
+
+    fn auditor() -> Auditor {
+        Auditor::new(config(), SigningKey::from_bytes(&AUDITOR_KEY)).expect("valid config")
+    }
+
+    fn bit(key: &[u8; 32], n: usize) -> bool {
+        key[n / 8] & (1 << (7 - n % 8)) != 0
+    }
+
+    /// A naive, fully materialized version of the service's prefix and log trees.
+    #[derive(Default)]
+    struct TestLog {
+        leaves: BTreeMap<[u8; 32], KeyState>,
+        log: FullLogTree,
+    }
+
+    impl TestLog {
+        fn subtree_hash(
+            leaves: &[(&[u8; 32], &KeyState)],
+            depth: usize,
+            empty: &[[u8; 32]],
+        ) -> [u8; 32] {
+            match leaves {
+                [] => empty[empty.len() - 1 - depth],
+                [(key, state)] if depth == empty.len() - 1 => {
+                    prefix::leaf_hash(key, state.counter, state.position)
+                }
+                _ => {
+                    let split = leaves.partition_point(|(key, _)| !bit(key, depth));
+                    prefix::parent_hash(
+                        &Self::subtree_hash(&leaves[..split], depth + 1, empty),
+                        &Self::subtree_hash(&leaves[split..], depth + 1, empty),
+                    )
+                }
+            }
+        }
+
+        fn copath(&self, index: &[u8; 32]) -> Vec<Vec<u8>> {
+            let empty: Vec<_> = prefix::empty_subtree_hashes().collect();
+            let leaves: Vec<_> = self.leaves.iter().collect();
+            (0..256)
+                .rev()
+                .map(|n| {
+                    let sibling: Vec<_> = leaves
+                        .iter()
+                        .copied()
+                        .filter(|(key, _)| {
+                            (0..n).all(|i| bit(key, i) == bit(index, i))
+                                && bit(key, n) != bit(index, n)
+                        })
+                        .collect();
+                    Self::subtree_hash(&sibling, n + 1, &empty).to_vec()
+                })
+                .collect()
+        }
+
+        fn prefix_root(&self) -> [u8; 32] {
+            let empty: Vec<_> = prefix::empty_subtree_hashes().collect();
+            let leaves: Vec<_> = self.leaves.iter().collect();
+            Self::subtree_hash(&leaves, 0, &empty)
+        }
+
+        fn update(&mut self, index: [u8; 32], commitment: [u8; 32]) -> AuditorUpdate {
+            let copath = self.copath(&index);
+            let position = self.log.len();
+            self.leaves
+                .entry(index)
+                .and_modify(|state| state.counter += 1)
+                .or_insert(KeyState {
+                    counter: 0,
+                    position,
+                });
+            let prefix_root = self.prefix_root();
+            self.log.append(leaf_hash(&prefix_root, &commitment));
+            AuditorUpdate {
+                index: index.to_vec(),
+                commitment: commitment.to_vec(),
+                copath,
+            }
+        }
+
+        fn signed_tree_head(&self, timestamp: SystemTime) -> (TreeHead, TreeRoot) {
+            let config = config();
+            let root = self.log.root().expect("not empty");
+            let auditor_key = SigningKey::from_bytes(&AUDITOR_KEY).verifying_key();
+            let mut head = SingleSignatureTreeHead(TreeHead {
+                tree_size: self.log.len(),
+                timestamp: timestamp
+                    .duration_since(UNIX_EPOCH)
+                    .expect("valid time")
+                    .as_millis()
+                    .try_into()
+                    .expect("fits"),
+                signatures: vec![],
+            });
+            let to_be_signed = head.to_signable_header(&root, &config, Some(&auditor_key));
+            head.0.signatures = vec![Signature {
+                auditor_public_key: auditor_key.as_bytes().to_vec(),
+                signature: SigningKey::from_bytes(&SERVICE_KEY)
+                    .sign(&to_be_signed)
+                    .to_bytes()
+                    .to_vec(),
+            }];
+            (head.0, root)
+        }
+    }
+
+    const INDEX_A: [u8; 32] = [0x11; 32];
+    const INDEX_B: [u8; 32] = [0xee; 32];
+    const INDEX_C: [u8; 32] = [0x12; 32];
+
+    fn apply(test_log: &mut TestLog, auditor: &mut Auditor, indices: &[[u8; 32]]) {
+        for (i, index) in indices.iter().enumerate() {
+            let commitment = [u8::try_from(i).expect("small"); 32];
+            auditor
+                .update(&test_log.update(*index, commitment))
+                .expect("valid update");
+        }
+    }
+
+    #[test]
+    fn tracks_prefix_and_log_trees() {
+        let mut test_log = TestLog::default();
+        let mut auditor = auditor();
+        assert_eq!(auditor.tree_root(), None);
+        assert_eq!(auditor.prefix_root, test_log.prefix_root());
+
+        apply(
+            &mut test_log,
+            &mut auditor,
+            &[INDEX_A, INDEX_B, INDEX_A, INDEX_C, INDEX_A],
+        );
+
+        assert_eq!(auditor.tree_size(), 5);
+        assert_eq!(auditor.prefix_root, test_log.prefix_root());
+        assert_eq!(auditor.tree_root(), test_log.log.root().ok());
+    }
+
+    #[test]
+    fn signed_tree_head_is_accepted_by_clients() {
+        let mut test_log = TestLog::default();
+        let mut auditor = auditor();
+        apply(&mut test_log, &mut auditor, &[INDEX_A, INDEX_B, INDEX_A]);
+
+        let now = SystemTime::now();
+        let (tree_head, root) = test_log.signed_tree_head(now);
+        let auditor_head = auditor.sign_tree_head(&tree_head, now).expect("can sign");
+
+        let full_tree_head = FullTreeHead {
+            tree_head: Some(tree_head),
+            full_auditor_tree_heads: vec![FullAuditorTreeHead {
+                tree_head: Some(auditor_head),
+                root_value: None,
+                consistency: vec![],
+                public_key: auditor.verifying_key().as_bytes().to_vec(),
+            }],
+            ..Default::default()
+        };
+        verify_full_tree_head(&config(), &full_tree_head, root, None, None, now)
+            .expect("valid tree head");
+    }
+
+    #[test]
+    fn lagging_auditor_tree_head_is_accepted_by_clients() {
+        let mut test_log = TestLog::default();
+        let mut auditor = auditor();
+        apply(&mut test_log, &mut auditor, &[INDEX_A, INDEX_B, INDEX_A]);
+
+        let now = SystemTime::now();
+        let (old_tree_head, old_root) = test_log.signed_tree_head(now);
+        let auditor_head = auditor
+            .sign_tree_head(&old_tree_head, now)
+            .expect("can sign");
+
+        apply(&mut test_log, &mut auditor, &[INDEX_C, INDEX_B]);
+        let (tree_head, root) = test_log.signed_tree_head(now);
+
+        let full_tree_head = FullTreeHead {
+            tree_head: Some(tree_head),
+            full_auditor_tree_heads: vec![FullAuditorTreeHead {
+                tree_head: Some(auditor_head),
+                root_value: Some(old_root.to_vec()),
+                consistency: auditor
+                    .consistency_proof(old_tree_head.tree_size)
+                    .expect("can prove")
+                    .into_iter()
+                    .map(Vec::from)
+                    .collect(),
+                public_key: auditor.verifying_key().as_bytes().to_vec(),
+            }],
+            ..Default::default()
+        };
+        verify_full_tree_head(&config(), &full_tree_head, root, None, None, now)
+            .expect("valid tree head");
+    }
+
+    #[test]
+    fn rejects_inconsistent_prefix_proof() {
+        let mut test_log = TestLog::default();
+        let mut auditor = auditor();
+        apply(&mut test_log, &mut auditor, &[INDEX_A]);
+
+        let mut update = test_log.update(INDEX_B, [0; 32]);
+        update.copath[0][0] ^= 1;
+        assert_matches!(auditor.update(&update), Err(Error::VerificationFailed(_)));
+        assert_eq!(auditor.tree_size(), 1);
+
+        // Replaying the first update claims INDEX_A is still absent.
+        let mut other_log = TestLog::default();
+        let replayed = other_log.update(INDEX_A, [0; 32]);
+        assert_matches!(auditor.update(&replayed), Err(Error::VerificationFailed(_)));
+    }
+
+    #[test]
+    fn rejects_mismatched_tree_heads() {
+        let mut test_log = TestLog::default();
+        let mut auditor = auditor();
+        apply(&mut test_log, &mut auditor, &[INDEX_A, INDEX_B]);
+        let now = SystemTime::now();
+
+        let (mut tree_head, _) = test_log.signed_tree_head(now);
+        tree_head.tree_size += 1;
+        assert_matches!(
+            auditor.sign_tree_head(&tree_head, now),
+            Err(Error::BadData(_))
+        );
+
+        let (mut tree_head, _) = test_log.signed_tree_head(now);
+        tree_head.timestamp += 1;
+        assert_matches!(
+            auditor.sign_tree_head(&tree_head, now),
+            Err(Error::VerificationFailed(_))
+        );
+
+        let (mut tree_head, _) = test_log.signed_tree_head(now);
+        tree_head.signatures.clear();
+        assert_matches!(
+            auditor.sign_tree_head(&tree_head, now),
+            Err(Error::BadData(_))
+        );
+    }
+
+    #[test]
+    fn stored_state_round_trips() {
+        let mut test_log = TestLog::default();
+        let mut auditor = auditor();
+        apply(&mut test_log, &mut auditor, &[INDEX_A, INDEX_B, INDEX_A]);
+        let now = SystemTime::now();
+        let (tree_head, _) = test_log.signed_tree_head(now);
+        auditor.sign_tree_head(&tree_head, now).expect("can sign");
+
+        let bytes = auditor.to_stored().encode_to_vec();
+        let stored = StoredAuditorState::decode(bytes.as_slice()).expect("valid proto");
+        let mut restored =
+            Auditor::from_stored(config(), SigningKey::from_bytes(&AUDITOR_KEY), stored)
+                .expect("valid state");
+        assert_eq!(restored.prefix_root, auditor.prefix_root);
+        assert_eq!(restored.tree_root(), auditor.tree_root());
+        assert_eq!(restored.last_timestamp, auditor.last_timestamp);
+
+        // The restored auditor can carry on where the original left off.
+        apply(&mut test_log, &mut restored, &[INDEX_C, INDEX_A]);
+        assert_eq!(restored.prefix_root, test_log.prefix_root());
+        assert_eq!(restored.tree_root(), test_log.log.root().ok());
+    }
+
+    #[test]
+    fn rejects_invalid_stored_state() {
+        let mut test_log = TestLog::default();
+        let mut auditor = auditor();
+        apply(&mut test_log, &mut auditor, &[INDEX_A]);
+        let restore =
+            |stored| Auditor::from_stored(config(), SigningKey::from_bytes(&AUDITOR_KEY), stored);
+
+        let mut stored = auditor.to_stored();
+        stored.prefix_root.pop();
+        assert_matches!(restore(stored), Err(Error::BadData(_)));
+
+        let mut stored = auditor.to_stored();
+        stored.keys[0].position = 1;
+        assert_matches!(restore(stored), Err(Error::BadData(_)));
+
+        let mut stored = auditor.to_stored();
+        stored.keys.push(stored.keys[0].clone());
+        assert_matches!(restore(stored), Err(Error::BadData(_)));
+    }
+
+    #[test]
+    fn rejects_non_auditing_config() {
+        let contact_monitoring = PublicConfig {
+            mode: DeploymentMode::ContactMonitoring,
+            ..config()
+        };
+        assert_matches!(
+            Auditor::new(contact_monitoring, SigningKey::from_bytes(&AUDITOR_KEY)),
+            Err(Error::BadData(_))
+        );
+        assert_matches!(
+            Auditor::new(config(), SigningKey::from_bytes(&SERVICE_KEY)),
+            Err(Error::BadData(_))
+        );
+    }
+}
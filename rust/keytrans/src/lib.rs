@@ -5,6 +5,7 @@
 
 #![warn(clippy::unwrap_used)]
 
+mod auditor;
 mod commitments;
 mod guide;
 mod implicit;
@@ -12,6 +13,8 @@ mod left_balanced;
 mod log;
 mod prefix;
 mod proto;
+#[cfg(any(test, feature = "test-util"))]
+pub mod reference_log;
 mod verify;
 mod vrf;
 
@@ -19,21 +22,20 @@ use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
 use std::time::SystemTime;
 
-pub use ed25519_dalek::VerifyingKey;
+pub use auditor::Auditor;
+pub use ed25519_dalek::{SigningKey, VerifyingKey};
 use itertools::Itertools;
 pub use proto::{
-    ChatMonitorResponse, CondensedTreeSearchResponse,
+    AuditorTreeHead, AuditorUpdate, ChatMonitorResponse, CondensedTreeSearchResponse, Consistency,
     DistinguishedResponse as ChatDistinguishedResponse, FullAuditorTreeHead, FullTreeHead,
     MonitorKey, MonitorProof, MonitorRequest, MonitorResponse,
-    SearchResponse as ChatSearchResponse, Signature, StoredAccountData, StoredMonitoringData,
-    StoredTreeHead, TreeHead, UpdateRequest, UpdateResponse,
+    SearchResponse as ChatSearchResponse, Signature, StoredAccountData, StoredAuditorState,
+    StoredMonitoringData, StoredTreeHead, TreeHead, UpdateRequest, UpdateResponse,
 };
 pub use verify::Error;
 use verify::{verify_distinguished, verify_monitor, verify_search};
 pub use vrf::PublicKey as VrfPublicKey;
 
-use crate::proto::AuditorTreeHead;
-
 #[derive(PartialEq, Clone)]
 pub struct VerifyingKeys(Vec<VerifyingKey>);
 
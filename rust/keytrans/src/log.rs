@@ -283,6 +283,7 @@ fn tree_hash(left: &NodeData, right: &NodeData) -> NodeData {
     }
 }
 
+#[derive(Clone, Default)]
 struct SimpleRootCalculator {
     chain: Vec<Option<NodeData>>,
 }
@@ -453,6 +454,123 @@ pub fn verify_consistency_proof(
     }
 }
 
+/// A log tree that retains all of its leaves, as kept by an auditor.
+///
+/// The value of every complete subtree is cached as leaves are appended, so the
+/// value of any node (and thus any proof) can be computed in `O(log n)`.
+#[derive(Clone, Default)]
+pub struct FullLogTree {
+    // `subtrees[k][i]` is the value of the complete subtree covering leaves
+    // `i * 2^k .. (i + 1) * 2^k`. `subtrees[0]` holds the leaves themselves.
+    subtrees: Vec<Vec<NodeData>>,
+}
+
+impl FullLogTree {
+    pub fn len(&self) -> u64 {
+        self.subtrees
+            .first()
+            .map_or(0, Vec::len)
+            .try_into()
+            .expect("fits in u64")
+    }
+
+    pub fn leaves(&self) -> impl ExactSizeIterator<Item = &Hash> {
+        self.subtrees
+            .first()
+            .into_iter()
+            .flatten()
+            .map(|nd| &nd.value)
+    }
+
+    pub fn append(&mut self, leaf: Hash) {
+        let mut acc = NodeData {
+            interior: false,
+            value: leaf,
+        };
+        for level in 0.. {
+            if level == self.subtrees.len() {
+                self.subtrees.push(vec![]);
+            }
+            let row = &mut self.subtrees[level];
+            row.push(acc);
+            if row.len() % 2 == 1 {
+                break;
+            }
+            acc = tree_hash(&row[row.len() - 2], &row[row.len() - 1]);
+        }
+    }
+
+    pub fn root(&self) -> Result<Hash> {
+        self.range_value(0, self.len())
+    }
+
+    // Returns a consistency proof between the tree made of the first `m` leaves
+    // and the current tree, in the form expected by `verify_consistency_proof`.
+    pub fn consistency_proof(&self, m: u64) -> Result<Vec<Hash>> {
+        let n = self.len();
+        if m == 0 || m >= n {
+            return Err(Error::InvalidInput("m must be within (0, n)"));
+        }
+        math::consistency_proof(m, n)
+            .into_iter()
+            .map(|id| self.node_value(id, n))
+            .collect()
+    }
+
+    // Returns a batch inclusion proof for the leaves at positions `x`, in the
+    // form expected by `evaluate_batch_proof`.
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn inclusion_proof(&self, x: &[u64]) -> Result<Vec<Hash>> {
+        let n = self.len();
+        if x.is_empty() {
+            return Err(Error::InvalidInput("at least one leaf must be provided"));
+        }
+        let sorted = x.windows(2).all(|w| w[0] < w[1]);
+        if !sorted {
+            return Err(Error::InvalidInput("input entries must be in sorted order"));
+        }
+        if x.last().is_some_and(|&last| last >= n) {
+            return Err(Error::InvalidInput("leaf is outside of the tree"));
+        }
+        math::batch_copath(x, n)
+            .into_iter()
+            .map(|id| self.node_value(id, n))
+            .collect()
+    }
+
+    // Returns the value of node `id` in a tree with `n` leaves.
+    fn node_value(&self, id: u64, n: u64) -> Result<Hash> {
+        let width = 1u64 << math::level(id);
+        let start = (id + 1 - width) / 2;
+        self.range_value(start, n.min(start + width))
+    }
+
+    // Returns the value of the subtree covering leaves `start..end`, where
+    // `start` is aligned to the width of that subtree.
+    //
+    // The range is split into complete subtrees of decreasing size, whose
+    // cached values are then combined.
+    fn range_value(&self, start: u64, end: u64) -> Result<Hash> {
+        let mut calc = SimpleRootCalculator::new();
+        let mut pos = start;
+        while pos < end {
+            let level = pos.trailing_zeros().min((end - pos).ilog2());
+            let (row, index) = usize::try_from(level)
+                .ok()
+                .zip(usize::try_from(pos >> level).ok())
+                .ok_or(Error::Unexpected("position out of range"))?;
+            let node = self
+                .subtrees
+                .get(row)
+                .and_then(|row| row.get(index))
+                .ok_or(Error::Unexpected("subtree not yet complete"))?;
+            calc.insert(row, node.value);
+            pos += 1 << level;
+        }
+        calc.root()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use const_str::hex;
@@ -504,4 +622,72 @@ mod test {
         assert!(verify_consistency_proof(1078, 2000, proof, &m_root, &m_root).is_err());
         assert!(verify_consistency_proof(1078, 2000, proof, &n_root, &n_root).is_err());
     }
+
+    #[test]
+    fn full_log_tree_matches_uncached_root() {
+        let mut tree = FullLogTree::default();
+        assert!(tree.root().is_err());
+
+        let mut calc = SimpleRootCalculator::new();
+        for i in 0..=40u8 {
+            tree.append([i; 32]);
+            calc.insert(0, [i; 32]);
+            assert_eq!(
+                tree.root().expect("not empty"),
+                calc.root().expect("not empty"),
+                "n = {}",
+                tree.len()
+            );
+        }
+        assert_eq!(tree.leaves().len(), 41);
+    }
+
+    #[test]
+    fn full_log_tree_consistency_proofs_verify() {
+        let leaves: Vec<Hash> = (0..20u8).map(|i| [i; 32]).collect();
+
+        let mut tree = FullLogTree::default();
+        let mut roots = vec![];
+        for leaf in &leaves {
+            tree.append(*leaf);
+            roots.push(tree.root().expect("not empty"));
+        }
+
+        let n = tree.len();
+        let n_root = tree.root().expect("not empty");
+        for m in 1..n {
+            let proof = tree.consistency_proof(m).expect("valid m");
+            let m_root = roots[usize::try_from(m - 1).expect("small")];
+            verify_consistency_proof(m, n, &proof, &m_root, &n_root)
+                .unwrap_or_else(|e| panic!("m = {m}: {e}"));
+        }
+        assert!(tree.consistency_proof(0).is_err());
+        assert!(tree.consistency_proof(n).is_err());
+    }
+
+    #[test]
+    fn full_log_tree_inclusion_proofs_verify() {
+        let mut tree = FullLogTree::default();
+        for i in 0..=22u8 {
+            tree.append([i; 32]);
+        }
+        let n = tree.len();
+        let root = tree.root().expect("not empty");
+
+        for x in [vec![0], vec![22], vec![3, 4, 5], vec![0, 7, 8, 16, 21, 22]] {
+            let values = x
+                .iter()
+                .map(|&i| [u8::try_from(i).expect("small"); 32])
+                .collect::<Vec<_>>();
+            let proof = tree.inclusion_proof(&x).expect("valid leaves");
+            assert_eq!(
+                evaluate_batch_proof(&x, n, &values, &proof).expect("valid proof"),
+                root,
+                "x = {x:?}"
+            );
+        }
+        assert!(tree.inclusion_proof(&[]).is_err());
+        assert!(tree.inclusion_proof(&[2, 1]).is_err());
+        assert!(tree.inclusion_proof(&[n]).is_err());
+    }
 }
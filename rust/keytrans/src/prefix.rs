@@ -11,11 +11,14 @@ use crate::proto::PrefixProof as SearchResult;
 
 const KEY_LENGTH: usize = 32;
 
+/// The value of a leaf for a key that isn't present in the tree.
+const EMPTY_LEAF: [u8; 32] = [0; 32];
+
 /// Malformed proof
 #[derive(Debug, displaydoc::Display)]
 pub struct MalformedProof;
 
-fn leaf_hash(key: &[u8; 32], ctr: u32, pos: u64) -> [u8; 32] {
+pub fn leaf_hash(key: &[u8; 32], ctr: u32, pos: u64) -> [u8; 32] {
     let mut hasher = Sha256::new();
     hasher.update([0x00]);
     hasher.update(key);
@@ -25,7 +28,7 @@ fn leaf_hash(key: &[u8; 32], ctr: u32, pos: u64) -> [u8; 32] {
     hasher.finalize().into()
 }
 
-fn parent_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+pub fn parent_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
     let mut hasher = Sha256::new();
     hasher.update([0x01]);
     hasher.update(left);
@@ -63,5 +66,105 @@ fn evaluate_proof(
 // `key`, and returns the root that would make the proof valid. `pos` is the
 // position of the first instance of `key` in the log.
 pub fn evaluate(key: &[u8; 32], pos: u64, res: &SearchResult) -> Result<[u8; 32], MalformedProof> {
-    evaluate_proof(key, &leaf_hash(key, res.counter, pos), &res.proof)
+    evaluate_leaf(key, res.counter, pos, &res.proof)
+}
+
+// Same as `evaluate`, but with the counter and the copath of `key` provided
+// separately.
+pub fn evaluate_leaf(
+    key: &[u8; 32],
+    ctr: u32,
+    pos: u64,
+    proof: &[Vec<u8>],
+) -> Result<[u8; 32], MalformedProof> {
+    evaluate_proof(key, &leaf_hash(key, ctr, pos), proof)
+}
+
+// Returns the root that would make `proof` a valid proof that `key` is not in
+// the tree.
+pub fn evaluate_absent(key: &[u8; 32], proof: &[Vec<u8>]) -> Result<[u8; 32], MalformedProof> {
+    evaluate_proof(key, &EMPTY_LEAF, proof)
+}
+
+// Returns the hash of each level of an empty subtree, starting from the
+// leaves.
+pub fn empty_subtree_hashes() -> impl Iterator<Item = [u8; 32]> {
+    std::iter::successors(Some(EMPTY_LEAF), |child| Some(parent_hash(child, child)))
+        .take(8 * KEY_LENGTH + 1)
+}
+
+// Returns the root of a tree with no keys in it.
+pub fn empty_root() -> [u8; 32] {
+    empty_subtree_hashes().last().expect("not empty")
+}
+
+#[cfg(test)]
+mod test {
+    use const_str::hex;
+
+    use super::*;
+
+    // The expected values below were computed independently from the hashing
+    // scheme used by the key transparency server, rather than with this module.
+    const KEY: [u8; 32] = hex!("5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a01");
+
+    fn proof() -> Vec<Vec<u8>> {
+        (0..=255u8).map(|i| Sha256::digest([i]).to_vec()).collect()
+    }
+
+    #[test]
+    fn empty_subtree_hashes_vectors() {
+        let hashes: Vec<_> = empty_subtree_hashes().collect();
+        assert_eq!(hashes.len(), 257);
+        assert_eq!(hashes[0], EMPTY_LEAF);
+        assert_eq!(
+            hashes[1],
+            hex!("ae0798d0ecaed2b778eddebf18f071a561c53658c05e76cedecc27cafbdbc577")
+        );
+        assert_eq!(
+            hashes[2],
+            hex!("90534fe0aff6db9edb29eee74e78a386916a581c8e6465349493e1a6c87241e1")
+        );
+        assert_eq!(
+            empty_root(),
+            hex!("6155289130893872355eac98042d22aefa2c2e708bea169402760e3b55f9a2dc")
+        );
+    }
+
+    #[test]
+    fn evaluate_absent_vectors() {
+        assert_eq!(
+            evaluate_absent(&KEY, &proof()).expect("valid proof"),
+            hex!("1bc2003ebbcd35524efdd092b609e849cde49263644b11c63086a0fc452051df")
+        );
+    }
+
+    #[test]
+    fn evaluate_absent_in_empty_tree() {
+        let copath: Vec<_> = empty_subtree_hashes()
+            .take(8 * KEY_LENGTH)
+            .map(Vec::from)
+            .collect();
+        assert_eq!(
+            evaluate_absent(&KEY, &copath).expect("valid proof"),
+            empty_root()
+        );
+    }
+
+    #[test]
+    fn evaluate_leaf_vectors() {
+        assert_eq!(
+            leaf_hash(&KEY, 3, 42),
+            hex!("798aa75f6f03b760221907eae5fb7265e61c67596167320fa6e9a9210299059d")
+        );
+        assert_eq!(
+            evaluate_leaf(&KEY, 3, 42, &proof()).expect("valid proof"),
+            hex!("17fc2c9111ef82cd28a834034284b7941befa79d61facbeb33b3a727f97bf8c3")
+        );
+    }
+
+    #[test]
+    fn rejects_short_proof() {
+        assert!(evaluate_absent(&KEY, &proof()[1..]).is_err());
+    }
 }
@@ -0,0 +1,777 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! An in-process implementation of the server side of a key transparency
+//! log, for exercising clients in tests.
+//!
+//! [`ReferenceLog`] keeps the entire log in memory and answers search, monitor
+//! and distinguished requests with real proofs, so that they can be checked
+//! with [`KeyTransparency`](crate::KeyTransparency) exactly like responses
+//! from the production service. It is [`Clone`], which makes it easy to
+//! simulate a service that equivocates by presenting diverging forks of the
+//! log to different clients.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, LazyLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ed25519_dalek::Signer as _;
+use sha2::{Digest as _, Sha256};
+
+use crate::commitments::commit;
+use crate::guide::ProofGuide;
+use crate::implicit::full_monitoring_path;
+use crate::log::FullLogTree;
+use crate::proto::{Consistency, PrefixProof, ProofStep, SearchProof, Signature, UpdateValue};
+use crate::verify::{leaf_hash, marshal_update_value};
+use crate::{
+    Auditor, AuditorUpdate, ChatDistinguishedResponse, CondensedTreeSearchResponse, DeploymentMode,
+    Error, FullAuditorTreeHead, FullTreeHead, LastTreeHead, MonitorProof, MonitorRequest,
+    MonitorResponse, PublicConfig, SigningKey, SingleSignatureTreeHead, TreeHead,
+    VerifiableTreeHead as _, VerifyingKey, prefix, vrf,
+};
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// The search key of the entry that clients use to establish a shared view of
+/// the log.
+pub const DISTINGUISHED_KEY: &[u8] = b"distinguished";
+
+/// A single version of a search key's value.
+#[derive(Clone)]
+struct ValueVersion {
+    value: Vec<u8>,
+    opening: [u8; 16],
+}
+
+#[derive(Clone)]
+struct SearchKeyState {
+    index: [u8; 32],
+    vrf_proof: [u8; 80],
+    versions: Vec<ValueVersion>,
+}
+
+/// The log positions at which an index in the prefix tree was updated.
+///
+/// The first element is the position where the index was added, and the
+/// counter of the index at position `p` is the number of later updates at or
+/// before `p`.
+#[derive(Clone, Default)]
+struct IndexHistory {
+    positions: Vec<u64>,
+}
+
+impl IndexHistory {
+    fn first_position(&self) -> u64 {
+        self.positions[0]
+    }
+
+    fn counter_at(&self, position: u64) -> Option<u32> {
+        let updates = self.positions.partition_point(|&p| p <= position);
+        let counter = updates.checked_sub(1)?;
+        Some(u32::try_from(counter).expect("too many updates to index"))
+    }
+}
+
+/// A node of the prefix tree as of some log position.
+///
+/// Updating an index only changes the nodes on its path, so each version of
+/// the tree shares every other subtree with the version before it. A missing
+/// child is an empty subtree.
+struct PrefixNode {
+    hash: [u8; 32],
+    children: [Option<Arc<PrefixNode>>; 2],
+}
+
+/// The hash of an empty subtree at each depth.
+static EMPTY_SUBTREE_HASHES: LazyLock<Vec<[u8; 32]>> = LazyLock::new(|| {
+    let mut hashes = prefix::empty_subtree_hashes().collect::<Vec<_>>();
+    hashes.reverse();
+    hashes
+});
+
+impl PrefixNode {
+    // Returns a copy of `node` (which is at `depth`) with the leaf for `index`
+    // set to `leaf`.
+    fn with_leaf(
+        node: Option<&Arc<Self>>,
+        index: &[u8; 32],
+        depth: usize,
+        leaf: [u8; 32],
+    ) -> Arc<Self> {
+        if depth == 256 {
+            return Arc::new(Self {
+                hash: leaf,
+                children: Default::default(),
+            });
+        }
+        let mut children = node.map(|node| node.children.clone()).unwrap_or_default();
+        let side = usize::from(bit(index, depth));
+        children[side] = Some(Self::with_leaf(
+            children[side].as_ref(),
+            index,
+            depth + 1,
+            leaf,
+        ));
+        let [left, right] = children.each_ref().map(|child| hash_at(child, depth + 1));
+        Arc::new(Self {
+            hash: prefix::parent_hash(&left, &right),
+            children,
+        })
+    }
+}
+
+// Returns the hash of the subtree `node`, which is at `depth`.
+fn hash_at(node: &Option<Arc<PrefixNode>>, depth: usize) -> [u8; 32] {
+    node.as_ref()
+        .map_or(EMPTY_SUBTREE_HASHES[depth], |node| node.hash)
+}
+
+/// The most recently published tree head, along with what is needed to
+/// present it to clients.
+#[derive(Clone)]
+struct PublishedTreeHead {
+    tree_head: TreeHead,
+    auditor_tree_heads: Vec<FullAuditorTreeHead>,
+}
+
+/// An in-memory key transparency log that produces verifiable responses.
+///
+/// Every change to the log is followed by a newly signed tree head, and is
+/// forwarded to the log's auditors (if any), whose signatures are included in
+/// every [`FullTreeHead`].
+#[derive(Clone)]
+pub struct ReferenceLog {
+    config: PublicConfig,
+    signing_key: SigningKey,
+    vrf_key: vrf::SecretKey,
+    auditors: Vec<Auditor>,
+    search_keys: HashMap<Vec<u8>, SearchKeyState>,
+    indices: BTreeMap<[u8; 32], IndexHistory>,
+    commitments: Vec<[u8; 32]>,
+    /// The root of the prefix tree after each entry in the log.
+    prefix_roots: Vec<Arc<PrefixNode>>,
+    log: FullLogTree,
+    published: Option<PublishedTreeHead>,
+}
+
+impl ReferenceLog {
+    /// Creates an empty log.
+    ///
+    /// If `auditor_keys` is empty the log is deployed in
+    /// [`DeploymentMode::ContactMonitoring`] mode, otherwise it uses
+    /// [`DeploymentMode::ThirdPartyAuditing`] with an in-process [`Auditor`]
+    /// for each key.
+    pub fn new(signing_key: SigningKey, vrf_seed: [u8; 32], auditor_keys: Vec<SigningKey>) -> Self {
+        let vrf_key = vrf::SecretKey::from_seed(&vrf_seed);
+        let mode = if auditor_keys.is_empty() {
+            DeploymentMode::ContactMonitoring
+        } else {
+            DeploymentMode::ThirdPartyAuditing(
+                auditor_keys.iter().map(SigningKey::verifying_key).into(),
+            )
+        };
+        let config = PublicConfig {
+            mode,
+            signature_key: signing_key.verifying_key(),
+            vrf_key: vrf_key.public_key().clone(),
+        };
+        let auditors = auditor_keys
+            .into_iter()
+            .map(|key| Auditor::new(config.clone(), key).expect("auditor key is in the config"))
+            .collect();
+        Self {
+            config,
+            signing_key,
+            vrf_key,
+            auditors,
+            search_keys: HashMap::new(),
+            indices: BTreeMap::new(),
+            commitments: vec![],
+            prefix_roots: vec![],
+            log: FullLogTree::default(),
+            published: None,
+        }
+    }
+
+    /// The configuration clients should use to verify this log's responses.
+    pub fn config(&self) -> &PublicConfig {
+        &self.config
+    }
+
+    /// The number of entries in the log.
+    pub fn tree_size(&self) -> u64 {
+        self.log.len()
+    }
+
+    /// The most recently published tree head and the matching root, as a
+    /// client would store it after verifying a response.
+    pub fn last_tree_head(&self) -> Option<LastTreeHead> {
+        let published = self.published.as_ref()?;
+        let root = self.log.root().ok()?;
+        Some(LastTreeHead(published.tree_head.clone(), root))
+    }
+
+    /// Sets `search_key` to `value`, and publishes the new tree head.
+    ///
+    /// Returns the position of the new entry in the log.
+    pub fn update(&mut self, search_key: &[u8], value: &[u8]) -> Result<u64> {
+        let position = self.log.len();
+        let opening = self.opening(position);
+        let commitment: [u8; 32] = commit(search_key, &marshal_update_value(value)?, &opening)
+            .try_into()
+            .expect("commitment is a SHA-256 hash");
+
+        let vrf_key = &self.vrf_key;
+        let state = self
+            .search_keys
+            .entry(search_key.to_vec())
+            .or_insert_with(|| {
+                let (vrf_proof, index) = vrf_key.prove(search_key);
+                SearchKeyState {
+                    index,
+                    vrf_proof,
+                    versions: vec![],
+                }
+            });
+        state.versions.push(ValueVersion {
+            value: value.to_vec(),
+            opening,
+        });
+        let index = state.index;
+
+        // The copath of the index is the same before and after the update,
+        // since no other index changes.
+        let copath = self.copath(&index, position.checked_sub(1));
+        let history = self.indices.entry(index).or_default();
+        history.positions.push(position);
+        let counter = history
+            .counter_at(position)
+            .expect("index was just updated");
+        let prefix_root =
+            prefix::evaluate_leaf(&index, counter, history.first_position(), &copath)?;
+        let new_prefix_root = PrefixNode::with_leaf(
+            self.prefix_roots.last(),
+            &index,
+            0,
+            prefix::leaf_hash(&index, counter, history.first_position()),
+        );
+        debug_assert_eq!(new_prefix_root.hash, prefix_root);
+
+        let auditor_update = AuditorUpdate {
+            index: index.to_vec(),
+            commitment: commitment.to_vec(),
+            copath,
+        };
+        for auditor in &mut self.auditors {
+            auditor.update(&auditor_update)?;
+        }
+
+        self.commitments.push(commitment);
+        self.prefix_roots.push(new_prefix_root);
+        self.log.append(leaf_hash(&prefix_root, &commitment));
+        self.publish()?;
+        Ok(position)
+    }
+
+    /// Adds a new entry for [`DISTINGUISHED_KEY`].
+    pub fn update_distinguished(&mut self) -> Result<u64> {
+        self.update(DISTINGUISHED_KEY, &[])
+    }
+
+    /// Returns the current tree head, with consistency proofs from the tree
+    /// sizes in `consistency`.
+    pub fn full_tree_head(&self, consistency: Consistency) -> Result<FullTreeHead> {
+        let Consistency {
+            last,
+            distinguished,
+        } = consistency;
+        let published = self
+            .published
+            .as_ref()
+            .ok_or_else(|| Error::BadData("log is empty".to_string()))?;
+        Ok(FullTreeHead {
+            tree_head: Some(published.tree_head.clone()),
+            last: self.consistency_proof(last)?,
+            distinguished: self.consistency_proof(distinguished)?,
+            full_auditor_tree_heads: published.auditor_tree_heads.clone(),
+        })
+    }
+
+    /// Searches for `version` of `search_key`, or its latest version if
+    /// `version` is `None`.
+    pub fn search(
+        &self,
+        search_key: &[u8],
+        version: Option<u32>,
+    ) -> Result<CondensedTreeSearchResponse> {
+        let state = self.search_key_state(search_key)?;
+        let history = &self.indices[&state.index];
+        let pos = history.first_position();
+
+        let mut steps = vec![];
+        let mut ids = vec![];
+        let result = ProofGuide::new(version, pos, self.log.len()).consume(|guide, id| {
+            let (step, counter) = self.proof_step(&state.index, history, id);
+            guide.insert(id, counter);
+            steps.push(step);
+            ids.push(id);
+            Ok::<(), Error>(())
+        })?;
+        let (_, result_id) =
+            result.ok_or_else(|| Error::BadData("version not found".to_string()))?;
+        let counter = history.counter_at(result_id).expect("result is in range");
+        let ValueVersion { value, opening } =
+            &state.versions[usize::try_from(counter).expect("fits in usize")];
+
+        ids.sort();
+        Ok(CondensedTreeSearchResponse {
+            vrf_proof: state.vrf_proof.to_vec(),
+            search: Some(SearchProof {
+                pos,
+                steps,
+                inclusion: self.inclusion_proof(&ids)?,
+            }),
+            opening: opening.to_vec(),
+            value: Some(UpdateValue {
+                value: value.clone(),
+            }),
+        })
+    }
+
+    /// Answers a monitoring request for any number of search keys.
+    pub fn monitor(&self, request: &MonitorRequest) -> Result<MonitorResponse> {
+        let MonitorRequest { keys, consistency } = request;
+        let n = self.log.len();
+
+        let mut ids = BTreeSet::new();
+        let proofs = keys
+            .iter()
+            .map(|key| {
+                let state = self.search_key_state(&key.search_key)?;
+                let history = &self.indices[&state.index];
+                let pos = history.first_position();
+                if !(pos..n).contains(&key.entry_position) {
+                    return Err(Error::BadData("entry position out of range".to_string()));
+                }
+                let steps = full_monitoring_path(key.entry_position, pos, n)
+                    .into_iter()
+                    .map(|id| {
+                        ids.insert(id);
+                        self.proof_step(&state.index, history, id).0
+                    })
+                    .collect();
+                Ok(MonitorProof { steps })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let inclusion = if ids.is_empty() {
+            vec![self.log.root()?.to_vec()]
+        } else {
+            self.inclusion_proof(&ids.into_iter().collect::<Vec<_>>())?
+        };
+        Ok(MonitorResponse {
+            tree_head: Some(self.full_tree_head(consistency.unwrap_or_default())?),
+            proofs,
+            inclusion,
+        })
+    }
+
+    /// Returns the latest version of [`DISTINGUISHED_KEY`], with a
+    /// consistency proof from the client's last distinguished tree size
+    /// `last_distinguished`.
+    pub fn distinguished(
+        &self,
+        last_distinguished: Option<u64>,
+    ) -> Result<ChatDistinguishedResponse> {
+        Ok(ChatDistinguishedResponse {
+            tree_head: Some(self.full_tree_head(Consistency {
+                last: None,
+                distinguished: last_distinguished,
+            })?),
+            distinguished: Some(self.search(DISTINGUISHED_KEY, None)?),
+        })
+    }
+
+    fn search_key_state(&self, search_key: &[u8]) -> Result<&SearchKeyState> {
+        self.search_keys
+            .get(search_key)
+            .ok_or_else(|| Error::BadData("search key not found".to_string()))
+    }
+
+    // Derives the commitment opening for the entry at `position`. Real
+    // deployments use a secret key for this; the service's signing key is as
+    // good as any here.
+    fn opening(&self, position: u64) -> [u8; 16] {
+        let hash = Sha256::new()
+            .chain_update(self.signing_key.as_bytes())
+            .chain_update(position.to_be_bytes())
+            .finalize();
+        *hash.first_chunk().expect("hash has enough bytes")
+    }
+
+    // Returns the proof step for `index` in the log entry at position `id`,
+    // along with the index's counter at that position.
+    fn proof_step(&self, index: &[u8; 32], history: &IndexHistory, id: u64) -> (ProofStep, u32) {
+        let counter = history
+            .counter_at(id)
+            .expect("ids are never before the index's first position");
+        let step = ProofStep {
+            prefix: Some(PrefixProof {
+                proof: self.copath(index, Some(id)),
+                counter,
+            }),
+            commitment: self.commitments[usize::try_from(id).expect("fits in usize")].to_vec(),
+        };
+        (step, counter)
+    }
+
+    // Returns the copath of `index` in the prefix tree as of log position
+    // `version` (or the empty prefix tree if `version` is `None`), deepest
+    // sibling first.
+    fn copath(&self, index: &[u8; 32], version: Option<u64>) -> Vec<Vec<u8>> {
+        let mut node = version
+            .map(|version| &self.prefix_roots[usize::try_from(version).expect("fits in usize")]);
+        let mut copath = Vec::with_capacity(256);
+        for depth in 0..256 {
+            let Some(current) = node else {
+                copath.push(EMPTY_SUBTREE_HASHES[depth + 1].to_vec());
+                continue;
+            };
+            let side = usize::from(bit(index, depth));
+            copath.push(hash_at(&current.children[1 - side], depth + 1).to_vec());
+            node = current.children[side].as_ref();
+        }
+        copath.reverse();
+        copath
+    }
+
+    fn consistency_proof(&self, tree_size: Option<u64>) -> Result<Vec<Vec<u8>>> {
+        let n = self.log.len();
+        match tree_size {
+            None => Ok(vec![]),
+            Some(m) if m == n => Ok(vec![]),
+            Some(m) if m > n => Err(Error::BadData(
+                "tree size is greater than the current tree size".to_string(),
+            )),
+            Some(m) => Ok(self
+                .log
+                .consistency_proof(m)?
+                .into_iter()
+                .map(Vec::from)
+                .collect()),
+        }
+    }
+
+    fn inclusion_proof(&self, ids: &[u64]) -> Result<Vec<Vec<u8>>> {
+        Ok(self
+            .log
+            .inclusion_proof(ids)?
+            .into_iter()
+            .map(Vec::from)
+            .collect())
+    }
+
+    // Signs a tree head for the current log, and has every auditor sign it as
+    // well.
+    fn publish(&mut self) -> Result<()> {
+        let now = SystemTime::now();
+        let root = self.log.root()?;
+        let unsigned_head = SingleSignatureTreeHead(TreeHead {
+            tree_size: self.log.len(),
+            timestamp: now
+                .duration_since(UNIX_EPOCH)
+                .expect("valid SystemTime")
+                .as_millis()
+                .try_into()
+                .expect("enough millis since UNIX_EPOCH"),
+            signatures: vec![],
+        });
+        let sign = |auditor_key: Option<&VerifyingKey>| Signature {
+            auditor_public_key: auditor_key
+                .map(|key| key.as_bytes().to_vec())
+                .unwrap_or_default(),
+            signature: self
+                .signing_key
+                .sign(&unsigned_head.to_signable_header(&root, &self.config, auditor_key))
+                .to_bytes()
+                .to_vec(),
+        };
+        let signatures = match &self.config.mode {
+            DeploymentMode::ContactMonitoring => vec![sign(None)],
+            DeploymentMode::ThirdPartyManagement(keys)
+            | DeploymentMode::ThirdPartyAuditing(keys) => keys.iter().map(Some).map(sign).collect(),
+        };
+        let tree_head = TreeHead {
+            signatures,
+            ..unsigned_head.0
+        };
+
+        let auditor_tree_heads = self
+            .auditors
+            .iter_mut()
+            .map(|auditor| {
+                Ok(FullAuditorTreeHead {
+                    tree_head: Some(auditor.sign_tree_head(&tree_head, now)?),
+                    root_value: None,
+                    consistency: vec![],
+                    public_key: auditor.verifying_key().as_bytes().to_vec(),
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        self.published = Some(PublishedTreeHead {
+            tree_head,
+            auditor_tree_heads,
+        });
+        Ok(())
+    }
+}
+
+// Returns bit `depth` of `key`, counting from the most significant bit.
+fn bit(key: &[u8; 32], depth: usize) -> bool {
+    (key[depth / 8] >> (7 - (depth % 8))) & 1 == 1
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+
+    use super::*;
+    use crate::{
+        FullSearchResponse, KeyTransparency, MonitorContext, MonitorKey, MonitoringData,
+        SearchContext, SlimSearchRequest,
+    };
+
+    fn reference_log(auditors: u8) -> ReferenceLog {
+        ReferenceLog::new(
+            SigningKey::from_bytes(&[1; 32]),
+            [2; 32],
+            (0..auditors)
+                .map(|i| SigningKey::from_bytes(&[3 + i; 32]))
+                .collect(),
+        )
+    }
+
+    fn search_key(i: usize) -> Vec<u8> {
+        format!("key {i}").into_bytes()
+    }
+
+    /// A client's view of the log, as it would be kept in its storage.
+    #[derive(Default)]
+    struct Client {
+        last_tree_head: Option<LastTreeHead>,
+        data: HashMap<Vec<u8>, MonitoringData>,
+    }
+
+    impl Client {
+        fn search(
+            &mut self,
+            log: &ReferenceLog,
+            search_key: &[u8],
+            version: Option<u32>,
+        ) -> Result<Vec<u8>> {
+            let kt = KeyTransparency {
+                config: log.config().clone(),
+            };
+            let tree_head = log.full_tree_head(Consistency {
+                last: self
+                    .last_tree_head
+                    .as_ref()
+                    .map(|LastTreeHead(head, _)| head.tree_size),
+                distinguished: None,
+            })?;
+            let result = kt.verify_search(
+                SlimSearchRequest {
+                    search_key: search_key.to_vec(),
+                    version,
+                },
+                FullSearchResponse::new(log.search(search_key, version)?, &tree_head),
+                SearchContext {
+                    last_tree_head: self.last_tree_head.as_ref(),
+                    last_distinguished_tree_head: None,
+                    data: self.data.get(search_key).cloned(),
+                },
+                true,
+                SystemTime::now(),
+            )?;
+            let update = result.state_update;
+            self.last_tree_head = Some(LastTreeHead(update.tree_head, update.tree_root));
+            if let Some(data) = update.monitoring_data {
+                self.data.insert(search_key.to_vec(), data);
+            }
+            Ok(result.value)
+        }
+
+        fn monitor(&mut self, log: &ReferenceLog) -> Result<()> {
+            let kt = KeyTransparency {
+                config: log.config().clone(),
+            };
+            let last_tree_head = self.last_tree_head.clone().expect("searched before");
+            let last_size = last_tree_head.0.tree_size;
+            let request = MonitorRequest {
+                keys: self
+                    .data
+                    .iter()
+                    .map(|(search_key, data)| MonitorKey {
+                        search_key: search_key.clone(),
+                        entry_position: data.latest_log_position(),
+                        commitment_index: vec![],
+                    })
+                    .collect(),
+                consistency: Some(Consistency {
+                    last: Some(last_size),
+                    distinguished: Some(last_size),
+                }),
+            };
+            let response = log.monitor(&request)?;
+            let update = kt.verify_monitor(
+                &request,
+                &response,
+                MonitorContext {
+                    last_tree_head: Some(&last_tree_head),
+                    last_distinguished_tree_head: &last_tree_head,
+                    data: self.data.clone(),
+                },
+                SystemTime::now(),
+            )?;
+            self.last_tree_head = Some(LastTreeHead(update.tree_head, update.tree_root));
+            self.data.extend(update.monitoring_data);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn searches_verify_across_many_insertions() {
+        for auditors in [0, 1, 2] {
+            let mut log = reference_log(auditors);
+            let mut client = Client::default();
+            for i in 0..40 {
+                log.update(&search_key(i), format!("value {i}").as_bytes())
+                    .expect("can update");
+                // Look up the new key and an older one, to exercise proofs
+                // against different parts of the log.
+                for j in [i, i / 2] {
+                    let value = client
+                        .search(&log, &search_key(j), None)
+                        .unwrap_or_else(|e| panic!("auditors = {auditors}, j = {j}: {e}"));
+                    assert_eq!(value, format!("value {j}").into_bytes());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn key_rotations_are_found_and_monitored() {
+        let mut log = reference_log(1);
+        let mut client = Client::default();
+        let key = search_key(0);
+
+        log.update(&key, b"v0").expect("can update");
+        assert_eq!(client.search(&log, &key, None).expect("valid"), b"v0");
+
+        for i in 1..20 {
+            log.update(&search_key(i), b"other").expect("can update");
+            if i % 4 == 0 {
+                log.update(&key, format!("v{}", i / 4).as_bytes())
+                    .expect("can update");
+            }
+            client.monitor(&log).expect("valid monitor response");
+        }
+
+        assert_eq!(client.search(&log, &key, None).expect("valid"), b"v4");
+        for version in 0..=4 {
+            let value = client.search(&log, &key, Some(version)).expect("valid");
+            assert_eq!(value, format!("v{version}").into_bytes());
+        }
+        assert_matches!(log.search(&key, Some(5)), Err(Error::BadData(_)));
+    }
+
+    #[test]
+    fn distinguished_verifies() {
+        let mut log = reference_log(1);
+        let kt = KeyTransparency {
+            config: log.config().clone(),
+        };
+        log.update_distinguished().expect("can update");
+        let distinguished = log.last_tree_head().expect("published");
+        for i in 0..10 {
+            log.update(&search_key(i), b"value").expect("can update");
+        }
+
+        let mut client = Client::default();
+        client
+            .search(&log, &search_key(3), None)
+            .expect("valid search");
+        let tree_head = log
+            .distinguished(None)
+            .expect("has distinguished key")
+            .tree_head
+            .expect("present");
+        kt.verify_distinguished(
+            &tree_head,
+            client.last_tree_head.as_ref(),
+            &log.last_tree_head().expect("published"),
+        )
+        .expect("same tree head");
+
+        let with_proof = log
+            .distinguished(Some(distinguished.0.tree_size))
+            .expect("has distinguished key")
+            .tree_head
+            .expect("present");
+        kt.verify_distinguished(&with_proof, client.last_tree_head.as_ref(), &distinguished)
+            .expect("consistent with older distinguished tree head");
+    }
+
+    #[test]
+    fn equivocation_is_detected() {
+        let mut log = reference_log(0);
+        for i in 0..5 {
+            log.update(&search_key(i), b"value").expect("can update");
+        }
+        let mut fork = log.clone();
+
+        log.update(&search_key(0), b"honest").expect("can update");
+        fork.update(&search_key(0), b"evil").expect("can update");
+
+        // Both forks are internally consistent...
+        let mut client = Client::default();
+        assert_eq!(
+            client.search(&log, &search_key(0), None).expect("valid"),
+            b"honest"
+        );
+        let mut other_client = Client::default();
+        assert_eq!(
+            other_client
+                .search(&fork, &search_key(0), None)
+                .expect("valid"),
+            b"evil"
+        );
+
+        // ...but a client that has seen one can't be shown the other, whether
+        // or not the log has grown since.
+        assert_matches!(
+            client.search(&fork, &search_key(0), None),
+            Err(Error::BadData(_) | Error::VerificationFailed(_))
+        );
+        fork.update(&search_key(1), b"more").expect("can update");
+        assert_matches!(
+            client.search(&fork, &search_key(1), None),
+            Err(Error::VerificationFailed(_))
+        );
+    }
+
+    #[test]
+    fn unknown_search_key_is_rejected() {
+        let mut log = reference_log(0);
+        assert_matches!(
+            log.full_tree_head(Consistency::default()),
+            Err(Error::BadData(_))
+        );
+        log.update(&search_key(0), b"value").expect("can update");
+        assert_matches!(log.search(&search_key(1), None), Err(Error::BadData(_)));
+    }
+}
@@ -43,7 +43,7 @@ const ENTRIES_MAX_BEHIND: u64 = 10_000_000;
 /// Tree math in [`crate::implicit`] and [`crate::log`] performs arithmetic
 /// like `2*(n-1)+1` that would overflow for `n > 2^63`. Bounding `tree_size`
 /// at `2^62` keeps all such arithmetic safely within `u64`.
-const MAX_TREE_SIZE: u64 = 1u64 << 62;
+pub(crate) const MAX_TREE_SIZE: u64 = 1u64 << 62;
 
 #[derive(Clone, Debug, displaydoc::Display)]
 pub enum Error {
@@ -53,6 +53,12 @@ pub enum Error {
     BadData(String),
     /// Verification failed: {0}
     VerificationFailed(String),
+    /// Verification failed: version change detected while self-monitoring
+    SelfMonitorVersionChanged,
+    /// Verification failed: version of the mapping decreased
+    VersionDecreased,
+    /// Verification failed: unexpected search value
+    UnexpectedSearchValue,
 }
 
 impl std::error::Error for Error {}
@@ -95,7 +101,7 @@ fn get_hash_proof(proof: &[Vec<u8>]) -> Result<Vec<[u8; 32]>> {
         .map_err(|_| Error::BadData("proof element is wrong size".to_string()))
 }
 
-fn marshal_update_value(value: &[u8]) -> Result<Vec<u8>> {
+pub(crate) fn marshal_update_value(value: &[u8]) -> Result<Vec<u8>> {
     let mut buf = vec![];
 
     let length = u32::try_from(value.len())
@@ -107,7 +113,7 @@ fn marshal_update_value(value: &[u8]) -> Result<Vec<u8>> {
 }
 
 /// Returns the hash of the leaf of the transparency tree.
-fn leaf_hash(prefix_root: &[u8; 32], commitment: &[u8; 32]) -> [u8; 32] {
+pub(crate) fn leaf_hash(prefix_root: &[u8; 32], commitment: &[u8; 32]) -> [u8; 32] {
     let mut hasher = Sha256::new();
     hasher.update(prefix_root);
     hasher.update(commitment);
@@ -116,7 +122,7 @@ fn leaf_hash(prefix_root: &[u8; 32], commitment: &[u8; 32]) -> [u8; 32] {
 }
 
 /// Checks the signature on the provided transparency tree head using the given key
-fn verify_tree_head_signature(
+pub(crate) fn verify_tree_head_signature(
     config: &PublicConfig,
     head: &impl VerifiableTreeHead,
     root: &[u8; 32],
@@ -133,7 +139,7 @@ fn verify_tree_head_signature(
 
 /// Checks that a FullTreeHead structure is valid. It stores the tree head for
 /// later requests if it succeeds.
-fn verify_full_tree_head(
+pub(crate) fn verify_full_tree_head(
     config: &PublicConfig,
     fth: &FullTreeHead,
     root: [u8; 32],
@@ -155,6 +155,76 @@ impl PublicKey {
     }
 }
 
+/// SecretKey holds a VRF secret key, and is only used to produce proofs in
+/// tests.
+#[cfg(any(test, feature = "test-util"))]
+#[derive(Clone)]
+pub struct SecretKey {
+    scalar: Scalar,
+    nonce_prefix: [u8; 32],
+    public_key: PublicKey,
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl SecretKey {
+    /// Expands a 32-byte seed into a secret key, the same way as Ed25519
+    /// (RFC 8032, section 5.1.5).
+    pub fn from_seed(seed: &[u8; 32]) -> Self {
+        let hash = Sha512::digest(seed);
+        let (scalar_bytes, nonce_prefix) = hash.split_at(32);
+        let scalar = Scalar::from_bytes_mod_order(curve25519_dalek::scalar::clamp_integer(
+            scalar_bytes.try_into().expect("hash has enough bytes"),
+        ));
+        let point = EdwardsPoint::mul_base(&scalar);
+        Self {
+            scalar,
+            nonce_prefix: nonce_prefix.try_into().expect("hash has enough bytes"),
+            public_key: PublicKey {
+                compressed: point.compress().0,
+                decompressed: point,
+            },
+        }
+    }
+
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    /// Produces the VRF proof for message m, along with the index it proves.
+    pub fn prove(&self, m: &[u8]) -> ([u8; 80], [u8; 32]) {
+        let h = encode_to_curve_try_and_increment(&self.public_key.compressed, m);
+        let h_bytes = h.compress().0;
+        let gamma = h * self.scalar;
+        let gamma_bytes = gamma.compress().0;
+
+        // Nonce generation as in RFC 9381, section 5.4.2.2.
+        let k_hash = Sha512::new()
+            .chain_update(self.nonce_prefix)
+            .chain_update(h_bytes)
+            .finalize();
+        let k = Scalar::from_bytes_mod_order_wide(
+            k_hash.as_slice().try_into().expect("hash has enough bytes"),
+        );
+
+        let c_lower_bytes = generate_challenge([
+            &self.public_key.compressed,
+            &h_bytes,
+            &gamma_bytes,
+            &EdwardsPoint::mul_base(&k).compress().0,
+            &(h * k).compress().0,
+        ]);
+        let mut c_bytes = [0u8; 32];
+        c_bytes[..16].copy_from_slice(&c_lower_bytes);
+        let s = k + Scalar::from_bytes_mod_order(c_bytes) * self.scalar;
+
+        let mut proof = [0u8; 80];
+        proof[..32].copy_from_slice(&gamma_bytes);
+        proof[32..48].copy_from_slice(&c_lower_bytes);
+        proof[48..].copy_from_slice(s.as_bytes());
+        (proof, proof_to_hash(&gamma))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use const_str::hex;
@@ -163,6 +233,7 @@ mod tests {
     use super::*;
 
     struct TestVector {
+        sk: [u8; 32],
         pk: [u8; 32],
         alpha: &'static [u8],
         h: [u8; 32],
@@ -172,6 +243,7 @@ mod tests {
 
     const TEST_VECTORS: [TestVector; 3] = [
         TestVector {
+            sk: hex!("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60"),
             pk: hex!("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"),
             alpha: &hex!(""),
             h: hex!("91bbed02a99461df1ad4c6564a5f5d829d0b90cfc7903e7a5797bd658abf3318"),
@@ -181,6 +253,7 @@ mod tests {
             beta: hex!("90cf1df3b703cce59e2a35b925d411164068269d7b2d29f3301c03dd757876ff"),
         },
         TestVector {
+            sk: hex!("4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb"),
             pk: hex!("3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c"),
             alpha: &hex!("72"),
             h: hex!("5b659fc3d4e9263fd9a4ed1d022d75eaacc20df5e09f9ea937502396598dc551"),
@@ -190,6 +263,7 @@ mod tests {
             beta: hex!("eb4440665d3891d668e7e0fcaf587f1b4bd7fbfe99d0eb2211ccec90496310eb"),
         },
         TestVector {
+            sk: hex!("c5aa8df43f9f837bedb7442f31dcb7b166d38535076f094b85ce3a2e0b4458f7"),
             pk: hex!("fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025"),
             alpha: &hex!("af82"),
             h: hex!("bf4339376f5542811de615e3313d2b36f6f53c0acfebb482159711201192576a"),
@@ -226,6 +300,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_prove() {
+        for v in TEST_VECTORS {
+            let sk = SecretKey::from_seed(&v.sk);
+            assert_eq!(*sk.public_key().as_bytes(), v.pk);
+
+            let (proof, index) = sk.prove(v.alpha);
+            assert_eq!(proof, v.pi);
+            assert_eq!(index, v.beta);
+        }
+    }
+
     #[test]
     fn test_proof_to_hash_fails() {
         for v in TEST_VECTORS {
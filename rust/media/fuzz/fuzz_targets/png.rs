@@ -0,0 +1,27 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+#![no_main]
+
+use futures::executor::block_on;
+use futures::io::Cursor;
+use libfuzzer_sys::fuzz_target;
+use signal_media::sanitize::png;
+use signal_media::sanitize::png::ImagePart;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(sanitized) = block_on(png::sanitize(Cursor::new(data))) else {
+        return;
+    };
+    let mut end = 0;
+    for part in &sanitized.data {
+        let ImagePart::Input(span) = part else {
+            continue;
+        };
+        assert!(span.offset >= end, "spans out of order");
+        end = span.offset + span.len;
+    }
+    assert!(end <= data.len() as u64, "span past end of input");
+});
@@ -4,9 +4,19 @@
 //
 
 mod error;
+#[cfg(any(feature = "mp4san", feature = "webpsan"))]
+mod observe;
+#[cfg(any(feature = "gifsan", feature = "jpegsan", feature = "pngsan"))]
+mod reader;
 
+#[cfg(feature = "gifsan")]
+pub mod gif;
+#[cfg(feature = "jpegsan")]
+pub mod jpeg;
 #[cfg(feature = "mp4san")]
 pub mod mp4;
+#[cfg(feature = "pngsan")]
+pub mod png;
 #[cfg(feature = "webpsan")]
 pub mod webp;
 
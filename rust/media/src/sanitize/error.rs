@@ -37,3 +37,25 @@ impl<E: Clone + ReportableError> From<mediasan_common::Error<E>> for SanitizerEr
         }
     }
 }
+
+#[cfg(any(feature = "gifsan", feature = "jpegsan", feature = "pngsan"))]
+impl<E: std::fmt::Display> SanitizerError<E> {
+    /// Creates a [`SanitizerError::Parse`] from an error found by one of this crate's own parsers.
+    pub(crate) fn parse(kind: E, context: impl std::fmt::Display) -> Self {
+        let report = format!("{kind}\n - {context}");
+        Self::Parse(ParseErrorReport { kind, report })
+    }
+
+    /// Converts an IO error, treating a premature end of input as `truncated`.
+    pub(crate) fn io_or_truncated(
+        err: io::Error,
+        truncated: E,
+        context: impl std::fmt::Display,
+    ) -> Self {
+        if err.kind() == io::ErrorKind::UnexpectedEof {
+            Self::parse(truncated, context)
+        } else {
+            Self::Io(err)
+        }
+    }
+}
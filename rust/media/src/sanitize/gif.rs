@@ -0,0 +1,499 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! GIF sanitization, including animated GIFs.
+//!
+//! The sanitizer checks the block structure of a GIF image and produces a copy of it containing
+//! only the header, color tables, images, graphic control extensions (which carry frame delays
+//! and transparency), and the application extension that sets how many times an animation loops
+//! (`NETSCAPE2.0`, or its alias `ANIMEXTS1.0`). Other application extensions (such as XMP),
+//! comment extensions, plain text extensions, and anything after the trailer are dropped.
+//!
+//! Because animated GIFs can be very expensive to decode, the number of frames and the size of
+//! the canvas and frames are limited by a [`Config`]. The image data itself is not decompressed.
+
+use futures_util::AsyncRead;
+use mediasan_common::AsyncSkip;
+
+use super::reader::Reader;
+pub use super::reader::{ImagePart, SanitizedImage};
+
+/// Error type returned by [`sanitize`].
+pub type Error = super::error::SanitizerError<ParseError>;
+
+/// A decomposed and stringified parse error, as returned in [`Error::Parse`].
+pub type ParseErrorReport = super::error::ParseErrorReport<ParseError>;
+
+/// Error parsing a GIF image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ParseError {
+    /// The input does not start with a GIF header.
+    #[error("invalid input")]
+    InvalidInput,
+
+    /// A block is malformed, or appears where it isn't allowed.
+    #[error("invalid block layout")]
+    InvalidBlockLayout,
+
+    /// The image has no frames.
+    #[error("missing required block: image descriptor")]
+    MissingImage,
+
+    /// The input ended in the middle of the image.
+    #[error("truncated block")]
+    TruncatedBlock,
+
+    /// The canvas is larger than [`Config::max_canvas_pixels`].
+    #[error("canvas too large")]
+    CanvasTooLarge,
+
+    /// There are more frames than [`Config::max_frames`].
+    #[error("too many frames")]
+    TooManyFrames,
+
+    /// The frames add up to more than [`Config::max_total_pixels`].
+    #[error("too many pixels in total")]
+    TooManyPixels,
+}
+
+/// Limits on the resources needed to decode a sanitized GIF.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Config {
+    /// The maximum number of frames.
+    pub max_frames: u32,
+    /// The maximum width × height of the logical screen.
+    pub max_canvas_pixels: u64,
+    /// The maximum width × height of all frames, added together.
+    pub max_total_pixels: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_frames: 2000,
+            max_canvas_pixels: 4096 * 4096,
+            max_total_pixels: 1 << 28,
+        }
+    }
+}
+
+/// Sanitize a GIF input with the default [`Config`].
+///
+/// The input must implement [`AsyncRead`] + [`AsyncSkip`], where `AsyncSkip` represents the
+/// ability to skip forward, but not necessarily seek to arbitrary positions.
+///
+/// # Errors
+///
+/// If the input cannot be parsed, exceeds the default limits, or an IO error occurs, an `Error`
+/// is returned.
+pub async fn sanitize<R: AsyncRead + AsyncSkip>(input: R) -> Result<SanitizedImage, Error> {
+    sanitize_with_config(input, Config::default()).await
+}
+
+/// Sanitize a GIF input, enforcing the limits in `config`.
+///
+/// See [`sanitize`].
+pub async fn sanitize_with_config<R: AsyncRead + AsyncSkip>(
+    input: R,
+    config: Config,
+) -> Result<SanitizedImage, Error> {
+    let input = std::pin::pin!(input);
+    let mut reader = Reader::new(input).await.map_err(Error::Io)?;
+    let mut output = SanitizedImage::default();
+
+    let start = reader.position();
+    let header: [u8; 13] = reader
+        .read_array()
+        .await
+        .map_err(|e| Error::io_or_truncated(e, ParseError::InvalidInput, "reading header"))?;
+    let [
+        signature @ ..,
+        width_lo,
+        width_hi,
+        height_lo,
+        height_hi,
+        flags,
+        _background_color,
+        _aspect_ratio,
+    ] = header;
+    if !matches!(&signature, b"GIF87a" | b"GIF89a") {
+        return Err(Error::parse(ParseError::InvalidInput, "bad signature"));
+    }
+    let canvas_width = u16::from_le_bytes([width_lo, width_hi]);
+    let canvas_height = u16::from_le_bytes([height_lo, height_hi]);
+    if canvas_width == 0 || canvas_height == 0 {
+        return Err(Error::parse(
+            ParseError::InvalidBlockLayout,
+            format!("empty canvas ({canvas_width}x{canvas_height})"),
+        ));
+    }
+    if u64::from(canvas_width) * u64::from(canvas_height) > config.max_canvas_pixels {
+        return Err(Error::parse(
+            ParseError::CanvasTooLarge,
+            format!("canvas is {canvas_width}x{canvas_height}"),
+        ));
+    }
+    let global_color_table_len = color_table_len(flags);
+    reader.skip(global_color_table_len).await.map_err(|e| {
+        Error::io_or_truncated(e, ParseError::TruncatedBlock, "reading global color table")
+    })?;
+    output.keep(start, reader.position() - start);
+
+    let mut frames = 0u32;
+    let mut total_pixels = 0u64;
+    // A graphic control extension applies to the next graphic rendering block, so it isn't kept
+    // until we know what that block is.
+    let mut pending_graphic_control = None;
+    let mut seen_loop_count = false;
+    loop {
+        let offset = reader.position();
+        let context = |action: &str| format!("{action} block at offset {offset}");
+        let truncated = |action: &'static str| {
+            move |e| Error::io_or_truncated(e, ParseError::TruncatedBlock, context(action))
+        };
+        let invalid = |action: &str| Error::parse(ParseError::InvalidBlockLayout, context(action));
+
+        match reader.read_u8().await.map_err(truncated("reading"))? {
+            TRAILER => {
+                if frames == 0 {
+                    return Err(Error::parse(ParseError::MissingImage, context("trailer")));
+                }
+                if pending_graphic_control.is_some() {
+                    return Err(invalid("graphic control extension before trailer"));
+                }
+                output.keep(offset, 1);
+                break;
+            }
+            EXTENSION_INTRODUCER => {
+                let label = reader.read_u8().await.map_err(truncated("reading"))?;
+                match label {
+                    GRAPHIC_CONTROL_LABEL => {
+                        let [
+                            block_size,
+                            _flags,
+                            _delay_lo,
+                            _delay_hi,
+                            _transparent,
+                            terminator,
+                        ] = reader.read_array().await.map_err(truncated("reading"))?;
+                        if block_size != 4 || terminator != 0 {
+                            return Err(invalid("invalid graphic control extension"));
+                        }
+                        if pending_graphic_control.is_some() {
+                            return Err(invalid("repeated graphic control extension"));
+                        }
+                        pending_graphic_control = Some(offset);
+                    }
+                    PLAIN_TEXT_LABEL => {
+                        // Plain text is a graphic rendering block, so any pending graphic control
+                        // extension applies to it and is dropped along with it.
+                        pending_graphic_control = None;
+                        skip_sub_blocks(&mut reader)
+                            .await
+                            .map_err(truncated("skipping"))?;
+                    }
+                    APPLICATION_LABEL => {
+                        let is_loop_count = read_application_extension(&mut reader)
+                            .await
+                            .map_err(truncated("reading"))?;
+                        // Only the first loop count is used. Keeping one that comes between a
+                        // graphic control extension and its image would put the output out of
+                        // order, but that's not where encoders put it anyway.
+                        if is_loop_count && !seen_loop_count && pending_graphic_control.is_none() {
+                            seen_loop_count = true;
+                            output.keep(offset, reader.position() - offset);
+                        }
+                    }
+                    _ => {
+                        // Comment and unknown extensions.
+                        skip_sub_blocks(&mut reader)
+                            .await
+                            .map_err(truncated("skipping"))?;
+                    }
+                }
+            }
+            IMAGE_SEPARATOR => {
+                let [
+                    left_lo,
+                    left_hi,
+                    top_lo,
+                    top_hi,
+                    width_lo,
+                    width_hi,
+                    height_lo,
+                    height_hi,
+                    flags,
+                ] = reader.read_array().await.map_err(truncated("reading"))?;
+                let left = u16::from_le_bytes([left_lo, left_hi]);
+                let top = u16::from_le_bytes([top_lo, top_hi]);
+                let width = u16::from_le_bytes([width_lo, width_hi]);
+                let height = u16::from_le_bytes([height_lo, height_hi]);
+                if width == 0
+                    || height == 0
+                    || u32::from(left) + u32::from(width) > u32::from(canvas_width)
+                    || u32::from(top) + u32::from(height) > u32::from(canvas_height)
+                {
+                    return Err(invalid("image outside canvas in"));
+                }
+
+                frames += 1;
+                if frames > config.max_frames {
+                    return Err(Error::parse(ParseError::TooManyFrames, context("image")));
+                }
+                total_pixels = total_pixels.saturating_add(u64::from(width) * u64::from(height));
+                if total_pixels > config.max_total_pixels {
+                    return Err(Error::parse(ParseError::TooManyPixels, context("image")));
+                }
+
+                reader
+                    .skip(color_table_len(flags))
+                    .await
+                    .map_err(truncated("skipping local color table in"))?;
+                let min_code_size = reader.read_u8().await.map_err(truncated("reading"))?;
+                if !(2..=8).contains(&min_code_size) {
+                    return Err(invalid("invalid LZW code size in"));
+                }
+                skip_sub_blocks(&mut reader)
+                    .await
+                    .map_err(truncated("skipping"))?;
+
+                if let Some(graphic_control_offset) = pending_graphic_control.take() {
+                    output.keep(graphic_control_offset, GRAPHIC_CONTROL_LEN);
+                }
+                output.keep(offset, reader.position() - offset);
+            }
+            _ => return Err(invalid("unknown")),
+        }
+    }
+
+    Ok(output)
+}
+
+const EXTENSION_INTRODUCER: u8 = 0x21;
+const IMAGE_SEPARATOR: u8 = 0x2C;
+const TRAILER: u8 = 0x3B;
+
+const PLAIN_TEXT_LABEL: u8 = 0x01;
+const GRAPHIC_CONTROL_LABEL: u8 = 0xF9;
+const APPLICATION_LABEL: u8 = 0xFF;
+
+/// Application identifiers (including the authentication code) for the loop count extension.
+const LOOP_COUNT_IDENTIFIERS: [&[u8; 11]; 2] = [b"NETSCAPE2.0", b"ANIMEXTS1.0"];
+/// The ID of the loop count sub-block, which is followed by a 16-bit count.
+const LOOP_COUNT_SUB_BLOCK_ID: u8 = 0x01;
+
+/// The introducer, label, one fixed-size sub-block, and terminator.
+const GRAPHIC_CONTROL_LEN: u64 = 8;
+
+/// The size of the color table described by a logical screen descriptor or image descriptor's
+/// `flags`.
+fn color_table_len(flags: u8) -> u64 {
+    const HAS_COLOR_TABLE: u8 = 0x80;
+    const SIZE_MASK: u8 = 0x07;
+    if flags & HAS_COLOR_TABLE == 0 {
+        return 0;
+    }
+    3 << ((flags & SIZE_MASK) + 1)
+}
+
+/// Reads an application extension following its label, up to and including the empty block that
+/// ends it.
+///
+/// Returns whether it is a well-formed loop count extension: one with a loop count identifier
+/// and a single three-byte loop count sub-block.
+async fn read_application_extension<R: AsyncRead + AsyncSkip + ?Sized>(
+    reader: &mut Reader<'_, R>,
+) -> std::io::Result<bool> {
+    let identifier_len = reader.read_u8().await?;
+    if usize::from(identifier_len) != LOOP_COUNT_IDENTIFIERS[0].len() {
+        reader.skip(identifier_len.into()).await?;
+        skip_sub_blocks(reader).await?;
+        return Ok(false);
+    }
+    let identifier = reader.read_array().await?;
+    let mut is_loop_count = LOOP_COUNT_IDENTIFIERS.contains(&&identifier);
+
+    let mut sub_blocks = 0;
+    loop {
+        let len = reader.read_u8().await?;
+        if len == 0 {
+            return Ok(is_loop_count && sub_blocks == 1);
+        }
+        sub_blocks += 1;
+        if is_loop_count && sub_blocks == 1 && len == 3 {
+            let [id, _count_lo, _count_hi] = reader.read_array().await?;
+            is_loop_count = id == LOOP_COUNT_SUB_BLOCK_ID;
+        } else {
+            is_loop_count = false;
+            reader.skip(len.into()).await?;
+        }
+    }
+}
+
+/// Skips a sequence of data sub-blocks, up to and including the empty block that ends it.
+async fn skip_sub_blocks<R: AsyncRead + AsyncSkip + ?Sized>(
+    reader: &mut Reader<'_, R>,
+) -> std::io::Result<()> {
+    loop {
+        let len = reader.read_u8().await?;
+        if len == 0 {
+            return Ok(());
+        }
+        reader.skip(len.into()).await?;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::executor::block_on;
+    use futures::io::Cursor;
+    use test_case::test_case;
+
+    use super::*;
+
+    /// A 2x2 canvas with a two-color global color table.
+    const HEADER: &[u8] = b"GIF89a\x02\0\x02\0\x80\0\0\0\0\0\xff\xff\xff";
+    const GRAPHIC_CONTROL: &[u8] = b"\x21\xf9\x04\x04\x0a\0\0\0";
+    const IMAGE: &[u8] = b"\x2c\0\0\0\0\x02\0\x02\0\0\x02\x02\x44\x01\0";
+    const LOOP: &[u8] = b"\x21\xff\x0bNETSCAPE2.0\x03\x01\0\0\0";
+    const COMMENT: &[u8] = b"\x21\xfe\x05hello\x05world\0";
+    const TRAILER_BLOCK: &[u8] = b"\x3b";
+
+    fn sanitized_bytes(input: &[u8], config: Config) -> Result<Vec<u8>, ParseError> {
+        let result = block_on(sanitize_with_config(Cursor::new(input), config));
+        let sanitized = match result {
+            Ok(sanitized) => sanitized,
+            Err(Error::Parse(report)) => return Err(report.kind),
+            Err(Error::Io(e)) => panic!("unexpected IO error: {e}"),
+        };
+        Ok(sanitized.apply_to(input))
+    }
+
+    #[test]
+    fn still_image_is_unchanged() {
+        let image = [HEADER, IMAGE, TRAILER_BLOCK].concat();
+        assert_eq!(
+            sanitized_bytes(&image, Config::default()).expect("valid"),
+            image
+        );
+    }
+
+    #[test]
+    fn strips_extensions() {
+        let image = [
+            HEADER,
+            LOOP,
+            COMMENT,
+            GRAPHIC_CONTROL,
+            IMAGE,
+            GRAPHIC_CONTROL,
+            COMMENT,
+            IMAGE,
+            TRAILER_BLOCK,
+            b"trailing data",
+        ]
+        .concat();
+        let expected = [
+            HEADER,
+            LOOP,
+            GRAPHIC_CONTROL,
+            IMAGE,
+            GRAPHIC_CONTROL,
+            IMAGE,
+            TRAILER_BLOCK,
+        ]
+        .concat();
+        assert_eq!(
+            sanitized_bytes(&image, Config::default()).expect("valid"),
+            expected
+        );
+    }
+
+    /// Finds the loop count in a sanitized GIF, assuming it comes right after [`HEADER`].
+    fn loop_count(sanitized: &[u8]) -> Option<u16> {
+        let extension = sanitized
+            .strip_prefix(HEADER)?
+            .strip_prefix(b"\x21\xff\x0b")?;
+        let (identifier, rest) = extension.split_first_chunk::<11>()?;
+        assert!(LOOP_COUNT_IDENTIFIERS.contains(&identifier));
+        let &[3, LOOP_COUNT_SUB_BLOCK_ID, lo, hi, 0, ..] = rest else {
+            panic!("malformed loop count extension: {rest:02x?}");
+        };
+        Some(u16::from_le_bytes([lo, hi]))
+    }
+
+    #[test_case(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x05\0\0" => Some(5); "netscape")]
+    #[test_case(b"\x21\xff\x0bANIMEXTS1.0\x03\x01\0\x01\0" => Some(256); "animexts")]
+    #[test_case(b"\x21\xff\x0bNETSCAPE2.0\x05\x02\0\0\x01\0\0" => None; "buffering sub-block")]
+    #[test_case(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x05\0\x03\x01\x05\0\0" => None; "extra sub-block")]
+    #[test_case(b"\x21\xff\x0bNETSCAPE2.0\x04\x01\x05\0\0\0" => None; "long sub-block")]
+    #[test_case(b"\x21\xff\x0bNETSCAPE2.0\0" => None; "no sub-blocks")]
+    #[test_case(b"\x21\xff\x0aNETSCAPE2.\x03\x01\x05\0\0" => None; "short identifier")]
+    #[test_case(b"\x21\xff\x0bXMP DataXMP\x03\x01\x05\0\0" => None; "other application")]
+    fn loop_count_survives(extension: &[u8]) -> Option<u16> {
+        let image = [HEADER, extension, IMAGE, IMAGE, TRAILER_BLOCK].concat();
+        let sanitized = sanitized_bytes(&image, Config::default()).expect("valid");
+        let count = loop_count(&sanitized);
+        let expected = match count {
+            Some(_) => image,
+            None => [HEADER, IMAGE, IMAGE, TRAILER_BLOCK].concat(),
+        };
+        assert_eq!(sanitized, expected);
+        count
+    }
+
+    #[test]
+    fn keeps_only_first_loop_count() {
+        let second_loop = b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x07\0\0";
+        let image = [HEADER, LOOP, IMAGE, second_loop, IMAGE, TRAILER_BLOCK].concat();
+        let sanitized = sanitized_bytes(&image, Config::default()).expect("valid");
+        assert_eq!(
+            sanitized,
+            [HEADER, LOOP, IMAGE, IMAGE, TRAILER_BLOCK].concat()
+        );
+        assert_eq!(loop_count(&sanitized), Some(0));
+    }
+
+    #[test]
+    fn strips_plain_text_with_its_graphic_control() {
+        let plain_text = b"\x21\x01\x0c\0\0\0\0\x02\0\x02\0\x01\x01\0\0\x02hi\0";
+        let image = [HEADER, GRAPHIC_CONTROL, plain_text, IMAGE, TRAILER_BLOCK].concat();
+        let expected = [HEADER, IMAGE, TRAILER_BLOCK].concat();
+        assert_eq!(
+            sanitized_bytes(&image, Config::default()).expect("valid"),
+            expected
+        );
+    }
+
+    #[test_case(Config { max_frames: 2, ..Default::default() } => Ok(()); "frames at limit")]
+    #[test_case(Config { max_frames: 1, ..Default::default() } => Err(ParseError::TooManyFrames); "too many frames")]
+    #[test_case(Config { max_canvas_pixels: 4, ..Default::default() } => Ok(()); "canvas at limit")]
+    #[test_case(Config { max_canvas_pixels: 3, ..Default::default() } => Err(ParseError::CanvasTooLarge); "canvas too large")]
+    #[test_case(Config { max_total_pixels: 8, ..Default::default() } => Ok(()); "pixels at limit")]
+    #[test_case(Config { max_total_pixels: 7, ..Default::default() } => Err(ParseError::TooManyPixels); "too many pixels")]
+    fn limits(config: Config) -> Result<(), ParseError> {
+        let image = [HEADER, IMAGE, IMAGE, TRAILER_BLOCK].concat();
+        sanitized_bytes(&image, config).map(|_| ())
+    }
+
+    #[test_case(b"\x89PNG\r\n\x1a\n\0\0\0\0\0" => ParseError::InvalidInput; "not a GIF")]
+    #[test_case(b"GIF89a" => ParseError::InvalidInput; "truncated header")]
+    #[test_case(&HEADER[..15] => ParseError::TruncatedBlock; "truncated color table")]
+    #[test_case(&[HEADER, IMAGE].concat() => ParseError::TruncatedBlock; "missing trailer")]
+    #[test_case(&[HEADER, &IMAGE[..12]].concat() => ParseError::TruncatedBlock; "truncated image")]
+    #[test_case(&[HEADER, TRAILER_BLOCK].concat() => ParseError::MissingImage; "no images")]
+    #[test_case(&[HEADER, LOOP, TRAILER_BLOCK].concat() => ParseError::MissingImage; "only extensions")]
+    #[test_case(&[HEADER, &LOOP[..16]].concat() => ParseError::TruncatedBlock; "truncated loop count")]
+    #[test_case(&[HEADER, b"\x00"].concat() => ParseError::InvalidBlockLayout; "unknown block")]
+    #[test_case(&[&b"GIF89a\0\0\x02\0\0\0\0"[..], IMAGE].concat() => ParseError::InvalidBlockLayout; "empty canvas")]
+    #[test_case(&[HEADER, b"\x2c\x01\0\0\0\x02\0\x02\0\0\x02\x02\x44\x01\0"].concat() => ParseError::InvalidBlockLayout; "image outside canvas")]
+    #[test_case(&[HEADER, b"\x2c\0\0\0\0\x02\0\x02\0\0\x0c\x02\x44\x01\0"].concat() => ParseError::InvalidBlockLayout; "bad LZW code size")]
+    #[test_case(&[HEADER, b"\x21\xf9\x05\0\0\0\0\0\0", IMAGE].concat() => ParseError::InvalidBlockLayout; "bad graphic control size")]
+    #[test_case(&[HEADER, GRAPHIC_CONTROL, GRAPHIC_CONTROL, IMAGE].concat() => ParseError::InvalidBlockLayout; "repeated graphic control")]
+    #[test_case(&[HEADER, IMAGE, GRAPHIC_CONTROL, TRAILER_BLOCK].concat() => ParseError::InvalidBlockLayout; "dangling graphic control")]
+    fn invalid(input: &[u8]) -> ParseError {
+        sanitized_bytes(input, Config::default()).expect_err("should be rejected")
+    }
+}
@@ -0,0 +1,723 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! JPEG sanitization.
+//!
+//! The sanitizer checks the segment structure of a JPEG image and produces a copy of it without
+//! metadata. Kept are the segments needed to decode the image (frame and scan headers, tables,
+//! and entropy-coded data), the JFIF header, ICC profiles, and the Adobe header (which affects
+//! color decoding). Everything else is dropped, including:
+//!
+//! - EXIF (camera details, GPS coordinates, thumbnails) and XMP,
+//! - other application-specific segments,
+//! - comments,
+//! - anything after the end-of-image marker.
+//!
+//! Two segments are rewritten rather than copied: a JFIF header with an embedded thumbnail is
+//! replaced by one without, and the EXIF orientation, if any, is kept in a minimal EXIF segment
+//! of its own, so that the image is still displayed the right way up.
+//!
+//! The image data itself is not decoded.
+
+use std::fmt;
+
+use futures_util::AsyncRead;
+use mediasan_common::AsyncSkip;
+
+use super::reader::Reader;
+pub use super::reader::{ImagePart, SanitizedImage};
+
+/// Error type returned by [`sanitize`].
+pub type Error = super::error::SanitizerError<ParseError>;
+
+/// A decomposed and stringified parse error, as returned in [`Error::Parse`].
+pub type ParseErrorReport = super::error::ParseErrorReport<ParseError>;
+
+/// Error parsing a JPEG image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ParseError {
+    /// The input does not start with a JPEG start-of-image marker.
+    #[error("invalid input")]
+    InvalidInput,
+
+    /// A segment is malformed, or appears where it isn't allowed.
+    #[error("invalid segment layout")]
+    InvalidSegmentLayout,
+
+    /// A segment required to decode the image never appeared.
+    #[error("missing required segment: {0}")]
+    MissingRequiredSegment(&'static str),
+
+    /// The input ended in the middle of the image.
+    #[error("truncated segment")]
+    TruncatedSegment,
+
+    /// The image uses a JPEG feature that is not supported, such as hierarchical coding.
+    #[error("unsupported segment: 0xFF{0:02X}")]
+    UnsupportedSegment(u8),
+}
+
+/// Sanitize a JPEG input.
+///
+/// The input must implement [`AsyncRead`] + [`AsyncSkip`], where `AsyncSkip` represents the
+/// ability to skip forward, but not necessarily seek to arbitrary positions. Segments that are
+/// going to be dropped are skipped over rather than read.
+///
+/// # Errors
+///
+/// If the input cannot be parsed, or an IO error occurs, an `Error` is returned.
+pub async fn sanitize<R: AsyncRead + AsyncSkip>(input: R) -> Result<SanitizedImage, Error> {
+    let input = std::pin::pin!(input);
+    let mut reader = Reader::new(input).await.map_err(Error::Io)?;
+    let mut output = SanitizedImage::default();
+
+    let start = reader.position();
+    let soi = reader
+        .read_array()
+        .await
+        .map_err(|e| Error::io_or_truncated(e, ParseError::InvalidInput, "reading SOI"))?;
+    if soi != [0xFF, SOI] {
+        return Err(Error::parse(
+            ParseError::InvalidInput,
+            "expected SOI marker",
+        ));
+    }
+    output.keep(start, 2);
+
+    let mut seen_frame = false;
+    let mut seen_scan = false;
+    let mut seen_exif = false;
+    let mut next_marker = None;
+    loop {
+        let (offset, code) = match next_marker.take() {
+            Some(marker) => marker,
+            None => read_marker(&mut reader).await?,
+        };
+        let marker = Marker(code);
+        let context = |action: &str| format!("{action} {marker} at offset {offset}");
+
+        match code {
+            EOI => {
+                if !seen_scan {
+                    return Err(Error::parse(
+                        ParseError::MissingRequiredSegment("SOS"),
+                        context("found"),
+                    ));
+                }
+                output.keep(offset, 2);
+                break;
+            }
+            SOI | TEM | RST0..=RST7 => {
+                return Err(Error::parse(
+                    ParseError::InvalidSegmentLayout,
+                    context("unexpected"),
+                ));
+            }
+            DHP | EXP | JPG | JPG0..=JPG13 | 0x02..=0xBF => {
+                return Err(Error::parse(
+                    ParseError::UnsupportedSegment(code),
+                    context("found"),
+                ));
+            }
+            _ => {}
+        }
+
+        let length = u16::from_be_bytes(reader.read_array().await.map_err(|e| {
+            Error::io_or_truncated(e, ParseError::TruncatedSegment, context("reading"))
+        })?);
+        let Some(payload_len) = length.checked_sub(2) else {
+            return Err(Error::parse(
+                ParseError::InvalidSegmentLayout,
+                context("invalid length for"),
+            ));
+        };
+        let segment_len = 2 + u64::from(length);
+
+        if !is_kept(code) {
+            reader.skip(payload_len.into()).await.map_err(|e| {
+                Error::io_or_truncated(e, ParseError::TruncatedSegment, context("skipping"))
+            })?;
+            continue;
+        }
+
+        let mut payload = vec![0; payload_len.into()];
+        reader.read_exact(&mut payload).await.map_err(|e| {
+            Error::io_or_truncated(e, ParseError::TruncatedSegment, context("reading"))
+        })?;
+
+        match code {
+            APP0 if !payload.starts_with(JFIF_IDENTIFIER) => continue,
+            APP0 => {
+                if payload.len() < JFIF_HEADER_LEN {
+                    return Err(Error::parse(
+                        ParseError::InvalidSegmentLayout,
+                        context("invalid"),
+                    ));
+                }
+                let (header, thumbnail) = payload.split_at(JFIF_HEADER_LEN);
+                if !thumbnail.is_empty() || header[JFIF_HEADER_LEN - 2..] != [0, 0] {
+                    let mut header = header.to_vec();
+                    header[JFIF_HEADER_LEN - 2..].fill(0);
+                    output.rewrite(segment(APP0, &header));
+                    continue;
+                }
+            }
+            APP1 => {
+                // Only the first EXIF segment is used by decoders.
+                if let Some(tiff) = payload.strip_prefix(EXIF_IDENTIFIER)
+                    && !seen_exif
+                {
+                    seen_exif = true;
+                    if let Some(orientation) = exif_orientation(tiff)
+                        && orientation != DEFAULT_ORIENTATION
+                    {
+                        output.rewrite(exif_orientation_segment(orientation));
+                    }
+                }
+                continue;
+            }
+            APP2 if !payload.starts_with(ICC_PROFILE_IDENTIFIER) => continue,
+            APP14 if !payload.starts_with(b"Adobe") => continue,
+            SOF0..=SOF3 | SOF5..=SOF7 | SOF9..=SOF11 | SOF13..=SOF15 => {
+                if seen_frame || !is_valid_frame_header(&payload) {
+                    return Err(Error::parse(
+                        ParseError::InvalidSegmentLayout,
+                        context("invalid"),
+                    ));
+                }
+                seen_frame = true;
+            }
+            SOS => {
+                if !seen_frame {
+                    return Err(Error::parse(
+                        ParseError::MissingRequiredSegment("SOF"),
+                        context("found"),
+                    ));
+                }
+                if !is_valid_scan_header(&payload) {
+                    return Err(Error::parse(
+                        ParseError::InvalidSegmentLayout,
+                        context("invalid"),
+                    ));
+                }
+                seen_scan = true;
+                output.keep(offset, segment_len);
+
+                let data_start = reader.position();
+                let (end_offset, end_code) = find_end_of_scan(&mut reader).await.map_err(|e| {
+                    Error::io_or_truncated(
+                        e,
+                        ParseError::TruncatedSegment,
+                        context("scanning data after"),
+                    )
+                })?;
+                output.keep(data_start, end_offset - data_start);
+                next_marker = Some((end_offset, end_code));
+                continue;
+            }
+            DRI if payload.len() != 2 => {
+                return Err(Error::parse(
+                    ParseError::InvalidSegmentLayout,
+                    context("invalid"),
+                ));
+            }
+            DHT | DQT | DAC if payload.is_empty() => {
+                return Err(Error::parse(
+                    ParseError::InvalidSegmentLayout,
+                    context("empty"),
+                ));
+            }
+            _ => {}
+        }
+        output.keep(offset, segment_len);
+    }
+
+    Ok(output)
+}
+
+const TEM: u8 = 0x01;
+const SOF0: u8 = 0xC0;
+const SOF3: u8 = 0xC3;
+const DHT: u8 = 0xC4;
+const SOF5: u8 = 0xC5;
+const SOF7: u8 = 0xC7;
+const JPG: u8 = 0xC8;
+const SOF9: u8 = 0xC9;
+const SOF11: u8 = 0xCB;
+const DAC: u8 = 0xCC;
+const SOF13: u8 = 0xCD;
+const SOF15: u8 = 0xCF;
+const RST0: u8 = 0xD0;
+const RST7: u8 = 0xD7;
+const SOI: u8 = 0xD8;
+const EOI: u8 = 0xD9;
+const SOS: u8 = 0xDA;
+const DQT: u8 = 0xDB;
+const DNL: u8 = 0xDC;
+const DRI: u8 = 0xDD;
+const DHP: u8 = 0xDE;
+const EXP: u8 = 0xDF;
+const APP0: u8 = 0xE0;
+const APP1: u8 = 0xE1;
+const APP2: u8 = 0xE2;
+const APP14: u8 = 0xEE;
+const APP15: u8 = 0xEF;
+const JPG0: u8 = 0xF0;
+const JPG13: u8 = 0xFD;
+const COM: u8 = 0xFE;
+
+const JFIF_IDENTIFIER: &[u8] = b"JFIF\0";
+/// The length of the JFIF APP0 payload up to and including the thumbnail dimensions.
+const JFIF_HEADER_LEN: usize = JFIF_IDENTIFIER.len() + 9;
+const EXIF_IDENTIFIER: &[u8] = b"Exif\0\0";
+const ICC_PROFILE_IDENTIFIER: &[u8] = b"ICC_PROFILE\0";
+
+const TIFF_ORIENTATION_TAG: u16 = 0x0112;
+const TIFF_SHORT_TYPE: u16 = 3;
+const DEFAULT_ORIENTATION: u16 = 1;
+
+/// Whether a segment with a length field may be kept in the output, pending a check of its
+/// contents.
+///
+/// APP1 segments are never kept as-is, but EXIF ones have to be read to find the orientation.
+fn is_kept(code: u8) -> bool {
+    match code {
+        APP0 | APP1 | APP2 | APP14 => true,
+        APP0..=APP15 | COM => false,
+        _ => true,
+    }
+}
+
+/// Encodes a segment with a length field.
+fn segment(code: u8, payload: &[u8]) -> Vec<u8> {
+    let length = u16::try_from(payload.len() + 2).expect("small enough");
+    [&[0xFF, code][..], &length.to_be_bytes(), payload].concat()
+}
+
+/// Finds the orientation in the first IFD of the TIFF structure in an EXIF segment.
+///
+/// Returns `None` if there isn't one, or if the structure is malformed.
+fn exif_orientation(tiff: &[u8]) -> Option<u16> {
+    let big_endian = match tiff.get(..4)? {
+        b"MM\0\x2a" => true,
+        b"II\x2a\0" => false,
+        _ => return None,
+    };
+    let u16_at = |offset: usize| {
+        let bytes = *tiff.get(offset..)?.first_chunk()?;
+        Some(if big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    };
+    let u32_at = |offset: usize| {
+        let bytes = *tiff.get(offset..)?.first_chunk()?;
+        Some(if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    };
+
+    let ifd_offset = usize::try_from(u32_at(4)?).ok()?;
+    // Reading the entry count checks that the IFD starts within the (at most 64KiB) segment, so
+    // the offsets below can't overflow.
+    let entry_count = u16_at(ifd_offset)?;
+    let entry = (0..usize::from(entry_count))
+        .map(|i| ifd_offset + 2 + 12 * i)
+        .find(|&entry| u16_at(entry) == Some(TIFF_ORIENTATION_TAG))?;
+    if u16_at(entry + 2)? != TIFF_SHORT_TYPE || u32_at(entry + 4)? != 1 {
+        return None;
+    }
+    let orientation = u16_at(entry + 8)?;
+    (1..=8).contains(&orientation).then_some(orientation)
+}
+
+/// Encodes an EXIF segment containing nothing but `orientation`.
+fn exif_orientation_segment(orientation: u16) -> Vec<u8> {
+    let tiff = [
+        &b"MM\0\x2a"[..],
+        // The first IFD immediately follows the header.
+        &8u32.to_be_bytes(),
+        &1u16.to_be_bytes(),
+        &TIFF_ORIENTATION_TAG.to_be_bytes(),
+        &TIFF_SHORT_TYPE.to_be_bytes(),
+        &1u32.to_be_bytes(),
+        // The value is padded to fill the four-byte field.
+        &orientation.to_be_bytes(),
+        &[0, 0],
+        // There is no next IFD.
+        &0u32.to_be_bytes(),
+    ]
+    .concat();
+    segment(APP1, &[EXIF_IDENTIFIER, &tiff].concat())
+}
+
+/// Reads a marker outside of entropy-coded data, returning its offset and code.
+async fn read_marker<R: AsyncRead + AsyncSkip + ?Sized>(
+    reader: &mut Reader<'_, R>,
+) -> Result<(u64, u8), Error> {
+    let truncated = |e| Error::io_or_truncated(e, ParseError::TruncatedSegment, "reading marker");
+    let mut offset = reader.position();
+    if reader.read_u8().await.map_err(truncated)? != 0xFF {
+        return Err(Error::parse(
+            ParseError::InvalidSegmentLayout,
+            format!("expected marker at offset {offset}"),
+        ));
+    }
+    // Any number of 0xFF fill bytes may precede a marker; the last one starts the marker itself.
+    let mut code = reader.read_u8().await.map_err(truncated)?;
+    while code == 0xFF {
+        offset = reader.position() - 1;
+        code = reader.read_u8().await.map_err(truncated)?;
+    }
+    if code == 0x00 {
+        return Err(Error::parse(
+            ParseError::InvalidSegmentLayout,
+            format!("stuffed byte outside of scan at offset {offset}"),
+        ));
+    }
+    Ok((offset, code))
+}
+
+/// Advances past the entropy-coded data following a scan header, returning the offset and code
+/// of the marker that ends it.
+///
+/// Stuffed zero bytes and restart markers are part of the data.
+async fn find_end_of_scan<R: AsyncRead + AsyncSkip + ?Sized>(
+    reader: &mut Reader<'_, R>,
+) -> std::io::Result<(u64, u8)> {
+    loop {
+        loop {
+            let available = reader.fill_buf().await?;
+            if available.is_empty() {
+                return Err(std::io::ErrorKind::UnexpectedEof.into());
+            }
+            if let Some(index) = available.iter().position(|&b| b == 0xFF) {
+                reader.consume(index + 1);
+                break;
+            }
+            let len = available.len();
+            reader.consume(len);
+        }
+
+        let mut offset = reader.position() - 1;
+        let mut code = reader.read_u8().await?;
+        while code == 0xFF {
+            offset = reader.position() - 1;
+            code = reader.read_u8().await?;
+        }
+        match code {
+            0x00 | RST0..=RST7 => continue,
+            _ => return Ok((offset, code)),
+        }
+    }
+}
+
+fn is_valid_frame_header(payload: &[u8]) -> bool {
+    let &[
+        precision,
+        _height_hi,
+        _height_lo,
+        width_hi,
+        width_lo,
+        num_components,
+        ref components @ ..,
+    ] = payload
+    else {
+        return false;
+    };
+    // A height of zero is allowed; it means the height is given by a DNL segment later.
+    let width = u16::from_be_bytes([width_hi, width_lo]);
+    precision != 0
+        && width != 0
+        && (1..=4).contains(&num_components)
+        && components.len() == 3 * usize::from(num_components)
+}
+
+fn is_valid_scan_header(payload: &[u8]) -> bool {
+    let Some(&num_components) = payload.first() else {
+        return false;
+    };
+    (1..=4).contains(&num_components) && payload.len() == 4 + 2 * usize::from(num_components)
+}
+
+/// A marker code, displayed by name for error reports.
+struct Marker(u8);
+
+impl fmt::Display for Marker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            code @ (SOF0..=SOF3 | SOF5..=SOF7 | SOF9..=SOF11 | SOF13..=SOF15) => {
+                write!(f, "SOF{}", code - SOF0)
+            }
+            DHT => f.write_str("DHT"),
+            DAC => f.write_str("DAC"),
+            code @ RST0..=RST7 => write!(f, "RST{}", code - RST0),
+            SOI => f.write_str("SOI"),
+            EOI => f.write_str("EOI"),
+            SOS => f.write_str("SOS"),
+            DQT => f.write_str("DQT"),
+            DNL => f.write_str("DNL"),
+            DRI => f.write_str("DRI"),
+            code @ APP0..=APP15 => write!(f, "APP{}", code - APP0),
+            COM => f.write_str("COM"),
+            code => write!(f, "marker 0xFF{code:02X}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use futures::executor::block_on;
+    use futures::io::Cursor;
+    use test_case::test_case;
+
+    use super::*;
+
+    fn jfif() -> Vec<u8> {
+        segment(APP0, b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0")
+    }
+
+    fn frame_header() -> Vec<u8> {
+        segment(SOF0, &[8, 0, 1, 0, 1, 1, 1, 0x11, 0])
+    }
+
+    fn tables() -> Vec<u8> {
+        [segment(DQT, &[0; 65]), segment(DHT, &[0; 17])].concat()
+    }
+
+    fn scan() -> Vec<u8> {
+        [
+            segment(SOS, &[1, 1, 0, 0, 63, 0]),
+            // Entropy-coded data, including a stuffed 0xFF and a restart marker.
+            vec![0x12, 0xFF, 0x00, 0x34, 0xFF, RST0, 0x56],
+        ]
+        .concat()
+    }
+
+    const SOI_MARKER: [u8; 2] = [0xFF, SOI];
+    const EOI_MARKER: [u8; 2] = [0xFF, EOI];
+
+    fn sanitized_bytes(input: &[u8]) -> Result<Vec<u8>, ParseError> {
+        let result = block_on(sanitize(Cursor::new(input)));
+        let sanitized = match result {
+            Ok(sanitized) => sanitized,
+            Err(Error::Parse(report)) => return Err(report.kind),
+            Err(Error::Io(e)) => panic!("unexpected IO error: {e}"),
+        };
+        Ok(sanitized.apply_to(input))
+    }
+
+    #[test]
+    fn minimal_image_is_unchanged() {
+        let image = [
+            &SOI_MARKER[..],
+            &jfif(),
+            &tables(),
+            &frame_header(),
+            &scan(),
+            &EOI_MARKER,
+        ]
+        .concat();
+        assert_eq!(sanitized_bytes(&image).expect("valid"), image);
+    }
+
+    #[test]
+    fn strips_metadata() {
+        let exif = segment(0xE1, b"Exif\0\0MM\0\x2a\0\0\0\x08GPS");
+        let xmp = segment(0xE1, b"http://ns.adobe.com/xap/1.0/\0<x:xmpmeta/>");
+        let icc = segment(0xE2, b"ICC_PROFILE\0\x01\x01profile");
+        let jfxx_thumbnail = segment(APP0, b"JFXX\0\x10thumbnail");
+        let comment = segment(COM, b"taken at home");
+
+        let image = [
+            &SOI_MARKER[..],
+            &jfif(),
+            &exif,
+            &xmp,
+            &icc,
+            &jfxx_thumbnail,
+            &tables(),
+            &comment,
+            &frame_header(),
+            &scan(),
+            &EOI_MARKER,
+            b"trailing data",
+        ]
+        .concat();
+        let expected = [
+            &SOI_MARKER[..],
+            &jfif(),
+            &icc,
+            &tables(),
+            &frame_header(),
+            &scan(),
+            &EOI_MARKER,
+        ]
+        .concat();
+        assert_eq!(sanitized_bytes(&image).expect("valid"), expected);
+    }
+
+    #[test]
+    fn removes_jfif_thumbnail() {
+        // A 1x2 thumbnail, followed by a stray byte.
+        let jfif_with_thumbnail = segment(APP0, b"JFIF\0\x01\x01\0\0\x01\0\x01\x01\x02abcdef!");
+        let image = [
+            &SOI_MARKER[..],
+            &jfif_with_thumbnail,
+            &frame_header(),
+            &scan(),
+            &EOI_MARKER,
+        ]
+        .concat();
+        let expected = [
+            &SOI_MARKER[..],
+            &jfif(),
+            &frame_header(),
+            &scan(),
+            &EOI_MARKER,
+        ]
+        .concat();
+        assert_eq!(sanitized_bytes(&image).expect("valid"), expected);
+    }
+
+    /// An EXIF segment with a camera model and the given orientation, in little-endian order.
+    fn exif_with_orientation(orientation: u16) -> Vec<u8> {
+        let tiff = [
+            &b"II\x2a\0"[..],
+            &8u32.to_le_bytes(),
+            &2u16.to_le_bytes(),
+            // Model, stored after the IFD.
+            &0x0110u16.to_le_bytes(),
+            &2u16.to_le_bytes(),
+            &6u32.to_le_bytes(),
+            &38u32.to_le_bytes(),
+            // Orientation.
+            &TIFF_ORIENTATION_TAG.to_le_bytes(),
+            &TIFF_SHORT_TYPE.to_le_bytes(),
+            &1u32.to_le_bytes(),
+            &orientation.to_le_bytes(),
+            &[0, 0],
+            &0u32.to_le_bytes(),
+            b"Phone\0",
+        ]
+        .concat();
+        segment(APP1, &[EXIF_IDENTIFIER, &tiff].concat())
+    }
+
+    #[test_case(1 => None; "default")]
+    #[test_case(6 => Some(6); "rotated")]
+    #[test_case(8 => Some(8); "rotated the other way")]
+    #[test_case(9 => None; "invalid")]
+    fn keeps_only_exif_orientation(orientation: u16) -> Option<u16> {
+        let image = [
+            &SOI_MARKER[..],
+            &jfif(),
+            &exif_with_orientation(orientation),
+            // Only the first EXIF segment counts.
+            &exif_with_orientation(3),
+            &frame_header(),
+            &scan(),
+            &EOI_MARKER,
+        ]
+        .concat();
+        let sanitized = sanitized_bytes(&image).expect("valid");
+
+        let expected_exif = match orientation {
+            2..=8 => exif_orientation_segment(orientation),
+            _ => vec![],
+        };
+        let expected = [
+            &SOI_MARKER[..],
+            &jfif(),
+            &expected_exif,
+            &frame_header(),
+            &scan(),
+            &EOI_MARKER,
+        ]
+        .concat();
+        assert_eq!(sanitized, expected);
+
+        let (_, after_jfif) = sanitized.split_at(SOI_MARKER.len() + jfif().len());
+        let exif_payload = after_jfif.strip_prefix(&[0xFF, APP1, 0, 34])?;
+        exif_orientation(exif_payload.strip_prefix(EXIF_IDENTIFIER)?)
+    }
+
+    #[test]
+    fn exif_orientation_reads_both_byte_orders() {
+        let big_endian = exif_orientation_segment(6);
+        let little_endian = exif_with_orientation(6);
+        for exif in [big_endian, little_endian] {
+            let tiff = &exif[4 + EXIF_IDENTIFIER.len()..];
+            assert_eq!(exif_orientation(tiff), Some(6));
+        }
+        assert_eq!(exif_orientation(b"MM\0\x2a\0\0\0\x08GPS"), None);
+        assert_eq!(exif_orientation(b"MM\0\x2a\xFF\xFF\xFF\xFF"), None);
+    }
+
+    #[test]
+    fn progressive_scans() {
+        let image = [
+            &SOI_MARKER[..],
+            &segment(0xC2, &[8, 0, 1, 0, 1, 1, 1, 0x11, 0]),
+            &tables(),
+            &scan(),
+            &segment(DHT, &[0; 17]),
+            &scan(),
+            &EOI_MARKER,
+        ]
+        .concat();
+        assert_eq!(sanitized_bytes(&image).expect("valid"), image);
+    }
+
+    #[test]
+    fn fill_bytes_before_marker() {
+        let image = [
+            &SOI_MARKER[..],
+            &[0xFF, 0xFF],
+            &frame_header(),
+            &scan(),
+            &[0xFF],
+            &EOI_MARKER,
+        ]
+        .concat();
+        let expected = [
+            &SOI_MARKER[..],
+            &frame_header(),
+            &scan(),
+            &[0xFF],
+            &EOI_MARKER,
+        ]
+        .concat();
+        assert_eq!(sanitized_bytes(&image).expect("valid"), expected);
+    }
+
+    #[test_case(b"\x89PNG" => ParseError::InvalidInput; "not a JPEG")]
+    #[test_case(b"\xFF" => ParseError::InvalidInput; "truncated SOI")]
+    #[test_case(&[&SOI_MARKER[..], &frame_header()[..5]].concat() => ParseError::TruncatedSegment; "truncated segment")]
+    #[test_case(&[&SOI_MARKER[..], &frame_header(), &scan()].concat() => ParseError::TruncatedSegment; "missing EOI")]
+    #[test_case(&[&SOI_MARKER[..], &frame_header(), &EOI_MARKER].concat() => ParseError::MissingRequiredSegment("SOS"); "missing scan")]
+    #[test_case(&[&SOI_MARKER[..], &scan(), &EOI_MARKER].concat() => ParseError::MissingRequiredSegment("SOF"); "missing frame header")]
+    #[test_case(&[&SOI_MARKER[..], &frame_header(), &frame_header()].concat() => ParseError::InvalidSegmentLayout; "two frame headers")]
+    #[test_case(&[&SOI_MARKER[..], &segment(SOF0, &[8, 0, 1, 0, 1, 2, 1, 0x11, 0])].concat() => ParseError::InvalidSegmentLayout; "frame header too short")]
+    #[test_case(&[&SOI_MARKER[..], &segment(SOF0, &[8, 0, 1, 0, 0, 1, 1, 0x11, 0])].concat() => ParseError::InvalidSegmentLayout; "zero width")]
+    #[test_case(&[&SOI_MARKER[..], &[0xFF, COM, 0, 1]].concat() => ParseError::InvalidSegmentLayout; "length too small")]
+    #[test_case(&[&SOI_MARKER[..], &segment(APP0, b"JFIF\0\x01\x01")].concat() => ParseError::InvalidSegmentLayout; "JFIF header too short")]
+    #[test_case(&[&SOI_MARKER[..], &[0xFF, RST0]].concat() => ParseError::InvalidSegmentLayout; "restart outside scan")]
+    #[test_case(&[&SOI_MARKER[..], &[0x00]].concat() => ParseError::InvalidSegmentLayout; "garbage between segments")]
+    #[test_case(&[&SOI_MARKER[..], &segment(DHP, &[8, 0, 1, 0, 1, 1, 1, 0x11, 0])].concat() => ParseError::UnsupportedSegment(DHP); "hierarchical")]
+    fn invalid(input: &[u8]) -> ParseError {
+        sanitized_bytes(input).expect_err("should be rejected")
+    }
+
+    #[test]
+    fn skipped_segment_past_end() {
+        let image = [&SOI_MARKER[..], &[0xFF, 0xE3, 0xFF, 0xFF]].concat();
+        assert_matches!(sanitized_bytes(&image), Err(ParseError::TruncatedSegment));
+    }
+}
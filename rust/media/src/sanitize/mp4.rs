@@ -3,12 +3,19 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use std::pin::pin;
+
 use futures_util::AsyncRead;
 use mediasan_common::AsyncSkip;
 pub use mp4san::parse::ParseError;
 use mp4san::{Config, sanitize_async_with_config};
 pub use mp4san::{InputSpan, SanitizedMetadata};
 
+use super::observe::{Capturer, Observed};
+
+mod info;
+pub use info::{Mp4Info, TrackInfo, TrackKind};
+
 /// Error type returned by [`sanitize`].
 pub type Error = super::error::SanitizerError<ParseError>;
 
@@ -40,5 +47,28 @@ pub async fn sanitize<R: AsyncRead + AsyncSkip>(
     Ok(metadata)
 }
 
+/// Sanitize an MP4 input, also describing its duration and tracks.
+///
+/// This is the same as [`sanitize`], but watches the input as it's read to find the `moov` box.
+/// The [`Mp4Info`] is `None` if the input's structure was unusual enough that the `moov` box
+/// couldn't be found that way, even though the input was sanitized successfully.
+///
+/// # Errors
+///
+/// If the input cannot be parsed, or an IO error occurs, an `Error` is returned.
+pub async fn sanitize_with_info<R: AsyncRead + AsyncSkip>(
+    input: R,
+    cumulative_mdat_box_size: Option<u32>,
+) -> Result<(SanitizedMetadata, Option<Mp4Info>), Error> {
+    let input = pin!(input);
+    let mut capturer = Capturer::new(info::MoovWalker::default());
+    let observed = Observed {
+        inner: input,
+        capturer: &mut capturer,
+    };
+    let metadata = sanitize(observed, cumulative_mdat_box_size).await?;
+    Ok((metadata, capturer.into_walker().into_info()))
+}
+
 /// The maximum size of metadata to support, setting an upper bound on memory consumption in the parser.
 const MAX_METADATA_SIZE: u64 = 300 * 1024 * 1024;
@@ -0,0 +1,491 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::time::Duration;
+
+use crate::sanitize::observe::Walker;
+
+/// Information about an MP4 file, read from its `moov` box.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Mp4Info {
+    /// The presentation duration from the movie header, if it was specified.
+    pub duration: Option<Duration>,
+    pub tracks: Vec<TrackInfo>,
+}
+
+/// Information about a single track of an MP4 file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrackInfo {
+    pub kind: TrackKind,
+    /// The type of the track's first sample entry, such as `avc1` or `mp4a`.
+    pub codec: Option<String>,
+    /// The duration of the track's media, if it was specified.
+    pub duration: Option<Duration>,
+    /// The display width in pixels from the track header, zero for tracks without a visual
+    /// presentation.
+    pub width: u32,
+    /// The display height in pixels from the track header, zero for tracks without a visual
+    /// presentation.
+    pub height: u32,
+    /// The clockwise rotation to apply for display, in degrees: 0, 90, 180, or 270.
+    ///
+    /// Transformations other than rotations by a multiple of 90 degrees are reported as 0.
+    pub rotation: u16,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrackKind {
+    Video,
+    Audio,
+    Other,
+}
+
+impl Mp4Info {
+    /// The first video track, if any.
+    pub fn video_track(&self) -> Option<&TrackInfo> {
+        self.tracks
+            .iter()
+            .find(|track| track.kind == TrackKind::Video)
+    }
+
+    /// The first audio track, if any.
+    pub fn audio_track(&self) -> Option<&TrackInfo> {
+        self.tracks
+            .iter()
+            .find(|track| track.kind == TrackKind::Audio)
+    }
+}
+
+impl TrackInfo {
+    /// Builds a track's info from the boxes of its `trak`, ignoring anything malformed or
+    /// unrecognized.
+    ///
+    /// The sanitizer has already validated the box structure by the time this is used, so there's
+    /// no attempt to report errors here.
+    fn from_boxes(boxes: &TrakBoxes) -> Self {
+        let TrakBoxes {
+            tkhd,
+            mdhd,
+            hdlr,
+            stsd,
+        } = boxes;
+        let kind = match hdlr.as_deref().and_then(|hdlr| hdlr.get(8..12)) {
+            Some(b"vide") => TrackKind::Video,
+            Some(b"soun") => TrackKind::Audio,
+            _ => TrackKind::Other,
+        };
+        let codec = stsd
+            .as_deref()
+            .and_then(|stsd| stsd.get(12..16))
+            .and_then(fourcc_to_string);
+        let duration = mdhd.as_deref().and_then(media_duration);
+
+        let (width, height, rotation) = tkhd.as_deref().and_then(presentation).unwrap_or_default();
+
+        Self {
+            kind,
+            codec,
+            duration,
+            width,
+            height,
+            rotation,
+        }
+    }
+}
+
+/// Reads the timescale and duration shared by the layouts of `mvhd` and `mdhd`.
+fn media_duration(header: &[u8]) -> Option<Duration> {
+    let (timescale, duration, unknown) = match *header.first()? {
+        0 => (
+            read_u32(header, 12)?,
+            u64::from(read_u32(header, 16)?),
+            u64::from(u32::MAX),
+        ),
+        1 => (read_u32(header, 20)?, read_u64(header, 24)?, u64::MAX),
+        _ => return None,
+    };
+    if timescale == 0 || duration == unknown {
+        return None;
+    }
+    let timescale = u64::from(timescale);
+    let nanos = u128::from(duration % timescale) * 1_000_000_000 / u128::from(timescale);
+    Some(Duration::new(
+        duration / timescale,
+        u32::try_from(nanos).expect("less than a second"),
+    ))
+}
+
+/// Reads the display width, height, and rotation from a `tkhd`.
+fn presentation(tkhd: &[u8]) -> Option<(u32, u32, u16)> {
+    const ONE: u32 = 0x0001_0000;
+    const MINUS_ONE: u32 = ONE.wrapping_neg();
+
+    let matrix_offset = match *tkhd.first()? {
+        0 => 40,
+        1 => 52,
+        _ => return None,
+    };
+    let [a, b, c, d] = [0, 1, 3, 4].map(|index| read_u32(tkhd, matrix_offset + 4 * index));
+    let rotation = match (a?, b?, c?, d?) {
+        (0, ONE, MINUS_ONE, 0) => 90,
+        (MINUS_ONE, 0, 0, MINUS_ONE) => 180,
+        (0, MINUS_ONE, ONE, 0) => 270,
+        _ => 0,
+    };
+    // Both are 16.16 fixed point.
+    let width = read_u32(tkhd, matrix_offset + 36)? >> 16;
+    let height = read_u32(tkhd, matrix_offset + 40)? >> 16;
+    Some((width, height, rotation))
+}
+
+fn fourcc_to_string(fourcc: &[u8]) -> Option<String> {
+    fourcc
+        .iter()
+        .all(|byte| byte.is_ascii_graphic() || *byte == b' ')
+        .then(|| String::from_utf8_lossy(fourcc).into_owned())
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(*data.get(offset..)?.first_chunk()?))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(*data.get(offset..)?.first_chunk()?))
+}
+
+/// The most of any one box the walker captures.
+///
+/// Every field [`TrackInfo::from_boxes`] and [`media_duration`] read is within this prefix, even in
+/// the version 1 box layouts.
+const MAX_CAPTURED_BOX_PREFIX: u64 = 128;
+
+/// Reads the `moov` box incrementally as the sanitizer reads through the input.
+///
+/// Only the containers leading to the boxes above are descended into, and only a short prefix of
+/// each of those boxes is captured, so memory use doesn't grow with the size of the `moov`.
+#[derive(Default)]
+pub(super) struct MoovWalker {
+    expecting: Expecting,
+    /// The boxes the next header is nested in, outermost first, along with where each one ends.
+    parents: Vec<([u8; 4], u64)>,
+    moov: Mp4Info,
+    trak: TrakBoxes,
+    /// Set once the whole `moov` has been seen.
+    info: Option<Mp4Info>,
+}
+
+#[derive(Default)]
+enum Expecting {
+    #[default]
+    Header,
+    LargeSize {
+        box_type: [u8; 4],
+        offset: u64,
+    },
+    Body {
+        box_type: [u8; 4],
+        end: u64,
+    },
+}
+
+/// The prefixes of the boxes within a `trak` that [`TrackInfo`] is read from.
+#[derive(Default)]
+struct TrakBoxes {
+    tkhd: Option<Vec<u8>>,
+    mdhd: Option<Vec<u8>>,
+    hdlr: Option<Vec<u8>>,
+    stsd: Option<Vec<u8>>,
+}
+
+impl MoovWalker {
+    pub(super) fn into_info(self) -> Option<Mp4Info> {
+        self.info
+    }
+
+    fn parent(&self) -> Option<&[u8; 4]> {
+        self.parents.last().map(|(box_type, _)| box_type)
+    }
+
+    fn found_box(
+        &mut self,
+        box_type: [u8; 4],
+        offset: u64,
+        header_len: u64,
+        size: u64,
+    ) -> Option<(u64, usize)> {
+        if size < header_len {
+            return None;
+        }
+        let end = offset.checked_add(size)?;
+        if let Some(&(_, parent_end)) = self.parents.last()
+            && end > parent_end
+        {
+            return None;
+        }
+        let body = offset + header_len;
+
+        match (self.parent(), &box_type) {
+            (None, b"moov")
+            | (Some(b"moov"), b"trak")
+            | (Some(b"trak"), b"mdia")
+            | (Some(b"mdia"), b"minf")
+            | (Some(b"minf"), b"stbl") => {
+                self.parents.push((box_type, end));
+                self.next_box(body)
+            }
+            (Some(b"moov"), b"mvhd")
+            | (Some(b"trak"), b"tkhd")
+            | (Some(b"mdia"), b"mdhd" | b"hdlr")
+            | (Some(b"stbl"), b"stsd") => {
+                self.expecting = Expecting::Body { box_type, end };
+                let len = (end - body).min(MAX_CAPTURED_BOX_PREFIX);
+                Some((body, usize::try_from(len).expect("small")))
+            }
+            _ => self.next_box(end),
+        }
+    }
+
+    /// Moves on to the box at `offset`, first closing any parents that have no room left for it.
+    fn next_box(&mut self, mut offset: u64) -> Option<(u64, usize)> {
+        while let Some(&(box_type, end)) = self.parents.last() {
+            if offset.saturating_add(8) <= end {
+                break;
+            }
+            self.parents.pop();
+            offset = end;
+            match &box_type {
+                b"trak" => {
+                    let trak = std::mem::take(&mut self.trak);
+                    self.moov.tracks.push(TrackInfo::from_boxes(&trak));
+                }
+                b"moov" => {
+                    self.info = Some(std::mem::take(&mut self.moov));
+                    return None;
+                }
+                _ => {}
+            }
+        }
+        self.expecting = Expecting::Header;
+        Some((offset, 8))
+    }
+
+    fn captured_body(&mut self, box_type: [u8; 4], data: Vec<u8>) {
+        let slot = match &box_type {
+            b"mvhd" => {
+                self.moov.duration = self.moov.duration.or_else(|| media_duration(&data));
+                return;
+            }
+            b"tkhd" => &mut self.trak.tkhd,
+            b"mdhd" => &mut self.trak.mdhd,
+            b"hdlr" => &mut self.trak.hdlr,
+            b"stsd" => &mut self.trak.stsd,
+            _ => return,
+        };
+        // Like the sanitizer, only the first of each box counts.
+        slot.get_or_insert(data);
+    }
+}
+
+impl Walker for MoovWalker {
+    fn first_capture(&mut self) -> Option<(u64, usize)> {
+        Some((0, 8))
+    }
+
+    fn captured(&mut self, offset: u64, data: Vec<u8>) -> Option<(u64, usize)> {
+        match std::mem::take(&mut self.expecting) {
+            Expecting::Header => {
+                let box_type: [u8; 4] = data[4..8].try_into().expect("correct length");
+                match read_u32(&data, 0)? {
+                    // The box extends to the end of the input.
+                    0 => None,
+                    1 => {
+                        self.expecting = Expecting::LargeSize { box_type, offset };
+                        Some((offset + 8, 8))
+                    }
+                    size => self.found_box(box_type, offset, 8, size.into()),
+                }
+            }
+            Expecting::LargeSize { box_type, offset } => {
+                self.found_box(box_type, offset, 16, read_u64(&data, 0)?)
+            }
+            Expecting::Body { box_type, end } => {
+                self.captured_body(box_type, data);
+                self.next_box(end)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sanitize::observe::Capturer;
+
+    fn mp4_box(box_type: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let size = u32::try_from(8 + body.len()).expect("small enough");
+        [&size.to_be_bytes()[..], box_type, body].concat()
+    }
+
+    fn full_box(box_type: &[u8; 4], version: u8, body: &[u8]) -> Vec<u8> {
+        mp4_box(box_type, &[&[version, 0, 0, 0][..], body].concat())
+    }
+
+    fn mvhd(timescale: u32, duration: u32) -> Vec<u8> {
+        let mut body = vec![0; 96];
+        body[8..12].copy_from_slice(&timescale.to_be_bytes());
+        body[12..16].copy_from_slice(&duration.to_be_bytes());
+        full_box(b"mvhd", 0, &body)
+    }
+
+    fn tkhd(matrix: [i32; 4], width: u32, height: u32) -> Vec<u8> {
+        let mut body = vec![0; 80];
+        for (index, value) in [0, 1, 3, 4].into_iter().zip(matrix) {
+            let offset = 36 + 4 * index;
+            body[offset..offset + 4].copy_from_slice(&(value << 16).to_be_bytes());
+        }
+        body[72..76].copy_from_slice(&(width << 16).to_be_bytes());
+        body[76..80].copy_from_slice(&(height << 16).to_be_bytes());
+        full_box(b"tkhd", 0, &body)
+    }
+
+    fn trak(
+        matrix: [i32; 4],
+        width: u32,
+        height: u32,
+        handler: &[u8; 4],
+        codec: &[u8; 4],
+    ) -> Vec<u8> {
+        let mut mdhd = mvhd(1000, 2500);
+        mdhd[4..8].copy_from_slice(b"mdhd");
+        let hdlr = full_box(b"hdlr", 0, &[&[0; 4][..], handler, &[0; 12]].concat());
+        let entry = mp4_box(codec, &[0; 8]);
+        let stsd = full_box(b"stsd", 0, &[&1u32.to_be_bytes()[..], &entry].concat());
+        let stco = full_box(b"stco", 0, &[0; 4000]);
+        let minf = mp4_box(b"minf", &mp4_box(b"stbl", &[stsd, stco].concat()));
+        let mdia = mp4_box(b"mdia", &[mdhd, hdlr, minf].concat());
+        mp4_box(b"trak", &[tkhd(matrix, width, height), mdia].concat())
+    }
+
+    fn moov() -> Vec<u8> {
+        let video = trak([0, 1, -1, 0], 1920, 1080, b"vide", b"avc1");
+        let audio = trak([1, 0, 0, 1], 0, 0, b"soun", b"mp4a");
+        let udta = mp4_box(b"udta", &[0; 2000]);
+        mp4_box(b"moov", &[mvhd(600, 1500), video, udta, audio].concat())
+    }
+
+    /// Wraps a [`MoovWalker`], recording the longest capture it asks for.
+    #[derive(Default)]
+    struct LongestCapture(MoovWalker, usize);
+
+    impl LongestCapture {
+        fn record(&mut self, next: Option<(u64, usize)>) -> Option<(u64, usize)> {
+            if let Some((_, len)) = next {
+                self.1 = self.1.max(len);
+            }
+            next
+        }
+    }
+
+    impl Walker for LongestCapture {
+        fn first_capture(&mut self) -> Option<(u64, usize)> {
+            let next = self.0.first_capture();
+            self.record(next)
+        }
+
+        fn captured(&mut self, offset: u64, data: Vec<u8>) -> Option<(u64, usize)> {
+            let next = self.0.captured(offset, data);
+            self.record(next)
+        }
+    }
+
+    fn walk(input: &[u8]) -> Option<Mp4Info> {
+        let mut capturer = Capturer::new(MoovWalker::default());
+        capturer.observe_read(input);
+        capturer.into_walker().into_info()
+    }
+
+    #[test]
+    fn parses_moov() {
+        let info = walk(&moov()).expect("found moov");
+        assert_eq!(info.duration, Some(Duration::from_millis(2500)));
+        assert_eq!(
+            info.video_track(),
+            Some(&TrackInfo {
+                kind: TrackKind::Video,
+                codec: Some("avc1".to_owned()),
+                duration: Some(Duration::from_millis(2500)),
+                width: 1920,
+                height: 1080,
+                rotation: 90,
+            })
+        );
+        let audio = info.audio_track().expect("has audio");
+        assert_eq!(audio.codec.as_deref(), Some("mp4a"));
+        assert_eq!((audio.width, audio.height, audio.rotation), (0, 0, 0));
+    }
+
+    #[test]
+    fn only_captures_box_prefixes() {
+        let moov = moov();
+        let mut capturer = Capturer::new(LongestCapture::default());
+        capturer.observe_read(&moov);
+        let LongestCapture(walker, longest) = capturer.into_walker();
+        assert_eq!(walker.into_info().map(|info| info.tracks.len()), Some(2));
+        assert!(
+            u64::try_from(longest).expect("small") <= MAX_CAPTURED_BOX_PREFIX,
+            "captured {longest} bytes at once"
+        );
+    }
+
+    #[test]
+    fn incomplete_moov_has_no_info() {
+        let moov = moov();
+        assert_eq!(walk(&moov[..moov.len() / 2]), None);
+    }
+
+    #[test]
+    fn ignores_truncated_boxes() {
+        let moov = moov();
+        // Claim the first trak is larger than the moov.
+        let mut oversized = moov.clone();
+        let trak_offset = 8 + mvhd(600, 1500).len();
+        oversized[trak_offset..trak_offset + 4].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert_eq!(walk(&oversized), None);
+    }
+
+    #[test]
+    fn finds_moov_after_skipped_mdat() {
+        let ftyp = mp4_box(b"ftyp", b"isom\0\0\0\0");
+        let mdat = mp4_box(b"mdat", &[0xAA; 1000]);
+        let moov = moov();
+
+        let mut capturer = Capturer::new(MoovWalker::default());
+        capturer.observe_read(&ftyp);
+        capturer.observe_read(&mdat[..8]);
+        capturer.observe_skip(1000);
+        for piece in moov.chunks(7) {
+            capturer.observe_read(piece);
+        }
+
+        let info = capturer.into_walker().into_info().expect("found moov");
+        assert_eq!(Some(info), walk(&moov));
+    }
+
+    #[test]
+    fn finds_moov_with_large_size() {
+        let moov = moov();
+        let large_size = u64::try_from(moov.len() + 8).expect("small enough");
+        let moov = [
+            &1u32.to_be_bytes()[..],
+            &moov[4..8],
+            &large_size.to_be_bytes(),
+            &moov[8..],
+        ]
+        .concat();
+
+        let info = walk(&moov).expect("found moov");
+        assert_eq!(info.tracks.len(), 2);
+    }
+}
@@ -0,0 +1,249 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Watching the bytes an external sanitizer reads, to collect information about the media without
+//! a second pass over the input.
+
+use std::io::{self, Read};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_util::AsyncRead;
+use mediasan_common::{AsyncSkip, Skip};
+
+/// Decides which parts of the input to capture.
+///
+/// Captures must be requested in increasing order of offset, since the input is only observed
+/// going forward.
+pub(super) trait Walker {
+    /// The first range to capture, as an offset from the start of observation and a length.
+    fn first_capture(&mut self) -> Option<(u64, usize)>;
+
+    /// Handles a completed capture, returning the next range to capture, if any.
+    fn captured(&mut self, offset: u64, data: Vec<u8>) -> Option<(u64, usize)>;
+}
+
+/// Collects ranges of the input requested by a [`Walker`] as they are read.
+///
+/// If a requested range is skipped over instead of read, observation stops; the walker will then
+/// not see any further captures.
+pub(super) struct Capturer<W> {
+    walker: W,
+    position: u64,
+    capture: Option<Capture>,
+    started: bool,
+}
+
+struct Capture {
+    offset: u64,
+    len: usize,
+    buf: Vec<u8>,
+}
+
+impl<W: Walker> Capturer<W> {
+    pub(super) fn new(walker: W) -> Self {
+        Self {
+            walker,
+            position: 0,
+            capture: None,
+            started: false,
+        }
+    }
+
+    pub(super) fn into_walker(self) -> W {
+        self.walker
+    }
+
+    fn start_capture(&mut self, next: Option<(u64, usize)>) {
+        self.capture = next.map(|(offset, len)| Capture {
+            offset,
+            len,
+            buf: Vec::with_capacity(len.min(MAX_PREALLOCATION)),
+        });
+    }
+
+    fn ensure_started(&mut self) {
+        if !self.started {
+            self.started = true;
+            let first = self.walker.first_capture();
+            self.start_capture(first);
+        }
+    }
+
+    pub(super) fn observe_read(&mut self, mut data: &[u8]) {
+        self.ensure_started();
+        while !data.is_empty() {
+            let Some(capture) = &mut self.capture else {
+                self.position += data.len() as u64;
+                return;
+            };
+
+            let wanted = capture.offset + capture.buf.len() as u64;
+            if self.position > wanted {
+                // Part of the capture was skipped.
+                self.capture = None;
+                continue;
+            }
+            if self.position < wanted {
+                let ignored = usize::try_from(wanted - self.position)
+                    .unwrap_or(usize::MAX)
+                    .min(data.len());
+                self.position += ignored as u64;
+                data = &data[ignored..];
+                continue;
+            }
+
+            let amount = (capture.len - capture.buf.len()).min(data.len());
+            capture.buf.extend_from_slice(&data[..amount]);
+            self.position += amount as u64;
+            data = &data[amount..];
+
+            if capture.buf.len() == capture.len {
+                let Capture { offset, buf, .. } = self.capture.take().expect("checked above");
+                let next = self.walker.captured(offset, buf);
+                self.start_capture(next);
+            }
+        }
+    }
+
+    pub(super) fn observe_skip(&mut self, amount: u64) {
+        self.ensure_started();
+        self.position = self.position.saturating_add(amount);
+        if let Some(capture) = &self.capture
+            && self.position > capture.offset + capture.buf.len() as u64
+        {
+            self.capture = None;
+        }
+    }
+}
+
+/// Don't trust lengths from the input for allocation up front; grow as data actually arrives.
+const MAX_PREALLOCATION: usize = 64 * 1024;
+
+/// An input wrapper that reports everything read or skipped to a [`Capturer`].
+///
+/// The capturer is borrowed so that it outlives the wrapper, which sanitizers take by value.
+pub(super) struct Observed<'c, R, W> {
+    pub(super) inner: R,
+    pub(super) capturer: &'c mut Capturer<W>,
+}
+
+impl<R: Read, W: Walker> Read for Observed<'_, R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let amount = self.inner.read(buf)?;
+        self.capturer.observe_read(&buf[..amount]);
+        Ok(amount)
+    }
+}
+
+impl<R: Skip, W: Walker> Skip for Observed<'_, R, W> {
+    fn skip(&mut self, amount: u64) -> io::Result<()> {
+        self.inner.skip(amount)?;
+        self.capturer.observe_skip(amount);
+        Ok(())
+    }
+
+    fn stream_position(&mut self) -> io::Result<u64> {
+        self.inner.stream_position()
+    }
+
+    fn stream_len(&mut self) -> io::Result<u64> {
+        self.inner.stream_len()
+    }
+}
+
+impl<R: AsyncRead + ?Sized, W: Walker> AsyncRead for Observed<'_, Pin<&mut R>, W> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let result = this.inner.as_mut().poll_read(cx, buf);
+        if let Poll::Ready(Ok(amount)) = result {
+            this.capturer.observe_read(&buf[..amount]);
+        }
+        result
+    }
+}
+
+impl<R: AsyncSkip + ?Sized, W: Walker> AsyncSkip for Observed<'_, Pin<&mut R>, W> {
+    fn poll_skip(self: Pin<&mut Self>, cx: &mut Context<'_>, amount: u64) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let result = this.inner.as_mut().poll_skip(cx, amount);
+        if let Poll::Ready(Ok(())) = result {
+            this.capturer.observe_skip(amount);
+        }
+        result
+    }
+
+    fn poll_stream_position(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        self.get_mut().inner.as_mut().poll_stream_position(cx)
+    }
+
+    fn poll_stream_len(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        self.get_mut().inner.as_mut().poll_stream_len(cx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Captures two bytes at every multiple of 10.
+    #[derive(Default)]
+    struct EveryTen(Vec<(u64, Vec<u8>)>);
+
+    impl Walker for EveryTen {
+        fn first_capture(&mut self) -> Option<(u64, usize)> {
+            Some((0, 2))
+        }
+
+        fn captured(&mut self, offset: u64, data: Vec<u8>) -> Option<(u64, usize)> {
+            self.0.push((offset, data));
+            Some((offset + 10, 2))
+        }
+    }
+
+    #[test]
+    fn captures_across_reads() {
+        let input = (0..40).collect::<Vec<u8>>();
+        let mut capturer = Capturer::new(EveryTen::default());
+        for piece in input.chunks(3) {
+            capturer.observe_read(piece);
+        }
+        assert_eq!(
+            capturer.into_walker().0,
+            [
+                (0, vec![0, 1]),
+                (10, vec![10, 11]),
+                (20, vec![20, 21]),
+                (30, vec![30, 31]),
+            ]
+        );
+    }
+
+    #[test]
+    fn skipping_a_capture_stops_observation() {
+        let mut capturer = Capturer::new(EveryTen::default());
+        capturer.observe_read(&[0, 1, 2]);
+        capturer.observe_skip(8);
+        capturer.observe_read(&[11, 12]);
+        capturer.observe_read(&(13..40).collect::<Vec<u8>>());
+        assert_eq!(capturer.into_walker().0, [(0, vec![0, 1])]);
+    }
+
+    #[test]
+    fn skipping_between_captures_is_fine() {
+        let mut capturer = Capturer::new(EveryTen::default());
+        capturer.observe_read(&[0, 1, 2]);
+        capturer.observe_skip(7);
+        capturer.observe_read(&[10, 11, 12]);
+        assert_eq!(
+            capturer.into_walker().0,
+            [(0, vec![0, 1]), (10, vec![10, 11])]
+        );
+    }
+}
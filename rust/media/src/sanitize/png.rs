@@ -0,0 +1,531 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! PNG sanitization.
+//!
+//! The sanitizer checks the chunk structure of a PNG (or APNG) image and produces a copy of it
+//! without metadata. Critical chunks and the ancillary chunks that affect how the image is
+//! displayed, including color profiles (`iCCP`, `sRGB`, `cICP`, and so on), are kept, after
+//! checking their CRCs. Everything else is dropped, including:
+//!
+//! - EXIF (`eXIf`) and text chunks (`tEXt`, `zTXt`, `iTXt`, which is where XMP lives),
+//! - modification times (`tIME`),
+//! - private and unknown ancillary chunks,
+//! - anything after the `IEND` chunk.
+//!
+//! The image data itself is not decompressed.
+
+use std::{fmt, io};
+
+use futures_util::AsyncRead;
+use mediasan_common::AsyncSkip;
+
+use super::reader::Reader;
+pub use super::reader::{ImagePart, SanitizedImage};
+
+/// Error type returned by [`sanitize`].
+pub type Error = super::error::SanitizerError<ParseError>;
+
+/// A decomposed and stringified parse error, as returned in [`Error::Parse`].
+pub type ParseErrorReport = super::error::ParseErrorReport<ParseError>;
+
+/// Error parsing a PNG image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ParseError {
+    /// The input does not start with the PNG signature.
+    #[error("invalid input")]
+    InvalidInput,
+
+    /// A chunk is malformed, has a bad CRC, or appears where it isn't allowed.
+    #[error("invalid chunk layout")]
+    InvalidChunkLayout,
+
+    /// A chunk required to decode the image never appeared.
+    #[error("missing required chunk: {0}")]
+    MissingRequiredChunk(ChunkType),
+
+    /// The input ended in the middle of the image.
+    #[error("truncated chunk")]
+    TruncatedChunk,
+
+    /// The image contains a critical chunk that isn't part of the PNG specification.
+    #[error("unsupported chunk: {0}")]
+    UnsupportedChunk(ChunkType),
+}
+
+/// A four-letter chunk type, such as `IHDR`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct ChunkType(pub [u8; 4]);
+
+impl ChunkType {
+    pub const IHDR: Self = Self(*b"IHDR");
+    pub const PLTE: Self = Self(*b"PLTE");
+    pub const IDAT: Self = Self(*b"IDAT");
+    pub const IEND: Self = Self(*b"IEND");
+
+    const KNOWN_CRITICAL: [Self; 4] = [Self::IHDR, Self::PLTE, Self::IDAT, Self::IEND];
+
+    /// Ancillary chunks that affect how the image is displayed, and carry no other information.
+    const KEPT_ANCILLARY: [Self; 15] = [
+        Self(*b"tRNS"),
+        Self(*b"gAMA"),
+        Self(*b"cHRM"),
+        Self(*b"sRGB"),
+        Self(*b"iCCP"),
+        Self(*b"cICP"),
+        Self(*b"mDCV"),
+        Self(*b"cLLI"),
+        Self(*b"sBIT"),
+        Self(*b"bKGD"),
+        Self(*b"pHYs"),
+        Self(*b"hIST"),
+        Self(*b"acTL"),
+        Self(*b"fcTL"),
+        Self(*b"fdAT"),
+    ];
+
+    /// Whether the chunk must be understood to display the image, as indicated by the case of its
+    /// first letter.
+    fn is_critical(self) -> bool {
+        self.0[0].is_ascii_uppercase()
+    }
+
+    fn is_valid(self) -> bool {
+        // The third letter is reserved and must be uppercase.
+        self.0.iter().all(u8::is_ascii_alphabetic) && self.0[2].is_ascii_uppercase()
+    }
+}
+
+impl fmt::Display for ChunkType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.iter().all(u8::is_ascii_alphabetic) {
+            self.0
+                .iter()
+                .try_for_each(|&b| write!(f, "{}", char::from(b)))
+        } else {
+            write!(f, "{:02X?}", self.0)
+        }
+    }
+}
+
+impl fmt::Debug for ChunkType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ChunkType({self})")
+    }
+}
+
+/// Sanitize a PNG input.
+///
+/// The input must implement [`AsyncRead`] + [`AsyncSkip`], where `AsyncSkip` represents the
+/// ability to skip forward, but not necessarily seek to arbitrary positions. Chunks that are
+/// going to be dropped are skipped over rather than read.
+///
+/// # Errors
+///
+/// If the input cannot be parsed, or an IO error occurs, an `Error` is returned.
+pub async fn sanitize<R: AsyncRead + AsyncSkip>(input: R) -> Result<SanitizedImage, Error> {
+    let input = std::pin::pin!(input);
+    let mut reader = Reader::new(input).await.map_err(Error::Io)?;
+    let mut output = SanitizedImage::default();
+
+    let start = reader.position();
+    let signature = reader
+        .read_array()
+        .await
+        .map_err(|e| Error::io_or_truncated(e, ParseError::InvalidInput, "reading signature"))?;
+    if signature != SIGNATURE {
+        return Err(Error::parse(ParseError::InvalidInput, "bad signature"));
+    }
+    output.keep(start, SIGNATURE.len() as u64);
+
+    let mut header = None;
+    let mut seen_palette = false;
+    let mut idat = IdatState::NotSeen;
+    loop {
+        let offset = reader.position();
+        let chunk_header: [u8; 8] = reader.read_array().await.map_err(|e| {
+            Error::io_or_truncated(
+                e,
+                ParseError::TruncatedChunk,
+                format!("reading chunk at offset {offset}"),
+            )
+        })?;
+        let [length @ .., _, _, _, _] = chunk_header;
+        let [_, _, _, _, chunk_type @ ..] = chunk_header;
+        let length = u32::from_be_bytes(length);
+        let chunk_type = ChunkType(chunk_type);
+        let context = |action: &str| format!("{action} {chunk_type} chunk at offset {offset}");
+        let truncated = |action: &'static str| {
+            move |e| Error::io_or_truncated(e, ParseError::TruncatedChunk, context(action))
+        };
+        let invalid = |action: &str| Error::parse(ParseError::InvalidChunkLayout, context(action));
+
+        if length > MAX_CHUNK_LENGTH || !chunk_type.is_valid() {
+            return Err(invalid("invalid"));
+        }
+        let is_first = header.is_none();
+        if is_first != (chunk_type == ChunkType::IHDR) {
+            return Err(if is_first {
+                Error::parse(
+                    ParseError::MissingRequiredChunk(ChunkType::IHDR),
+                    context("found"),
+                )
+            } else {
+                invalid("duplicate")
+            });
+        }
+        if idat == IdatState::InProgress && chunk_type != ChunkType::IDAT {
+            idat = IdatState::Done;
+        }
+
+        let kept = chunk_type.is_critical() || ChunkType::KEPT_ANCILLARY.contains(&chunk_type);
+        if !kept {
+            reader
+                .skip(u64::from(length) + CRC_LEN)
+                .await
+                .map_err(truncated("skipping"))?;
+            continue;
+        }
+
+        if chunk_type.is_critical() && !ChunkType::KNOWN_CRITICAL.contains(&chunk_type) {
+            return Err(Error::parse(
+                ParseError::UnsupportedChunk(chunk_type),
+                context("found"),
+            ));
+        }
+
+        // Only the contents of small, known chunks are inspected; the rest (mostly image data)
+        // only have their CRCs checked, without being held in memory.
+        let inspected_len = match chunk_type {
+            ChunkType::IHDR => Some(IHDR_LEN),
+            ChunkType::PLTE => Some(MAX_PLTE_LEN),
+            ChunkType::IEND => Some(0),
+            _ => None,
+        };
+        if let Some(max_len) = inspected_len
+            && length > max_len
+        {
+            return Err(invalid("invalid length for"));
+        }
+        let data = read_chunk_data(&mut reader, chunk_type, length, inspected_len.is_some())
+            .await
+            .map_err(truncated("reading"))?
+            .ok_or_else(|| invalid("bad CRC for"))?;
+
+        match chunk_type {
+            ChunkType::IHDR => {
+                header = Some(ImageHeader::parse(&data).ok_or_else(|| invalid("invalid"))?);
+            }
+            ChunkType::PLTE => {
+                let palette_allowed = header.is_some_and(|h| h.color_type.allows_palette());
+                if seen_palette
+                    || idat != IdatState::NotSeen
+                    || !palette_allowed
+                    || data.is_empty()
+                    || !data.len().is_multiple_of(3)
+                {
+                    return Err(invalid("invalid"));
+                }
+                seen_palette = true;
+            }
+            ChunkType::IDAT => match idat {
+                IdatState::NotSeen | IdatState::InProgress => idat = IdatState::InProgress,
+                IdatState::Done => return Err(invalid("non-consecutive")),
+            },
+            ChunkType::IEND => {
+                if idat == IdatState::NotSeen {
+                    return Err(Error::parse(
+                        ParseError::MissingRequiredChunk(ChunkType::IDAT),
+                        context("found"),
+                    ));
+                }
+                if header.is_some_and(|h| h.color_type == ColorType::Indexed) && !seen_palette {
+                    return Err(Error::parse(
+                        ParseError::MissingRequiredChunk(ChunkType::PLTE),
+                        context("found"),
+                    ));
+                }
+                output.keep(offset, CHUNK_OVERHEAD);
+                break;
+            }
+            _ => {}
+        }
+        output.keep(offset, u64::from(length) + CHUNK_OVERHEAD);
+    }
+
+    Ok(output)
+}
+
+/// Reads the data and CRC of a chunk.
+///
+/// Returns `None` if the CRC doesn't match. Otherwise, returns the chunk data if `retain` is set,
+/// or an empty `Vec` if not.
+async fn read_chunk_data<R: AsyncRead + AsyncSkip + ?Sized>(
+    reader: &mut Reader<'_, R>,
+    chunk_type: ChunkType,
+    length: u32,
+    retain: bool,
+) -> io::Result<Option<Vec<u8>>> {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&chunk_type.0);
+    let mut data = Vec::new();
+    let mut remaining = u64::from(length);
+    while remaining > 0 {
+        let available = reader.fill_buf().await?;
+        if available.is_empty() {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let amount = usize::try_from(remaining).map_or(available.len(), |r| r.min(available.len()));
+        hasher.update(&available[..amount]);
+        if retain {
+            data.extend_from_slice(&available[..amount]);
+        }
+        reader.consume(amount);
+        remaining -= amount as u64;
+    }
+
+    let crc = u32::from_be_bytes(reader.read_array().await?);
+    Ok((hasher.finalize() == crc).then_some(data))
+}
+
+const SIGNATURE: [u8; 8] = *b"\x89PNG\r\n\x1a\n";
+
+const IHDR_LEN: u32 = 13;
+
+/// A palette has at most 256 three-byte entries.
+const MAX_PLTE_LEN: u32 = 3 * 256;
+
+/// The largest chunk length allowed by the PNG specification.
+const MAX_CHUNK_LENGTH: u32 = (1 << 31) - 1;
+
+/// The largest image width or height allowed by the PNG specification.
+const MAX_DIMENSION: u32 = (1 << 31) - 1;
+
+const CRC_LEN: u64 = 4;
+
+/// The length of a chunk's length, type, and CRC fields.
+const CHUNK_OVERHEAD: u64 = 8 + CRC_LEN;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum IdatState {
+    NotSeen,
+    InProgress,
+    Done,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ColorType {
+    Grayscale,
+    Truecolor,
+    Indexed,
+    GrayscaleAlpha,
+    TruecolorAlpha,
+}
+
+impl ColorType {
+    fn from_byte(byte: u8) -> Option<Self> {
+        Some(match byte {
+            0 => Self::Grayscale,
+            2 => Self::Truecolor,
+            3 => Self::Indexed,
+            4 => Self::GrayscaleAlpha,
+            6 => Self::TruecolorAlpha,
+            _ => return None,
+        })
+    }
+
+    fn allows_bit_depth(self, bit_depth: u8) -> bool {
+        match self {
+            Self::Grayscale => [1, 2, 4, 8, 16].contains(&bit_depth),
+            Self::Indexed => [1, 2, 4, 8].contains(&bit_depth),
+            Self::Truecolor | Self::GrayscaleAlpha | Self::TruecolorAlpha => {
+                [8, 16].contains(&bit_depth)
+            }
+        }
+    }
+
+    fn allows_palette(self) -> bool {
+        matches!(self, Self::Indexed | Self::Truecolor | Self::TruecolorAlpha)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct ImageHeader {
+    color_type: ColorType,
+}
+
+impl ImageHeader {
+    fn parse(data: &[u8]) -> Option<Self> {
+        let &[
+            w0,
+            w1,
+            w2,
+            w3,
+            h0,
+            h1,
+            h2,
+            h3,
+            bit_depth,
+            color_type,
+            compression_method,
+            filter_method,
+            interlace_method,
+        ] = data
+        else {
+            return None;
+        };
+        let width = u32::from_be_bytes([w0, w1, w2, w3]);
+        let height = u32::from_be_bytes([h0, h1, h2, h3]);
+        let color_type = ColorType::from_byte(color_type)?;
+        let valid = (1..=MAX_DIMENSION).contains(&width)
+            && (1..=MAX_DIMENSION).contains(&height)
+            && color_type.allows_bit_depth(bit_depth)
+            && compression_method == 0
+            && filter_method == 0
+            && interlace_method <= 1;
+        valid.then_some(Self { color_type })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::executor::block_on;
+    use futures::io::Cursor;
+    use test_case::test_case;
+
+    use super::*;
+
+    fn chunk(chunk_type: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let length = u32::try_from(data.len()).expect("small enough");
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(chunk_type);
+        hasher.update(data);
+        [
+            &length.to_be_bytes()[..],
+            chunk_type,
+            data,
+            &hasher.finalize().to_be_bytes(),
+        ]
+        .concat()
+    }
+
+    fn header(color_type: u8) -> Vec<u8> {
+        chunk(b"IHDR", &[0, 0, 0, 1, 0, 0, 0, 1, 8, color_type, 0, 0, 0])
+    }
+
+    fn data() -> Vec<u8> {
+        chunk(b"IDAT", b"\x78\x9c\x63\x60\x00\x00\x00\x02\x00\x01")
+    }
+
+    fn end() -> Vec<u8> {
+        chunk(b"IEND", &[])
+    }
+
+    fn sanitized_bytes(input: &[u8]) -> Result<Vec<u8>, ParseError> {
+        let result = block_on(sanitize(Cursor::new(input)));
+        let sanitized = match result {
+            Ok(sanitized) => sanitized,
+            Err(Error::Parse(report)) => return Err(report.kind),
+            Err(Error::Io(e)) => panic!("unexpected IO error: {e}"),
+        };
+        Ok(sanitized.apply_to(input))
+    }
+
+    #[test]
+    fn minimal_image_is_unchanged() {
+        let image = [&SIGNATURE[..], &header(0), &data(), &end()].concat();
+        assert_eq!(sanitized_bytes(&image).expect("valid"), image);
+    }
+
+    #[test]
+    fn strips_metadata() {
+        let image = [
+            &SIGNATURE[..],
+            &header(2),
+            &chunk(b"iCCP", b"profile\0\0data"),
+            &chunk(b"sRGB", &[0]),
+            &chunk(b"eXIf", b"MM\0\x2a\0\0\0\x08GPS"),
+            &chunk(b"tEXt", b"Author\0me"),
+            &chunk(b"iTXt", b"XML:com.adobe.xmp\0\0\0\0\0<x:xmpmeta/>"),
+            &chunk(b"tIME", &[7, 233, 1, 1, 0, 0, 0]),
+            &chunk(b"prVt", b"private"),
+            &data(),
+            &data(),
+            &end(),
+            b"trailing data",
+        ]
+        .concat();
+        let expected = [
+            &SIGNATURE[..],
+            &header(2),
+            &chunk(b"iCCP", b"profile\0\0data"),
+            &chunk(b"sRGB", &[0]),
+            &data(),
+            &data(),
+            &end(),
+        ]
+        .concat();
+        assert_eq!(sanitized_bytes(&image).expect("valid"), expected);
+    }
+
+    #[test]
+    fn keeps_color_information() {
+        let image = [
+            &SIGNATURE[..],
+            &header(6),
+            &chunk(b"iCCP", b"Display P3\0\0\x78\x9c"),
+            &chunk(b"cHRM", &[0; 32]),
+            &chunk(b"gAMA", &[0, 0, 0xB1, 0x8F]),
+            &chunk(b"cICP", &[12, 13, 0, 1]),
+            &chunk(b"mDCV", &[0; 24]),
+            &chunk(b"cLLI", &[0; 8]),
+            &data(),
+            &end(),
+        ]
+        .concat();
+        assert_eq!(sanitized_bytes(&image).expect("valid"), image);
+    }
+
+    #[test]
+    fn indexed_color() {
+        let image = [
+            &SIGNATURE[..],
+            &header(3),
+            &chunk(b"PLTE", &[0, 0, 0, 255, 255, 255]),
+            &chunk(b"tRNS", &[0]),
+            &data(),
+            &end(),
+        ]
+        .concat();
+        assert_eq!(sanitized_bytes(&image).expect("valid"), image);
+    }
+
+    #[test]
+    fn bad_crc() {
+        let mut bad_data = data();
+        *bad_data.last_mut().expect("has a CRC") ^= 1;
+        let image = [&SIGNATURE[..], &header(0), &bad_data, &end()].concat();
+        assert_eq!(sanitized_bytes(&image), Err(ParseError::InvalidChunkLayout));
+    }
+
+    #[test_case(b"\xFF\xD8\xFF\xE0" => ParseError::InvalidInput; "not a PNG")]
+    #[test_case(&[&SIGNATURE[..], &header(0)[..10]].concat() => ParseError::TruncatedChunk; "truncated chunk")]
+    #[test_case(&[&SIGNATURE[..], &header(0), &data()].concat() => ParseError::TruncatedChunk; "missing IEND")]
+    #[test_case(&[&SIGNATURE[..], &data(), &end()].concat() => ParseError::MissingRequiredChunk(ChunkType::IHDR); "missing IHDR")]
+    #[test_case(&[&SIGNATURE[..], &header(0), &end()].concat() => ParseError::MissingRequiredChunk(ChunkType::IDAT); "missing IDAT")]
+    #[test_case(&[&SIGNATURE[..], &header(3), &data(), &end()].concat() => ParseError::MissingRequiredChunk(ChunkType::PLTE); "missing PLTE")]
+    #[test_case(&[&SIGNATURE[..], &header(0), &header(0)].concat() => ParseError::InvalidChunkLayout; "two headers")]
+    #[test_case(&[&SIGNATURE[..], &header(5)].concat() => ParseError::InvalidChunkLayout; "bad color type")]
+    #[test_case(&[&SIGNATURE[..], &chunk(b"IHDR", &[0; 13])].concat() => ParseError::InvalidChunkLayout; "zero size")]
+    #[test_case(&[&SIGNATURE[..], &header(0), &chunk(b"PLTE", &[0, 0, 0])].concat() => ParseError::InvalidChunkLayout; "palette for grayscale")]
+    #[test_case(&[&SIGNATURE[..], &header(0), &data(), &chunk(b"gAMA", &[0; 4]), &data()].concat() => ParseError::InvalidChunkLayout; "non-consecutive IDAT")]
+    #[test_case(&[&SIGNATURE[..], &header(0), &chunk(b"ab1d", &[])].concat() => ParseError::InvalidChunkLayout; "non-letter chunk type")]
+    #[test_case(&[&SIGNATURE[..], &header(0), &chunk(b"abcd", &[])].concat() => ParseError::InvalidChunkLayout; "reserved bit set")]
+    #[test_case(&[&SIGNATURE[..], &header(0), &chunk(b"ABCD", &[])].concat() => ParseError::UnsupportedChunk(ChunkType(*b"ABCD")); "unknown critical chunk")]
+    #[test_case(&[&SIGNATURE[..], &header(0), &[0xFF, 0xFF, 0xFF, 0xFF], b"tEXt"].concat() => ParseError::InvalidChunkLayout; "length too large")]
+    fn invalid(input: &[u8]) -> ParseError {
+        sanitized_bytes(input).expect_err("should be rejected")
+    }
+}
@@ -0,0 +1,244 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Shared input handling for the image sanitizers, which copy through only the parts of their
+//! input they've checked.
+
+use std::io;
+use std::pin::Pin;
+
+use futures_util::AsyncRead;
+use futures_util::future::poll_fn;
+use mediasan_common::{AsyncSkip, InputSpan};
+
+/// The result of sanitizing an image.
+///
+/// Writing out each part in [`data`](Self::data), in order, produces the sanitized image. Spans
+/// of the input are in increasing order and never overlap.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SanitizedImage {
+    pub data: Vec<ImagePart>,
+}
+
+/// A piece of a [`SanitizedImage`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImagePart {
+    /// A span of the input, to be copied through unchanged.
+    Input(InputSpan),
+    /// Bytes produced by the sanitizer in place of part of the input, such as a header with its
+    /// embedded metadata removed.
+    Rewritten(Box<[u8]>),
+}
+
+impl ImagePart {
+    pub fn len(&self) -> u64 {
+        match self {
+            Self::Input(span) => span.len,
+            Self::Rewritten(bytes) => bytes.len() as u64,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl SanitizedImage {
+    /// The length of the sanitized image.
+    pub fn len(&self) -> u64 {
+        self.data.iter().map(ImagePart::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Marks `len` bytes at `offset` as part of the output.
+    pub(super) fn keep(&mut self, offset: u64, len: u64) {
+        if let Some(ImagePart::Input(last)) = self.data.last_mut()
+            && last.offset + last.len == offset
+        {
+            last.len += len;
+        } else {
+            self.data.push(ImagePart::Input(InputSpan { offset, len }));
+        }
+    }
+
+    /// Adds `bytes` to the output, in place of whatever part of the input was just skipped.
+    pub(super) fn rewrite(&mut self, bytes: Vec<u8>) {
+        self.data
+            .push(ImagePart::Rewritten(bytes.into_boxed_slice()));
+    }
+
+    /// Assembles the sanitized image from the input it was produced from.
+    #[cfg(test)]
+    pub(super) fn apply_to(&self, input: &[u8]) -> Vec<u8> {
+        self.data
+            .iter()
+            .flat_map(|part| match part {
+                ImagePart::Input(span) => {
+                    let start = usize::try_from(span.offset).expect("small");
+                    let len = usize::try_from(span.len).expect("small");
+                    &input[start..][..len]
+                }
+                ImagePart::Rewritten(bytes) => &bytes[..],
+            })
+            .copied()
+            .collect()
+    }
+}
+
+/// How much to read from the input at once when scanning byte-by-byte.
+const BUFFER_SIZE: usize = 8 * 1024;
+
+/// A buffered reader that keeps track of its offset within the input.
+///
+/// Skips that go past the end of the buffer are passed through to the underlying [`AsyncSkip`]
+/// implementation, so large regions that don't need checking never have to be read.
+pub(super) struct Reader<'a, R: ?Sized> {
+    input: Pin<&'a mut R>,
+    buf: Box<[u8]>,
+    start: usize,
+    end: usize,
+    /// The offset in the input of `buf[start]`.
+    position: u64,
+}
+
+impl<'a, R: AsyncRead + AsyncSkip + ?Sized> Reader<'a, R> {
+    pub(super) async fn new(mut input: Pin<&'a mut R>) -> io::Result<Self> {
+        let position = poll_fn(|cx| input.as_mut().poll_stream_position(cx)).await?;
+        Ok(Self {
+            input,
+            buf: vec![0; BUFFER_SIZE].into_boxed_slice(),
+            start: 0,
+            end: 0,
+            position,
+        })
+    }
+
+    /// The offset in the input of the next byte to be read.
+    pub(super) fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Returns the currently buffered bytes, reading more if there aren't any.
+    ///
+    /// An empty result means the end of the input has been reached.
+    pub(super) async fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.start == self.end {
+            let Self { input, buf, .. } = self;
+            let amount_read = poll_fn(|cx| input.as_mut().poll_read(cx, &mut buf[..])).await?;
+            self.start = 0;
+            self.end = amount_read;
+        }
+        Ok(&self.buf[self.start..self.end])
+    }
+
+    /// Marks `amount` bytes returned by [`Self::fill_buf`] as read.
+    pub(super) fn consume(&mut self, amount: usize) {
+        assert!(
+            amount <= self.end - self.start,
+            "consumed more than was buffered"
+        );
+        self.start += amount;
+        self.position += amount as u64;
+    }
+
+    pub(super) async fn read_exact(&mut self, out: &mut [u8]) -> io::Result<()> {
+        let mut filled = 0;
+        while filled < out.len() {
+            let available = self.fill_buf().await?;
+            if available.is_empty() {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            let amount = available.len().min(out.len() - filled);
+            out[filled..][..amount].copy_from_slice(&available[..amount]);
+            self.consume(amount);
+            filled += amount;
+        }
+        Ok(())
+    }
+
+    pub(super) async fn read_array<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let mut result = [0; N];
+        self.read_exact(&mut result).await?;
+        Ok(result)
+    }
+
+    pub(super) async fn read_u8(&mut self) -> io::Result<u8> {
+        let [byte] = self.read_array().await?;
+        Ok(byte)
+    }
+
+    pub(super) async fn skip(&mut self, amount: u64) -> io::Result<()> {
+        let buffered = self.end - self.start;
+        if let Ok(amount) = usize::try_from(amount)
+            && amount <= buffered
+        {
+            self.consume(amount);
+            return Ok(());
+        }
+
+        self.consume(buffered);
+        let remaining = amount - buffered as u64;
+        poll_fn(|cx| self.input.as_mut().poll_skip(cx, remaining)).await?;
+        self.position = self
+            .position
+            .checked_add(remaining)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "input length overflow"))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::executor::block_on;
+    use futures::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn keep_merges_adjacent_spans() {
+        let mut image = SanitizedImage::default();
+        image.keep(0, 2);
+        image.keep(2, 3);
+        image.rewrite(vec![0xAA; 4]);
+        image.keep(5, 1);
+        image.keep(10, 1);
+        assert_eq!(
+            image.data,
+            [
+                ImagePart::Input(InputSpan { offset: 0, len: 5 }),
+                ImagePart::Rewritten([0xAA; 4].into()),
+                ImagePart::Input(InputSpan { offset: 5, len: 1 }),
+                ImagePart::Input(InputSpan { offset: 10, len: 1 }),
+            ]
+        );
+        assert_eq!(image.len(), 11);
+    }
+
+    #[test]
+    fn skip_past_buffer() {
+        let input = (0..=255).cycle().take(3 * BUFFER_SIZE).collect::<Vec<u8>>();
+        let mut input = Cursor::new(input);
+        block_on(async {
+            let mut reader = Reader::new(Pin::new(&mut input)).await.expect("can start");
+            assert_eq!(reader.read_u8().await.expect("can read"), 0);
+            reader.skip(1).await.expect("can skip within buffer");
+            assert_eq!(reader.read_u8().await.expect("can read"), 2);
+
+            reader
+                .skip(2 * BUFFER_SIZE as u64)
+                .await
+                .expect("can skip past buffer");
+            let expected_position = 3 + 2 * BUFFER_SIZE as u64;
+            assert_eq!(reader.position(), expected_position);
+            assert_eq!(
+                reader.read_u8().await.expect("can read"),
+                u8::try_from(expected_position % 256).expect("in range")
+            );
+        });
+    }
+}
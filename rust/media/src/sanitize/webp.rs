@@ -3,11 +3,36 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use std::io::Read;
+
+use mediasan_common::Skip;
 pub use webpsan::parse::ParseError;
 pub use webpsan::sanitize;
 
+use super::observe::{Capturer, Observed};
+
+mod info;
+pub use info::WebpInfo;
+
 /// Error type returned by [`sanitize`].
 pub type Error = super::error::SanitizerError<ParseError>;
 
 /// A decomposed and stringified [`error_stack::Report<ParseError>`](mediasan_common::Error::Parse).
 pub type ParseErrorReport = super::error::ParseErrorReport<ParseError>;
+
+/// Sanitize a WebP input, also describing its dimensions and animation.
+///
+/// This is the same as [`sanitize`], but watches the input as it's read to find the image's
+/// top-level chunks.
+///
+/// # Errors
+///
+/// If the input cannot be parsed, or an IO error occurs, an `Error` is returned.
+pub fn sanitize_with_info<R: Read + Skip>(input: R) -> Result<WebpInfo, Error> {
+    let mut capturer = Capturer::new(info::ChunkWalker::default());
+    sanitize(Observed {
+        inner: input,
+        capturer: &mut capturer,
+    })?;
+    Ok(capturer.into_walker().into_info())
+}
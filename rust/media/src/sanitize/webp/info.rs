@@ -0,0 +1,214 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use crate::sanitize::observe::Walker;
+
+/// Information about a WebP image, read from its top-level chunks.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WebpInfo {
+    /// The canvas width and height in pixels, if they could be determined.
+    pub dimensions: Option<(u32, u32)>,
+    /// Whether the image is marked as animated.
+    pub animated: bool,
+    /// The number of frames: the number of `ANMF` chunks for an animated image, and 1 otherwise.
+    pub frame_count: u32,
+}
+
+const RIFF_HEADER_LEN: usize = 12;
+const CHUNK_HEADER_LEN: usize = 8;
+
+/// How much of each chunk's payload is needed to read the dimensions.
+const VP8X_PREFIX_LEN: usize = 10;
+const VP8_PREFIX_LEN: usize = 10;
+const VP8L_PREFIX_LEN: usize = 5;
+
+const VP8X_ANIMATION_FLAG: u8 = 0x02;
+const VP8_START_CODE: [u8; 3] = [0x9d, 0x01, 0x2a];
+const VP8L_SIGNATURE: u8 = 0x2f;
+
+/// Reads the header of each top-level chunk as the sanitizer reads through the input.
+#[derive(Default)]
+pub(super) struct ChunkWalker {
+    riff_end: u64,
+    /// The chunk whose payload prefix is being captured, and the offset of the chunk after it.
+    payload: Option<([u8; 4], u64)>,
+    info: WebpInfo,
+    animation_frames: u32,
+}
+
+impl ChunkWalker {
+    pub(super) fn into_info(self) -> WebpInfo {
+        let Self {
+            mut info,
+            animation_frames,
+            ..
+        } = self;
+        info.frame_count = animation_frames.max(1);
+        info
+    }
+
+    fn next_chunk(&self, offset: u64) -> Option<(u64, usize)> {
+        (offset + CHUNK_HEADER_LEN as u64 <= self.riff_end).then_some((offset, CHUNK_HEADER_LEN))
+    }
+
+    fn found_chunk(&mut self, offset: u64, header: &[u8]) -> Option<(u64, usize)> {
+        let fourcc: [u8; 4] = header[..4].try_into().expect("correct length");
+        let size = u32::from_le_bytes(header[4..].try_into().expect("correct length"));
+        let payload_offset = offset + CHUNK_HEADER_LEN as u64;
+        let next = payload_offset + u64::from(size) + u64::from(size % 2);
+
+        let prefix_len = match &fourcc {
+            b"VP8X" => VP8X_PREFIX_LEN,
+            b"VP8 " => VP8_PREFIX_LEN,
+            b"VP8L" => VP8L_PREFIX_LEN,
+            b"ANMF" => {
+                self.animation_frames = self.animation_frames.saturating_add(1);
+                return self.next_chunk(next);
+            }
+            _ => return self.next_chunk(next),
+        };
+        if u64::from(size) < prefix_len as u64 {
+            return self.next_chunk(next);
+        }
+        self.payload = Some((fourcc, next));
+        Some((payload_offset, prefix_len))
+    }
+
+    fn found_payload(&mut self, fourcc: [u8; 4], prefix: &[u8]) {
+        match &fourcc {
+            b"VP8X" => {
+                self.info.animated = prefix[0] & VP8X_ANIMATION_FLAG != 0;
+                self.info.dimensions =
+                    Some((read_u24(&prefix[4..]) + 1, read_u24(&prefix[7..]) + 1));
+            }
+            // The canvas size from VP8X takes precedence when present.
+            _ if self.info.dimensions.is_some() => {}
+            b"VP8 " if prefix[3..6] == VP8_START_CODE => {
+                let width = u16::from_le_bytes([prefix[6], prefix[7]]) & 0x3fff;
+                let height = u16::from_le_bytes([prefix[8], prefix[9]]) & 0x3fff;
+                self.info.dimensions = Some((width.into(), height.into()));
+            }
+            b"VP8L" if prefix[0] == VP8L_SIGNATURE => {
+                let bits = u32::from_le_bytes(prefix[1..5].try_into().expect("correct length"));
+                let width = (bits & 0x3fff) + 1;
+                let height = ((bits >> 14) & 0x3fff) + 1;
+                self.info.dimensions = Some((width, height));
+            }
+            _ => {}
+        }
+    }
+}
+
+fn read_u24(data: &[u8]) -> u32 {
+    u32::from_le_bytes([data[0], data[1], data[2], 0])
+}
+
+impl Walker for ChunkWalker {
+    fn first_capture(&mut self) -> Option<(u64, usize)> {
+        Some((0, RIFF_HEADER_LEN))
+    }
+
+    fn captured(&mut self, offset: u64, data: Vec<u8>) -> Option<(u64, usize)> {
+        if offset == 0 {
+            if &data[..4] != b"RIFF" || &data[8..12] != b"WEBP" {
+                return None;
+            }
+            let riff_size = u32::from_le_bytes(data[4..8].try_into().expect("correct length"));
+            self.riff_end = 8 + u64::from(riff_size);
+            return self.next_chunk(RIFF_HEADER_LEN as u64);
+        }
+        match self.payload.take() {
+            Some((fourcc, next)) => {
+                self.found_payload(fourcc, &data);
+                self.next_chunk(next)
+            }
+            None => self.found_chunk(offset, &data),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sanitize::observe::Capturer;
+
+    fn chunk(fourcc: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let size = u32::try_from(payload.len()).expect("small enough");
+        let padding: &[u8] = if payload.len() % 2 == 1 { &[0] } else { &[] };
+        [&fourcc[..], &size.to_le_bytes(), payload, padding].concat()
+    }
+
+    fn riff(chunks: &[Vec<u8>]) -> Vec<u8> {
+        let body = chunks.concat();
+        let size = u32::try_from(4 + body.len()).expect("small enough");
+        [&b"RIFF"[..], &size.to_le_bytes(), b"WEBP", &body].concat()
+    }
+
+    fn walk(input: &[u8]) -> WebpInfo {
+        let mut capturer = Capturer::new(ChunkWalker::default());
+        for piece in input.chunks(3) {
+            capturer.observe_read(piece);
+        }
+        capturer.into_walker().into_info()
+    }
+
+    #[test]
+    fn lossy() {
+        let vp8 = [
+            0x10, 0x02, 0x00, 0x9d, 0x01, 0x2a, 0x40, 0x01, 0xf0, 0x00, 0xaa,
+        ];
+        let info = walk(&riff(&[chunk(b"VP8 ", &vp8)]));
+        assert_eq!(
+            info,
+            WebpInfo {
+                dimensions: Some((320, 240)),
+                animated: false,
+                frame_count: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn lossless() {
+        // 100x50, stored as width - 1 and height - 1 in 14 bits each.
+        let bits: u32 = 99 | (49 << 14);
+        let vp8l = [&[VP8L_SIGNATURE][..], &bits.to_le_bytes(), &[0xaa; 3]].concat();
+        let info = walk(&riff(&[chunk(b"VP8L", &vp8l)]));
+        assert_eq!(info.dimensions, Some((100, 50)));
+        assert_eq!(info.frame_count, 1);
+    }
+
+    #[test]
+    fn animated() {
+        // 320x240, stored as width - 1 and height - 1.
+        let mut vp8x = [0; 10];
+        vp8x[0] = VP8X_ANIMATION_FLAG;
+        vp8x[4..7].copy_from_slice(&[0x3f, 0x01, 0x00]);
+        vp8x[7..10].copy_from_slice(&[0xef, 0x00, 0x00]);
+        let frame = chunk(b"ANMF", &[0; 21]);
+        let input = riff(&[
+            chunk(b"VP8X", &vp8x),
+            chunk(b"ANIM", &[0; 6]),
+            frame.clone(),
+            frame.clone(),
+            frame,
+        ]);
+        assert_eq!(
+            walk(&input),
+            WebpInfo {
+                dimensions: Some((320, 240)),
+                animated: true,
+                frame_count: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn not_webp() {
+        let mut input = riff(&[chunk(b"VP8L", &[0; 5])]);
+        input[8..12].copy_from_slice(b"WAVE");
+        assert_eq!(walk(&input).dimensions, None);
+    }
+}
@@ -3,23 +3,21 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
-use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::task::ready;
 
 use clap::Parser;
 use clap_stdin::FileOrStdin;
-use futures::future::Either;
 use futures::io::Cursor;
 use libsignal_cli_utils::read_file;
-use libsignal_message_backup::frame::{FramesReader, LimitedReaderFactory};
-use libsignal_message_backup::parse::VarintDelimitedReader;
+use libsignal_message_backup::backup::Purpose;
+use libsignal_message_backup::frame::LimitedReaderFactory;
+use libsignal_message_backup::stats::BackupStatistics;
+use libsignal_message_backup::{BackupReader, ReadResult};
 
 #[path = "../src/bin/support/mod.rs"]
 mod support;
-use libsignal_message_backup::proto;
-use protobuf::Message;
 use support::KeyArgs;
 
 #[derive(Parser)]
@@ -93,125 +91,103 @@ fn main() {
     let contents = read_file(input);
     eprintln!("read {} bytes", contents.len());
 
-    println!("frame\tcount\tcomp_size\traw_size");
-
-    futures::executor::block_on(async {
-        let position = Arc::<AtomicU64>::default();
-
-        let reader = if let Some(key) = key {
-            Either::Left(
-                FramesReader::new(
-                    &key,
-                    LimitedReaderFactory::new([
-                        // FramesReader consumes the first reader to validate the HMAC...
-                        TrackingCursor {
-                            inner: Cursor::new(&contents),
-                            pos: Default::default(),
-                        },
-                        // ...then uses the second reader for the actual file contents.
-                        TrackingCursor {
-                            inner: Cursor::new(&contents),
-                            pos: position.clone(),
-                        },
-                    ]),
-                )
-                .await
-                .expect("valid HMAC"),
+    let position = Arc::<AtomicU64>::default();
+    let current_position = || position.load(Ordering::SeqCst);
+
+    let (
+        start,
+        ReadResult {
+            result,
+            found_unknown_fields,
+        },
+    ) = futures::executor::block_on(async {
+        if let Some(key) = key {
+            let reader = BackupReader::new_encrypted_compressed(
+                &key,
+                LimitedReaderFactory::new([
+                    // FramesReader consumes the first reader to validate the HMAC...
+                    TrackingCursor {
+                        inner: Cursor::new(&contents),
+                        pos: Default::default(),
+                    },
+                    // ...then uses the second reader for the actual file contents.
+                    TrackingCursor {
+                        inner: Cursor::new(&contents),
+                        pos: position.clone(),
+                    },
+                ]),
+                Purpose::RemoteBackup,
             )
-        } else {
-            Either::Right(TrackingCursor {
-                inner: Cursor::new(&contents),
-                pos: position.clone(),
-            })
-        };
-
-        let mut reader = VarintDelimitedReader::new(reader);
-
-        let start = position.load(Ordering::SeqCst);
-        print_row("header", 1, start, start);
-
-        let backup_info = reader
-            .read_next()
             .await
-            .expect("can read")
-            .expect("not empty");
-        let mut pos = position.load(Ordering::SeqCst);
-        print_row(
-            "BackupInfo",
-            1,
-            pos - start,
-            backup_info.len().try_into().unwrap(),
-        );
-
-        let mut stats: HashMap<&'static str, (u64, u64, u64)> = HashMap::new();
-        while let Some(frame) = reader.read_next().await.expect("can read") {
-            let frame_end = position.load(Ordering::SeqCst);
-            let frame_proto = proto::backup::Frame::parse_from_bytes(&frame).expect("valid proto");
-            let key = match frame_proto.item.expect("not empty") {
-                proto::backup::frame::Item::ChatItem(ci) => match ci.item.expect("not empty") {
-                    proto::backup::chat_item::Item::StandardMessage(_) => {
-                        "ChatItem.StandardMessage"
-                    }
-                    proto::backup::chat_item::Item::ContactMessage(_) => "ChatItem.ContactMessage",
-                    proto::backup::chat_item::Item::StickerMessage(_) => "ChatItem.StickerMessage",
-                    proto::backup::chat_item::Item::RemoteDeletedMessage(_) => {
-                        "ChatItem.RemoteDeletedMessage"
-                    }
-                    proto::backup::chat_item::Item::UpdateMessage(_) => "ChatItem.UpdateMessage",
-                    proto::backup::chat_item::Item::PaymentNotification(_) => {
-                        "ChatItem.PaymentNotification"
-                    }
-                    proto::backup::chat_item::Item::GiftBadge(_) => "ChatItem.GiftBadge",
-                    proto::backup::chat_item::Item::ViewOnceMessage(_) => {
-                        "ChatItem.ViewOnceMessage"
-                    }
-                    proto::backup::chat_item::Item::DirectStoryReplyMessage(_) => {
-                        "ChatItem.DirectStoryReplyMessage"
-                    }
-                    proto::backup::chat_item::Item::Poll(_) => "ChatItem.Poll",
-                    _ => "ChatItem.unknown",
+            .expect("valid HMAC");
+            let start = current_position();
+            (
+                start,
+                reader
+                    .collect_statistics_with_input_position(current_position)
+                    .await,
+            )
+        } else {
+            let reader = BackupReader::new_unencrypted(
+                TrackingCursor {
+                    inner: Cursor::new(&contents),
+                    pos: position.clone(),
                 },
-                proto::backup::frame::Item::Recipient(r) => {
-                    match r.destination.expect("not empty") {
-                        proto::backup::recipient::Destination::Contact(_) => "Recipient.Contact",
-                        proto::backup::recipient::Destination::Group(_) => "Recipient.Group",
-                        proto::backup::recipient::Destination::DistributionList(_) => {
-                            "Recipient.DistributionList"
-                        }
-                        proto::backup::recipient::Destination::Self_(_) => "Recipient.Self",
-                        proto::backup::recipient::Destination::ReleaseNotes(_) => {
-                            "Recipient.ReleaseNotes"
-                        }
-                        proto::backup::recipient::Destination::CallLink(_) => "Recipient.CallLink",
-                        _ => "Recipient.unknown",
-                    }
-                }
-                proto::backup::frame::Item::Account(_) => "Account",
-                proto::backup::frame::Item::Chat(_) => "Chat",
-                proto::backup::frame::Item::StickerPack(_) => "StickerPack",
-                proto::backup::frame::Item::AdHocCall(_) => "AdHocCall",
-                proto::backup::frame::Item::NotificationProfile(_) => "NotificationProfile",
-                proto::backup::frame::Item::ChatFolder(_) => "ChatFolder",
-                _ => "Unknown",
-            };
-            let (count, compressed_size, raw_size) = stats.entry(key).or_default();
-            *count += 1;
-            *compressed_size += frame_end - pos;
-            *raw_size += u64::try_from(frame.len()).unwrap();
-            pos = frame_end;
+                Purpose::RemoteBackup,
+            );
+            (
+                0,
+                reader
+                    .collect_statistics_with_input_position(current_position)
+                    .await,
+            )
         }
+    });
 
-        // This won't print the frames in the same order every time, but that's fine; the consumer
-        // will almost certainly want to sort them anyway.
-        for (k, (c, csz, rsz)) in stats {
-            print_row(k, c, csz, rsz);
-        }
+    for field in found_unknown_fields {
+        eprintln!("{field}");
+    }
 
+    let stats = result.expect("can read");
+    let BackupStatistics {
+        frames,
+        attachments,
+        messages_per_chat,
+        unknown_field_count: _,
+    } = &stats;
+
+    println!("frame\tcount\tcomp_size\traw_size");
+    print_row("header", 1, start, start);
+    for (label, frame_stats) in frames {
         print_row(
-            "padding",
-            1,
-            u64::try_from(contents.len()).unwrap() - pos,
-            0,
+            label,
+            frame_stats.count,
+            frame_stats.compressed_len,
+            frame_stats.total_len,
         );
-    });
+    }
+    let end_of_frames = start + stats.total_compressed_len();
+    print_row(
+        "padding",
+        1,
+        u64::try_from(contents.len()).unwrap() - end_of_frames,
+        0,
+    );
+
+    eprintln!(
+        "{} attachments totalling {} bytes",
+        attachments.count, attachments.total_size
+    );
+    eprintln!(
+        "{} chats, at most {} messages in a single chat",
+        messages_per_chat.len(),
+        messages_per_chat
+            .values()
+            .max()
+            .copied()
+            .unwrap_or_default()
+    );
+    if let Some(ratio) = stats.compression_ratio(contents.len().try_into().expect("fits in u64")) {
+        eprintln!("compression ratio: {ratio:.2}");
+    }
 }
@@ -32,6 +32,7 @@ use crate::backup::sticker::{PackId as StickerPackId, StickerPack, StickerPackEr
 use crate::backup::time::{
     ReportUnusualTimestamp, Timestamp, TimestampError, TimestampIssue, UnusualTimestampTracker,
 };
+use crate::migrate::Migrations;
 use crate::proto::backup as proto;
 use crate::proto::backup::frame::Item as FrameItem;
 #[cfg(feature = "json")]
@@ -96,6 +97,8 @@ pub struct PartialBackup<M: Method + ReferencedTypes> {
     chat_folders: Vec<ChatFolder<M::RecipientReference>>,
     /// Stored here so PartialBackup can be the only context necessary for processing backup frames.
     unusual_timestamp_tracker: RefCell<UnusualTimestampTracker>,
+    /// Rewrites applied to frames before they're validated, based on the backup version.
+    migrations: Migrations,
 }
 
 #[derive_where(Debug)]
@@ -226,6 +229,7 @@ impl<M: Method + ReferencedTypes> TryFrom<PartialBackup<M>> for CompletedBackup<
             notification_profiles,
             chat_folders,
             unusual_timestamp_tracker: _,
+            migrations: _,
         } = value;
 
         let account_data = account_data.ok_or(CompletionError::MissingAccountData)?;
@@ -668,9 +672,17 @@ impl<M: Method + ReferencedTypes> PartialBackup<M> {
             notification_profiles: Default::default(),
             chat_folders: Default::default(),
             unusual_timestamp_tracker,
+            migrations: Migrations::for_version(version),
         })
     }
 
+    /// Rewrites `frame` from the shape used by this backup's version to the current one.
+    ///
+    /// See [`crate::migrate`].
+    pub(crate) fn migrate_frame(&self, frame: &mut proto::Frame) {
+        self.migrations.apply(frame)
+    }
+
     pub fn add_frame(&mut self, frame: proto::Frame) -> Result<(), ValidationError> {
         self.add_frame_item(frame.item.ok_or_else(|| {
             ValidationError::EmptyFrame(HasUnknownFields::check(&frame.special_fields))
@@ -28,7 +28,9 @@ pub mod args;
 pub mod backup;
 pub mod frame;
 pub mod key;
+mod migrate;
 pub mod parse;
+pub mod stats;
 pub mod unknown;
 
 #[cfg(feature = "json")]
@@ -193,6 +195,36 @@ impl<R: AsyncRead + Unpin + VerifyHmac> BackupReader<R> {
             result,
         }
     }
+
+    /// Summarizes the contents of the backup without validating them.
+    ///
+    /// See [`stats`] for more details.
+    pub async fn collect_statistics(self) -> ReadResult<stats::BackupStatistics> {
+        self.collect_statistics_with_input_position(|| 0).await
+    }
+
+    /// Like [`Self::collect_statistics`], but also attributes consumed input to each kind of frame.
+    ///
+    /// `input_position` should report how many bytes have been read from the underlying backup
+    /// file so far. See [`stats::FrameStatistics::compressed_len`].
+    pub async fn collect_statistics_with_input_position(
+        self,
+        input_position: impl Fn() -> u64,
+    ) -> ReadResult<stats::BackupStatistics> {
+        let Self {
+            reader,
+            visitor: _,
+            purpose: _,
+        } = self;
+
+        let mut found_unknown_fields = Vec::new();
+        let result =
+            stats::collect_statistics(reader, input_position, &mut found_unknown_fields).await;
+        ReadResult {
+            found_unknown_fields,
+            result,
+        }
+    }
 }
 
 impl<R: AsyncRead + Unpin> BackupReader<UnvalidatedHmacReader<R>> {
@@ -364,6 +396,8 @@ impl<M: backup::method::Method + backup::ReferencedTypes> backup::PartialBackup<
         // case of the Result. (This is guaranteed equivalent by protobuf.)
         let mut frame_proto = proto::backup::Frame::new();
         frame_proto.merge_from_bytes(raw_frame)?;
+        // Migrate before looking for unknown fields, since a migration may have handled some.
+        self.migrate_frame(&mut frame_proto);
         visitor(&frame_proto);
         let unknown_fields = frame_proto.collect_unknown_fields();
         self.add_frame(frame_proto)?;
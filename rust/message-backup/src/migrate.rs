@@ -0,0 +1,437 @@
+//
+// Copyright 2026 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Rewriting of frames from other versions of the backup format.
+//!
+//! Frames are migrated after they're parsed from the input stream but before they're validated,
+//! so the validation code only ever has to deal with the current shape of the protos. Migrations
+//! also run before unknown fields are collected, so a migration that moves a legacy field (which
+//! the current protos no longer know about) into its replacement will keep it from being reported
+//! as unknown.
+//!
+//! To add a migration, append it to [`REGISTERED_MIGRATIONS`] with the range of
+//! `BackupInfo.version`s it applies to. Migrations for a particular version are applied in the
+//! order they're listed.
+
+use std::ops::RangeInclusive;
+
+use protobuf::reflect::{ReflectValueBox, ReflectValueRef, RuntimeFieldType, RuntimeType};
+use protobuf::{MessageDyn, UnknownValueRef};
+
+use crate::proto::backup as proto;
+use crate::stats::visit_file_pointers;
+
+/// A single transformation on backup frames.
+#[derive(Debug)]
+pub(crate) struct Migration {
+    /// Short description of the migration, for logging.
+    pub(crate) name: &'static str,
+    /// The backup versions the migration should be applied to.
+    pub(crate) versions: RangeInclusive<u64>,
+    /// Rewrites a single frame in place.
+    ///
+    /// Migrations must be idempotent: applying one to a frame that's already in the current shape
+    /// should leave the frame unchanged.
+    pub(crate) migrate: fn(&mut proto::Frame),
+}
+
+/// Every migration known to this version of libsignal.
+static REGISTERED_MIGRATIONS: &[Migration] = &[Migration {
+    name: "LocatorInfo.legacyDigest to encryptedDigest",
+    versions: 0..=1,
+    migrate: migrate_legacy_attachment_digests,
+}];
+
+/// The field number used for an attachment's encrypted digest before it moved into
+/// `LocatorInfo.integrityCheck`. Now reserved as `legacyDigest`.
+const LEGACY_DIGEST_TAG: u32 = 2;
+
+/// Moves `legacyDigest` into `encryptedDigest` for every attachment in the frame.
+///
+/// Early version 1 backups recorded the digest of an attachment that hadn't been downloaded in a
+/// field that has since been removed. Without this, such attachments would fail validation for
+/// having no integrity check.
+fn migrate_legacy_attachment_digests(frame: &mut proto::Frame) {
+    migrate_file_pointers(frame)
+}
+
+/// Applies [`migrate_file_pointer`] to every [`proto::FilePointer`] reachable from `message`.
+///
+/// Like [`visit_file_pointers`], this walks the message dynamically so that file pointers in
+/// revisions, wallpapers, and any other place aren't missed. Only the parts of the message that
+/// actually contain a legacy digest are touched, so most frames are left alone.
+fn migrate_file_pointers(message: &mut dyn MessageDyn) {
+    if let Some(pointer) = message.downcast_mut::<proto::FilePointer>() {
+        migrate_file_pointer(pointer);
+        return;
+    }
+
+    for field in message.descriptor_dyn().fields() {
+        match field.runtime_field_type() {
+            RuntimeFieldType::Singular(RuntimeType::Message(_)) => {
+                // Check presence first; `mut_message` would insert a default value.
+                if field.has_field(&*message)
+                    && contains_legacy_digest(&*field.get_message(&*message))
+                {
+                    migrate_file_pointers(field.mut_message(message));
+                }
+            }
+            RuntimeFieldType::Repeated(RuntimeType::Message(_)) => {
+                // Repeated fields can't be modified in place through reflection, so replace the
+                // affected elements with migrated copies.
+                let mut values = field.mut_repeated(message);
+                for i in 0..values.len() {
+                    let ReflectValueRef::Message(value) = values.get(i) else {
+                        continue;
+                    };
+                    if !contains_legacy_digest(&*value) {
+                        continue;
+                    }
+                    let mut value = value.clone_box();
+                    migrate_file_pointers(&mut *value);
+                    values.set(i, ReflectValueBox::Message(value));
+                }
+            }
+            RuntimeFieldType::Map(_, _) => {
+                // The backup protos don't have any maps, so there's nothing to reach here.
+            }
+            RuntimeFieldType::Singular(_) | RuntimeFieldType::Repeated(_) => {}
+        }
+    }
+}
+
+fn contains_legacy_digest(message: &dyn MessageDyn) -> bool {
+    let mut found = false;
+    visit_file_pointers(message, &mut |pointer| {
+        found |= pointer.locatorInfo.as_ref().is_some_and(|locator| {
+            locator
+                .special_fields
+                .unknown_fields()
+                .get(LEGACY_DIGEST_TAG)
+                .is_some()
+        })
+    });
+    found
+}
+
+fn migrate_file_pointer(pointer: &mut proto::FilePointer) {
+    let Some(locator) = pointer.locatorInfo.as_mut() else {
+        return;
+    };
+
+    let unknown_fields = locator.special_fields.mut_unknown_fields();
+    let Some(UnknownValueRef::LengthDelimited(digest)) = unknown_fields.get(LEGACY_DIGEST_TAG)
+    else {
+        return;
+    };
+    let digest = digest.to_vec();
+    unknown_fields.remove(LEGACY_DIGEST_TAG);
+
+    // If the current field is already present, it takes precedence.
+    if locator.integrityCheck.is_none() && !digest.is_empty() {
+        locator.integrityCheck =
+            Some(proto::file_pointer::locator_info::IntegrityCheck::EncryptedDigest(digest));
+    }
+}
+
+/// The set of migrations that apply to a particular backup.
+#[derive(Debug, Default)]
+pub(crate) struct Migrations {
+    applicable: Vec<&'static Migration>,
+}
+
+impl Migrations {
+    /// Selects the registered migrations that apply to a backup with the given version.
+    pub(crate) fn for_version(version: u64) -> Self {
+        Self::from_registry(REGISTERED_MIGRATIONS, version)
+    }
+
+    fn from_registry(registry: &'static [Migration], version: u64) -> Self {
+        let applicable: Vec<_> = registry
+            .iter()
+            .filter(|migration| migration.versions.contains(&version))
+            .collect();
+        for migration in &applicable {
+            log::info!(
+                "applying migration '{}' to version {version} backup",
+                migration.name
+            );
+        }
+        Self { applicable }
+    }
+
+    pub(crate) fn apply(&self, frame: &mut proto::Frame) {
+        for migration in &self.applicable {
+            (migration.migrate)(frame)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use const_str::hex;
+    use protobuf::Message as _;
+    use test_case::test_case;
+
+    use super::*;
+    use crate::unknown::VisitUnknownFieldsExt as _;
+
+    /// A field number that isn't used in the current `ChatItem` definition.
+    const LEGACY_DATE_SENT_TAG: u32 = 100;
+
+    /// Pretends that `dateSent` used to live in a different field.
+    fn move_legacy_date_sent(frame: &mut proto::Frame) {
+        let Some(proto::frame::Item::ChatItem(chat_item)) = &mut frame.item else {
+            return;
+        };
+        let unknown_fields = chat_item.special_fields.mut_unknown_fields();
+        let Some(UnknownValueRef::Varint(date_sent)) = unknown_fields.get(LEGACY_DATE_SENT_TAG)
+        else {
+            return;
+        };
+        unknown_fields.remove(LEGACY_DATE_SENT_TAG);
+        chat_item.dateSent = date_sent;
+    }
+
+    fn set_author(frame: &mut proto::Frame) {
+        if let Some(proto::frame::Item::ChatItem(chat_item)) = &mut frame.item {
+            chat_item.authorId = chat_item.dateSent;
+        }
+    }
+
+    static TEST_MIGRATIONS: &[Migration] = &[
+        Migration {
+            name: "legacy dateSent",
+            versions: 0..=1,
+            migrate: move_legacy_date_sent,
+        },
+        Migration {
+            name: "authorId from dateSent",
+            versions: 1..=1,
+            migrate: set_author,
+        },
+    ];
+
+    fn legacy_chat_item_frame() -> proto::Frame {
+        let mut chat_item = proto::ChatItem::default();
+        chat_item
+            .special_fields
+            .mut_unknown_fields()
+            .add_varint(LEGACY_DATE_SENT_TAG, 12345);
+        proto::Frame {
+            item: Some(chat_item.into()),
+            ..Default::default()
+        }
+    }
+
+    #[test_case(0, 12345, 0; "only first")]
+    #[test_case(1, 12345, 12345; "both in order")]
+    #[test_case(2, 0, 0; "none")]
+    fn applies_migrations_for_version(version: u64, date_sent: u64, author_id: u64) {
+        let migrations = Migrations::from_registry(TEST_MIGRATIONS, version);
+
+        let mut frame = legacy_chat_item_frame();
+        migrations.apply(&mut frame);
+
+        let Some(proto::frame::Item::ChatItem(chat_item)) = &frame.item else {
+            panic!("still a chat item");
+        };
+        assert_eq!(chat_item.dateSent, date_sent);
+        assert_eq!(chat_item.authorId, author_id);
+    }
+
+    #[test]
+    fn migrated_fields_are_not_unknown() {
+        let mut frame = legacy_chat_item_frame();
+        assert_eq!(frame.collect_unknown_fields().len(), 1);
+
+        Migrations::from_registry(TEST_MIGRATIONS, 0).apply(&mut frame);
+        assert!(frame.collect_unknown_fields().is_empty());
+    }
+
+    #[test]
+    fn migrations_are_idempotent() {
+        let migrations = Migrations::from_registry(TEST_MIGRATIONS, 1);
+
+        let mut frame = legacy_chat_item_frame();
+        migrations.apply(&mut frame);
+        let once = frame.clone();
+        migrations.apply(&mut frame);
+        assert_eq!(frame, once);
+    }
+
+    /// A version 1 `ChatItem` frame whose only attachment has its digest in `legacyDigest`.
+    const LEGACY_DIGEST_FRAME: &[u8] =
+        &hex!("221808015a141a120a106a0e0a04010101011204dddddddd1864");
+
+    fn migrated_locator(frame: &proto::Frame) -> &proto::file_pointer::LocatorInfo {
+        let Some(proto::frame::Item::ChatItem(chat_item)) = &frame.item else {
+            panic!("not a chat item");
+        };
+        let Some(proto::chat_item::Item::StandardMessage(message)) = &chat_item.item else {
+            panic!("not a standard message");
+        };
+        &message.attachments[0].pointer.locatorInfo
+    }
+
+    #[test]
+    fn legacy_digest_becomes_encrypted_digest() {
+        let mut frame = proto::Frame::parse_from_bytes(LEGACY_DIGEST_FRAME).expect("valid fixture");
+        assert_eq!(frame.collect_unknown_fields().len(), 1);
+
+        Migrations::for_version(1).apply(&mut frame);
+
+        assert!(frame.collect_unknown_fields().is_empty());
+        let locator = migrated_locator(&frame);
+        assert_eq!(
+            locator.integrityCheck,
+            Some(proto::file_pointer::locator_info::IntegrityCheck::EncryptedDigest(vec![0xdd; 4]))
+        );
+        assert_eq!(locator.key, [1; 4]);
+        assert_eq!(locator.size, 100);
+    }
+
+    #[test]
+    fn legacy_digest_does_not_replace_current_integrity_check() {
+        let mut frame = proto::Frame::parse_from_bytes(LEGACY_DIGEST_FRAME).expect("valid fixture");
+        let Some(proto::frame::Item::ChatItem(chat_item)) = &mut frame.item else {
+            unreachable!("checked above");
+        };
+        let Some(proto::chat_item::Item::StandardMessage(message)) = &mut chat_item.item else {
+            unreachable!("checked above");
+        };
+        let plaintext_hash =
+            proto::file_pointer::locator_info::IntegrityCheck::PlaintextHash(vec![0xaa; 4]);
+        message.attachments[0]
+            .pointer
+            .mut_or_insert_default()
+            .locatorInfo
+            .mut_or_insert_default()
+            .integrityCheck = Some(plaintext_hash.clone());
+
+        Migrations::for_version(1).apply(&mut frame);
+
+        assert!(frame.collect_unknown_fields().is_empty());
+        assert_eq!(
+            migrated_locator(&frame).integrityCheck,
+            Some(plaintext_hash)
+        );
+    }
+
+    fn legacy_file_pointer() -> proto::FilePointer {
+        let mut locator = proto::file_pointer::LocatorInfo {
+            key: vec![1; 4],
+            size: 100,
+            ..Default::default()
+        };
+        locator
+            .special_fields
+            .mut_unknown_fields()
+            .add_length_delimited(LEGACY_DIGEST_TAG, vec![0xdd; 4]);
+        proto::FilePointer {
+            locatorInfo: Some(locator).into(),
+            ..Default::default()
+        }
+    }
+
+    fn assert_migrated(pointer: &proto::FilePointer) {
+        assert_eq!(
+            pointer.locatorInfo.integrityCheck,
+            Some(proto::file_pointer::locator_info::IntegrityCheck::EncryptedDigest(vec![0xdd; 4]))
+        );
+    }
+
+    #[test]
+    fn legacy_digest_in_revision_is_migrated() {
+        let revision = proto::ChatItem {
+            item: Some(
+                proto::StandardMessage {
+                    attachments: vec![proto::MessageAttachment {
+                        pointer: Some(legacy_file_pointer()).into(),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }
+                .into(),
+            ),
+            ..Default::default()
+        };
+        let mut frame = proto::Frame {
+            item: Some(
+                proto::ChatItem {
+                    revisions: vec![revision],
+                    ..Default::default()
+                }
+                .into(),
+            ),
+            ..Default::default()
+        };
+        assert_eq!(frame.collect_unknown_fields().len(), 1);
+
+        Migrations::for_version(1).apply(&mut frame);
+
+        assert!(frame.collect_unknown_fields().is_empty());
+        let Some(proto::frame::Item::ChatItem(chat_item)) = &frame.item else {
+            panic!("not a chat item");
+        };
+        let Some(proto::chat_item::Item::StandardMessage(message)) = &chat_item.revisions[0].item
+        else {
+            panic!("not a standard message");
+        };
+        assert_migrated(&message.attachments[0].pointer);
+    }
+
+    #[test]
+    fn legacy_digest_in_wallpaper_is_migrated() {
+        let mut frame = proto::Frame {
+            item: Some(
+                proto::Chat {
+                    style: Some(proto::ChatStyle {
+                        wallpaper: Some(proto::chat_style::Wallpaper::WallpaperPhoto(
+                            legacy_file_pointer(),
+                        )),
+                        ..Default::default()
+                    })
+                    .into(),
+                    ..Default::default()
+                }
+                .into(),
+            ),
+            ..Default::default()
+        };
+        assert_eq!(frame.collect_unknown_fields().len(), 1);
+
+        Migrations::for_version(1).apply(&mut frame);
+
+        assert!(frame.collect_unknown_fields().is_empty());
+        let Some(proto::frame::Item::Chat(chat)) = &frame.item else {
+            panic!("not a chat");
+        };
+        let Some(proto::chat_style::Wallpaper::WallpaperPhoto(pointer)) = &chat.style.wallpaper
+        else {
+            panic!("not a wallpaper photo");
+        };
+        assert_migrated(pointer);
+    }
+
+    #[test]
+    fn frames_without_legacy_digests_are_untouched() {
+        let frame = proto::Frame {
+            item: Some(proto::Chat::default().into()),
+            ..Default::default()
+        };
+        let mut migrated = frame.clone();
+        Migrations::for_version(1).apply(&mut migrated);
+        // In particular, no unset message fields were filled in with defaults.
+        assert_eq!(migrated, frame);
+    }
+
+    #[test]
+    fn registered_migrations_have_valid_ranges() {
+        for migration in REGISTERED_MIGRATIONS {
+            assert!(!migration.versions.is_empty(), "{}", migration.name);
+        }
+    }
+}
@@ -0,0 +1,461 @@
+//
+// Copyright 2026 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Summary statistics about the contents of a backup.
+//!
+//! Collecting statistics only checks that each frame parses as the expected protobuf message; it
+//! does *not* validate the contents the way [`BackupReader::read_all`](crate::BackupReader::read_all)
+//! does. This makes it suitable for showing a user "what's in this backup" before restoring it.
+
+use std::collections::BTreeMap;
+
+use futures::AsyncRead;
+use protobuf::reflect::{ReflectFieldRef, ReflectValueRef};
+use protobuf::{Message as _, MessageDyn};
+
+use crate::frame::VerifyHmac;
+use crate::migrate::Migrations;
+use crate::parse::VarintDelimitedReader;
+use crate::proto::backup as proto;
+use crate::unknown::{PathPart, UnknownValue, VisitUnknownFieldsExt as _};
+use crate::{Error, FoundUnknownField};
+
+/// Label used for the leading `BackupInfo` frame in [`BackupStatistics::frames`].
+pub const BACKUP_INFO_LABEL: &str = "BackupInfo";
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BackupStatistics {
+    /// Counts and sizes of frames, keyed by kind (e.g. `"ChatItem.StandardMessage"`).
+    pub frames: BTreeMap<&'static str, FrameStatistics>,
+    /// Attachments referenced anywhere in the backup.
+    pub attachments: AttachmentStatistics,
+    /// Number of chat items in each chat, keyed by the chat's ID within the backup.
+    pub messages_per_chat: BTreeMap<u64, u64>,
+    /// Number of unknown fields and enum values encountered.
+    pub unknown_field_count: usize,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct FrameStatistics {
+    pub count: u64,
+    /// Total length of the serialized frames, not counting their length prefixes.
+    pub total_len: u64,
+    /// Number of bytes of the backup file consumed while reading these frames.
+    ///
+    /// This is only tracked by
+    /// [`BackupReader::collect_statistics_with_input_position`](crate::BackupReader::collect_statistics_with_input_position),
+    /// and is zero otherwise. Because reads are buffered, the attribution to individual frames is
+    /// approximate, but the total is exact.
+    pub compressed_len: u64,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct AttachmentStatistics {
+    pub count: u64,
+    /// Sum of the plaintext sizes of all attachments, as recorded in the backup.
+    pub total_size: u64,
+}
+
+impl BackupStatistics {
+    /// The number of frames in the backup, including the leading `BackupInfo`.
+    pub fn frame_count(&self) -> u64 {
+        self.frames.values().map(|f| f.count).sum()
+    }
+
+    /// The total length of all serialized frames, not counting their length prefixes.
+    pub fn total_frame_len(&self) -> u64 {
+        self.frames.values().map(|f| f.total_len).sum()
+    }
+
+    /// The ratio of the uncompressed frame data to the size of the backup file it was read from.
+    ///
+    /// Returns `None` if `backup_len` is zero.
+    pub fn compression_ratio(&self, backup_len: u64) -> Option<f64> {
+        if backup_len == 0 {
+            return None;
+        }
+        Some(self.total_frame_len() as f64 / backup_len as f64)
+    }
+
+    /// The number of bytes of the backup file consumed while reading frames.
+    ///
+    /// See [`FrameStatistics::compressed_len`].
+    pub fn total_compressed_len(&self) -> u64 {
+        self.frames.values().map(|f| f.compressed_len).sum()
+    }
+
+    fn record_frame(&mut self, label: &'static str, len: usize, compressed_len: u64) {
+        let entry = self.frames.entry(label).or_default();
+        entry.count += 1;
+        entry.total_len += u64::try_from(len).expect("usize fits in u64");
+        entry.compressed_len += compressed_len;
+    }
+
+    fn record_contents(&mut self, frame: &proto::Frame) {
+        if let Some(proto::frame::Item::ChatItem(chat_item)) = &frame.item {
+            *self.messages_per_chat.entry(chat_item.chatId).or_default() += 1;
+        }
+
+        visit_file_pointers(frame, &mut |pointer| {
+            self.attachments.count += 1;
+            self.attachments.total_size += pointer
+                .locatorInfo
+                .as_ref()
+                .map_or(0, |locator| u64::from(locator.size));
+        });
+    }
+
+    fn record_unknown_fields(
+        &mut self,
+        unknown_fields: &mut Vec<FoundUnknownField>,
+        found: Vec<(Vec<PathPart>, UnknownValue)>,
+        frame_index: usize,
+    ) {
+        self.unknown_field_count += found.len();
+        unknown_fields.extend(
+            found
+                .into_iter()
+                .map(FoundUnknownField::in_frame(frame_index)),
+        );
+    }
+}
+
+/// Reads every frame from `reader`, accumulating statistics about them.
+///
+/// Like validation, this checks the HMAC of the input once all frames have been read.
+///
+/// `input_position` is consulted after each frame to attribute consumed input to that frame; see
+/// [`FrameStatistics::compressed_len`].
+pub(crate) async fn collect_statistics(
+    mut reader: VarintDelimitedReader<impl AsyncRead + Unpin + VerifyHmac>,
+    input_position: impl Fn() -> u64,
+    unknown_fields: &mut Vec<FoundUnknownField>,
+) -> Result<BackupStatistics, Error> {
+    let mut stats = BackupStatistics::default();
+
+    let mut position = input_position();
+    let mut advance = || {
+        let previous = std::mem::replace(&mut position, input_position());
+        position.saturating_sub(previous)
+    };
+
+    let first = reader
+        .read_next()
+        .await
+        .map_err(Error::Parse)?
+        .ok_or(Error::NoFrames)?;
+    let backup_info = proto::BackupInfo::parse_from_bytes(&first)?;
+    stats.record_frame(BACKUP_INFO_LABEL, first.len(), advance());
+    stats.record_unknown_fields(unknown_fields, backup_info.collect_unknown_fields(), 0);
+
+    // As in validation, migrate before looking for unknown fields.
+    let migrations = Migrations::for_version(backup_info.version);
+
+    let mut frame_index = 1;
+    while let Some(raw_frame) = reader.read_next().await.map_err(Error::Parse)? {
+        let mut frame = proto::Frame::parse_from_bytes(&raw_frame)?;
+        migrations.apply(&mut frame);
+        stats.record_frame(frame_label(&frame), raw_frame.len(), advance());
+        stats.record_contents(&frame);
+        stats.record_unknown_fields(unknown_fields, frame.collect_unknown_fields(), frame_index);
+        frame_index += 1;
+    }
+
+    reader.into_inner().verify_hmac().await?;
+
+    Ok(stats)
+}
+
+fn frame_label(frame: &proto::Frame) -> &'static str {
+    use proto::frame::Item;
+
+    let Some(item) = &frame.item else {
+        return "Empty";
+    };
+    match item {
+        Item::Account(_) => "Account",
+        Item::Recipient(recipient) => {
+            use proto::recipient::Destination;
+            match &recipient.destination {
+                None => "Recipient.Empty",
+                Some(Destination::Contact(_)) => "Recipient.Contact",
+                Some(Destination::Group(_)) => "Recipient.Group",
+                Some(Destination::DistributionList(_)) => "Recipient.DistributionList",
+                Some(Destination::Self_(_)) => "Recipient.Self",
+                Some(Destination::ReleaseNotes(_)) => "Recipient.ReleaseNotes",
+                Some(Destination::CallLink(_)) => "Recipient.CallLink",
+            }
+        }
+        Item::Chat(_) => "Chat",
+        Item::ChatItem(chat_item) => {
+            use proto::chat_item::Item as MessageItem;
+            match &chat_item.item {
+                None => "ChatItem.Empty",
+                Some(MessageItem::StandardMessage(_)) => "ChatItem.StandardMessage",
+                Some(MessageItem::ContactMessage(_)) => "ChatItem.ContactMessage",
+                Some(MessageItem::StickerMessage(_)) => "ChatItem.StickerMessage",
+                Some(MessageItem::RemoteDeletedMessage(_)) => "ChatItem.RemoteDeletedMessage",
+                Some(MessageItem::UpdateMessage(_)) => "ChatItem.UpdateMessage",
+                Some(MessageItem::PaymentNotification(_)) => "ChatItem.PaymentNotification",
+                Some(MessageItem::GiftBadge(_)) => "ChatItem.GiftBadge",
+                Some(MessageItem::ViewOnceMessage(_)) => "ChatItem.ViewOnceMessage",
+                Some(MessageItem::DirectStoryReplyMessage(_)) => "ChatItem.DirectStoryReplyMessage",
+                Some(MessageItem::Poll(_)) => "ChatItem.Poll",
+                Some(MessageItem::AdminDeletedMessage(_)) => "ChatItem.AdminDeletedMessage",
+            }
+        }
+        Item::StickerPack(_) => "StickerPack",
+        Item::AdHocCall(_) => "AdHocCall",
+        Item::NotificationProfile(_) => "NotificationProfile",
+        Item::ChatFolder(_) => "ChatFolder",
+    }
+}
+
+/// Calls `visitor` for every [`proto::FilePointer`] reachable from `message`.
+///
+/// File pointers show up in many places (message attachments, quotes, link previews, avatars,
+/// wallpapers...), so rather than enumerating them we walk the message dynamically.
+pub(crate) fn visit_file_pointers(
+    message: &dyn MessageDyn,
+    visitor: &mut impl FnMut(&proto::FilePointer),
+) {
+    if let Some(pointer) = message.downcast_ref::<proto::FilePointer>() {
+        visitor(pointer);
+        return;
+    }
+
+    let mut visit_value = |value: ReflectValueRef<'_>| {
+        if let ReflectValueRef::Message(message) = value {
+            visit_file_pointers(&*message, visitor)
+        }
+    };
+
+    for field in message.descriptor_dyn().fields() {
+        match field.get_reflect(message) {
+            ReflectFieldRef::Optional(value) => {
+                if let Some(value) = value.value() {
+                    visit_value(value)
+                }
+            }
+            ReflectFieldRef::Repeated(values) => values.into_iter().for_each(&mut visit_value),
+            ReflectFieldRef::Map(values) => {
+                for (_key, value) in &values {
+                    visit_value(value)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::executor::block_on;
+    use protobuf::MessageField;
+
+    use super::*;
+    use crate::BackupReader;
+    use crate::backup::Purpose;
+
+    fn attachment(size: u32) -> proto::MessageAttachment {
+        proto::MessageAttachment {
+            pointer: MessageField::some(proto::FilePointer {
+                locatorInfo: MessageField::some(proto::file_pointer::LocatorInfo {
+                    size,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn chat_item(chat_id: u64, attachment_sizes: &[u32]) -> proto::Frame {
+        proto::Frame {
+            item: Some(proto::frame::Item::ChatItem(proto::ChatItem {
+                chatId: chat_id,
+                item: Some(
+                    proto::StandardMessage {
+                        attachments: attachment_sizes.iter().copied().map(attachment).collect(),
+                        ..Default::default()
+                    }
+                    .into(),
+                ),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+    }
+
+    /// Reports how many bytes have been read from `inner` to a shared atomic.
+    struct TrackingReader<R> {
+        inner: R,
+        position: std::sync::Arc<std::sync::atomic::AtomicU64>,
+    }
+
+    impl<R: AsyncRead + Unpin> AsyncRead for TrackingReader<R> {
+        fn poll_read(
+            mut self: std::pin::Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+            buf: &mut [u8],
+        ) -> std::task::Poll<std::io::Result<usize>> {
+            let count = std::task::ready!(std::pin::Pin::new(&mut self.inner).poll_read(cx, buf))?;
+            self.position.fetch_add(
+                count.try_into().expect("usize fits in u64"),
+                std::sync::atomic::Ordering::SeqCst,
+            );
+            std::task::Poll::Ready(Ok(count))
+        }
+    }
+
+    fn serialize_backup(frames: impl IntoIterator<Item = proto::Frame>) -> Vec<u8> {
+        let mut bytes = proto::BackupInfo {
+            version: 1,
+            ..Default::default()
+        }
+        .write_length_delimited_to_bytes()
+        .expect("can serialize");
+        for frame in frames {
+            frame
+                .write_length_delimited_to_vec(&mut bytes)
+                .expect("can serialize");
+        }
+        bytes
+    }
+
+    fn collect(bytes: &[u8]) -> (BackupStatistics, Vec<FoundUnknownField>) {
+        let reader = BackupReader::new_unencrypted(bytes, Purpose::RemoteBackup);
+        let crate::ReadResult {
+            result,
+            found_unknown_fields,
+        } = block_on(reader.collect_statistics());
+        (result.expect("can collect"), found_unknown_fields)
+    }
+
+    #[test]
+    fn counts_frames_messages_and_attachments() {
+        let frames = [
+            proto::Frame {
+                item: Some(proto::AccountData::default().into()),
+                ..Default::default()
+            },
+            chat_item(1, &[100, 20]),
+            chat_item(1, &[]),
+            chat_item(2, &[3]),
+        ];
+        let chat_item_len: u64 = frames[1..].iter().map(|f| f.compute_size()).sum();
+        let bytes = serialize_backup(frames);
+
+        let (stats, found_unknown_fields) = collect(&bytes);
+        assert!(found_unknown_fields.is_empty());
+        assert_eq!(stats.unknown_field_count, 0);
+
+        assert_eq!(stats.frame_count(), 5);
+        assert_eq!(stats.frames[BACKUP_INFO_LABEL].count, 1);
+        assert_eq!(stats.frames["Account"].count, 1);
+        assert_eq!(
+            stats.frames["ChatItem.StandardMessage"],
+            FrameStatistics {
+                count: 3,
+                total_len: chat_item_len,
+                compressed_len: 0,
+            }
+        );
+        assert_eq!(
+            stats.attachments,
+            AttachmentStatistics {
+                count: 3,
+                total_size: 123,
+            }
+        );
+        assert_eq!(stats.messages_per_chat, BTreeMap::from([(1, 2), (2, 1)]));
+
+        assert!(stats.total_frame_len() < u64::try_from(bytes.len()).expect("small"));
+        assert_eq!(stats.compression_ratio(0), None);
+        assert_eq!(stats.compression_ratio(stats.total_frame_len()), Some(1.0));
+    }
+
+    #[test]
+    fn tracks_input_position() {
+        let bytes = serialize_backup([chat_item(1, &[10]), chat_item(2, &[])]);
+
+        let position = std::sync::Arc::<std::sync::atomic::AtomicU64>::default();
+        let reader = BackupReader::new_unencrypted(
+            TrackingReader {
+                inner: &bytes[..],
+                position: position.clone(),
+            },
+            Purpose::RemoteBackup,
+        );
+        let crate::ReadResult {
+            result,
+            found_unknown_fields: _,
+        } = block_on(reader.collect_statistics_with_input_position(|| {
+            position.load(std::sync::atomic::Ordering::SeqCst)
+        }));
+        let stats = result.expect("can collect");
+
+        assert_eq!(
+            stats.total_compressed_len(),
+            u64::try_from(bytes.len()).expect("small")
+        );
+        assert_ne!(stats.frames[BACKUP_INFO_LABEL].compressed_len, 0);
+    }
+
+    #[test]
+    fn counts_unknown_fields() {
+        let mut frame = chat_item(1, &[]);
+        frame
+            .special_fields
+            .mut_unknown_fields()
+            .add_length_delimited(999, vec![]);
+        let bytes = serialize_backup([frame]);
+
+        let (stats, found_unknown_fields) = collect(&bytes);
+        assert_eq!(stats.unknown_field_count, 1);
+        assert_eq!(
+            found_unknown_fields,
+            [FoundUnknownField {
+                frame_index: 1,
+                path: vec![],
+                value: UnknownValue::Field { tag: 999 },
+            }]
+        );
+    }
+
+    #[test]
+    fn applies_migrations() {
+        let mut frame = chat_item(1, &[10]);
+        let Some(proto::frame::Item::ChatItem(chat_item)) = &mut frame.item else {
+            unreachable!("just created");
+        };
+        let Some(proto::chat_item::Item::StandardMessage(message)) = &mut chat_item.item else {
+            unreachable!("just created");
+        };
+        // The pre-migration location of the attachment digest.
+        message.attachments[0]
+            .pointer
+            .mut_or_insert_default()
+            .locatorInfo
+            .mut_or_insert_default()
+            .special_fields
+            .mut_unknown_fields()
+            .add_length_delimited(2, vec![0xdd; 32]);
+        let bytes = serialize_backup([frame]);
+
+        let (stats, found_unknown_fields) = collect(&bytes);
+        assert!(found_unknown_fields.is_empty());
+        assert_eq!(stats.unknown_field_count, 0);
+    }
+
+    #[test]
+    fn empty_input_has_no_frames() {
+        let reader = BackupReader::new_unencrypted(&[][..], Purpose::RemoteBackup);
+        let crate::ReadResult {
+            result,
+            found_unknown_fields: _,
+        } = block_on(reader.collect_statistics());
+        assert!(matches!(result, Err(Error::NoFrames)));
+    }
+}
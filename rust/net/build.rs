@@ -5,6 +5,7 @@
 
 fn main() {
     let protos = [
+        "src/proto/account_recovery.proto",
         "src/proto/cds2.proto",
         "src/proto/chat_provisioning.proto",
         "src/proto/chat_websocket.proto",
@@ -14,6 +14,7 @@ use libsignal_net::infra::errors::RetryLater;
 use ref_cast::RefCast as _;
 
 pub mod backups;
+pub mod devices;
 pub mod keys;
 pub mod keytrans;
 pub mod messages;
@@ -207,6 +208,7 @@ pub struct UploadForm {
 /// UnauthenticatedChatApi generically should accept an arbitrary `T` here.
 pub trait UnauthenticatedChatApi<T>:
     backups::UnauthenticatedChatApi<T>
+    + devices::UnauthenticatedChatApi<T>
     + keys::UnauthenticatedChatApi<T>
     + keytrans::UnauthenticatedChatApi
     + messages::UnauthenticatedChatApi<T>
@@ -216,6 +218,7 @@ pub trait UnauthenticatedChatApi<T>:
 }
 impl<T, U> UnauthenticatedChatApi<T> for U where
     U: backups::UnauthenticatedChatApi<T>
+        + devices::UnauthenticatedChatApi<T>
         + keys::UnauthenticatedChatApi<T>
         + keytrans::UnauthenticatedChatApi
         + messages::UnauthenticatedChatApi<T>
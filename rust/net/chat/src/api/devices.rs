@@ -0,0 +1,123 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use displaydoc::Display;
+use libsignal_core::{Aci, DeviceId, Pni};
+
+use super::registration::{ForServiceIds, NewMessageNotification, SignedPreKeyBody};
+use super::{AllowRateLimitChallenges, RequestError};
+
+/// Attributes a new device provides about itself when linking to an existing account.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LinkedDeviceAttributes<'a> {
+    /// Generated ID associated with the account's ACI on this device.
+    pub registration_id: u16,
+    /// Generated ID associated with the account's PNI on this device.
+    pub pni_registration_id: u16,
+    /// Encrypted device name.
+    pub name: &'a [u8],
+    pub capabilities: HashSet<&'a str>,
+}
+
+/// Pre-keys uploaded for a single service ID when linking a new device.
+pub struct LinkedDeviceKeys<'a> {
+    pub signed_pre_key: SignedPreKeyBody<&'a [u8]>,
+    pub pq_last_resort_pre_key: SignedPreKeyBody<&'a [u8]>,
+}
+
+/// Successful response for [`UnauthenticatedChatApi::link_device`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LinkDeviceResponse {
+    pub aci: Aci,
+    pub pni: Pni,
+    /// The device ID assigned to the newly-linked device.
+    pub device_id: DeviceId,
+}
+
+/// A single-use code that lets a new device link to the current account.
+///
+/// The verification code is sent to the new device as part of its
+/// [`ProvisionMessage`](libsignal_net::chat::provisioning::ProvisionMessage).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LinkingCode {
+    pub verification_code: String,
+    /// Identifies the code when waiting for the new device to finish linking.
+    pub token_identifier: String,
+}
+
+/// Recoverable errors produced by [`UnauthenticatedChatApi::link_device`].
+#[derive(Debug, Display)]
+pub enum LinkDeviceError {
+    /// The provisioning code is invalid or has already been used.
+    InvalidProvisioningCode,
+    /// The account already has the maximum number of linked devices.
+    DeviceLimitReached,
+}
+
+/// The account already has the maximum number of linked devices.
+#[derive(Debug, Display)]
+pub struct DeviceLimitReached;
+
+/// The provisioning address is not connected.
+#[derive(Debug, Display)]
+pub struct ProvisioningAddressNotFound;
+
+/// High-level chat-server APIs used by a device linking to an existing account
+///
+/// ### Generic?
+///
+/// The type parameter `T` is a marker to distinguish blanket impls that would otherwise overlap.
+/// Any concrete type will only impl this trait in one way; anywhere that needs to use
+/// UnauthenticatedChatApi generically should accept an arbitrary `T` here.
+#[async_trait]
+pub trait UnauthenticatedChatApi<T> {
+    // Not intended to be overridden.
+    const ALLOW_RATE_LIMIT_CHALLENGES: AllowRateLimitChallenges = AllowRateLimitChallenges::No;
+
+    /// Register this device as a new device on the account identified by `number`.
+    ///
+    /// `provisioning_code` comes from the primary device's
+    /// [`ProvisionMessage`](libsignal_net::chat::provisioning::ProvisionMessage), and `password`
+    /// is generated by this device to authenticate itself in the future.
+    async fn link_device(
+        &self,
+        number: &str,
+        password: &str,
+        provisioning_code: &str,
+        attributes: LinkedDeviceAttributes<'_>,
+        keys: ForServiceIds<LinkedDeviceKeys<'_>>,
+        message_notification: NewMessageNotification<&str>,
+    ) -> Result<LinkDeviceResponse, RequestError<LinkDeviceError>>;
+}
+
+/// High-level chat-server APIs used by an existing device to link new ones
+///
+/// ### Generic?
+///
+/// The type parameter `T` is a marker to distinguish blanket impls that would otherwise overlap.
+/// Any concrete type will only impl this trait in one way; anywhere that needs to use
+/// AuthenticatedChatApi generically should accept an arbitrary `T` here.
+#[async_trait]
+pub trait AuthenticatedChatApi<T> {
+    const ALLOW_RATE_LIMIT_CHALLENGES: AllowRateLimitChallenges = AllowRateLimitChallenges::Yes;
+
+    /// Request a code that will let a new device link to this account.
+    async fn get_device_linking_code(
+        &self,
+    ) -> Result<LinkingCode, RequestError<DeviceLimitReached>>;
+
+    /// Deliver an encrypted provisioning envelope to the new device waiting at `address`.
+    ///
+    /// `envelope` should be produced by
+    /// [`encrypt_provision_message`](libsignal_net::chat::provisioning::encrypt_provision_message).
+    async fn send_provisioning_message(
+        &self,
+        address: &str,
+        envelope: &[u8],
+    ) -> Result<(), RequestError<ProvisioningAddressNotFound>>;
+}
@@ -5,6 +5,7 @@
 
 mod maybe_partial;
 mod monitor_and_search;
+mod self_monitor;
 mod verify_ext;
 
 use std::borrow::Cow;
@@ -25,6 +26,10 @@ use libsignal_net::env::KeyTransConfig;
 use libsignal_protocol::PublicKey;
 pub use maybe_partial::{AccountDataField, MaybePartial};
 pub use monitor_and_search::{TreeHeadWithTimestamp, check};
+pub use self_monitor::{
+    InMemoryKeyTransparencyStore, KeyTransparencyMonitor, KeyTransparencyStore, MonitorEvent,
+    MonitorSchedule, MonitoredAccount,
+};
 use verify_ext::KeyTransparencyVerifyExt as _;
 
 use super::RequestError;
@@ -555,11 +560,13 @@ impl AccountDataFieldReset for StoredAccountData {
 #[cfg(test)]
 pub(crate) mod test_support {
     use std::cell::Cell;
-    use std::time::Duration;
 
     use assert_matches::assert_matches;
     use const_str::hex;
-    use libsignal_keytrans::{StoredAccountData, TreeHead};
+    use libsignal_keytrans::reference_log::ReferenceLog;
+    use libsignal_keytrans::{
+        Consistency, MonitoringData, SigningKey, StoredAccountData, TreeHead,
+    };
     use libsignal_net::env;
     use prost::Message as _;
 
@@ -631,11 +638,6 @@ pub(crate) mod test_support {
         KeyTransparencyClient::new(chat, KEYTRANS_CONFIG_STAGING)
     }
 
-    pub const CHAT_SEARCH_RESPONSE: &[u8] =
-        include_bytes!("../../../tests/data/chat_search_response.dat");
-    pub const CHAT_SEARCH_RESPONSE_VALID_AT: Duration =
-        include!("../../../tests/data/chat_response_valid_at.in");
-
     const DISTINGUISHED_TREE_677_HEAD: &[u8] = &hex!(
         "08a50510f981afc4d7331a640a20bd1e26a0fbdbfa923486ccc9296f4227db490b4add29f5507775171ea0fb7a4e1240404fcd202d174fa3c1db70dd7a2af28aa7289230f16bbbabfbb0cf8ac0351ce8ddcc770a4e5ab2a2b32b4af7fba5e056f2d6f70be1039c152aeda2e7c6117a0d1a640a201123b13ee32479ae6af5739e5d687b51559abf7684120511f68cde7a21a0e7551240521e691c9356343feee2e80c5355d1f257550d870542ac0b6e25d349b6223966eb0859dd0df942cd5e541b37e9028682c9c986a5c9f33ce4739e205f58cbd2061a640a20093ee42d95502b3e81f4e604179c82c149fffb96167642b9eb81b03d6e2dd636124081185aaf680e96e329dee42cdb2f1ce7bef6da769b51dabfda8db0163977d500a47d00fe60a6fe3e2562f08e5ff8c4ec4dfcf054e31a85b28d0665ff92c0f901"
     );
@@ -662,6 +664,193 @@ pub(crate) mod test_support {
         AccountData::try_from(test_stored_account_data()).expect("valid account data")
     }
 
+    /// A [`LowLevelChatApi`] that answers requests from an in-process
+    /// [`ReferenceLog`] containing [`test_account`], the way the chat server
+    /// would.
+    pub struct ReferenceChat {
+        pub log: ReferenceLog,
+        pub distinguished_tree_head: LastTreeHead,
+    }
+
+    impl ReferenceChat {
+        pub fn new() -> Self {
+            let mut log = ReferenceLog::new(
+                SigningKey::from_bytes(&[1; 32]),
+                [2; 32],
+                vec![SigningKey::from_bytes(&[3; 32])],
+            );
+            log.update_distinguished().expect("can update");
+            let distinguished_tree_head = log.last_tree_head().expect("published");
+
+            let aci = test_account::aci();
+            let aci_bytes = aci.service_id_binary();
+            for (search_key, value) in [
+                (aci.as_search_key(), test_account::ACI_IDENTITY_KEY_BYTES),
+                (test_account::PHONE_NUMBER.as_search_key(), &aci_bytes[..]),
+                (
+                    test_account::username_hash().as_search_key(),
+                    &aci_bytes[..],
+                ),
+            ] {
+                log.update(&search_key, &search_value(value))
+                    .expect("can update");
+            }
+
+            Self {
+                log,
+                distinguished_tree_head,
+            }
+        }
+
+        pub fn client(&self) -> KeyTransparencyClient<'_> {
+            KeyTransparencyClient {
+                inner: KeyTransparency {
+                    config: self.log.config().clone(),
+                },
+                chat: self,
+            }
+        }
+
+        /// Adds `count` entries for other accounts to the log.
+        pub fn add_other_entries(&mut self, count: usize) {
+            for _ in 0..count {
+                let search_key = format!("other {}", self.log.tree_size());
+                self.log
+                    .update(search_key.as_bytes(), &search_value(b"value"))
+                    .expect("can update");
+            }
+        }
+
+        pub fn search_response(
+            &self,
+            aci: &Aci,
+            e164: Option<&E164>,
+            username_hash: Option<&UsernameHash<'_>>,
+            last_tree_size: Option<u64>,
+        ) -> Result<ChatSearchResponse, libsignal_keytrans::Error> {
+            let search = |search_key: Vec<u8>| self.log.search(&search_key, None);
+            Ok(ChatSearchResponse {
+                tree_head: Some(self.log.full_tree_head(Consistency {
+                    last: last_tree_size,
+                    distinguished: Some(self.distinguished_tree_head.0.tree_size),
+                })?),
+                aci: Some(search(aci.as_search_key())?),
+                e164: e164.map(|e164| search(e164.as_search_key())).transpose()?,
+                username_hash: username_hash
+                    .map(|username_hash| search(username_hash.as_search_key()))
+                    .transpose()?,
+            })
+        }
+    }
+
+    // Values in the log are prefixed with a version byte.
+    fn search_value(value: &[u8]) -> Vec<u8> {
+        [&[0], value].concat()
+    }
+
+    fn log_error(error: libsignal_keytrans::Error) -> RequestError<Error> {
+        RequestError::Unexpected {
+            log_safe: error.to_string(),
+        }
+    }
+
+    fn monitor_key(
+        search_key: Vec<u8>,
+        data: Option<&MonitoringData>,
+    ) -> Result<MonitorKey, RequestError<Error>> {
+        let data = data.ok_or(RequestError::Other(Error::InvalidRequest(
+            "account data does not match the monitor request",
+        )))?;
+        Ok(MonitorKey {
+            search_key,
+            entry_position: data.latest_log_position(),
+            commitment_index: data.index.to_vec(),
+        })
+    }
+
+    #[async_trait]
+    impl LowLevelChatApi for ReferenceChat {
+        async fn search(
+            &self,
+            aci: &Aci,
+            _aci_identity_key: &PublicKey,
+            e164: Option<&(E164, Vec<u8>)>,
+            username_hash: Option<&UsernameHash<'_>>,
+            stored_account_data: Option<&AccountData>,
+            distinguished_tree_head: &LastTreeHead,
+        ) -> Result<Vec<u8>, RequestError<Error>> {
+            assert_eq!(
+                distinguished_tree_head, &self.distinguished_tree_head,
+                "unexpected distinguished tree head"
+            );
+            self.search_response(
+                aci,
+                e164.map(|(e164, _)| e164),
+                username_hash,
+                stored_account_data.map(|data| data.last_tree_head.0.tree_size),
+            )
+            .map(|response| response.encode_to_vec())
+            .map_err(log_error)
+        }
+
+        async fn distinguished(
+            &self,
+            last_distinguished: Option<&LastTreeHead>,
+        ) -> Result<Vec<u8>, RequestError<Error>> {
+            self.log
+                .distinguished(last_distinguished.map(|head| head.0.tree_size))
+                .map(|response| response.encode_to_vec())
+                .map_err(log_error)
+        }
+
+        async fn monitor(
+            &self,
+            aci: &Aci,
+            e164: Option<&E164>,
+            username_hash: Option<&UsernameHash<'_>>,
+            account_data: &AccountData,
+            last_distinguished_tree_head: &LastTreeHead,
+        ) -> Result<Vec<u8>, RequestError<Error>> {
+            let mut keys = vec![monitor_key(aci.as_search_key(), Some(&account_data.aci))?];
+            if let Some(e164) = e164 {
+                keys.push(monitor_key(
+                    e164.as_search_key(),
+                    account_data.e164.as_ref(),
+                )?);
+            }
+            if let Some(username_hash) = username_hash {
+                keys.push(monitor_key(
+                    username_hash.as_search_key(),
+                    account_data.username_hash.as_ref(),
+                )?);
+            }
+
+            let MonitorResponse {
+                tree_head,
+                proofs,
+                inclusion,
+            } = self
+                .log
+                .monitor(&MonitorRequest {
+                    keys,
+                    consistency: Some(Consistency {
+                        last: Some(account_data.last_tree_head.0.tree_size),
+                        distinguished: Some(last_distinguished_tree_head.0.tree_size),
+                    }),
+                })
+                .map_err(log_error)?;
+            let mut proofs = proofs.into_iter();
+            Ok(ChatMonitorResponse {
+                tree_head,
+                aci: proofs.next(),
+                e164: e164.and_then(|_| proofs.next()),
+                username_hash: username_hash.and_then(|_| proofs.next()),
+                inclusion,
+            }
+            .encode_to_vec())
+        }
+    }
+
     #[derive(Debug, Clone)]
     pub struct OwnedParameters {
         pub aci: Aci,
@@ -773,19 +962,23 @@ pub(crate) mod test_support {
 mod test {
     use assert_matches::assert_matches;
     use libsignal_keytrans::StoredMonitoringData;
-    use prost::Message as _;
+    use libsignal_protocol::IdentityKeyPair;
+    use rand::TryRngCore as _;
+    use rand::rngs::OsRng;
     use test_case::{test_case, test_matrix};
 
-    use super::test_support::{
-        CHAT_SEARCH_RESPONSE, CHAT_SEARCH_RESPONSE_VALID_AT, KEYTRANS_CONFIG_STAGING, test_account,
-        test_account_data, test_distinguished_tree,
-    };
+    use super::test_support::{ReferenceChat, test_account};
     use super::*;
 
-    fn test_search_response() -> TypedSearchResponse {
-        let chat_search_response =
-            libsignal_keytrans::ChatSearchResponse::decode(CHAT_SEARCH_RESPONSE)
-                .expect("valid response");
+    fn test_search_response(chat: &ReferenceChat) -> TypedSearchResponse {
+        let chat_search_response = chat
+            .search_response(
+                &test_account::aci(),
+                Some(&test_account::PHONE_NUMBER),
+                Some(&test_account::username_hash()),
+                None,
+            )
+            .expect("valid response");
         TypedSearchResponse::from_untyped(chat_search_response)
             .expect("valid typed search response")
     }
@@ -794,7 +987,7 @@ mod test {
     #[test_case(&[AccountDataField::UsernameHash]; "username_hash")]
     #[test_case(&[AccountDataField::E164, AccountDataField::UsernameHash]; "e164 + username_hash")]
     fn search_returns_data_not_requested(skip: &[AccountDataField]) {
-        let valid_at = SystemTime::UNIX_EPOCH + CHAT_SEARCH_RESPONSE_VALID_AT;
+        let chat = ReferenceChat::new();
 
         let aci = test_account::aci();
         let mut e164 = Some(test_account::PHONE_NUMBER);
@@ -811,21 +1004,15 @@ mod test {
             }
         }
 
-        let account_data = test_account_data();
-
-        let kt = KeyTransparency {
-            config: KEYTRANS_CONFIG_STAGING.into(),
-        };
-
-        let result = kt.verify_chat_search_response(
+        let result = chat.client().inner.verify_chat_search_response(
             &aci,
             &test_account::aci_identity_key(),
             e164,
             username_hash,
-            Some(account_data),
-            test_search_response(),
-            Some(&test_distinguished_tree()),
-            valid_at,
+            None,
+            test_search_response(&chat),
+            Some(&chat.distinguished_tree_head),
+            SystemTime::now(),
         );
 
         assert_matches!(result, Err(Error::InvalidResponse(_)))
@@ -835,13 +1022,13 @@ mod test {
     #[test_case(&[AccountDataField::UsernameHash]; "username_hash")]
     #[test_case(&[AccountDataField::E164, AccountDataField::UsernameHash]; "e164 + username_hash")]
     fn search_does_not_return_requested_data(skip: &[AccountDataField]) {
-        let valid_at = SystemTime::UNIX_EPOCH + CHAT_SEARCH_RESPONSE_VALID_AT;
+        let chat = ReferenceChat::new();
 
         let aci = test_account::aci();
         let e164 = test_account::PHONE_NUMBER;
         let username_hash = test_account::username_hash();
 
-        let mut search_response = test_search_response();
+        let mut search_response = test_search_response(&chat);
         for what in skip {
             match what {
                 AccountDataField::E164 => {
@@ -853,21 +1040,15 @@ mod test {
             }
         }
 
-        let account_data = test_account_data();
-
-        let kt = KeyTransparency {
-            config: KEYTRANS_CONFIG_STAGING.into(),
-        };
-
-        let result = kt.verify_chat_search_response(
+        let result = chat.client().inner.verify_chat_search_response(
             &aci,
             &test_account::aci_identity_key(),
             Some(e164),
             Some(username_hash),
-            Some(account_data),
+            None,
             search_response,
-            Some(&test_distinguished_tree()),
-            valid_at,
+            Some(&chat.distinguished_tree_head),
+            SystemTime::now(),
         );
 
         assert_matches!(result, Ok(MaybePartial {missing_fields, ..}) =>
@@ -875,6 +1056,128 @@ mod test {
         );
     }
 
+    #[tokio::test]
+    #[test_case(false, false; "ACI")]
+    #[test_case(true, false; "ACI + E164")]
+    #[test_case(false, true; "ACI + Username Hash")]
+    #[test_case(true, true; "ACI + E164 + Username Hash")]
+    async fn search_then_monitor(use_e164: bool, use_username_hash: bool) {
+        let mut chat = ReferenceChat::new();
+        let aci = test_account::aci();
+        let e164 = use_e164.then_some(test_account::PHONE_NUMBER);
+        let username_hash = use_username_hash.then(test_account::username_hash);
+
+        let MaybePartial {
+            inner: account_data,
+            missing_fields,
+        } = chat
+            .client()
+            .search(
+                &aci,
+                &test_account::aci_identity_key(),
+                use_e164.then(test_account::e164_pair),
+                username_hash.clone(),
+                None,
+                &chat.distinguished_tree_head,
+            )
+            .await
+            .expect("can search");
+        assert!(missing_fields.is_empty());
+        assert_eq!(account_data.e164.is_some(), use_e164);
+        assert_eq!(account_data.username_hash.is_some(), use_username_hash);
+
+        chat.add_other_entries(20);
+        let monitored = chat
+            .client()
+            .monitor(
+                &aci,
+                e164,
+                username_hash.clone(),
+                account_data.clone(),
+                &chat.distinguished_tree_head,
+            )
+            .await
+            .expect("can monitor");
+        assert_eq!(monitored.last_tree_head.0.tree_size, chat.log.tree_size());
+        assert_eq!(monitored.aci.pos, account_data.aci.pos);
+
+        chat.add_other_entries(20);
+        chat.client()
+            .search(
+                &aci,
+                &test_account::aci_identity_key(),
+                use_e164.then(test_account::e164_pair),
+                username_hash,
+                Some(monitored),
+                &chat.distinguished_tree_head,
+            )
+            .await
+            .expect("can search again");
+    }
+
+    #[tokio::test]
+    async fn distinguished_is_consistent_with_last_distinguished() {
+        let mut chat = ReferenceChat::new();
+        let first = chat.client().distinguished(None).await.expect("valid");
+        assert_eq!(first.tree_head.tree_size, chat.log.tree_size());
+
+        chat.add_other_entries(10);
+        chat.log.update_distinguished().expect("can update");
+        let second = chat
+            .client()
+            .distinguished(Some(LastTreeHead(first.tree_head, first.tree_root)))
+            .await
+            .expect("consistent with the last distinguished tree head");
+        assert_eq!(second.tree_head.tree_size, chat.log.tree_size());
+    }
+
+    #[tokio::test]
+    async fn search_with_wrong_identity_key() {
+        let chat = ReferenceChat::new();
+        let wrong_identity_key = {
+            let mut rng = OsRng.unwrap_err();
+            let key_pair = IdentityKeyPair::generate(&mut rng);
+            *key_pair.public_key()
+        };
+
+        let result = chat
+            .client()
+            .search(
+                &test_account::aci(),
+                &wrong_identity_key,
+                None,
+                None,
+                None,
+                &chat.distinguished_tree_head,
+            )
+            .await;
+        assert_matches!(
+            result,
+            Err(RequestError::Other(Error::VerificationFailed(
+                libsignal_keytrans::Error::UnexpectedSearchValue
+            )))
+        );
+    }
+
+    #[tokio::test]
+    async fn search_for_account_that_isnt() {
+        let chat = ReferenceChat::new();
+        let aci = Aci::from(uuid::uuid!("00000000-0000-0000-0000-000000000000"));
+
+        let result = chat
+            .client()
+            .search(
+                &aci,
+                &test_account::aci_identity_key(),
+                None,
+                None,
+                None,
+                &chat.distinguished_tree_head,
+            )
+            .await;
+        assert_matches!(result, Err(RequestError::Unexpected { .. }));
+    }
+
     #[test_matrix([AccountDataField::E164, AccountDataField::UsernameHash])]
     fn reset_account_data_field(field: AccountDataField) {
         let field_data = StoredMonitoringData::default();
@@ -565,10 +565,7 @@ impl<'a> PostMonitorAction<'a> {
 
         match (mode, any_version_changed) {
             (CheckMode::SelfCheck { .. }, VersionChanged::Yes) => Err(RequestError::Other(
-                libsignal_keytrans::Error::VerificationFailed(
-                    "version change detected while self-monitoring".to_string(),
-                )
-                .into(),
+                libsignal_keytrans::Error::SelfMonitorVersionChanged.into(),
             )),
             (_, VersionChanged::Yes) => Ok(PostMonitorAction::Search {
                 // Merging of the parameters here covers the case where, for example,
@@ -735,14 +732,7 @@ async fn monitor_then_search<'a>(
     log::info!("Updated versions: {}", updated_versions.short_description());
     let version_delta = updated_versions
         .try_subtract(&stored_versions)
-        .map_err(|_| {
-            RequestError::Other(
-                libsignal_keytrans::Error::VerificationFailed(
-                    "version of the mapping decreased".to_string(),
-                )
-                .into(),
-            )
-        })?;
+        .map_err(|_| RequestError::Other(libsignal_keytrans::Error::VersionDecreased.into()))?;
 
     let any_version_changed = version_delta.maximum_version().is_some_and(|n| n > 0);
     let post_monitor_plan = PostMonitorAction::plan(
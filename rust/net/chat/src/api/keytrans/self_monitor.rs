@@ -0,0 +1,525 @@
+//
+// Copyright 2026 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Periodic self-monitoring of the local account's key transparency mappings.
+//!
+//! [`check`] performs a single round of monitor/search, and leaves it to the caller to persist
+//! the results and decide what a failure means. [`KeyTransparencyMonitor`] does both: it loads
+//! and saves state through a [`KeyTransparencyStore`], decides when the next check is due, and
+//! turns the outcome of each check into a [`MonitorEvent`].
+
+use std::collections::BTreeSet;
+use std::ops::ControlFlow;
+use std::time::{Duration, SystemTime};
+
+use libsignal_core::curve::PublicKey;
+use libsignal_core::{Aci, E164};
+use libsignal_keytrans::{AccountData, LastTreeHead, StoredAccountData, StoredTreeHead};
+
+use super::{
+    AccountDataField, CheckMode, Error, SearchKey as _, TreeHeadWithTimestamp,
+    UnauthenticatedChatApi, UsernameHash, check,
+};
+use crate::api::RequestError;
+
+/// Persistent state used by [`KeyTransparencyMonitor`].
+///
+/// Implementations are expected to store the values as-is (for example, as serialized protos) and
+/// return them unchanged on the next launch.
+pub trait KeyTransparencyStore {
+    fn account_data(&self) -> Option<StoredAccountData>;
+    fn set_account_data(&mut self, account_data: StoredAccountData);
+    fn distinguished_tree_head(&self) -> Option<StoredTreeHead>;
+    fn set_distinguished_tree_head(&mut self, tree_head: StoredTreeHead);
+}
+
+/// A [`KeyTransparencyStore`] that doesn't outlive the process.
+#[derive(Clone, Debug, Default)]
+pub struct InMemoryKeyTransparencyStore {
+    pub account_data: Option<StoredAccountData>,
+    pub distinguished_tree_head: Option<StoredTreeHead>,
+}
+
+impl KeyTransparencyStore for InMemoryKeyTransparencyStore {
+    fn account_data(&self) -> Option<StoredAccountData> {
+        self.account_data.clone()
+    }
+
+    fn set_account_data(&mut self, account_data: StoredAccountData) {
+        self.account_data = Some(account_data);
+    }
+
+    fn distinguished_tree_head(&self) -> Option<StoredTreeHead> {
+        self.distinguished_tree_head.clone()
+    }
+
+    fn set_distinguished_tree_head(&mut self, tree_head: StoredTreeHead) {
+        self.distinguished_tree_head = Some(tree_head);
+    }
+}
+
+/// The local account's mappings, as the client believes them to be.
+#[derive(Clone, Debug)]
+pub struct MonitoredAccount {
+    pub aci: Aci,
+    pub aci_identity_key: PublicKey,
+    /// The account's phone number together with its unidentified access key.
+    pub e164: Option<(E164, Vec<u8>)>,
+    pub username_hash: Option<UsernameHash<'static>>,
+    pub is_e164_discoverable: bool,
+}
+
+/// How often [`KeyTransparencyMonitor`] checks the log.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MonitorSchedule {
+    /// Time between successful checks.
+    pub interval: Duration,
+    /// Time to wait after a check that failed without reaching a verdict, e.g. because of a
+    /// network error.
+    ///
+    /// A server-provided retry-after delay takes precedence.
+    pub retry_interval: Duration,
+}
+
+impl Default for MonitorSchedule {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(24 * 60 * 60),
+            retry_interval: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+/// The outcome of a single self-check.
+#[derive(Debug)]
+pub enum MonitorEvent {
+    /// All mappings were verified against a tree of the given size.
+    Verified { tree_size: u64 },
+    /// The log was verified, but it doesn't contain some of the mappings the account has.
+    MappingsMissing(BTreeSet<AccountDataField>),
+    /// One of the account's mappings changed without this client's involvement.
+    ///
+    /// For the ACI this means the log has an identity key other than ours; for the E.164 and
+    /// username hash, that they map to a different account.
+    MappingChanged,
+    /// The log presented a view of its history that's inconsistent with one it presented before.
+    ///
+    /// This is the case when a tree head is older than, or has the same size but a different root
+    /// than, one previously stored, or when the version of a mapping goes backwards.
+    TreeHeadFork,
+    /// The server's response failed verification for any other reason.
+    VerificationFailed(Error),
+    /// The check couldn't be completed, and will be retried.
+    RequestFailed(RequestError<Error>),
+}
+
+impl MonitorEvent {
+    /// Whether the event indicates a problem with the log or the account that should be surfaced
+    /// to the user.
+    pub fn is_alert(&self) -> bool {
+        match self {
+            MonitorEvent::Verified { .. } | MonitorEvent::RequestFailed(_) => false,
+            MonitorEvent::MappingsMissing(_)
+            | MonitorEvent::MappingChanged
+            | MonitorEvent::TreeHeadFork
+            | MonitorEvent::VerificationFailed(_) => true,
+        }
+    }
+
+    fn from_error(error: RequestError<Error>) -> Self {
+        match error {
+            RequestError::Other(Error::VerificationFailed(
+                libsignal_keytrans::Error::SelfMonitorVersionChanged
+                | libsignal_keytrans::Error::UnexpectedSearchValue,
+            )) => MonitorEvent::MappingChanged,
+            RequestError::Other(Error::VerificationFailed(
+                libsignal_keytrans::Error::VersionDecreased,
+            )) => MonitorEvent::TreeHeadFork,
+            RequestError::Other(error) => MonitorEvent::VerificationFailed(error),
+            error => MonitorEvent::RequestFailed(error),
+        }
+    }
+}
+
+/// Periodically checks the local account's mappings in the key transparency log.
+pub struct KeyTransparencyMonitor<S> {
+    account: MonitoredAccount,
+    store: S,
+    schedule: MonitorSchedule,
+    next_check_at: Option<SystemTime>,
+}
+
+impl<S: KeyTransparencyStore> KeyTransparencyMonitor<S> {
+    pub fn new(account: MonitoredAccount, store: S, schedule: MonitorSchedule) -> Self {
+        Self {
+            account,
+            store,
+            schedule,
+            next_check_at: None,
+        }
+    }
+
+    pub fn account(&self) -> &MonitoredAccount {
+        &self.account
+    }
+
+    /// Updates the account's mappings, e.g. after a phone number change.
+    ///
+    /// A check for the new values is due immediately.
+    pub fn set_account(&mut self, account: MonitoredAccount) {
+        self.account = account;
+        self.next_check_at = Some(SystemTime::UNIX_EPOCH);
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    pub fn into_store(self) -> S {
+        self.store
+    }
+
+    /// When the next check should run.
+    ///
+    /// Before the first check in this process, this is based on when the stored account data was
+    /// last updated; if there's none, a check is due immediately.
+    pub fn next_check_at(&self) -> SystemTime {
+        if let Some(next_check_at) = self.next_check_at {
+            return next_check_at;
+        }
+        self.store
+            .account_data()
+            .and_then(|data| data.last_tree_head)
+            .map(|tree_head| {
+                SystemTime::UNIX_EPOCH
+                    + Duration::from_millis(tree_head.stored_at_ms)
+                    + self.schedule.interval
+            })
+            .unwrap_or(SystemTime::UNIX_EPOCH)
+    }
+
+    /// Checks the account's mappings right away, regardless of the schedule.
+    ///
+    /// Verified results are saved to the store. Nothing is saved if the check fails or detects a
+    /// fork, so the next check is made against the last known-good state.
+    pub async fn check_now(&mut self, kt: &impl UnauthenticatedChatApi) -> MonitorEvent {
+        let stored_account_data = self.store.account_data().and_then(|stored| {
+            AccountData::try_from(stored)
+                .inspect_err(|e| log::warn!("discarding invalid stored account data: {e}"))
+                .ok()
+        });
+        let stored_distinguished = self
+            .store
+            .distinguished_tree_head()
+            .and_then(TreeHeadWithTimestamp::from_stored);
+
+        let previous_tree_head = stored_account_data
+            .as_ref()
+            .map(|data| data.last_tree_head.clone());
+        let previous_distinguished = stored_distinguished
+            .as_ref()
+            .map(|distinguished| distinguished.tree_head.clone());
+
+        let MonitoredAccount {
+            aci,
+            aci_identity_key,
+            e164,
+            username_hash,
+            is_e164_discoverable,
+        } = &self.account;
+
+        let result = check(
+            kt,
+            aci,
+            aci_identity_key,
+            e164.clone(),
+            username_hash.clone(),
+            stored_account_data,
+            stored_distinguished,
+            CheckMode::SelfCheck {
+                is_e164_discoverable: *is_e164_discoverable,
+            },
+        )
+        .await;
+
+        let now = SystemTime::now();
+        let event = match result {
+            Ok((account_data, distinguished)) => {
+                if is_fork(
+                    previous_tree_head.as_ref(),
+                    &account_data.inner.last_tree_head,
+                ) || is_fork(previous_distinguished.as_ref(), &distinguished)
+                {
+                    MonitorEvent::TreeHeadFork
+                } else {
+                    let missing_fields = account_data.missing_fields;
+                    let tree_size = account_data.inner.last_tree_head.0.tree_size;
+                    self.store.set_account_data(account_data.inner.into_stored(
+                        aci.as_search_key(),
+                        e164.as_ref().map(|(e164, _)| e164.as_search_key()),
+                        username_hash.as_ref().map(|hash| hash.as_search_key()),
+                        now,
+                    ));
+                    // An unchanged distinguished tree head must keep its original stored_at, or
+                    // it will never be considered stale.
+                    if previous_distinguished.as_ref() != Some(&distinguished) {
+                        self.store
+                            .set_distinguished_tree_head(distinguished.into_stored(now));
+                    }
+                    if missing_fields.is_empty() {
+                        MonitorEvent::Verified { tree_size }
+                    } else {
+                        MonitorEvent::MappingsMissing(missing_fields)
+                    }
+                }
+            }
+            Err(error) => MonitorEvent::from_error(error),
+        };
+
+        let delay = match &event {
+            MonitorEvent::RequestFailed(RequestError::RetryLater(retry_later)) => {
+                Duration::from_secs(retry_later.retry_after_seconds.into())
+            }
+            MonitorEvent::RequestFailed(_) => self.schedule.retry_interval,
+            _ => self.schedule.interval,
+        };
+        self.next_check_at = Some(now + delay);
+
+        event
+    }
+
+    /// Checks the account's mappings on schedule, reporting each outcome to `on_event`.
+    ///
+    /// Runs until `on_event` returns [`ControlFlow::Break`].
+    pub async fn run(
+        &mut self,
+        kt: &impl UnauthenticatedChatApi,
+        mut on_event: impl FnMut(MonitorEvent) -> ControlFlow<()>,
+    ) {
+        loop {
+            let delay = self
+                .next_check_at()
+                .duration_since(SystemTime::now())
+                .unwrap_or_default();
+            tokio::time::sleep(delay).await;
+
+            let event = self.check_now(kt).await;
+            if event.is_alert() {
+                log::warn!("key transparency self-check: {event:?}");
+            } else {
+                log::info!("key transparency self-check: {event:?}");
+            }
+            if on_event(event).is_break() {
+                return;
+            }
+        }
+    }
+}
+
+/// Whether `new` cannot be a later view of the same log as `previous`.
+///
+/// Full consistency proofs are checked by [`libsignal_keytrans`]; this only catches the cases
+/// visible from the tree heads alone.
+fn is_fork(previous: Option<&LastTreeHead>, new: &LastTreeHead) -> bool {
+    let Some(LastTreeHead(previous_head, previous_root)) = previous else {
+        return false;
+    };
+    let LastTreeHead(new_head, new_root) = new;
+    new_head.tree_size < previous_head.tree_size
+        || (new_head.tree_size == previous_head.tree_size && new_root != previous_root)
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use libsignal_net::infra::errors::RetryLater;
+    use test_case::test_case;
+
+    use super::*;
+    use crate::api::keytrans::test_support::{
+        TestKt, test_account, test_account_data, test_distinguished_tree,
+    };
+    use crate::api::keytrans::{MaybePartial, SearchKey as _};
+
+    fn monitored_account() -> MonitoredAccount {
+        MonitoredAccount {
+            aci: test_account::aci(),
+            aci_identity_key: test_account::aci_identity_key(),
+            e164: Some(test_account::e164_pair()),
+            username_hash: Some(test_account::username_hash()),
+            is_e164_discoverable: true,
+        }
+    }
+
+    fn store_with_account_data() -> InMemoryKeyTransparencyStore {
+        let now = SystemTime::now();
+        InMemoryKeyTransparencyStore {
+            account_data: Some(test_account_data().into_stored(
+                test_account::aci().as_search_key(),
+                Some(test_account::PHONE_NUMBER.as_search_key()),
+                Some(test_account::username_hash().as_search_key()),
+                now,
+            )),
+            distinguished_tree_head: Some(test_distinguished_tree().into_stored(now)),
+        }
+    }
+
+    fn verification_failed(error: libsignal_keytrans::Error) -> RequestError<Error> {
+        RequestError::Other(Error::VerificationFailed(error))
+    }
+
+    fn forked(mut account_data: AccountData) -> AccountData {
+        account_data.last_tree_head.1[0] ^= 0xff;
+        account_data
+    }
+
+    fn rolled_back(mut account_data: AccountData) -> AccountData {
+        account_data.last_tree_head.0.tree_size -= 1;
+        account_data
+    }
+
+    #[tokio::test]
+    async fn first_check_searches_and_persists() {
+        let kt = TestKt::for_search(Ok(test_account_data().into()));
+        let mut monitor = KeyTransparencyMonitor::new(
+            monitored_account(),
+            InMemoryKeyTransparencyStore::default(),
+            MonitorSchedule::default(),
+        );
+        assert_eq!(monitor.next_check_at(), SystemTime::UNIX_EPOCH);
+
+        let event = monitor.check_now(&kt).await;
+        let expected_size = test_account_data().last_tree_head.0.tree_size;
+        assert_matches!(event, MonitorEvent::Verified { tree_size } if tree_size == expected_size);
+
+        let store = monitor.into_store();
+        let stored =
+            AccountData::try_from(store.account_data.expect("stored")).expect("valid account data");
+        assert_eq!(stored, test_account_data());
+        let distinguished = store.distinguished_tree_head.expect("stored");
+        assert_eq!(
+            distinguished.into_last_tree_head(),
+            Some(test_distinguished_tree())
+        );
+    }
+
+    #[tokio::test]
+    async fn subsequent_check_monitors_and_keeps_distinguished() {
+        // TestKt constructed like this will panic if search is invoked
+        let kt = TestKt::for_monitor(Ok(test_account_data()));
+        let store = store_with_account_data();
+        let stored_distinguished = store.distinguished_tree_head.clone();
+        let mut monitor =
+            KeyTransparencyMonitor::new(monitored_account(), store, MonitorSchedule::default());
+
+        assert_matches!(monitor.check_now(&kt).await, MonitorEvent::Verified { .. });
+        assert_eq!(
+            monitor.store().distinguished_tree_head,
+            stored_distinguished
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_mappings_are_reported() {
+        let mut account_data = test_account_data();
+        account_data.username_hash = None;
+        let kt = TestKt::for_search(Ok(MaybePartial {
+            inner: account_data,
+            missing_fields: BTreeSet::from([AccountDataField::UsernameHash]),
+        }));
+        let mut monitor = KeyTransparencyMonitor::new(
+            monitored_account(),
+            InMemoryKeyTransparencyStore::default(),
+            MonitorSchedule::default(),
+        );
+
+        assert_matches!(
+            monitor.check_now(&kt).await,
+            MonitorEvent::MappingsMissing(fields) if fields == BTreeSet::from([AccountDataField::UsernameHash])
+        );
+    }
+
+    #[test_case(forked; "same size different root")]
+    #[test_case(rolled_back; "smaller tree")]
+    #[tokio::test]
+    async fn tree_head_fork_is_detected(tamper: fn(AccountData) -> AccountData) {
+        let kt = TestKt::for_monitor(Ok(tamper(test_account_data())));
+        let store = store_with_account_data();
+        let stored_account_data = store.account_data.clone();
+        let mut monitor =
+            KeyTransparencyMonitor::new(monitored_account(), store, MonitorSchedule::default());
+
+        assert_matches!(monitor.check_now(&kt).await, MonitorEvent::TreeHeadFork);
+        assert_eq!(monitor.store().account_data, stored_account_data);
+    }
+
+    #[test_case(verification_failed(libsignal_keytrans::Error::SelfMonitorVersionChanged) => matches MonitorEvent::MappingChanged; "self version changed")]
+    #[test_case(verification_failed(libsignal_keytrans::Error::UnexpectedSearchValue) => matches MonitorEvent::MappingChanged; "identity key changed")]
+    #[test_case(verification_failed(libsignal_keytrans::Error::VersionDecreased) => matches MonitorEvent::TreeHeadFork; "version decreased")]
+    #[test_case(verification_failed(libsignal_keytrans::Error::VerificationFailed("bad signature".to_string())) => matches MonitorEvent::VerificationFailed(_); "other verification failure")]
+    #[test_case(RequestError::Other(Error::InvalidResponse("bad".to_string())) => matches MonitorEvent::VerificationFailed(_); "invalid response")]
+    #[test_case(RequestError::Timeout => matches MonitorEvent::RequestFailed(RequestError::Timeout); "timeout")]
+    fn errors_are_classified(error: RequestError<Error>) -> MonitorEvent {
+        MonitorEvent::from_error(error)
+    }
+
+    #[test_case(TestKt::expected_error(), MonitorSchedule::default().retry_interval; "retry interval")]
+    #[test_case(verification_failed(libsignal_keytrans::Error::UnexpectedSearchValue), MonitorSchedule::default().interval; "regular interval")]
+    #[test_case(RetryLater { retry_after_seconds: 42 }.into(), Duration::from_secs(42); "server retry after")]
+    #[tokio::test]
+    async fn failed_check_is_rescheduled(error: RequestError<Error>, expected_delay: Duration) {
+        let kt = TestKt::for_monitor(Err(error));
+        let mut monitor = KeyTransparencyMonitor::new(
+            monitored_account(),
+            store_with_account_data(),
+            MonitorSchedule::default(),
+        );
+
+        let before = SystemTime::now();
+        _ = monitor.check_now(&kt).await;
+        let after = SystemTime::now();
+
+        let next_check_at = monitor.next_check_at();
+        assert!(next_check_at >= before + expected_delay);
+        assert!(next_check_at <= after + expected_delay);
+    }
+
+    #[test]
+    fn next_check_is_based_on_stored_data() {
+        let store = store_with_account_data();
+        let stored_at_ms = store
+            .account_data
+            .as_ref()
+            .and_then(|data| data.last_tree_head.as_ref())
+            .expect("has tree head")
+            .stored_at_ms;
+        let schedule = MonitorSchedule::default();
+        let monitor = KeyTransparencyMonitor::new(monitored_account(), store, schedule);
+
+        assert_eq!(
+            monitor.next_check_at(),
+            SystemTime::UNIX_EPOCH + Duration::from_millis(stored_at_ms) + schedule.interval
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn run_reports_events_until_stopped() {
+        let kt = TestKt::for_search(Ok(test_account_data().into()));
+        let mut monitor = KeyTransparencyMonitor::new(
+            monitored_account(),
+            InMemoryKeyTransparencyStore::default(),
+            MonitorSchedule::default(),
+        );
+
+        let mut events = vec![];
+        monitor
+            .run(&kt, |event| {
+                events.push(event);
+                ControlFlow::Break(())
+            })
+            .await;
+        assert_matches!(&events[..], [MonitorEvent::Verified { .. }]);
+    }
+}
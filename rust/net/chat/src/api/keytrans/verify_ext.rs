@@ -243,9 +243,6 @@ impl<T: AsRef<[u8]>> SearchValue<T> {
         self.as_bytes()
             .filter(|returned| bool::from(returned.ct_eq(expected)))
             .map(|_| ())
-            .ok_or_else(|| {
-                libsignal_keytrans::Error::VerificationFailed("unexpected search value".to_string())
-                    .into()
-            })
+            .ok_or_else(|| libsignal_keytrans::Error::UnexpectedSearchValue.into())
     }
 }
@@ -470,7 +470,7 @@ impl<T> SignedPreKeyBody<T> {
     }
 }
 
-struct MappedToTrue;
+pub(crate) struct MappedToTrue;
 
 impl<T> serde_with::SerializeAs<HashSet<T>> for MappedToTrue
 where
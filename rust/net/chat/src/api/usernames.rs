@@ -6,11 +6,37 @@
 use std::convert::Infallible;
 
 use async_trait::async_trait;
+use displaydoc::Display;
 use either::Either;
 use libsignal_core::Aci;
 
 use super::{AllowRateLimitChallenges, RequestError};
 
+mod reservation;
+pub use reservation::{
+    ConfirmedUsername, ReserveUsernameError, SetUsernameError, UsernameLink, UsernameReservation,
+    rotate_username_link, set_username, username_suggestions,
+};
+
+pub type UsernameHash = [u8; 32];
+pub type UsernameLinkHandle = uuid::Uuid;
+
+#[derive(Debug, Display)]
+/// None of the candidate usernames were available.
+pub struct UsernameNotAvailable;
+
+#[derive(Debug, Display)]
+/// The authenticated account did not have a username set.
+pub struct UsernameNotSet;
+
+#[derive(Debug, Display, PartialEq, Eq)]
+pub enum ConfirmUsernameHashError {
+    /// the username hash was not reserved for this account
+    ReservationNotFound,
+    /// the reservation lapsed and the username was claimed by another account
+    UsernameNotAvailable,
+}
+
 /// High-level chat-server APIs for usernames
 ///
 /// ### Generic?
@@ -63,6 +89,43 @@ where
     }
 }
 
+/// High-level chat-server APIs for managing the authenticated account's own username.
+///
+/// See [`UnauthenticatedChatApi`] for an explanation of the type parameter.
+#[async_trait]
+pub trait AuthenticatedChatApi<T> {
+    // Not intended to be overridden.
+    const ALLOW_RATE_LIMIT_CHALLENGES: AllowRateLimitChallenges = AllowRateLimitChallenges::No;
+
+    /// Given a prioritized list of between 1 and 20 username hashes, try reserving them (in order)
+    ///
+    /// The first successfully reserved hash will be returned.
+    async fn reserve_username_hash(
+        &self,
+        username_hashes: &[UsernameHash],
+    ) -> Result<UsernameHash, RequestError<UsernameNotAvailable>>;
+
+    /// Sets the account's username to a previously-reserved hash, returning the handle for the
+    /// username link created from `username_ciphertext`.
+    async fn confirm_username_hash(
+        &self,
+        username_hash: &UsernameHash,
+        zk_proof: &[u8],
+        username_ciphertext: &[u8],
+    ) -> Result<UsernameLinkHandle, RequestError<ConfirmUsernameHashError>>;
+
+    /// Clears the current username hash, ciphertext, and link for the authenticated account.
+    async fn delete_username_hash(&self) -> Result<(), RequestError<Infallible>>;
+
+    /// Replaces the encrypted username for the account's username link, returning the link
+    /// handle.
+    async fn set_username_link(
+        &self,
+        username_ciphertext: &[u8],
+        keep_link_handle: bool,
+    ) -> Result<UsernameLinkHandle, RequestError<UsernameNotSet>>;
+}
+
 /// Wraps [`usernames::Username::new`] with error handling appropriate for a username retrieved from
 /// a link.
 pub(crate) fn validate_username_from_link(
@@ -0,0 +1,511 @@
+//
+// Copyright 2026 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! The client side of choosing a username.
+//!
+//! Setting a username takes two requests: first the client reserves one of several candidate
+//! username hashes, then it confirms whichever one the server picked, proving that the hash came
+//! from a valid username and uploading an encrypted copy for the account's username link.
+//! Reservations lapse after a while, so confirmation can fail even after a successful reservation.
+//!
+//! Confirmation is atomic on the server: if it fails, the account keeps whatever username it had
+//! before. Rolling back a failed attempt therefore only means discarding the reservation, which
+//! [`set_username`] does before starting over with new candidates.
+
+use std::num::NonZeroUsize;
+
+use displaydoc::Display;
+use rand::Rng as _;
+use usernames::constants::USERNAME_LINK_ENTROPY_SIZE;
+use usernames::{NicknameLimits, Username, UsernameError};
+
+use super::{
+    AuthenticatedChatApi, ConfirmUsernameHashError, UsernameHash, UsernameLinkHandle,
+    UsernameNotAvailable, UsernameNotSet,
+};
+use crate::api::RequestError;
+
+#[derive(Debug, Display)]
+pub enum ReserveUsernameError {
+    /// invalid nickname: {0}
+    InvalidNickname(UsernameError),
+    /// none of the candidate usernames were available
+    UsernameNotAvailable,
+}
+
+#[derive(Debug, Display)]
+pub enum SetUsernameError {
+    /// invalid nickname: {0}
+    InvalidNickname(UsernameError),
+    /// no username could be claimed after {0} attempts
+    UsernameNotAvailable(NonZeroUsize),
+}
+
+/// A username hash reserved for the authenticated account, waiting to be
+/// [confirmed](Self::confirm).
+#[derive(Debug)]
+pub struct UsernameReservation {
+    username: Username,
+    hash: UsernameHash,
+}
+
+/// The account's username link: a server-side handle plus the key used to encrypt the username.
+///
+/// Both are needed to build a link or QR code that others can use to look up the username.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UsernameLink {
+    pub handle: UsernameLinkHandle,
+    pub entropy: [u8; USERNAME_LINK_ENTROPY_SIZE],
+}
+
+/// The result of successfully confirming a [`UsernameReservation`].
+#[derive(Debug)]
+pub struct ConfirmedUsername {
+    pub username: Username,
+    pub hash: UsernameHash,
+    pub link: UsernameLink,
+}
+
+impl UsernameReservation {
+    /// Reserves one of a batch of randomly-generated usernames for `nickname`.
+    ///
+    /// Candidates are generated by [`Username::candidates_from`], with `nickname` checked against
+    /// `limits`.
+    pub async fn reserve_from_nickname<T>(
+        chat: &impl AuthenticatedChatApi<T>,
+        nickname: &str,
+        limits: NicknameLimits,
+        rng: &mut (dyn rand::CryptoRng + Send),
+    ) -> Result<Self, RequestError<ReserveUsernameError>> {
+        let candidates = username_suggestions(nickname, limits, rng)
+            .map_err(|e| RequestError::Other(ReserveUsernameError::InvalidNickname(e)))?;
+        Self::reserve_first_available(chat, candidates).await
+    }
+
+    /// Reserves exactly `username`, for when the user has picked their own discriminator.
+    pub async fn reserve<T>(
+        chat: &impl AuthenticatedChatApi<T>,
+        username: Username,
+    ) -> Result<Self, RequestError<ReserveUsernameError>> {
+        Self::reserve_first_available(chat, vec![username]).await
+    }
+
+    async fn reserve_first_available<T>(
+        chat: &impl AuthenticatedChatApi<T>,
+        candidates: Vec<Username>,
+    ) -> Result<Self, RequestError<ReserveUsernameError>> {
+        let hashes = candidates.iter().map(Username::hash).collect::<Vec<_>>();
+        let hash = chat.reserve_username_hash(&hashes).await.map_err(|e| {
+            e.flat_map_other(|UsernameNotAvailable| {
+                RequestError::Other(ReserveUsernameError::UsernameNotAvailable)
+            })
+        })?;
+        let index = hashes
+            .iter()
+            .position(|candidate| *candidate == hash)
+            .ok_or_else(|| RequestError::Unexpected {
+                log_safe: "server reserved a username hash that wasn't requested".to_owned(),
+            })?;
+        let username = candidates
+            .into_iter()
+            .nth(index)
+            .expect("one hash per candidate");
+        Ok(Self { username, hash })
+    }
+
+    pub fn username(&self) -> &Username {
+        &self.username
+    }
+
+    pub fn hash(&self) -> &UsernameHash {
+        &self.hash
+    }
+
+    /// Claims the reserved username for the account, along with a new username link.
+    ///
+    /// On failure the reservation is consumed and the account's previous username (if any) is left
+    /// in place.
+    pub async fn confirm<T>(
+        self,
+        chat: &impl AuthenticatedChatApi<T>,
+        rng: &mut (dyn rand::CryptoRng + Send),
+    ) -> Result<ConfirmedUsername, RequestError<ConfirmUsernameHashError>> {
+        let Self { username, hash } = self;
+        let proof = username
+            .proof(&rng.random())
+            .map_err(|e| RequestError::Unexpected {
+                log_safe: format!("failed to prove username hash: {e}"),
+            })?;
+        let (entropy, username_ciphertext) = encrypt_for_link(&username, &mut *rng)?;
+        let handle = chat
+            .confirm_username_hash(&hash, &proof, &username_ciphertext)
+            .await?;
+        Ok(ConfirmedUsername {
+            username,
+            hash,
+            link: UsernameLink { handle, entropy },
+        })
+    }
+}
+
+/// Generates a batch of random usernames for `nickname`, suitable for offering to the user or for
+/// [reserving](UsernameReservation::reserve_from_nickname) all at once.
+pub fn username_suggestions(
+    nickname: &str,
+    limits: NicknameLimits,
+    mut rng: &mut (dyn rand::CryptoRng + Send),
+) -> Result<Vec<Username>, UsernameError> {
+    Username::candidates_from(&mut rng, nickname, limits)?
+        .iter()
+        .map(|candidate| Username::new(candidate))
+        .collect()
+}
+
+/// Reserves and confirms a username for `nickname`, retrying with new candidates when none are
+/// available or a reservation lapses before it can be confirmed.
+///
+/// Gives up after `max_attempts` rounds of reservation, leaving the account's existing username
+/// (if any) in place.
+pub async fn set_username<T>(
+    chat: &impl AuthenticatedChatApi<T>,
+    nickname: &str,
+    limits: &NicknameLimits,
+    max_attempts: NonZeroUsize,
+    rng: &mut (dyn rand::CryptoRng + Send),
+) -> Result<ConfirmedUsername, RequestError<SetUsernameError>> {
+    let not_available = || SetUsernameError::UsernameNotAvailable(max_attempts);
+    for attempt in 1..=max_attempts.get() {
+        let reservation = match UsernameReservation::reserve_from_nickname(
+            chat,
+            nickname,
+            limits.clone(),
+            &mut *rng,
+        )
+        .await
+        {
+            Ok(reservation) => reservation,
+            Err(RequestError::Other(ReserveUsernameError::UsernameNotAvailable)) => {
+                log::info!("no candidate usernames available (attempt {attempt})");
+                continue;
+            }
+            Err(e) => {
+                return Err(e.flat_map_other(|e| {
+                    RequestError::Other(match e {
+                        ReserveUsernameError::InvalidNickname(e) => {
+                            SetUsernameError::InvalidNickname(e)
+                        }
+                        ReserveUsernameError::UsernameNotAvailable => not_available(),
+                    })
+                }));
+            }
+        };
+
+        match reservation.confirm(chat, &mut *rng).await {
+            Ok(confirmed) => return Ok(confirmed),
+            Err(RequestError::Other(e)) => {
+                log::info!("failed to confirm username reservation (attempt {attempt}): {e}");
+            }
+            Err(e) => return Err(e.flat_map_other(|_| RequestError::Other(not_available()))),
+        }
+    }
+    Err(RequestError::Other(not_available()))
+}
+
+/// Replaces the account's username link with one encrypted under a fresh key, so that previously
+/// shared links and QR codes stop working.
+///
+/// If `keep_link_handle` is set, the server-side handle is reused; old links will still point to
+/// the new ciphertext, but can no longer decrypt it.
+pub async fn rotate_username_link<T>(
+    chat: &impl AuthenticatedChatApi<T>,
+    username: &Username,
+    keep_link_handle: bool,
+    rng: &mut (dyn rand::CryptoRng + Send),
+) -> Result<UsernameLink, RequestError<UsernameNotSet>> {
+    let (entropy, username_ciphertext) = encrypt_for_link(username, rng)?;
+    let handle = chat
+        .set_username_link(&username_ciphertext, keep_link_handle)
+        .await?;
+    Ok(UsernameLink { handle, entropy })
+}
+
+fn encrypt_for_link<E>(
+    username: &Username,
+    mut rng: &mut (dyn rand::CryptoRng + Send),
+) -> Result<([u8; USERNAME_LINK_ENTROPY_SIZE], Vec<u8>), RequestError<E>> {
+    usernames::create_for_username(&mut rng, username.to_string(), None).map_err(|e| {
+        RequestError::Unexpected {
+            log_safe: format!("failed to encrypt username for link: {e}"),
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::Infallible;
+    use std::sync::Mutex;
+
+    use assert_matches::assert_matches;
+    use async_trait::async_trait;
+    use futures_util::FutureExt as _;
+    use nonzero_ext::nonzero;
+
+    use super::*;
+    use crate::api::testutil::fixed_seed_test_rng;
+
+    const LINK_HANDLE: UsernameLinkHandle = uuid::uuid!("C525F4F7-AF58-47CC-936E-D1B717F3C50A");
+
+    /// A fake server that hands out reservations and confirmations from scripted responses.
+    #[derive(Default)]
+    struct FakeChat {
+        /// For each reservation request, the index of the candidate to reserve, or `None` if
+        /// nothing is available.
+        reservations: Mutex<Vec<Option<usize>>>,
+        /// The result of each confirmation request.
+        confirmations: Mutex<Vec<Result<(), ConfirmUsernameHashError>>>,
+        reserved: Mutex<Option<UsernameHash>>,
+        confirmed: Mutex<Option<(UsernameHash, Vec<u8>)>>,
+        link_ciphertext: Mutex<Option<Vec<u8>>>,
+    }
+
+    impl FakeChat {
+        fn new(
+            reservations: impl IntoIterator<Item = Option<usize>>,
+            confirmations: impl IntoIterator<Item = Result<(), ConfirmUsernameHashError>>,
+        ) -> Self {
+            // Stored in reverse so that we can pop from the end.
+            let mut reservations = Vec::from_iter(reservations);
+            reservations.reverse();
+            let mut confirmations = Vec::from_iter(confirmations);
+            confirmations.reverse();
+            Self {
+                reservations: reservations.into(),
+                confirmations: confirmations.into(),
+                ..Default::default()
+            }
+        }
+    }
+
+    #[async_trait]
+    impl AuthenticatedChatApi<()> for FakeChat {
+        async fn reserve_username_hash(
+            &self,
+            username_hashes: &[UsernameHash],
+        ) -> Result<UsernameHash, RequestError<UsernameNotAvailable>> {
+            let index = self
+                .reservations
+                .lock()
+                .expect("not poisoned")
+                .pop()
+                .expect("unexpected reservation")
+                .ok_or(RequestError::Other(UsernameNotAvailable))?;
+            let hash = username_hashes[index];
+            *self.reserved.lock().expect("not poisoned") = Some(hash);
+            Ok(hash)
+        }
+
+        async fn confirm_username_hash(
+            &self,
+            username_hash: &UsernameHash,
+            zk_proof: &[u8],
+            username_ciphertext: &[u8],
+        ) -> Result<UsernameLinkHandle, RequestError<ConfirmUsernameHashError>> {
+            assert_eq!(
+                self.reserved.lock().expect("not poisoned").take().as_ref(),
+                Some(username_hash),
+                "only reserved hashes should be confirmed"
+            );
+            Username::verify_proof(zk_proof, *username_hash).expect("valid proof");
+            self.confirmations
+                .lock()
+                .expect("not poisoned")
+                .pop()
+                .expect("unexpected confirmation")
+                .map_err(RequestError::Other)?;
+            *self.confirmed.lock().expect("not poisoned") =
+                Some((*username_hash, username_ciphertext.to_vec()));
+            Ok(LINK_HANDLE)
+        }
+
+        async fn delete_username_hash(&self) -> Result<(), RequestError<Infallible>> {
+            *self.reserved.lock().expect("not poisoned") = None;
+            *self.confirmed.lock().expect("not poisoned") = None;
+            *self.link_ciphertext.lock().expect("not poisoned") = None;
+            Ok(())
+        }
+
+        async fn set_username_link(
+            &self,
+            username_ciphertext: &[u8],
+            keep_link_handle: bool,
+        ) -> Result<UsernameLinkHandle, RequestError<UsernameNotSet>> {
+            if self.confirmed.lock().expect("not poisoned").is_none() {
+                return Err(RequestError::Other(UsernameNotSet));
+            }
+            *self.link_ciphertext.lock().expect("not poisoned") =
+                Some(username_ciphertext.to_vec());
+            Ok(if keep_link_handle {
+                LINK_HANDLE
+            } else {
+                uuid::Uuid::nil()
+            })
+        }
+    }
+
+    fn decrypt_link(link: &UsernameLink, ciphertext: &[u8]) -> String {
+        usernames::decrypt_username(&link.entropy, ciphertext).expect("valid ciphertext")
+    }
+
+    #[test]
+    fn reserve_and_confirm() {
+        let chat = FakeChat::new([Some(2)], [Ok(())]);
+        let mut rng = fixed_seed_test_rng();
+
+        let reservation = UsernameReservation::reserve_from_nickname(
+            &chat,
+            "moxie",
+            NicknameLimits::default(),
+            &mut rng,
+        )
+        .now_or_never()
+        .expect("sync")
+        .expect("reserved");
+        assert!(reservation.username().to_string().starts_with("moxie."));
+
+        let expected_hash = *reservation.hash();
+        let confirmed = reservation
+            .confirm(&chat, &mut rng)
+            .now_or_never()
+            .expect("sync")
+            .expect("confirmed");
+        assert_eq!(confirmed.hash, expected_hash);
+        assert_eq!(confirmed.link.handle, LINK_HANDLE);
+
+        let (hash, ciphertext) = chat
+            .confirmed
+            .lock()
+            .expect("not poisoned")
+            .take()
+            .expect("confirmed");
+        assert_eq!(hash, expected_hash);
+        assert_eq!(
+            decrypt_link(&confirmed.link, &ciphertext),
+            confirmed.username.to_string()
+        );
+    }
+
+    #[test]
+    fn reserve_exact_username() {
+        let chat = FakeChat::new([None], []);
+        let username = Username::new("moxie.01").expect("valid");
+        assert_matches!(
+            UsernameReservation::reserve(&chat, username)
+                .now_or_never()
+                .expect("sync"),
+            Err(RequestError::Other(
+                ReserveUsernameError::UsernameNotAvailable
+            ))
+        );
+    }
+
+    #[test]
+    fn nickname_limits_are_enforced() {
+        let chat = FakeChat::default();
+        assert_matches!(
+            set_username(
+                &chat,
+                "moxie",
+                &NicknameLimits::new(6, 32),
+                nonzero!(3usize),
+                &mut fixed_seed_test_rng(),
+            )
+            .now_or_never()
+            .expect("sync"),
+            Err(RequestError::Other(SetUsernameError::InvalidNickname(
+                UsernameError::NicknameTooShort
+            )))
+        );
+    }
+
+    #[test]
+    fn set_username_retries_after_collisions() {
+        // The first round has no available candidates, and the second round's reservation lapses
+        // before it's confirmed.
+        let chat = FakeChat::new(
+            [None, Some(0), Some(5)],
+            [Err(ConfirmUsernameHashError::UsernameNotAvailable), Ok(())],
+        );
+        let confirmed = set_username(
+            &chat,
+            "moxie",
+            &NicknameLimits::default(),
+            nonzero!(3usize),
+            &mut fixed_seed_test_rng(),
+        )
+        .now_or_never()
+        .expect("sync")
+        .expect("confirmed on third attempt");
+        assert!(confirmed.username.to_string().starts_with("moxie."));
+        assert!(chat.reservations.lock().expect("not poisoned").is_empty());
+        assert!(chat.confirmations.lock().expect("not poisoned").is_empty());
+    }
+
+    #[test]
+    fn set_username_gives_up() {
+        let chat = FakeChat::new(
+            [Some(0), None],
+            [Err(ConfirmUsernameHashError::ReservationNotFound)],
+        );
+        assert_matches!(
+            set_username(
+                &chat,
+                "moxie",
+                &NicknameLimits::default(),
+                nonzero!(2usize),
+                &mut fixed_seed_test_rng(),
+            )
+            .now_or_never()
+            .expect("sync"),
+            Err(RequestError::Other(SetUsernameError::UsernameNotAvailable(n))) if n.get() == 2
+        );
+        assert!(chat.confirmed.lock().expect("not poisoned").is_none());
+    }
+
+    #[test]
+    fn rotate_link() {
+        let chat = FakeChat::new([Some(0)], [Ok(())]);
+        let mut rng = fixed_seed_test_rng();
+        let confirmed = set_username(
+            &chat,
+            "moxie",
+            &NicknameLimits::default(),
+            nonzero!(1usize),
+            &mut rng,
+        )
+        .now_or_never()
+        .expect("sync")
+        .expect("confirmed");
+
+        let rotated = rotate_username_link(&chat, &confirmed.username, false, &mut rng)
+            .now_or_never()
+            .expect("sync")
+            .expect("rotated");
+        assert_ne!(rotated.entropy, confirmed.link.entropy);
+        assert_ne!(rotated.handle, confirmed.link.handle);
+
+        let ciphertext = chat
+            .link_ciphertext
+            .lock()
+            .expect("not poisoned")
+            .take()
+            .expect("link set");
+        assert_eq!(
+            decrypt_link(&rotated, &ciphertext),
+            confirmed.username.to_string()
+        );
+        usernames::decrypt_username(&confirmed.link.entropy, &ciphertext)
+            .expect_err("old link no longer works");
+    }
+}
@@ -6,7 +6,6 @@
 use std::convert::Infallible;
 
 use async_trait::async_trait;
-use displaydoc::Display;
 use libsignal_core::Aci;
 use libsignal_net_grpc::proto::chat::account::accounts_anonymous_client::AccountsAnonymousClient;
 use libsignal_net_grpc::proto::chat::account::accounts_client::AccountsClient;
@@ -16,18 +15,13 @@ use uuid::Uuid;
 
 use super::{GrpcServiceProvider, OverGrpc, log_and_send};
 use crate::api::usernames::validate_username_from_link;
+pub use crate::api::usernames::{
+    ConfirmUsernameHashError, UsernameHash, UsernameLinkHandle, UsernameNotAvailable,
+    UsernameNotSet,
+};
 use crate::api::{Auth, RequestError, Unauth};
 use crate::logging::{Redact, RedactHex};
 
-pub type UsernameHash = [u8; 32];
-#[derive(Debug, Display)]
-/// None of the candidate usernames were available.
-pub struct UsernameNotAvailable;
-
-#[derive(Debug, Display)]
-/// The authenticated account did not have a username set.
-pub struct UsernameNotSet;
-
 impl std::fmt::Display for Redact<ReserveUsernameHashRequest> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let Self(ReserveUsernameHashRequest { username_hashes }) = self;
@@ -37,6 +31,21 @@ impl std::fmt::Display for Redact<ReserveUsernameHashRequest> {
     }
 }
 
+impl std::fmt::Display for Redact<ConfirmUsernameHashRequest> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self(ConfirmUsernameHashRequest {
+            username_hash,
+            zk_proof,
+            username_ciphertext,
+        }) = self;
+        f.debug_struct("ConfirmUsernameHash")
+            .field("username_hash", &RedactHex(&hex::encode(username_hash)))
+            .field("zk_proof.len", &zk_proof.len())
+            .field("username_ciphertext.len", &username_ciphertext.len())
+            .finish()
+    }
+}
+
 impl std::fmt::Display for Redact<SetUsernameLinkRequest> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let Self(SetUsernameLinkRequest {
@@ -64,8 +73,6 @@ impl std::fmt::Display for Redact<DeleteUsernameLinkRequest> {
     }
 }
 
-pub type UsernameLinkHandle = Uuid;
-
 impl<T: GrpcServiceProvider> Auth<T> {
     /// Given a prioritized list of between 1 and 20 username hashes, try reserving them (in order)
     ///
@@ -98,6 +105,58 @@ impl<T: GrpcServiceProvider> Auth<T> {
         }
     }
 
+    /// Sets the account's username to `username_hash`, which must have been reserved with
+    /// [`Self::reserve_username_hash`].
+    ///
+    /// `zk_proof` shows that the hash was derived from a valid username (see
+    /// [`usernames::Username::proof`]), and `username_ciphertext` is the encrypted username for the
+    /// account's new username link. Returns the handle for that link.
+    pub async fn confirm_username_hash(
+        &self,
+        username_hash: &UsernameHash,
+        zk_proof: &[u8],
+        username_ciphertext: &[u8],
+    ) -> Result<UsernameLinkHandle, RequestError<ConfirmUsernameHashError>> {
+        let mut client = AccountsClient::new(self.0.service());
+        let request = ConfirmUsernameHashRequest {
+            username_hash: username_hash.to_vec(),
+            zk_proof: zk_proof.to_vec(),
+            username_ciphertext: username_ciphertext.to_vec(),
+        };
+        let desc = Redact(&request).to_string();
+        match log_and_send("auth", &desc, || client.confirm_username_hash(request))
+            .await?
+            .into_inner()
+            .response
+            .ok_or_else(|| RequestError::Unexpected {
+                log_safe: "missing response".to_string(),
+            })? {
+            confirm_username_hash_response::Response::ConfirmedUsernameHash(
+                confirm_username_hash_response::ConfirmedUsernameHash {
+                    username_hash: confirmed_hash,
+                    username_link_handle,
+                },
+            ) => {
+                if confirmed_hash != username_hash {
+                    return Err(RequestError::Unexpected {
+                        log_safe: "confirmed a different username hash".to_string(),
+                    });
+                }
+                Uuid::from_slice(&username_link_handle).map_err(|_| RequestError::Unexpected {
+                    log_safe: "invalid uuid".to_string(),
+                })
+            }
+            confirm_username_hash_response::Response::ReservationNotFound(_) => Err(
+                RequestError::Other(ConfirmUsernameHashError::ReservationNotFound),
+            ),
+            confirm_username_hash_response::Response::UsernameNotAvailable(
+                libsignal_net_grpc::proto::chat::account::UsernameNotAvailable {},
+            ) => Err(RequestError::Other(
+                ConfirmUsernameHashError::UsernameNotAvailable,
+            )),
+        }
+    }
+
     /// For the given encrypted username, generate a username link handle. The username link handle
     /// can be used to lookup the encrypted username.
     ///
@@ -167,6 +226,37 @@ impl<T: GrpcServiceProvider> Auth<T> {
     }
 }
 
+#[async_trait]
+impl<T: GrpcServiceProvider> crate::api::usernames::AuthenticatedChatApi<OverGrpc> for Auth<T> {
+    async fn reserve_username_hash(
+        &self,
+        username_hashes: &[UsernameHash],
+    ) -> Result<UsernameHash, RequestError<UsernameNotAvailable>> {
+        Auth::reserve_username_hash(self, username_hashes).await
+    }
+
+    async fn confirm_username_hash(
+        &self,
+        username_hash: &UsernameHash,
+        zk_proof: &[u8],
+        username_ciphertext: &[u8],
+    ) -> Result<UsernameLinkHandle, RequestError<ConfirmUsernameHashError>> {
+        Auth::confirm_username_hash(self, username_hash, zk_proof, username_ciphertext).await
+    }
+
+    async fn delete_username_hash(&self) -> Result<(), RequestError<Infallible>> {
+        Auth::delete_username_hash(self).await
+    }
+
+    async fn set_username_link(
+        &self,
+        username_ciphertext: &[u8],
+        keep_link_handle: bool,
+    ) -> Result<UsernameLinkHandle, RequestError<UsernameNotSet>> {
+        Auth::set_username_link(self, username_ciphertext, keep_link_handle).await
+    }
+}
+
 #[async_trait]
 impl<T: GrpcServiceProvider> crate::api::usernames::UnauthenticatedChatApi<OverGrpc> for Unauth<T> {
     async fn look_up_username_hash(
@@ -327,6 +417,82 @@ pub mod test_cases {
             },
         ]
     }
+    pub struct ConfirmUsernameHashArgs {
+        pub username_hash: UsernameHash,
+        pub zk_proof: Vec<u8>,
+        pub username_ciphertext: Vec<u8>,
+    }
+    pub enum ConfirmUsernameHashOut {
+        Success(UsernameLinkHandle),
+        Failure(ConfirmUsernameHashError),
+        Unexpected,
+    }
+    pub fn confirm_username_hash_test_cases() -> Vec<
+        GrpcTestCase<
+            ConfirmUsernameHashArgs,
+            ConfirmUsernameHashRequest,
+            ConfirmUsernameHashResponse,
+            ConfirmUsernameHashOut,
+        >,
+    > {
+        let method = "/org.signal.chat.account.Accounts/ConfirmUsernameHash";
+        let username_hash = *b"................................";
+        let zk_proof = b"proof".to_vec();
+        let username_ciphertext = b"fun encrypted username".to_vec();
+        let username_link_handle = uuid::uuid!("C525F4F7-AF58-47CC-936E-D1B717F3C50A");
+        let test_case = |name: &str,
+                         response: confirm_username_hash_response::Response,
+                         out: ConfirmUsernameHashOut| GrpcTestCase {
+            name: name.to_string(),
+            method: method.to_string(),
+            request: ConfirmUsernameHashArgs {
+                username_hash,
+                zk_proof: zk_proof.clone(),
+                username_ciphertext: username_ciphertext.clone(),
+            },
+            request_grpc: ConfirmUsernameHashRequest {
+                username_hash: username_hash.to_vec(),
+                zk_proof: zk_proof.clone(),
+                username_ciphertext: username_ciphertext.clone(),
+            },
+            response_grpc: ConfirmUsernameHashResponse {
+                response: Some(response),
+            },
+            response: out,
+        };
+        vec![
+            test_case(
+                "success",
+                confirm_username_hash_response::Response::ConfirmedUsernameHash(
+                    confirm_username_hash_response::ConfirmedUsernameHash {
+                        username_hash: username_hash.to_vec(),
+                        username_link_handle: username_link_handle.into(),
+                    },
+                ),
+                ConfirmUsernameHashOut::Success(username_link_handle),
+            ),
+            test_case(
+                "confirmed a different hash",
+                confirm_username_hash_response::Response::ConfirmedUsernameHash(
+                    confirm_username_hash_response::ConfirmedUsernameHash {
+                        username_hash: b"++++++++++++++++++++++++++++++++".to_vec(),
+                        username_link_handle: username_link_handle.into(),
+                    },
+                ),
+                ConfirmUsernameHashOut::Unexpected,
+            ),
+            test_case(
+                "reservation not found",
+                confirm_username_hash_response::Response::ReservationNotFound(Default::default()),
+                ConfirmUsernameHashOut::Failure(ConfirmUsernameHashError::ReservationNotFound),
+            ),
+            test_case(
+                "reservation lapsed",
+                confirm_username_hash_response::Response::UsernameNotAvailable(Default::default()),
+                ConfirmUsernameHashOut::Failure(ConfirmUsernameHashError::UsernameNotAvailable),
+            ),
+        ]
+    }
     pub struct SetUsernameLinkArgs {
         pub username_ciphertext: Vec<u8>,
         pub keep_link_handle: bool,
@@ -579,6 +745,34 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_confirm_username_hash() {
+        use test_cases::*;
+        run_tests(
+            confirm_username_hash_test_cases(),
+            |chat: Auth<_>,
+             ConfirmUsernameHashArgs {
+                 username_hash,
+                 zk_proof,
+                 username_ciphertext,
+             }| async move {
+                chat.confirm_username_hash(&username_hash, &zk_proof, &username_ciphertext)
+                    .await
+            },
+            |resp, result| match resp {
+                ConfirmUsernameHashOut::Success(out) => {
+                    assert_matches!(result, Ok(x) if x == out)
+                }
+                ConfirmUsernameHashOut::Failure(expected) => {
+                    assert_matches!(result, Err(RequestError::Other(e)) if e == expected)
+                }
+                ConfirmUsernameHashOut::Unexpected => {
+                    assert_matches!(result, Err(RequestError::Unexpected { .. }))
+                }
+            },
+        );
+    }
+
     #[test]
     fn test_set_username_link() {
         use test_cases::*;
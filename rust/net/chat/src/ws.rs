@@ -7,6 +7,7 @@
 //! websocket, as implemented in [`libsignal_net::chat`].
 
 mod backups;
+mod devices;
 mod keys;
 mod keytrans;
 mod messages;
@@ -0,0 +1,575 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use http::{HeaderMap, Method};
+use libsignal_core::{Aci, DeviceId, Pni};
+use libsignal_net::auth::Auth as BasicAuth;
+use libsignal_net::chat::Request;
+use libsignal_net::infra::AsHttpHeader as _;
+use serde_with::{FromInto, serde_as, skip_serializing_none};
+use uuid::Uuid;
+
+use super::{CONTENT_TYPE_JSON, CustomError, Empty, OverWs, TryIntoResponse, WsConnection};
+use crate::api::devices::{
+    DeviceLimitReached, LinkDeviceError, LinkDeviceResponse, LinkedDeviceAttributes,
+    LinkedDeviceKeys, LinkingCode, ProvisioningAddressNotFound,
+};
+use crate::api::registration::{
+    ForServiceIds, MappedToTrue, NewMessageNotification, SignedPreKeyBody,
+};
+use crate::api::{Auth, RequestError, Unauth};
+use crate::logging::RedactBase64;
+
+type Base64Padded =
+    serde_with::base64::Base64<serde_with::base64::Standard, serde_with::formats::Padded>;
+
+#[serde_as]
+#[skip_serializing_none]
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LinkDeviceRequest<'a> {
+    verification_code: &'a str,
+    account_attributes: LinkedAccountAttributes<'a>,
+    aci_signed_pre_key: SignedPreKeyBody<&'a [u8]>,
+    pni_signed_pre_key: SignedPreKeyBody<&'a [u8]>,
+    aci_pq_last_resort_pre_key: SignedPreKeyBody<&'a [u8]>,
+    pni_pq_last_resort_pre_key: SignedPreKeyBody<&'a [u8]>,
+    apn_token: Option<ApnToken<'a>>,
+    gcm_token: Option<GcmToken<'a>>,
+}
+
+#[serde_as]
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LinkedAccountAttributes<'a> {
+    fetches_messages: bool,
+    registration_id: u16,
+    pni_registration_id: u16,
+    #[serde_as(as = "Base64Padded")]
+    name: &'a [u8],
+    #[serde_as(as = "MappedToTrue")]
+    capabilities: HashSet<&'a str>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ApnToken<'a> {
+    apn_registration_id: &'a str,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GcmToken<'a> {
+    gcm_registration_id: &'a str,
+}
+
+#[serde_as]
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LinkDeviceResponseBody {
+    #[serde_as(as = "FromInto<Uuid>")]
+    uuid: Aci,
+    #[serde_as(as = "FromInto<Uuid>")]
+    pni: Pni,
+    device_id: u8,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LinkingCodeResponse {
+    verification_code: String,
+    token_identifier: String,
+}
+
+#[serde_as]
+#[derive(serde::Serialize)]
+struct ProvisioningMessageRequest<'a> {
+    #[serde_as(as = "Base64Padded")]
+    body: &'a [u8],
+}
+
+#[async_trait]
+impl<T: WsConnection> crate::api::devices::UnauthenticatedChatApi<OverWs> for Unauth<T> {
+    async fn link_device(
+        &self,
+        number: &str,
+        password: &str,
+        provisioning_code: &str,
+        attributes: LinkedDeviceAttributes<'_>,
+        keys: ForServiceIds<LinkedDeviceKeys<'_>>,
+        message_notification: NewMessageNotification<&str>,
+    ) -> Result<LinkDeviceResponse, RequestError<LinkDeviceError>> {
+        let LinkedDeviceAttributes {
+            registration_id,
+            pni_registration_id,
+            name,
+            capabilities,
+        } = attributes;
+
+        let (fetches_messages, apn_token, gcm_token) = match message_notification {
+            NewMessageNotification::Apn(apn) => (
+                false,
+                Some(ApnToken {
+                    apn_registration_id: apn,
+                }),
+                None,
+            ),
+            NewMessageNotification::Gcm(gcm) => (
+                false,
+                None,
+                Some(GcmToken {
+                    gcm_registration_id: gcm,
+                }),
+            ),
+            NewMessageNotification::WillFetchMessages => (true, None, None),
+        };
+
+        let request = LinkDeviceRequest {
+            verification_code: provisioning_code,
+            account_attributes: LinkedAccountAttributes {
+                fetches_messages,
+                registration_id,
+                pni_registration_id,
+                name,
+                capabilities,
+            },
+            aci_signed_pre_key: keys.aci.signed_pre_key,
+            pni_signed_pre_key: keys.pni.signed_pre_key,
+            aci_pq_last_resort_pre_key: keys.aci.pq_last_resort_pre_key,
+            pni_pq_last_resort_pre_key: keys.pni.pq_last_resort_pre_key,
+            apn_token,
+            gcm_token,
+        };
+
+        let response = self
+            .send(
+                "unauth",
+                "/v1/devices/link",
+                Request {
+                    method: Method::PUT,
+                    path: http::uri::PathAndQuery::from_static("/v1/devices/link"),
+                    headers: HeaderMap::from_iter([
+                        CONTENT_TYPE_JSON,
+                        BasicAuth {
+                            username: number,
+                            password,
+                        }
+                        .as_header(),
+                    ]),
+                    body: Some(serde_json::to_vec(&request).expect("can serialize").into()),
+                },
+            )
+            .await?;
+
+        let LinkDeviceResponseBody {
+            uuid: aci,
+            pni,
+            device_id,
+        } = response.try_into_response().map_err(|e| {
+            e.into_request_error(Self::ALLOW_RATE_LIMIT_CHALLENGES, |response| match response
+                .status
+                .as_u16()
+            {
+                403 => CustomError::Err(LinkDeviceError::InvalidProvisioningCode),
+                411 => CustomError::Err(LinkDeviceError::DeviceLimitReached),
+                _ => CustomError::NoCustomHandling,
+            })
+        })?;
+
+        let device_id = DeviceId::new(device_id).map_err(|_| RequestError::Unexpected {
+            log_safe: format!("invalid device ID {device_id}"),
+        })?;
+
+        Ok(LinkDeviceResponse {
+            aci,
+            pni,
+            device_id,
+        })
+    }
+}
+
+#[async_trait]
+impl<T: WsConnection> crate::api::devices::AuthenticatedChatApi<OverWs> for Auth<T> {
+    async fn get_device_linking_code(
+        &self,
+    ) -> Result<LinkingCode, RequestError<DeviceLimitReached>> {
+        let response = self
+            .send(
+                "auth",
+                "/v1/devices/provisioning/code",
+                Request {
+                    method: Method::GET,
+                    path: http::uri::PathAndQuery::from_static("/v1/devices/provisioning/code"),
+                    headers: HeaderMap::new(),
+                    body: None,
+                },
+            )
+            .await?;
+
+        let LinkingCodeResponse {
+            verification_code,
+            token_identifier,
+        } = response.try_into_response().map_err(|e| {
+            e.into_request_error(Self::ALLOW_RATE_LIMIT_CHALLENGES, |response| match response
+                .status
+                .as_u16()
+            {
+                411 => CustomError::Err(DeviceLimitReached),
+                _ => CustomError::NoCustomHandling,
+            })
+        })?;
+
+        Ok(LinkingCode {
+            verification_code,
+            token_identifier,
+        })
+    }
+
+    async fn send_provisioning_message(
+        &self,
+        address: &str,
+        envelope: &[u8],
+    ) -> Result<(), RequestError<ProvisioningAddressNotFound>> {
+        let path = format!("/v1/provisioning/{address}").parse().map_err(|_| {
+            RequestError::Unexpected {
+                log_safe: "provisioning address is not valid in a URL path".to_owned(),
+            }
+        })?;
+
+        let response = self
+            .send(
+                "auth",
+                &format!("/v1/provisioning/{}", RedactBase64(address)),
+                Request {
+                    method: Method::PUT,
+                    path,
+                    headers: HeaderMap::from_iter([CONTENT_TYPE_JSON]),
+                    body: Some(
+                        serde_json::to_vec(&ProvisioningMessageRequest { body: envelope })
+                            .expect("can serialize")
+                            .into(),
+                    ),
+                },
+            )
+            .await?;
+
+        let Empty = response.try_into_response().map_err(|e| {
+            e.into_request_error(Self::ALLOW_RATE_LIMIT_CHALLENGES, |response| match response
+                .status
+                .as_u16()
+            {
+                404 => CustomError::Err(ProvisioningAddressNotFound),
+                _ => CustomError::NoCustomHandling,
+            })
+        })?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use base64::prelude::{BASE64_STANDARD, Engine as _};
+    use futures_util::FutureExt as _;
+    use libsignal_account_keys::AccountEntropyPool;
+    use libsignal_net::chat::provisioning::{
+        DeviceLinkUrl, ProvisionMessage, ProvisioningCipher, encrypt_provision_message,
+    };
+    use libsignal_net::chat::{self, ChatConnection};
+    use libsignal_net::proto::chat_websocket::WebSocketResponseMessage;
+    use libsignal_protocol::IdentityKeyPair;
+    use serde_json::json;
+    use test_case::test_case;
+
+    use super::*;
+    use crate::api::devices::{AuthenticatedChatApi as _, UnauthenticatedChatApi as _};
+    use crate::api::testutil::fixed_seed_test_rng;
+    use crate::ws::testutil::{JsonRequestValidator, RequestValidator, empty, json};
+
+    const ACI_UUID: &str = "9d0652a3-dcc3-4d11-975f-74d61598733f";
+    const PNI_UUID: &str = "796abedb-ca4e-4f18-8803-1fde5b921f9f";
+    const NUMBER: &str = "+18005550101";
+    const PASSWORD: &str = "device password";
+
+    fn link_device_response() -> String {
+        format!(r#"{{"uuid":"{ACI_UUID}","pni":"{PNI_UUID}","deviceId":2}}"#)
+    }
+
+    fn link_device_request_json() -> serde_json::Value {
+        let pre_key = |id: u8| {
+            json!({
+                "keyId": id,
+                "publicKey": BASE64_STANDARD.encode([id; 4]),
+                "signature": BASE64_STANDARD.encode([id; 8]),
+            })
+        };
+        json!({
+            "verificationCode": "provisioning code",
+            "accountAttributes": {
+                "fetchesMessages": false,
+                "registrationId": 123,
+                "pniRegistrationId": 456,
+                "name": "ZGV2aWNlIG5hbWU=",
+                "capabilities": {"capability": true},
+            },
+            "aciSignedPreKey": pre_key(1),
+            "pniSignedPreKey": pre_key(2),
+            "aciPqLastResortPreKey": pre_key(3),
+            "pniPqLastResortPreKey": pre_key(4),
+            "gcmToken": {"gcmRegistrationId": "gcm id"},
+        })
+    }
+
+    fn link_device(
+        chat: &Unauth<impl WsConnection>,
+        provisioning_code: &str,
+    ) -> impl Future<Output = Result<LinkDeviceResponse, RequestError<LinkDeviceError>>> {
+        let pre_key = |id: u8| SignedPreKeyBody {
+            key_id: id.into(),
+            public_key: [id; 4].to_vec(),
+            signature: [id; 8].to_vec(),
+        };
+        let keys = [pre_key(1), pre_key(2), pre_key(3), pre_key(4)];
+        let provisioning_code = provisioning_code.to_owned();
+        async move {
+            chat.link_device(
+                NUMBER,
+                PASSWORD,
+                &provisioning_code,
+                LinkedDeviceAttributes {
+                    registration_id: 123,
+                    pni_registration_id: 456,
+                    name: b"device name",
+                    capabilities: HashSet::from(["capability"]),
+                },
+                ForServiceIds {
+                    aci: LinkedDeviceKeys {
+                        signed_pre_key: keys[0].as_deref(),
+                        pq_last_resort_pre_key: keys[2].as_deref(),
+                    },
+                    pni: LinkedDeviceKeys {
+                        signed_pre_key: keys[1].as_deref(),
+                        pq_last_resort_pre_key: keys[3].as_deref(),
+                    },
+                },
+                NewMessageNotification::Gcm("gcm id"),
+            )
+            .await
+        }
+    }
+
+    #[test_case(json(200, link_device_response()) => matches Ok(LinkDeviceResponse { device_id, .. }) if u8::from(device_id) == 2)]
+    #[test_case(json(200, format!(r#"{{"uuid":"{ACI_UUID}","pni":"{PNI_UUID}","deviceId":0}}"#)) => matches Err(RequestError::Unexpected { .. }))]
+    #[test_case(empty(403) => matches Err(RequestError::Other(LinkDeviceError::InvalidProvisioningCode)))]
+    #[test_case(empty(411) => matches Err(RequestError::Other(LinkDeviceError::DeviceLimitReached)))]
+    #[test_case(empty(422) => matches Err(RequestError::Unexpected { .. }))]
+    #[test_case(empty(500) => matches Err(RequestError::ServerSideError))]
+    fn test_link_device(
+        response: chat::Response,
+    ) -> Result<LinkDeviceResponse, RequestError<LinkDeviceError>> {
+        let validator = JsonRequestValidator {
+            expected: Request {
+                method: Method::PUT,
+                path: http::uri::PathAndQuery::from_static("/v1/devices/link"),
+                headers: HeaderMap::from_iter([
+                    CONTENT_TYPE_JSON,
+                    BasicAuth {
+                        username: NUMBER,
+                        password: PASSWORD,
+                    }
+                    .as_header(),
+                ]),
+                body: None,
+            },
+            body: link_device_request_json(),
+            response,
+        };
+        link_device(&Unauth(validator), "provisioning code")
+            .now_or_never()
+            .expect("sync")
+    }
+
+    #[test_case(json(
+        200, r#"{"verificationCode":"code","tokenIdentifier":"token"}"#
+    ) => matches Ok(LinkingCode { verification_code, token_identifier }) if verification_code == "code" && token_identifier == "token")]
+    #[test_case(empty(411) => matches Err(RequestError::Other(DeviceLimitReached)))]
+    #[test_case(empty(500) => matches Err(RequestError::ServerSideError))]
+    fn test_get_device_linking_code(
+        response: chat::Response,
+    ) -> Result<LinkingCode, RequestError<DeviceLimitReached>> {
+        let validator = RequestValidator {
+            expected: Request {
+                method: Method::GET,
+                path: http::uri::PathAndQuery::from_static("/v1/devices/provisioning/code"),
+                headers: HeaderMap::new(),
+                body: None,
+            },
+            response,
+        };
+        Auth(validator)
+            .get_device_linking_code()
+            .now_or_never()
+            .expect("sync")
+    }
+
+    #[test_case(empty(204) => matches Ok(()))]
+    #[test_case(empty(404) => matches Err(RequestError::Other(ProvisioningAddressNotFound)))]
+    #[test_case(empty(500) => matches Err(RequestError::ServerSideError))]
+    fn test_send_provisioning_message(
+        response: chat::Response,
+    ) -> Result<(), RequestError<ProvisioningAddressNotFound>> {
+        let validator = JsonRequestValidator {
+            expected: Request {
+                method: Method::PUT,
+                path: http::uri::PathAndQuery::from_static("/v1/provisioning/address"),
+                headers: HeaderMap::from_iter([CONTENT_TYPE_JSON]),
+                body: None,
+            },
+            body: json!({ "body": "ZW52ZWxvcGU=" }),
+            response,
+        };
+        Auth(validator)
+            .send_provisioning_message("address", b"envelope")
+            .now_or_never()
+            .expect("sync")
+    }
+
+    fn json_response(id: Option<u64>, body: impl Into<bytes::Bytes>) -> WebSocketResponseMessage {
+        WebSocketResponseMessage {
+            id,
+            status: Some(200),
+            message: None,
+            headers: vec!["content-type: application/json".to_owned()],
+            body: Some(body.into()),
+        }
+    }
+
+    #[tokio::test]
+    async fn link_device_end_to_end() {
+        let mut rng = fixed_seed_test_rng();
+        let (primary_chat, primary_remote) =
+            ChatConnection::new_fake(tokio::runtime::Handle::current(), Box::new(|_| {}), [], []);
+        let (secondary_chat, secondary_remote) =
+            ChatConnection::new_fake(tokio::runtime::Handle::current(), Box::new(|_| {}), [], []);
+
+        // The new device waits on its provisioning connection and shows a QR code.
+        const PROVISIONING_ADDRESS: &str = "provisioning-address";
+        let cipher = ProvisioningCipher::generate(&mut rng);
+        let link_url = cipher
+            .device_link_url(PROVISIONING_ADDRESS.to_owned())
+            .to_string();
+
+        // The primary device scans it, gets a linking code, and sends its account state over.
+        let aci_identity_key_pair = IdentityKeyPair::generate(&mut rng);
+        let account_entropy_pool = AccountEntropyPool::generate(&mut rng);
+        let expected_entropy_pool = account_entropy_pool.to_string();
+        let primary = async {
+            let url: DeviceLinkUrl = link_url.parse().expect("valid");
+            let LinkingCode {
+                verification_code, ..
+            } = Auth(&primary_chat)
+                .get_device_linking_code()
+                .await
+                .expect("can get code");
+            let message = ProvisionMessage {
+                aci: ACI_UUID.parse::<Uuid>().expect("valid").into(),
+                pni: PNI_UUID.parse::<Uuid>().expect("valid").into(),
+                number: NUMBER.to_owned(),
+                aci_identity_key_pair,
+                pni_identity_key_pair: IdentityKeyPair::generate(&mut rng),
+                profile_key: [0x55; 32],
+                account_entropy_pool,
+                media_root_backup_key: None,
+                provisioning_code: verification_code,
+                read_receipts: false,
+                user_agent: None,
+            };
+            let envelope = encrypt_provision_message(&url.public_key, &message, &mut rng);
+            Auth(&primary_chat)
+                .send_provisioning_message(&url.address, &envelope)
+                .await
+        };
+        let server = async {
+            let request = primary_remote
+                .receive_request()
+                .await
+                .expect("still receiving")
+                .expect("received request");
+            assert_eq!(request.path(), "/v1/devices/provisioning/code");
+            primary_remote
+                .send_response(json_response(
+                    request.id,
+                    r#"{"verificationCode":"the code","tokenIdentifier":"token"}"#,
+                ))
+                .expect("not disconnected");
+
+            let request = primary_remote
+                .receive_request()
+                .await
+                .expect("still receiving")
+                .expect("received request");
+            assert_eq!(
+                request.path(),
+                format!("/v1/provisioning/{PROVISIONING_ADDRESS}")
+            );
+            let body: serde_json::Value =
+                serde_json::from_slice(request.body()).expect("valid JSON");
+            primary_remote
+                .send_response(WebSocketResponseMessage {
+                    id: request.id,
+                    status: Some(204),
+                    ..Default::default()
+                })
+                .expect("not disconnected");
+
+            BASE64_STANDARD
+                .decode(body["body"].as_str().expect("has body"))
+                .expect("valid base64")
+        };
+        let (sent, envelope) = tokio::join!(primary, server);
+        sent.expect("can send provisioning message");
+
+        // The server relays the envelope to the new device, which decrypts it and links itself.
+        let message = cipher.decrypt(&envelope).expect("can decrypt");
+        assert_eq!(message.number, NUMBER);
+        assert_eq!(message.aci.service_id_string(), ACI_UUID);
+        assert_eq!(
+            message.aci_identity_key_pair.public_key(),
+            aci_identity_key_pair.public_key()
+        );
+        assert_eq!(
+            message.account_entropy_pool.to_string(),
+            expected_entropy_pool
+        );
+
+        let secondary_chat = Unauth(&secondary_chat);
+        let secondary = link_device(&secondary_chat, &message.provisioning_code);
+        let server = async {
+            let request = secondary_remote
+                .receive_request()
+                .await
+                .expect("still receiving")
+                .expect("received request");
+            assert_eq!(request.path(), "/v1/devices/link");
+            let body: serde_json::Value =
+                serde_json::from_slice(request.body()).expect("valid JSON");
+            assert_eq!(body["verificationCode"], "the code");
+            secondary_remote
+                .send_response(json_response(request.id, link_device_response()))
+                .expect("not disconnected");
+        };
+        let (linked, ()) = tokio::join!(secondary, server);
+        let linked = linked.expect("can link");
+        assert_matches!(linked, LinkDeviceResponse { aci, pni, device_id } => {
+            assert_eq!(aci, message.aci);
+            assert_eq!(pni, message.pni);
+            assert_eq!(u8::from(device_id), 2);
+        });
+    }
+}
@@ -354,7 +354,6 @@ impl<T: WsConnection> LowLevelChatApi for Unauth<T> {
 mod test_support {
     use std::time::SystemTime;
 
-    use libsignal_keytrans::ChatSearchResponse;
     use libsignal_net::chat::ChatConnection;
     use libsignal_net::env;
     use libsignal_net::infra::EnableDomainFronting;
@@ -389,7 +388,6 @@ mod test_support {
     // - Execute the test as `cargo test --package libsignal-net-chat --all-features collect_test_data -- --nocapture`
     // - Follow the prompts
     // - Replace the "const" definitions in the code with the ones printed out by the test.
-    // - Copy the "chat_search_response.dat" file to "rust/net/tests/data/" replacing the existing one.
     //
     // #[tokio::test]
     async fn collect_test_data() {
@@ -477,40 +475,6 @@ mod test_support {
                 hex::encode(stored_account_data)
             );
         }
-
-        prompt("Now advance the tree. Yes, again! (and press ENTER)");
-
-        let raw_request = RawChatSearchRequest::new(
-            &aci,
-            &aci_identity_key,
-            Some(&e164),
-            Some(&username_hash),
-            Some(account_data.last_tree_head.0.tree_size),
-            distinguished_tree.0.tree_size,
-        );
-        let response_bytes = chat
-            .send_kt_request(raw_request.into())
-            .await
-            .expect("can send raw search request");
-
-        {
-            let search_response = ChatSearchResponse::decode(response_bytes.as_ref())
-                .map_err(|_| Error::InvalidResponse("bad protobuf".to_string()))
-                .and_then(TypedSearchResponse::from_untyped)
-                .expect("valid search response");
-
-            let tree_size = search_response.full_tree_head.tree_head.unwrap().tree_size;
-            assert_ne!(last_tree_size, tree_size, "The tree did not advance!");
-        }
-
-        println!(
-            "Update the net/tests/data/chat_response_valid_at.in file to: `Duration::from_secs({})`",
-            SystemTime::UNIX_EPOCH.elapsed().unwrap().as_secs()
-        );
-
-        const PATH: &str = "/tmp/chat_search_response.dat";
-        println!("Response written to '{PATH}'");
-        std::fs::write(PATH, &response_bytes).unwrap()
     }
 }
 
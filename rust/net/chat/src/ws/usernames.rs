@@ -12,9 +12,15 @@ use libsignal_net::chat::Request;
 use libsignal_net_grpc::proto::chat::services;
 use serde_with::serde_as;
 
-use super::{CustomError, OverWs, ResponseError, TryIntoResponse, WsConnection, expect_empty_body};
-use crate::api::usernames::validate_username_from_link;
-use crate::api::{RequestError, Unauth};
+use super::{
+    CONTENT_TYPE_JSON, CustomError, Empty, OverWs, ResponseError, TryIntoResponse, WsConnection,
+    expect_empty_body,
+};
+use crate::api::usernames::{
+    ConfirmUsernameHashError, UsernameHash, UsernameLinkHandle, UsernameNotAvailable,
+    UsernameNotSet, validate_username_from_link,
+};
+use crate::api::{Auth, RequestError, Unauth};
 use crate::logging::{Redact, RedactBase64};
 
 type Base64Url =
@@ -137,6 +143,215 @@ impl<T: WsConnection> crate::api::usernames::UnauthenticatedChatApi<OverWs> for
     }
 }
 
+#[serde_as]
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReserveUsernameHashRequest<'a> {
+    #[serde_as(as = "Vec<Base64Url>")]
+    username_hashes: Vec<&'a UsernameHash>,
+}
+
+#[serde_as]
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConfirmUsernameHashRequest<'a> {
+    #[serde_as(as = "Base64Url")]
+    username_hash: &'a UsernameHash,
+    #[serde_as(as = "Base64Url")]
+    zk_proof: &'a [u8],
+    #[serde_as(as = "Base64Url")]
+    encrypted_username: &'a [u8],
+}
+
+#[serde_as]
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SetUsernameLinkRequest<'a> {
+    #[serde_as(as = "Base64Url")]
+    username_link_encrypted_value: &'a [u8],
+    keep_link_handle: bool,
+}
+
+#[serde_as]
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UsernameHashResponse {
+    #[serde_as(as = "Base64Url")]
+    username_hash: UsernameHash,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UsernameLinkHandleResponse {
+    username_link_handle: UsernameLinkHandle,
+}
+
+#[serde_as]
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConfirmUsernameHashResponse {
+    #[serde_as(as = "Base64Url")]
+    username_hash: UsernameHash,
+    username_link_handle: UsernameLinkHandle,
+}
+
+#[async_trait]
+impl<T: WsConnection> crate::api::usernames::AuthenticatedChatApi<OverWs> for Auth<T> {
+    async fn reserve_username_hash(
+        &self,
+        username_hashes: &[UsernameHash],
+    ) -> Result<UsernameHash, RequestError<UsernameNotAvailable>> {
+        let response = self
+            .send(
+                "auth",
+                "/v1/accounts/username_hash/reserve",
+                Request {
+                    method: http::Method::PUT,
+                    path: http::uri::PathAndQuery::from_static(
+                        "/v1/accounts/username_hash/reserve",
+                    ),
+                    headers: http::HeaderMap::from_iter([CONTENT_TYPE_JSON]),
+                    body: Some(
+                        serde_json::to_vec(&ReserveUsernameHashRequest {
+                            username_hashes: username_hashes.iter().collect(),
+                        })
+                        .expect("can serialize")
+                        .into(),
+                    ),
+                },
+            )
+            .await?;
+
+        let UsernameHashResponse { username_hash } = response.try_into_response().map_err(|e| {
+            e.into_request_error(Self::ALLOW_RATE_LIMIT_CHALLENGES, |response| match response
+                .status
+                .as_u16()
+            {
+                409 => CustomError::Err(UsernameNotAvailable),
+                _ => CustomError::NoCustomHandling,
+            })
+        })?;
+
+        Ok(username_hash)
+    }
+
+    async fn confirm_username_hash(
+        &self,
+        username_hash: &UsernameHash,
+        zk_proof: &[u8],
+        username_ciphertext: &[u8],
+    ) -> Result<UsernameLinkHandle, RequestError<ConfirmUsernameHashError>> {
+        let response = self
+            .send(
+                "auth",
+                "/v1/accounts/username_hash/confirm",
+                Request {
+                    method: http::Method::PUT,
+                    path: http::uri::PathAndQuery::from_static(
+                        "/v1/accounts/username_hash/confirm",
+                    ),
+                    headers: http::HeaderMap::from_iter([CONTENT_TYPE_JSON]),
+                    body: Some(
+                        serde_json::to_vec(&ConfirmUsernameHashRequest {
+                            username_hash,
+                            zk_proof,
+                            encrypted_username: username_ciphertext,
+                        })
+                        .expect("can serialize")
+                        .into(),
+                    ),
+                },
+            )
+            .await?;
+
+        let ConfirmUsernameHashResponse {
+            username_hash: confirmed_hash,
+            username_link_handle,
+        } = response.try_into_response().map_err(|e| {
+            e.into_request_error(Self::ALLOW_RATE_LIMIT_CHALLENGES, |response| match response
+                .status
+                .as_u16()
+            {
+                409 => CustomError::Err(ConfirmUsernameHashError::ReservationNotFound),
+                410 => CustomError::Err(ConfirmUsernameHashError::UsernameNotAvailable),
+                _ => CustomError::NoCustomHandling,
+            })
+        })?;
+
+        if &confirmed_hash != username_hash {
+            return Err(RequestError::Unexpected {
+                log_safe: "confirmed a different username hash".to_string(),
+            });
+        }
+
+        Ok(username_link_handle)
+    }
+
+    async fn delete_username_hash(&self) -> Result<(), RequestError<Infallible>> {
+        let response = self
+            .send(
+                "auth",
+                "/v1/accounts/username_hash",
+                Request {
+                    method: http::Method::DELETE,
+                    path: http::uri::PathAndQuery::from_static("/v1/accounts/username_hash"),
+                    headers: http::HeaderMap::new(),
+                    body: None,
+                },
+            )
+            .await?;
+
+        let Empty = response.try_into_response().map_err(|e| {
+            e.into_request_error(
+                Self::ALLOW_RATE_LIMIT_CHALLENGES,
+                CustomError::no_custom_handling,
+            )
+        })?;
+
+        Ok(())
+    }
+
+    async fn set_username_link(
+        &self,
+        username_ciphertext: &[u8],
+        keep_link_handle: bool,
+    ) -> Result<UsernameLinkHandle, RequestError<UsernameNotSet>> {
+        let response = self
+            .send(
+                "auth",
+                "/v1/accounts/username_link",
+                Request {
+                    method: http::Method::PUT,
+                    path: http::uri::PathAndQuery::from_static("/v1/accounts/username_link"),
+                    headers: http::HeaderMap::from_iter([CONTENT_TYPE_JSON]),
+                    body: Some(
+                        serde_json::to_vec(&SetUsernameLinkRequest {
+                            username_link_encrypted_value: username_ciphertext,
+                            keep_link_handle,
+                        })
+                        .expect("can serialize")
+                        .into(),
+                    ),
+                },
+            )
+            .await?;
+
+        let UsernameLinkHandleResponse {
+            username_link_handle,
+        } = response.try_into_response().map_err(|e| {
+            e.into_request_error(Self::ALLOW_RATE_LIMIT_CHALLENGES, |response| match response
+                .status
+                .as_u16()
+            {
+                409 => CustomError::Err(UsernameNotSet),
+                _ => CustomError::NoCustomHandling,
+            })
+        })?;
+
+        Ok(username_link_handle)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use assert_matches::assert_matches;
@@ -145,9 +360,9 @@ mod test {
     use test_case::test_case;
 
     use super::*;
-    use crate::api::usernames::UnauthenticatedChatApi;
+    use crate::api::usernames::{AuthenticatedChatApi, UnauthenticatedChatApi};
     use crate::grpc::testutil::GrpcOverrideRequestValidator;
-    use crate::ws::testutil::{RequestValidator, empty, json};
+    use crate::ws::testutil::{JsonRequestValidator, RequestValidator, empty, json};
 
     const ACI_UUID: &str = "9d0652a3-dcc3-4d11-975f-74d61598733f";
 
@@ -271,6 +486,129 @@ mod test {
         );
     }
 
+    const HASH_0: UsernameHash = [0xff; 32];
+    const HASH_1: UsernameHash = [0x00; 32];
+    // base64url of HASH_0 and HASH_1, respectively.
+    const HASH_0_BASE64: &str = "__________________________________________8";
+    const HASH_1_BASE64: &str = "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA";
+    const LINK_HANDLE: &str = "c525f4f7-af58-47cc-936e-d1b717f3c50a";
+
+    #[test_case(json(
+        200, format!(r#"{{"usernameHash":"{HASH_1_BASE64}"}}"#)
+    ) => matches Ok(hash) if hash == HASH_1)]
+    #[test_case(json(
+        200, r#"{"usernameHash":"AAAA"}"#
+    ) => matches Err(RequestError::Unexpected { .. }))]
+    #[test_case(empty(409) => matches Err(RequestError::Other(UsernameNotAvailable)))]
+    #[test_case(empty(422) => matches Err(RequestError::Unexpected { .. }))]
+    #[test_case(empty(500) => matches Err(RequestError::ServerSideError))]
+    fn test_reserve_username_hash(
+        response: chat::Response,
+    ) -> Result<UsernameHash, RequestError<UsernameNotAvailable>> {
+        let validator = JsonRequestValidator {
+            expected: Request {
+                method: http::Method::PUT,
+                path: http::uri::PathAndQuery::from_static("/v1/accounts/username_hash/reserve"),
+                headers: http::HeaderMap::from_iter([CONTENT_TYPE_JSON]),
+                body: None,
+            },
+            body: serde_json::json!({ "usernameHashes": [HASH_0_BASE64, HASH_1_BASE64] }),
+            response,
+        };
+        Auth(validator)
+            .reserve_username_hash(&[HASH_0, HASH_1])
+            .now_or_never()
+            .expect("sync")
+    }
+
+    #[test_case(json(
+        200, format!(r#"{{"usernameHash":"{HASH_0_BASE64}","usernameLinkHandle":"{LINK_HANDLE}"}}"#)
+    ) => matches Ok(handle) if handle.to_string() == LINK_HANDLE)]
+    #[test_case(json(
+        200, format!(r#"{{"usernameHash":"{HASH_0_BASE64}","usernameLinkHandle":"garbage"}}"#)
+    ) => matches Err(RequestError::Unexpected { .. }))]
+    #[test_case(json(
+        200, format!(r#"{{"usernameHash":"{HASH_1_BASE64}","usernameLinkHandle":"{LINK_HANDLE}"}}"#)
+    ) => matches Err(RequestError::Unexpected { .. }); "different hash")]
+    #[test_case(json(
+        200, format!(r#"{{"usernameLinkHandle":"{LINK_HANDLE}"}}"#)
+    ) => matches Err(RequestError::Unexpected { .. }); "missing hash")]
+    #[test_case(empty(409) => matches Err(RequestError::Other(ConfirmUsernameHashError::ReservationNotFound)))]
+    #[test_case(empty(410) => matches Err(RequestError::Other(ConfirmUsernameHashError::UsernameNotAvailable)))]
+    #[test_case(empty(500) => matches Err(RequestError::ServerSideError))]
+    fn test_confirm_username_hash(
+        response: chat::Response,
+    ) -> Result<UsernameLinkHandle, RequestError<ConfirmUsernameHashError>> {
+        let validator = JsonRequestValidator {
+            expected: Request {
+                method: http::Method::PUT,
+                path: http::uri::PathAndQuery::from_static("/v1/accounts/username_hash/confirm"),
+                headers: http::HeaderMap::from_iter([CONTENT_TYPE_JSON]),
+                body: None,
+            },
+            body: serde_json::json!({
+                "usernameHash": HASH_0_BASE64,
+                "zkProof": "cHJvb2Y",
+                "encryptedUsername": "Y2lwaGVydGV4dA",
+            }),
+            response,
+        };
+        Auth(validator)
+            .confirm_username_hash(&HASH_0, b"proof", b"ciphertext")
+            .now_or_never()
+            .expect("sync")
+    }
+
+    #[test_case(empty(204) => matches Ok(()))]
+    #[test_case(json(200, "{}") => matches Err(RequestError::Unexpected { .. }))]
+    #[test_case(empty(500) => matches Err(RequestError::ServerSideError))]
+    fn test_delete_username_hash(response: chat::Response) -> Result<(), RequestError<Infallible>> {
+        let validator = RequestValidator {
+            expected: Request {
+                method: http::Method::DELETE,
+                path: http::uri::PathAndQuery::from_static("/v1/accounts/username_hash"),
+                headers: http::HeaderMap::new(),
+                body: None,
+            },
+            response,
+        };
+        Auth(validator)
+            .delete_username_hash()
+            .now_or_never()
+            .expect("sync")
+    }
+
+    #[test_case(true, json(
+        200, format!(r#"{{"usernameLinkHandle":"{LINK_HANDLE}"}}"#)
+    ) => matches Ok(handle) if handle.to_string() == LINK_HANDLE)]
+    #[test_case(false, json(
+        200, format!(r#"{{"usernameLinkHandle":"{LINK_HANDLE}"}}"#)
+    ) => matches Ok(handle) if handle.to_string() == LINK_HANDLE)]
+    #[test_case(false, empty(409) => matches Err(RequestError::Other(UsernameNotSet)))]
+    #[test_case(false, empty(500) => matches Err(RequestError::ServerSideError))]
+    fn test_set_username_link(
+        keep_link_handle: bool,
+        response: chat::Response,
+    ) -> Result<UsernameLinkHandle, RequestError<UsernameNotSet>> {
+        let validator = JsonRequestValidator {
+            expected: Request {
+                method: http::Method::PUT,
+                path: http::uri::PathAndQuery::from_static("/v1/accounts/username_link"),
+                headers: http::HeaderMap::from_iter([CONTENT_TYPE_JSON]),
+                body: None,
+            },
+            body: serde_json::json!({
+                "usernameLinkEncryptedValue": "Y2lwaGVydGV4dA",
+                "keepLinkHandle": keep_link_handle,
+            }),
+            response,
+        };
+        Auth(validator)
+            .set_username_link(b"ciphertext", keep_link_handle)
+            .now_or_never()
+            .expect("sync")
+    }
+
     #[test]
     fn test_grpc_override() {
         use libsignal_net_grpc::proto::chat as grpc_chat;
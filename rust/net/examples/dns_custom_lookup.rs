@@ -7,12 +7,12 @@ use std::sync::Arc;
 
 use clap::{Parser, ValueEnum};
 use const_str::ip_addr;
-use either::{Either, for_both};
 use libsignal_net::infra::certs::RootCertificates;
 use libsignal_net::infra::dns::custom_resolver::CustomDnsResolver;
 use libsignal_net::infra::dns::dns_lookup::{DnsLookup, DnsLookupRequest};
 use libsignal_net::infra::host::Host;
 use libsignal_net_infra::dns::dns_transport_doh::DohTransportConnectorFactory;
+use libsignal_net_infra::dns::dns_transport_dot::{DEFAULT_DOT_PORT, DotTransportConnectorFactory};
 use libsignal_net_infra::dns::dns_transport_udp::UdpTransportConnectorFactory;
 use libsignal_net_infra::route::{
     HttpRouteFragment, HttpVersion, HttpsTlsRoute, TcpRoute, TlsRoute, TlsRouteFragment, UdpRoute,
@@ -29,6 +29,8 @@ enum Transport {
     Udp,
     /// Send DNS-over-HTTPS request
     Doh,
+    /// Send DNS-over-TLS request
+    Dot,
 }
 
 #[derive(Parser, Debug)]
@@ -51,13 +53,13 @@ async fn main() {
     let args = Args::parse();
     const HOST_IP: IpAddr = ip_addr!("1.1.1.1");
 
-    let custom_resolver = match args.transport {
+    let custom_resolver: Box<dyn DnsLookup> = match args.transport {
         Transport::Udp => {
             let ns_address = UdpRoute {
                 address: HOST_IP,
                 port: nonzero!(53u16),
             };
-            Either::Left(CustomDnsResolver::new(
+            Box::new(CustomDnsResolver::new(
                 vec![ns_address],
                 UdpTransportConnectorFactory,
                 &no_network_change_events(),
@@ -79,6 +81,7 @@ async fn main() {
                         sni: Host::Domain(host.clone()),
                         alpn: Some(Alpn::Http2),
                         min_protocol_version: None,
+                        ech_config: None,
                     },
                     inner: TcpRoute {
                         address: HOST_IP,
@@ -87,13 +90,35 @@ async fn main() {
                     },
                 },
             };
-            Either::Right(CustomDnsResolver::new(
+            Box::new(CustomDnsResolver::new(
                 vec![target],
                 DohTransportConnectorFactory,
                 &no_network_change_events(),
                 DNS_LATER_RESPONSE_GRACE_PERIOD,
             ))
         }
+        Transport::Dot => {
+            let target = TlsRoute {
+                fragment: TlsRouteFragment {
+                    root_certs: RootCertificates::Native,
+                    sni: Host::Ip(HOST_IP),
+                    alpn: None,
+                    min_protocol_version: None,
+                    ech_config: None,
+                },
+                inner: TcpRoute {
+                    address: HOST_IP,
+                    port: DEFAULT_DOT_PORT,
+                    override_nagle_algorithm: OverrideNagleAlgorithm::UseSystemDefault,
+                },
+            };
+            Box::new(CustomDnsResolver::new(
+                vec![target],
+                DotTransportConnectorFactory,
+                &no_network_change_events(),
+                DNS_LATER_RESPONSE_GRACE_PERIOD,
+            ))
+        }
     };
 
     let lookup_request = DnsLookupRequest {
@@ -103,7 +128,7 @@ async fn main() {
 
     // first time making a DNS query
     let started_at = Instant::now();
-    let result = for_both!(custom_resolver, ref c => c.dns_lookup(lookup_request.clone()).await);
+    let result = custom_resolver.dns_lookup(lookup_request.clone()).await;
     log::info!(
         "DNS lookup result for [{}] after {:?}: {:?}",
         args.domain,
@@ -113,7 +138,7 @@ async fn main() {
 
     // second time retrieving from cache
     let started_at = Instant::now();
-    let result = for_both!(custom_resolver, ref c => c.dns_lookup(lookup_request.clone()).await);
+    let result = custom_resolver.dns_lookup(lookup_request.clone()).await;
     log::info!(
         "DNS lookup result for [{}] after {:?}: {:?}",
         args.domain,
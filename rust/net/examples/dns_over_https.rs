@@ -58,6 +58,7 @@ async fn main() {
                 sni: Host::Domain(host),
                 alpn: Some(Alpn::Http2),
                 min_protocol_version: None,
+                ech_config: None,
             },
             inner: TcpRoute {
                 address,
@@ -87,9 +88,8 @@ async fn main() {
     log::info!("sending DNS request: {request:?}");
     let mut stream = doh_transport.send_queries(request).await.unwrap();
 
-    let next_response = stream.next().await;
-    log::info!("received first response from DNS: [{next_response:?}]");
-
-    let next_response = stream.next().await;
-    log::info!("received second response from DNS: [{next_response:?}]");
+    // Responses to the A, AAAA (if enabled), and HTTPS queries, in whatever order they arrive.
+    while let Some(response) = stream.next().await {
+        log::info!("received response from DNS: [{response:?}]");
+    }
 }
@@ -100,6 +100,7 @@ async fn main() {
                 sni: Host::Domain(host_name),
                 alpn: None,
                 min_protocol_version: None,
+                ech_config: None,
             },
             inner: SocksRoute {
                 proxy: TcpRoute {
@@ -7,18 +7,24 @@ use std::borrow::Cow;
 use std::sync::{Arc, OnceLock};
 
 use boring_signal::error::ErrorStack;
-use boring_signal::ssl::{SslAlert, SslConnectorBuilder, SslVerifyMode};
+use boring_signal::ex_data::Index;
+use boring_signal::ssl::{Ssl, SslAlert, SslConnectorBuilder, SslRef, SslVerifyMode};
 use boring_signal::x509::X509;
 use boring_signal::x509::store::X509StoreBuilder;
 use futures_util::future::BoxFuture;
 use libsignal_core::LogSafeDisplay;
-use rustls::client::danger::{ServerCertVerified, ServerCertVerifier};
-use rustls::pki_types::{CertificateDer, ServerName};
+use rustls::DigitallySignedStruct;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
 
 use crate::dns::dns_utils::log_safe_domain;
 use crate::host::Host;
 
 mod error;
+pub use error::PolicyViolation;
+
+mod policy;
+pub use policy::*;
 
 #[derive(thiserror::Error, Debug, displaydoc::Display)]
 pub enum Error {
@@ -26,6 +32,10 @@ pub enum Error {
     BadCertificate,
     /// Bad hostname
     BadHostname,
+    /// Certificate pins must include at least one backup
+    MissingBackupPin,
+    /// Certificate Transparency can only be required for native roots
+    CertificateTransparencyRequiresNativeRoots,
 }
 
 impl From<ErrorStack> for Error {
@@ -39,15 +49,24 @@ pub enum RootCertificates {
     Native,
     FromStaticDers(&'static [&'static [u8]]),
     FromDer(Cow<'static, [u8]>),
+    /// Verifies against `roots`, then additionally enforces `policy` on the resulting chain.
+    ///
+    /// Useful when connecting to a server whose certificate isn't issued from Signal's own roots,
+    /// so that a compromised or coerced CA can't be used to intercept the connection.
+    WithPolicy {
+        roots: Arc<RootCertificates>,
+        policy: Arc<CertificatePolicy>,
+    },
 }
 
 impl RootCertificates {
     /// Configures `connector` to verify certificates against `self`.
     ///
-    /// **Warning:** If `self` is [`RootCertificates::Native`], the resulting connector will
-    /// **depend on tokio** to verify certificates (using rustls-platform-verifier, isolated to a
-    /// blocking task thread). Moreover, when using the resulting [`Ssl`](boring_signal::ssl::Ssl)
-    /// object, you must call `set_task_waker`. This will be taken care of for you if you use
+    /// **Warning:** If `self` is [`RootCertificates::Native`] (including within
+    /// [`RootCertificates::WithPolicy`]), the resulting connector will **depend on tokio** to verify
+    /// certificates (using rustls-platform-verifier, isolated to a blocking task thread). Moreover,
+    /// when using the resulting [`Ssl`] object with `Native` or `WithPolicy` roots, you must call
+    /// `set_task_waker`. This will be taken care of for you if you use
     /// tokio-boring (and always poll within a tokio context).
     pub fn apply_to_connector(
         &self,
@@ -56,37 +75,15 @@ impl RootCertificates {
     ) -> Result<(), Error> {
         let ders: &[&[u8]] = match self {
             RootCertificates::Native => {
-                static VERIFIER: OnceLock<Box<dyn LimitedServerCertVerifier>> = OnceLock::new();
-
-                let verifier = VERIFIER.get_or_init(|| {
-                    let mut verifier = rustls_platform_verifier::Verifier::new();
-                    if cfg!(target_os = "linux")
-                        && rustls::crypto::CryptoProvider::get_default().is_none()
-                    {
-                        // On Linux rustls-platform-verifier uses the webpki crate, which requires a
-                        // rustls CryptoProvider. On the other platforms, rustls-platform-verifier ought
-                        // to work even with no provider set, so we omit this to avoid taking a
-                        // dependency on ring.
-                        verifier.set_provider(rustls::crypto::ring::default_provider().into())
-                    }
-
-                    if cfg!(target_os = "android") {
-                        // rustls-platform-verifier's Android code permanently
-                        // attaches the thread that makes the verification calls
-                        // to the JVM. Use an implementation that calls into
-                        // verification code on a background thread to prevent
-                        // the current thread from being attached to the JVM.
-                        //
-                        // See https://github.com/rustls/rustls-platform-verifier/issues/184
-                        Box::new(BackgroundThreadVerifier::new(verifier))
-                    } else {
-                        Box::new(TokioBlockingThreadVerifier::new(verifier))
-                    }
-                });
-                return set_up_platform_verifier(connector, host, &**verifier);
+                return set_up_platform_verifier(connector, host, native_verifier());
             }
             RootCertificates::FromStaticDers(ders) => ders,
             RootCertificates::FromDer(der) => &[der],
+            RootCertificates::WithPolicy { .. } => {
+                // BoringSSL's own verification can't be extended with our policy checks, so the
+                // whole chain goes through rustls instead.
+                return set_up_platform_verifier(connector, host, self.limited_verifier()?);
+            }
         };
         let mut store_builder = X509StoreBuilder::new()?;
         for der in ders {
@@ -95,6 +92,109 @@ impl RootCertificates {
         connector.set_verify_cert_store(store_builder.build())?;
         Ok(())
     }
+
+    /// Produces a TLS 1.3 [`rustls::ClientConfig`] that verifies certificates against `self`.
+    ///
+    /// This is for QUIC, where the handshake is carried out by rustls rather than BoringSSL.
+    ///
+    /// **Warning:** Unlike [`Self::apply_to_connector`], if `self` is
+    /// [`RootCertificates::Native`], the platform verifier is invoked synchronously from within the
+    /// handshake.
+    #[cfg(feature = "quic")]
+    pub fn rustls_client_config(&self, alpn: crate::Alpn) -> Result<rustls::ClientConfig, Error> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let verifier = self.rustls_verifier(provider.clone())?;
+        let mut config = rustls::ClientConfig::builder_with_provider(provider)
+            .with_protocol_versions(&[&rustls::version::TLS13])
+            .expect("ring supports TLS 1.3")
+            .dangerous()
+            .with_custom_certificate_verifier(verifier)
+            .with_no_client_auth();
+        config.alpn_protocols = vec![alpn.encoded().to_vec()];
+        Ok(config)
+    }
+
+    /// Produces a synchronous rustls verifier for `self`.
+    fn rustls_verifier(
+        &self,
+        provider: Arc<rustls::crypto::CryptoProvider>,
+    ) -> Result<Arc<dyn ServerCertVerifier>, Error> {
+        let ders: &[&[u8]] = match self {
+            RootCertificates::Native => {
+                let mut verifier = rustls_platform_verifier::Verifier::new();
+                verifier.set_provider(provider);
+                return Ok(Arc::new(verifier));
+            }
+            RootCertificates::FromStaticDers(ders) => ders,
+            RootCertificates::FromDer(der) => &[der],
+            RootCertificates::WithPolicy { roots, policy } => {
+                policy.validate_for(roots)?;
+                return Ok(Arc::new(PolicyEnforcingVerifier {
+                    inner: roots.rustls_verifier(provider)?,
+                    policy: policy.clone(),
+                }));
+            }
+        };
+        let mut roots = rustls::RootCertStore::empty();
+        for der in ders {
+            roots
+                .add(CertificateDer::from(der.to_vec()))
+                .map_err(|_| Error::BadCertificate)?;
+        }
+        let verifier =
+            rustls::client::WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider)
+                .build()
+                .map_err(|_| Error::BadCertificate)?;
+        Ok(verifier)
+    }
+
+    /// Produces a verifier for `self` suitable for [`set_up_platform_verifier`].
+    fn limited_verifier(&self) -> Result<Box<dyn LimitedServerCertVerifier>, Error> {
+        match self {
+            RootCertificates::Native => Ok(Box::new(native_verifier())),
+            RootCertificates::FromStaticDers(_) | RootCertificates::FromDer(_) => {
+                let provider = Arc::new(rustls::crypto::ring::default_provider());
+                Ok(Box::new(InlineVerifier(self.rustls_verifier(provider)?)))
+            }
+            RootCertificates::WithPolicy { roots, policy } => {
+                policy.validate_for(roots)?;
+                Ok(Box::new(PolicyEnforcingVerifier {
+                    inner: roots.limited_verifier()?,
+                    policy: policy.clone(),
+                }))
+            }
+        }
+    }
+}
+
+/// The shared [`LimitedServerCertVerifier`] for [`RootCertificates::Native`].
+fn native_verifier() -> &'static dyn LimitedServerCertVerifier {
+    static VERIFIER: OnceLock<Box<dyn LimitedServerCertVerifier>> = OnceLock::new();
+
+    let verifier = VERIFIER.get_or_init(|| {
+        let mut verifier = rustls_platform_verifier::Verifier::new();
+        if cfg!(target_os = "linux") && rustls::crypto::CryptoProvider::get_default().is_none() {
+            // On Linux rustls-platform-verifier uses the webpki crate, which requires a
+            // rustls CryptoProvider. On the other platforms, rustls-platform-verifier ought
+            // to work even with no provider set, so we omit this to avoid taking a
+            // dependency on ring.
+            verifier.set_provider(rustls::crypto::ring::default_provider().into())
+        }
+
+        if cfg!(target_os = "android") {
+            // rustls-platform-verifier's Android code permanently
+            // attaches the thread that makes the verification calls
+            // to the JVM. Use an implementation that calls into
+            // verification code on a background thread to prevent
+            // the current thread from being attached to the JVM.
+            //
+            // See https://github.com/rustls/rustls-platform-verifier/issues/184
+            Box::new(BackgroundThreadVerifier::new(verifier))
+        } else {
+            Box::new(TokioBlockingThreadVerifier::new(verifier))
+        }
+    });
+    &**verifier
 }
 
 impl std::fmt::Debug for RootCertificates {
@@ -106,6 +206,11 @@ impl std::fmt::Debug for RootCertificates {
                 .field(&format_args!("<{} cert(s)>", ders.len()))
                 .finish(),
             Self::FromDer(_) => f.debug_tuple("FromDer").field(&"_").finish(),
+            Self::WithPolicy { roots, policy } => f
+                .debug_struct("WithPolicy")
+                .field("roots", roots)
+                .field("policy", policy)
+                .finish(),
         }
     }
 }
@@ -132,6 +237,28 @@ impl LimitedServerCertVerifier for &dyn LimitedServerCertVerifier {
     }
 }
 
+impl LimitedServerCertVerifier for Box<dyn LimitedServerCertVerifier> {
+    fn verify_server_cert(
+        &self,
+        end_entity: CertificateDer<'static>,
+        intermediates: Vec<CertificateDer<'static>>,
+        server_name: &Arc<ServerName<'static>>,
+    ) -> BoxFuture<'static, Result<ServerCertVerified, rustls::Error>> {
+        (**self).verify_server_cert(end_entity, intermediates, server_name)
+    }
+}
+
+/// The [`Ssl`] ex_data slot used to report a [`PolicyViolation`] out of the verify callback.
+fn policy_violation_index() -> Index<Ssl, PolicyViolation> {
+    static INDEX: OnceLock<Index<Ssl, PolicyViolation>> = OnceLock::new();
+    *INDEX.get_or_init(|| Ssl::new_ex_index().expect("can allocate ex_data index"))
+}
+
+/// Returns the [`PolicyViolation`] that caused `ssl`'s handshake to fail, if any.
+pub(crate) fn recorded_policy_violation(ssl: &SslRef) -> Option<&PolicyViolation> {
+    ssl.ex_data(policy_violation_index())
+}
+
 /// Configures [rustls_platform_verifier] as a BoringSSL (async) custom verify callback.
 ///
 /// We make it async because the platform verification can do unbounded work (on Android we have
@@ -183,7 +310,25 @@ fn set_up_platform_verifier(
         let host_for_logging = host_as_server_name.clone();
 
         Ok(Box::pin(async move {
-            task.await.map_err(move |e| {
+            let result = task.await;
+            if let Err(e) = &result
+                && let Some(violation) = error::policy_violation(e)
+            {
+                log::info!(
+                    "TLS certificate for {} violated policy: {}",
+                    log_safe_domain(&host_for_logging.to_str()),
+                    violation
+                );
+                // Stash the structured reason where the handshake error can find it.
+                let violation = violation.clone();
+                return Ok(Box::new(move |ssl: &mut SslRef| {
+                    ssl.set_ex_data(policy_violation_index(), violation);
+                    Err(SslAlert::CERTIFICATE_UNKNOWN)
+                })
+                    as boring_signal::ssl::BoxCustomVerifyFinish);
+            }
+
+            result.map_err(move |e| {
                 // The most important thing is to reject the certificate. Mapping the errors over
                 // only affects what message gets reported in logs. Which isn't *unimportant*, but
                 // isn't critical for correctness either.
@@ -246,6 +391,101 @@ fn set_up_platform_verifier(
     Ok(())
 }
 
+/// [`LimitedServerCertVerifier`] that runs verification synchronously.
+///
+/// Only appropriate for verifiers that never block, like [`rustls::client::WebPkiServerVerifier`].
+struct InlineVerifier(Arc<dyn ServerCertVerifier>);
+
+impl LimitedServerCertVerifier for InlineVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: CertificateDer<'static>,
+        intermediates: Vec<CertificateDer<'static>>,
+        server_name: &Arc<ServerName<'static>>,
+    ) -> BoxFuture<'static, Result<ServerCertVerified, rustls::Error>> {
+        // We don't do our own OCSP. Either the platform will do its own checks, or it won't.
+        let ocsp_response = [];
+        Box::pin(std::future::ready(self.0.verify_server_cert(
+            &end_entity,
+            &intermediates,
+            server_name,
+            &ocsp_response,
+            UnixTime::now(),
+        )))
+    }
+}
+
+/// Wraps another verifier, checking a [`CertificatePolicy`] once the chain itself is verified.
+#[derive(Debug)]
+struct PolicyEnforcingVerifier<V> {
+    inner: V,
+    policy: Arc<CertificatePolicy>,
+}
+
+impl<V: LimitedServerCertVerifier> LimitedServerCertVerifier for PolicyEnforcingVerifier<V> {
+    fn verify_server_cert(
+        &self,
+        end_entity: CertificateDer<'static>,
+        intermediates: Vec<CertificateDer<'static>>,
+        server_name: &Arc<ServerName<'static>>,
+    ) -> BoxFuture<'static, Result<ServerCertVerified, rustls::Error>> {
+        // The policy check is cheap, so do it now rather than holding on to the chain. But only
+        // report it if the chain is otherwise valid, so that ordinary failures aren't obscured.
+        let policy_result = self.policy.check(&end_entity, &intermediates);
+        let task = self
+            .inner
+            .verify_server_cert(end_entity, intermediates, server_name);
+        Box::pin(async move {
+            let verified = task.await?;
+            policy_result?;
+            Ok(verified)
+        })
+    }
+}
+
+impl ServerCertVerifier for PolicyEnforcingVerifier<Arc<dyn ServerCertVerifier>> {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let verified = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        )?;
+        self.policy.check(end_entity, intermediates)?;
+        Ok(verified)
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
 /// [`LimitedServerCertVerifier`] that runs verification on a background thread.
 struct BackgroundThreadVerifier {
     sender: tokio::sync::mpsc::Sender<(VerifyContext, BackgroundResultSender)>,
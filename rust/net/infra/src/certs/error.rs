@@ -13,6 +13,32 @@ use rustls::{
 
 use crate::dns::dns_utils::log_safe_domain;
 
+/// A verified certificate chain that nonetheless failed a [`CertificatePolicy`](super::CertificatePolicy).
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error, displaydoc::Display)]
+pub enum PolicyViolation {
+    /// no certificate in the chain matches a pinned public key
+    PinMismatch,
+    /// leaf certificate has valid SCTs from {found} distinct log operator(s), but {required} are required
+    InsufficientScts { found: usize, required: usize },
+    /// certificate could not be parsed for policy checks
+    MalformedCertificate,
+}
+impl LogSafeDisplay for PolicyViolation {}
+
+impl From<PolicyViolation> for Error {
+    fn from(value: PolicyViolation) -> Self {
+        CertificateError::Other(rustls::OtherError(std::sync::Arc::new(value))).into()
+    }
+}
+
+/// Recovers a [`PolicyViolation`] that was passed through rustls as an [`Error`].
+pub(super) fn policy_violation(error: &Error) -> Option<&PolicyViolation> {
+    match error {
+        Error::InvalidCertificate(CertificateError::Other(other)) => other.0.downcast_ref(),
+        _ => None,
+    }
+}
+
 pub(super) struct LogSafeTlsError<'a>(pub(super) &'a Error);
 
 impl LogSafeDisplay for LogSafeTlsError<'_> {}
@@ -277,7 +303,10 @@ impl std::fmt::Display for LogSafeTlsError<'_> {
                         presented.iter().map(|s| log_safe_domain(s)).collect_vec()
                     );
                 }
-                CertificateError::Other(_other_error) => {
+                CertificateError::Other(other_error) => {
+                    if let Some(violation) = other_error.0.downcast_ref::<PolicyViolation>() {
+                        return write!(f, "invalid certificate, {violation}");
+                    }
                     return f.write_str("invalid certificate, other error");
                 }
                 // This enum is non-exhaustive
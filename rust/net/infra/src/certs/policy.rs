@@ -0,0 +1,789 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Constraints on a server's certificate chain beyond "chains to a trusted root".
+
+// asn1::ParseError is large, and the derive macros return it.
+#![allow(clippy::result_large_err)]
+
+use std::collections::HashSet;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+
+use boring_signal::hash::MessageDigest;
+use boring_signal::pkey::{PKey, PKeyRef, Public};
+use boring_signal::sign::Verifier;
+use boring_signal::x509::{X509, X509Ref};
+use rustls::pki_types::CertificateDer;
+use sha2::{Digest as _, Sha256};
+
+use super::error::PolicyViolation;
+use super::{Error, RootCertificates};
+
+/// SHA-256 digest of a DER-encoded X.509 SubjectPublicKeyInfo.
+pub type SpkiSha256 = [u8; 32];
+
+/// A set of public keys, at least one of which must appear in the server's certificate chain.
+///
+/// Pins are matched against the leaf and the presented intermediates that actually issued it, i.e.
+/// the chain linked by signatures from the leaf upwards. Extra certificates the server sends
+/// alongside don't count, and a root certificate the server doesn't send can't be pinned.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct SpkiPins {
+    pins: Vec<SpkiSha256>,
+}
+
+impl SpkiPins {
+    /// Creates a pin set from the key currently in use and one or more backups.
+    ///
+    /// At least one backup pin distinct from `primary` is required, so that a lost or compromised
+    /// key can be rotated out without locking out every client that shipped with the old pins.
+    pub fn new(
+        primary: SpkiSha256,
+        backups: impl IntoIterator<Item = SpkiSha256>,
+    ) -> Result<Self, Error> {
+        let mut pins = vec![primary];
+        for backup in backups {
+            if !pins.contains(&backup) {
+                pins.push(backup);
+            }
+        }
+        if pins.len() < 2 {
+            return Err(Error::MissingBackupPin);
+        }
+        Ok(Self { pins })
+    }
+
+    fn matches_any(&self, chain: &IssuanceChain) -> bool {
+        chain
+            .spki_hashes
+            .iter()
+            .any(|spki| self.pins.contains(spki))
+    }
+}
+
+impl std::fmt::Debug for SpkiPins {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SpkiPins")
+            .field(&format_args!("<{} pin(s)>", self.pins.len()))
+            .finish()
+    }
+}
+
+/// Requirements for Certificate Transparency information in the server's leaf certificate.
+///
+/// Only signed certificate timestamps (SCTs) embedded in the certificate are considered; SCTs
+/// delivered via a TLS extension or OCSP stapling are not. An SCT only counts if it was issued by
+/// one of `logs` and its signature checks out against that log's key.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CertificateTransparencyPolicy {
+    /// The logs whose SCTs are accepted.
+    pub logs: Vec<CtLog>,
+    /// The minimum number of distinct operators whose logs must have issued a valid SCT for the
+    /// certificate.
+    ///
+    /// Counting operators rather than logs means a single operator running several logs can't
+    /// satisfy the policy on its own.
+    pub min_distinct_operators: NonZeroUsize,
+}
+
+/// A Certificate Transparency log trusted by a [`CertificateTransparencyPolicy`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CtLog {
+    /// The organization running the log.
+    pub operator: Arc<str>,
+    /// The log's public key, as a DER-encoded SubjectPublicKeyInfo.
+    pub public_key_der: Arc<[u8]>,
+}
+
+impl CtLog {
+    /// The log ID, as it appears in the log's SCTs (RFC 6962 §3.2).
+    fn log_id(&self) -> [u8; 32] {
+        Sha256::digest(&self.public_key_der).into()
+    }
+}
+
+/// Extra checks applied to a certificate chain after it has been verified against a set of
+/// roots.
+///
+/// See [`RootCertificates::WithPolicy`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CertificatePolicy {
+    pub pins: Option<SpkiPins>,
+    /// Only allowed with [`RootCertificates::Native`]; private roots don't issue SCTs.
+    pub certificate_transparency: Option<CertificateTransparencyPolicy>,
+}
+
+impl CertificatePolicy {
+    pub(super) fn validate_for(&self, roots: &RootCertificates) -> Result<(), Error> {
+        if self.certificate_transparency.is_some() && *roots != RootCertificates::Native {
+            return Err(Error::CertificateTransparencyRequiresNativeRoots);
+        }
+        Ok(())
+    }
+
+    /// Checks an already-verified chain against the policy.
+    ///
+    /// Only the certificates in [`IssuanceChain`] order are considered, so that a server can't
+    /// satisfy a pin or supply a CT issuer key just by sending an unrelated certificate.
+    pub(super) fn check(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+    ) -> Result<(), PolicyViolation> {
+        let Self {
+            pins,
+            certificate_transparency,
+        } = self;
+
+        let chain = IssuanceChain::build(end_entity, intermediates)?;
+
+        if let Some(pins) = pins
+            && !pins.matches_any(&chain)
+        {
+            return Err(PolicyViolation::PinMismatch);
+        }
+
+        if let Some(certificate_transparency) = certificate_transparency {
+            let found = certificate_transparency
+                .verified_operators(end_entity, chain.spki_hashes.get(1))?;
+            let required = certificate_transparency.min_distinct_operators.get();
+            if found < required {
+                return Err(PolicyViolation::InsufficientScts { found, required });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl CertificateTransparencyPolicy {
+    /// Counts the distinct operators of trusted logs that issued a valid SCT for `end_entity`.
+    ///
+    /// SCTs are signed over the issuer's key hash, so without `issuer_key_hash` none of them can be
+    /// verified.
+    fn verified_operators(
+        &self,
+        end_entity: &[u8],
+        issuer_key_hash: Option<&SpkiSha256>,
+    ) -> Result<usize, PolicyViolation> {
+        let Some(sct_list) = embedded_sct_list(end_entity)? else {
+            return Ok(0);
+        };
+        let Some(issuer_key_hash) = issuer_key_hash else {
+            return Ok(0);
+        };
+        let precert_tbs = precert_tbs_certificate(end_entity)?;
+
+        let logs: Vec<_> = self
+            .logs
+            .iter()
+            .filter_map(|log| {
+                let key = PKey::public_key_from_der(&log.public_key_der).ok()?;
+                Some((log.log_id(), &log.operator, key))
+            })
+            .collect();
+
+        let mut operators = HashSet::new();
+        for sct in sct_list
+            .iter()
+            .filter_map(SignedCertificateTimestamp::parse)
+        {
+            let Some((_, operator, key)) = logs.iter().find(|(log_id, _, _)| *log_id == sct.log_id)
+            else {
+                continue;
+            };
+            if sct.verify(key, issuer_key_hash, &precert_tbs) {
+                operators.insert(operator);
+            }
+        }
+        Ok(operators.len())
+    }
+}
+
+/// The leaf certificate followed by each presented intermediate that signed the one before it.
+///
+/// The verifiers we wrap don't report the path they built, so this reconstructs the part of it the
+/// server sent. Every link is checked by signature, so a certificate can only appear here if its key
+/// really vouched for the leaf. Intermediates that don't link up are ignored, as a path builder
+/// would.
+struct IssuanceChain {
+    /// SPKI hashes of the certificates in the chain, starting with the leaf.
+    spki_hashes: Vec<SpkiSha256>,
+}
+
+impl IssuanceChain {
+    fn build(
+        end_entity: &[u8],
+        intermediates: &[CertificateDer<'_>],
+    ) -> Result<Self, PolicyViolation> {
+        let mut current =
+            X509::from_der(end_entity).map_err(|_| PolicyViolation::MalformedCertificate)?;
+        let mut spki_hashes = vec![x509_spki_sha256(&current)?];
+
+        // Each candidate is used at most once, so this terminates even if the server sends a loop.
+        let mut candidates: Vec<X509> = intermediates
+            .iter()
+            .filter_map(|der| X509::from_der(der).ok())
+            .collect();
+        while let Some(index) = candidates.iter().position(|candidate| {
+            candidate
+                .public_key()
+                .and_then(|key| current.verify(&key))
+                .unwrap_or(false)
+        }) {
+            let issuer = candidates.swap_remove(index);
+            let Ok(spki_hash) = x509_spki_sha256(&issuer) else {
+                break;
+            };
+            spki_hashes.push(spki_hash);
+            current = issuer;
+        }
+
+        Ok(Self { spki_hashes })
+    }
+}
+
+/// Computes the SHA-256 digest of a DER-encoded certificate's SubjectPublicKeyInfo.
+///
+/// This is the form [`SpkiPins`] expects, and matches the "SPKI fingerprint" used by HPKP and
+/// `openssl x509 -pubkey | openssl pkey -pubin -outform der | openssl dgst -sha256`.
+pub fn spki_sha256(certificate_der: &[u8]) -> Result<SpkiSha256, PolicyViolation> {
+    let cert =
+        X509::from_der(certificate_der).map_err(|_| PolicyViolation::MalformedCertificate)?;
+    x509_spki_sha256(&cert)
+}
+
+fn x509_spki_sha256(cert: &X509Ref) -> Result<SpkiSha256, PolicyViolation> {
+    let spki = cert
+        .public_key()
+        .and_then(|key| key.public_key_to_der())
+        .map_err(|_| PolicyViolation::MalformedCertificate)?;
+    Ok(Sha256::digest(spki).into())
+}
+
+/// The embedded SCT list extension from RFC 6962 §3.3.
+const SCT_LIST_EXTENSION_OID: asn1::ObjectIdentifier = asn1::oid!(1, 3, 6, 1, 4, 1, 11129, 2, 4, 2);
+
+/// The serialized SCTs embedded in `cert`, if it has any.
+fn embedded_sct_list(cert: &[u8]) -> Result<Option<SctList<'_>>, PolicyViolation> {
+    let malformed = |_| PolicyViolation::MalformedCertificate;
+    let cert = asn1::parse_single::<Certificate<'_>>(cert).map_err(malformed)?;
+    let Some(extensions) = cert.tbs_certificate.extensions else {
+        return Ok(None);
+    };
+    for extension in extensions {
+        let extension = extension.parse::<Extension<'_>>().map_err(malformed)?;
+        if extension.extn_id == SCT_LIST_EXTENSION_OID {
+            // The extension value is itself a DER OCTET STRING wrapping the TLS-encoded list.
+            let list = asn1::parse_single::<&[u8]>(extension.extn_value).map_err(malformed)?;
+            return SctList::parse(list).map(Some);
+        }
+    }
+    Ok(None)
+}
+
+/// Reconstructs the TBSCertificate of the precertificate that `cert`'s embedded SCTs were issued
+/// for, which is `cert`'s own TBSCertificate without the SCT list extension (RFC 6962 §3.2).
+fn precert_tbs_certificate(cert: &[u8]) -> Result<Vec<u8>, PolicyViolation> {
+    let malformed = |_| PolicyViolation::MalformedCertificate;
+    let TbsCertificate {
+        version,
+        serial_number,
+        signature,
+        issuer,
+        validity,
+        subject,
+        subject_public_key_info,
+        issuer_unique_id,
+        subject_unique_id,
+        extensions,
+    } = asn1::parse_single::<Certificate<'_>>(cert)
+        .map_err(malformed)?
+        .tbs_certificate;
+
+    let mut kept_extensions = vec![];
+    for extension in extensions.into_iter().flatten() {
+        if extension
+            .parse::<Extension<'_>>()
+            .map_err(malformed)?
+            .extn_id
+            != SCT_LIST_EXTENSION_OID
+        {
+            kept_extensions.push(extension);
+        }
+    }
+
+    asn1::write_single(&PrecertTbsCertificate {
+        version,
+        serial_number,
+        signature,
+        issuer,
+        validity,
+        subject,
+        subject_public_key_info,
+        issuer_unique_id,
+        subject_unique_id,
+        extensions: (!kept_extensions.is_empty())
+            .then(|| asn1::SequenceOfWriter::new(kept_extensions)),
+    })
+    .map_err(|_| PolicyViolation::MalformedCertificate)
+}
+
+// Just enough of RFC 5280 to find a certificate's extensions and re-encode its TBSCertificate.
+// Anything we don't look inside is kept as an opaque TLV.
+
+#[derive(asn1::Asn1Read)]
+struct Certificate<'a> {
+    tbs_certificate: TbsCertificate<'a>,
+    _signature_algorithm: asn1::Tlv<'a>,
+    _signature_value: asn1::BitString<'a>,
+}
+
+#[derive(asn1::Asn1Read)]
+struct TbsCertificate<'a> {
+    #[explicit(0)]
+    version: Option<u8>,
+    serial_number: asn1::Tlv<'a>,
+    signature: asn1::Tlv<'a>,
+    issuer: asn1::Tlv<'a>,
+    validity: asn1::Tlv<'a>,
+    subject: asn1::Tlv<'a>,
+    subject_public_key_info: asn1::Tlv<'a>,
+    #[implicit(1)]
+    issuer_unique_id: Option<asn1::BitString<'a>>,
+    #[implicit(2)]
+    subject_unique_id: Option<asn1::BitString<'a>>,
+    #[explicit(3)]
+    extensions: Option<asn1::SequenceOf<'a, asn1::Tlv<'a>>>,
+}
+
+#[derive(asn1::Asn1Write)]
+struct PrecertTbsCertificate<'a> {
+    #[explicit(0)]
+    version: Option<u8>,
+    serial_number: asn1::Tlv<'a>,
+    signature: asn1::Tlv<'a>,
+    issuer: asn1::Tlv<'a>,
+    validity: asn1::Tlv<'a>,
+    subject: asn1::Tlv<'a>,
+    subject_public_key_info: asn1::Tlv<'a>,
+    #[implicit(1)]
+    issuer_unique_id: Option<asn1::BitString<'a>>,
+    #[implicit(2)]
+    subject_unique_id: Option<asn1::BitString<'a>>,
+    #[explicit(3)]
+    extensions: Option<asn1::SequenceOfWriter<'a, asn1::Tlv<'a>, Vec<asn1::Tlv<'a>>>>,
+}
+
+#[derive(asn1::Asn1Read)]
+struct Extension<'a> {
+    extn_id: asn1::ObjectIdentifier,
+    #[default(false)]
+    _critical: bool,
+    extn_value: &'a [u8],
+}
+
+/// The TLS-encoded `SignedCertificateTimestampList` from RFC 6962 §3.3.
+struct SctList<'a>(Vec<&'a [u8]>);
+
+impl<'a> SctList<'a> {
+    fn parse(input: &'a [u8]) -> Result<Self, PolicyViolation> {
+        // struct { opaque sct_list<1..2^16-1>; } where each entry is opaque SerializedSCT<1..2^16-1>.
+        let mut entries = tls_u16_prefixed(input)
+            .filter(|(_, rest)| rest.is_empty())
+            .map(|(list, _)| list)
+            .ok_or(PolicyViolation::MalformedCertificate)?;
+        let mut scts = vec![];
+        while !entries.is_empty() {
+            let (sct, rest) =
+                tls_u16_prefixed(entries).ok_or(PolicyViolation::MalformedCertificate)?;
+            scts.push(sct);
+            entries = rest;
+        }
+        Ok(Self(scts))
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &'a [u8]> + '_ {
+        self.0.iter().copied()
+    }
+}
+
+/// A version 1 SCT (RFC 6962 §3.2).
+struct SignedCertificateTimestamp<'a> {
+    log_id: [u8; 32],
+    timestamp: [u8; 8],
+    extensions: &'a [u8],
+    hash_algorithm: u8,
+    signature: &'a [u8],
+}
+
+impl<'a> SignedCertificateTimestamp<'a> {
+    /// The TLS `HashAlgorithm` value for SHA-256, the only one RFC 6962 permits.
+    const SHA256: u8 = 4;
+
+    /// Parses a serialized SCT, returning `None` if it isn't a well-formed version 1 SCT.
+    ///
+    /// Other versions aren't defined, so we skip them rather than guess at their layout.
+    fn parse(sct: &'a [u8]) -> Option<Self> {
+        let (&[0], sct) = sct.split_first_chunk::<1>()? else {
+            return None;
+        };
+        let (log_id, sct) = sct.split_first_chunk::<32>()?;
+        let (timestamp, sct) = sct.split_first_chunk::<8>()?;
+        let (extensions, sct) = tls_u16_prefixed(sct)?;
+        let (&[hash_algorithm, _signature_algorithm], sct) = sct.split_first_chunk::<2>()?;
+        let (signature, sct) = tls_u16_prefixed(sct)?;
+        sct.is_empty().then_some(Self {
+            log_id: *log_id,
+            timestamp: *timestamp,
+            extensions,
+            hash_algorithm,
+            signature,
+        })
+    }
+
+    /// Checks the SCT's signature over a precertificate entry for `precert_tbs`.
+    fn verify(
+        &self,
+        log_key: &PKeyRef<Public>,
+        issuer_key_hash: &[u8; 32],
+        precert_tbs: &[u8],
+    ) -> bool {
+        if self.hash_algorithm != Self::SHA256 {
+            return false;
+        }
+        let Some(signed) = sct_signed_data(
+            &self.timestamp,
+            issuer_key_hash,
+            precert_tbs,
+            self.extensions,
+        ) else {
+            return false;
+        };
+        Verifier::new(MessageDigest::sha256(), log_key)
+            .and_then(|mut verifier| {
+                verifier.update(&signed)?;
+                verifier.verify(self.signature)
+            })
+            .unwrap_or(false)
+    }
+}
+
+/// The data an SCT for a precertificate entry is a signature over (RFC 6962 §3.2).
+///
+/// Returns `None` if the lengths don't fit in their TLS encodings.
+fn sct_signed_data(
+    timestamp: &[u8; 8],
+    issuer_key_hash: &[u8; 32],
+    precert_tbs: &[u8],
+    extensions: &[u8],
+) -> Option<Vec<u8>> {
+    const SCT_VERSION_V1: u8 = 0;
+    const SIGNATURE_TYPE_CERTIFICATE_TIMESTAMP: u8 = 0;
+    const ENTRY_TYPE_PRECERT: [u8; 2] = [0, 1];
+
+    let tbs_len = u32::try_from(precert_tbs.len())
+        .ok()
+        .filter(|len| *len < 1 << 24)?
+        .to_be_bytes();
+    let extensions_len = u16::try_from(extensions.len()).ok()?.to_be_bytes();
+
+    Some(
+        [
+            &[SCT_VERSION_V1, SIGNATURE_TYPE_CERTIFICATE_TIMESTAMP][..],
+            timestamp,
+            &ENTRY_TYPE_PRECERT,
+            issuer_key_hash,
+            &tbs_len[1..],
+            precert_tbs,
+            &extensions_len,
+            extensions,
+        ]
+        .concat(),
+    )
+}
+
+fn tls_u16_prefixed(input: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (len, rest) = input.split_first_chunk::<2>()?;
+    let len = usize::from(u16::from_be_bytes(*len));
+    (rest.len() >= len).then(|| rest.split_at(len))
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use boring_signal::ec::{EcGroup, EcKey};
+    use boring_signal::nid::Nid;
+    use boring_signal::pkey::Private;
+    use boring_signal::sign::Signer;
+    use nonzero_ext::nonzero;
+
+    use super::*;
+    use crate::tcp_ssl::proxy::testutil::PROXY_CERTIFICATE;
+    use crate::tcp_ssl::testutil::SERVER_CERTIFICATE;
+
+    fn server_cert() -> CertificateDer<'static> {
+        SERVER_CERTIFICATE.cert.der().clone()
+    }
+
+    fn pins_for(primary: &CertificateDer<'_>) -> SpkiPins {
+        SpkiPins::new(spki_sha256(primary).expect("valid"), [[0xAA; 32]]).expect("has backup")
+    }
+
+    /// Issues a leaf certificate from a new CA, returning both.
+    fn leaf_and_issuer() -> (CertificateDer<'static>, CertificateDer<'static>) {
+        let issuer_key = rcgen::KeyPair::generate().expect("can generate");
+        let mut issuer_params = rcgen::CertificateParams::new(Vec::new()).expect("valid");
+        issuer_params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        let issuer_cert = issuer_params
+            .self_signed(&issuer_key)
+            .expect("can sign")
+            .der()
+            .clone();
+        let issuer = rcgen::Issuer::new(issuer_params, issuer_key);
+
+        let leaf_key = rcgen::KeyPair::generate().expect("can generate");
+        let leaf = rcgen::CertificateParams::new(["localhost".to_owned()])
+            .expect("valid")
+            .signed_by(&leaf_key, &issuer)
+            .expect("can sign")
+            .der()
+            .clone();
+        (leaf, issuer_cert)
+    }
+
+    /// A CT log that can issue SCTs in tests.
+    struct TestLog {
+        key: PKey<Private>,
+        log: CtLog,
+    }
+
+    impl TestLog {
+        fn new(operator: &str) -> Self {
+            let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).expect("valid");
+            let key =
+                PKey::from_ec_key(EcKey::generate(&group).expect("can generate")).expect("valid");
+            let log = CtLog {
+                operator: operator.into(),
+                public_key_der: key.public_key_to_der().expect("can encode").into(),
+            };
+            Self { key, log }
+        }
+
+        /// Issues a serialized SCT for the precertificate `precert_tbs`.
+        fn issue_sct(&self, issuer_key_hash: &[u8; 32], precert_tbs: &[u8]) -> Vec<u8> {
+            let timestamp = 1_700_000_000_000u64.to_be_bytes();
+            let signed =
+                sct_signed_data(&timestamp, issuer_key_hash, precert_tbs, &[]).expect("fits");
+            let mut signer = Signer::new(MessageDigest::sha256(), &self.key).expect("valid");
+            signer.update(&signed).expect("can sign");
+            let signature = signer.sign_to_vec().expect("can sign");
+
+            let mut sct = vec![0];
+            sct.extend_from_slice(&self.log.log_id());
+            sct.extend_from_slice(&timestamp);
+            sct.extend_from_slice(&[0, 0]);
+            // SHA-256, ECDSA
+            sct.extend_from_slice(&[SignedCertificateTimestamp::SHA256, 3]);
+            sct.extend_from_slice(&u16::try_from(signature.len()).expect("small").to_be_bytes());
+            sct.extend(signature);
+            sct
+        }
+    }
+
+    /// Builds a self-signed certificate with embedded SCTs.
+    ///
+    /// `issue_scts` is given the issuer key hash and precertificate TBSCertificate to sign over.
+    fn cert_with_scts(
+        issue_scts: impl FnOnce(&[u8; 32], &[u8]) -> Vec<Vec<u8>>,
+    ) -> CertificateDer<'static> {
+        let key = rcgen::KeyPair::generate().expect("can generate");
+        let params = || {
+            let mut params =
+                rcgen::CertificateParams::new(["localhost".to_owned()]).expect("valid");
+            params.serial_number = Some(rcgen::SerialNumber::from_slice(&[1]));
+            params
+        };
+
+        // Everything but the SCT extension is deterministic, so the final certificate's
+        // precertificate TBS will match this one's.
+        let precert = params().self_signed(&key).expect("can sign");
+        let precert_tbs = precert_tbs_certificate(precert.der()).expect("valid");
+        let issuer_key_hash = spki_sha256(precert.der()).expect("valid");
+
+        let mut list = vec![];
+        for sct in issue_scts(&issuer_key_hash, &precert_tbs) {
+            list.extend_from_slice(&u16::try_from(sct.len()).expect("small").to_be_bytes());
+            list.extend(sct);
+        }
+        let mut sct_list = u16::try_from(list.len())
+            .expect("small")
+            .to_be_bytes()
+            .to_vec();
+        sct_list.extend(list);
+
+        let mut params = params();
+        params
+            .custom_extensions
+            .push(rcgen::CustomExtension::from_oid_content(
+                &[1, 3, 6, 1, 4, 1, 11129, 2, 4, 2],
+                asn1::write_single(&sct_list.as_slice()).expect("can encode"),
+            ));
+        let cert = params.self_signed(&key).expect("can sign").der().clone();
+        assert_eq!(
+            precert_tbs_certificate(&cert).expect("valid"),
+            precert_tbs,
+            "only the SCT extension should differ"
+        );
+        cert
+    }
+
+    #[test]
+    fn spki_hash_matches_key() {
+        assert_eq!(
+            spki_sha256(&server_cert()).expect("valid"),
+            <[u8; 32]>::from(Sha256::digest(
+                rcgen::PublicKeyData::subject_public_key_info(&SERVER_CERTIFICATE.signing_key)
+            ))
+        );
+        assert_matches!(
+            spki_sha256(b"not a certificate"),
+            Err(PolicyViolation::MalformedCertificate)
+        );
+    }
+
+    #[test]
+    fn pins_require_a_distinct_backup() {
+        assert_matches!(SpkiPins::new([1; 32], []), Err(Error::MissingBackupPin));
+        assert_matches!(
+            SpkiPins::new([1; 32], [[1; 32]]),
+            Err(Error::MissingBackupPin)
+        );
+        assert_matches!(SpkiPins::new([1; 32], [[1; 32], [2; 32]]), Ok(_));
+    }
+
+    #[test]
+    fn pins_match_the_issuance_chain() {
+        let (leaf, issuer) = leaf_and_issuer();
+        let pinned = |cert| CertificatePolicy {
+            pins: Some(pins_for(cert)),
+            certificate_transparency: None,
+        };
+
+        pinned(&leaf).check(&leaf, &[]).expect("leaf is pinned");
+        pinned(&issuer)
+            .check(&leaf, std::slice::from_ref(&issuer))
+            .expect("issuer is pinned");
+        assert_matches!(
+            pinned(&issuer).check(&leaf, &[]),
+            Err(PolicyViolation::PinMismatch),
+            "issuer not presented"
+        );
+
+        let proxy_cert = PROXY_CERTIFICATE.cert.der().clone();
+        assert_matches!(
+            pinned(&proxy_cert).check(&leaf, &[issuer, proxy_cert.clone()]),
+            Err(PolicyViolation::PinMismatch),
+            "presented, but didn't issue anything in the chain"
+        );
+    }
+
+    #[test]
+    fn certificate_transparency_counts_verified_operators() {
+        let [a1, a2, b1, untrusted] = ["A", "A", "B", "C"].map(TestLog::new);
+        let policy = CertificatePolicy {
+            pins: None,
+            certificate_transparency: Some(CertificateTransparencyPolicy {
+                logs: [&a1, &a2, &b1].map(|log| log.log.clone()).to_vec(),
+                min_distinct_operators: nonzero!(2usize),
+            }),
+        };
+        // The test certificates are self-signed, so each one is its own issuer.
+        let check =
+            |cert: CertificateDer<'static>| policy.check(&cert, std::slice::from_ref(&cert));
+
+        let valid = cert_with_scts(|issuer, tbs| {
+            vec![a1.issue_sct(issuer, tbs), b1.issue_sct(issuer, tbs)]
+        });
+        check(valid.clone()).expect("enough operators");
+        assert_matches!(
+            policy.check(&valid, &[server_cert()]),
+            Err(PolicyViolation::InsufficientScts {
+                found: 0,
+                required: 2
+            }),
+            "issuer key taken from a certificate that didn't issue the leaf"
+        );
+
+        assert_matches!(
+            check(cert_with_scts(|issuer, tbs| {
+                vec![a1.issue_sct(issuer, tbs), a2.issue_sct(issuer, tbs)]
+            })),
+            Err(PolicyViolation::InsufficientScts {
+                found: 1,
+                required: 2
+            }),
+            "two logs from the same operator"
+        );
+        assert_matches!(
+            check(cert_with_scts(|issuer, tbs| {
+                vec![a1.issue_sct(issuer, tbs), untrusted.issue_sct(issuer, tbs)]
+            })),
+            Err(PolicyViolation::InsufficientScts {
+                found: 1,
+                required: 2
+            }),
+            "SCT from an untrusted log"
+        );
+        assert_matches!(
+            check(cert_with_scts(|issuer, tbs| {
+                let mut forged = b1.issue_sct(issuer, tbs);
+                *forged.last_mut().expect("has signature") ^= 1;
+                vec![a1.issue_sct(issuer, tbs), forged]
+            })),
+            Err(PolicyViolation::InsufficientScts {
+                found: 1,
+                required: 2
+            }),
+            "SCT with a bad signature"
+        );
+        assert_matches!(
+            check(cert_with_scts(|_issuer, tbs| {
+                vec![a1.issue_sct(&[0; 32], tbs), b1.issue_sct(&[0; 32], tbs)]
+            })),
+            Err(PolicyViolation::InsufficientScts {
+                found: 0,
+                required: 2
+            }),
+            "SCTs for a different issuer"
+        );
+        assert_matches!(
+            check(server_cert()),
+            Err(PolicyViolation::InsufficientScts {
+                found: 0,
+                required: 2
+            })
+        );
+    }
+
+    #[test]
+    fn certificate_transparency_requires_native_roots() {
+        let policy = CertificatePolicy {
+            pins: None,
+            certificate_transparency: Some(CertificateTransparencyPolicy {
+                logs: vec![],
+                min_distinct_operators: nonzero!(1usize),
+            }),
+        };
+        policy
+            .validate_for(&RootCertificates::Native)
+            .expect("allowed");
+        assert_matches!(
+            policy.validate_for(&RootCertificates::FromDer(server_cert().to_vec().into())),
+            Err(Error::CertificateTransparencyRequiresNativeRoots)
+        );
+    }
+}
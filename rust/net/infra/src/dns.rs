@@ -18,6 +18,7 @@ use crate::dns::custom_resolver::CustomDnsResolver;
 use crate::dns::dns_errors::Error;
 use crate::dns::dns_lookup::{DnsLookup, DnsLookupRequest, StaticDnsMap, SystemDnsLookup};
 use crate::dns::dns_transport_doh::{CLOUDFLARE_IPS, DohTransportConnectorFactory};
+use crate::dns::dns_transport_dot::{DEFAULT_DOT_PORT, DotTransportConnectorFactory};
 use crate::dns::dns_types::ResourceType;
 use crate::dns::dns_utils::log_safe_domain;
 use crate::dns::lookup_result::LookupResult;
@@ -31,13 +32,14 @@ use crate::timeouts::{
 };
 use crate::utils::NetworkChangeEvent;
 use crate::utils::oneshot_broadcast::{self, Receiver};
-use crate::{Alpn, OverrideNagleAlgorithm, utils};
+use crate::{Alpn, DnsSource, OverrideNagleAlgorithm, utils};
 
 pub mod custom_resolver;
 mod dns_errors;
 pub mod dns_lookup;
 mod dns_message;
 pub mod dns_transport_doh;
+pub mod dns_transport_dot;
 pub mod dns_transport_udp;
 mod dns_types;
 pub(crate) mod dns_utils;
@@ -60,6 +62,34 @@ struct DnsResolverState {
     /// Controls if lookup results will contain IPv6 entries.
     ipv6_enabled: bool,
     in_flight_lookups: HashMap<String, Receiver<Result<LookupResult>>>,
+    /// Which sources answered lookups since the last network change.
+    lookup_counts: DnsLookupCounts,
+}
+
+/// The number of successful lookups answered by each kind of [`DnsSource`] the resolver uses.
+///
+/// Sources the resolver never falls back to, like [`DnsSource::UdpLookup`], aren't counted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DnsLookupCounts {
+    pub system: u64,
+    pub doh: u64,
+    pub dot: u64,
+    pub static_fallback: u64,
+}
+
+impl DnsLookupCounts {
+    fn record(&mut self, source: DnsSource) {
+        let count = match source {
+            DnsSource::SystemLookup => &mut self.system,
+            DnsSource::DnsOverHttpsLookup => &mut self.doh,
+            DnsSource::DnsOverTlsLookup => &mut self.dot,
+            DnsSource::Static => &mut self.static_fallback,
+            DnsSource::Cache | DnsSource::UdpLookup | DnsSource::Delegated => return,
+            #[cfg(any(test, feature = "test-util"))]
+            DnsSource::Test => return,
+        };
+        *count += 1;
+    }
 }
 
 impl std::fmt::Debug for DnsResolverState {
@@ -67,6 +97,7 @@ impl std::fmt::Debug for DnsResolverState {
         f.debug_struct("DnsResolverState")
             .field("ipv6_enabled", &self.ipv6_enabled)
             .field("in_flight_lookups", &self.in_flight_lookups.keys())
+            .field("lookup_counts", &self.lookup_counts)
             .finish()
     }
 }
@@ -76,6 +107,7 @@ impl Default for DnsResolverState {
         Self {
             ipv6_enabled: true,
             in_flight_lookups: Default::default(),
+            lookup_counts: Default::default(),
         }
     }
 }
@@ -87,12 +119,27 @@ pub struct DnsResolver {
     known_good_results: Arc<HashMap<&'static str, HashSet<IpAddr>>>,
 }
 
+/// The encrypted DNS service [`DnsResolver`] falls back to when the system
+/// resolver fails.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EncryptedDnsFallback {
+    /// Cloudflare's DNS-over-HTTPS service.
+    #[default]
+    Https,
+    /// Cloudflare's DNS-over-TLS service.
+    ///
+    /// Useful on networks that block DoH endpoints but allow port 853.
+    Tls,
+}
+
 /// A single DNS resolution strategy that can be tried.
 #[derive(Debug)]
 struct LookupOption {
     lookup: Box<dyn DnsLookup>,
     /// How long to wait for the lookup to finish before giving up on it.
     timeout_after: Duration,
+    /// Which [`DnsLookupCounts`] bucket successes are attributed to, if any.
+    source: Option<DnsSource>,
 }
 
 pub fn build_custom_resolver_cloudflare_doh(
@@ -115,6 +162,7 @@ pub fn build_custom_resolver_cloudflare_doh(
                     root_certs: RootCertificates::Native,
                     alpn: Some(Alpn::Http2),
                     min_protocol_version: Some(boring_signal::ssl::SslVersion::TLS1_2),
+                    ech_config: None,
                 },
                 inner: TcpRoute {
                     address: ip_addr,
@@ -132,6 +180,36 @@ pub fn build_custom_resolver_cloudflare_doh(
     )
 }
 
+/// Like [`build_custom_resolver_cloudflare_doh`], but using DNS-over-TLS.
+///
+/// Useful on networks that block DoH endpoints but allow port 853.
+pub fn build_custom_resolver_cloudflare_dot(
+    network_change_event: &NetworkChangeEvent,
+    secondary_request_grace_period: Duration,
+) -> CustomDnsResolver<TlsRoute<TcpRoute<IpAddr>>, DotTransportConnectorFactory> {
+    let (v4, v6) = CLOUDFLARE_IPS;
+    let targets = [IpAddr::V6(v6), IpAddr::V4(v4)].map(|ip_addr| TlsRoute {
+        fragment: TlsRouteFragment {
+            sni: Host::Ip(ip_addr),
+            root_certs: RootCertificates::Native,
+            alpn: None,
+            min_protocol_version: Some(boring_signal::ssl::SslVersion::TLS1_2),
+            ech_config: None,
+        },
+        inner: TcpRoute {
+            address: ip_addr,
+            port: DEFAULT_DOT_PORT,
+            override_nagle_algorithm: OverrideNagleAlgorithm::UseSystemDefault,
+        },
+    });
+    CustomDnsResolver::new(
+        targets.into(),
+        DotTransportConnectorFactory,
+        network_change_event,
+        secondary_request_grace_period,
+    )
+}
+
 impl DnsResolver {
     #[cfg(any(test, feature = "test-util"))]
     pub fn new_custom(lookup_options: Vec<(Box<dyn DnsLookup>, Duration)>) -> Self {
@@ -140,6 +218,7 @@ impl DnsResolver {
             .map(|(lookup, timeout_after)| LookupOption {
                 lookup,
                 timeout_after,
+                source: None,
             })
             .collect();
 
@@ -162,6 +241,7 @@ impl DnsResolver {
             lookup_options: Arc::new([LookupOption {
                 lookup: Box::new(StaticDnsMap(static_map)),
                 timeout_after: Duration::from_millis(1),
+                source: Some(DnsSource::Static),
             }]),
             state: Default::default(),
             known_good_results: Arc::new(HashMap::new()),
@@ -174,10 +254,38 @@ impl DnsResolver {
         static_map: HashMap<&'static str, LookupResult>,
         network_change_event: &NetworkChangeEvent,
     ) -> Self {
-        let cloudflare_doh = Box::new(build_custom_resolver_cloudflare_doh(
+        Self::new_with_encrypted_fallback(
+            static_map,
+            EncryptedDnsFallback::default(),
             network_change_event,
-            DNS_LATER_RESPONSE_GRACE_PERIOD,
-        ));
+        )
+    }
+
+    /// Like [`Self::new_with_static_fallback`], but with a choice of which
+    /// encrypted DNS service to try after the system resolver.
+    pub fn new_with_encrypted_fallback(
+        static_map: HashMap<&'static str, LookupResult>,
+        encrypted_fallback: EncryptedDnsFallback,
+        network_change_event: &NetworkChangeEvent,
+    ) -> Self {
+        let encrypted_lookup = match encrypted_fallback {
+            EncryptedDnsFallback::Https => LookupOption {
+                lookup: Box::new(build_custom_resolver_cloudflare_doh(
+                    network_change_event,
+                    DNS_LATER_RESPONSE_GRACE_PERIOD,
+                )),
+                timeout_after: DOH_FALLBACK_LOOKUP_TIMEOUT,
+                source: Some(DnsSource::DnsOverHttpsLookup),
+            },
+            EncryptedDnsFallback::Tls => LookupOption {
+                lookup: Box::new(build_custom_resolver_cloudflare_dot(
+                    network_change_event,
+                    DNS_LATER_RESPONSE_GRACE_PERIOD,
+                )),
+                timeout_after: DOH_FALLBACK_LOOKUP_TIMEOUT,
+                source: Some(DnsSource::DnsOverTlsLookup),
+            },
+        };
 
         let known_good_results = Arc::new(
             static_map
@@ -191,14 +299,13 @@ impl DnsResolver {
             LookupOption {
                 lookup: Box::new(SystemDnsLookup),
                 timeout_after: DNS_SYSTEM_LOOKUP_TIMEOUT,
+                source: Some(DnsSource::SystemLookup),
             },
-            LookupOption {
-                lookup: cloudflare_doh,
-                timeout_after: DOH_FALLBACK_LOOKUP_TIMEOUT,
-            },
+            encrypted_lookup,
             LookupOption {
                 lookup: Box::new(StaticDnsMap(static_map)),
                 timeout_after: Duration::from_secs(1),
+                source: Some(DnsSource::Static),
             },
         ];
 
@@ -221,6 +328,15 @@ impl DnsResolver {
         for option in &self.lookup_options[..] {
             option.lookup.on_network_change(now);
         }
+        self.state.lock().expect("not poisoned").lookup_counts = Default::default();
+    }
+
+    /// Returns how many lookups each source has answered since the last network change.
+    ///
+    /// Lookups for IP address literals, and lookups that joined one already in flight, aren't
+    /// counted.
+    pub fn lookup_counts(&self) -> DnsLookupCounts {
+        self.state.lock().expect("not poisoned").lookup_counts
     }
 
     pub async fn lookup_ip(&self, hostname: &str) -> Result<LookupResult> {
@@ -234,7 +350,7 @@ impl DnsResolver {
                 std::net::IpAddr::V4(ip) => (vec![ip], vec![]),
                 std::net::IpAddr::V6(ip) => (vec![], vec![ip]),
             };
-            return Ok(LookupResult { ipv4, ipv6 });
+            return Ok(LookupResult::new(ipv4, ipv6));
         }
         match self.start_or_join_lookup(hostname).val().await {
             Ok(r) => r,
@@ -276,13 +392,18 @@ impl DnsResolver {
                 ipv6_enabled,
             };
 
-            let successful_lookups = futures_util::stream::iter(lookup_options.iter())
-                .filter_map(|lookup_option| lookup_option.attempt(request.clone()).map(Result::ok));
+            let successful_lookups =
+                futures_util::stream::iter(lookup_options.iter()).filter_map(|lookup_option| {
+                    lookup_option
+                        .attempt(request.clone())
+                        .map(|result| Some((lookup_option.source, result.ok()?)))
+                });
             let mut perform_lookups = std::pin::pin!(successful_lookups);
 
-            let result = perform_lookups
-                .next()
-                .await
+            let first_success = perform_lookups.next().await;
+            let source = first_success.as_ref().and_then(|(source, _)| *source);
+            let result = first_success
+                .map(|(_source, res)| res)
                 .ok_or(Error::LookupFailed)
                 .and_then(|res| match ipv6_enabled {
                     true => Ok(res),
@@ -320,11 +441,15 @@ impl DnsResolver {
                 }
             }
 
-            state
-                .lock()
-                .expect("not poisoned")
-                .in_flight_lookups
-                .remove(&hostname);
+            {
+                let mut state = state.lock().expect("not poisoned");
+                state.in_flight_lookups.remove(&hostname);
+                if result.is_ok()
+                    && let Some(source) = source
+                {
+                    state.lookup_counts.record(source);
+                }
+            }
             if result_sender.send(result).is_err() {
                 log::debug!("No DNS result listeners left for domain [{log_safe_hostname}]",);
             }
@@ -337,6 +462,7 @@ impl LookupOption {
         let Self {
             lookup,
             timeout_after,
+            source: _,
         } = self;
         let started_at = Instant::now();
         let log_safe_domain = log_safe_domain(&request.hostname).to_string();
@@ -374,6 +500,7 @@ mod test {
     use assert_matches::assert_matches;
     use async_trait::async_trait;
     use const_str::ip_addr;
+    use test_case::test_case;
 
     use super::*;
     use crate::dns::dns_lookup::DnsLookupRequest;
@@ -540,6 +667,73 @@ mod test {
         assert_non_empty!(result.ipv6);
     }
 
+    #[tokio::test(start_paused = true)]
+    async fn test_lookup_counts_by_source() {
+        let dns_resolver = DnsResolver {
+            lookup_options: Arc::new([
+                LookupOption {
+                    lookup: TestLookup::standard_responses(Duration::ZERO),
+                    timeout_after: ATTEMPT_TIMEOUT,
+                    source: Some(DnsSource::SystemLookup),
+                },
+                LookupOption {
+                    lookup: Box::new(StaticDnsMap(HashMap::from([(
+                        FALLBACK_ONLY_DOMAIN,
+                        IPV4.into(),
+                    )]))),
+                    timeout_after: ATTEMPT_TIMEOUT,
+                    source: Some(DnsSource::Static),
+                },
+            ]),
+            state: Default::default(),
+            known_good_results: Default::default(),
+        };
+
+        _ = dns_resolver
+            .lookup_ip(IPV4_ONLY_DOMAIN)
+            .await
+            .expect("success");
+        _ = dns_resolver
+            .lookup_ip(FALLBACK_ONLY_DOMAIN)
+            .await
+            .expect("success");
+        _ = dns_resolver
+            .lookup_ip(CUSTOM_DOMAIN)
+            .await
+            .expect_err("no lookup knows this domain");
+
+        assert_eq!(
+            dns_resolver.lookup_counts(),
+            DnsLookupCounts {
+                system: 1,
+                doh: 0,
+                dot: 0,
+                static_fallback: 1,
+            }
+        );
+
+        dns_resolver.on_network_change(Instant::now());
+        assert_eq!(dns_resolver.lookup_counts(), DnsLookupCounts::default());
+    }
+
+    #[test]
+    fn lookup_counts_distinguish_encrypted_sources() {
+        let mut counts = DnsLookupCounts::default();
+        counts.record(DnsSource::DnsOverHttpsLookup);
+        counts.record(DnsSource::DnsOverTlsLookup);
+        counts.record(DnsSource::DnsOverTlsLookup);
+        counts.record(DnsSource::UdpLookup);
+        assert_eq!(
+            counts,
+            DnsLookupCounts {
+                system: 0,
+                doh: 1,
+                dot: 2,
+                static_fallback: 0,
+            }
+        );
+    }
+
     #[tokio::test(start_paused = true)]
     async fn test_fallback_timing() {
         // We have two resolvers chained together:
@@ -571,6 +765,32 @@ mod test {
         );
     }
 
+    #[test_case(EncryptedDnsFallback::Https, DnsSource::DnsOverHttpsLookup)]
+    #[test_case(EncryptedDnsFallback::Tls, DnsSource::DnsOverTlsLookup)]
+    fn encrypted_fallback_follows_system_lookup(
+        encrypted_fallback: EncryptedDnsFallback,
+        expected: DnsSource,
+    ) {
+        let dns_resolver = DnsResolver::new_with_encrypted_fallback(
+            HashMap::new(),
+            encrypted_fallback,
+            &crate::utils::no_network_change_events(),
+        );
+        let sources = dns_resolver
+            .lookup_options
+            .iter()
+            .map(|option| option.source)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sources,
+            [
+                Some(DnsSource::SystemLookup),
+                Some(expected),
+                Some(DnsSource::Static)
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn test_dns_lookup_ipv6_disabled() {
         let static_dns_map =
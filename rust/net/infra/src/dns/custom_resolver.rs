@@ -12,7 +12,6 @@ use std::net::{Ipv4Addr, Ipv6Addr};
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 
-use either::Either;
 use futures_util::{FutureExt as _, Stream, StreamExt as _};
 use tokio::sync::oneshot;
 use tokio::time::Instant;
@@ -21,7 +20,7 @@ use crate::dns::dns_errors::Error;
 use crate::dns::dns_lookup::DnsLookupRequest;
 use crate::dns::dns_types::Expiring;
 use crate::dns::dns_utils::log_safe_domain;
-use crate::dns::lookup_result::LookupResult;
+use crate::dns::lookup_result::{LookupResult, ServiceBinding};
 use crate::route::{
     ConnectionOutcomeParams, ConnectionOutcomes, ConnectorFactory, InterfaceMonitor, ResolvedRoute,
 };
@@ -35,7 +34,15 @@ use crate::{DnsSource, dns};
 
 pub type DnsIpv4Result = Expiring<Vec<Ipv4Addr>>;
 pub type DnsIpv6Result = Expiring<Vec<Ipv6Addr>>;
-pub type DnsQueryResult = Either<DnsIpv4Result, DnsIpv6Result>;
+pub type DnsHttpsResult = Expiring<Vec<ServiceBinding>>;
+
+/// The result of a single DNS query.
+#[derive(Debug)]
+pub enum DnsQueryResult {
+    Ipv4(DnsIpv4Result),
+    Ipv6(DnsIpv6Result),
+    Https(DnsHttpsResult),
+}
 
 /// Artificially limit DNS lookup results, so we don't get stuck on stale info with a bad TTL field.
 const MAX_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
@@ -51,9 +58,9 @@ pub trait DnsTransport: Debug + Sized + Send {
     ///
     /// The returned stream of results is not guaranteed to produce exactly two elements.
     /// Depending on the context and restrictions, implementations may choose to return
-    /// streams with fewer elements.
+    /// streams with fewer elements, or with a third element for the name's HTTPS record.
     ///
-    /// Each result is a list of either IPv4 or IPv6 records
+    /// Each result is a list of IPv4, IPv6, or HTTPS records
     /// with the order of results not specified.
     fn send_queries(
         self,
@@ -201,7 +208,8 @@ where
         );
         let transport = result.map_err(|_| dns::DnsError::TransportFailure)?;
 
-        let (ipv4_res_rx, ipv6_res_rx) = self.send_dns_queries(transport, request);
+        let hostname = Arc::clone(&request.hostname);
+        let (ipv4_res_rx, ipv6_res_rx, https_res_rx) = self.send_dns_queries(transport, request);
         let (maybe_ipv4, maybe_ipv6) = results_within_interval(
             ipv4_res_rx.map(Result::ok),
             ipv6_res_rx.map(Result::ok),
@@ -210,15 +218,27 @@ where
         .await;
         let ipv4s = maybe_ipv4.map_or(vec![], |r| r.data);
         let ipv6s = maybe_ipv6.map_or(vec![], |r| r.data);
-        match LookupResult::new(ipv4s, ipv6s) {
-            lookup_result if !lookup_result.is_empty() => Ok(lookup_result),
-            _ => Err(Error::LookupFailed),
+        let lookup_result = LookupResult::new(ipv4s, ipv6s);
+        if lookup_result.is_empty() {
+            return Err(Error::LookupFailed);
         }
+
+        // The HTTPS record only carries hints, so it's not worth holding up the connection for
+        // longer than the grace period. If it arrives later, it still makes it into the cache.
+        // If the transport didn't make an HTTPS query, the sender is dropped as soon as the
+        // address results are in.
+        let service_binding = tokio::time::timeout(self.second_response_grace_period, https_res_rx)
+            .await
+            .ok()
+            .and_then(Result::ok)
+            .and_then(|result| preferred_service_binding(&hostname, result));
+        Ok(lookup_result.with_service_binding(service_binding))
     }
 
     /// This method connects to the DNS server using the transport `T`,
-    /// sends DNS queries for both IPv4 and IPv6 records, and then processes
-    /// the responses. It will also take care of caching the results when they are received.
+    /// sends DNS queries for both IPv4 and IPv6 records (and possibly the HTTPS record),
+    /// and then processes the responses. It will also take care of caching the results
+    /// when they are received.
     ///
     /// The method has its own timeout value to wait for the results to arrive.
     /// It doesn't depend on the caller to drive the returned futures.
@@ -229,9 +249,11 @@ where
     ) -> (
         oneshot::Receiver<DnsIpv4Result>,
         oneshot::Receiver<DnsIpv6Result>,
+        oneshot::Receiver<DnsHttpsResult>,
     ) {
         let (ipv4_res_tx, ipv4_res_rx) = oneshot::channel::<DnsIpv4Result>();
         let (ipv6_res_tx, ipv6_res_rx) = oneshot::channel::<DnsIpv6Result>();
+        let (https_res_tx, https_res_rx) = oneshot::channel::<DnsHttpsResult>();
         let cache = self.cache.clone();
         let generation_before_lookup = cache.lock().expect("not poisoned").generation;
         let hostname = request.hostname.clone();
@@ -243,7 +265,7 @@ where
         tokio::spawn(do_lookup_task_body(
             transport,
             request,
-            (ipv4_res_tx, ipv6_res_tx),
+            (ipv4_res_tx, ipv6_res_tx, https_res_tx),
             move |expiring_entry| {
                 let mut guard = cache.lock().expect("not poisoned");
                 // There are two ways the generation could be out of date:
@@ -259,10 +281,21 @@ where
             },
         ));
 
-        (ipv4_res_rx, ipv6_res_rx)
+        (ipv4_res_rx, ipv6_res_rx, https_res_rx)
     }
 }
 
+/// Picks the record with the lowest priority, as the server intends.
+///
+/// Records pointing at some other TargetName are skipped, since we'd have to look that name up too.
+fn preferred_service_binding(hostname: &str, result: DnsHttpsResult) -> Option<ServiceBinding> {
+    result
+        .data
+        .into_iter()
+        .filter(|binding| binding.targets(hostname))
+        .min_by_key(|binding| binding.priority)
+}
+
 impl<R: Debug, T> std::fmt::Debug for CustomDnsResolver<R, T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct(std::any::type_name::<Self>())
@@ -275,9 +308,10 @@ impl<R: Debug, T> std::fmt::Debug for CustomDnsResolver<R, T> {
 async fn do_lookup_task_body<T: DnsTransport>(
     transport: T,
     request: DnsLookupRequest,
-    (ipv4_res_tx, ipv6_res_tx): (
+    (ipv4_res_tx, ipv6_res_tx, https_res_tx): (
         oneshot::Sender<DnsIpv4Result>,
         oneshot::Sender<DnsIpv6Result>,
+        oneshot::Sender<DnsHttpsResult>,
     ),
     try_cache_result: impl FnOnce(Expiring<LookupResult>),
 ) {
@@ -298,20 +332,29 @@ async fn do_lookup_task_body<T: DnsTransport>(
     };
     let mut stream = std::pin::pin!(stream);
 
-    // We're expecting two responses from the DNS server,
+    // We're expecting up to three responses from the DNS server,
     // but they can arrive in any order.
     let mut ipv4_res_tx_opt = Some(ipv4_res_tx);
     let mut ipv6_res_tx_opt = Some(ipv6_res_tx);
+    let mut https_res_tx_opt = Some(https_res_tx);
 
     let mut maybe_ipv4_res = None;
     let mut maybe_ipv6_res = None;
-
-    for _ in 0..2 {
+    let mut maybe_https_res = None;
+
+    // Each of the (up to) three queries produces at most one item, so there is
+    // no need to poll the stream more times than that, even if the transport
+    // misbehaves and keeps yielding items.
+    const MAX_QUERY_RESULTS: usize = 3;
+    for _ in 0..MAX_QUERY_RESULTS {
+        if maybe_ipv4_res.is_some() && maybe_ipv6_res.is_some() && maybe_https_res.is_some() {
+            break;
+        }
         match tokio::select! {
             _ = tokio::time::sleep_until(timeout_at) => None,
             res = stream.next() => res,
         } {
-            Some(Ok(DnsQueryResult::Left(res))) => {
+            Some(Ok(DnsQueryResult::Ipv4(res))) => {
                 maybe_ipv4_res = Some(res.clone());
                 if let Some(p) = ipv4_res_tx_opt.take() {
                     // it is possible that the receiver is dropped,
@@ -324,7 +367,7 @@ async fn do_lookup_task_body<T: DnsTransport>(
                     started_at.elapsed()
                 );
             }
-            Some(Ok(DnsQueryResult::Right(res))) => {
+            Some(Ok(DnsQueryResult::Ipv6(res))) => {
                 maybe_ipv6_res = Some(res.clone());
                 if let Some(p) = ipv6_res_tx_opt.take() {
                     // it is possible that the receiver is dropped,
@@ -337,6 +380,19 @@ async fn do_lookup_task_body<T: DnsTransport>(
                     started_at.elapsed()
                 );
             }
+            Some(Ok(DnsQueryResult::Https(res))) => {
+                maybe_https_res = Some(res.clone());
+                if let Some(p) = https_res_tx_opt.take() {
+                    // it is possible that the receiver is dropped,
+                    // so we're not treating this as an error
+                    let _ = p.send(res);
+                }
+                log::info!(
+                    "Received result of the HTTPS DNS query for [{}] after {:?}",
+                    log_safe_domain(&request.hostname),
+                    started_at.elapsed()
+                );
+            }
             Some(Err(error)) => {
                 log::warn!(
                     "One of DNS queries for [{}] failed with an error after {:?}: {}",
@@ -346,7 +402,7 @@ async fn do_lookup_task_body<T: DnsTransport>(
                 );
             }
             None => {
-                log::warn!(
+                log::debug!(
                     "Stopped waiting for DNS queries results for [{}] after {:?}",
                     log_safe_domain(&request.hostname),
                     started_at.elapsed()
@@ -355,6 +411,8 @@ async fn do_lookup_task_body<T: DnsTransport>(
             }
         };
     }
+    // Let the lookup know not to wait for a result that isn't coming.
+    drop(https_res_tx_opt);
 
     let Some(expiration) = min(
         maybe_ipv4_res.as_ref().map(|e| e.expiration),
@@ -364,11 +422,19 @@ async fn do_lookup_task_body<T: DnsTransport>(
         return;
     };
 
+    // The HTTPS record doesn't get cached on its own; it can only shorten the
+    // lifetime of the address records it accompanies.
+    let expiration = maybe_https_res
+        .as_ref()
+        .map_or(expiration, |e| min(expiration, e.expiration));
+
     // update cache
     let v4 = maybe_ipv4_res.map_or(vec![], |e| e.data);
     let v6 = maybe_ipv6_res.map_or(vec![], |e| e.data);
+    let service_binding =
+        maybe_https_res.and_then(|result| preferred_service_binding(&request.hostname, result));
     let expiring_entry = Expiring {
-        data: LookupResult::new(v4, v6),
+        data: LookupResult::new(v4, v6).with_service_binding(service_binding),
         // Clamp cached TTLs.
         expiration: min(expiration, started_at + MAX_CACHE_TTL),
     };
@@ -391,8 +457,8 @@ pub(crate) mod test {
     use test_case::test_case;
 
     use super::*;
-    use crate::route::Connector;
     use crate::route::testutils::ConnectFn;
+    use crate::route::{Connector, EchConfigList};
     use crate::timeouts::DNS_LATER_RESPONSE_GRACE_PERIOD;
     use crate::utils::{no_network_change_events, sleep_and_catch_up, sleep_until_and_catch_up};
 
@@ -580,19 +646,50 @@ pub(crate) mod test {
     }
 
     fn ok_query_result_ipv4(ttl: Duration, data: &[Ipv4Addr]) -> dns::Result<DnsQueryResult> {
-        Ok(DnsQueryResult::Left(Expiring {
+        Ok(DnsQueryResult::Ipv4(Expiring {
             data: data.to_vec(),
             expiration: Instant::now() + ttl,
         }))
     }
 
     fn ok_query_result_ipv6(ttl: Duration, data: &[Ipv6Addr]) -> dns::Result<DnsQueryResult> {
-        Ok(DnsQueryResult::Right(Expiring {
+        Ok(DnsQueryResult::Ipv6(Expiring {
             data: data.to_vec(),
             expiration: Instant::now() + ttl,
         }))
     }
 
+    fn ok_query_result_https(
+        ttl: Duration,
+        data: &[ServiceBinding],
+    ) -> dns::Result<DnsQueryResult> {
+        Ok(DnsQueryResult::Https(Expiring {
+            data: data.to_vec(),
+            expiration: Instant::now() + ttl,
+        }))
+    }
+
+    fn test_service_bindings() -> [ServiceBinding; 3] {
+        [
+            ServiceBinding {
+                priority: 3,
+                ..Default::default()
+            },
+            ServiceBinding {
+                priority: 2,
+                ech_config_list: Some(EchConfigList::new(b"ech config".as_slice())),
+                ..Default::default()
+            },
+            // Preferred by the server, but describes a host we didn't look up.
+            ServiceBinding {
+                priority: 1,
+                target_name: Some("svc.example.net".into()),
+                ech_config_list: Some(EchConfigList::new(b"other ech config".as_slice())),
+                ..Default::default()
+            },
+        ]
+    }
+
     fn respond_after_timeout(
         timeout: Duration,
         tx: OneshotDnsQueryResultSender,
@@ -700,6 +797,58 @@ pub(crate) mod test {
         assert_lookup_result_content_equal(&result.unwrap(), IP_V4_LIST_1, IP_V6_LIST_1);
     }
 
+    #[tokio::test(start_paused = true)]
+    async fn includes_preferred_https_record() {
+        let (transport, resolver) =
+            TestDnsTransportWithThreeResponses::transport_and_custom_dns_resolver(|_, _, txs| {
+                let [tx_1, tx_2, tx_3] = txs;
+                tx_1.send(ok_query_result_ipv4(NORMAL_TTL, IP_V4_LIST_1))
+                    .unwrap();
+                tx_2.send(ok_query_result_ipv6(NORMAL_TTL, IP_V6_LIST_1))
+                    .unwrap();
+                tx_3.send(ok_query_result_https(NORMAL_TTL, &test_service_bindings()))
+                    .unwrap();
+            });
+        let [_, expected_binding, _] = test_service_bindings();
+
+        let result = resolver.resolve(test_request()).await.expect("success");
+        assert_lookup_result_content_equal(&result, IP_V4_LIST_1, IP_V6_LIST_1);
+        assert_eq!(result.service_binding(), Some(&expected_binding));
+
+        let cached = resolver.resolve(test_request()).await.expect("success");
+        assert_eq!(cached.service_binding(), Some(&expected_binding));
+        assert_eq!(1, transport.queries_count());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn late_https_record_is_only_cached() {
+        let https_delay = DNS_LATER_RESPONSE_GRACE_PERIOD * 3;
+        let resolver = TestDnsTransportWithThreeResponses::custom_dns_resolver(move |_, _, txs| {
+            let [tx_1, tx_2, tx_3] = txs;
+            tx_1.send(ok_query_result_ipv4(NORMAL_TTL, IP_V4_LIST_1))
+                .unwrap();
+            tx_2.send(ok_query_result_ipv6(NORMAL_TTL, IP_V6_LIST_1))
+                .unwrap();
+            respond_after_timeout(
+                https_delay,
+                tx_3,
+                ok_query_result_https(NORMAL_TTL, &test_service_bindings()),
+            );
+        });
+
+        let result = resolver.resolve(test_request()).await.expect("success");
+        assert_eq!(result.service_binding(), None);
+
+        sleep_and_catch_up(https_delay).await;
+        let cached = resolver
+            .cache_get(&test_request().hostname)
+            .expect("cached");
+        assert_matches!(
+            cached.service_binding(),
+            Some(ServiceBinding { priority: 2, .. })
+        );
+    }
+
     #[tokio::test(start_paused = true)]
     async fn returns_second_result_if_first_result_fails() {
         let resolver = TestDnsTransportWithTwoResponses::custom_dns_resolver(|_, _, txs| {
@@ -16,6 +16,8 @@ use tokio::time::Instant;
 
 use crate::dns::ResourceType;
 use crate::dns::dns_types::Expiring;
+use crate::dns::lookup_result::ServiceBinding;
+use crate::route::EchConfigList;
 
 pub(crate) const QCLASS_IN: u16 = 1;
 const POINTER_MASK: u8 = 0xC0;
@@ -27,6 +29,14 @@ pub(crate) const MAX_DNS_NAME_LEN: usize = 255;
 pub(crate) const MAX_DNS_UDP_MESSAGE_LEN: usize = 512;
 
 const MAX_DNS_ANSWERS_TO_PARSE: u16 = 1024;
+
+// SvcParamKeys we understand:
+// https://datatracker.ietf.org/doc/html/rfc9460#section-14.3.2
+const SVC_PARAM_KEY_MANDATORY: u16 = 0;
+const SVC_PARAM_KEY_ALPN: u16 = 1;
+const SVC_PARAM_KEY_NO_DEFAULT_ALPN: u16 = 2;
+const SVC_PARAM_KEY_PORT: u16 = 3;
+const SVC_PARAM_KEY_ECH: u16 = 5;
 // Maximum number of pointer indirections to follow while parsing names.
 // Value is chosen arbitrarily to be sufficiently large in practice yet
 // not cause stack exhaustion due to recursion.
@@ -127,6 +137,115 @@ pub fn parse_aaaa_record(bytes_vec: &[u8]) -> Result<Ipv6Addr> {
     Ok(Ipv6Addr::from(octets))
 }
 
+/// Parses the RDATA of an HTTPS record.
+///
+/// Only ServiceMode records are supported; AliasMode records (priority 0) are rejected, since
+/// following them would require another round of queries.
+///
+/// [RDATA wire format](https://datatracker.ietf.org/doc/html/rfc9460#section-2.2)
+pub fn parse_https_record(bytes: &[u8]) -> Result<ServiceBinding> {
+    fn split_u16(bytes: &[u8]) -> Result<(u16, &[u8])> {
+        let (value, rest) = bytes
+            .split_first_chunk()
+            .ok_or(Error::ProtocolErrorFailedToParseResourceRecord)?;
+        Ok((u16::from_be_bytes(*value), rest))
+    }
+
+    let (priority, rest) = split_u16(bytes)?;
+    if priority == 0 {
+        return Err(Error::ProtocolErrorUnexpectedValue);
+    }
+
+    // The target name is not allowed to use compression, so every label is spelled out.
+    let mut params = rest;
+    let mut target_labels = vec![];
+    loop {
+        let (&label_len, rest) = params
+            .split_first()
+            .ok_or(Error::ProtocolErrorFailedToParseResourceRecord)?;
+        if label_len & POINTER_MASK != 0 {
+            return Err(Error::ProtocolErrorInvalidMessage);
+        }
+        let (label, rest) = rest
+            .split_at_checked(usize::from(label_len))
+            .ok_or(Error::ProtocolErrorFailedToParseResourceRecord)?;
+        params = rest;
+        if label_len == 0 {
+            break;
+        }
+        target_labels.push(String::from_utf8_lossy(label));
+    }
+
+    let mut binding = ServiceBinding {
+        priority,
+        // An empty target name is ".", which stands for the name the record was found under.
+        target_name: (!target_labels.is_empty()).then(|| target_labels.join(".").into()),
+        ..Default::default()
+    };
+    let mut mandatory_keys = vec![];
+    let mut previous_key = None;
+    while !params.is_empty() {
+        let (key, rest) = split_u16(params)?;
+        let (len, rest) = split_u16(rest)?;
+        let (value, rest) = rest
+            .split_at_checked(len.into())
+            .ok_or(Error::ProtocolErrorFailedToParseResourceRecord)?;
+        params = rest;
+
+        // Keys are required to be in strictly increasing order.
+        if previous_key.is_some_and(|previous| previous >= key) {
+            return Err(Error::ProtocolErrorFailedToParseResourceRecord);
+        }
+        previous_key = Some(key);
+
+        match key {
+            SVC_PARAM_KEY_MANDATORY => {
+                mandatory_keys = value
+                    .chunks(2)
+                    .map(|key| split_u16(key).map(|(key, _)| key))
+                    .collect::<Result<_>>()?;
+            }
+            SVC_PARAM_KEY_ALPN => {
+                let mut ids = value;
+                while let Some((&len, rest)) = ids.split_first() {
+                    let (id, rest) = rest
+                        .split_at_checked(len.into())
+                        .filter(|(id, _)| !id.is_empty())
+                        .ok_or(Error::ProtocolErrorFailedToParseResourceRecord)?;
+                    binding.alpn_ids.push(id.into());
+                    ids = rest;
+                }
+            }
+            SVC_PARAM_KEY_NO_DEFAULT_ALPN => binding.no_default_alpn = true,
+            SVC_PARAM_KEY_PORT => {
+                let port = value
+                    .try_into()
+                    .map_err(|_| Error::ProtocolErrorFailedToParseResourceRecord)?;
+                binding.port = Some(u16::from_be_bytes(port));
+            }
+            SVC_PARAM_KEY_ECH => binding.ech_config_list = Some(EchConfigList::new(value)),
+            _ => {}
+        }
+    }
+
+    // A client that doesn't understand one of the mandatory keys must ignore the record.
+    // https://datatracker.ietf.org/doc/html/rfc9460#section-8
+    if let Some(key) = mandatory_keys.iter().find(|key| {
+        ![
+            SVC_PARAM_KEY_ALPN,
+            SVC_PARAM_KEY_NO_DEFAULT_ALPN,
+            SVC_PARAM_KEY_PORT,
+            SVC_PARAM_KEY_ECH,
+        ]
+        .contains(key)
+    }) {
+        log::debug!("ignoring HTTPS record with unsupported mandatory key {key}");
+        return Err(Error::ProtocolErrorUnexpectedValue);
+    }
+
+    Ok(binding)
+}
+
 pub fn parse_response<T>(
     message: &[u8],
     expected_type: ResourceType,
@@ -295,7 +414,8 @@ mod test {
     use assert_matches::assert_matches;
     use const_str::{concat_bytes, ip_addr};
     use hickory_proto::op::{Message, MessageType, OpCode, ResponseCode};
-    use hickory_proto::rr::rdata::{A, CNAME};
+    use hickory_proto::rr::rdata::svcb::{self, SVCB, SvcParamKey, SvcParamValue};
+    use hickory_proto::rr::rdata::{A, CNAME, HTTPS};
     use hickory_proto::rr::{Name, RecordType};
     use hickory_proto::serialize::binary::BinEncodable;
     use itertools::Itertools;
@@ -565,6 +685,151 @@ mod test {
         assert_eq!(&[EXPECTED_IP], response.data.as_slice());
     }
 
+    fn https_record(priority: u16, params: Vec<(SvcParamKey, SvcParamValue)>) -> HTTPS {
+        HTTPS(SVCB::new(priority, Name::root(), params))
+    }
+
+    #[test]
+    fn https_record_parsed_correctly() {
+        const ECH_CONFIG: &[u8] = b"not really an ECHConfigList";
+        let name = Name::from_str(VALID_DOMAIN).expect("valid name");
+        let response_message = response_bytes(RecordType::HTTPS, |message| {
+            let record = https_record(
+                1,
+                vec![
+                    (
+                        SvcParamKey::Alpn,
+                        SvcParamValue::Alpn(svcb::Alpn(vec!["h2".into(), "http/1.1".into()])),
+                    ),
+                    (SvcParamKey::Port, SvcParamValue::Port(443)),
+                    (
+                        SvcParamKey::EchConfigList,
+                        SvcParamValue::EchConfigList(svcb::EchConfigList(ECH_CONFIG.to_vec())),
+                    ),
+                ],
+            );
+            let rr = hickory_proto::rr::Record::from_rdata(name.clone(), 100, record);
+            message.add_answer(rr.into_record_of_rdata());
+        });
+
+        let response = parse_response(
+            response_message.as_slice(),
+            ResourceType::HTTPS,
+            parse_https_record,
+        )
+        .expect("parsed result");
+
+        assert_eq!(
+            response.data,
+            [ServiceBinding {
+                priority: 1,
+                target_name: None,
+                port: Some(443),
+                alpn_ids: vec![b"h2".as_slice().into(), b"http/1.1".as_slice().into()],
+                no_default_alpn: false,
+                ech_config_list: Some(EchConfigList::new(ECH_CONFIG)),
+            }]
+        );
+    }
+
+    #[test]
+    fn https_record_target_name_parsed() {
+        let name = Name::from_str(VALID_DOMAIN).expect("valid name");
+        let response_message = response_bytes(RecordType::HTTPS, |message| {
+            let record = HTTPS(SVCB::new(
+                1,
+                Name::from_str("svc.signal.org.").expect("valid name"),
+                vec![],
+            ));
+            let rr = hickory_proto::rr::Record::from_rdata(name.clone(), 100, record);
+            message.add_answer(rr.into_record_of_rdata());
+        });
+
+        let response = parse_response(
+            response_message.as_slice(),
+            ResourceType::HTTPS,
+            parse_https_record,
+        )
+        .expect("parsed result");
+        assert_matches!(
+            response.data.as_slice(),
+            [ServiceBinding { target_name: Some(target), .. }] if &**target == "svc.signal.org"
+        );
+    }
+
+    #[test]
+    fn https_record_in_alias_mode_is_skipped() {
+        let name = Name::from_str(VALID_DOMAIN).expect("valid name");
+        let response_message = response_bytes(RecordType::HTTPS, |message| {
+            let alias = HTTPS(SVCB::new(
+                0,
+                Name::from_str("alias.signal.org").expect("valid name"),
+                vec![],
+            ));
+            let rr = hickory_proto::rr::Record::from_rdata(name.clone(), 100, alias);
+            message.add_answer(rr.into_record_of_rdata());
+        });
+
+        let response = parse_response(
+            response_message.as_slice(),
+            ResourceType::HTTPS,
+            parse_https_record,
+        )
+        .expect("parsed result");
+        assert!(response.data.is_empty());
+    }
+
+    #[test]
+    fn https_record_with_unsupported_mandatory_key_is_skipped() {
+        let name = Name::from_str(VALID_DOMAIN).expect("valid name");
+        let response_message = response_bytes(RecordType::HTTPS, |message| {
+            for (priority, mandatory) in
+                [(1, SvcParamKey::Ipv4Hint), (2, SvcParamKey::EchConfigList)]
+            {
+                let record = https_record(
+                    priority,
+                    vec![
+                        (
+                            SvcParamKey::Mandatory,
+                            SvcParamValue::Mandatory(svcb::Mandatory(vec![mandatory])),
+                        ),
+                        (
+                            SvcParamKey::Ipv4Hint,
+                            SvcParamValue::Ipv4Hint(svcb::IpHint(vec![A::new(192, 0, 2, 1)])),
+                        ),
+                        (
+                            SvcParamKey::EchConfigList,
+                            SvcParamValue::EchConfigList(svcb::EchConfigList(vec![1, 2, 3])),
+                        ),
+                    ],
+                );
+                let rr = hickory_proto::rr::Record::from_rdata(name.clone(), 100, record);
+                message.add_answer(rr.into_record_of_rdata());
+            }
+        });
+
+        let response = parse_response(
+            response_message.as_slice(),
+            ResourceType::HTTPS,
+            parse_https_record,
+        )
+        .expect("parsed result");
+        assert_matches!(
+            response.data.as_slice(),
+            [ServiceBinding { priority: 2, .. }]
+        );
+    }
+
+    #[test]
+    fn https_record_with_truncated_params_is_rejected() {
+        // priority 1, root target name, then an ALPN param claiming more bytes than are present
+        let rdata = [0, 1, 0, 0, 1, 0, 5, 2, b'h', b'2'];
+        assert_matches!(
+            parse_https_record(&rdata),
+            Err(Error::ProtocolErrorFailedToParseResourceRecord)
+        );
+    }
+
     fn make_dns_pointer(offset: u16) -> [u8; 2] {
         // DNS pointer: top 2 bits set, remaining 14 bits are the offset.
         [POINTER_MASK | ((offset >> 8) as u8), (offset & 0xFF) as u8]
@@ -17,7 +17,7 @@ use crate::dns::custom_resolver::{DnsQueryResult, DnsTransport};
 use crate::dns::dns_errors::Error;
 use crate::dns::dns_lookup::DnsLookupRequest;
 use crate::dns::dns_message;
-use crate::dns::dns_message::{parse_a_record, parse_aaaa_record};
+use crate::dns::dns_message::{parse_a_record, parse_aaaa_record, parse_https_record};
 use crate::dns::dns_types::ResourceType;
 use crate::errors::TransportConnectError;
 use crate::http_client::{AggregatingHttp2Client, Http2Connector, HttpConnectError};
@@ -114,7 +114,11 @@ impl DnsTransport for DohTransport {
                     .send_request(request.clone(), ResourceType::AAAA)
             })
             .into_iter()
-            .chain([self.send_request(request, ResourceType::A)]);
+            .chain([
+                self.clone()
+                    .send_request(request.clone(), ResourceType::HTTPS),
+                self.send_request(request, ResourceType::A),
+            ]);
         Ok(FuturesUnordered::from_iter(futures))
     }
 }
@@ -152,16 +156,21 @@ impl DohTransport {
             return Err(Error::DohRequestBadStatus(response_parts.status.as_u16()));
         }
         let result = match resource_type {
-            ResourceType::A => DnsQueryResult::Left(dns_message::parse_response(
+            ResourceType::A => DnsQueryResult::Ipv4(dns_message::parse_response(
                 &response_body,
                 ResourceType::A,
                 parse_a_record,
             )?),
-            ResourceType::AAAA => DnsQueryResult::Right(dns_message::parse_response(
+            ResourceType::AAAA => DnsQueryResult::Ipv6(dns_message::parse_response(
                 &response_body,
                 ResourceType::AAAA,
                 parse_aaaa_record,
             )?),
+            ResourceType::HTTPS => DnsQueryResult::Https(dns_message::parse_response(
+                &response_body,
+                ResourceType::HTTPS,
+                parse_https_record,
+            )?),
         };
         Ok(result)
     }
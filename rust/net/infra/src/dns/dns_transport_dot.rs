@@ -0,0 +1,427 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::net::IpAddr;
+use std::num::NonZeroU16;
+
+use futures_util::{Stream, stream};
+use nonzero_ext::nonzero;
+use tokio::io::{AsyncRead, AsyncReadExt as _, AsyncWriteExt as _};
+use tokio::net::TcpStream;
+
+use crate::dns::custom_resolver::{DnsQueryResult, DnsTransport};
+use crate::dns::dns_errors::Error;
+use crate::dns::dns_lookup::DnsLookupRequest;
+use crate::dns::dns_message;
+use crate::dns::dns_message::{parse_a_record, parse_aaaa_record, parse_https_record};
+use crate::dns::dns_types::ResourceType;
+use crate::errors::TransportConnectError;
+use crate::route::{
+    Connector, ConnectorExt as _, ConnectorFactory, TcpRoute, ThrottledConnection,
+    ThrottlingConnector, TlsRoute, VariableTlsTimeoutConnector,
+};
+use crate::timeouts::MIN_TLS_HANDSHAKE_TIMEOUT;
+use crate::{DnsSource, dns};
+
+/// The well-known port for DNS-over-TLS.
+///
+/// <https://datatracker.ietf.org/doc/html/rfc7858#section-3.1>
+pub const DEFAULT_DOT_PORT: NonZeroU16 = nonzero!(853u16);
+
+const A_REQUEST_ID: u16 = 0;
+const AAAA_REQUEST_ID: u16 = 1;
+const HTTPS_REQUEST_ID: u16 = 2;
+
+pub struct DotTransportConnectorFactory;
+
+impl ConnectorFactory<TlsRoute<TcpRoute<IpAddr>>> for DotTransportConnectorFactory {
+    type Connector = DotTransportConnector;
+    type Connection = DotTransport;
+
+    fn make(&self) -> Self::Connector {
+        Default::default()
+    }
+}
+
+pub struct DotTransportConnector {
+    transport_connector: VariableTlsTimeoutConnector<
+        ThrottlingConnector<crate::tcp_ssl::StatelessTls>,
+        crate::tcp_ssl::StatelessTcp,
+        TransportConnectError,
+    >,
+}
+
+impl Default for DotTransportConnector {
+    fn default() -> Self {
+        Self {
+            transport_connector: VariableTlsTimeoutConnector::new(
+                ThrottlingConnector::new(crate::tcp_ssl::StatelessTls, 1),
+                crate::tcp_ssl::StatelessTcp,
+                MIN_TLS_HANDSHAKE_TIMEOUT,
+            ),
+        }
+    }
+}
+
+impl Connector<TlsRoute<TcpRoute<IpAddr>>, ()> for DotTransportConnector {
+    type Connection = DotTransport;
+    type Error = Error;
+
+    async fn connect_over(
+        &self,
+        _over: (),
+        route: TlsRoute<TcpRoute<IpAddr>>,
+        log_tag: &str,
+    ) -> Result<Self::Connection, Self::Error> {
+        let stream = self
+            .transport_connector
+            .connect(route, log_tag)
+            .await
+            .map_err(|e| {
+                log::warn!("[{log_tag}] Failed to connect to DNS-over-TLS server: {e}");
+                Error::TransportFailure
+            })?;
+        Ok(DotTransport { stream })
+    }
+}
+
+/// DNS transport that sends queries over a TLS stream
+///
+/// Uses the framing from [RFC 7858](https://datatracker.ietf.org/doc/html/rfc7858), which is the
+/// same as for DNS over TCP: each message is prefixed by its length as a two-byte big-endian
+/// integer.
+pub struct DotTransport {
+    stream: ThrottledConnection<tokio_boring_signal::SslStream<TcpStream>>,
+}
+
+impl std::fmt::Debug for DotTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DotTransport").finish_non_exhaustive()
+    }
+}
+
+impl DnsTransport for DotTransport {
+    const SOURCE: DnsSource = DnsSource::DnsOverTlsLookup;
+
+    async fn send_queries(
+        self,
+        request: DnsLookupRequest,
+    ) -> dns::Result<impl Stream<Item = dns::Result<DnsQueryResult>> + Send + 'static> {
+        let Self { mut stream } = self;
+
+        // All queries are written up front; the server is allowed to answer them in any order.
+        let mut queries = vec![];
+        let mut expected_responses = 0;
+        let resource_types = [
+            (AAAA_REQUEST_ID, ResourceType::AAAA),
+            (HTTPS_REQUEST_ID, ResourceType::HTTPS),
+            (A_REQUEST_ID, ResourceType::A),
+        ];
+        for (request_id, resource_type) in resource_types {
+            if matches!(resource_type, ResourceType::AAAA) && !request.ipv6_enabled {
+                continue;
+            }
+            write_framed_request(&mut queries, &request.hostname, request_id, resource_type)?;
+            expected_responses += 1;
+        }
+
+        stream.write_all(&queries).await?;
+        stream.flush().await?;
+
+        Ok(stream::unfold(
+            (stream, expected_responses),
+            |(mut stream, remaining)| async move {
+                if remaining == 0 {
+                    return None;
+                }
+                let (result, remaining) = match read_framed_response(&mut stream).await {
+                    Ok(message) => (parse_query_result(&message), remaining - 1),
+                    // There's no way to resynchronize after a read error, so give up on the rest.
+                    Err(e) => (Err(e), 0),
+                };
+                Some((result, (stream, remaining)))
+            },
+        ))
+    }
+}
+
+fn write_framed_request(
+    output: &mut Vec<u8>,
+    hostname: &str,
+    request_id: u16,
+    resource_type: ResourceType,
+) -> dns::Result<()> {
+    let request = dns_message::create_request_with_id(request_id, hostname, resource_type)?;
+    let len = u16::try_from(request.len()).map_err(|_| Error::MessageTooLong)?;
+    output.extend_from_slice(&len.to_be_bytes());
+    output.extend_from_slice(&request);
+    Ok(())
+}
+
+async fn read_framed_response(stream: &mut (impl AsyncRead + Unpin)) -> dns::Result<Vec<u8>> {
+    let len = stream.read_u16().await?;
+    let mut message = vec![0; len.into()];
+    stream.read_exact(&mut message).await?;
+    Ok(message)
+}
+
+fn parse_query_result(message: &[u8]) -> dns::Result<DnsQueryResult> {
+    let result = match dns_message::get_id(message)? {
+        A_REQUEST_ID => DnsQueryResult::Ipv4(dns_message::parse_response(
+            message,
+            ResourceType::A,
+            parse_a_record,
+        )?),
+        AAAA_REQUEST_ID => DnsQueryResult::Ipv6(dns_message::parse_response(
+            message,
+            ResourceType::AAAA,
+            parse_aaaa_record,
+        )?),
+        HTTPS_REQUEST_ID => DnsQueryResult::Https(dns_message::parse_response(
+            message,
+            ResourceType::HTTPS,
+            parse_https_record,
+        )?),
+        _ => Err(Error::UnexpectedMessageId)?,
+    };
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+    use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+    use std::time::Duration;
+
+    use assert_matches::assert_matches;
+    use const_str::ip_addr;
+    use hickory_proto::op::{Message, MessageType};
+    use hickory_proto::rr::rdata::svcb::{self, SVCB, SvcParamKey, SvcParamValue};
+    use hickory_proto::rr::rdata::{A, AAAA, HTTPS};
+    use hickory_proto::rr::{Name, RData, Record, RecordType};
+    use hickory_proto::serialize::binary::{BinDecodable as _, BinEncodable as _};
+    use itertools::Itertools as _;
+
+    use super::*;
+    use crate::OverrideNagleAlgorithm;
+    use crate::certs::RootCertificates;
+    use crate::dns::DnsResolver;
+    use crate::dns::custom_resolver::CustomDnsResolver;
+    use crate::host::Host;
+    use crate::tcp_ssl::proxy::testutil::PROXY_CERTIFICATE;
+    use crate::tcp_ssl::testutil::{SERVER_CERTIFICATE, SERVER_HOSTNAME};
+    use crate::utils::no_network_change_events;
+
+    const IPV4: Ipv4Addr = ip_addr!(v4, "192.0.2.10");
+    const IPV6: Ipv6Addr = ip_addr!(v6, "3fff::10");
+    const ECH_CONFIG: &[u8] = b"ech config";
+
+    /// Starts a DNS-over-TLS server on `::1` that answers every A and AAAA query with [`IPV4`] and
+    /// [`IPV6`], respectively, and every HTTPS query with a record containing [`ECH_CONFIG`].
+    ///
+    /// Responses to each connection are sent in reverse order, to make sure the client doesn't
+    /// depend on the order of responses. The returned future runs the server.
+    fn localhost_dot_server() -> (SocketAddr, impl Future<Output = ()>) {
+        let listener =
+            std::net::TcpListener::bind((Ipv6Addr::LOCALHOST, 0)).expect("can bind to localhost");
+        listener
+            .set_nonblocking(true)
+            .expect("can make nonblocking");
+        let addr = listener.local_addr().expect("successful bind");
+
+        let server = async move {
+            let listener =
+                tokio::net::TcpListener::from_std(listener).expect("can convert to tokio");
+            let private_key = boring_signal::pkey::PKey::private_key_from_der(
+                SERVER_CERTIFICATE.signing_key.serialized_der(),
+            )
+            .expect("valid key");
+            let cert = boring_signal::x509::X509::from_der(SERVER_CERTIFICATE.cert.der())
+                .expect("valid certificate");
+
+            loop {
+                let (stream, _addr) = listener
+                    .accept()
+                    .await
+                    .expect("can accept an incoming connection");
+                let mut tls_acceptor = boring_signal::ssl::SslAcceptor::mozilla_modern(
+                    boring_signal::ssl::SslMethod::tls(),
+                )
+                .expect("can build");
+                tls_acceptor
+                    .set_private_key(&private_key)
+                    .expect("valid key");
+                tls_acceptor
+                    .set_certificate(&cert)
+                    .expect("valid certificate");
+                let mut stream =
+                    match tokio_boring_signal::accept(&tls_acceptor.build(), stream).await {
+                        Ok(stream) => stream,
+                        Err(e) => {
+                            log::error!("[server] TLS handshake failed: {e}");
+                            continue;
+                        }
+                    };
+
+                // The client writes all of its queries at once, so once there's a pause, answer
+                // everything received so far.
+                let mut responses = vec![];
+                loop {
+                    let next_len = if responses.is_empty() {
+                        Ok(stream.read_u16().await)
+                    } else {
+                        tokio::time::timeout(Duration::from_millis(100), stream.read_u16()).await
+                    };
+                    match next_len {
+                        Ok(Ok(len)) => {
+                            let mut request = vec![0; len.into()];
+                            stream.read_exact(&mut request).await.expect("full message");
+                            responses.push(answer(&request));
+                        }
+                        Ok(Err(_closed)) => break,
+                        Err(_timed_out) => {
+                            for response in responses.drain(..).rev() {
+                                let len = u16::try_from(response.len()).expect("short response");
+                                stream.write_u16(len).await.expect("can write");
+                                stream.write_all(&response).await.expect("can write");
+                            }
+                            stream.flush().await.expect("can flush");
+                        }
+                    }
+                }
+            }
+        };
+        (addr, server)
+    }
+
+    fn answer(request: &[u8]) -> Vec<u8> {
+        let request = Message::from_bytes(request).expect("valid request");
+        let query = request.queries.first().expect("has a query").clone();
+        let mut response = Message::new(
+            request.metadata.id,
+            MessageType::Response,
+            request.metadata.op_code,
+        );
+        let rdata = match query.query_type() {
+            RecordType::A => RData::A(A::from(IPV4)),
+            RecordType::AAAA => RData::AAAA(AAAA::from(IPV6)),
+            RecordType::HTTPS => RData::HTTPS(HTTPS(SVCB::new(
+                1,
+                Name::root(),
+                vec![(
+                    SvcParamKey::EchConfigList,
+                    SvcParamValue::EchConfigList(svcb::EchConfigList(ECH_CONFIG.to_vec())),
+                )],
+            ))),
+            other => panic!("unexpected query type {other}"),
+        };
+        response.add_answer(Record::from_rdata(query.name().clone(), 300, rdata));
+        response.add_query(query);
+        response.to_bytes().expect("can encode")
+    }
+
+    fn resolver_for(
+        addr: SocketAddr,
+        root_cert: &'static [u8],
+    ) -> CustomDnsResolver<TlsRoute<TcpRoute<IpAddr>>, DotTransportConnectorFactory> {
+        CustomDnsResolver::new(
+            vec![TlsRoute {
+                fragment: crate::route::TlsRouteFragment {
+                    root_certs: RootCertificates::FromDer(Cow::Borrowed(root_cert)),
+                    sni: Host::Domain(SERVER_HOSTNAME.into()),
+                    alpn: None,
+                    min_protocol_version: None,
+                    ech_config: None,
+                },
+                inner: TcpRoute {
+                    address: addr.ip(),
+                    port: NonZeroU16::new(addr.port())
+                        .expect("successful listener has a valid port"),
+                    override_nagle_algorithm: OverrideNagleAlgorithm::UseSystemDefault,
+                },
+            }],
+            DotTransportConnectorFactory,
+            &no_network_change_events(),
+            Duration::MAX,
+        )
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn lookup_over_tls() {
+        let (addr, server) = localhost_dot_server();
+        tokio::spawn(server);
+
+        let resolver = resolver_for(addr, SERVER_CERTIFICATE.cert.der());
+        let result = resolver
+            .resolve(DnsLookupRequest {
+                hostname: "chat.signal.org".into(),
+                ipv6_enabled: true,
+            })
+            .await
+            .expect("can look up");
+        assert_eq!(
+            result
+                .service_binding()
+                .and_then(|binding| binding.ech_config_list.as_ref())
+                .map(|ech| ech.as_bytes()),
+            Some(ECH_CONFIG)
+        );
+        assert_eq!(
+            result.into_iter().sorted().collect_vec(),
+            [IpAddr::V4(IPV4), IpAddr::V6(IPV6)]
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn lookup_ipv4_only() {
+        let (addr, server) = localhost_dot_server();
+        tokio::spawn(server);
+
+        let resolver = resolver_for(addr, SERVER_CERTIFICATE.cert.der());
+        let result = resolver
+            .resolve(DnsLookupRequest {
+                hostname: "chat.signal.org".into(),
+                ipv6_enabled: false,
+            })
+            .await
+            .expect("can look up");
+        assert_eq!(result.into_iter().collect_vec(), [IpAddr::V4(IPV4)]);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn rejects_untrusted_certificate() {
+        let (addr, server) = localhost_dot_server();
+        tokio::spawn(server);
+
+        let resolver = resolver_for(addr, PROXY_CERTIFICATE.cert.der());
+        let result = resolver
+            .resolve(DnsLookupRequest {
+                hostname: "chat.signal.org".into(),
+                ipv6_enabled: true,
+            })
+            .await;
+        assert_matches!(result, Err(Error::TransportFailure));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn selectable_in_dns_resolver() {
+        let (addr, server) = localhost_dot_server();
+        tokio::spawn(server);
+
+        let resolver = DnsResolver::new_custom(vec![(
+            Box::new(resolver_for(addr, SERVER_CERTIFICATE.cert.der())),
+            Duration::from_secs(5),
+        )]);
+        let result = resolver
+            .lookup_ip("chat.signal.org")
+            .await
+            .expect("can look up");
+        assert_eq!(
+            result.into_iter().sorted().collect_vec(),
+            [IpAddr::V4(IPV4), IpAddr::V6(IPV6)]
+        );
+        assert_eq!(DotTransport::SOURCE, DnsSource::DnsOverTlsLookup);
+    }
+}
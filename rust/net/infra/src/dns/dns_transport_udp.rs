@@ -100,12 +100,12 @@ impl UdpTransport {
         let bytes_received = self.socket.recv(&mut buf).await?;
         let message = &buf[..bytes_received];
         let result = match dns_message::get_id(message)? {
-            A_REQUEST_ID => DnsQueryResult::Left(dns_message::parse_response(
+            A_REQUEST_ID => DnsQueryResult::Ipv4(dns_message::parse_response(
                 message,
                 ResourceType::A,
                 parse_a_record,
             )?),
-            AAAA_REQUEST_ID => DnsQueryResult::Right(dns_message::parse_response(
+            AAAA_REQUEST_ID => DnsQueryResult::Ipv6(dns_message::parse_response(
                 message,
                 ResourceType::AAAA,
                 parse_aaaa_record,
@@ -17,6 +17,7 @@ pub struct Expiring<T> {
 /// Values for the variants are assigned based on the Resource Record type values
 /// from [RFC1035](https://datatracker.ietf.org/doc/html/rfc1035#section-3.2.2)
 /// and [RFC3596](https://datatracker.ietf.org/doc/html/rfc3596#section-2.1)
+/// and [RFC9460](https://datatracker.ietf.org/doc/html/rfc9460#section-14.1)
 #[repr(u16)]
 #[derive(Clone, Copy)]
 #[expect(clippy::upper_case_acronyms)]
@@ -29,4 +30,8 @@ pub enum ResourceType {
     ///
     /// <https://datatracker.ietf.org/doc/html/rfc3596#section-2.1>
     AAAA = 28,
+    /// Service binding parameters for HTTPS endpoints
+    ///
+    /// <https://datatracker.ietf.org/doc/html/rfc9460#section-9>
+    HTTPS = 65,
 }
@@ -8,10 +8,67 @@ use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::slice::Iter;
 use std::vec::IntoIter;
 
+use crate::Alpn;
+use crate::route::EchConfigList;
+
 #[derive(Debug, Clone)]
 pub struct LookupResult {
     pub(crate) ipv4: Vec<Ipv4Addr>,
     pub(crate) ipv6: Vec<Ipv6Addr>,
+    pub(crate) service_binding: Option<ServiceBinding>,
+}
+
+/// Connection hints published for a name in a DNS HTTPS record.
+///
+/// Only the [RFC 9460](https://datatracker.ietf.org/doc/html/rfc9460) parameters that affect
+/// how we connect are kept.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ServiceBinding {
+    /// The SvcPriority of the record; when there are several, the lowest one is used.
+    pub(crate) priority: u16,
+    /// The TargetName of the record, or `None` if it was `"."`, meaning the record's owner name.
+    pub target_name: Option<Box<str>>,
+    /// The `port` parameter, if present.
+    pub port: Option<u16>,
+    /// Protocol identifiers from the `alpn` parameter, in the server's order of preference.
+    pub alpn_ids: Vec<Box<[u8]>>,
+    /// Whether the `no-default-alpn` parameter was present.
+    pub no_default_alpn: bool,
+    /// The `ech` parameter, if present.
+    pub ech_config_list: Option<EchConfigList>,
+}
+
+/// The port an HTTPS record describes when it doesn't have a `port` parameter.
+const DEFAULT_HTTPS_PORT: u16 = 443;
+
+impl ServiceBinding {
+    /// Returns whether this record, found by looking up `hostname`, describes the endpoint at
+    /// `hostname` and `port`.
+    ///
+    /// A record with some other TargetName describes a server whose addresses we didn't look up,
+    /// and one for a different port describes a different endpoint; neither one's hints can be
+    /// used, as per [RFC 9460](https://datatracker.ietf.org/doc/html/rfc9460#section-2.4.2).
+    pub fn applies_to(&self, hostname: &str, port: u16) -> bool {
+        self.targets(hostname) && self.port.unwrap_or(DEFAULT_HTTPS_PORT) == port
+    }
+
+    /// Returns whether this record, found by looking up `hostname`, points back at `hostname`.
+    pub fn targets(&self, hostname: &str) -> bool {
+        self.target_name.as_deref().is_none_or(|target| {
+            target
+                .strip_suffix('.')
+                .unwrap_or(target)
+                .eq_ignore_ascii_case(hostname.strip_suffix('.').unwrap_or(hostname))
+        })
+    }
+
+    /// Returns whether the endpoint described by this record can speak `alpn`.
+    ///
+    /// `http/1.1` is implicitly supported unless `no-default-alpn` is set.
+    pub fn supports_alpn(&self, alpn: Alpn) -> bool {
+        (alpn == Alpn::Http1_1 && !self.no_default_alpn)
+            || self.alpn_ids.iter().any(|id| **id == *alpn.encoded())
+    }
 }
 
 impl IntoIterator for LookupResult {
@@ -52,7 +109,23 @@ impl<'a> IntoIterator for &'a LookupResult {
 
 impl LookupResult {
     pub fn new(ipv4: Vec<Ipv4Addr>, ipv6: Vec<Ipv6Addr>) -> Self {
-        Self { ipv4, ipv6 }
+        Self {
+            ipv4,
+            ipv6,
+            service_binding: None,
+        }
+    }
+
+    pub fn with_service_binding(self, service_binding: Option<ServiceBinding>) -> Self {
+        Self {
+            service_binding,
+            ..self
+        }
+    }
+
+    /// The hints from the name's HTTPS record, if one was looked up and found.
+    pub fn service_binding(&self) -> Option<&ServiceBinding> {
+        self.service_binding.as_ref()
     }
 
     pub fn iter(&self) -> <&Self as IntoIterator>::IntoIter {
@@ -77,7 +150,8 @@ mod test {
 
     use const_str::ip_addr;
 
-    use crate::dns::lookup_result::LookupResult;
+    use crate::Alpn;
+    use crate::dns::lookup_result::{LookupResult, ServiceBinding};
 
     #[test]
     fn lookup_result_iterates_in_the_right_order() {
@@ -130,6 +204,47 @@ mod test {
         );
     }
 
+    #[test]
+    fn service_binding_alpn_support() {
+        let binding = ServiceBinding {
+            alpn_ids: vec![Alpn::Http2.encoded().into()],
+            ..Default::default()
+        };
+        assert!(binding.supports_alpn(Alpn::Http2));
+        assert!(binding.supports_alpn(Alpn::Http1_1));
+
+        let binding = ServiceBinding {
+            no_default_alpn: true,
+            ..binding
+        };
+        assert!(binding.supports_alpn(Alpn::Http2));
+        assert!(!binding.supports_alpn(Alpn::Http1_1));
+
+        assert!(!ServiceBinding::default().supports_alpn(Alpn::Http2));
+    }
+
+    #[test]
+    fn service_binding_target_and_port() {
+        let binding = ServiceBinding::default();
+        assert!(binding.applies_to("chat.signal.org", 443));
+        assert!(!binding.applies_to("chat.signal.org", 8443));
+
+        let binding = ServiceBinding {
+            target_name: Some("Chat.Signal.org.".into()),
+            port: Some(8443),
+            ..Default::default()
+        };
+        assert!(binding.applies_to("chat.signal.org", 8443));
+        assert!(!binding.applies_to("chat.signal.org", 443));
+
+        let binding = ServiceBinding {
+            target_name: Some("cdn.example.net".into()),
+            ..Default::default()
+        };
+        assert!(!binding.targets("chat.signal.org"));
+        assert!(!binding.applies_to("chat.signal.org", 443));
+    }
+
     fn validate_expected_order(ipv4s: Vec<Ipv4Addr>, ipv6s: Vec<Ipv6Addr>, expected: Vec<IpAddr>) {
         let lookup_result = LookupResult::new(ipv4s, ipv6s);
         let actual: Vec<IpAddr> = lookup_result.into_iter().collect();
@@ -13,6 +13,7 @@ use http::{HeaderName, HeaderValue};
 use libsignal_core::LogSafeDisplay;
 use tokio_boring_signal::HandshakeError;
 
+use crate::route::EchConfigList;
 use crate::{AsStaticHttpHeader, certs};
 
 #[derive(Copy, Clone, Debug, thiserror::Error, displaydoc::Display)]
@@ -30,6 +31,8 @@ pub enum TransportConnectError {
     InvalidConfiguration,
     /// Failed to establish TCP connection to any of the IPs
     TcpConnectionFailed,
+    /// Failed to establish QUIC connection
+    QuicConnectionFailed,
     /// SSL error: {0}
     SslError(SslErrorReasons),
     /// Failed to load certificates
@@ -70,6 +73,14 @@ pub enum FailedHandshakeReason {
         error: boring_signal::x509::X509VerifyError,
         cert_hashes: Vec<X509CertSha256>,
     },
+    /// The certificate chain was valid, but didn't satisfy the configured
+    /// [`CertificatePolicy`](certs::CertificatePolicy).
+    CertificatePolicy(certs::PolicyViolation),
+    /// The server rejected Encrypted Client Hello, authenticating as the ECH config's public
+    /// name instead; `retry_configs` is what it offered to use instead, if anything.
+    EchRejected {
+        retry_configs: Option<EchConfigList>,
+    },
     OtherBoring(boring_signal::ssl::ErrorCode),
 }
 
@@ -128,6 +139,22 @@ impl<S> From<HandshakeError<S>> for FailedHandshakeReason {
             return Self::Io(io);
         }
 
+        if let Some(violation) = value.ssl().and_then(certs::recorded_policy_violation) {
+            return Self::CertificatePolicy(violation.clone());
+        }
+
+        if value
+            .as_ssl_error_stack()
+            .is_some_and(|stack| stack.errors().iter().any(is_ech_rejected))
+        {
+            return Self::EchRejected {
+                retry_configs: value
+                    .ssl()
+                    .and_then(SslRef::get_ech_retry_configs)
+                    .map(EchConfigList::new),
+            };
+        }
+
         let code = value.code().unwrap_or(boring_signal::ssl::ErrorCode::NONE);
 
         // If we specifically have an *SSL* error, check if it's an *X509* error underneath.
@@ -148,6 +175,14 @@ impl<S> From<HandshakeError<S>> for FailedHandshakeReason {
     }
 }
 
+/// Checks for BoringSSL's `SSL_R_ECH_REJECTED`.
+///
+/// The reason string is a fixed constant for each error code, so unlike in logging, there's no
+/// risk of user data here.
+fn is_ech_rejected(error: &boring_signal::error::Error) -> bool {
+    error.library() == Some("SSL routines") && error.reason() == Some("ECH_REJECTED")
+}
+
 impl LogSafeDisplay for FailedHandshakeReason {}
 impl Display for FailedHandshakeReason {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -168,6 +203,18 @@ impl Display for FailedHandshakeReason {
                     .entries(cert_hashes.iter().map(|hash| BASE64_STANDARD.encode(hash)))
                     .finish()
             }
+            Self::CertificatePolicy(violation) => {
+                write!(f, "certificate policy violation: {violation}")
+            }
+            Self::EchRejected { retry_configs } => write!(
+                f,
+                "encrypted client hello rejected ({} retry configs)",
+                if retry_configs.is_some() {
+                    "with"
+                } else {
+                    "without"
+                }
+            ),
             Self::OtherBoring(code) => write!(f, "boring SSL error code: {}", code.as_raw()),
         }
     }
@@ -211,7 +258,8 @@ impl From<TransportConnectError> for std::io::Error {
         use std::io::ErrorKind;
         let kind = match value {
             TransportConnectError::InvalidConfiguration => ErrorKind::InvalidInput,
-            TransportConnectError::TcpConnectionFailed => ErrorKind::ConnectionRefused,
+            TransportConnectError::TcpConnectionFailed
+            | TransportConnectError::QuicConnectionFailed => ErrorKind::ConnectionRefused,
             TransportConnectError::SslFailedHandshake(_)
             | TransportConnectError::SslError(_)
             | TransportConnectError::CertError
@@ -262,6 +310,7 @@ mod test {
                             sni: Host::Domain(SERVER_HOSTNAME.into()),
                             alpn: None,
                             min_protocol_version: None,
+                            ech_config: None,
                         },
                         inner: TcpRoute {
                             address: addr.ip(),
@@ -25,6 +25,11 @@ use crate::errors::TransportConnectError;
 use crate::route::{Connector, HttpRouteFragment, HttpVersion};
 use crate::{AsyncDuplexStream, Connection};
 
+#[cfg(feature = "quic")]
+mod http3;
+#[cfg(feature = "quic")]
+pub use http3::*;
+
 #[derive(displaydoc::Display, Debug)]
 pub enum HttpError {
     /// SSL handshake failed
@@ -80,21 +85,7 @@ impl<B: hyper::body::Body + 'static> Http2Client<B> {
         mut req: http::Request<B>,
     ) -> impl Future<Output = Result<http::Response<hyper::body::Incoming>, Http2TransportError>> + 'static
     {
-        let mut uri = std::mem::take(req.uri_mut()).into_parts();
-        uri.authority = Some(self.authority.clone());
-        uri.scheme = Some(http::uri::Scheme::HTTPS);
-        if let Some(prefix) = self.path_prefix.as_ref() {
-            uri.path_and_query = Some(
-                http::uri::PathAndQuery::from_str(&format!(
-                    "{}{}",
-                    prefix,
-                    uri.path_and_query.as_ref().map_or("", |path| path.as_str())
-                ))
-                .expect("valid path prefix"),
-            );
-        }
-        *req.uri_mut() = http::Uri::from_parts(uri).expect("valid parts");
-
+        qualify_uri(req.uri_mut(), &self.authority, self.path_prefix.as_ref());
         add_default_headers(req.headers_mut(), &self.default_per_request_headers);
 
         let req_fut = self.service.send_request(req);
@@ -130,6 +121,31 @@ impl<B: hyper::body::Body + 'static> Http2Client<B> {
     }
 }
 
+/// Fills in the scheme and authority of `uri`, and prepends `path_prefix` to its path.
+fn qualify_uri(
+    uri: &mut http::Uri,
+    authority: &http::uri::Authority,
+    path_prefix: Option<&http::uri::PathAndQuery>,
+) {
+    let mut parts = std::mem::take(uri).into_parts();
+    parts.authority = Some(authority.clone());
+    parts.scheme = Some(http::uri::Scheme::HTTPS);
+    if let Some(prefix) = path_prefix {
+        parts.path_and_query = Some(
+            http::uri::PathAndQuery::from_str(&format!(
+                "{}{}",
+                prefix,
+                parts
+                    .path_and_query
+                    .as_ref()
+                    .map_or("", |path| path.as_str())
+            ))
+            .expect("valid path prefix"),
+        );
+    }
+    *uri = http::Uri::from_parts(parts).expect("valid parts");
+}
+
 /// Copy all headers from `default_headers` into `headers` *except* for those already present.
 ///
 /// `HeaderMap` is a multimap, so we have to be careful how we do this.
@@ -415,16 +431,7 @@ where
             }
         });
 
-        let authority = http::uri::Authority::from_str(&host_header)
-            .map_err(|_| HttpConnectError::InvalidConfig("invalid host"))?;
-        let path_prefix = if path_prefix.is_empty() {
-            None
-        } else {
-            Some(
-                http::uri::PathAndQuery::from_str(&path_prefix)
-                    .map_err(|_| HttpConnectError::InvalidConfig("invalid path prefix"))?,
-            )
-        };
+        let (authority, path_prefix) = parse_authority_and_prefix(&host_header, &path_prefix)?;
 
         Ok(Http2Client {
             service: sender,
@@ -437,6 +444,23 @@ where
     }
 }
 
+fn parse_authority_and_prefix(
+    host_header: &str,
+    path_prefix: &str,
+) -> Result<(http::uri::Authority, Option<http::uri::PathAndQuery>), HttpConnectError> {
+    let authority = http::uri::Authority::from_str(host_header)
+        .map_err(|_| HttpConnectError::InvalidConfig("invalid host"))?;
+    let path_prefix = if path_prefix.is_empty() {
+        None
+    } else {
+        Some(
+            http::uri::PathAndQuery::from_str(path_prefix)
+                .map_err(|_| HttpConnectError::InvalidConfig("invalid path prefix"))?,
+        )
+    };
+    Ok((authority, path_prefix))
+}
+
 #[cfg(test)]
 mod test {
     use std::borrow::Cow;
@@ -602,6 +626,7 @@ mod test {
                         )),
                         alpn: Some(crate::Alpn::Http2),
                         min_protocol_version: None,
+                        ech_config: None,
                     },
                     inner: TcpRoute {
                         address: Ipv6Addr::LOCALHOST.into(),
@@ -681,6 +706,7 @@ mod test {
                         )),
                         alpn: Some(crate::Alpn::Http2),
                         min_protocol_version: None,
+                        ech_config: None,
                     },
                     inner: TcpRoute {
                         address: Ipv6Addr::LOCALHOST.into(),
@@ -782,6 +808,7 @@ mod test {
                         )),
                         alpn: None,
                         min_protocol_version: None,
+                        ech_config: None,
                     },
                     inner: TcpRoute {
                         address: Ipv6Addr::LOCALHOST.into(),
@@ -0,0 +1,489 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::pin::{Pin, pin};
+use std::sync::Arc;
+use std::task::{Context, Poll, ready};
+
+use bytes::{Buf, Bytes};
+use futures_util::FutureExt as _;
+use http_body::Frame;
+use http_body_util::BodyExt as _;
+
+use super::{HttpConnectError, add_default_headers, parse_authority_and_prefix, qualify_uri};
+use crate::Connection as _;
+use crate::quic::QuicConnection;
+use crate::route::{Connector, Http3RouteFragment};
+
+type SendRequest = h3::client::SendRequest<h3_quinn::OpenStreams, Bytes>;
+type RequestSendStream = h3::client::RequestStream<h3_quinn::SendStream<Bytes>, Bytes>;
+type ResponseRecvStream = h3::client::RequestStream<h3_quinn::RecvStream, Bytes>;
+
+/// The HTTP/3 counterpart to [`Http2Client`](super::Http2Client), running over a
+/// [`QuicConnection`].
+///
+/// Created using [`Http3Connector`].
+///
+/// When `tower-service` is enabled, `Http3Client` can be used as a [`tower_service::Service`],
+/// which makes it usable as a gRPC channel.
+///
+/// HTTP/3 doesn't carry WebSockets here: the extended CONNECT form for WebSockets over HTTP/3
+/// ([RFC 9220](https://www.rfc-editor.org/rfc/rfc9220)) isn't supported by our HTTP/3
+/// implementation.
+#[derive(Clone)]
+pub struct Http3Client {
+    sender: SendRequest,
+    cancellation_token: tokio_util::sync::CancellationToken,
+    authority: http::uri::Authority,
+    path_prefix: Option<http::uri::PathAndQuery>,
+    default_per_request_headers: Arc<http::HeaderMap>,
+    h3_connection_cancellation_token: tokio_util::sync::CancellationToken,
+}
+
+impl std::fmt::Debug for Http3Client {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Http3Client")
+            .field("authority", &self.authority)
+            .field("path_prefix", &self.path_prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(thiserror::Error, Debug, displaydoc::Display)]
+pub enum Http3TransportError {
+    /// Locally disconnected
+    LocalDisconnect,
+    /// {0}
+    Stream(#[from] h3::error::StreamError),
+}
+
+/// The body of a response received by an [`Http3Client`].
+pub struct Http3Body {
+    stream: ResponseRecvStream,
+    data_finished: bool,
+    trailers_finished: bool,
+}
+
+impl Http3Client {
+    /// Sends `req`, resolving once the response headers have been received.
+    ///
+    /// The request body is sent from a separate task, so the response can start arriving before
+    /// the request is complete (as needed for bidirectional streaming).
+    pub fn send_request<B>(
+        &mut self,
+        mut req: http::Request<B>,
+    ) -> impl Future<Output = Result<http::Response<Http3Body>, Http3TransportError>> + Send + 'static
+    where
+        B: http_body::Body<Data: Send> + Send + 'static,
+    {
+        qualify_uri(req.uri_mut(), &self.authority, self.path_prefix.as_ref());
+        add_default_headers(req.headers_mut(), &self.default_per_request_headers);
+
+        let mut sender = self.sender.clone();
+        let req_fut = async move {
+            let (parts, body) = req.into_parts();
+            let stream = sender
+                .send_request(http::Request::from_parts(parts, ()))
+                .await?;
+            let (send, mut recv) = stream.split();
+            tokio::spawn(send_body(send, body));
+
+            let response = recv.recv_response().await?;
+            Ok(response.map(|()| Http3Body {
+                stream: recv,
+                data_finished: false,
+                trailers_finished: false,
+            }))
+        };
+
+        self.cancellation_token
+            .clone()
+            .run_until_cancelled_owned(req_fut)
+            .map(|result| result.unwrap_or(Err(Http3TransportError::LocalDisconnect)))
+    }
+
+    pub fn set_default_per_request_headers(&mut self, default_headers: http::HeaderMap) {
+        self.default_per_request_headers = Arc::new(default_headers);
+    }
+
+    /// Cancels all existing and future requests sent on this H3 connection, no matter which
+    /// `Http3Client` clone they were sent from.
+    pub fn disconnect_all(self) {
+        self.cancellation_token.cancel();
+    }
+
+    /// Awaits the closing of the underlying QUIC connection.
+    pub fn wait_for_h3_shutdown(&self) -> impl Future<Output = ()> + Send + 'static {
+        self.h3_connection_cancellation_token
+            .clone()
+            .cancelled_owned()
+    }
+}
+
+/// Forwards the frames of `body` to `stream`, then finishes the stream.
+///
+/// If the body fails, the stream is reset so the server doesn't mistake a partial request for a
+/// complete one.
+async fn send_body<B: http_body::Body>(mut stream: RequestSendStream, body: B) {
+    let mut body = pin!(body);
+    while let Some(frame) = body.frame().await {
+        let frame = match frame {
+            Ok(frame) => frame,
+            Err(_) => {
+                log::warn!("HTTP/3 request body failed; cancelling the request");
+                stream.stop_stream(h3::error::Code::H3_REQUEST_CANCELLED);
+                return;
+            }
+        };
+        let result = match frame.into_data() {
+            Ok(mut data) => stream.send_data(data.copy_to_bytes(data.remaining())).await,
+            Err(frame) => match frame.into_trailers() {
+                Ok(trailers) => stream.send_trailers(trailers).await,
+                Err(_unknown_frame) => Ok(()),
+            },
+        };
+        if let Err(e) = result {
+            log::info!("failed to send HTTP/3 request body: {e}");
+            return;
+        }
+    }
+    if let Err(e) = stream.finish().await {
+        log::info!("failed to finish HTTP/3 request: {e}");
+    }
+}
+
+impl http_body::Body for Http3Body {
+    type Data = Bytes;
+    type Error = Http3TransportError;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.get_mut();
+        if !this.data_finished {
+            match ready!(this.stream.poll_recv_data(cx)) {
+                Ok(Some(mut data)) => {
+                    return Poll::Ready(Some(Ok(Frame::data(
+                        data.copy_to_bytes(data.remaining()),
+                    ))));
+                }
+                Ok(None) => this.data_finished = true,
+                Err(e) => return Poll::Ready(Some(Err(e.into()))),
+            }
+        }
+        if this.trailers_finished {
+            return Poll::Ready(None);
+        }
+        let trailers = ready!(this.stream.poll_recv_trailers(cx));
+        this.trailers_finished = true;
+        match trailers {
+            Ok(Some(trailers)) => Poll::Ready(Some(Ok(Frame::trailers(trailers)))),
+            Ok(None) => Poll::Ready(None),
+            Err(e) => Poll::Ready(Some(Err(e.into()))),
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.data_finished && self.trailers_finished
+    }
+}
+
+#[cfg(feature = "tower-service")]
+impl<B> tower_service::Service<http::Request<B>> for Http3Client
+where
+    B: http_body::Body<Data: Send> + Send + 'static,
+{
+    type Response = http::Response<Http3Body>;
+    type Error = Http3TransportError;
+    type Future = futures_util::future::BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // Streams are opened (and flow-controlled) as part of sending each request.
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        Box::pin(self.send_request(req))
+    }
+}
+
+/// Establishes HTTP/3 sessions over [`QuicConnection`]s.
+#[derive(Debug, Default)]
+pub struct Http3Connector;
+
+impl Connector<Http3RouteFragment, QuicConnection> for Http3Connector {
+    type Connection = Http3Client;
+    type Error = HttpConnectError;
+
+    async fn connect_over(
+        &self,
+        over: QuicConnection,
+        route: Http3RouteFragment,
+        log_tag: &str,
+    ) -> Result<Self::Connection, Self::Error> {
+        let Http3RouteFragment {
+            host_header,
+            path_prefix,
+        } = route;
+
+        let ip_version = over.transport_info().ip_version();
+        let (mut connection, sender) = h3::client::builder()
+            .build(h3_quinn::Connection::new(over.into_inner()))
+            .await
+            .map_err(|_: h3::error::ConnectionError| HttpConnectError::HttpHandshake)?;
+
+        // As with HTTP/2, a separate task drives the connection; it finishes once the connection
+        // is closed, either due to an error or because all senders were dropped.
+        let log_tag = log_tag.to_owned();
+        let h3_connection_cancellation_token = tokio_util::sync::CancellationToken::new();
+        let h3_connection_cancellation_token_guard =
+            h3_connection_cancellation_token.clone().drop_guard();
+        _ = tokio::spawn(async move {
+            let _guard = h3_connection_cancellation_token_guard;
+            let err = std::future::poll_fn(|cx| connection.poll_close(cx)).await;
+            if err.is_h3_no_error() {
+                log::info!("[{log_tag}] HTTP3 connection [{ip_version}] closed");
+            } else {
+                log::warn!("[{log_tag}] HTTP3 connection [{ip_version}] failed: {err}");
+            }
+        });
+
+        let (authority, path_prefix) = parse_authority_and_prefix(&host_header, &path_prefix)?;
+
+        Ok(Http3Client {
+            sender,
+            cancellation_token: tokio_util::sync::CancellationToken::new(),
+            authority,
+            path_prefix,
+            default_per_request_headers: Default::default(),
+            h3_connection_cancellation_token,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+    use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+    use std::time::Duration;
+
+    use assert_matches::assert_matches;
+    use http::{HeaderMap, HeaderValue};
+    use http_body_util::Full;
+    use warp::Filter as _;
+
+    use super::*;
+    use crate::certs::RootCertificates;
+    use crate::host::Host;
+    use crate::http_client::Http2Connector;
+    use crate::quic::StatelessQuic;
+    use crate::quic::testutil::localhost_quic_server;
+    use crate::route::{
+        ComposedConnector, ConnectionOutcomeParams, ConnectionOutcomes, ConnectorExt as _,
+        ErrorHandling, Http3Route, HttpRouteFragment, HttpVersion, HttpsTlsRoute, QuicRoute,
+        QuicRouteFragment, TcpOrQuic, TcpOrQuicRoute, TcpRoute, TlsRoute, TlsRouteFragment,
+        UdpRoute,
+    };
+    use crate::tcp_ssl::proxy::testutil::PROXY_CERTIFICATE;
+    use crate::tcp_ssl::testutil::{SERVER_CERTIFICATE, SERVER_HOSTNAME, localhost_https_server};
+    use crate::tcp_ssl::{StatelessTcp, StatelessTls};
+    use crate::{Alpn, OverrideNagleAlgorithm};
+
+    const HOST_HEADER: &str = "different-from-sni.test-hostname";
+
+    /// Serves HTTP/3 on `endpoint`, echoing each request body back.
+    ///
+    /// The response has the request's URI in a header, and the request's trailers are echoed back
+    /// as the response trailers, along with `grpc-status: 0`.
+    async fn serve_echo(endpoint: quinn::Endpoint) {
+        while let Some(incoming) = endpoint.accept().await {
+            tokio::spawn(async move {
+                let Ok(connection) = incoming.await else {
+                    return;
+                };
+                let mut connection: h3::server::Connection<_, Bytes> = h3::server::builder()
+                    .build(h3_quinn::Connection::new(connection))
+                    .await
+                    .expect("h3 handshake succeeds");
+                while let Ok(Some(resolver)) = connection.accept().await {
+                    tokio::spawn(async move {
+                        let (request, mut stream) =
+                            resolver.resolve_request().await.expect("valid request");
+                        let mut body = Vec::new();
+                        while let Some(mut chunk) = stream.recv_data().await.expect("can read") {
+                            body.extend_from_slice(&chunk.copy_to_bytes(chunk.remaining()));
+                        }
+                        let mut trailers = stream
+                            .recv_trailers()
+                            .await
+                            .expect("can read trailers")
+                            .unwrap_or_default();
+                        trailers.insert("grpc-status", HeaderValue::from_static("0"));
+
+                        let response = http::Response::builder()
+                            .header("request-uri", request.uri().to_string())
+                            .body(())
+                            .expect("valid response");
+                        stream.send_response(response).await.expect("can send");
+                        stream.send_data(body.into()).await.expect("can send");
+                        stream.send_trailers(trailers).await.expect("can send");
+                        stream.finish().await.expect("can finish");
+                    });
+                }
+            });
+        }
+    }
+
+    fn quic_route(addr: SocketAddr, root_certs: RootCertificates) -> Http3Route<IpAddr> {
+        Http3Route {
+            fragment: Http3RouteFragment {
+                host_header: HOST_HEADER.into(),
+                path_prefix: "/prefix".into(),
+            },
+            inner: QuicRoute {
+                fragment: QuicRouteFragment {
+                    root_certs,
+                    sni: Host::Domain(SERVER_HOSTNAME.into()),
+                    alpn: Alpn::Http3,
+                    ech_config: None,
+                },
+                inner: UdpRoute {
+                    address: addr.ip(),
+                    port: addr.port().try_into().expect("bound to a real port"),
+                },
+            },
+        }
+    }
+
+    fn server_root_certs() -> RootCertificates {
+        RootCertificates::FromDer(Cow::Borrowed(SERVER_CERTIFICATE.cert.der()))
+    }
+
+    #[tokio::test]
+    async fn request_round_trip_with_trailers() {
+        let (endpoint, addr) = localhost_quic_server(Alpn::Http3);
+        tokio::spawn(serve_echo(endpoint));
+
+        let mut client = ComposedConnector::new(Http3Connector, StatelessQuic)
+            .connect(quic_route(addr, server_root_certs()), "test")
+            .await
+            .expect("can connect");
+
+        let mut request_trailers = HeaderMap::new();
+        request_trailers.insert("request-trailer", HeaderValue::from_static("value"));
+        let request = http::Request::post("/path")
+            .body(
+                Full::new(Bytes::from_static(b"request body"))
+                    .with_trailers(std::future::ready(Some(Ok(request_trailers)))),
+            )
+            .expect("valid request");
+
+        let response = client.send_request(request).await.expect("can send");
+        assert_eq!(
+            response.headers()["request-uri"],
+            format!("https://{HOST_HEADER}/prefix/path")
+        );
+
+        let body = response.into_body().collect().await.expect("can read body");
+        let trailers = body.trailers().expect("has trailers").clone();
+        assert_eq!(body.to_bytes(), b"request body".as_slice());
+        assert_eq!(trailers["request-trailer"], "value");
+        assert_eq!(trailers["grpc-status"], "0");
+    }
+
+    #[tokio::test]
+    async fn disconnect_all_cancels_requests() {
+        let (endpoint, addr) = localhost_quic_server(Alpn::Http3);
+        tokio::spawn(serve_echo(endpoint));
+
+        let client = ComposedConnector::new(Http3Connector, StatelessQuic)
+            .connect(quic_route(addr, server_root_certs()), "test")
+            .await
+            .expect("can connect");
+
+        client.clone().disconnect_all();
+        assert_matches!(
+            client
+                .clone()
+                .send_request(
+                    http::Request::get("/")
+                        .body(Full::<Bytes>::default())
+                        .expect("valid")
+                )
+                .await,
+            Err(Http3TransportError::LocalDisconnect)
+        );
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_tcp_when_quic_fails() {
+        let _ = env_logger::try_init();
+
+        // The QUIC server's certificate won't be trusted, so the handshake fails.
+        let (endpoint, quic_addr) = localhost_quic_server(Alpn::Http3);
+        tokio::spawn(serve_echo(endpoint));
+        let (tcp_addr, tcp_server) = localhost_https_server(warp::any().map(|| "over TCP"));
+        tokio::spawn(tcp_server);
+
+        let routes = vec![
+            TcpOrQuicRoute::Quic(quic_route(
+                quic_addr,
+                RootCertificates::FromDer(Cow::Borrowed(PROXY_CERTIFICATE.cert.der())),
+            )),
+            TcpOrQuicRoute::Tcp(HttpsTlsRoute {
+                fragment: HttpRouteFragment {
+                    host_header: HOST_HEADER.into(),
+                    path_prefix: "".into(),
+                    http_version: Some(HttpVersion::Http2),
+                    front_name: None,
+                },
+                inner: TlsRoute {
+                    fragment: TlsRouteFragment {
+                        root_certs: server_root_certs(),
+                        sni: Host::Domain(SERVER_HOSTNAME.into()),
+                        alpn: Some(Alpn::Http2),
+                        min_protocol_version: None,
+                        ech_config: None,
+                    },
+                    inner: TcpRoute {
+                        address: Ipv6Addr::LOCALHOST.into(),
+                        port: tcp_addr.port().try_into().expect("bound to a real port"),
+                        override_nagle_algorithm: OverrideNagleAlgorithm::UseSystemDefault,
+                    },
+                },
+            }),
+        ];
+
+        let connector = TcpOrQuic::<_, _, HttpConnectError>::new(
+            ComposedConnector::new(
+                Http2Connector::<Full<Bytes>>::new(),
+                ComposedConnector::new(StatelessTls, StatelessTcp),
+            ),
+            ComposedConnector::new(Http3Connector, StatelessQuic),
+        );
+        let mut outcomes = ConnectionOutcomes::new(ConnectionOutcomeParams {
+            short_term_age_cutoff: Duration::from_secs(1000),
+            long_term_age_cutoff: Duration::from_secs(1000),
+            cooldown_growth_factor: 2.0,
+            count_growth_factor: 10.0,
+            max_count: 5,
+            max_delay: Duration::from_secs(100),
+        });
+
+        let (result, updates) =
+            crate::route::connect_resolved(routes, &mut outcomes, connector, (), "test", |_| {
+                ErrorHandling::<HttpConnectError>::Continue
+            })
+            .await;
+
+        assert_matches!(result, Ok(tokio_util::either::Either::Left(_)));
+        assert_matches!(
+            &*updates.outcomes,
+            [(TcpOrQuicRoute::Quic(_), _), (TcpOrQuicRoute::Tcp(_), _)]
+                | [(TcpOrQuicRoute::Tcp(_), _), (TcpOrQuicRoute::Quic(_), _)]
+        );
+    }
+}
@@ -23,6 +23,8 @@ pub mod dns;
 pub mod errors;
 pub mod host;
 pub mod http_client;
+#[cfg(feature = "quic")]
+pub mod quic;
 pub mod route;
 pub mod stream;
 pub mod tcp_ssl;
@@ -201,6 +203,8 @@ pub enum DnsSource {
     UdpLookup,
     /// The result came from performing a DNS-over-HTTPS query.
     DnsOverHttpsLookup,
+    /// The result came from performing a DNS-over-TLS query.
+    DnsOverTlsLookup,
     /// The result came from performing a DNS query using a system resolver.
     SystemLookup,
     /// The result was resolved from a preconfigured static entry.
@@ -254,6 +258,7 @@ impl<S: AsyncRead + AsyncWrite + Unpin + Send> AsyncDuplexStream for S {}
 pub enum Alpn {
     Http1_1,
     Http2,
+    Http3,
 }
 
 impl Alpn {
@@ -268,6 +273,7 @@ impl Alpn {
         match self {
             Self::Http1_1 => b"\x08http/1.1",
             Self::Http2 => b"\x02h2",
+            Self::Http3 => b"\x02h3",
         }
     }
 }
@@ -284,6 +290,9 @@ impl TryFrom<&'_ [u8]> for Alpn {
         if value == Self::Http1_1.encoded() {
             return Ok(Self::Http1_1);
         }
+        if value == Self::Http3.encoded() {
+            return Ok(Self::Http3);
+        }
         Err(UnrecognizedAlpn)
     }
 }
@@ -0,0 +1,297 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! QUIC connections, as used for HTTP/3.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+
+use quinn::crypto::rustls::QuicClientConfig;
+
+use crate::errors::TransportConnectError;
+use crate::host::Host;
+use crate::route::{Connector, QuicRoute, QuicRouteFragment, UdpRoute};
+use crate::{Connection, TransportInfo};
+
+/// Stateless [`Connector`] for [`QuicRoute`]s.
+///
+/// Each connection gets its own UDP socket, so that a change in network
+/// conditions affecting one connection won't affect any others.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StatelessQuic;
+
+/// An established QUIC connection.
+#[derive(Debug)]
+pub struct QuicConnection {
+    connection: quinn::Connection,
+    transport_info: TransportInfo,
+}
+
+impl QuicConnection {
+    pub fn into_inner(self) -> quinn::Connection {
+        self.connection
+    }
+}
+
+impl Connection for QuicConnection {
+    fn transport_info(&self) -> TransportInfo {
+        self.transport_info.clone()
+    }
+}
+
+impl Connector<QuicRoute<IpAddr>, ()> for StatelessQuic {
+    type Connection = QuicConnection;
+    type Error = TransportConnectError;
+
+    async fn connect_over(
+        &self,
+        (): (),
+        route: QuicRoute<IpAddr>,
+        log_tag: &str,
+    ) -> Result<Self::Connection, Self::Error> {
+        let QuicRoute {
+            fragment:
+                QuicRouteFragment {
+                    root_certs,
+                    sni,
+                    alpn,
+                    ech_config,
+                },
+            inner: UdpRoute { address, port },
+        } = route;
+
+        if ech_config.is_some() {
+            // Our QUIC stack can't send ECH. Rather than send the SNI in the clear, leave this
+            // server to TCP routes, which can.
+            log::info!("[{log_tag}] not using QUIC for a server that publishes an ECH config");
+            return Err(TransportConnectError::InvalidConfiguration);
+        }
+
+        let tls_config = root_certs.rustls_client_config(alpn)?;
+        let quic_config = QuicClientConfig::try_from(tls_config)
+            .map_err(|_| TransportConnectError::InvalidConfiguration)?;
+
+        let local_addr = match address {
+            IpAddr::V4(_) => SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+            IpAddr::V6(_) => SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)),
+        };
+        let endpoint = quinn::Endpoint::client(local_addr).map_err(|e| {
+            log::warn!("[{log_tag}] failed to bind UDP socket: {}", e.kind());
+            TransportConnectError::QuicConnectionFailed
+        })?;
+        let local_addr = endpoint
+            .local_addr()
+            .map_err(|_| TransportConnectError::QuicConnectionFailed)?;
+
+        let server_name = match &sni {
+            Host::Domain(domain) => domain.to_string(),
+            Host::Ip(ip) => ip.to_string(),
+        };
+        let remote_addr = SocketAddr::new(address, port.get());
+        let connection = endpoint
+            .connect_with(
+                quinn::ClientConfig::new(Arc::new(quic_config)),
+                remote_addr,
+                &server_name,
+            )
+            .map_err(|_| TransportConnectError::InvalidConfiguration)?
+            .await
+            .map_err(|e| {
+                log::info!(
+                    "[{log_tag}] QUIC connection failed: {}",
+                    describe_connection_error(&e)
+                );
+                TransportConnectError::QuicConnectionFailed
+            })?;
+
+        Ok(QuicConnection {
+            transport_info: TransportInfo {
+                local_addr,
+                remote_addr: connection.remote_address(),
+            },
+            connection,
+        })
+    }
+}
+
+/// A log-safe summary of a [`quinn::ConnectionError`].
+///
+/// The full error can contain reason strings sent by the peer.
+fn describe_connection_error(error: &quinn::ConnectionError) -> &'static str {
+    match error {
+        quinn::ConnectionError::VersionMismatch => "version mismatch",
+        quinn::ConnectionError::TransportError(_) => "transport error",
+        quinn::ConnectionError::ConnectionClosed(_) => "aborted by peer",
+        quinn::ConnectionError::ApplicationClosed(_) => "closed by peer",
+        quinn::ConnectionError::Reset => "reset by peer",
+        quinn::ConnectionError::TimedOut => "timed out",
+        quinn::ConnectionError::LocallyClosed => "closed locally",
+        quinn::ConnectionError::CidsExhausted => "connection IDs exhausted",
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod testutil {
+    use std::net::{Ipv6Addr, SocketAddr};
+    use std::sync::Arc;
+
+    use quinn::crypto::rustls::QuicServerConfig;
+    use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+
+    use crate::Alpn;
+    use crate::tcp_ssl::testutil::SERVER_CERTIFICATE;
+
+    /// Creates a QUIC server endpoint on localhost that presents [`SERVER_CERTIFICATE`] and
+    /// negotiates `alpn`.
+    pub(crate) fn localhost_quic_server(alpn: Alpn) -> (quinn::Endpoint, SocketAddr) {
+        let mut tls_config = rustls::ServerConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_protocol_versions(&[&rustls::version::TLS13])
+        .expect("ring supports TLS 1.3")
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![CertificateDer::from(SERVER_CERTIFICATE.cert.der().to_vec())],
+            PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(
+                SERVER_CERTIFICATE.signing_key.serialized_der().to_vec(),
+            )),
+        )
+        .expect("valid certificate");
+        tls_config.alpn_protocols = vec![alpn.encoded().to_vec()];
+
+        let server_config = quinn::ServerConfig::with_crypto(Arc::new(
+            QuicServerConfig::try_from(tls_config).expect("valid for QUIC"),
+        ));
+        let endpoint =
+            quinn::Endpoint::server(server_config, SocketAddr::from((Ipv6Addr::LOCALHOST, 0)))
+                .expect("can bind");
+        let addr = endpoint.local_addr().expect("bound");
+        (endpoint, addr)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use test_case::test_case;
+
+    use super::testutil::localhost_quic_server;
+    use super::*;
+    use crate::Alpn;
+    use crate::certs::{CertificatePolicy, RootCertificates, SpkiPins, spki_sha256};
+    use crate::route::{ConnectorExt as _, EchConfigList};
+    use crate::tcp_ssl::proxy::testutil::PROXY_CERTIFICATE;
+    use crate::tcp_ssl::testutil::{SERVER_CERTIFICATE, SERVER_HOSTNAME};
+
+    fn route_to(addr: SocketAddr) -> QuicRoute<IpAddr> {
+        QuicRoute {
+            fragment: QuicRouteFragment {
+                root_certs: RootCertificates::FromDer(
+                    SERVER_CERTIFICATE.cert.der().to_vec().into(),
+                ),
+                sni: Host::Domain(SERVER_HOSTNAME.into()),
+                alpn: Alpn::Http3,
+                ech_config: None,
+            },
+            inner: UdpRoute {
+                address: addr.ip(),
+                port: addr.port().try_into().expect("bound to a real port"),
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn connect_to_local_server() {
+        let (server, addr) = localhost_quic_server(Alpn::Http3);
+        let server_task = tokio::spawn(async move {
+            let incoming = server.accept().await.expect("has a connection");
+            let connection = incoming.await.expect("handshake succeeds");
+            let mut recv = connection
+                .accept_uni()
+                .await
+                .expect("client opens a stream");
+            recv.read_to_end(100).await.expect("can read")
+        });
+
+        let connection = StatelessQuic
+            .connect(route_to(addr), "test")
+            .await
+            .expect("can connect");
+        assert_eq!(connection.transport_info().remote_addr, addr);
+
+        let connection = connection.into_inner();
+        let mut send = connection.open_uni().await.expect("can open a stream");
+        send.write_all(b"hello").await.expect("can write");
+        send.finish().expect("can finish");
+
+        assert_eq!(server_task.await.expect("no panic"), b"hello");
+    }
+
+    #[tokio::test]
+    async fn untrusted_server_certificate_is_rejected() {
+        let (server, addr) = localhost_quic_server(Alpn::Http3);
+        let _server_task = tokio::spawn(async move {
+            while let Some(incoming) = server.accept().await {
+                _ = incoming.await;
+            }
+        });
+
+        let mut route = route_to(addr);
+        route.fragment.root_certs =
+            RootCertificates::FromDer(PROXY_CERTIFICATE.cert.der().to_vec().into());
+
+        assert_matches!(
+            StatelessQuic.connect(route, "test").await,
+            Err(TransportConnectError::QuicConnectionFailed)
+        );
+    }
+
+    #[test_case(true; "matching pin")]
+    #[test_case(false; "mismatched pin")]
+    #[tokio::test]
+    async fn pinned_certificate(pin_server_key: bool) {
+        let (server, addr) = localhost_quic_server(Alpn::Http3);
+        let _server_task = tokio::spawn(async move {
+            while let Some(incoming) = server.accept().await {
+                _ = incoming.await;
+            }
+        });
+
+        let pinned_cert = if pin_server_key {
+            SERVER_CERTIFICATE.cert.der()
+        } else {
+            PROXY_CERTIFICATE.cert.der()
+        };
+        let pins = SpkiPins::new(spki_sha256(pinned_cert).expect("valid"), [[0xAA; 32]])
+            .expect("has a backup");
+
+        let mut route = route_to(addr);
+        route.fragment.root_certs = RootCertificates::WithPolicy {
+            roots: Arc::new(route.fragment.root_certs),
+            policy: Arc::new(CertificatePolicy {
+                pins: Some(pins),
+                certificate_transparency: None,
+            }),
+        };
+
+        let result = StatelessQuic.connect(route, "test").await;
+        if pin_server_key {
+            assert_matches!(result, Ok(_));
+        } else {
+            assert_matches!(result, Err(TransportConnectError::QuicConnectionFailed));
+        }
+    }
+
+    #[tokio::test]
+    async fn refuses_to_connect_without_ech() {
+        let mut route = route_to(SocketAddr::from((Ipv6Addr::LOCALHOST, 443)));
+        route.fragment.ech_config = Some(EchConfigList::new(b"ech config".as_slice()));
+
+        assert_matches!(
+            StatelessQuic.connect(route, "test").await,
+            Err(TransportConnectError::InvalidConfiguration)
+        );
+    }
+}
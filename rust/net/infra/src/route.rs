@@ -107,6 +107,11 @@ pub use crate::route::provider::RouteProviderExt;
 mod proxy;
 pub use proxy::*;
 
+#[cfg(feature = "quic")]
+mod quic;
+#[cfg(feature = "quic")]
+pub use quic::*;
+
 mod resolve;
 pub use resolve::*;
 
@@ -485,7 +490,7 @@ where
                     let result = connector
                         .connect_over(inner, route.clone(), &log_tag_for_connect)
                         .await;
-                    (route, result, started)
+                    (route, result, started, Instant::now())
                 });
                 poll_schedule_for_next = false;
                 most_recent_connection_start = Instant::now();
@@ -499,8 +504,17 @@ where
                 schedule.set(None);
                 poll_schedule_for_next = false;
             }
-            Event::ConnectionAttemptFinished((route, result, started)) => {
-                let make_outcome = |result| (route, AttemptOutcome { started, result });
+            Event::ConnectionAttemptFinished((route, result, started, finished)) => {
+                let make_outcome = |result| {
+                    (
+                        route,
+                        AttemptOutcome {
+                            started,
+                            finished,
+                            result,
+                        },
+                    )
+                };
                 match result.map_err(&mut on_error) {
                     Ok(connection) => {
                         // We've got a successful connection!
@@ -738,6 +752,7 @@ mod test {
     use crate::route::resolve::testutils::FakeResolver;
     use crate::route::testutils::{FakeConnectError, FakeContext, FakeRoute};
     use crate::route::{SocksProxy, TlsProxy};
+    use crate::tcp_ssl::proxy::pluggable::{PluggableTransport, PluggableTransportHandle};
     use crate::tcp_ssl::proxy::socks;
     use crate::{Alpn, OverrideNagleAlgorithm, RouteType};
 
@@ -806,6 +821,7 @@ mod test {
                             sni: Host::Domain("sni-name".into()),
                             alpn: Some(Alpn::Http1_1),
                             min_protocol_version: Some(boring_signal::ssl::SslVersion::TLS1_3),
+                            ech_config: None,
                         },
                         inner: TcpRoute {
                             address: UnresolvedHost("target-host".into()),
@@ -834,6 +850,7 @@ mod test {
                             sni: Host::Domain("front-sni1".into()),
                             alpn: Some(Alpn::Http2),
                             min_protocol_version: None,
+                            ech_config: None,
                         },
                         inner: TcpRoute {
                             address: UnresolvedHost("front-sni1".into()),
@@ -862,6 +879,7 @@ mod test {
                             sni: Host::Domain("front-sni2".into()),
                             alpn: Some(Alpn::Http2),
                             min_protocol_version: None,
+                            ech_config: None,
                         },
                         inner: TcpRoute {
                             address: UnresolvedHost("front-sni2".into()),
@@ -915,6 +933,7 @@ mod test {
                     sni: Host::Domain("direct-sni".into()),
                     alpn: None,
                     min_protocol_version: Some(boring_signal::ssl::SslVersion::TLS1_1),
+                    ech_config: None,
                 },
                 inner: DirectOrProxyRoute::Proxy(ConnectionProxyRoute::Tls {
                     proxy: TlsRoute {
@@ -928,6 +947,7 @@ mod test {
                             sni: Host::Domain("tls-proxy".into()),
                             alpn: None,
                             min_protocol_version: None,
+                            ech_config: None,
                         },
                     },
                 }),
@@ -976,6 +996,7 @@ mod test {
                     sni: Host::Domain("direct-sni".into()),
                     alpn: None,
                     min_protocol_version: Some(boring_signal::ssl::SslVersion::TLS1_1),
+                    ech_config: None,
                 },
                 inner: DirectOrProxyRoute::Proxy(ConnectionProxyRoute::Socks(SocksRoute {
                     proxy: TcpRoute {
@@ -996,6 +1017,7 @@ mod test {
                     sni: Host::Domain("direct-sni".into()),
                     alpn: None,
                     min_protocol_version: Some(boring_signal::ssl::SslVersion::TLS1_1),
+                    ech_config: None,
                 },
                 inner: DirectOrProxyRoute::Direct(TcpRoute {
                     address: UnresolvedHost("direct-target".into()),
@@ -1007,6 +1029,84 @@ mod test {
         pretty_assertions::assert_eq!(expected_routes, routes);
     }
 
+    #[test]
+    fn pluggable_transport_proxy_route() {
+        const TARGET_PORT: NonZeroU16 = nonzero!(7898u16);
+        const BRIDGE_PORT: NonZeroU16 = nonzero!(8443u16);
+
+        #[derive(Debug)]
+        struct UnusedTransport;
+
+        #[async_trait::async_trait]
+        impl PluggableTransport for UnusedTransport {
+            fn name(&self) -> &'static str {
+                "unused"
+            }
+
+            async fn wrap(
+                &self,
+                _bridge: crate::tcp_ssl::TcpStream,
+                _target_host: &str,
+                _target_port: NonZeroU16,
+            ) -> std::io::Result<Box<dyn crate::AsyncDuplexStream>> {
+                unreachable!("routes are never connected")
+            }
+        }
+
+        let transport = PluggableTransportHandle::new(UnusedTransport);
+
+        let direct_provider = TlsRouteProvider {
+            sni: Host::Domain("direct-sni".into()),
+            certs: ROOT_CERTS.clone(),
+            min_protocol_version: None,
+            inner: DirectTcpRouteProvider {
+                dns_hostname: "direct-target".into(),
+                port: TARGET_PORT,
+                override_nagle_algorithm: OverrideNagleAlgorithm::UseSystemDefault,
+            },
+        };
+
+        let provider = DirectOrProxyProvider {
+            mode: DirectOrProxyMode::ProxyOnly(
+                PluggableTransportProxy {
+                    bridge_host: Host::Domain("bridge".into()),
+                    bridge_port: BRIDGE_PORT,
+                    transport: transport.clone(),
+                }
+                .into(),
+            ),
+            inner: direct_provider,
+        };
+
+        let routes = provider.routes(&mut FakeContext::new()).collect_vec();
+
+        let expected_routes = vec![TlsRoute {
+            fragment: TlsRouteFragment {
+                root_certs: ROOT_CERTS.clone(),
+                sni: Host::Domain("direct-sni".into()),
+                alpn: None,
+                min_protocol_version: None,
+                ech_config: None,
+            },
+            inner: DirectOrProxyRoute::Proxy(ConnectionProxyRoute::Pluggable(
+                PluggableTransportRoute {
+                    bridge: TcpRoute {
+                        address: Host::Domain(UnresolvedHost("bridge".into())),
+                        port: BRIDGE_PORT,
+                        override_nagle_algorithm: OverrideNagleAlgorithm::UseSystemDefault,
+                    },
+                    transport,
+                    target_host: "direct-target".into(),
+                    target_port: TARGET_PORT,
+                },
+            )),
+        }];
+        pretty_assertions::assert_eq!(expected_routes, routes);
+
+        let hostnames = routes[0].hostnames().map(|h| &*h.0).collect_vec();
+        assert_eq!(hostnames, ["bridge"]);
+    }
+
     #[test]
     fn direct_then_proxy_preserves_global_ordering() {
         const PROXY_PORT: NonZeroU16 = nonzero!(13u16);
@@ -1019,6 +1119,7 @@ mod test {
                     sni: Host::Domain("direct-sni-1".into()),
                     alpn: None,
                     min_protocol_version: Some(boring_signal::ssl::SslVersion::TLS1_1),
+                    ech_config: None,
                 },
                 inner: TcpRoute {
                     address: UnresolvedHost("direct-target-1".into()),
@@ -1032,6 +1133,7 @@ mod test {
                     sni: Host::Domain("direct-sni-2".into()),
                     alpn: None,
                     min_protocol_version: Some(boring_signal::ssl::SslVersion::TLS1_1),
+                    ech_config: None,
                 },
                 inner: TcpRoute {
                     address: UnresolvedHost("direct-target-2".into()),
@@ -1078,6 +1180,7 @@ mod test {
                             sni: Host::Domain("tls-proxy".into()),
                             alpn: None,
                             min_protocol_version: None,
+                            ech_config: None,
                         },
                     },
                 }),
@@ -1096,6 +1199,7 @@ mod test {
                             sni: Host::Domain("tls-proxy".into()),
                             alpn: None,
                             min_protocol_version: None,
+                            ech_config: None,
                         },
                     },
                 }),
@@ -1162,6 +1266,7 @@ mod test {
                     sni: Host::Domain("direct-sni-1".into()),
                     alpn: None,
                     min_protocol_version: Some(boring_signal::ssl::SslVersion::TLS1_1),
+                    ech_config: None,
                 },
                 inner: TcpRoute {
                     address: UnresolvedHost("chat.signal.org".into()),
@@ -1175,6 +1280,7 @@ mod test {
                     sni: Host::Domain("direct-sni-2".into()),
                     alpn: None,
                     min_protocol_version: Some(boring_signal::ssl::SslVersion::TLS1_1),
+                    ech_config: None,
                 },
                 inner: TcpRoute {
                     address: UnresolvedHost("grpc.chat.signal.org".into()),
@@ -1551,6 +1657,7 @@ mod test {
                 LookupResult {
                     ipv4: vec![],
                     ipv6: vec![*ip],
+                    service_binding: None,
                 },
             )
         }));
@@ -1604,6 +1711,7 @@ mod test {
                 LookupResult {
                     ipv4: vec![],
                     ipv6: vec![*ip],
+                    service_binding: None,
                 },
             )
         }));
@@ -21,6 +21,9 @@ pub use composed::*;
 mod direct_or_proxy;
 pub use direct_or_proxy::*;
 
+mod ech_retry;
+pub use ech_retry::*;
+
 mod interface_monitor;
 pub use interface_monitor::*;
 
@@ -39,6 +42,11 @@ pub use variable_timeout::*;
 mod static_tcp_timeout;
 pub use static_tcp_timeout::*;
 
+#[cfg(feature = "quic")]
+mod tcp_or_quic;
+#[cfg(feature = "quic")]
+pub use tcp_or_quic::*;
+
 /// Establishes a connection to a route over an inner transport.
 pub trait Connector<R, Inner> {
     /// The type of connection returned on success.
@@ -0,0 +1,201 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::future::Future;
+
+use crate::errors::{FailedHandshakeReason, TransportConnectError};
+use crate::route::{Connector, TlsRoute};
+
+/// A [`Connector`] that makes a second attempt when the server rejects Encrypted Client Hello.
+///
+/// The ECH configuration from DNS can be stale, or the server can have turned ECH off. Either
+/// way, the server completes the handshake as the configuration's public name and reports why,
+/// so the client can try again over a fresh connection (see [draft-ietf-tls-esni, Section
+/// 6.1.6](https://datatracker.ietf.org/doc/html/draft-ietf-tls-esni-25#section-6.1.6)):
+///
+/// - if the server sent `retry_configs`, they replace the configuration from DNS;
+/// - if it didn't, the server has securely disabled ECH, and the retry goes without.
+///
+/// Only one retry is made; a second rejection is returned as an error.
+#[derive(Debug, Default)]
+pub struct EchRetryConnector<Inner> {
+    inner_connector: Inner,
+}
+
+impl<I> EchRetryConnector<I> {
+    pub fn new(inner: I) -> Self {
+        Self {
+            inner_connector: inner,
+        }
+    }
+
+    /// Consumes the connector and returns its inner connector.
+    pub fn into_inner(self) -> I {
+        self.inner_connector
+    }
+}
+
+impl<Inner, T> Connector<TlsRoute<T>, ()> for EchRetryConnector<Inner>
+where
+    Inner: Connector<TlsRoute<T>, (), Error = TransportConnectError> + Sync,
+    T: Clone + Send,
+{
+    type Connection = Inner::Connection;
+
+    type Error = TransportConnectError;
+
+    fn connect_over(
+        &self,
+        (): (),
+        route: TlsRoute<T>,
+        log_tag: &str,
+    ) -> impl Future<Output = Result<Self::Connection, Self::Error>> + Send {
+        let retry_route = route.fragment.ech_config.is_some().then(|| route.clone());
+
+        async move {
+            let error = match self.inner_connector.connect_over((), route, log_tag).await {
+                Ok(connection) => return Ok(connection),
+                Err(error) => error,
+            };
+            match (retry_route, error) {
+                (
+                    Some(mut retry_route),
+                    TransportConnectError::SslFailedHandshake(FailedHandshakeReason::EchRejected {
+                        retry_configs,
+                    }),
+                ) => {
+                    if retry_configs.is_some() {
+                        log::info!(
+                            "[{log_tag}] ECH rejected; retrying with the server's new configs"
+                        );
+                    } else {
+                        log::info!("[{log_tag}] ECH disabled by the server; retrying without it");
+                    }
+                    retry_route.fragment.ech_config = retry_configs;
+                    self.inner_connector
+                        .connect_over((), retry_route, log_tag)
+                        .await
+                }
+                (_, error) => Err(error),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use assert_matches::assert_matches;
+    use futures_util::FutureExt as _;
+    use test_case::test_case;
+
+    use super::*;
+    use crate::certs::RootCertificates;
+    use crate::host::Host;
+    use crate::route::connect::testutils::ConnectFn;
+    use crate::route::{EchConfigList, TlsRouteFragment};
+
+    fn route(ech_config: Option<EchConfigList>) -> TlsRoute<()> {
+        TlsRoute {
+            fragment: TlsRouteFragment {
+                root_certs: RootCertificates::Native,
+                sni: Host::Domain("chat.signal.org".into()),
+                alpn: None,
+                min_protocol_version: None,
+                ech_config,
+            },
+            inner: (),
+        }
+    }
+
+    fn rejected(retry_configs: Option<EchConfigList>) -> TransportConnectError {
+        TransportConnectError::SslFailedHandshake(FailedHandshakeReason::EchRejected {
+            retry_configs,
+        })
+    }
+
+    /// Connects with `outcomes` as the results of successive attempts, and returns the result
+    /// along with the ECH configs of the attempts made.
+    fn connect(
+        route: TlsRoute<()>,
+        outcomes: Vec<Result<(), TransportConnectError>>,
+    ) -> (
+        Result<(), TransportConnectError>,
+        Vec<Option<EchConfigList>>,
+    ) {
+        let attempts = Mutex::new(vec![]);
+        let outcomes = Mutex::new(outcomes.into_iter());
+        let connector = EchRetryConnector::new(ConnectFn(|(), route: TlsRoute<()>| {
+            attempts
+                .lock()
+                .expect("not poisoned")
+                .push(route.fragment.ech_config);
+            let outcome = outcomes
+                .lock()
+                .expect("not poisoned")
+                .next()
+                .expect("no more attempts than expected");
+            std::future::ready(outcome)
+        }));
+        let result = connector
+            .connect_over((), route, "test")
+            .now_or_never()
+            .expect("sync");
+        drop(connector);
+        (result, attempts.into_inner().expect("not poisoned"))
+    }
+
+    #[test]
+    fn retries_with_server_configs() {
+        let original = EchConfigList::new(b"stale".as_slice());
+        let fresh = EchConfigList::new(b"fresh".as_slice());
+        let (result, attempts) = connect(
+            route(Some(original.clone())),
+            vec![Err(rejected(Some(fresh.clone()))), Ok(())],
+        );
+        assert_matches!(result, Ok(()));
+        assert_eq!(attempts, [Some(original), Some(fresh)]);
+    }
+
+    #[test]
+    fn retries_without_ech_when_disabled() {
+        let original = EchConfigList::new(b"stale".as_slice());
+        let (result, attempts) = connect(
+            route(Some(original.clone())),
+            vec![Err(rejected(None)), Ok(())],
+        );
+        assert_matches!(result, Ok(()));
+        assert_eq!(attempts, [Some(original), None]);
+    }
+
+    #[test]
+    fn retries_only_once() {
+        let original = EchConfigList::new(b"stale".as_slice());
+        let fresh = EchConfigList::new(b"fresh".as_slice());
+        let (result, attempts) = connect(
+            route(Some(original)),
+            vec![
+                Err(rejected(Some(fresh.clone()))),
+                Err(rejected(Some(fresh))),
+            ],
+        );
+        assert_matches!(
+            result,
+            Err(TransportConnectError::SslFailedHandshake(
+                FailedHandshakeReason::EchRejected { .. }
+            ))
+        );
+        assert_eq!(attempts.len(), 2);
+    }
+
+    #[test_case(Some(EchConfigList::new(b"ech".as_slice())), TransportConnectError::TcpConnectionFailed; "other error")]
+    #[test_case(None, rejected(None); "ech not offered")]
+    fn does_not_retry(ech_config: Option<EchConfigList>, error: TransportConnectError) {
+        let (result, attempts) = connect(route(ech_config), vec![Err(error)]);
+        assert_matches!(result, Err(_));
+        assert_eq!(attempts.len(), 1);
+    }
+}
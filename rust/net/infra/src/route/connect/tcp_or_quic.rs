@@ -0,0 +1,70 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::fmt::Debug;
+use std::future::Future;
+use std::marker::PhantomData;
+
+use derive_where::derive_where;
+use futures_util::TryFutureExt as _;
+use tokio_util::either::Either;
+
+use crate::route::{Connector, TcpOrQuicRoute};
+
+/// A [`Connector`] for [`TcpOrQuicRoute`] that delegates to TCP or QUIC
+/// connectors.
+#[derive_where(Debug; T: Debug, Q: Debug)]
+#[derive_where(Default; T: Default, Q: Default)]
+pub struct TcpOrQuic<T, Q, E> {
+    tcp: T,
+    quic: Q,
+    _error: PhantomData<E>,
+}
+
+impl<T, Q, E> TcpOrQuic<T, Q, E> {
+    pub fn new(tcp: T, quic: Q) -> Self {
+        Self {
+            tcp,
+            quic,
+            _error: PhantomData,
+        }
+    }
+}
+
+/// Establishes a connection over either TCP or QUIC.
+///
+/// Delegates to the respective wrapped connector: [`TcpOrQuic`]'s `tcp` for
+/// [`TcpOrQuicRoute::Tcp`] and `quic` for [`TcpOrQuicRoute::Quic`].
+impl<T, Q, TR, QR, Inner, Err> Connector<TcpOrQuicRoute<TR, QR>, Inner> for TcpOrQuic<T, Q, Err>
+where
+    T: Connector<TR, Inner, Error: Into<Err>>,
+    Q: Connector<QR, Inner, Error: Into<Err>>,
+{
+    type Connection = Either<T::Connection, Q::Connection>;
+
+    type Error = Err;
+
+    fn connect_over(
+        &self,
+        over: Inner,
+        route: TcpOrQuicRoute<TR, QR>,
+        log_tag: &str,
+    ) -> impl Future<Output = Result<Self::Connection, Self::Error>> + Send {
+        match route {
+            TcpOrQuicRoute::Tcp(t) => Either::Left(
+                self.tcp
+                    .connect_over(over, t, log_tag)
+                    .map_ok(Either::Left)
+                    .map_err(Into::into),
+            ),
+            TcpOrQuicRoute::Quic(q) => Either::Right(
+                self.quic
+                    .connect_over(over, q, log_tag)
+                    .map_ok(Either::Right)
+                    .map_err(Into::into),
+            ),
+        }
+    }
+}
@@ -16,9 +16,10 @@ use crate::dns::dns_utils::log_safe_domain;
 use crate::host::Host;
 use crate::route::{
     ConnectionProxyKind, ConnectionProxyRoute, Connector, DEFAULT_HTTPS_PORT, DirectOrProxyRoute,
-    HttpProxyRouteFragment, HttpsProxyRoute, ProxyTarget, ResolveHostnames, ResolvedRoute,
-    SocksRoute, TcpRoute, TlsRoute, TransportRoute, UnresolvedHost, UnresolvedHttpsServiceRoute,
-    UnresolvedTransportRoute, UnresolvedWebsocketServiceRoute, UsesTransport,
+    HttpProxyRouteFragment, HttpsProxyRoute, PluggableTransportRoute, ProxyTarget,
+    ResolveHostnames, ResolvedRoute, SocksRoute, TcpRoute, TlsRoute, TransportRoute,
+    UnresolvedHost, UnresolvedHttpsServiceRoute, UnresolvedTransportRoute,
+    UnresolvedWebsocketServiceRoute, UsesTransport,
 };
 
 /// A type that is not itself loggable but can produce a [`LogSafeDisplay`]
@@ -213,6 +214,11 @@ impl<Transport: UsesTransport<UnresolvedTransportRoute>> DescribeForLog
                     Host::Domain(reflector.target_host.clone()),
                     reflector.target_port,
                 ),
+                ConnectionProxyRoute::Pluggable(PluggableTransportRoute {
+                    target_host,
+                    target_port,
+                    ..
+                }) => (Host::Domain(target_host.clone()), *target_port),
             },
         };
 
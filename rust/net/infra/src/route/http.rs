@@ -154,6 +154,7 @@ impl RouteProvider for DomainFrontRouteProvider {
                             sni: Host::Domain(Arc::clone(sni)),
                             alpn: Some((*http_version).into()),
                             min_protocol_version: None,
+                            ech_config: None,
                         },
                     },
                     fragment: HttpRouteFragment {
@@ -292,6 +293,7 @@ mod test {
                             sni: Host::Domain("direct-host".into()),
                             alpn: Some(Alpn::Http2),
                             min_protocol_version: Some(boring_signal::ssl::SslVersion::TLS1_1),
+                            ech_config: None,
                         },
                         inner: TcpRoute {
                             address: UnresolvedHost("direct-tcp-host".into()),
@@ -313,6 +315,7 @@ mod test {
                             sni: Host::Domain("front-sni-1a".into()),
                             alpn: Some(Alpn::Http1_1),
                             min_protocol_version: None,
+                            ech_config: None,
                         },
                         inner: TcpRoute {
                             address: UnresolvedHost("front-sni-1a".into()),
@@ -334,6 +337,7 @@ mod test {
                             sni: Host::Domain("front-sni-1b".into()),
                             alpn: Some(Alpn::Http1_1),
                             min_protocol_version: None,
+                            ech_config: None,
                         },
                         inner: TcpRoute {
                             address: UnresolvedHost("front-sni-1b".into()),
@@ -355,6 +359,7 @@ mod test {
                             sni: Host::Domain("front-sni-2a".into()),
                             alpn: Some(Alpn::Http1_1),
                             min_protocol_version: None,
+                            ech_config: None,
                         },
                         inner: TcpRoute {
                             address: UnresolvedHost("front-sni-2a".into()),
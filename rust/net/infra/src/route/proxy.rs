@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use std::net::Ipv4Addr;
 use std::num::NonZeroU16;
 use std::sync::Arc;
 
@@ -19,6 +20,7 @@ use crate::route::{
     RouteProvider, RouteProviderContext, SimpleRoute, TcpRoute, TlsRoute, TlsRouteFragment,
     UnresolvedHost, WebSocketRoute, WebSocketRouteFragment,
 };
+use crate::tcp_ssl::proxy::pluggable::{LocalSocksTransport, PluggableTransportHandle};
 use crate::tcp_ssl::proxy::socks;
 use crate::{Alpn, RouteType};
 
@@ -62,8 +64,21 @@ pub struct ReflectorProxyRoute<Addr> {
     pub target_port: NonZeroU16,
 }
 
+/// Route for connecting via an app-supplied
+/// [`PluggableTransport`](crate::tcp_ssl::proxy::pluggable::PluggableTransport).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PluggableTransportRoute<Addr> {
+    /// The bridge that the transport will perform its handshake with.
+    pub bridge: TcpRoute<Addr>,
+    pub transport: PluggableTransportHandle,
+    /// The target to pass to the bridge; it will resolve the name itself.
+    pub target_host: Arc<str>,
+    pub target_port: NonZeroU16,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash, strum::EnumDiscriminants)]
 #[strum_discriminants(name(ConnectionProxyKind))]
+#[strum_discriminants(derive(strum::IntoStaticStr))]
 pub enum ConnectionProxyRoute<Addr> {
     Tls {
         proxy: TlsRoute<TcpRoute<Addr>>,
@@ -77,6 +92,7 @@ pub enum ConnectionProxyRoute<Addr> {
     Https(HttpsProxyRoute<Addr>),
     // Boxed because it's much larger than the other variants.
     Reflector(Box<ReflectorProxyRoute<Addr>>),
+    Pluggable(PluggableTransportRoute<Addr>),
 }
 
 /// Target address for proxy protocols that support remote resolution.
@@ -166,6 +182,28 @@ pub struct HttpProxy {
     pub resolve_hostname_locally: bool,
 }
 
+/// A bridge reached through an obfuscating transport provided by the app.
+#[derive(Debug, Clone)]
+pub struct PluggableTransportProxy {
+    pub bridge_host: Host<Arc<str>>,
+    pub bridge_port: NonZeroU16,
+    pub transport: PluggableTransportHandle,
+}
+
+impl PluggableTransportProxy {
+    /// A bridge reached through a Pluggable Transports client that listens for
+    /// SOCKS5 connections on `local_port`.
+    ///
+    /// See [`LocalSocksTransport`].
+    pub fn via_local_socks(local_port: NonZeroU16, transport: LocalSocksTransport) -> Self {
+        Self {
+            bridge_host: Host::Ip(Ipv4Addr::LOCALHOST.into()),
+            bridge_port: local_port,
+            transport: PluggableTransportHandle::new(transport),
+        }
+    }
+}
+
 #[derive(Debug, Clone, derive_more::From, strum::IntoStaticStr)]
 pub enum ConnectionProxyConfig {
     Tls(TlsProxy),
@@ -177,6 +215,7 @@ pub enum ConnectionProxyConfig {
     /// slice from the surrounding environment/domain config so prod and staging
     /// can't be mispaired.
     Reflector(&'static [ReflectorProviderConfig]),
+    Pluggable(PluggableTransportProxy),
 }
 
 #[derive(Debug)]
@@ -309,7 +348,7 @@ impl ConnectionProxyConfig {
             Self::Tls(_) => true,
             #[cfg(feature = "dev-util")]
             Self::Tcp(_) => true,
-            Self::Socks(_) | Self::Http(_) | Self::Reflector(_) => false,
+            Self::Socks(_) | Self::Http(_) | Self::Reflector(_) | Self::Pluggable(_) => false,
         }
     }
 }
@@ -424,6 +463,9 @@ impl ConnectionProxyConfig {
                     .map(|provider| ConcreteProxyConfig::Reflector(provider.pick_sni(sni_index)))
                     .collect()
             }
+            Self::Pluggable(pluggable_proxy) => {
+                vec![ConcreteProxyConfig::Pluggable(pluggable_proxy)]
+            }
         }
     }
 }
@@ -461,6 +503,7 @@ enum ConcreteProxyConfig<'a> {
     Socks(&'a SocksProxy),
     Http(&'a HttpProxy),
     Reflector(ConcreteReflectorRouteProvider),
+    Pluggable(&'a PluggableTransportProxy),
 }
 
 impl ConcreteProxyConfig<'_> {
@@ -492,6 +535,7 @@ impl ConcreteProxyConfig<'_> {
                         sni: proxy_host.clone(),
                         alpn: None,
                         min_protocol_version: None,
+                        ech_config: None,
                     },
                 },
             },
@@ -571,6 +615,7 @@ impl ConcreteProxyConfig<'_> {
                                     sni: proxy_host.clone(),
                                     alpn: Some(Alpn::Http1_1),
                                     min_protocol_version: None,
+                                    ech_config: None,
                                 },
                             }),
                             None => Either::Right(proxy_tcp),
@@ -605,6 +650,7 @@ impl ConcreteProxyConfig<'_> {
                                 sni: Host::Domain((*sni).into()),
                                 alpn: Some(Alpn::Http1_1),
                                 min_protocol_version: None,
+                                ech_config: None,
                             },
                             inner: TcpRoute {
                                 address: Host::Domain(UnresolvedHost((*sni).into())),
@@ -617,6 +663,28 @@ impl ConcreteProxyConfig<'_> {
                 target_host: tcp_route.address.0,
                 target_port: tcp_route.port,
             })),
+
+            Self::Pluggable(PluggableTransportProxy {
+                bridge_host,
+                bridge_port,
+                transport,
+            }) => {
+                let TcpRoute {
+                    address,
+                    port,
+                    override_nagle_algorithm,
+                } = tcp_route;
+                ConnectionProxyRoute::Pluggable(PluggableTransportRoute {
+                    bridge: TcpRoute {
+                        address: proxy_host_as_unresolved(bridge_host),
+                        port: *bridge_port,
+                        override_nagle_algorithm,
+                    },
+                    transport: transport.clone(),
+                    target_host: address.0,
+                    target_port: port,
+                })
+            }
         }
     }
 }
@@ -0,0 +1,277 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::num::NonZeroU16;
+use std::sync::Arc;
+
+use crate::Alpn;
+use crate::certs::RootCertificates;
+use crate::dns::lookup_result::ServiceBinding;
+use crate::host::Host;
+use crate::route::{
+    EchConfigList, HttpRouteFragment, HttpsTlsRoute, RouteProvider, RouteProviderContext,
+    SimpleRoute, TcpRoute, TlsRoute, UdpRoute, UnresolvedHost,
+};
+
+/// Handshake parameters for a QUIC connection.
+///
+/// QUIC always runs TLS 1.3, so unlike [`TlsRouteFragment`](super::TlsRouteFragment) there is no
+/// minimum version to pick, and an application protocol must always be negotiated.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct QuicRouteFragment {
+    pub root_certs: RootCertificates,
+    pub sni: Host<Arc<str>>,
+    pub alpn: Alpn,
+    /// The server's published Encrypted Client Hello configuration, if any.
+    ///
+    /// Our QUIC stack can't send ECH, so a route with this set is refused rather than revealing
+    /// the SNI that a TCP route would have kept encrypted.
+    pub ech_config: Option<EchConfigList>,
+}
+
+impl QuicRouteFragment {
+    /// Applies the hints from an HTTPS record for `hostname`, if it is this fragment's SNI.
+    ///
+    /// See [`TlsRouteFragment::apply_service_binding`](super::TlsRouteFragment::apply_service_binding).
+    pub(crate) fn apply_service_binding(
+        &mut self,
+        hostname: &str,
+        port: NonZeroU16,
+        binding: &ServiceBinding,
+    ) {
+        let Host::Domain(sni) = &self.sni else {
+            return;
+        };
+        if !sni.eq_ignore_ascii_case(hostname)
+            || !binding.applies_to(hostname, port.get())
+            || !binding.supports_alpn(self.alpn)
+        {
+            return;
+        }
+        if let Some(ech_config) = &binding.ech_config_list {
+            self.ech_config = Some(ech_config.clone());
+        }
+    }
+}
+
+pub type QuicRoute<Addr> = SimpleRoute<QuicRouteFragment, UdpRoute<Addr>>;
+
+/// HTTP-level parameters for an HTTP/3 session.
+///
+/// This is separate from [`HttpRouteFragment`] so that HTTP/3 can only be requested over a
+/// [`QuicRoute`], and HTTP/1.1 and HTTP/2 only over TCP.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Http3RouteFragment {
+    pub host_header: Arc<str>,
+    pub path_prefix: Arc<str>,
+}
+
+pub type Http3Route<T> = SimpleRoute<Http3RouteFragment, QuicRoute<T>>;
+
+/// A route that runs over either TCP or QUIC.
+///
+/// Lets a single [`RouteProvider`] hand out both kinds of route, so that
+/// [`connect`](super::connect) can race them against each other.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TcpOrQuicRoute<T, Q> {
+    Tcp(T),
+    Quic(Q),
+}
+
+/// [`RouteProvider`] that offers HTTP/3 ahead of each direct HTTPS route from
+/// an inner provider.
+///
+/// Each QUIC route targets the same host and port (over UDP) as the TCP route
+/// it precedes. Since routes are attempted in order, HTTP/3 gets a head start,
+/// and the TCP route is tried if the QUIC handshake fails or stalls (which is
+/// common on networks that block UDP). Domain-fronted routes are passed through
+/// unchanged; fronts aren't expected to accept QUIC.
+#[derive(Debug)]
+pub struct QuicRouteProvider<P> {
+    pub(crate) inner: P,
+}
+
+impl<P> QuicRouteProvider<P> {
+    pub fn new(inner: P) -> Self {
+        Self { inner }
+    }
+}
+
+type DirectHttpsRoute = HttpsTlsRoute<TlsRoute<TcpRoute<UnresolvedHost>>>;
+
+impl<P> RouteProvider for QuicRouteProvider<P>
+where
+    P: RouteProvider<Route = DirectHttpsRoute>,
+{
+    type Route = TcpOrQuicRoute<DirectHttpsRoute, Http3Route<UnresolvedHost>>;
+
+    fn routes<'s, C: RouteProviderContext>(
+        &'s self,
+        context: &mut C,
+    ) -> impl Iterator<Item = Self::Route> + use<'s, C, P> {
+        self.inner.routes(context).flat_map(|route| {
+            let quic = route
+                .fragment
+                .front_name
+                .is_none()
+                .then(|| TcpOrQuicRoute::Quic(quic_equivalent(&route)));
+            quic.into_iter().chain([TcpOrQuicRoute::Tcp(route)])
+        })
+    }
+}
+
+/// Produces an HTTP/3 route to the same destination as `route`.
+fn quic_equivalent(route: &DirectHttpsRoute) -> Http3Route<UnresolvedHost> {
+    let HttpsTlsRoute {
+        fragment:
+            HttpRouteFragment {
+                host_header,
+                path_prefix,
+                http_version: _,
+                front_name: _,
+            },
+        inner: TlsRoute {
+            fragment: tls_fragment,
+            inner: tcp,
+        },
+    } = route;
+
+    Http3Route {
+        fragment: Http3RouteFragment {
+            host_header: host_header.clone(),
+            path_prefix: path_prefix.clone(),
+        },
+        inner: QuicRoute {
+            fragment: QuicRouteFragment {
+                root_certs: tls_fragment.root_certs.clone(),
+                sni: tls_fragment.sni.clone(),
+                alpn: Alpn::Http3,
+                ech_config: tls_fragment.ech_config.clone(),
+            },
+            inner: UdpRoute {
+                address: tcp.address.clone(),
+                port: tcp.port,
+            },
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::num::NonZeroU16;
+
+    use itertools::Itertools as _;
+    use nonzero_ext::nonzero;
+
+    use super::*;
+    use crate::OverrideNagleAlgorithm;
+    use crate::route::testutils::FakeContext;
+    use crate::route::{
+        DEFAULT_HTTPS_PORT, DirectTcpRouteProvider, DomainFrontConfig, DomainFrontRouteProvider,
+        HttpVersion, HttpsProvider, TlsRouteFragment, TlsRouteProvider,
+    };
+
+    #[test]
+    fn quic_provider_route_order() {
+        const DIRECT_PORT: NonZeroU16 = nonzero!(1234u16);
+        let provider = QuicRouteProvider::new(HttpsProvider {
+            direct_host_header: "direct-host".into(),
+            direct_http_version: HttpVersion::Http2,
+            domain_front: DomainFrontRouteProvider {
+                fronts: vec![DomainFrontConfig {
+                    http_host: "front-host".into(),
+                    sni_list: vec!["front-sni".into()],
+                    root_certs: RootCertificates::Native,
+                    path_prefix: "/prefix".into(),
+                    front_name: "front",
+                    return_routes_with_all_snis: true,
+                }],
+                http_version: HttpVersion::Http1_1,
+                override_nagle_algorithm: OverrideNagleAlgorithm::UseSystemDefault,
+            },
+            inner: TlsRouteProvider {
+                sni: Host::Domain("direct-host".into()),
+                certs: RootCertificates::Native,
+                min_protocol_version: None,
+                inner: DirectTcpRouteProvider {
+                    dns_hostname: "direct-target".into(),
+                    port: DIRECT_PORT,
+                    override_nagle_algorithm: OverrideNagleAlgorithm::UseSystemDefault,
+                },
+            },
+        });
+
+        let routes = provider.routes(&mut FakeContext::new()).collect_vec();
+
+        let direct_http = HttpRouteFragment {
+            host_header: "direct-host".into(),
+            path_prefix: "".into(),
+            http_version: Some(HttpVersion::Http2),
+            front_name: None,
+        };
+        assert_eq!(
+            routes,
+            vec![
+                TcpOrQuicRoute::Quic(Http3Route {
+                    fragment: Http3RouteFragment {
+                        host_header: "direct-host".into(),
+                        path_prefix: "".into(),
+                    },
+                    inner: QuicRoute {
+                        fragment: QuicRouteFragment {
+                            root_certs: RootCertificates::Native,
+                            sni: Host::Domain("direct-host".into()),
+                            alpn: Alpn::Http3,
+                            ech_config: None,
+                        },
+                        inner: UdpRoute {
+                            address: UnresolvedHost::from(Arc::from("direct-target")),
+                            port: DIRECT_PORT,
+                        },
+                    },
+                }),
+                TcpOrQuicRoute::Tcp(HttpsTlsRoute {
+                    fragment: direct_http,
+                    inner: TlsRoute {
+                        fragment: TlsRouteFragment {
+                            root_certs: RootCertificates::Native,
+                            sni: Host::Domain("direct-host".into()),
+                            alpn: Some(Alpn::Http2),
+                            min_protocol_version: None,
+                            ech_config: None,
+                        },
+                        inner: TcpRoute {
+                            address: UnresolvedHost::from(Arc::from("direct-target")),
+                            port: DIRECT_PORT,
+                            override_nagle_algorithm: OverrideNagleAlgorithm::UseSystemDefault,
+                        },
+                    },
+                }),
+                TcpOrQuicRoute::Tcp(HttpsTlsRoute {
+                    fragment: HttpRouteFragment {
+                        host_header: "front-host".into(),
+                        path_prefix: "/prefix".into(),
+                        http_version: Some(HttpVersion::Http1_1),
+                        front_name: Some("front"),
+                    },
+                    inner: TlsRoute {
+                        fragment: TlsRouteFragment {
+                            root_certs: RootCertificates::Native,
+                            sni: Host::Domain("front-sni".into()),
+                            alpn: Some(Alpn::Http1_1),
+                            min_protocol_version: None,
+                            ech_config: None,
+                        },
+                        inner: TcpRoute {
+                            address: UnresolvedHost::from(Arc::from("front-sni")),
+                            port: DEFAULT_HTTPS_PORT,
+                            override_nagle_algorithm: OverrideNagleAlgorithm::UseSystemDefault,
+                        },
+                    },
+                }),
+            ]
+        );
+    }
+}
@@ -6,20 +6,23 @@
 use std::fmt::Debug;
 use std::future::Future;
 use std::net::IpAddr;
+use std::num::NonZeroU16;
 use std::sync::Arc;
 
 use either::Either;
 use futures_util::FutureExt as _;
 use itertools::Itertools;
 
-use crate::dns::lookup_result::LookupResult;
+use crate::dns::lookup_result::{LookupResult, ServiceBinding};
 use crate::dns::{DnsError, DnsResolver};
 use crate::host::Host;
 use crate::route::{
     ConnectionProxyRoute, DirectOrProxyRoute, HttpProxyRouteFragment, HttpsProxyRoute,
-    HttpsTlsRoute, ProxyTarget, ReflectorProxyRoute, SocksRoute, TcpRoute, TlsRoute, UdpRoute,
-    UnresolvedHost, UsePreconnect, WebSocketRoute,
+    HttpsTlsRoute, PluggableTransportRoute, ProxyTarget, ReflectorProxyRoute, SocksRoute, TcpRoute,
+    TlsRoute, UdpRoute, UnresolvedHost, UsePreconnect, WebSocketRoute,
 };
+#[cfg(feature = "quic")]
+use crate::route::{Http3Route, QuicRoute, TcpOrQuicRoute};
 
 /// A route with hostnames that can be resolved.
 ///
@@ -39,6 +42,19 @@ pub trait ResolveHostnames {
     /// The provided `lookup` callback must be able to resolve every hostname
     /// that is yielded by `self.hostnames()`.
     fn resolve(self, lookup: impl FnMut(&str) -> IpAddr) -> Self::Resolved;
+
+    /// Applies the hints published in the HTTPS record for `hostname`.
+    ///
+    /// Only TLS routes make use of these. The default implementation ignores them.
+    fn apply_service_binding(&mut self, _hostname: &str, _binding: &ServiceBinding) {}
+
+    /// The port this route connects to `hostname` on, if it connects to it directly.
+    ///
+    /// Used to check that an HTTPS record for `hostname` describes the endpoint a TLS route is
+    /// going to talk to. The default implementation returns `None`.
+    fn direct_port(&self, _hostname: &str) -> Option<NonZeroU16> {
+        None
+    }
 }
 
 /// A route that has had all its hostnames resolved to IP addresses.
@@ -87,7 +103,7 @@ type ResolveRouteIter<R> = std::iter::Chain<
 /// attempted.
 pub async fn resolve_route<R: ResolveHostnames + Clone + 'static>(
     dns: &impl Resolver,
-    route: R,
+    mut route: R,
 ) -> Result<ResolveRouteIter<R::Resolved>, (Arc<str>, DnsError)> {
     let to_resolve = route.hostnames().map(|UnresolvedHost(hostname)| {
         dns.lookup_ip(hostname).map(|result| match result {
@@ -98,6 +114,12 @@ pub async fn resolve_route<R: ResolveHostnames + Clone + 'static>(
 
     let resolved = futures_util::future::try_join_all(to_resolve).await?;
 
+    for (hostname, lookup) in &resolved {
+        if let Some(binding) = lookup.service_binding() {
+            route.apply_service_binding(hostname, binding);
+        }
+    }
+
     let resolutions = resolved
         .into_iter()
         .map(|(hostname, result)| std::iter::repeat(hostname).zip(result))
@@ -201,18 +223,94 @@ macro_rules! impl_resolve_hostnames {
                     $($other_fields)*
                 }
             }
+
+            fn apply_service_binding(&mut self, hostname: &str, binding: &ServiceBinding) {
+                self.$delegate_field.apply_service_binding(hostname, binding)
+            }
+
+            fn direct_port(&self, hostname: &str) -> Option<NonZeroU16> {
+                impl_resolve_hostnames!(@direct_port self, hostname, $delegate_field, $($other_fields)*)
+            }
         }
     };
+    // Routes with a port connect directly to their address.
+    (@direct_port $self:ident, $hostname:ident, address, port, $($rest:tt)*) => {
+        $self
+            .address
+            .hostnames()
+            .any(|UnresolvedHost(h)| **h == *$hostname)
+            .then_some($self.port)
+    };
+    (@direct_port $self:ident, $hostname:ident, $delegate_field:ident, $($rest:tt)*) => {
+        $self.$delegate_field.direct_port($hostname)
+    };
     ($typ:ident, $delegate_field:ident) => {
         impl_resolve_hostnames!($typ, $delegate_field,);
     }
 }
 
 impl_resolve_hostnames!(TcpRoute, address, port, override_nagle_algorithm);
-impl_resolve_hostnames!(TlsRoute, inner, fragment);
 impl_resolve_hostnames!(HttpsTlsRoute, inner, fragment);
 impl_resolve_hostnames!(WebSocketRoute, inner, fragment);
 impl_resolve_hostnames!(UsePreconnect, inner, should);
+impl_resolve_hostnames!(UdpRoute, address, port,);
+#[cfg(feature = "quic")]
+impl_resolve_hostnames!(Http3Route, inner, fragment);
+
+impl<A: ResolveHostnames> ResolveHostnames for TlsRoute<A> {
+    type Resolved = TlsRoute<A::Resolved>;
+
+    fn hostnames(&self) -> impl Iterator<Item = &UnresolvedHost> {
+        self.inner.hostnames()
+    }
+
+    fn resolve(self, lookup: impl FnMut(&str) -> IpAddr) -> Self::Resolved {
+        let Self { inner, fragment } = self;
+        TlsRoute {
+            inner: inner.resolve(lookup),
+            fragment,
+        }
+    }
+
+    fn apply_service_binding(&mut self, hostname: &str, binding: &ServiceBinding) {
+        if let Some(port) = self.inner.direct_port(hostname) {
+            self.fragment.apply_service_binding(hostname, port, binding);
+        }
+        self.inner.apply_service_binding(hostname, binding);
+    }
+
+    fn direct_port(&self, hostname: &str) -> Option<NonZeroU16> {
+        self.inner.direct_port(hostname)
+    }
+}
+
+#[cfg(feature = "quic")]
+impl<A: ResolveHostnames> ResolveHostnames for QuicRoute<A> {
+    type Resolved = QuicRoute<A::Resolved>;
+
+    fn hostnames(&self) -> impl Iterator<Item = &UnresolvedHost> {
+        self.inner.hostnames()
+    }
+
+    fn resolve(self, lookup: impl FnMut(&str) -> IpAddr) -> Self::Resolved {
+        let Self { inner, fragment } = self;
+        QuicRoute {
+            inner: inner.resolve(lookup),
+            fragment,
+        }
+    }
+
+    fn apply_service_binding(&mut self, hostname: &str, binding: &ServiceBinding) {
+        if let Some(port) = self.inner.direct_port(hostname) {
+            self.fragment.apply_service_binding(hostname, port, binding);
+        }
+        self.inner.apply_service_binding(hostname, binding);
+    }
+
+    fn direct_port(&self, hostname: &str) -> Option<NonZeroU16> {
+        self.inner.direct_port(hostname)
+    }
+}
 
 impl<D: ResolveHostnames, P: ResolveHostnames> ResolveHostnames for DirectOrProxyRoute<D, P> {
     type Resolved = DirectOrProxyRoute<D::Resolved, P::Resolved>;
@@ -230,6 +328,54 @@ impl<D: ResolveHostnames, P: ResolveHostnames> ResolveHostnames for DirectOrProx
             DirectOrProxyRoute::Proxy(p) => DirectOrProxyRoute::Proxy(p.resolve(lookup)),
         }
     }
+
+    fn apply_service_binding(&mut self, hostname: &str, binding: &ServiceBinding) {
+        match self {
+            DirectOrProxyRoute::Direct(d) => d.apply_service_binding(hostname, binding),
+            DirectOrProxyRoute::Proxy(p) => p.apply_service_binding(hostname, binding),
+        }
+    }
+
+    fn direct_port(&self, hostname: &str) -> Option<NonZeroU16> {
+        match self {
+            DirectOrProxyRoute::Direct(d) => d.direct_port(hostname),
+            // A proxied connection's endpoint is the proxy's target, not the proxy itself.
+            DirectOrProxyRoute::Proxy(_) => None,
+        }
+    }
+}
+
+#[cfg(feature = "quic")]
+impl<T: ResolveHostnames, Q: ResolveHostnames> ResolveHostnames for TcpOrQuicRoute<T, Q> {
+    type Resolved = TcpOrQuicRoute<T::Resolved, Q::Resolved>;
+
+    fn hostnames(&self) -> impl Iterator<Item = &UnresolvedHost> {
+        match self {
+            TcpOrQuicRoute::Tcp(t) => Either::Left(t.hostnames()),
+            TcpOrQuicRoute::Quic(q) => Either::Right(q.hostnames()),
+        }
+    }
+
+    fn resolve(self, lookup: impl FnMut(&str) -> IpAddr) -> Self::Resolved {
+        match self {
+            TcpOrQuicRoute::Tcp(t) => TcpOrQuicRoute::Tcp(t.resolve(lookup)),
+            TcpOrQuicRoute::Quic(q) => TcpOrQuicRoute::Quic(q.resolve(lookup)),
+        }
+    }
+
+    fn apply_service_binding(&mut self, hostname: &str, binding: &ServiceBinding) {
+        match self {
+            TcpOrQuicRoute::Tcp(t) => t.apply_service_binding(hostname, binding),
+            TcpOrQuicRoute::Quic(q) => q.apply_service_binding(hostname, binding),
+        }
+    }
+
+    fn direct_port(&self, hostname: &str) -> Option<NonZeroU16> {
+        match self {
+            TcpOrQuicRoute::Tcp(t) => t.direct_port(hostname),
+            TcpOrQuicRoute::Quic(q) => q.direct_port(hostname),
+        }
+    }
 }
 
 impl<A: ResolveHostnames> ResolveHostnames for ConnectionProxyRoute<A> {
@@ -245,11 +391,14 @@ impl<A: ResolveHostnames> ResolveHostnames for ConnectionProxyRoute<A> {
             }
             #[cfg(feature = "dev-util")]
             Self::Tcp { proxy } => Either::Left(Either::Right(proxy.hostnames())),
-            Self::Socks(socks) => Either::Right(Either::Right(socks.hostnames())),
+            Self::Socks(socks) => Either::Right(Either::Right(Either::Left(socks.hostnames()))),
             Self::Https(http) => Either::Right(Either::Left(Either::Left(http.hostnames()))),
             Self::Reflector(reflector) => {
                 Either::Right(Either::Left(Either::Right(reflector.outer.hostnames())))
             }
+            Self::Pluggable(pluggable) => {
+                Either::Right(Either::Right(Either::Right(pluggable.hostnames())))
+            }
         }
     }
 
@@ -278,6 +427,9 @@ impl<A: ResolveHostnames> ResolveHostnames for ConnectionProxyRoute<A> {
                     target_port,
                 }))
             }
+            ConnectionProxyRoute::Pluggable(pluggable) => {
+                ConnectionProxyRoute::Pluggable(pluggable.resolve(lookup))
+            }
         }
     }
 }
@@ -358,6 +510,35 @@ impl<A: ResolveHostnames> ResolveHostnames for SocksRoute<A> {
     }
 }
 
+impl<A: ResolveHostnames> ResolveHostnames for PluggableTransportRoute<A> {
+    type Resolved = PluggableTransportRoute<A::Resolved>;
+
+    fn hostnames(&self) -> impl Iterator<Item = &UnresolvedHost> {
+        let Self {
+            bridge,
+            transport: _,
+            target_host: _,
+            target_port: _,
+        } = self;
+        bridge.hostnames()
+    }
+
+    fn resolve(self, lookup: impl FnMut(&str) -> IpAddr) -> Self::Resolved {
+        let Self {
+            bridge,
+            transport,
+            target_host,
+            target_port,
+        } = self;
+        PluggableTransportRoute {
+            bridge: bridge.resolve(lookup),
+            transport,
+            target_host,
+            target_port,
+        }
+    }
+}
+
 impl<A: ResolveHostnames> ProxyTarget<A> {
     fn locally_resolved_hostnames(&self) -> impl Iterator<Item = &UnresolvedHost> {
         match self {
@@ -404,6 +585,20 @@ impl_resolved_route!(HttpsProxyRoute, inner);
 impl_resolved_route!(WebSocketRoute, inner);
 impl_resolved_route!(UsePreconnect, inner);
 impl_resolved_route!(UdpRoute, address);
+#[cfg(feature = "quic")]
+impl_resolved_route!(QuicRoute, inner);
+#[cfg(feature = "quic")]
+impl_resolved_route!(Http3Route, inner);
+
+#[cfg(feature = "quic")]
+impl<T: ResolvedRoute, Q: ResolvedRoute> ResolvedRoute for TcpOrQuicRoute<T, Q> {
+    fn immediate_target(&self) -> &IpAddr {
+        match self {
+            TcpOrQuicRoute::Tcp(t) => t.immediate_target(),
+            TcpOrQuicRoute::Quic(q) => q.immediate_target(),
+        }
+    }
+}
 
 impl<D: ResolvedRoute, P: ResolvedRoute> ResolvedRoute for DirectOrProxyRoute<D, P> {
     fn immediate_target(&self) -> &IpAddr {
@@ -423,6 +618,7 @@ impl<A: ResolvedRoute> ResolvedRoute for ConnectionProxyRoute<A> {
             ConnectionProxyRoute::Socks(proxy) => proxy.immediate_target(),
             ConnectionProxyRoute::Https(proxy) => proxy.immediate_target(),
             ConnectionProxyRoute::Reflector(proxy) => proxy.outer.immediate_target(),
+            ConnectionProxyRoute::Pluggable(proxy) => proxy.bridge.immediate_target(),
         }
     }
 }
@@ -520,15 +716,15 @@ mod test {
     use nonzero_ext::nonzero;
 
     use super::*;
-    use crate::OverrideNagleAlgorithm;
     use crate::certs::RootCertificates;
     use crate::host::Host;
     use crate::route::resolve::testutils::{FakeResolver, FakeResponder};
     use crate::route::{
-        DirectOrProxyRoute, HttpRouteFragment, SocksRoute, TlsRouteFragment,
+        DirectOrProxyRoute, EchConfigList, HttpRouteFragment, SocksRoute, TlsRouteFragment,
         UnresolvedHttpsServiceRoute,
     };
     use crate::tcp_ssl::proxy::socks;
+    use crate::{Alpn, OverrideNagleAlgorithm};
 
     const PROXY_PORT: NonZeroU16 = nonzero!(444u16);
     const TARGET_PORT: NonZeroU16 = nonzero!(888u16);
@@ -622,6 +818,7 @@ mod test {
             .respond(Ok(LookupResult {
                 ipv4: vec![],
                 ipv6: vec![ip_addr!(v6, "3fff::11")],
+                service_binding: None,
             }));
         responders
             .remove("host-3")
@@ -629,6 +826,7 @@ mod test {
             .respond(Ok(LookupResult {
                 ipv4: vec![ip_addr!(v4, "192.0.2.55")],
                 ipv6: vec![ip_addr!(v6, "3fff::22")],
+                service_binding: None,
             }));
 
         let () = tokio::select! {
@@ -643,6 +841,7 @@ mod test {
             .respond(Ok(LookupResult {
                 ipv4: vec![],
                 ipv6: vec![ip_addr!(v6, "3fff::33")],
+                service_binding: None,
             }));
         let result = resolve.await.expect("finished");
 
@@ -680,6 +879,7 @@ mod test {
                 LookupResult {
                     ipv4: vec![ip_addr!(v4, "192.0.2.100")],
                     ipv6: vec![ip_addr!(v6, "3fff::ffff")],
+                    service_binding: None,
                 },
             ),
             (
@@ -687,6 +887,7 @@ mod test {
                 LookupResult {
                     ipv4: vec![ip_addr!(v4, "192.0.2.1"), ip_addr!(v4, "192.0.2.2")],
                     ipv6: vec![ip_addr!(v6, "3fff::1234")],
+                    service_binding: None,
                 },
             ),
         ]);
@@ -703,6 +904,7 @@ mod test {
             sni: Host::Domain("target-domain".into()),
             alpn: None,
             min_protocol_version: None,
+            ech_config: None,
         };
 
         fn socks_route<A>(proxy: A, target: A) -> ConnectionProxyRoute<A> {
@@ -805,4 +1007,159 @@ mod test {
 
         pretty_assertions::assert_eq!(resolved, expected_routes);
     }
+
+    #[test]
+    fn service_binding_applied_to_matching_tls_routes() {
+        let ech_config = EchConfigList::new(b"ech config".as_slice());
+        let binding = ServiceBinding {
+            priority: 1,
+            target_name: None,
+            port: Some(TARGET_PORT.get()),
+            alpn_ids: vec![Alpn::Http2.encoded().into()],
+            no_default_alpn: true,
+            ech_config_list: Some(ech_config.clone()),
+        };
+        let dns = HashMap::from([(
+            "target-domain",
+            LookupResult::new(vec![ip_addr!(v4, "192.0.2.1")], vec![])
+                .with_service_binding(Some(binding)),
+        )]);
+
+        let tls_route_on_port = |sni: &str, alpn, port| TlsRoute {
+            inner: TcpRoute {
+                address: UnresolvedHost("target-domain".into()),
+                port,
+                override_nagle_algorithm: OverrideNagleAlgorithm::UseSystemDefault,
+            },
+            fragment: TlsRouteFragment {
+                root_certs: RootCertificates::Native,
+                sni: Host::Domain(sni.into()),
+                alpn: Some(alpn),
+                min_protocol_version: None,
+                ech_config: None,
+            },
+        };
+        let tls_route = |sni, alpn| tls_route_on_port(sni, alpn, TARGET_PORT);
+
+        let resolve = |route| {
+            resolve_route(&dns, route)
+                .now_or_never()
+                .expect("all resolution is static")
+                .expect("all hostnames are resolvable")
+                .exactly_one()
+                .expect("one address")
+        };
+
+        let resolved = resolve(tls_route("target-domain", Alpn::Http2));
+        assert_eq!(resolved.fragment.ech_config, Some(ech_config));
+
+        // The record doesn't apply to a different SNI...
+        let resolved = resolve(tls_route("other-domain", Alpn::Http2));
+        assert_eq!(resolved.fragment.ech_config, None);
+
+        // ...or to an ALPN the record doesn't support...
+        let resolved = resolve(tls_route("target-domain", Alpn::Http1_1));
+        assert_eq!(resolved.fragment.ech_config, None);
+
+        // ...or to a different port.
+        let resolved = resolve(tls_route_on_port(
+            "target-domain",
+            Alpn::Http2,
+            nonzero!(443u16),
+        ));
+        assert_eq!(resolved.fragment.ech_config, None);
+    }
+
+    #[test]
+    fn service_binding_not_applied_through_proxy() {
+        let binding = ServiceBinding {
+            priority: 1,
+            port: Some(TARGET_PORT.get()),
+            ech_config_list: Some(EchConfigList::new(b"ech config".as_slice())),
+            ..Default::default()
+        };
+        let dns = HashMap::from([(
+            "target-domain",
+            LookupResult::new(vec![ip_addr!(v4, "192.0.2.1")], vec![])
+                .with_service_binding(Some(binding)),
+        )]);
+
+        // The proxy happens to have the same name as the TLS server, but the record describes
+        // the proxy's endpoint, not wherever it forwards to.
+        let route = TlsRoute {
+            inner: DirectOrProxyRoute::<TcpRoute<Host<UnresolvedHost>>, _>::Proxy(
+                ConnectionProxyRoute::Socks(SocksRoute {
+                    proxy: TcpRoute {
+                        address: Host::Domain(UnresolvedHost("target-domain".into())),
+                        port: TARGET_PORT,
+                        override_nagle_algorithm: OverrideNagleAlgorithm::UseSystemDefault,
+                    },
+                    target_addr: ProxyTarget::ResolvedRemotely {
+                        name: "target-domain".into(),
+                    },
+                    target_port: TARGET_PORT,
+                    protocol: socks::Protocol::Socks5 {
+                        username_password: None,
+                    },
+                }),
+            ),
+            fragment: TlsRouteFragment {
+                root_certs: RootCertificates::Native,
+                sni: Host::Domain("target-domain".into()),
+                alpn: None,
+                min_protocol_version: None,
+                ech_config: None,
+            },
+        };
+
+        let resolved = resolve_route(&dns, route)
+            .now_or_never()
+            .expect("all resolution is static")
+            .expect("all hostnames are resolvable")
+            .exactly_one()
+            .expect("one address");
+        assert_eq!(resolved.fragment.ech_config, None);
+    }
+
+    #[cfg(feature = "quic")]
+    #[test]
+    fn service_binding_applied_to_quic_routes() {
+        use crate::route::QuicRouteFragment;
+
+        let ech_config = EchConfigList::new(b"ech config".as_slice());
+        let binding = ServiceBinding {
+            priority: 1,
+            target_name: None,
+            port: Some(TARGET_PORT.get()),
+            alpn_ids: vec![Alpn::Http3.encoded().into(), Alpn::Http2.encoded().into()],
+            no_default_alpn: true,
+            ech_config_list: Some(ech_config.clone()),
+        };
+        let dns = HashMap::from([(
+            "target-domain",
+            LookupResult::new(vec![ip_addr!(v4, "192.0.2.1")], vec![])
+                .with_service_binding(Some(binding)),
+        )]);
+
+        let route = QuicRoute {
+            inner: UdpRoute {
+                address: UnresolvedHost("target-domain".into()),
+                port: TARGET_PORT,
+            },
+            fragment: QuicRouteFragment {
+                root_certs: RootCertificates::Native,
+                sni: Host::Domain("target-domain".into()),
+                alpn: Alpn::Http3,
+                ech_config: None,
+            },
+        };
+
+        let resolved = resolve_route(&dns, route)
+            .now_or_never()
+            .expect("all resolution is static")
+            .expect("all hostnames are resolvable")
+            .exactly_one()
+            .expect("one address");
+        assert_eq!(resolved.fragment.ech_config, Some(ech_config));
+    }
 }
@@ -320,6 +320,8 @@ where
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct AttemptOutcome {
     pub started: Instant,
+    /// When the attempt succeeded or gave up.
+    pub finished: Instant,
     pub result: Result<(), UnsuccessfulOutcome>,
 }
 
@@ -381,7 +383,11 @@ impl<R: Hash + Eq + Clone> ConnectionOutcomes<R> {
         });
 
         for (route, outcome) in updates {
-            let AttemptOutcome { started, result } = outcome;
+            let AttemptOutcome {
+                started,
+                finished: _,
+                result,
+            } = outcome;
 
             match result {
                 Ok(()) => {
@@ -952,6 +958,7 @@ mod test {
             LookupResult {
                 ipv4: vec![ip_addr!(v4, "192.0.2.1")],
                 ipv6: vec![ip_addr!(v6, "3fff::1234")],
+                service_binding: None,
             },
         )]);
 
@@ -987,6 +994,7 @@ mod test {
                 LookupResult {
                     ipv4: vec![ip_addr!(v4, "192.0.2.11")],
                     ipv6: vec![ip_addr!(v6, "3fff::1234")],
+                    service_binding: None,
                 },
             ),
             (
@@ -994,6 +1002,7 @@ mod test {
                 LookupResult {
                     ipv4: vec![ip_addr!(v4, "192.0.2.22")],
                     ipv6: vec![ip_addr!(v6, "3fff::5678")],
+                    service_binding: None,
                 },
             ),
         ]);
@@ -1092,9 +1101,17 @@ mod test {
             connect_duration: Duration,
             result: Result<(), UnsuccessfulOutcome>,
         ) {
+            let finished = started + connect_duration;
             self.apply_outcome_updates(
-                [(route, AttemptOutcome { started, result })],
-                started + connect_duration,
+                [(
+                    route,
+                    AttemptOutcome {
+                        started,
+                        finished,
+                        result,
+                    },
+                )],
+                finished,
                 SystemTime::now(),
             )
         }
@@ -1374,6 +1391,7 @@ mod test {
                 ROUTE,
                 AttemptOutcome {
                     started: start,
+                    finished: start,
                     result: Err(UnsuccessfulOutcome::default()),
                 },
             )],
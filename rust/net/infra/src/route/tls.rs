@@ -3,12 +3,14 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use std::num::NonZeroU16;
 use std::sync::Arc;
 
 use boring_signal::ssl::SslVersion;
 
 use crate::Alpn;
 use crate::certs::RootCertificates;
+use crate::dns::lookup_result::ServiceBinding;
 use crate::host::Host;
 use crate::route::{ReplaceFragment, RouteProvider, RouteProviderContext, SimpleRoute};
 
@@ -18,6 +20,8 @@ pub struct TlsRouteFragment {
     pub sni: Host<Arc<str>>,
     pub alpn: Option<Alpn>,
     pub min_protocol_version: Option<SslVersion>,
+    /// If present, the real SNI is encrypted using this configuration.
+    pub ech_config: Option<EchConfigList>,
 }
 
 impl std::hash::Hash for TlsRouteFragment {
@@ -26,6 +30,63 @@ impl std::hash::Hash for TlsRouteFragment {
         self.sni.hash(state);
         self.alpn.hash(state);
         // Ignore SslVersion, an opaque enum. Unfortunate, but a valid hash implementation.
+        self.ech_config.hash(state);
+    }
+}
+
+/// A serialized `ECHConfigList`, as published by a server for Encrypted Client Hello.
+///
+/// See [draft-ietf-tls-esni](https://datatracker.ietf.org/doc/html/draft-ietf-tls-esni-25#section-4).
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct EchConfigList(Arc<[u8]>);
+
+impl EchConfigList {
+    pub fn new(bytes: impl Into<Arc<[u8]>>) -> Self {
+        Self(bytes.into())
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for EchConfigList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "EchConfigList({} bytes)", self.0.len())
+    }
+}
+
+impl TlsRouteFragment {
+    /// Applies the hints from an HTTPS record for `hostname`, if it is this fragment's SNI and
+    /// the record describes the endpoint at `port`.
+    ///
+    /// A record that doesn't list the ALPN we're going to negotiate describes an endpoint we
+    /// won't talk to, and is ignored as per [RFC 9460](https://datatracker.ietf.org/doc/html/rfc9460#section-7.1.2).
+    pub(crate) fn apply_service_binding(
+        &mut self,
+        hostname: &str,
+        port: NonZeroU16,
+        binding: &ServiceBinding,
+    ) {
+        let Host::Domain(sni) = &self.sni else {
+            return;
+        };
+        if !sni.eq_ignore_ascii_case(hostname) {
+            return;
+        }
+        if !binding.applies_to(hostname, port.get()) {
+            log::debug!("ignoring service binding for a different endpoint");
+            return;
+        }
+        if let Some(alpn) = self.alpn
+            && !binding.supports_alpn(alpn)
+        {
+            log::debug!("ignoring service binding that doesn't support {alpn:?}");
+            return;
+        }
+        if let Some(ech_config) = &binding.ech_config_list {
+            self.ech_config = Some(ech_config.clone());
+        }
     }
 }
 
@@ -81,6 +142,7 @@ impl<P: RouteProvider> RouteProvider for TlsRouteProvider<P> {
                 sni: sni.clone(),
                 alpn: None,
                 min_protocol_version: *min_protocol_version,
+                ech_config: None,
             },
             inner: route,
         })
@@ -173,13 +173,14 @@ where
         &self,
         inner: Inner,
         fragment: TlsRouteFragment,
-        _log_tag: &str,
+        log_tag: &str,
     ) -> impl Future<Output = Result<Self::Connection, Self::Error>> + Send {
         let TlsRouteFragment {
             root_certs,
             sni,
             alpn,
             min_protocol_version,
+            ech_config,
         } = fragment;
         let host = sni;
 
@@ -190,7 +191,13 @@ where
                 Host::Ip(ip_addr) => either::Either::Left(ip_addr.to_string()),
                 Host::Domain(domain) => either::Either::Right(&**domain),
             };
-            let ssl_config = ssl_config?;
+            let mut ssl_config = ssl_config?;
+            if let Some(ech_config) = ech_config {
+                // The server name in the outer ClientHello comes from the ECH config, so `domain`
+                // is only ever sent encrypted.
+                ssl_config.set_ech_config_list(ech_config.as_bytes())?;
+                log::debug!("[{log_tag}] using encrypted client hello");
+            }
 
             tokio_boring_signal::connect(ssl_config, &domain, inner)
                 .await
@@ -397,6 +404,7 @@ pub(crate) mod testutil {
                         .await
                         .expect("H2 connection completes without error");
                     }
+                    Alpn::Http3 => unreachable!("h3 is never negotiated over TCP"),
                 }
             }
         };
@@ -449,6 +457,7 @@ pub(crate) mod testutil {
 mod test {
     use std::borrow::Cow;
     use std::num::NonZero;
+    use std::sync::Arc;
 
     use assert_matches::assert_matches;
     use boring_signal::x509::X509VerifyError;
@@ -459,6 +468,7 @@ mod test {
     use super::testutil::*;
     use super::*;
     use crate::OverrideNagleAlgorithm;
+    use crate::certs::{CertificatePolicy, PolicyViolation, SpkiPins, spki_sha256};
     use crate::errors::FailedHandshakeReason;
     use crate::route::{ComposedConnector, ConnectorExt as _, TlsRoute};
     use crate::tcp_ssl::proxy::testutil::PROXY_CERTIFICATE;
@@ -488,6 +498,7 @@ mod test {
                     sni: Host::Domain(SERVER_HOSTNAME.into()),
                     alpn: Some(client_alpn),
                     min_protocol_version: None,
+                    ech_config: None,
                 },
                 inner: TcpRoute {
                     address: addr.ip(),
@@ -537,6 +548,7 @@ mod test {
                     sni: Host::Domain(SERVER_HOSTNAME.into()),
                     alpn: None,
                     min_protocol_version: None,
+                    ech_config: None,
                 },
                 inner: TcpRoute {
                     address: addr.ip(),
@@ -560,4 +572,64 @@ mod test {
             })
         );
     }
+
+    #[test_case(true; "matching pin")]
+    #[test_case(false; "mismatched pin")]
+    #[test_log::test(tokio::test)]
+    async fn pinned_certificate(pin_server_key: bool) {
+        let (addr, server) = simple_localhost_https_server();
+        let server = Box::pin(server);
+
+        let pinned_cert = if pin_server_key {
+            SERVER_CERTIFICATE.cert.der()
+        } else {
+            PROXY_CERTIFICATE.cert.der()
+        };
+        let pins = SpkiPins::new(spki_sha256(pinned_cert).expect("valid"), [[0xAA; 32]])
+            .expect("has a backup");
+
+        type StatelessTlsConnector = ComposedConnector<StatelessTls, StatelessTcp>;
+        let connector = StatelessTlsConnector::default();
+        let client = std::pin::pin!(connector.connect(
+            TlsRoute {
+                fragment: TlsRouteFragment {
+                    root_certs: RootCertificates::WithPolicy {
+                        roots: Arc::new(RootCertificates::FromDer(Cow::Borrowed(
+                            SERVER_CERTIFICATE.cert.der(),
+                        ))),
+                        policy: Arc::new(CertificatePolicy {
+                            pins: Some(pins),
+                            certificate_transparency: None,
+                        }),
+                    },
+                    sni: Host::Domain(SERVER_HOSTNAME.into()),
+                    alpn: None,
+                    min_protocol_version: None,
+                    ech_config: None,
+                },
+                inner: TcpRoute {
+                    address: addr.ip(),
+                    port: NonZero::new(addr.port()).expect("successful listener has a valid port"),
+                    override_nagle_algorithm: OverrideNagleAlgorithm::UseSystemDefault,
+                },
+            },
+            "transport",
+        ));
+
+        let result = match futures_util::future::select(client, server).await {
+            Either::Left((stream, _server)) => stream.map(|_| ()),
+            Either::Right(_) => panic!("server exited unexpectedly"),
+        };
+
+        if pin_server_key {
+            result.expect("server presents the pinned key");
+        } else {
+            assert_matches!(
+                result,
+                Err(TransportConnectError::SslFailedHandshake(
+                    FailedHandshakeReason::CertificatePolicy(PolicyViolation::PinMismatch)
+                ))
+            );
+        }
+    }
 }
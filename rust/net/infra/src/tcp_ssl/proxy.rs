@@ -16,6 +16,7 @@ use crate::route::{
 };
 
 pub mod https;
+pub mod pluggable;
 pub mod reflector;
 pub mod socks;
 
@@ -101,6 +102,16 @@ impl Connector<ConnectionProxyRoute<IpAddr>, ()> for StatelessProxied {
                     .map_ok(|stream| ProxyStream::Reflector(Box::new(stream)))
                     .await
             }
+            ConnectionProxyRoute::Pluggable(route) => {
+                LoggingConnector::new(
+                    self,
+                    pluggable::LONG_FULL_CONNECT_THRESHOLD,
+                    "Proxy-TCP+Pluggable",
+                )
+                .connect(route, log_tag)
+                .map_ok(Into::into)
+                .await
+            }
         }
     }
 }
@@ -396,6 +407,7 @@ mod test {
                     sni: Host::Domain(PROXY_HOSTNAME.into()),
                     alpn: None,
                     min_protocol_version: None,
+                    ech_config: None,
                 },
                 inner: TcpRoute {
                     address: proxy_addr.ip(),
@@ -422,6 +434,7 @@ mod test {
                     sni: Host::Domain(SERVER_HOSTNAME.into()),
                     alpn: Some(Alpn::Http1_1),
                     min_protocol_version: None,
+                    ech_config: None,
                 },
                 "tcp proxy test",
             )
@@ -468,6 +481,7 @@ mod test {
                     sni: Host::Domain(SERVER_HOSTNAME.into()),
                     alpn: Some(Alpn::Http1_1),
                     min_protocol_version: None,
+                    ech_config: None,
                 },
                 "tcp proxy test",
             )
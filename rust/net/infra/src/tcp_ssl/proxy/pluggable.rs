@@ -0,0 +1,547 @@
+//
+// Copyright 2026 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::net::IpAddr;
+use std::num::NonZeroU16;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio_socks::TargetAddr;
+use tokio_socks::tcp::Socks5Stream;
+
+use crate::errors::TransportConnectError;
+use crate::host::Host;
+use crate::route::{Connector, ConnectorExt as _, PluggableTransportRoute, TcpRoute};
+use crate::tcp_ssl::TcpStream;
+use crate::{AsyncDuplexStream, Connection, TransportInfo};
+
+pub(crate) const LONG_FULL_CONNECT_THRESHOLD: Duration = super::LONG_TCP_HANDSHAKE_THRESHOLD
+    .saturating_add(super::LONG_TLS_HANDSHAKE_THRESHOLD)
+    .saturating_add(Duration::from_secs(3));
+
+/// An obfuscating transport supplied by the embedding application.
+///
+/// libsignal establishes the TCP connection to the transport's bridge; the
+/// transport is then responsible for its own handshake with the bridge and for
+/// asking it to forward the tunnel to the target. Whatever bytes are written to
+/// the returned stream should arrive at the target unmodified, so that TLS to
+/// the target can be layered on top.
+///
+/// Transports are held by [`ConnectionProxyConfig`](crate::route::ConnectionProxyConfig),
+/// which is shared across the app language bridges, hence the unwind-safety
+/// requirements.
+#[async_trait]
+pub trait PluggableTransport: Debug + Send + Sync + UnwindSafe + RefUnwindSafe {
+    /// A short name for the transport (e.g. "obfs4").
+    ///
+    /// This will be logged, so it must not contain any user data.
+    fn name(&self) -> &'static str;
+
+    /// Wraps `bridge` in the obfuscated protocol, returning a stream that
+    /// tunnels to `target_host:target_port`.
+    async fn wrap(
+        &self,
+        bridge: TcpStream,
+        target_host: &str,
+        target_port: NonZeroU16,
+    ) -> io::Result<Box<dyn AsyncDuplexStream>>;
+}
+
+/// Shared reference to a [`PluggableTransport`].
+///
+/// Two handles are considered equal only if they refer to the same transport
+/// instance, which allows routes containing them to be compared and hashed.
+#[derive(Clone)]
+pub struct PluggableTransportHandle(Arc<dyn PluggableTransport>);
+
+impl PluggableTransportHandle {
+    pub fn new(transport: impl PluggableTransport + 'static) -> Self {
+        Self(Arc::new(transport))
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.0.name()
+    }
+}
+
+impl From<Arc<dyn PluggableTransport>> for PluggableTransportHandle {
+    fn from(value: Arc<dyn PluggableTransport>) -> Self {
+        Self(value)
+    }
+}
+
+impl Debug for PluggableTransportHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("PluggableTransportHandle")
+            .field(&self.0.name())
+            .finish()
+    }
+}
+
+impl PartialEq for PluggableTransportHandle {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::addr_eq(Arc::as_ptr(&self.0), Arc::as_ptr(&other.0))
+    }
+}
+
+impl Eq for PluggableTransportHandle {}
+
+impl Hash for PluggableTransportHandle {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.0).cast::<()>().hash(state)
+    }
+}
+
+/// A [`PluggableTransport`] provided by a Pluggable Transports client running
+/// alongside the app, such as an obfs4 or Snowflake client library.
+///
+/// Such clients listen for SOCKS5 connections on a local port and handle the
+/// obfuscated connection to the remote bridge themselves, as described in the
+/// Tor Pluggable Transport Specification. The route's bridge should be that
+/// local listener; this transport asks it to connect onward to
+/// `remote_bridge`, passing the bridge's arguments (e.g. `cert=...`) through
+/// the SOCKS5 username and password fields.
+pub struct LocalSocksTransport {
+    remote_bridge_host: Host<Arc<str>>,
+    remote_bridge_port: NonZeroU16,
+    username: String,
+    password: String,
+}
+
+/// The bridge arguments for a [`LocalSocksTransport`] don't fit in the SOCKS5
+/// username and password fields.
+#[derive(Debug, displaydoc::Display, thiserror::Error)]
+pub struct BridgeArgumentsTooLong;
+
+impl LocalSocksTransport {
+    // From RFC 1929.
+    const MAX_FIELD_LEN: usize = 255;
+
+    pub fn new<'a>(
+        remote_bridge_host: Host<Arc<str>>,
+        remote_bridge_port: NonZeroU16,
+        args: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> Result<Self, BridgeArgumentsTooLong> {
+        let (username, password) = Self::encode_args(args)?;
+        Ok(Self {
+            remote_bridge_host,
+            remote_bridge_port,
+            username,
+            password,
+        })
+    }
+
+    /// Encodes `args` as `key=value;key=value`, split across the username and
+    /// password fields as the Pluggable Transport Specification requires.
+    fn encode_args<'a>(
+        args: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> Result<(String, String), BridgeArgumentsTooLong> {
+        fn escape(s: &str, out: &mut String) {
+            for c in s.chars() {
+                if matches!(c, '\\' | '=' | ';') {
+                    out.push('\\');
+                }
+                out.push(c);
+            }
+        }
+
+        let mut username = String::new();
+        for (i, (key, value)) in args.into_iter().enumerate() {
+            if i != 0 {
+                username.push(';');
+            }
+            escape(key, &mut username);
+            username.push('=');
+            escape(value, &mut username);
+        }
+
+        let mut split_at = username.len().min(Self::MAX_FIELD_LEN);
+        while !username.is_char_boundary(split_at) {
+            split_at -= 1;
+        }
+        let password = username.split_off(split_at);
+        if password.len() > Self::MAX_FIELD_LEN {
+            return Err(BridgeArgumentsTooLong);
+        }
+
+        // SOCKS5 doesn't allow empty fields; the client strips a lone NUL.
+        let nul_if_empty = |field: String| {
+            if field.is_empty() {
+                "\0".to_owned()
+            } else {
+                field
+            }
+        };
+        Ok((nul_if_empty(username), nul_if_empty(password)))
+    }
+}
+
+impl Debug for LocalSocksTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The bridge address and arguments are secret.
+        f.debug_struct("LocalSocksTransport")
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl PluggableTransport for LocalSocksTransport {
+    fn name(&self) -> &'static str {
+        "local-socks"
+    }
+
+    async fn wrap(
+        &self,
+        bridge: TcpStream,
+        _target_host: &str,
+        _target_port: NonZeroU16,
+    ) -> io::Result<Box<dyn AsyncDuplexStream>> {
+        // The remote bridge forwards to a fixed destination, chosen by the
+        // TLS SNI of the connection layered on top.
+        let target = match &self.remote_bridge_host {
+            Host::Ip(ip) => TargetAddr::Ip((*ip, self.remote_bridge_port.get()).into()),
+            Host::Domain(domain) => {
+                TargetAddr::Domain(domain.to_string().into(), self.remote_bridge_port.get())
+            }
+        };
+        let stream = Socks5Stream::connect_with_password_and_socket(
+            bridge,
+            target,
+            &self.username,
+            &self.password,
+        )
+        .await
+        .map_err(io::Error::other)?;
+        Ok(Box::new(stream))
+    }
+}
+
+/// Stream produced by a [`PluggableTransport`].
+///
+/// Remembers the transport info of the underlying connection to the bridge,
+/// since the transport's own stream type is opaque.
+pub struct PluggableTransportStream {
+    inner: Box<dyn AsyncDuplexStream>,
+    transport_info: TransportInfo,
+}
+
+impl Debug for PluggableTransportStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PluggableTransportStream")
+            .field("transport_info", &self.transport_info)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AsyncRead for PluggableTransportStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for PluggableTransportStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+impl Connection for PluggableTransportStream {
+    fn transport_info(&self) -> TransportInfo {
+        self.transport_info.clone()
+    }
+}
+
+impl Connector<PluggableTransportRoute<IpAddr>, ()> for super::StatelessProxied {
+    type Connection = PluggableTransportStream;
+
+    type Error = TransportConnectError;
+
+    async fn connect_over(
+        &self,
+        (): (),
+        route: PluggableTransportRoute<IpAddr>,
+        log_tag: &str,
+    ) -> Result<Self::Connection, Self::Error> {
+        let PluggableTransportRoute {
+            bridge,
+            transport,
+            target_host,
+            target_port,
+        } = route;
+
+        let transport_name = transport.name();
+        // The bridge address is deliberately not logged, even at debug level;
+        // bridges are meant to be hard to find.
+        log::info!("[{log_tag}] connecting to {transport_name} bridge over TCP");
+
+        let stream = super::super::StatelessTcp.connect(bridge, log_tag).await?;
+        let transport_info = stream.transport_info();
+
+        log::info!("[{log_tag}] performing {transport_name} handshake");
+        let inner = transport
+            .0
+            .wrap(stream, &target_host, target_port)
+            .await
+            .map_err(|e| {
+                log::info!(
+                    "[{log_tag}] {transport_name} handshake failed: {}",
+                    e.kind()
+                );
+                TransportConnectError::ProxyProtocol
+            })?;
+
+        Ok(PluggableTransportStream {
+            inner,
+            transport_info,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::SocketAddr;
+
+    use assert_matches::assert_matches;
+    use tokio::io::{AsyncBufReadExt as _, AsyncReadExt as _, AsyncWriteExt as _, BufReader};
+
+    use super::*;
+    use crate::OverrideNagleAlgorithm;
+    use crate::tcp_ssl::proxy::testutil::TcpServer;
+
+    const TARGET_HOST: &str = "chat.signal.org";
+    const TARGET_PORT: NonZeroU16 = nonzero_ext::nonzero!(443u16);
+    const XOR_KEY: u8 = 0x5a;
+
+    /// Stand-in obfuscation: every byte is XORed with a fixed key.
+    ///
+    /// Because the key is a single byte, the transformation doesn't depend on
+    /// stream position and so partial reads and writes need no extra state.
+    struct XorStream<S>(S);
+
+    impl<S: AsyncRead + Unpin> AsyncRead for XorStream<S> {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            let already_filled = buf.filled().len();
+            let result = Pin::new(&mut self.get_mut().0).poll_read(cx, buf);
+            if let Poll::Ready(Ok(())) = result {
+                buf.filled_mut()[already_filled..]
+                    .iter_mut()
+                    .for_each(|b| *b ^= XOR_KEY);
+            }
+            result
+        }
+    }
+
+    impl<S: AsyncWrite + Unpin> AsyncWrite for XorStream<S> {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            let obfuscated: Vec<u8> = buf.iter().map(|b| b ^ XOR_KEY).collect();
+            Pin::new(&mut self.get_mut().0).poll_write(cx, &obfuscated)
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.get_mut().0).poll_flush(cx)
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.get_mut().0).poll_shutdown(cx)
+        }
+    }
+
+    /// Sends the target as a plaintext preamble line, then XORs everything.
+    #[derive(Debug)]
+    struct XorTransport;
+
+    #[async_trait]
+    impl PluggableTransport for XorTransport {
+        fn name(&self) -> &'static str {
+            "xor"
+        }
+
+        async fn wrap(
+            &self,
+            mut bridge: TcpStream,
+            target_host: &str,
+            target_port: NonZeroU16,
+        ) -> io::Result<Box<dyn AsyncDuplexStream>> {
+            bridge
+                .write_all(format!("XOR {target_host}:{target_port}\n").as_bytes())
+                .await?;
+            Ok(Box::new(XorStream(bridge)))
+        }
+    }
+
+    #[derive(Debug)]
+    struct FailingTransport;
+
+    #[async_trait]
+    impl PluggableTransport for FailingTransport {
+        fn name(&self) -> &'static str {
+            "failing"
+        }
+
+        async fn wrap(
+            &self,
+            _bridge: TcpStream,
+            _target_host: &str,
+            _target_port: NonZeroU16,
+        ) -> io::Result<Box<dyn AsyncDuplexStream>> {
+            Err(io::ErrorKind::ConnectionReset.into())
+        }
+    }
+
+    fn test_route(
+        bridge_addr: SocketAddr,
+        transport: PluggableTransportHandle,
+    ) -> PluggableTransportRoute<IpAddr> {
+        PluggableTransportRoute {
+            bridge: TcpRoute {
+                address: bridge_addr.ip(),
+                port: bridge_addr.port().try_into().expect("valid port"),
+                override_nagle_algorithm: OverrideNagleAlgorithm::UseSystemDefault,
+            },
+            transport,
+            target_host: TARGET_HOST.into(),
+            target_port: TARGET_PORT,
+        }
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn connect_through_stand_in_bridge() {
+        let bridge = TcpServer::bind_localhost();
+        let bridge_addr = bridge.listen_addr;
+
+        // The stand-in bridge checks the preamble and then echoes everything
+        // back through the obfuscation layer.
+        let bridge_task = tokio::spawn(async move {
+            let (stream, _remote_addr) = bridge.accept().await;
+            let mut stream = BufReader::new(stream);
+            let mut preamble = String::new();
+            stream.read_line(&mut preamble).await.expect("can read");
+            assert_eq!(preamble, format!("XOR {TARGET_HOST}:{TARGET_PORT}\n"));
+
+            let mut stream = XorStream(stream);
+            let mut received = [0; "from client".len()];
+            stream.read_exact(&mut received).await.expect("can read");
+            stream.write_all(&received).await.expect("can write");
+            stream.flush().await.expect("can flush");
+            stream
+        });
+
+        let mut stream = super::super::StatelessProxied
+            .connect(
+                test_route(bridge_addr, PluggableTransportHandle::new(XorTransport)),
+                "test",
+            )
+            .await
+            .expect("can connect");
+        assert_eq!(stream.transport_info().remote_addr, bridge_addr);
+
+        stream.write_all(b"from client").await.expect("can write");
+        stream.flush().await.expect("can flush");
+
+        let mut echoed = [0; "from client".len()];
+        stream.read_exact(&mut echoed).await.expect("can read");
+        assert_eq!(&echoed, b"from client");
+
+        let bridge_stream = tokio::time::timeout(Duration::from_secs(1), bridge_task)
+            .await
+            .expect("bridge task finished within 1s")
+            .expect("bridge task succeeded");
+        drop(bridge_stream);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn transport_failure_maps_to_proxy_protocol() {
+        let bridge = TcpServer::bind_localhost();
+        let bridge_addr = bridge.listen_addr;
+        let _bridge_task = tokio::spawn(async move { bridge.accept().await });
+
+        let result = super::super::StatelessProxied
+            .connect(
+                test_route(bridge_addr, PluggableTransportHandle::new(FailingTransport)),
+                "test",
+            )
+            .await;
+        assert_matches!(result, Err(TransportConnectError::ProxyProtocol));
+    }
+
+    #[test]
+    fn local_socks_args_are_escaped() {
+        let (username, password) =
+            LocalSocksTransport::encode_args([("cert", "a=b;c\\d"), ("iat-mode", "0")])
+                .expect("short enough");
+        assert_eq!(username, "cert=a\\=b\\;c\\\\d;iat-mode=0");
+        assert_eq!(password, "\0");
+    }
+
+    #[test]
+    fn local_socks_args_are_split_across_fields() {
+        let long_value = "x".repeat(300);
+        let (username, password) =
+            LocalSocksTransport::encode_args([("cert", long_value.as_str())])
+                .expect("short enough");
+        assert_eq!(username.len(), 255);
+        assert_eq!(
+            format!("{username}{password}"),
+            format!("cert={long_value}")
+        );
+
+        let too_long = "x".repeat(600);
+        assert_matches!(
+            LocalSocksTransport::encode_args([("cert", too_long.as_str())]),
+            Err(BridgeArgumentsTooLong)
+        );
+    }
+
+    #[test]
+    fn local_socks_transport_debug_is_redacted() {
+        let transport = LocalSocksTransport::new(
+            Host::Domain("secret-bridge.example".into()),
+            nonzero_ext::nonzero!(443u16),
+            [("cert", "secret-cert")],
+        )
+        .expect("short enough");
+        let debug = format!("{transport:?}");
+        assert!(!debug.contains("secret"), "{debug}");
+    }
+
+    #[test]
+    fn handles_compare_by_identity() {
+        let transport = PluggableTransportHandle::new(XorTransport);
+        assert_eq!(transport, transport.clone());
+        assert_ne!(transport, PluggableTransportHandle::new(XorTransport));
+    }
+}
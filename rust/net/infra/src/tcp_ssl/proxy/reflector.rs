@@ -302,6 +302,7 @@ mod test {
                             sni: Host::Domain(PROXY_HOSTNAME.into()),
                             alpn: Some(Alpn::Http1_1),
                             min_protocol_version: None,
+                            ech_config: None,
                         },
                         inner: TcpRoute {
                             address: server_addr.ip(),
@@ -9,6 +9,7 @@ use tokio_boring_signal::SslStream;
 use crate::Connection;
 use crate::tcp_ssl::TcpStream;
 use crate::tcp_ssl::proxy::https::HttpProxyStream;
+use crate::tcp_ssl::proxy::pluggable::PluggableTransportStream;
 use crate::tcp_ssl::proxy::reflector::ReflectorStream;
 use crate::tcp_ssl::proxy::socks::SocksStream;
 
@@ -20,6 +21,7 @@ pub enum ProxyStream {
     Socks(SocksStream<TcpStream>),
     Http(HttpProxyStream),
     Reflector(Box<ReflectorStream>),
+    Pluggable(PluggableTransportStream),
 }
 
 impl Connection for ProxyStream {
@@ -30,6 +32,7 @@ impl Connection for ProxyStream {
             ProxyStream::Socks(either) => either.transport_info(),
             ProxyStream::Http(http) => http.transport_info(),
             ProxyStream::Reflector(reflector) => reflector.transport_info(),
+            ProxyStream::Pluggable(pluggable) => pluggable.transport_info(),
         }
     }
 }
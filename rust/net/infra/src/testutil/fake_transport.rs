@@ -65,6 +65,10 @@ impl FakeTransportTarget {
                 host: Some(Host::Domain(reflector.target_host.clone())),
                 port: reflector.target_port,
             },
+            ConnectionProxyRoute::Pluggable(pluggable) => Self::TcpThroughProxy {
+                host: Some(Host::Domain(pluggable.target_host.clone())),
+                port: pluggable.target_port,
+            },
         }
     }
 }
@@ -684,6 +684,7 @@ mod test {
                     sni: Host::Domain(SERVER_HOSTNAME.into()),
                     alpn: Some(Alpn::Http2),
                     min_protocol_version: None,
+                    ech_config: None,
                 },
                 inner: TcpRoute {
                     address: addr.ip(),
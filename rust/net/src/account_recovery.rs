@@ -0,0 +1,846 @@
+//
+// Copyright 2026 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Recovering an account's keys when re-registering, in the order clients are expected to try.
+//!
+//! 1. Restore the [`SvrKey`] from SVR2 using the user's PIN. The SVR key is enough to re-register,
+//!    so on success the recovery skips straight to step 3. This is optional: the user may not
+//!    remember their PIN, may run out of tries, or may never have stored anything in SVR2.
+//! 2. Otherwise, collect the user's [`AccountEntropyPool`], the root of every other account key.
+//! 3. Restore the backup forward secrecy token from SVR-B using the [`BackupKey`] derived from the
+//!    pool and the metadata from the backup file, unless there is no backup to restore. If the
+//!    SVR key was restored with the PIN, the pool has to be provided here, and must match it.
+//!
+//! [`AccountRecovery`] drives these steps and can be [serialized](AccountRecovery::serialize)
+//! after each one, so that an app restart in the middle (say, while the user looks for their
+//! recovery key) picks up where it left off. Any operation that fails with an error leaves the
+//! recovery in the step it was in, so it can be retried.
+
+use std::str::FromStr as _;
+
+use libsignal_account_keys::{
+    AccountEntropyPool, BACKUP_FORWARD_SECRECY_TOKEN_LEN, BackupForwardSecrecyToken, BackupId,
+    BackupKey, PinHash, SVR_KEY_LEN, SvrKey,
+};
+use libsignal_core::{Aci, LogSafeDisplay};
+use prost::Message as _;
+use subtle::ConstantTimeEq as _;
+use thiserror::Error;
+
+use crate::proto::account_recovery as proto;
+use crate::svr2::ops::{Svr2Protocol, do_restore};
+use crate::svrb::{BackupFileMetadataRef, BackupPreviousSecretDataRef};
+use crate::{svr2, svrb};
+
+#[derive(Debug, Error, displaydoc::Display)]
+pub enum Error {
+    /// SVR2: {0}
+    Svr2(#[from] svr2::Error),
+    /// SVR-B: {0}
+    SvrB(#[from] svrb::Error),
+    /// The account entropy pool does not match the key restored with the PIN
+    AccountEntropyPoolMismatch,
+    /// Restoring a backup requires the account entropy pool
+    AccountEntropyPoolRequired,
+    /// Not valid while {0:?}
+    WrongStep(RecoveryStep),
+}
+
+impl LogSafeDisplay for Error {}
+
+#[derive(Debug, Error, displaydoc::Display)]
+pub enum StoredRecoveryDecodeError {
+    /// Failed to decode serialized recovery proto: {0}
+    Decode(#[from] prost::DecodeError),
+    /// Invalid field in serialized recovery: {0}
+    InvalidField(&'static str),
+}
+
+impl LogSafeDisplay for StoredRecoveryDecodeError {}
+
+/// Where an [`AccountRecovery`] is, without any of the associated key material.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecoveryStep {
+    /// Waiting for the user's PIN.
+    ///
+    /// `tries_remaining` is `None` until a PIN has been rejected.
+    AwaitingPin { tries_remaining: Option<u32> },
+    /// Waiting for the user's account entropy pool ("recovery key").
+    AwaitingAccountEntropyPool,
+    /// Waiting to restore the backup forward secrecy token from SVR-B.
+    ///
+    /// `has_account_entropy_pool` is `false` if only the SVR key was restored with the PIN; the
+    /// pool must be provided before a backup can be restored.
+    AwaitingBackupRestore { has_account_entropy_pool: bool },
+    /// All keys have been recovered.
+    Complete,
+}
+
+/// The root key material of a recovery: either the SVR key restored with the PIN, or the full
+/// account entropy pool.
+enum Root {
+    SvrKey([u8; SVR_KEY_LEN]),
+    AccountEntropyPool(AccountEntropyPool),
+}
+
+impl Root {
+    fn svr_key(&self) -> SvrKey {
+        match self {
+            Self::SvrKey(svr_key) => SvrKey::new(*svr_key),
+            Self::AccountEntropyPool(account_entropy_pool) => {
+                SvrKey::new(account_entropy_pool.derive_svr_key())
+            }
+        }
+    }
+
+    fn account_entropy_pool(&self) -> Option<&AccountEntropyPool> {
+        match self {
+            Self::SvrKey(_) => None,
+            Self::AccountEntropyPool(account_entropy_pool) => Some(account_entropy_pool),
+        }
+    }
+
+    fn to_proto(&self) -> proto::recovery_session::Root {
+        use proto::recovery_session::root::Kind;
+        let kind = match self {
+            Self::SvrKey(svr_key) => Kind::SvrKey(svr_key.to_vec()),
+            Self::AccountEntropyPool(account_entropy_pool) => {
+                Kind::AccountEntropyPool(account_entropy_pool.to_string())
+            }
+        };
+        proto::recovery_session::Root { kind: Some(kind) }
+    }
+
+    fn from_proto(
+        root: Option<proto::recovery_session::Root>,
+    ) -> Result<Self, StoredRecoveryDecodeError> {
+        use StoredRecoveryDecodeError::InvalidField;
+        use proto::recovery_session::root::Kind;
+        let kind = root
+            .and_then(|root| root.kind)
+            .ok_or(InvalidField("root"))?;
+        Ok(match kind {
+            Kind::SvrKey(svr_key) => {
+                Self::SvrKey(svr_key.try_into().map_err(|_| InvalidField("svr_key"))?)
+            }
+            Kind::AccountEntropyPool(account_entropy_pool) => Self::AccountEntropyPool(
+                AccountEntropyPool::from_str(&account_entropy_pool)
+                    .map_err(|_| InvalidField("account_entropy_pool"))?,
+            ),
+        })
+    }
+}
+
+/// Everything recovered by a completed [`AccountRecovery`].
+///
+/// This always includes the SVR key, but only includes the account entropy pool (and the keys
+/// derived from it) if the user provided it.
+pub struct RecoveredKeys {
+    aci: Aci,
+    root: Root,
+    forward_secrecy: Option<RestoredForwardSecrecy>,
+}
+
+struct RestoredForwardSecrecy {
+    token: [u8; BACKUP_FORWARD_SECRECY_TOKEN_LEN],
+    next_backup_data: Vec<u8>,
+}
+
+impl RecoveredKeys {
+    /// The account entropy pool, unless the recovery finished with only the SVR key.
+    pub fn account_entropy_pool(&self) -> Option<&AccountEntropyPool> {
+        self.root.account_entropy_pool()
+    }
+
+    pub fn svr_key(&self) -> SvrKey {
+        self.root.svr_key()
+    }
+
+    /// The backup key, if the account entropy pool was recovered.
+    pub fn backup_key(&self) -> Option<BackupKey> {
+        self.account_entropy_pool()
+            .map(BackupKey::derive_from_account_entropy_pool)
+    }
+
+    /// The backup ID, if the account entropy pool was recovered.
+    pub fn backup_id(&self) -> Option<BackupId> {
+        Some(self.backup_key()?.derive_backup_id(&self.aci))
+    }
+
+    /// The token needed to decrypt the backup, if one was restored.
+    pub fn forward_secrecy_token(&self) -> Option<BackupForwardSecrecyToken> {
+        self.forward_secrecy
+            .as_ref()
+            .map(|restored| BackupForwardSecrecyToken(restored.token))
+    }
+
+    /// The data to pass to the next [`svrb::store_backup`], if a backup was restored.
+    pub fn next_backup_data(&self) -> Option<BackupPreviousSecretDataRef<'_>> {
+        self.forward_secrecy
+            .as_ref()
+            .map(|restored| BackupPreviousSecretDataRef(&restored.next_backup_data))
+    }
+}
+
+enum State {
+    AwaitingPin { tries_remaining: Option<u32> },
+    AwaitingAccountEntropyPool,
+    AwaitingBackupRestore { root: Root },
+    Complete(RecoveredKeys),
+}
+
+/// An in-progress recovery of the keys for `aci`; see the [module-level docs](self).
+pub struct AccountRecovery {
+    aci: Aci,
+    state: State,
+}
+
+impl AccountRecovery {
+    pub fn new(aci: Aci) -> Self {
+        Self {
+            aci,
+            state: State::AwaitingPin {
+                tries_remaining: None,
+            },
+        }
+    }
+
+    pub fn step(&self) -> RecoveryStep {
+        match &self.state {
+            State::AwaitingPin { tries_remaining } => RecoveryStep::AwaitingPin {
+                tries_remaining: *tries_remaining,
+            },
+            State::AwaitingAccountEntropyPool => RecoveryStep::AwaitingAccountEntropyPool,
+            State::AwaitingBackupRestore { root } => RecoveryStep::AwaitingBackupRestore {
+                has_account_entropy_pool: root.account_entropy_pool().is_some(),
+            },
+            State::Complete(_) => RecoveryStep::Complete,
+        }
+    }
+
+    /// The SVR key, as soon as it is known.
+    ///
+    /// This is available right after a successful PIN restore, even if the account entropy pool
+    /// is never provided, so that a client can (for example) present the registration lock.
+    pub fn svr_key(&self) -> Option<SvrKey> {
+        match &self.state {
+            State::AwaitingPin { .. } | State::AwaitingAccountEntropyPool => None,
+            State::AwaitingBackupRestore { root } | State::Complete(RecoveredKeys { root, .. }) => {
+                Some(root.svr_key())
+            }
+        }
+    }
+
+    /// The recovered keys, once the recovery is complete.
+    pub fn recovered_keys(&self) -> Option<&RecoveredKeys> {
+        match &self.state {
+            State::Complete(keys) => Some(keys),
+            State::AwaitingPin { .. }
+            | State::AwaitingAccountEntropyPool
+            | State::AwaitingBackupRestore { .. } => None,
+        }
+    }
+
+    /// Tries to restore the SVR key from SVR2 using `pin_hash`.
+    ///
+    /// A successful restore moves on to [`RecoveryStep::AwaitingBackupRestore`] with only the SVR
+    /// key. An incorrect PIN stays in [`RecoveryStep::AwaitingPin`] with the number of tries
+    /// remaining, unless there are none left. Running out of tries, or finding nothing stored in
+    /// SVR2, moves on to [`RecoveryStep::AwaitingAccountEntropyPool`] as if the user had
+    /// [skipped](Self::skip_pin) the PIN. Only polls `connect` if the recovery is waiting for a
+    /// PIN.
+    pub async fn submit_pin<C: Svr2Protocol>(
+        &mut self,
+        pin_hash: &PinHash,
+        connect: impl Future<Output = Result<C, svr2::Error>>,
+    ) -> Result<RecoveryStep, Error> {
+        let State::AwaitingPin { .. } = self.state else {
+            return Err(Error::WrongStep(self.step()));
+        };
+        let mut conn = connect.await?;
+        self.state = match do_restore(&mut conn, &pin_hash.access_key).await {
+            Ok(restored) => {
+                let encoded = <[u8; 48]>::try_from(restored.data).map_err(|data| {
+                    svr2::Error::Protocol(format!(
+                        "expected 48 bytes of restored data, got {}",
+                        data.len()
+                    ))
+                })?;
+                let svr_key = pin_hash
+                    .decode_master_key(&encoded)
+                    .ok_or(svr2::Error::DecryptionError)?;
+                State::AwaitingBackupRestore {
+                    root: Root::SvrKey(svr_key),
+                }
+            }
+            Err(svr2::Error::RestoreFailed { tries_left }) if tries_left > 0 => {
+                log::info!("incorrect PIN; {tries_left} tries remaining");
+                State::AwaitingPin {
+                    tries_remaining: Some(tries_left),
+                }
+            }
+            Err(svr2::Error::RestoreFailed { .. } | svr2::Error::DataMissing) => {
+                log::info!(
+                    "nothing to restore from SVR2; falling back to the account entropy pool"
+                );
+                State::AwaitingAccountEntropyPool
+            }
+            Err(e) => return Err(e.into()),
+        };
+        Ok(self.step())
+    }
+
+    /// Moves on without a PIN, e.g. because the user doesn't remember it.
+    pub fn skip_pin(&mut self) -> Result<RecoveryStep, Error> {
+        let State::AwaitingPin { .. } = self.state else {
+            return Err(Error::WrongStep(self.step()));
+        };
+        self.state = State::AwaitingAccountEntropyPool;
+        Ok(self.step())
+    }
+
+    /// Provides the user's account entropy pool.
+    ///
+    /// This is accepted while [`RecoveryStep::AwaitingAccountEntropyPool`], or while
+    /// [`RecoveryStep::AwaitingBackupRestore`] with only the SVR key; in the latter case,
+    /// `account_entropy_pool` must match the SVR key restored with the PIN.
+    pub fn submit_account_entropy_pool(
+        &mut self,
+        account_entropy_pool: AccountEntropyPool,
+    ) -> Result<RecoveryStep, Error> {
+        match &self.state {
+            State::AwaitingAccountEntropyPool => {}
+            State::AwaitingBackupRestore {
+                root: Root::SvrKey(svr_key),
+            } => {
+                if !bool::from(
+                    account_entropy_pool
+                        .derive_svr_key()
+                        .ct_eq(svr_key.as_slice()),
+                ) {
+                    return Err(Error::AccountEntropyPoolMismatch);
+                }
+            }
+            State::AwaitingPin { .. }
+            | State::AwaitingBackupRestore {
+                root: Root::AccountEntropyPool(_),
+            }
+            | State::Complete(_) => return Err(Error::WrongStep(self.step())),
+        }
+        self.state = State::AwaitingBackupRestore {
+            root: Root::AccountEntropyPool(account_entropy_pool),
+        };
+        Ok(self.step())
+    }
+
+    /// Restores the backup forward secrecy token from SVR-B, using `metadata` from the backup file.
+    ///
+    /// Fails with [`Error::AccountEntropyPoolRequired`] if only the SVR key has been recovered.
+    /// See [`svrb::restore_backup`] for the meaning of `current_and_previous_svrbs`.
+    pub async fn restore_backup<R: svrb::traits::Restore>(
+        &mut self,
+        current_and_previous_svrbs: &[R],
+        metadata: BackupFileMetadataRef<'_>,
+    ) -> Result<RecoveryStep, Error> {
+        let State::AwaitingBackupRestore { root } = &self.state else {
+            return Err(Error::WrongStep(self.step()));
+        };
+        let account_entropy_pool = root
+            .account_entropy_pool()
+            .ok_or(Error::AccountEntropyPoolRequired)?;
+        let backup_key = BackupKey::derive_from_account_entropy_pool(account_entropy_pool);
+        let svrb::BackupRestoreResponse {
+            forward_secrecy_token,
+            next_backup_data,
+        } = svrb::restore_backup(current_and_previous_svrbs, &backup_key, metadata).await?;
+        self.complete(Some(RestoredForwardSecrecy {
+            token: forward_secrecy_token.0,
+            next_backup_data: next_backup_data.0,
+        }))
+    }
+
+    /// Finishes without restoring a backup, e.g. because the account never made one.
+    pub fn skip_backup_restore(&mut self) -> Result<RecoveryStep, Error> {
+        self.complete(None)
+    }
+
+    fn complete(
+        &mut self,
+        forward_secrecy: Option<RestoredForwardSecrecy>,
+    ) -> Result<RecoveryStep, Error> {
+        let placeholder = State::AwaitingPin {
+            tries_remaining: None,
+        };
+        match std::mem::replace(&mut self.state, placeholder) {
+            State::AwaitingBackupRestore { root } => {
+                self.state = State::Complete(RecoveredKeys {
+                    aci: self.aci,
+                    root,
+                    forward_secrecy,
+                });
+                Ok(self.step())
+            }
+            state => {
+                self.state = state;
+                Err(Error::WrongStep(self.step()))
+            }
+        }
+    }
+
+    /// Serializes the recovery so that it can be resumed after an app restart.
+    ///
+    /// The result contains key material (depending on the step, the SVR key or the account entropy
+    /// pool), and must be stored as securely as those keys.
+    pub fn serialize(&self) -> Vec<u8> {
+        use proto::recovery_session::{self, State as ProtoState};
+        let state = match &self.state {
+            State::AwaitingPin { tries_remaining } => {
+                ProtoState::AwaitingPin(recovery_session::AwaitingPin {
+                    tries: tries_remaining.map(|remaining| recovery_session::Tries { remaining }),
+                })
+            }
+            State::AwaitingAccountEntropyPool => ProtoState::AwaitingAccountEntropyPool(
+                recovery_session::AwaitingAccountEntropyPool {},
+            ),
+            State::AwaitingBackupRestore { root } => {
+                ProtoState::AwaitingBackupRestore(recovery_session::AwaitingBackupRestore {
+                    root: Some(root.to_proto()),
+                })
+            }
+            State::Complete(RecoveredKeys {
+                aci: _,
+                root,
+                forward_secrecy,
+            }) => {
+                let (forward_secrecy_token, next_backup_data) = forward_secrecy
+                    .as_ref()
+                    .map(|restored| (restored.token.to_vec(), restored.next_backup_data.clone()))
+                    .unwrap_or_default();
+                ProtoState::Complete(recovery_session::Complete {
+                    root: Some(root.to_proto()),
+                    forward_secrecy_token,
+                    next_backup_data,
+                })
+            }
+        };
+        proto::RecoverySession {
+            aci: self.aci.service_id_binary(),
+            state: Some(state),
+        }
+        .encode_to_vec()
+    }
+
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, StoredRecoveryDecodeError> {
+        use StoredRecoveryDecodeError::InvalidField;
+        use proto::recovery_session::{self, State as ProtoState};
+
+        let proto::RecoverySession { aci, state } = proto::RecoverySession::decode(bytes)?;
+        let aci = Aci::parse_from_service_id_binary(&aci).ok_or(InvalidField("aci"))?;
+        let state = match state.ok_or(InvalidField("state"))? {
+            ProtoState::AwaitingPin(recovery_session::AwaitingPin { tries }) => {
+                State::AwaitingPin {
+                    tries_remaining: tries.map(|recovery_session::Tries { remaining }| remaining),
+                }
+            }
+            ProtoState::AwaitingAccountEntropyPool(
+                recovery_session::AwaitingAccountEntropyPool {},
+            ) => State::AwaitingAccountEntropyPool,
+            ProtoState::AwaitingBackupRestore(recovery_session::AwaitingBackupRestore { root }) => {
+                State::AwaitingBackupRestore {
+                    root: Root::from_proto(root)?,
+                }
+            }
+            ProtoState::Complete(recovery_session::Complete {
+                root,
+                forward_secrecy_token,
+                next_backup_data,
+            }) => State::Complete(RecoveredKeys {
+                aci,
+                root: Root::from_proto(root)?,
+                forward_secrecy: if forward_secrecy_token.is_empty() {
+                    None
+                } else {
+                    Some(RestoredForwardSecrecy {
+                        token: forward_secrecy_token
+                            .try_into()
+                            .map_err(|_| InvalidField("forward_secrecy_token"))?,
+                        next_backup_data,
+                    })
+                },
+            }),
+        };
+        Ok(Self { aci, state })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+
+    use assert_matches::assert_matches;
+    use async_trait::async_trait;
+    use futures_util::FutureExt as _;
+    use libsignal_svrb::{Backup4, Secret};
+
+    use super::*;
+    use crate::proto::svr2 as svr2_proto;
+
+    const ACI: Aci = Aci::from_uuid_bytes([0x11; 16]);
+    const PIN: &[u8] = b"1234";
+    const AEP: &str = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+    const OTHER_AEP: &str = "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb";
+
+    fn aep(s: &str) -> AccountEntropyPool {
+        AccountEntropyPool::from_str(s).expect("valid")
+    }
+
+    fn pin_hash(pin: &[u8]) -> PinHash {
+        PinHash::create(pin, &[0x22; 32]).expect("can hash")
+    }
+
+    /// A fake SVR2 enclave holding the SVR key for [`AEP`], protected by [`PIN`].
+    struct FakeSvr2<'a> {
+        stored: Option<[u8; 48]>,
+        tries: &'a Cell<u32>,
+    }
+
+    impl<'a> FakeSvr2<'a> {
+        fn with_key(tries: &'a Cell<u32>) -> Self {
+            let stored = pin_hash(PIN).encode_master_key(&aep(AEP).derive_svr_key());
+            Self {
+                stored: Some(stored),
+                tries,
+            }
+        }
+
+        fn empty(tries: &'a Cell<u32>) -> Self {
+            Self {
+                stored: None,
+                tries,
+            }
+        }
+    }
+
+    impl Svr2Protocol for FakeSvr2<'_> {
+        async fn exchange(
+            &mut self,
+            request: svr2_proto::Request,
+        ) -> Result<svr2_proto::Response, svr2::Error> {
+            use svr2_proto::restore_response::Status;
+            let Some(svr2_proto::request::Inner::Restore(svr2_proto::RestoreRequest { pin })) =
+                request.inner
+            else {
+                panic!("unexpected request: {request:?}");
+            };
+            let (status, data) = match self.stored {
+                None => (Status::Missing, vec![]),
+                Some(_) if self.tries.get() == 0 => (Status::Missing, vec![]),
+                Some(stored) if pin == pin_hash(PIN).access_key => {
+                    self.tries.set(5);
+                    (Status::Ok, stored.to_vec())
+                }
+                Some(_) => {
+                    self.tries.set(self.tries.get() - 1);
+                    (Status::PinMismatch, vec![])
+                }
+            };
+            Ok(svr2_proto::Response {
+                inner: Some(svr2_proto::response::Inner::Restore(
+                    svr2_proto::RestoreResponse {
+                        status: status.into(),
+                        data,
+                        tries: self.tries.get(),
+                    },
+                )),
+            })
+        }
+    }
+
+    fn submit_pin(
+        recovery: &mut AccountRecovery,
+        pin: &[u8],
+        svr2: FakeSvr2<'_>,
+    ) -> Result<RecoveryStep, Error> {
+        recovery
+            .submit_pin(&pin_hash(pin), std::future::ready(Ok(svr2)))
+            .now_or_never()
+            .expect("sync")
+    }
+
+    /// A fake SVR-B enclave that always hands back the same secret.
+    struct FakeSvrB;
+
+    const SVRB_SECRET: Secret = [0x33; 32];
+
+    impl svrb::traits::Prepare for FakeSvrB {
+        fn prepare(&self, _password: &[u8]) -> Backup4 {
+            Backup4 {
+                requests: vec![],
+                output: SVRB_SECRET,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl svrb::traits::Backup for FakeSvrB {
+        async fn finalize(&self, _backup: &Backup4) -> Result<(), svrb::Error> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl svrb::traits::Remove for FakeSvrB {
+        async fn remove(&self) -> Result<(), svrb::Error> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl svrb::traits::Restore for FakeSvrB {
+        async fn restore(&self, _password: &[u8]) -> Result<Secret, svrb::Error> {
+            Ok(SVRB_SECRET)
+        }
+    }
+
+    async fn make_backup() -> svrb::BackupStoreResponse {
+        let backup_key = BackupKey::derive_from_account_entropy_pool(&aep(AEP));
+        svrb::store_backup(
+            &[FakeSvrB],
+            &[] as &[FakeSvrB],
+            &backup_key,
+            svrb::create_new_backup_chain(&FakeSvrB, &backup_key).as_ref(),
+        )
+        .await
+        .expect("can store")
+    }
+
+    fn round_trip(recovery: &AccountRecovery) -> AccountRecovery {
+        let resumed =
+            AccountRecovery::deserialize(&recovery.serialize()).expect("valid serialization");
+        assert_eq!(resumed.step(), recovery.step());
+        resumed
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn recover_with_pin() {
+        let tries = Cell::new(5);
+        let backup = make_backup().await;
+
+        let mut recovery = AccountRecovery::new(ACI);
+        assert_eq!(
+            submit_pin(&mut recovery, b"0000", FakeSvr2::with_key(&tries)).expect("no error"),
+            RecoveryStep::AwaitingPin {
+                tries_remaining: Some(4)
+            }
+        );
+        let mut recovery = round_trip(&recovery);
+        assert_eq!(
+            submit_pin(&mut recovery, PIN, FakeSvr2::with_key(&tries)).expect("no error"),
+            RecoveryStep::AwaitingBackupRestore {
+                has_account_entropy_pool: false
+            }
+        );
+        let svr_key = recovery.svr_key().expect("restored");
+        assert_eq!(
+            svr_key.derive_registration_lock(),
+            SvrKey::new(aep(AEP).derive_svr_key()).derive_registration_lock()
+        );
+
+        let mut recovery = round_trip(&recovery);
+        assert_matches!(
+            recovery
+                .restore_backup(&[FakeSvrB], backup.metadata.as_ref())
+                .await,
+            Err(Error::AccountEntropyPoolRequired)
+        );
+        assert_matches!(
+            recovery.submit_account_entropy_pool(aep(OTHER_AEP)),
+            Err(Error::AccountEntropyPoolMismatch)
+        );
+        assert_eq!(
+            recovery
+                .submit_account_entropy_pool(aep(AEP))
+                .expect("matches"),
+            RecoveryStep::AwaitingBackupRestore {
+                has_account_entropy_pool: true
+            }
+        );
+
+        let mut recovery = round_trip(&recovery);
+        assert_eq!(
+            recovery
+                .restore_backup(&[FakeSvrB], backup.metadata.as_ref())
+                .await
+                .expect("can restore"),
+            RecoveryStep::Complete
+        );
+
+        let recovery = round_trip(&recovery);
+        let keys = recovery.recovered_keys().expect("complete");
+        assert_eq!(
+            keys.forward_secrecy_token().expect("restored").0,
+            backup.forward_secrecy_token.0
+        );
+        assert!(keys.next_backup_data().is_some());
+        assert_eq!(
+            keys.backup_id().expect("has pool").0,
+            BackupKey::derive_from_account_entropy_pool(&aep(AEP))
+                .derive_backup_id(&ACI)
+                .0
+        );
+    }
+
+    #[test]
+    fn recover_with_pin_only() {
+        let tries = Cell::new(5);
+        let mut recovery = AccountRecovery::new(ACI);
+        submit_pin(&mut recovery, PIN, FakeSvr2::with_key(&tries)).expect("no error");
+        let mut recovery = round_trip(&recovery);
+        assert_eq!(
+            recovery.skip_backup_restore().expect("valid step"),
+            RecoveryStep::Complete
+        );
+
+        let recovery = round_trip(&recovery);
+        let keys = recovery.recovered_keys().expect("complete");
+        assert_eq!(
+            keys.svr_key().derive_registration_lock(),
+            SvrKey::new(aep(AEP).derive_svr_key()).derive_registration_lock()
+        );
+        assert!(keys.account_entropy_pool().is_none());
+        assert!(keys.backup_id().is_none());
+        assert!(keys.forward_secrecy_token().is_none());
+    }
+
+    #[test]
+    fn falls_back_to_account_entropy_pool() {
+        // Out of tries.
+        let tries = Cell::new(1);
+        let mut recovery = AccountRecovery::new(ACI);
+        assert_eq!(
+            submit_pin(&mut recovery, b"0000", FakeSvr2::with_key(&tries)).expect("no error"),
+            RecoveryStep::AwaitingAccountEntropyPool
+        );
+
+        // Nothing stored.
+        let mut recovery = AccountRecovery::new(ACI);
+        assert_eq!(
+            submit_pin(&mut recovery, PIN, FakeSvr2::empty(&tries)).expect("no error"),
+            RecoveryStep::AwaitingAccountEntropyPool
+        );
+
+        // Any pool is accepted when there's nothing to check it against.
+        recovery
+            .submit_account_entropy_pool(aep(OTHER_AEP))
+            .expect("nothing to mismatch");
+        recovery.skip_backup_restore().expect("valid step");
+        let keys = recovery.recovered_keys().expect("complete");
+        assert!(keys.forward_secrecy_token().is_none());
+        assert!(keys.next_backup_data().is_none());
+        assert_eq!(
+            keys.account_entropy_pool().expect("provided").to_string(),
+            OTHER_AEP
+        );
+    }
+
+    #[test]
+    fn errors_leave_step_unchanged() {
+        let mut recovery = AccountRecovery::new(ACI);
+        assert_matches!(
+            recovery
+                .submit_pin(
+                    &pin_hash(PIN),
+                    std::future::ready(Err::<FakeSvr2<'_>, _>(
+                        svr2::Error::AllConnectionAttemptsFailed
+                    )),
+                )
+                .now_or_never()
+                .expect("sync"),
+            Err(Error::Svr2(svr2::Error::AllConnectionAttemptsFailed))
+        );
+        assert_eq!(
+            recovery.step(),
+            RecoveryStep::AwaitingPin {
+                tries_remaining: None
+            }
+        );
+
+        assert_matches!(
+            recovery.submit_account_entropy_pool(aep(AEP)),
+            Err(Error::WrongStep(RecoveryStep::AwaitingPin { .. }))
+        );
+        assert_matches!(
+            recovery.skip_backup_restore(),
+            Err(Error::WrongStep(RecoveryStep::AwaitingPin { .. }))
+        );
+        recovery.skip_pin().expect("valid step");
+        assert_matches!(
+            recovery.skip_pin(),
+            Err(Error::WrongStep(RecoveryStep::AwaitingAccountEntropyPool))
+        );
+        assert_matches!(
+            recovery.skip_backup_restore(),
+            Err(Error::WrongStep(RecoveryStep::AwaitingAccountEntropyPool))
+        );
+        assert_eq!(recovery.step(), RecoveryStep::AwaitingAccountEntropyPool);
+
+        recovery
+            .submit_account_entropy_pool(aep(AEP))
+            .expect("valid step");
+        assert_matches!(
+            recovery.submit_account_entropy_pool(aep(AEP)),
+            Err(Error::WrongStep(RecoveryStep::AwaitingBackupRestore {
+                has_account_entropy_pool: true
+            }))
+        );
+    }
+
+    #[test]
+    fn deserialize_rejects_bad_input() {
+        assert_matches!(
+            AccountRecovery::deserialize(&[0; 42]),
+            Err(StoredRecoveryDecodeError::Decode(_))
+        );
+        let missing_state = proto::RecoverySession {
+            aci: ACI.service_id_binary(),
+            state: None,
+        }
+        .encode_to_vec();
+        assert_matches!(
+            AccountRecovery::deserialize(&missing_state),
+            Err(StoredRecoveryDecodeError::InvalidField("state"))
+        );
+        let bad_pool = proto::RecoverySession {
+            aci: ACI.service_id_binary(),
+            state: Some(proto::recovery_session::State::AwaitingBackupRestore(
+                proto::recovery_session::AwaitingBackupRestore {
+                    root: Some(proto::recovery_session::Root {
+                        kind: Some(proto::recovery_session::root::Kind::AccountEntropyPool(
+                            "too short".to_owned(),
+                        )),
+                    }),
+                },
+            )),
+        }
+        .encode_to_vec();
+        assert_matches!(
+            AccountRecovery::deserialize(&bad_pool),
+            Err(StoredRecoveryDecodeError::InvalidField(
+                "account_entropy_pool"
+            ))
+        );
+        let missing_root = proto::RecoverySession {
+            aci: ACI.service_id_binary(),
+            state: Some(proto::recovery_session::State::Complete(
+                proto::recovery_session::Complete::default(),
+            )),
+        }
+        .encode_to_vec();
+        assert_matches!(
+            AccountRecovery::deserialize(&missing_root),
+            Err(StoredRecoveryDecodeError::InvalidField("root"))
+        );
+    }
+}
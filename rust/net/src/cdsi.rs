@@ -24,6 +24,11 @@ use crate::connect_state::{ConnectionResources, ServiceName, WebSocketTransportC
 use crate::enclave::{EnclaveKind, EndpointParams, NewHandshake};
 use crate::proto::cds2::{ClientRequest, ClientResponse};
 
+mod discovery;
+#[cfg(any(test, feature = "test-util"))]
+pub mod fake;
+pub use discovery::{ContactDiscovery, DiscoveryChanges, DiscoveryState, InvalidDiscoveryState};
+
 trait FixedLengthSerializable {
     const SERIALIZED_LEN: usize;
 
@@ -84,6 +89,8 @@ impl FixedLengthSerializable for AciAndAccessKey {
 pub struct LookupRequest {
     pub new_e164s: Vec<E164>,
     pub prev_e164s: Vec<E164>,
+    /// Previously-queried E164s that should no longer be covered by the returned token.
+    pub discard_e164s: Vec<E164>,
     pub acis_and_access_keys: Vec<AciAndAccessKey>,
     pub token: Box<[u8]>,
 }
@@ -93,6 +100,7 @@ impl LookupRequest {
         let Self {
             new_e164s,
             prev_e164s,
+            discard_e164s,
             acis_and_access_keys,
             token,
         } = self;
@@ -100,15 +108,16 @@ impl LookupRequest {
         let aci_uak_pairs = acis_and_access_keys.into_iter().collect_serialized();
         let new_e164s = new_e164s.into_iter().collect_serialized();
         let prev_e164s = prev_e164s.into_iter().collect_serialized();
+        let discard_e164s = discard_e164s.into_iter().collect_serialized();
 
         ClientRequest {
             aci_uak_pairs,
             new_e164s,
             prev_e164s,
             token: token.into_vec(),
+            // The token is acknowledged separately, in ClientResponseCollector::collect.
             token_ack: false,
-            // TODO: use these for supporting non-desktop client requirements.
-            discard_e164s: Vec::new(),
+            discard_e164s,
         }
     }
 }
@@ -387,6 +396,7 @@ impl ClientResponseCollector {
 struct LookupRequestDebugInfo {
     new_e164s: usize,
     prev_e164s: usize,
+    discard_e164s: usize,
     acis_and_access_keys: usize,
     token: usize,
 }
@@ -396,6 +406,7 @@ impl std::fmt::Display for LookupRequestDebugInfo {
         f.debug_struct("LookupRequestDebugInfo")
             .field("new_e164s", &self.new_e164s)
             .field("prev_e164s", &self.prev_e164s)
+            .field("discard_e164s", &self.discard_e164s)
             .field("acis_and_access_keys", &self.acis_and_access_keys)
             .field("token", &self.token)
             .finish()
@@ -409,12 +420,14 @@ impl From<&LookupRequest> for LookupRequestDebugInfo {
         let LookupRequest {
             new_e164s,
             prev_e164s,
+            discard_e164s,
             acis_and_access_keys,
             token,
         } = value;
         Self {
             new_e164s: new_e164s.len(),
             prev_e164s: prev_e164s.len(),
+            discard_e164s: discard_e164s.len(),
             acis_and_access_keys: acis_and_access_keys.len(),
             token: token.len(),
         }
@@ -803,7 +816,8 @@ mod test {
         let request = LookupRequest {
             token: b"valid but ignored token".as_slice().into(),
             new_e164s: large_number_of_e164s.clone(),
-            prev_e164s: large_number_of_e164s,
+            prev_e164s: large_number_of_e164s.clone(),
+            discard_e164s: large_number_of_e164s,
             acis_and_access_keys: (1..=LARGE_NUMBER_OF_ENTRIES)
                 .map(|i| {
                     let mut bytes = [0; 16];
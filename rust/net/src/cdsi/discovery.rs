@@ -0,0 +1,538 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Incremental contact discovery on top of [`CdsiConnection`].
+//!
+//! A full CDSI lookup is expensive in terms of rate limit permits. Clients that sync their
+//! contact list periodically should instead hold on to the token from the previous lookup,
+//! along with the set of E164s it covered, so that the next lookup only pays for numbers that
+//! weren't queried before. [`ContactDiscovery`] manages that bookkeeping.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use libsignal_core::E164;
+use libsignal_net_infra::errors::RetryLater;
+
+use super::{
+    AciAndAccessKey, CdsiConnection, CollectSerialized as _, FixedLengthSerializable as _,
+    LookupError, LookupRequest, LookupResponse, LookupResponseEntry, Token,
+};
+
+/// Everything that needs to be persisted between contact discovery syncs.
+///
+/// Use [`serialize`](Self::serialize) and [`deserialize`](Self::deserialize) to store it.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct DiscoveryState {
+    token: Option<Box<[u8]>>,
+    /// Results for every E164 covered by `token`.
+    records: BTreeMap<E164, LookupResponseEntry>,
+    /// Set when the server rejected a lookup for exceeding the rate limit.
+    retry_after: Option<SystemTime>,
+}
+
+/// The stored form of a [`DiscoveryState`] could not be parsed.
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub struct InvalidDiscoveryState;
+
+/// The result of a successful [`ContactDiscovery::sync`].
+#[derive(Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct DiscoveryChanges {
+    /// Contacts that were not in the previous sync, or whose ACI or PNI has changed since.
+    pub updated: Vec<LookupResponseEntry>,
+    /// E164s from the previous sync that are no longer in the contact list.
+    pub removed: Vec<E164>,
+    pub debug_permits_used: i32,
+}
+
+/// Runs CDSI lookups for a changing contact list, spending as few rate limit permits as
+/// possible.
+pub struct ContactDiscovery {
+    state: DiscoveryState,
+}
+
+const STATE_VERSION: u8 = 1;
+
+impl DiscoveryState {
+    /// The token from the last successful lookup, if any.
+    pub fn token(&self) -> Option<&[u8]> {
+        self.token.as_deref()
+    }
+
+    /// The results of the last successful lookup.
+    pub fn records(&self) -> impl ExactSizeIterator<Item = &LookupResponseEntry> {
+        self.records.values()
+    }
+
+    /// If the server has asked for lookups to be delayed, the earliest time to try again.
+    pub fn retry_after(&self) -> Option<SystemTime> {
+        self.retry_after
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let Self {
+            token,
+            records,
+            retry_after,
+        } = self;
+
+        let retry_after_millis = retry_after.map_or(0, |retry_after| {
+            retry_after
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis()
+                .try_into()
+                .unwrap_or(u64::MAX)
+        });
+        let token = token.as_deref().unwrap_or_default();
+        let token_len = u32::try_from(token.len()).expect("token is a reasonable size");
+
+        let mut serialized = vec![STATE_VERSION];
+        serialized.extend_from_slice(&retry_after_millis.to_be_bytes());
+        serialized.extend_from_slice(&token_len.to_be_bytes());
+        serialized.extend_from_slice(token);
+        serialized.extend(records.values().cloned().collect_serialized());
+        serialized
+    }
+
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, InvalidDiscoveryState> {
+        let (&version, bytes) = bytes.split_first().ok_or(InvalidDiscoveryState)?;
+        if version != STATE_VERSION {
+            return Err(InvalidDiscoveryState);
+        }
+        let (retry_after_millis, bytes) = bytes.split_first_chunk().ok_or(InvalidDiscoveryState)?;
+        let (token_len, bytes) = bytes.split_first_chunk().ok_or(InvalidDiscoveryState)?;
+        let token_len =
+            usize::try_from(u32::from_be_bytes(*token_len)).map_err(|_| InvalidDiscoveryState)?;
+        let (token, bytes) = bytes
+            .split_at_checked(token_len)
+            .ok_or(InvalidDiscoveryState)?;
+
+        let (record_chunks, remainder) =
+            bytes.as_chunks::<{ LookupResponseEntry::SERIALIZED_LEN }>();
+        if !remainder.is_empty() {
+            return Err(InvalidDiscoveryState);
+        }
+        let records = record_chunks
+            .iter()
+            .map(|chunk| {
+                LookupResponseEntry::try_parse_from(chunk)
+                    .map(|entry| (entry.e164, entry))
+                    .ok_or(InvalidDiscoveryState)
+            })
+            .collect::<Result<_, _>>()?;
+
+        let retry_after = match u64::from_be_bytes(*retry_after_millis) {
+            0 => None,
+            millis => Some(UNIX_EPOCH + Duration::from_millis(millis)),
+        };
+
+        Ok(Self {
+            token: (!token.is_empty()).then(|| token.into()),
+            records,
+            retry_after,
+        })
+    }
+}
+
+impl ContactDiscovery {
+    pub fn new(state: DiscoveryState) -> Self {
+        Self { state }
+    }
+
+    /// The current state, which should be persisted after every sync, successful or not.
+    pub fn state(&self) -> &DiscoveryState {
+        &self.state
+    }
+
+    pub fn into_state(self) -> DiscoveryState {
+        self.state
+    }
+
+    /// Looks up `contacts` over `connection`, reusing the results of the previous sync.
+    ///
+    /// `now` is used to honor (and record) rate limits from the server.
+    pub async fn sync(
+        &mut self,
+        connection: CdsiConnection,
+        contacts: impl IntoIterator<Item = E164>,
+        acis_and_access_keys: Vec<AciAndAccessKey>,
+        now: SystemTime,
+    ) -> Result<DiscoveryChanges, LookupError> {
+        let contacts = contacts.into_iter().collect();
+        let request = self.prepare_request(&contacts, acis_and_access_keys, now)?;
+
+        let result = async {
+            let (token, collector) = connection.send_request(request).await?;
+            let response = collector.collect().await?;
+            Ok::<_, LookupError>((token, response))
+        }
+        .await;
+
+        match result {
+            Ok((token, response)) => Ok(self.apply_response(&contacts, token, response)),
+            Err(e) => {
+                self.record_failure(&e, now);
+                Err(e)
+            }
+        }
+    }
+
+    /// Builds the request to look up `contacts`.
+    ///
+    /// Fails with [`LookupError::RateLimited`] if the server's requested delay hasn't elapsed yet.
+    fn prepare_request(
+        &self,
+        contacts: &BTreeSet<E164>,
+        acis_and_access_keys: Vec<AciAndAccessKey>,
+        now: SystemTime,
+    ) -> Result<LookupRequest, LookupError> {
+        let DiscoveryState {
+            token,
+            records,
+            retry_after,
+        } = &self.state;
+
+        if let Some(remaining) = retry_after
+            .and_then(|retry_after| retry_after.duration_since(now).ok())
+            .filter(|remaining| !remaining.is_zero())
+        {
+            return Err(LookupError::RateLimited(RetryLater {
+                retry_after_seconds: remaining
+                    .as_millis()
+                    .div_ceil(1000)
+                    .try_into()
+                    .unwrap_or(u32::MAX),
+            }));
+        }
+
+        let Some(token) = token else {
+            return Ok(LookupRequest {
+                new_e164s: contacts.iter().copied().collect(),
+                acis_and_access_keys,
+                ..Default::default()
+            });
+        };
+
+        // The token covers exactly the previously-queried numbers, so all of them have to be sent
+        // back, even the ones being discarded.
+        Ok(LookupRequest {
+            new_e164s: contacts
+                .iter()
+                .filter(|e164| !records.contains_key(e164))
+                .copied()
+                .collect(),
+            prev_e164s: records.keys().copied().collect(),
+            discard_e164s: records
+                .keys()
+                .filter(|e164| !contacts.contains(e164))
+                .copied()
+                .collect(),
+            acis_and_access_keys,
+            token: token.clone(),
+        })
+    }
+
+    fn apply_response(
+        &mut self,
+        contacts: &BTreeSet<E164>,
+        token: Token,
+        response: LookupResponse,
+    ) -> DiscoveryChanges {
+        let LookupResponse {
+            records,
+            debug_permits_used,
+        } = response;
+
+        let mut results: BTreeMap<_, _> = records
+            .into_iter()
+            .filter(|entry| contacts.contains(&entry.e164))
+            .map(|entry| (entry.e164, entry))
+            .collect();
+        // The server should return an entry for every number, but treat any it leaves out as
+        // unregistered.
+        for &e164 in contacts {
+            results.entry(e164).or_insert(LookupResponseEntry {
+                e164,
+                aci: None,
+                pni: None,
+            });
+        }
+
+        let previous = &self.state.records;
+        let updated = results
+            .values()
+            .filter(|entry| {
+                previous
+                    .get(&entry.e164)
+                    .is_none_or(|old| old.aci != entry.aci || old.pni != entry.pni)
+            })
+            .cloned()
+            .collect();
+        let removed = previous
+            .keys()
+            .filter(|e164| !contacts.contains(e164))
+            .copied()
+            .collect();
+
+        self.state = DiscoveryState {
+            token: Some(token.0),
+            records: results,
+            retry_after: None,
+        };
+
+        DiscoveryChanges {
+            updated,
+            removed,
+            debug_permits_used,
+        }
+    }
+
+    fn record_failure(&mut self, error: &LookupError, now: SystemTime) {
+        match error {
+            LookupError::RateLimited(RetryLater {
+                retry_after_seconds,
+            }) => {
+                self.state.retry_after =
+                    Some(now + Duration::from_secs((*retry_after_seconds).into()));
+            }
+            LookupError::InvalidToken => {
+                log::warn!("CDSI token was rejected; the next sync will be a full lookup");
+                self.state.token = None;
+                self.state.records.clear();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use libsignal_core::{Aci, Pni};
+
+    use super::*;
+
+    fn e164(n: u64) -> E164 {
+        format!("+1800555{n:04}").parse().expect("valid")
+    }
+
+    fn entry(n: u64, aci: Option<u8>, pni: Option<u8>) -> LookupResponseEntry {
+        LookupResponseEntry {
+            e164: e164(n),
+            aci: aci.map(|b| Aci::from_uuid_bytes([b; 16])),
+            pni: pni.map(|b| Pni::from_uuid_bytes([b; 16])),
+        }
+    }
+
+    fn contacts(numbers: &[u64]) -> BTreeSet<E164> {
+        numbers.iter().copied().map(e164).collect()
+    }
+
+    fn e164s(numbers: &[u64]) -> Vec<E164> {
+        numbers.iter().copied().map(e164).collect()
+    }
+
+    fn now() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+    }
+
+    #[test]
+    fn first_sync_is_a_full_lookup() {
+        let mut discovery = ContactDiscovery::new(DiscoveryState::default());
+        let contacts = contacts(&[1, 2, 3]);
+
+        let request = discovery
+            .prepare_request(&contacts, vec![], now())
+            .expect("not rate limited");
+        assert_eq!(request.new_e164s, e164s(&[1, 2, 3]));
+        assert_eq!(request.prev_e164s, vec![]);
+        assert_eq!(request.discard_e164s, vec![]);
+        assert!(request.token.is_empty());
+
+        let changes = discovery.apply_response(
+            &contacts,
+            Token(b"token".as_slice().into()),
+            LookupResponse {
+                records: vec![entry(1, Some(1), Some(1)), entry(2, None, Some(2))],
+                debug_permits_used: 3,
+            },
+        );
+        assert_eq!(
+            changes,
+            DiscoveryChanges {
+                updated: vec![
+                    entry(1, Some(1), Some(1)),
+                    entry(2, None, Some(2)),
+                    entry(3, None, None)
+                ],
+                removed: vec![],
+                debug_permits_used: 3,
+            }
+        );
+        assert_eq!(discovery.state().token(), Some(b"token".as_slice()));
+        assert_eq!(discovery.state().records().len(), 3);
+    }
+
+    #[test]
+    fn later_syncs_are_incremental() {
+        let mut discovery = ContactDiscovery::new(DiscoveryState::default());
+        let first_contacts = contacts(&[1, 2, 3]);
+        discovery.apply_response(
+            &first_contacts,
+            Token(b"first".as_slice().into()),
+            LookupResponse {
+                records: vec![
+                    entry(1, Some(1), Some(1)),
+                    entry(2, None, Some(2)),
+                    entry(3, None, None),
+                ],
+                debug_permits_used: 3,
+            },
+        );
+
+        let second_contacts = contacts(&[2, 3, 4]);
+        let request = discovery
+            .prepare_request(&second_contacts, vec![], now())
+            .expect("not rate limited");
+        assert_eq!(request.new_e164s, e164s(&[4]));
+        assert_eq!(request.prev_e164s, e164s(&[1, 2, 3]));
+        assert_eq!(request.discard_e164s, e164s(&[1]));
+        assert_eq!(&*request.token, b"first");
+
+        let changes = discovery.apply_response(
+            &second_contacts,
+            Token(b"second".as_slice().into()),
+            LookupResponse {
+                records: vec![
+                    // Not requested, so should be ignored.
+                    entry(1, Some(1), Some(1)),
+                    entry(2, Some(2), Some(2)),
+                    entry(3, None, None),
+                    entry(4, None, Some(4)),
+                ],
+                debug_permits_used: 1,
+            },
+        );
+        assert_eq!(
+            changes,
+            DiscoveryChanges {
+                updated: vec![entry(2, Some(2), Some(2)), entry(4, None, Some(4))],
+                removed: e164s(&[1]),
+                debug_permits_used: 1,
+            }
+        );
+        assert_eq!(discovery.state().token(), Some(b"second".as_slice()));
+        assert_eq!(
+            discovery
+                .state()
+                .records()
+                .map(|e| e.e164)
+                .collect::<Vec<_>>(),
+            e164s(&[2, 3, 4])
+        );
+    }
+
+    #[test]
+    fn rate_limit_is_honored() {
+        let mut discovery = ContactDiscovery::new(DiscoveryState::default());
+        discovery.record_failure(
+            &LookupError::RateLimited(RetryLater {
+                retry_after_seconds: 100,
+            }),
+            now(),
+        );
+        assert_eq!(
+            discovery.state().retry_after(),
+            Some(now() + Duration::from_secs(100))
+        );
+
+        let contacts = contacts(&[1]);
+        assert_matches!(
+            discovery.prepare_request(&contacts, vec![], now() + Duration::from_millis(40_500)),
+            Err(LookupError::RateLimited(RetryLater {
+                retry_after_seconds: 60
+            }))
+        );
+        discovery
+            .prepare_request(&contacts, vec![], now() + Duration::from_secs(100))
+            .expect("rate limit has expired");
+    }
+
+    #[test]
+    fn invalid_token_resets_state() {
+        let mut discovery = ContactDiscovery::new(DiscoveryState::default());
+        let contacts = contacts(&[1, 2]);
+        discovery.apply_response(
+            &contacts,
+            Token(b"token".as_slice().into()),
+            LookupResponse {
+                records: vec![entry(1, Some(1), Some(1))],
+                debug_permits_used: 2,
+            },
+        );
+
+        discovery.record_failure(&LookupError::InvalidToken, now());
+        assert_eq!(discovery.state().token(), None);
+        assert_eq!(discovery.state().records().len(), 0);
+
+        let request = discovery
+            .prepare_request(&contacts, vec![], now())
+            .expect("not rate limited");
+        assert_eq!(request.new_e164s, e164s(&[1, 2]));
+        assert_eq!(request.prev_e164s, vec![]);
+    }
+
+    #[test]
+    fn discard_e164s_are_sent() {
+        let request = LookupRequest {
+            prev_e164s: e164s(&[1, 2]),
+            discard_e164s: e164s(&[2]),
+            token: b"token".as_slice().into(),
+            ..Default::default()
+        };
+        let client_request = request.into_client_request();
+        assert_eq!(client_request.discard_e164s, e164(2).to_be_bytes());
+        assert!(!client_request.token_ack);
+    }
+
+    #[test]
+    fn state_round_trip() {
+        let state = DiscoveryState {
+            token: Some(b"token".as_slice().into()),
+            records: [
+                entry(1, Some(1), Some(1)),
+                entry(2, None, Some(2)),
+                entry(3, None, None),
+            ]
+            .into_iter()
+            .map(|entry| (entry.e164, entry))
+            .collect(),
+            retry_after: Some(now()),
+        };
+        let serialized = state.serialize();
+        assert_eq!(
+            DiscoveryState::deserialize(&serialized).expect("valid"),
+            state
+        );
+
+        assert_eq!(
+            DiscoveryState::deserialize(&DiscoveryState::default().serialize()).expect("valid"),
+            DiscoveryState::default()
+        );
+
+        assert_matches!(
+            DiscoveryState::deserialize(&serialized[..serialized.len() - 1]),
+            Err(InvalidDiscoveryState)
+        );
+        assert_matches!(DiscoveryState::deserialize(&[]), Err(InvalidDiscoveryState));
+        assert_matches!(
+            DiscoveryState::deserialize(&[STATE_VERSION + 1]),
+            Err(InvalidDiscoveryState)
+        );
+    }
+}
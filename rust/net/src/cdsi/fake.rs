@@ -0,0 +1,530 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! An in-process CDSI server, for testing contact discovery without network access.
+//!
+//! The server runs the enclave side of the attested connection using the test attestation data
+//! in [`attest::sgx_session::testutil`], and answers lookups from a configurable directory. Like
+//! the real service, it checks that tokens match the previously-queried E164s, charges rate limit
+//! permits only for numbers the token doesn't already cover, and closes the connection with the
+//! appropriate CDSI close codes on failure.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use hmac::{Hmac, KeyInit as _, Mac as _};
+use libsignal_core::{Aci, E164, Pni};
+use libsignal_net_infra::ws::NextOrClose;
+use libsignal_net_infra::ws::attested::AttestedConnection;
+use libsignal_net_infra::ws::attested::testutil::{AttestedServerOutput, run_attested_server};
+use libsignal_net_infra::ws::testutil::fake_websocket;
+use prost::Message as _;
+use sha2::Sha256;
+use tungstenite::protocol::CloseFrame;
+use tungstenite::protocol::frame::coding::CloseCode;
+use uuid::Uuid;
+
+use super::{
+    CdsiCloseCode, CdsiConnection, CollectSerialized as _, FixedLengthSerializable as _,
+    LookupResponseEntry,
+};
+use crate::proto::cds2::{ClientRequest, ClientResponse};
+
+const FAKE_WS_CONFIG: libsignal_net_infra::ws::Config = libsignal_net_infra::ws::Config {
+    local_idle_timeout: Duration::from_secs(60),
+    remote_idle_ping_timeout: Duration::from_secs(60),
+    remote_idle_disconnect_timeout: Duration::from_secs(120),
+};
+
+/// An account registered with a [`FakeCdsiServer`].
+#[derive(Clone, Debug)]
+pub struct FakeCdsiAccount {
+    pub pni: Pni,
+    pub aci: Aci,
+    /// The ACI is only revealed to requests that include this access key.
+    pub access_key: [u8; 16],
+}
+
+/// Rate limit state for a [`FakeCdsiServer`].
+#[derive(Clone, Copy, Debug)]
+pub struct FakeRateLimit {
+    /// The number of E164s that can still be looked up.
+    pub remaining_permits: usize,
+    /// Sent to clients that exceed the limit.
+    pub retry_after_seconds: u32,
+}
+
+/// An in-process CDSI server; see the [module-level documentation](self).
+///
+/// Cloning produces a handle to the same server.
+#[derive(Clone)]
+pub struct FakeCdsiServer {
+    state: Arc<Mutex<ServerState>>,
+}
+
+struct ServerState {
+    token_key: [u8; 32],
+    directory: HashMap<E164, FakeCdsiAccount>,
+    rate_limit: Option<FakeRateLimit>,
+    permits_used: usize,
+}
+
+/// Progress of a single connection through the lookup protocol.
+enum ConnectionState {
+    AwaitingRequest,
+    AwaitingTokenAck { response: ClientResponse },
+    Finished,
+}
+
+impl Default for FakeCdsiServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FakeCdsiServer {
+    /// Creates a server with an empty directory and no rate limit.
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(ServerState {
+                token_key: rand::random(),
+                directory: HashMap::new(),
+                rate_limit: None,
+                permits_used: 0,
+            })),
+        }
+    }
+
+    pub fn insert_account(&self, e164: E164, account: FakeCdsiAccount) {
+        self.lock().directory.insert(e164, account);
+    }
+
+    pub fn remove_account(&self, e164: E164) {
+        self.lock().directory.remove(&e164);
+    }
+
+    /// Limits the number of E164s that can be looked up from now on, or removes the limit.
+    pub fn set_rate_limit(&self, rate_limit: Option<FakeRateLimit>) {
+        self.lock().rate_limit = rate_limit;
+    }
+
+    /// The total number of permits charged to clients so far.
+    pub fn permits_used(&self) -> usize {
+        self.lock().permits_used
+    }
+
+    /// Opens a new attested connection to this server.
+    ///
+    /// Each connection supports a single lookup, as with the real service.
+    pub async fn connect(&self) -> CdsiConnection {
+        let (server, client) = fake_websocket().await;
+
+        let mut connection_state = ConnectionState::AwaitingRequest;
+        let server_state = self.state.clone();
+        tokio::spawn(run_attested_server(
+            server,
+            attest::sgx_session::testutil::private_key(),
+            move |frame| {
+                let mut server_state = server_state.lock().expect("not poisoned");
+                connection_state.receive(&mut server_state, frame)
+            },
+        ));
+
+        let connection = AttestedConnection::connect(
+            client,
+            FAKE_WS_CONFIG,
+            "fake cdsi".into(),
+            |_attestation| attest::sgx_session::testutil::handshake_from_tests_data(),
+        )
+        .await
+        .expect("handshake with fake server succeeds");
+        CdsiConnection(connection)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ServerState> {
+        self.state.lock().expect("not poisoned")
+    }
+}
+
+impl ConnectionState {
+    fn receive(
+        &mut self,
+        server: &mut ServerState,
+        frame: NextOrClose<Vec<u8>>,
+    ) -> AttestedServerOutput {
+        let frame = match frame {
+            NextOrClose::Next(frame) => frame,
+            // The client gave up; there's nothing left to say.
+            NextOrClose::Close(_) => {
+                *self = Self::Finished;
+                return AttestedServerOutput::default();
+            }
+        };
+        let Ok(request) = ClientRequest::decode(frame.as_slice()) else {
+            return close(CdsiCloseCode::InvalidArgument, "invalid request");
+        };
+
+        match std::mem::replace(self, Self::Finished) {
+            Self::AwaitingRequest => match server.lookup(request) {
+                Ok((token, response)) => {
+                    *self = Self::AwaitingTokenAck { response };
+                    AttestedServerOutput::message(
+                        ClientResponse {
+                            token,
+                            ..Default::default()
+                        }
+                        .encode_to_vec(),
+                    )
+                }
+                Err(output) => output,
+            },
+            Self::AwaitingTokenAck { response } => {
+                if !request.token_ack {
+                    return close(CdsiCloseCode::InvalidArgument, "expected token ack");
+                }
+                AttestedServerOutput {
+                    message: Some(response.encode_to_vec()),
+                    close_after: Some(None),
+                }
+            }
+            Self::Finished => close(CdsiCloseCode::InvalidArgument, "lookup already finished"),
+        }
+    }
+}
+
+impl ServerState {
+    /// Processes the initial request, producing a new token and the eventual response.
+    fn lookup(
+        &mut self,
+        request: ClientRequest,
+    ) -> Result<(Vec<u8>, ClientResponse), AttestedServerOutput> {
+        let ClientRequest {
+            aci_uak_pairs,
+            prev_e164s,
+            new_e164s,
+            discard_e164s,
+            token,
+            token_ack,
+        } = request;
+
+        if token_ack {
+            return Err(close(
+                CdsiCloseCode::InvalidArgument,
+                "token ack before request",
+            ));
+        }
+
+        let parse_e164s = |bytes: &[u8]| -> Result<BTreeSet<E164>, AttestedServerOutput> {
+            let (chunks, remainder) = bytes.as_chunks::<{ E164::SERIALIZED_LEN }>();
+            if !remainder.is_empty() {
+                return Err(close(CdsiCloseCode::InvalidArgument, "invalid e164s"));
+            }
+            chunks
+                .iter()
+                .map(|chunk| {
+                    E164::from_be_bytes(*chunk)
+                        .ok_or_else(|| close(CdsiCloseCode::InvalidArgument, "invalid e164"))
+                })
+                .collect()
+        };
+        let prev_e164s = parse_e164s(&prev_e164s)?;
+        let new_e164s = parse_e164s(&new_e164s)?;
+        let discard_e164s = parse_e164s(&discard_e164s)?;
+
+        let (aci_uak_chunks, remainder) = aci_uak_pairs.as_chunks::<32>();
+        if !remainder.is_empty() {
+            return Err(close(
+                CdsiCloseCode::InvalidArgument,
+                "invalid ACI/UAK pairs",
+            ));
+        }
+        let access_keys: HashMap<Aci, &[u8]> = aci_uak_chunks
+            .iter()
+            .map(|chunk| {
+                let (aci, access_key) = chunk.split_at(Uuid::SERIALIZED_LEN);
+                (
+                    Aci::from_uuid_bytes(aci.try_into().expect("correct length")),
+                    access_key,
+                )
+            })
+            .collect();
+
+        // Numbers covered by a valid token are free; everything else costs a permit.
+        let permits_needed = if token.is_empty() {
+            prev_e164s.len() + new_e164s.len()
+        } else if token == self.token_for(&prev_e164s) {
+            new_e164s.len()
+        } else {
+            return Err(close(CdsiCloseCode::InvalidToken, ""));
+        };
+
+        if let Some(rate_limit) = &mut self.rate_limit {
+            if permits_needed > rate_limit.remaining_permits {
+                return Err(close(
+                    CdsiCloseCode::RateLimitExceeded,
+                    &format!(r#"{{"retry_after": {}}}"#, rate_limit.retry_after_seconds),
+                ));
+            }
+            rate_limit.remaining_permits -= permits_needed;
+        }
+        self.permits_used += permits_needed;
+
+        let queried: BTreeSet<E164> = prev_e164s
+            .difference(&discard_e164s)
+            .copied()
+            .chain(new_e164s)
+            .collect();
+
+        let e164_pni_aci_triples = queried
+            .iter()
+            .map(|&e164| {
+                let account = self.directory.get(&e164);
+                let aci = account
+                    .filter(|account| {
+                        access_keys
+                            .get(&account.aci)
+                            .is_some_and(|key| *key == account.access_key)
+                    })
+                    .map(|account| account.aci);
+                LookupResponseEntry {
+                    e164,
+                    aci,
+                    pni: account.map(|account| account.pni),
+                }
+            })
+            .collect_serialized();
+
+        Ok((
+            self.token_for(&queried),
+            ClientResponse {
+                e164_pni_aci_triples,
+                token: vec![],
+                debug_permits_used: permits_needed.try_into().unwrap_or(i32::MAX),
+            },
+        ))
+    }
+
+    fn token_for(&self, e164s: &BTreeSet<E164>) -> Vec<u8> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.token_key)
+            .expect("HMAC-SHA256 should accept any size key");
+        for e164 in e164s {
+            mac.update(&e164.to_be_bytes());
+        }
+        mac.finalize().into_bytes().to_vec()
+    }
+}
+
+fn close(code: CdsiCloseCode, reason: &str) -> AttestedServerOutput {
+    AttestedServerOutput::close(Some(CloseFrame {
+        code: CloseCode::Bad(code as u16),
+        reason: reason.into(),
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use assert_matches::assert_matches;
+    use libsignal_net_infra::errors::RetryLater;
+
+    use super::*;
+    use crate::cdsi::{
+        AciAndAccessKey, ContactDiscovery, DiscoveryState, LookupError, LookupRequest,
+    };
+
+    fn e164(n: u64) -> E164 {
+        format!("+1800555{n:04}").parse().expect("valid")
+    }
+
+    fn account(n: u8) -> FakeCdsiAccount {
+        FakeCdsiAccount {
+            pni: Pni::from_uuid_bytes([n; 16]),
+            aci: Aci::from_uuid_bytes([n | 0x80; 16]),
+            access_key: [n; 16],
+        }
+    }
+
+    fn now() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+    }
+
+    #[tokio::test]
+    async fn lookup_reveals_aci_only_with_access_key() {
+        let server = FakeCdsiServer::new();
+        server.insert_account(e164(1), account(1));
+        server.insert_account(e164(2), account(2));
+
+        let (token, collector) = server
+            .connect()
+            .await
+            .send_request(LookupRequest {
+                new_e164s: vec![e164(1), e164(2), e164(3)],
+                acis_and_access_keys: vec![AciAndAccessKey {
+                    aci: account(1).aci,
+                    access_key: account(1).access_key,
+                }],
+                ..Default::default()
+            })
+            .await
+            .expect("request accepted");
+        assert!(!token.0.is_empty());
+
+        let response = collector.collect().await.expect("lookup succeeds");
+        assert_eq!(
+            response.records,
+            vec![
+                LookupResponseEntry {
+                    e164: e164(1),
+                    aci: Some(account(1).aci),
+                    pni: Some(account(1).pni),
+                },
+                LookupResponseEntry {
+                    e164: e164(2),
+                    aci: None,
+                    pni: Some(account(2).pni),
+                },
+                LookupResponseEntry {
+                    e164: e164(3),
+                    aci: None,
+                    pni: None,
+                },
+            ]
+        );
+        assert_eq!(response.debug_permits_used, 3);
+    }
+
+    #[tokio::test]
+    async fn mismatched_token_is_rejected() {
+        let server = FakeCdsiServer::new();
+        let result = server
+            .connect()
+            .await
+            .send_request(LookupRequest {
+                prev_e164s: vec![e164(1)],
+                token: b"not a real token".as_slice().into(),
+                ..Default::default()
+            })
+            .await;
+        assert_matches!(result, Err(LookupError::InvalidToken));
+    }
+
+    #[tokio::test]
+    async fn incremental_discovery_end_to_end() {
+        let server = FakeCdsiServer::new();
+        server.insert_account(e164(1), account(1));
+        server.insert_account(e164(2), account(2));
+
+        let mut discovery = ContactDiscovery::new(DiscoveryState::default());
+        let changes = discovery
+            .sync(
+                server.connect().await,
+                [e164(1), e164(2), e164(3)],
+                vec![],
+                now(),
+            )
+            .await
+            .expect("sync succeeds");
+        assert_eq!(changes.updated.len(), 3);
+        assert_eq!(server.permits_used(), 3);
+
+        // Persist and restore the state, as a client would between syncs.
+        let mut discovery = ContactDiscovery::new(
+            DiscoveryState::deserialize(&discovery.state().serialize()).expect("valid"),
+        );
+
+        // Only the new number should be charged, and only the changes should be reported.
+        server.insert_account(e164(3), account(3));
+        server.insert_account(e164(4), account(4));
+        let changes = discovery
+            .sync(
+                server.connect().await,
+                [e164(2), e164(3), e164(4)],
+                vec![],
+                now(),
+            )
+            .await
+            .expect("sync succeeds");
+        assert_eq!(server.permits_used(), 4);
+        assert_eq!(changes.removed, vec![e164(1)]);
+        assert_eq!(
+            changes
+                .updated
+                .iter()
+                .map(|entry| (entry.e164, entry.pni))
+                .collect::<Vec<_>>(),
+            vec![
+                (e164(3), Some(account(3).pni)),
+                (e164(4), Some(account(4).pni))
+            ]
+        );
+
+        // The token now covers exactly the current contacts.
+        discovery
+            .sync(
+                server.connect().await,
+                [e164(2), e164(3), e164(4)],
+                vec![],
+                now(),
+            )
+            .await
+            .expect("sync succeeds");
+        assert_eq!(server.permits_used(), 4);
+    }
+
+    #[tokio::test]
+    async fn discovery_honors_rate_limit() {
+        let server = FakeCdsiServer::new();
+        server.set_rate_limit(Some(FakeRateLimit {
+            remaining_permits: 1,
+            retry_after_seconds: 30,
+        }));
+
+        let mut discovery = ContactDiscovery::new(DiscoveryState::default());
+        let result = discovery
+            .sync(server.connect().await, [e164(1), e164(2)], vec![], now())
+            .await;
+        assert_matches!(
+            result,
+            Err(LookupError::RateLimited(RetryLater {
+                retry_after_seconds: 30
+            }))
+        );
+        assert_eq!(
+            discovery.state().retry_after(),
+            Some(now() + Duration::from_secs(30))
+        );
+
+        // Until the delay has passed, the client shouldn't even try.
+        server.set_rate_limit(None);
+        let result = discovery
+            .sync(
+                server.connect().await,
+                [e164(1), e164(2)],
+                vec![],
+                now() + Duration::from_secs(10),
+            )
+            .await;
+        assert_matches!(
+            result,
+            Err(LookupError::RateLimited(RetryLater {
+                retry_after_seconds: 20
+            }))
+        );
+        assert_eq!(server.permits_used(), 0);
+
+        discovery
+            .sync(
+                server.connect().await,
+                [e164(1), e164(2)],
+                vec![],
+                now() + Duration::from_secs(30),
+            )
+            .await
+            .expect("sync succeeds");
+        assert_eq!(server.permits_used(), 2);
+    }
+}
@@ -38,6 +38,8 @@ mod error;
 pub use error::{ConnectError, SendError};
 
 pub mod fake;
+pub mod persistent;
+pub mod provisioning;
 pub mod server_requests;
 pub mod ws;
 
@@ -818,6 +820,7 @@ pub(crate) mod test {
                         sni: Host::Domain(CHAT_DOMAIN.into()),
                         alpn: Some(Alpn::Http1_1),
                         min_protocol_version: Some(boring_signal::ssl::SslVersion::TLS1_3),
+                        ech_config: None,
                     },
                     inner: DirectOrProxyRoute::Direct(TcpRoute {
                         address: UnresolvedHost(CHAT_DOMAIN.into()),
@@ -884,6 +887,7 @@ pub(crate) mod test {
                     sni: Host::Domain(CHAT_DOMAIN.into()),
                     alpn: Some(Alpn::Http1_1),
                     min_protocol_version: Some(boring_signal::ssl::SslVersion::TLS1_3),
+                    ech_config: None,
                 },
                 inner: DirectOrProxyRoute::Direct(TcpRoute {
                     address: UnresolvedHost(CHAT_DOMAIN.into()),
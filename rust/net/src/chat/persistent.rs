@@ -0,0 +1,674 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! A chat connection that transparently reconnects.
+//!
+//! [`ChatConnection`] represents a single websocket; once it reports
+//! [`ListenerEvent::Finished`] it's no longer usable. [`PersistentChatConnection`] wraps a sequence
+//! of `ChatConnection`s, establishing a new one whenever the previous one is lost, with backoff
+//! based on [`ConnectionOutcomes`]. Requests sent while disconnected wait for the next connection,
+//! and requests that can be safely repeated are replayed if the connection drops before they
+//! complete.
+
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use futures_util::future::BoxFuture;
+use futures_util::{Stream, StreamExt as _};
+use libsignal_net_infra::route::{
+    AttemptOutcome, ConnectionOutcomeParams, ConnectionOutcomes, RouteDelayPolicy as _,
+    UnsuccessfulOutcome,
+};
+use libsignal_net_infra::utils::NetworkChangeEvent;
+use libsignal_net_infra::ws::connection::NextEventError;
+use tokio::sync::{oneshot, watch};
+use tokio::time::{Duration, Instant};
+use tokio_stream::wrappers::WatchStream;
+use tungstenite::protocol::frame::coding::CloseCode;
+
+use crate::chat::ws::{FinishError, FinishReason, ListenerEvent, TaskExitError};
+use crate::chat::{ChatConnection, ConnectError, ConnectionInfo, Request, Response, SendError, ws};
+use crate::env::{CONNECTED_ELSEWHERE_CLOSE_CODE, CONNECTION_INVALIDATED_CLOSE_CODE};
+
+/// Connections that end sooner than this after being established count as failed attempts for
+/// backoff purposes, so that a server that accepts and immediately drops connections doesn't
+/// cause a reconnect loop.
+const MIN_STABLE_CONNECTION_DURATION: Duration = Duration::from_secs(30);
+
+/// Describes how to make a new [`ChatConnection`] for a [`PersistentChatConnection`].
+///
+/// This trait is a workaround for lack of dyn-compatible `AsyncFn`, which may or may not ever be
+/// provided by the language.
+pub trait ConnectChat: Send + Sync {
+    /// Starts an attempt to connect to the Chat server.
+    ///
+    /// The resulting connection must deliver its events to `listener`.
+    fn connect_chat(
+        &self,
+        listener: ws::EventListener,
+    ) -> BoxFuture<'_, Result<ChatConnection, ConnectError>>;
+}
+
+/// The externally-visible state of a [`PersistentChatConnection`].
+#[derive(Clone, Debug)]
+pub enum PersistentChatState {
+    /// A connection attempt is in progress.
+    Connecting,
+    /// Requests are being sent over an established connection.
+    Connected(ConnectionInfo),
+    /// The last connection attempt failed, or the last connection was lost.
+    ///
+    /// The next attempt will start after `delay`, or sooner if the network changes.
+    WaitingToReconnect { delay: Duration },
+    /// No further connection attempts will be made.
+    Closed(PersistentChatCloseReason),
+}
+
+/// Why a [`PersistentChatConnection`] stopped reconnecting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, displaydoc::Display)]
+pub enum PersistentChatCloseReason {
+    /// the connection was closed locally
+    LocalDisconnect,
+    /// the server disconnected us because we connected elsewhere with the same credentials
+    ConnectedElsewhere,
+    /// the server disconnected us because our credentials are no longer valid
+    ConnectionInvalidated,
+    /// app version is too old
+    AppExpired,
+    /// device was deregistered
+    DeviceDeregistered,
+}
+
+/// A chat connection that reconnects automatically; see the [module-level
+/// documentation](self).
+///
+/// Dropping the `PersistentChatConnection` has the same effect as calling
+/// [`disconnect`](Self::disconnect) without waiting for it to finish.
+pub struct PersistentChatConnection {
+    shared: watch::Receiver<SharedState>,
+    stop: std::sync::Mutex<Option<oneshot::Sender<()>>>,
+}
+
+#[derive(Clone)]
+struct SharedState {
+    state: PersistentChatState,
+    connection: Option<Arc<ChatConnection>>,
+}
+
+impl PersistentChatConnection {
+    /// Starts connecting in the background.
+    ///
+    /// `listener` receives events from every underlying connection except for the
+    /// [`ListenerEvent::Finished`] events of connections that will be replaced. Exactly one
+    /// `Finished` event is delivered, once the `PersistentChatConnection` is
+    /// [`Closed`](PersistentChatState::Closed).
+    pub fn start(
+        tokio_runtime: &tokio::runtime::Handle,
+        connect_chat: Box<dyn ConnectChat + 'static>,
+        reconnect_params: ConnectionOutcomeParams,
+        network_change_event: NetworkChangeEvent,
+        listener: ws::EventListener,
+    ) -> Self {
+        let (shared_tx, shared) = watch::channel(SharedState {
+            state: PersistentChatState::Connecting,
+            connection: None,
+        });
+        let (stop_tx, stop_rx) = oneshot::channel();
+
+        tokio_runtime.spawn(
+            ReconnectTask {
+                connect_chat,
+                outcomes: ConnectionOutcomes::new(reconnect_params),
+                network_change_event,
+                listener: Arc::new(std::sync::Mutex::new(listener)),
+                shared: shared_tx,
+                stop: stop_rx,
+            }
+            .run(),
+        );
+
+        Self {
+            shared,
+            stop: Some(stop_tx).into(),
+        }
+    }
+
+    /// The current state of the connection.
+    pub fn state(&self) -> PersistentChatState {
+        self.shared.borrow().state.clone()
+    }
+
+    /// Produces the current state, followed by subsequent state transitions.
+    ///
+    /// Transitions that happen in quick succession may be coalesced; the most recent state is
+    /// always delivered. The stream ends after producing
+    /// [`Closed`](PersistentChatState::Closed).
+    pub fn state_changes(&self) -> impl Stream<Item = PersistentChatState> + Send + use<> {
+        // The stream ends when the task exits and drops its sender, which happens right after the
+        // final state is published.
+        WatchStream::new(self.shared.clone()).map(|shared| shared.state)
+    }
+
+    /// Sends a request, waiting for a connection if necessary.
+    ///
+    /// If the connection is lost while the request is outstanding, it is resent on the next
+    /// connection only if it's a GET or HEAD request; anything else may already have been acted on
+    /// by the server, so the error is returned instead. `timeout` covers the entire operation,
+    /// including any time spent waiting to connect.
+    pub async fn send(&self, request: Request, timeout: Duration) -> Result<Response, SendError> {
+        let replay = matches!(request.method, http::Method::GET | http::Method::HEAD);
+        self.send_inner(request, timeout, replay).await
+    }
+
+    /// Like [`send`](Self::send), but resends the request after a lost connection regardless of
+    /// its method.
+    ///
+    /// Only use this for requests where the server having processed the request more than once is
+    /// indistinguishable from it having processed it once.
+    pub async fn send_replayable(
+        &self,
+        request: Request,
+        timeout: Duration,
+    ) -> Result<Response, SendError> {
+        self.send_inner(request, timeout, true).await
+    }
+
+    async fn send_inner(
+        &self,
+        request: Request,
+        timeout: Duration,
+        replay: bool,
+    ) -> Result<Response, SendError> {
+        let deadline = Instant::now() + timeout;
+        let mut shared = self.shared.clone();
+        let mut failed_connection = None;
+
+        loop {
+            let connection = tokio::time::timeout_at(
+                deadline,
+                wait_for_connection(&mut shared, failed_connection.as_ref()),
+            )
+            .await
+            .map_err(|_elapsed| SendError::RequestTimedOut)??;
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match connection.send(request.clone(), remaining).await {
+                Err(SendError::Disconnected | SendError::WebSocket(_)) if replay => {
+                    log::info!(
+                        "connection lost during {} request, will retry",
+                        request.method
+                    );
+                    failed_connection = Some(connection);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Stops reconnecting and closes the current connection, if any.
+    pub async fn disconnect(&self) {
+        if let Some(stop) = self.stop.lock().expect("not poisoned").take() {
+            // If the task already exited, there's nothing to stop.
+            let _ignore_closed = stop.send(());
+        }
+        let mut shared = self.shared.clone();
+        // An error means the task is gone, which is just as good.
+        let _ignore_closed = shared
+            .wait_for(|shared| matches!(shared.state, PersistentChatState::Closed(_)))
+            .await;
+    }
+}
+
+/// Waits for an established connection other than `failed_connection`.
+async fn wait_for_connection(
+    shared: &mut watch::Receiver<SharedState>,
+    failed_connection: Option<&Arc<ChatConnection>>,
+) -> Result<Arc<ChatConnection>, SendError> {
+    let shared = shared
+        .wait_for(|shared| match (&shared.state, &shared.connection) {
+            (PersistentChatState::Closed(_), _) => true,
+            (_, Some(connection)) => {
+                failed_connection.is_none_or(|failed| !Arc::ptr_eq(failed, connection))
+            }
+            (_, None) => false,
+        })
+        .await
+        .map_err(|_closed| SendError::Disconnected)?;
+
+    match (&shared.state, &shared.connection) {
+        (PersistentChatState::Closed(reason), _) => Err(match reason {
+            PersistentChatCloseReason::ConnectedElsewhere => SendError::ConnectedElsewhere,
+            PersistentChatCloseReason::ConnectionInvalidated => SendError::ConnectionInvalidated,
+            PersistentChatCloseReason::LocalDisconnect
+            | PersistentChatCloseReason::AppExpired
+            | PersistentChatCloseReason::DeviceDeregistered => SendError::Disconnected,
+        }),
+        (_, connection) => Ok(connection.clone().expect("checked in wait_for")),
+    }
+}
+
+/// Background task that owns the underlying connections.
+struct ReconnectTask {
+    connect_chat: Box<dyn ConnectChat>,
+    outcomes: ConnectionOutcomes<()>,
+    network_change_event: NetworkChangeEvent,
+    listener: Arc<std::sync::Mutex<ws::EventListener>>,
+    shared: watch::Sender<SharedState>,
+    stop: oneshot::Receiver<()>,
+}
+
+/// The final event produced by an underlying connection.
+type FinishResult = Result<FinishReason, FinishError>;
+
+impl ReconnectTask {
+    async fn run(mut self) {
+        let mut retry_after = Duration::ZERO;
+
+        let (reason, finished) = loop {
+            let delay = self
+                .outcomes
+                .compute_delay(&(), Instant::now())
+                .max(std::mem::take(&mut retry_after));
+            if !delay.is_zero() {
+                log::info!("waiting {delay:?} before reconnecting to chat");
+                self.set_state(PersistentChatState::WaitingToReconnect { delay }, None);
+                tokio::select! {
+                    () = tokio::time::sleep(delay) => {}
+                    Ok(()) = self.network_change_event.changed() => {
+                        log::info!("network changed, reconnecting to chat immediately");
+                        self.outcomes.reset(Instant::now());
+                    }
+                    _ = &mut self.stop => break (PersistentChatCloseReason::LocalDisconnect, None),
+                }
+            }
+
+            self.set_state(PersistentChatState::Connecting, None);
+            let (finished_tx, finished_rx) = oneshot::channel();
+            let listener = self.forwarding_listener(finished_tx);
+            let started = Instant::now();
+            let result = tokio::select! {
+                result = self.connect_chat.connect_chat(listener) => result,
+                _ = &mut self.stop => break (PersistentChatCloseReason::LocalDisconnect, None),
+            };
+
+            let connection = match result {
+                Ok(connection) => Arc::new(connection),
+                Err(e) => {
+                    log::warn!("failed to connect to chat: {e}");
+                    match e {
+                        ConnectError::AppExpired => {
+                            break (PersistentChatCloseReason::AppExpired, None);
+                        }
+                        ConnectError::DeviceDeregistered => {
+                            break (PersistentChatCloseReason::DeviceDeregistered, None);
+                        }
+                        ConnectError::RetryLater(later) => {
+                            retry_after = Duration::from_secs(later.retry_after_seconds.into());
+                        }
+                        ConnectError::Timeout
+                        | ConnectError::AllAttemptsFailed
+                        | ConnectError::InvalidConnectionConfiguration
+                        | ConnectError::WebSocket(_) => {}
+                    }
+                    self.record_outcome(started, Err(UnsuccessfulOutcome::ShortTerm));
+                    continue;
+                }
+            };
+
+            let connected_at = Instant::now();
+            self.record_outcome(started, Ok(()));
+            // Changes from before we connected have already been taken into account.
+            self.network_change_event.mark_unchanged();
+            self.set_state(
+                PersistentChatState::Connected(connection.connection_info().clone()),
+                Some(connection.clone()),
+            );
+
+            let finished = tokio::select! {
+                finished = finished_rx => finished.ok(),
+                _ = &mut self.stop => {
+                    connection.disconnect().await;
+                    break (PersistentChatCloseReason::LocalDisconnect, None);
+                }
+            };
+
+            match finished.as_ref().and_then(permanent_close_reason) {
+                Some(reason) => break (reason, finished),
+                None => {
+                    log::info!("chat connection lost, reconnecting");
+                    if connected_at.elapsed() < MIN_STABLE_CONNECTION_DURATION {
+                        self.record_outcome(connected_at, Err(UnsuccessfulOutcome::ShortTerm));
+                    }
+                }
+            }
+        };
+
+        log::info!("persistent chat connection closed: {reason}");
+        self.set_state(PersistentChatState::Closed(reason), None);
+        let finished = finished.unwrap_or(Ok(FinishReason::LocalDisconnect));
+        let mut listener = self.listener.lock().expect("not poisoned");
+        listener(ListenerEvent::Finished(finished));
+    }
+
+    fn set_state(&self, state: PersistentChatState, connection: Option<Arc<ChatConnection>>) {
+        self.shared.send_replace(SharedState { state, connection });
+    }
+
+    fn record_outcome(&mut self, started: Instant, result: Result<(), UnsuccessfulOutcome>) {
+        let finished = Instant::now();
+        self.outcomes.apply_outcome_updates(
+            [(
+                (),
+                AttemptOutcome {
+                    started,
+                    finished,
+                    result,
+                },
+            )],
+            finished,
+            SystemTime::now(),
+        );
+    }
+
+    /// Produces a listener for a single underlying connection.
+    ///
+    /// Events are forwarded to the persistent listener, except for the `Finished` event, which is
+    /// sent to `on_finished` so that the task can decide whether to reconnect.
+    fn forwarding_listener(&self, on_finished: oneshot::Sender<FinishResult>) -> ws::EventListener {
+        let listener = self.listener.clone();
+        let mut on_finished = Some(on_finished);
+        Box::new(move |event| match event {
+            ListenerEvent::Finished(finished) => {
+                if let Some(on_finished) = on_finished.take() {
+                    // The task might have already given up on this connection.
+                    let _ignore_closed = on_finished.send(finished);
+                }
+            }
+            event => {
+                let mut listener = listener.lock().expect("not poisoned");
+                listener(event)
+            }
+        })
+    }
+}
+
+/// Returns the reason reconnecting would be pointless, if any.
+fn permanent_close_reason(finished: &FinishResult) -> Option<PersistentChatCloseReason> {
+    match finished {
+        Ok(FinishReason::LocalDisconnect) => Some(PersistentChatCloseReason::LocalDisconnect),
+        Err(FinishError::Error(TaskExitError::WebsocketError(
+            NextEventError::AbnormalServerClose {
+                code: CloseCode::Library(code),
+                reason: _,
+            },
+        ))) => match *code {
+            CONNECTED_ELSEWHERE_CLOSE_CODE => Some(PersistentChatCloseReason::ConnectedElsewhere),
+            CONNECTION_INVALIDATED_CLOSE_CODE => {
+                Some(PersistentChatCloseReason::ConnectionInvalidated)
+            }
+            _ => None,
+        },
+        Ok(FinishReason::RemoteDisconnect) | Err(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Mutex;
+
+    use assert_matches::assert_matches;
+    use http::uri::PathAndQuery;
+    use http::{HeaderMap, Method, StatusCode};
+    use libsignal_net_infra::errors::RetryLater;
+    use test_case::test_case;
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::chat::fake::FakeChatRemote;
+    use crate::chat::{RequestProto, ResponseProto};
+    use crate::connect_state::SUGGESTED_CONNECT_PARAMS;
+
+    /// Hands out fake connections, reporting each new remote end to the test.
+    struct FakeConnector {
+        results: Mutex<Vec<Result<(), ConnectError>>>,
+        remotes: mpsc::UnboundedSender<FakeChatRemote>,
+    }
+
+    impl ConnectChat for FakeConnector {
+        fn connect_chat(
+            &self,
+            listener: ws::EventListener,
+        ) -> BoxFuture<'_, Result<ChatConnection, ConnectError>> {
+            // Succeed by default once the scripted results run out.
+            let result = self.results.lock().expect("not poisoned").pop();
+            Box::pin(async move {
+                result.unwrap_or(Ok(()))?;
+                let (chat, remote) =
+                    ChatConnection::new_fake(tokio::runtime::Handle::current(), listener, [], []);
+                self.remotes.send(remote).expect("test is listening");
+                Ok(chat)
+            })
+        }
+    }
+
+    fn start_fake(
+        results: impl IntoIterator<Item = Result<(), ConnectError>, IntoIter: DoubleEndedIterator>,
+        network_change_event: NetworkChangeEvent,
+    ) -> (
+        PersistentChatConnection,
+        mpsc::UnboundedReceiver<FakeChatRemote>,
+        mpsc::UnboundedReceiver<ListenerEvent>,
+    ) {
+        let (remotes_tx, remotes_rx) = mpsc::unbounded_channel();
+        let (events_tx, events_rx) = mpsc::unbounded_channel();
+        let connector = FakeConnector {
+            results: Mutex::new(results.into_iter().rev().collect()),
+            remotes: remotes_tx,
+        };
+        let chat = PersistentChatConnection::start(
+            &tokio::runtime::Handle::current(),
+            Box::new(connector),
+            SUGGESTED_CONNECT_PARAMS,
+            network_change_event,
+            Box::new(move |event| {
+                let _ignore_closed = events_tx.send(event);
+            }),
+        );
+        (chat, remotes_rx, events_rx)
+    }
+
+    fn request(method: Method) -> Request {
+        Request {
+            method,
+            path: PathAndQuery::from_static("/v1/test"),
+            headers: HeaderMap::new(),
+            body: None,
+        }
+    }
+
+    async fn next_finished(events: &mut mpsc::UnboundedReceiver<ListenerEvent>) -> FinishResult {
+        loop {
+            match events.recv().await.expect("not dropped") {
+                ListenerEvent::Finished(finished) => return finished,
+                ListenerEvent::ReceivedAlerts(_) | ListenerEvent::ServerTimestamp(_) => {}
+                event @ ListenerEvent::ReceivedMessage(..) => panic!("unexpected {event:?}"),
+            }
+        }
+    }
+
+    async fn respond_ok(remote: &FakeChatRemote) -> RequestProto {
+        let request = remote
+            .receive_request()
+            .await
+            .expect("valid")
+            .expect("still connected");
+        remote
+            .send_response(ResponseProto {
+                id: request.id,
+                status: Some(StatusCode::OK.as_u16().into()),
+                message: Some("OK".into()),
+                headers: vec![],
+                body: None,
+            })
+            .expect("still connected");
+        request
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn request_waits_for_connection() {
+        let (chat, mut remotes, _events) = start_fake(
+            [Err(ConnectError::AllAttemptsFailed)],
+            libsignal_net_infra::utils::no_network_change_events(),
+        );
+
+        let send = chat.send(request(Method::POST), Duration::from_secs(60));
+        let respond = async {
+            let remote = remotes.recv().await.expect("reconnected");
+            respond_ok(&remote).await
+        };
+        let (response, _request) = tokio::join!(send, respond);
+        assert_eq!(response.expect("success").status, StatusCode::OK);
+        assert_matches!(chat.state(), PersistentChatState::Connected(_));
+    }
+
+    #[test_case(Method::GET, false; "GET")]
+    #[test_case(Method::HEAD, false; "HEAD")]
+    #[test_case(Method::PUT, true; "PUT marked replayable")]
+    #[test_case(Method::POST, true; "POST marked replayable")]
+    #[tokio::test(start_paused = true)]
+    async fn reconnects_and_replays_request(method: Method, replayable: bool) {
+        let (chat, mut remotes, mut events) =
+            start_fake([], libsignal_net_infra::utils::no_network_change_events());
+        let first_remote = remotes.recv().await.expect("connected");
+
+        let send = async {
+            if replayable {
+                chat.send_replayable(request(method.clone()), Duration::from_secs(60))
+                    .await
+            } else {
+                chat.send(request(method.clone()), Duration::from_secs(60))
+                    .await
+            }
+        };
+        let respond = async {
+            let _dropped = first_remote
+                .receive_request()
+                .await
+                .expect("valid")
+                .expect("still connected");
+            first_remote.send_close(None).expect("still connected");
+            let second_remote = remotes.recv().await.expect("reconnected");
+            respond_ok(&second_remote).await
+        };
+        let (response, replayed) = tokio::join!(send, respond);
+        assert_eq!(response.expect("success").status, StatusCode::OK);
+        assert_eq!(replayed.verb.as_deref(), Some(method.as_str()));
+
+        // The lost connection isn't reported to the listener.
+        while let Ok(event) = events.try_recv() {
+            assert_matches!(event, ListenerEvent::ReceivedAlerts(_));
+        }
+    }
+
+    #[test_case(Method::POST; "POST")]
+    #[test_case(Method::PUT; "PUT")]
+    #[test_case(Method::DELETE; "DELETE")]
+    #[tokio::test(start_paused = true)]
+    async fn does_not_replay_unsafe_request(method: Method) {
+        let (chat, mut remotes, _events) =
+            start_fake([], libsignal_net_infra::utils::no_network_change_events());
+        let remote = remotes.recv().await.expect("connected");
+
+        let send = chat.send(request(method), Duration::from_secs(60));
+        let drop_connection = async {
+            let _dropped = remote
+                .receive_request()
+                .await
+                .expect("valid")
+                .expect("still connected");
+            remote.send_close(None).expect("still connected");
+        };
+        let (response, ()) = tokio::join!(send, drop_connection);
+        assert_matches!(
+            response,
+            Err(SendError::Disconnected | SendError::WebSocket(_))
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn backs_off_until_network_change() {
+        let (network_change_tx, network_change_rx) = watch::channel(());
+        let (chat, mut remotes, _events) = start_fake(
+            [
+                Err(ConnectError::AllAttemptsFailed),
+                Err(ConnectError::RetryLater(RetryLater {
+                    retry_after_seconds: 600,
+                })),
+            ],
+            network_change_rx,
+        );
+        let mut states = std::pin::pin!(chat.state_changes());
+
+        // Intermediate states may be coalesced, so skip ahead to the server-requested delay.
+        loop {
+            match states.next().await.expect("not closed") {
+                PersistentChatState::WaitingToReconnect { delay }
+                    if delay >= Duration::from_secs(600) =>
+                {
+                    break;
+                }
+                PersistentChatState::Connecting
+                | PersistentChatState::WaitingToReconnect { .. } => {}
+                state => panic!("unexpected state {state:?}"),
+            }
+        }
+
+        // A network change should cut the wait short.
+        let waiting_since = Instant::now();
+        network_change_tx.send_replace(());
+        let _remote = remotes.recv().await.expect("reconnected");
+        assert!(waiting_since.elapsed() < Duration::from_secs(600));
+        assert_matches!(chat.state(), PersistentChatState::Connected(_));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stops_when_connected_elsewhere() {
+        let (chat, mut remotes, mut events) =
+            start_fake([], libsignal_net_infra::utils::no_network_change_events());
+        let remote = remotes.recv().await.expect("connected");
+
+        remote
+            .send_close(Some(CONNECTED_ELSEWHERE_CLOSE_CODE))
+            .expect("still connected");
+        assert_matches!(next_finished(&mut events).await, Err(_));
+        assert_matches!(
+            chat.state(),
+            PersistentChatState::Closed(PersistentChatCloseReason::ConnectedElsewhere)
+        );
+        assert_matches!(
+            chat.send(request(Method::GET), Duration::from_secs(60))
+                .await,
+            Err(SendError::ConnectedElsewhere)
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn disconnect_stops_reconnecting() {
+        let (chat, mut remotes, mut events) =
+            start_fake([], libsignal_net_infra::utils::no_network_change_events());
+        let _remote = remotes.recv().await.expect("connected");
+
+        chat.disconnect().await;
+        assert_matches!(
+            chat.state(),
+            PersistentChatState::Closed(PersistentChatCloseReason::LocalDisconnect)
+        );
+        assert_matches!(
+            next_finished(&mut events).await,
+            Ok(FinishReason::LocalDisconnect)
+        );
+        assert_matches!(remotes.try_recv(), Err(_));
+    }
+}
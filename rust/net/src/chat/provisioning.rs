@@ -0,0 +1,581 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Encryption of the account state handed from an existing device to a newly-linked one.
+//!
+//! Linking proceeds as follows:
+//!
+//! 1. The new ("secondary") device opens a provisioning connection and generates a
+//!    [`ProvisioningCipher`]. Once the server assigns it an address (see
+//!    [`ProvisioningEvent::ReceivedAddress`]), it shows a [`DeviceLinkUrl`] to the user, usually
+//!    as a QR code.
+//! 2. The existing ("primary") device scans the URL and sends an envelope produced by
+//!    [`encrypt_provision_message`] to that address.
+//! 3. The secondary device receives the envelope (see [`ProvisioningEvent::ReceivedEnvelope`]),
+//!    decrypts it with its `ProvisioningCipher`, and uses the contents to register itself.
+//!
+//! [`ProvisioningEvent::ReceivedAddress`]: crate::chat::server_requests::ProvisioningEvent::ReceivedAddress
+//! [`ProvisioningEvent::ReceivedEnvelope`]: crate::chat::server_requests::ProvisioningEvent::ReceivedEnvelope
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use base64::prelude::{BASE64_STANDARD, BASE64_STANDARD_NO_PAD, Engine as _};
+use hkdf::Hkdf;
+use hmac::{Hmac, KeyInit as _, Mac as _};
+use libsignal_account_keys::AccountEntropyPool;
+use libsignal_core::{Aci, Pni};
+use libsignal_protocol::{IdentityKey, IdentityKeyPair, KeyPair, PrivateKey, PublicKey};
+use prost::Message as _;
+use rand::{CryptoRng, Rng};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::proto::chat_provisioning as pb;
+
+/// The version of the provisioning protocol implemented here.
+pub const PROVISIONING_VERSION: u32 = 1;
+
+const CIPHER_VERSION: u8 = 1;
+const IV_LEN: usize = 16;
+const MAC_LEN: usize = 32;
+const KDF_INFO: &[u8] = b"TextSecure Provisioning Message";
+
+const LINK_DEVICE_URL_SCHEME: &str = "sgnl";
+const LINK_DEVICE_URL_HOST: &str = "linkdevice";
+
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum ProvisioningError {
+    /// provisioning envelope could not be parsed
+    MalformedEnvelope,
+    /// unsupported provisioning cipher version {0}
+    UnsupportedVersion(u8),
+    /// provisioning envelope failed authentication
+    BadMac,
+    /// provisioning message could not be decrypted
+    BadCiphertext,
+    /// provisioning message is invalid: {0}
+    InvalidMessage(&'static str),
+}
+
+/// Everything a newly-linked device needs to act on behalf of the account.
+pub struct ProvisionMessage {
+    pub aci: Aci,
+    pub pni: Pni,
+    /// The account's phone number, in E.164 format.
+    pub number: String,
+    pub aci_identity_key_pair: IdentityKeyPair,
+    pub pni_identity_key_pair: IdentityKeyPair,
+    pub profile_key: [u8; 32],
+    pub account_entropy_pool: AccountEntropyPool,
+    pub media_root_backup_key: Option<[u8; 32]>,
+    /// The single-use code authorizing the new device to register, from
+    /// `GET /v1/devices/provisioning/code`.
+    pub provisioning_code: String,
+    pub read_receipts: bool,
+    pub user_agent: Option<String>,
+}
+
+/// The linking device's half of the provisioning exchange.
+///
+/// Holds an ephemeral key pair that only lives as long as a single provisioning connection.
+pub struct ProvisioningCipher {
+    key_pair: KeyPair,
+}
+
+/// The contents of the QR code shown by a device waiting to be linked.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeviceLinkUrl {
+    /// The provisioning address assigned to the new device by the server.
+    pub address: String,
+    /// The new device's ephemeral public key.
+    pub public_key: PublicKey,
+}
+
+impl ProvisioningCipher {
+    pub fn generate<R: Rng + CryptoRng>(rng: &mut R) -> Self {
+        Self {
+            key_pair: KeyPair::generate(rng),
+        }
+    }
+
+    pub fn public_key(&self) -> &PublicKey {
+        &self.key_pair.public_key
+    }
+
+    /// Produces the link to present to the primary device once the server has assigned
+    /// `address`.
+    pub fn device_link_url(&self, address: String) -> DeviceLinkUrl {
+        DeviceLinkUrl {
+            address,
+            public_key: self.key_pair.public_key,
+        }
+    }
+
+    /// Decrypts a serialized `ProvisionEnvelope` received on the provisioning connection.
+    pub fn decrypt(&self, envelope: &[u8]) -> Result<ProvisionMessage, ProvisioningError> {
+        let pb::ProvisionEnvelope { public_key, body } = pb::ProvisionEnvelope::decode(envelope)
+            .map_err(|_| ProvisioningError::MalformedEnvelope)?;
+        let their_public_key = PublicKey::deserialize(&public_key.unwrap_or_default())
+            .map_err(|_| ProvisioningError::MalformedEnvelope)?;
+        let body = body.unwrap_or_default();
+
+        let (&version, rest) = body
+            .split_first()
+            .ok_or(ProvisioningError::MalformedEnvelope)?;
+        if version != CIPHER_VERSION {
+            return Err(ProvisioningError::UnsupportedVersion(version));
+        }
+        if rest.len() < IV_LEN + MAC_LEN {
+            return Err(ProvisioningError::MalformedEnvelope);
+        }
+        let (authenticated, their_mac) = body.split_at(body.len() - MAC_LEN);
+        let (iv, ciphertext) = authenticated[1..].split_at(IV_LEN);
+
+        let keys = ProvisioningKeys::derive(&self.key_pair.private_key, &their_public_key)?;
+        keys.mac(authenticated)
+            .verify_slice(their_mac)
+            .map_err(|_| ProvisioningError::BadMac)?;
+
+        let plaintext = signal_crypto::aes_256_cbc_decrypt(ciphertext, &keys.cipher_key, iv)
+            .map_err(|_| ProvisioningError::BadCiphertext)?;
+        let message = pb::ProvisionMessage::decode(plaintext.as_slice())
+            .map_err(|_| ProvisioningError::InvalidMessage("not a ProvisionMessage"))?;
+        message.try_into()
+    }
+}
+
+/// Encrypts `message` for the device that presented `recipient` in its [`DeviceLinkUrl`],
+/// producing a serialized `ProvisionEnvelope`.
+pub fn encrypt_provision_message<R: Rng + CryptoRng>(
+    recipient: &PublicKey,
+    message: &ProvisionMessage,
+    rng: &mut R,
+) -> Vec<u8> {
+    let our_key_pair = KeyPair::generate(rng);
+    let keys = ProvisioningKeys::derive(&our_key_pair.private_key, recipient)
+        .expect("recipient key is a valid public key");
+
+    let iv: [u8; IV_LEN] = rng.random();
+    let plaintext = pb::ProvisionMessage::from(message).encode_to_vec();
+    let ciphertext = signal_crypto::aes_256_cbc_encrypt(&plaintext, &keys.cipher_key, &iv)
+        .expect("valid key and IV");
+
+    let mut body = Vec::with_capacity(1 + IV_LEN + ciphertext.len() + MAC_LEN);
+    body.push(CIPHER_VERSION);
+    body.extend_from_slice(&iv);
+    body.extend_from_slice(&ciphertext);
+    let mac = keys.mac(&body).finalize().into_bytes();
+    body.extend_from_slice(&mac);
+
+    pb::ProvisionEnvelope {
+        public_key: Some(our_key_pair.public_key.serialize().into_vec().into()),
+        body: Some(body.into()),
+    }
+    .encode_to_vec()
+}
+
+struct ProvisioningKeys {
+    cipher_key: [u8; 32],
+    mac_key: [u8; 32],
+}
+
+impl ProvisioningKeys {
+    fn derive(
+        our_private_key: &PrivateKey,
+        their_public_key: &PublicKey,
+    ) -> Result<Self, ProvisioningError> {
+        let shared_secret = our_private_key
+            .calculate_agreement(their_public_key)
+            .map_err(|_| ProvisioningError::MalformedEnvelope)?;
+        let mut derived = [0; 64];
+        Hkdf::<Sha256>::new(None, &shared_secret)
+            .expand(KDF_INFO, &mut derived)
+            .expect("valid length");
+        let (cipher_key, mac_key) = derived.split_at(32);
+        Ok(Self {
+            cipher_key: cipher_key.try_into().expect("correct length"),
+            mac_key: mac_key.try_into().expect("correct length"),
+        })
+    }
+
+    fn mac(&self, data: &[u8]) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.mac_key)
+            .expect("HMAC-SHA256 should accept any size key");
+        mac.update(data);
+        mac
+    }
+}
+
+impl From<&ProvisionMessage> for pb::ProvisionMessage {
+    fn from(message: &ProvisionMessage) -> Self {
+        let ProvisionMessage {
+            aci,
+            pni,
+            number,
+            aci_identity_key_pair,
+            pni_identity_key_pair,
+            profile_key,
+            account_entropy_pool,
+            media_root_backup_key,
+            provisioning_code,
+            read_receipts,
+            user_agent,
+        } = message;
+        let public_key = |pair: &IdentityKeyPair| pair.identity_key().serialize().into_vec().into();
+        let private_key = |pair: &IdentityKeyPair| pair.private_key().serialize().into();
+        let aci = Uuid::from(*aci);
+        let pni = Uuid::from(*pni);
+
+        Self {
+            aci_identity_key_public: Some(public_key(aci_identity_key_pair)),
+            aci_identity_key_private: Some(private_key(aci_identity_key_pair)),
+            number: Some(number.clone()),
+            provisioning_code: Some(provisioning_code.clone()),
+            user_agent: user_agent.clone(),
+            profile_key: Some(profile_key.to_vec().into()),
+            read_receipts: Some(*read_receipts),
+            aci: Some(aci.to_string()),
+            provisioning_version: Some(PROVISIONING_VERSION),
+            pni: Some(pni.to_string()),
+            pni_identity_key_public: Some(public_key(pni_identity_key_pair)),
+            pni_identity_key_private: Some(private_key(pni_identity_key_pair)),
+            master_key: Some(account_entropy_pool.derive_svr_key().to_vec().into()),
+            ephemeral_backup_key: None,
+            account_entropy_pool: Some(account_entropy_pool.to_string()),
+            media_root_backup_key: media_root_backup_key.map(|key| key.to_vec().into()),
+            aci_binary: Some(aci.as_bytes().to_vec().into()),
+            pni_binary: Some(pni.as_bytes().to_vec().into()),
+        }
+    }
+}
+
+impl TryFrom<pb::ProvisionMessage> for ProvisionMessage {
+    type Error = ProvisioningError;
+
+    fn try_from(message: pb::ProvisionMessage) -> Result<Self, Self::Error> {
+        let pb::ProvisionMessage {
+            aci_identity_key_public,
+            aci_identity_key_private,
+            number,
+            provisioning_code,
+            user_agent,
+            profile_key,
+            read_receipts,
+            aci,
+            provisioning_version: _,
+            pni,
+            pni_identity_key_public,
+            pni_identity_key_private,
+            // Derived from the account entropy pool.
+            master_key: _,
+            ephemeral_backup_key: _,
+            account_entropy_pool,
+            media_root_backup_key,
+            aci_binary,
+            pni_binary,
+        } = message;
+
+        fn service_id_uuid(
+            binary: Option<bytes::Bytes>,
+            string: Option<String>,
+            name: &'static str,
+        ) -> Result<Uuid, ProvisioningError> {
+            match (binary, string) {
+                (Some(binary), _) => Uuid::from_slice(&binary).ok(),
+                (None, Some(string)) => Uuid::parse_str(&string).ok(),
+                (None, None) => None,
+            }
+            .ok_or(ProvisioningError::InvalidMessage(name))
+        }
+
+        fn identity_key_pair(
+            public: Option<bytes::Bytes>,
+            private: Option<bytes::Bytes>,
+            name: &'static str,
+        ) -> Result<IdentityKeyPair, ProvisioningError> {
+            let public = IdentityKey::decode(&public.unwrap_or_default())
+                .map_err(|_| ProvisioningError::InvalidMessage(name))?;
+            let private = PrivateKey::deserialize(&private.unwrap_or_default())
+                .map_err(|_| ProvisioningError::InvalidMessage(name))?;
+            if private.public_key().ok().as_ref() != Some(public.public_key()) {
+                return Err(ProvisioningError::InvalidMessage(name));
+            }
+            Ok(IdentityKeyPair::new(public, private))
+        }
+
+        fn key_bytes(
+            bytes: Option<bytes::Bytes>,
+            name: &'static str,
+        ) -> Result<[u8; 32], ProvisioningError> {
+            bytes
+                .as_deref()
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or(ProvisioningError::InvalidMessage(name))
+        }
+
+        Ok(Self {
+            aci: service_id_uuid(aci_binary, aci, "ACI")?.into(),
+            pni: service_id_uuid(pni_binary, pni, "PNI")?.into(),
+            number: number.ok_or(ProvisioningError::InvalidMessage("number"))?,
+            aci_identity_key_pair: identity_key_pair(
+                aci_identity_key_public,
+                aci_identity_key_private,
+                "ACI identity key",
+            )?,
+            pni_identity_key_pair: identity_key_pair(
+                pni_identity_key_public,
+                pni_identity_key_private,
+                "PNI identity key",
+            )?,
+            profile_key: key_bytes(profile_key, "profile key")?,
+            account_entropy_pool: account_entropy_pool
+                .as_deref()
+                .and_then(|pool| AccountEntropyPool::from_str(pool).ok())
+                .ok_or(ProvisioningError::InvalidMessage("account entropy pool"))?,
+            media_root_backup_key: media_root_backup_key
+                .map(|key| key_bytes(Some(key), "media root backup key"))
+                .transpose()?,
+            provisioning_code: provisioning_code
+                .ok_or(ProvisioningError::InvalidMessage("provisioning code"))?,
+            read_receipts: read_receipts.unwrap_or_default(),
+            user_agent,
+        })
+    }
+}
+
+/// Error returned when a string is not a valid [`DeviceLinkUrl`].
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum InvalidDeviceLinkUrl {
+    /// not a device link URL
+    WrongScheme,
+    /// device link URL is missing {0}
+    MissingParameter(&'static str),
+    /// device link URL has an invalid public key
+    InvalidPublicKey,
+}
+
+impl Display for DeviceLinkUrl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            address,
+            public_key,
+        } = self;
+        let query = url::form_urlencoded::Serializer::new(String::new())
+            .append_pair("uuid", address)
+            .append_pair("pub_key", &BASE64_STANDARD.encode(public_key.serialize()))
+            .finish();
+        write!(
+            f,
+            "{LINK_DEVICE_URL_SCHEME}://{LINK_DEVICE_URL_HOST}?{query}"
+        )
+    }
+}
+
+impl FromStr for DeviceLinkUrl {
+    type Err = InvalidDeviceLinkUrl;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let url = url::Url::parse(s).map_err(|_| InvalidDeviceLinkUrl::WrongScheme)?;
+        if url.scheme() != LINK_DEVICE_URL_SCHEME || url.host_str() != Some(LINK_DEVICE_URL_HOST) {
+            return Err(InvalidDeviceLinkUrl::WrongScheme);
+        }
+
+        let mut address = None;
+        let mut public_key = None;
+        for (name, value) in url.query_pairs() {
+            match &*name {
+                "uuid" => address = Some(value.into_owned()),
+                "pub_key" => public_key = Some(value.into_owned()),
+                _ => {}
+            }
+        }
+        let address = address.ok_or(InvalidDeviceLinkUrl::MissingParameter("uuid"))?;
+        let public_key = public_key.ok_or(InvalidDeviceLinkUrl::MissingParameter("pub_key"))?;
+
+        // Some clients leave off the base64 padding.
+        let public_key = BASE64_STANDARD
+            .decode(&public_key)
+            .or_else(|_| BASE64_STANDARD_NO_PAD.decode(&public_key))
+            .ok()
+            .and_then(|key| PublicKey::deserialize(&key).ok())
+            .ok_or(InvalidDeviceLinkUrl::InvalidPublicKey)?;
+
+        Ok(Self {
+            address,
+            public_key,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use rand::SeedableRng as _;
+    use rand_chacha::ChaCha20Rng;
+
+    use super::*;
+
+    fn test_message(rng: &mut ChaCha20Rng) -> ProvisionMessage {
+        ProvisionMessage {
+            aci: Aci::from_uuid_bytes([0xaa; 16]),
+            pni: Pni::from_uuid_bytes([0xbb; 16]),
+            number: "+18005550101".to_owned(),
+            aci_identity_key_pair: IdentityKeyPair::generate(rng),
+            pni_identity_key_pair: IdentityKeyPair::generate(rng),
+            profile_key: [0xcc; 32],
+            account_entropy_pool: AccountEntropyPool::generate(rng),
+            media_root_backup_key: Some([0xdd; 32]),
+            provisioning_code: "provisioning code".to_owned(),
+            read_receipts: true,
+            user_agent: None,
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        let cipher = ProvisioningCipher::generate(&mut rng);
+        let message = test_message(&mut rng);
+
+        let envelope = encrypt_provision_message(cipher.public_key(), &message, &mut rng);
+        let decrypted = cipher.decrypt(&envelope).expect("can decrypt");
+
+        assert_eq!(decrypted.aci, message.aci);
+        assert_eq!(decrypted.pni, message.pni);
+        assert_eq!(decrypted.number, message.number);
+        assert_eq!(
+            decrypted.aci_identity_key_pair.serialize(),
+            message.aci_identity_key_pair.serialize()
+        );
+        assert_eq!(
+            decrypted.pni_identity_key_pair.serialize(),
+            message.pni_identity_key_pair.serialize()
+        );
+        assert_eq!(decrypted.profile_key, message.profile_key);
+        assert_eq!(
+            decrypted.account_entropy_pool.to_string(),
+            message.account_entropy_pool.to_string()
+        );
+        assert_eq!(
+            decrypted.media_root_backup_key,
+            message.media_root_backup_key
+        );
+        assert_eq!(decrypted.provisioning_code, message.provisioning_code);
+        assert!(decrypted.read_receipts);
+        assert_eq!(decrypted.user_agent, None);
+    }
+
+    #[test]
+    fn wrong_recipient() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        let cipher = ProvisioningCipher::generate(&mut rng);
+        let other_cipher = ProvisioningCipher::generate(&mut rng);
+        let message = test_message(&mut rng);
+
+        let envelope = encrypt_provision_message(other_cipher.public_key(), &message, &mut rng);
+        assert_matches!(cipher.decrypt(&envelope), Err(ProvisioningError::BadMac));
+    }
+
+    #[test]
+    fn tampered_envelope() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        let cipher = ProvisioningCipher::generate(&mut rng);
+        let message = test_message(&mut rng);
+        let envelope = pb::ProvisionEnvelope::decode(
+            encrypt_provision_message(cipher.public_key(), &message, &mut rng).as_slice(),
+        )
+        .expect("valid");
+        let body = envelope.body.clone().expect("present");
+
+        let with_body = |body: Vec<u8>| {
+            pb::ProvisionEnvelope {
+                body: Some(body.into()),
+                ..envelope.clone()
+            }
+            .encode_to_vec()
+        };
+
+        for i in [1, body.len() / 2, body.len() - 1] {
+            let mut tampered = body.to_vec();
+            tampered[i] ^= 1;
+            assert_matches!(
+                cipher.decrypt(&with_body(tampered)),
+                Err(ProvisioningError::BadMac),
+                "byte {i}"
+            );
+        }
+
+        let mut wrong_version = body.to_vec();
+        wrong_version[0] = 2;
+        assert_matches!(
+            cipher.decrypt(&with_body(wrong_version)),
+            Err(ProvisioningError::UnsupportedVersion(2))
+        );
+
+        assert_matches!(
+            cipher.decrypt(&with_body(body[..IV_LEN + MAC_LEN].to_vec())),
+            Err(ProvisioningError::MalformedEnvelope)
+        );
+        assert_matches!(
+            cipher.decrypt(b"not a protobuf"),
+            Err(ProvisioningError::MalformedEnvelope)
+        );
+    }
+
+    #[test]
+    fn device_link_url_round_trip() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        let cipher = ProvisioningCipher::generate(&mut rng);
+        let url = cipher.device_link_url("some/address+with=symbols".to_owned());
+
+        let serialized = url.to_string();
+        assert!(
+            serialized
+                .starts_with("sgnl://linkdevice?uuid=some%2Faddress%2Bwith%3Dsymbols&pub_key="),
+            "{serialized}"
+        );
+        assert_eq!(serialized.parse::<DeviceLinkUrl>().expect("valid"), url);
+    }
+
+    #[test]
+    fn device_link_url_without_padding() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        let public_key = ProvisioningCipher::generate(&mut rng)
+            .public_key()
+            .to_owned();
+        let url = format!(
+            "sgnl://linkdevice?uuid=address&pub_key={}",
+            BASE64_STANDARD_NO_PAD.encode(public_key.serialize())
+        );
+        assert_eq!(
+            url.parse::<DeviceLinkUrl>().expect("valid"),
+            DeviceLinkUrl {
+                address: "address".to_owned(),
+                public_key,
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_device_link_url() {
+        assert_matches!(
+            "https://linkdevice?uuid=a&pub_key=b".parse::<DeviceLinkUrl>(),
+            Err(InvalidDeviceLinkUrl::WrongScheme)
+        );
+        assert_matches!(
+            "sgnl://linkdevice?pub_key=b".parse::<DeviceLinkUrl>(),
+            Err(InvalidDeviceLinkUrl::MissingParameter("uuid"))
+        );
+        assert_matches!(
+            "sgnl://linkdevice?uuid=a".parse::<DeviceLinkUrl>(),
+            Err(InvalidDeviceLinkUrl::MissingParameter("pub_key"))
+        );
+        assert_matches!(
+            "sgnl://linkdevice?uuid=a&pub_key=AAAA".parse::<DeviceLinkUrl>(),
+            Err(InvalidDeviceLinkUrl::InvalidPublicKey)
+        );
+    }
+}
@@ -21,14 +21,14 @@ use libsignal_net_infra::http_client::HttpConnectError;
 use libsignal_net_infra::route::{
     AttemptOutcome, ComposedConnector, ConnectError, ConnectionOutcomeParams, ConnectionOutcomes,
     ConnectionProxyConfig, Connector, ConnectorFactory, DelayBasedOnTransport, DescribeForLog,
-    DescribedRouteConnector, DirectOrProxy, DirectOrProxyMode, DirectOrProxyRoute, ErrorHandling,
-    HttpRouteFragment, HttpsServiceRoute, InterfaceChangedOr, InterfaceMonitor, LoggingConnector,
-    NoSoonerThan, ResettingConnectionOutcomes, ResolveHostnames, ResolveWithSavedDescription,
-    ResolvedRoute, RouteDelayPolicy, RouteProvider, RouteProviderContext, RouteProviderExt as _,
-    RouteResolver, StaticTcpTimeoutConnector, ThrottlingConnector, TransportRoute,
-    UnresolvedRouteDescription, UnresolvedTransportRoute, UnresolvedWebsocketServiceRoute,
-    UnsuccessfulOutcome, UsePreconnect, UsesTransport, VariableTlsTimeoutConnector,
-    WebSocketRouteFragment, WebSocketServiceRoute,
+    DescribedRouteConnector, DirectOrProxy, DirectOrProxyMode, DirectOrProxyRoute,
+    EchRetryConnector, ErrorHandling, HttpRouteFragment, HttpsServiceRoute, InterfaceChangedOr,
+    InterfaceMonitor, LoggingConnector, NoSoonerThan, ResettingConnectionOutcomes,
+    ResolveHostnames, ResolveWithSavedDescription, ResolvedRoute, RouteDelayPolicy, RouteProvider,
+    RouteProviderContext, RouteProviderExt as _, RouteResolver, StaticTcpTimeoutConnector,
+    ThrottlingConnector, TransportRoute, UnresolvedRouteDescription, UnresolvedTransportRoute,
+    UnresolvedWebsocketServiceRoute, UnsuccessfulOutcome, UsePreconnect, UsesTransport,
+    VariableTlsTimeoutConnector, WebSocketRouteFragment, WebSocketServiceRoute,
 };
 use libsignal_net_infra::tcp_ssl::{LONG_TCP_HANDSHAKE_THRESHOLD, LONG_TLS_HANDSHAKE_THRESHOLD};
 use libsignal_net_infra::timeouts::{
@@ -47,6 +47,9 @@ use crate::auth::Auth;
 use crate::enclave::{EndpointParams, NewHandshake};
 use crate::ws::WebSocketServiceConnectError;
 
+mod telemetry;
+pub use telemetry::*;
+
 /// Suggested values for [`ConnectionOutcomeParams`].
 pub const SUGGESTED_CONNECT_PARAMS: ConnectionOutcomeParams = ConnectionOutcomeParams {
     short_term_age_cutoff: Duration::from_secs(5 * 60),
@@ -119,16 +122,20 @@ pub struct ConnectState<ConnectorFactory = DefaultConnectorFactory> {
     service_level_attempts_record: ConnectionOutcomes<ServiceName>,
     /// [`RouteProviderContext`] passed to route providers.
     route_provider_context: RouteProviderContextImpl,
+    /// Aggregate statistics since the last network change, for [`Self::connectivity_report`].
+    telemetry: ConnectionTelemetry,
 }
 
-pub type DefaultTransportConnector = VariableTlsTimeoutConnector<
-    ThrottlingConnector<LoggingConnector<crate::infra::tcp_ssl::StatelessTls>>,
-    crate::infra::route::DirectOrProxy<
-        LoggingConnector<StaticTcpTimeoutConnector<crate::infra::tcp_ssl::StatelessTcp>>,
-        crate::infra::tcp_ssl::proxy::StatelessProxied,
+pub type DefaultTransportConnector = EchRetryConnector<
+    VariableTlsTimeoutConnector<
+        ThrottlingConnector<LoggingConnector<crate::infra::tcp_ssl::StatelessTls>>,
+        crate::infra::route::DirectOrProxy<
+            LoggingConnector<StaticTcpTimeoutConnector<crate::infra::tcp_ssl::StatelessTcp>>,
+            crate::infra::tcp_ssl::proxy::StatelessProxied,
+            TransportConnectError,
+        >,
         TransportConnectError,
     >,
-    TransportConnectError,
 >;
 
 #[derive(Clone, Debug, PartialEq)]
@@ -168,11 +175,11 @@ where
             // Proxy connectors use LoggingConnector internally
             Default::default(),
         );
-        VariableTlsTimeoutConnector::new(
+        EchRetryConnector::new(VariableTlsTimeoutConnector::new(
             throttle_tls_connections,
             proxy_or_direct_connector,
             MIN_TLS_HANDSHAKE_TIMEOUT,
-        )
+        ))
     }
 }
 
@@ -202,6 +209,7 @@ impl<ConnectorFactory> ConnectState<ConnectorFactory> {
             attempts_record: ConnectionOutcomes::new(connect_params.clone()),
             service_level_attempts_record: ConnectionOutcomes::new(connect_params),
             route_provider_context: RouteProviderContextImpl::default(),
+            telemetry: ConnectionTelemetry::default(),
         }
         .into()
     }
@@ -210,6 +218,12 @@ impl<ConnectorFactory> ConnectState<ConnectorFactory> {
         self.attempts_record.reset(network_change_time);
         // We don't reset service_level_attempts_record because we assume that tracks server-side
         // issues rather than client network ones.
+        self.telemetry.reset(network_change_time);
+    }
+
+    /// Summarizes connection attempts made since the last network change.
+    pub fn connectivity_report(&self, dns_resolver: &DnsResolver) -> ConnectivityReport {
+        self.telemetry.report(dns_resolver)
     }
 }
 
@@ -266,6 +280,7 @@ impl<TC> ConnectState<TC> {
             attempts_record,
             service_level_attempts_record,
             route_provider_context,
+            telemetry: _,
         } = self;
 
         let system_now = SystemTime::now();
@@ -634,6 +649,7 @@ impl<TC> ConnectionResources<'_, TC> {
                 );
                 Some(AttemptOutcome {
                     started: start,
+                    finished: updates.finished_at,
                     result: Ok(()),
                 })
             }
@@ -646,6 +662,7 @@ impl<TC> ConnectionResources<'_, TC> {
                 {
                     Some(AttemptOutcome {
                         started: start,
+                        finished: updates.finished_at,
                         result: Err(*outcome),
                     })
                 } else {
@@ -654,11 +671,17 @@ impl<TC> ConnectionResources<'_, TC> {
             }
         };
 
+        let route_kind_outcomes = updates
+            .outcomes
+            .iter()
+            .map(|(route, outcome)| (RouteKind::from(&route.description), *outcome))
+            .collect_vec();
         let per_route_outcomes = process_outcomes(updates.outcomes);
         let system_now = SystemTime::now();
 
         {
             let mut connect_state_guard = connect_state.lock().expect("not poisoned");
+            connect_state_guard.telemetry.record(route_kind_outcomes);
             connect_state_guard.attempts_record.apply_outcome_updates(
                 per_route_outcomes,
                 updates.finished_at,
@@ -952,6 +975,7 @@ mod test {
             sni: Host::Domain("fake-sni".into()),
             alpn: Some(Alpn::Http1_1),
             min_protocol_version: Some(boring_signal::ssl::SslVersion::TLS1_3),
+            ech_config: None,
         },
         inner: DirectOrProxyRoute::Direct(TcpRoute {
             address: UnresolvedHost::from(Arc::from(FAKE_HOST_NAME)),
@@ -1031,6 +1055,7 @@ mod test {
             service_level_attempts_record: ConnectionOutcomes::new(SUGGESTED_CONNECT_PARAMS),
             make_transport_connector: fake_transport_connector,
             route_provider_context: Default::default(),
+            telemetry: Default::default(),
         }
         .into();
 
@@ -1059,6 +1084,27 @@ mod test {
         let RouteInfo { unresolved } = info;
 
         assert_eq!(unresolved.to_string(), "REDACTED:1234 fronted by proxyf");
+
+        let report = state
+            .lock()
+            .expect("not poisoned")
+            .connectivity_report(&resolver);
+        assert_matches!(
+            report.route_stats("direct"),
+            Some(RouteStats {
+                successes: 0,
+                failures: 1,
+                ..
+            })
+        );
+        assert_matches!(
+            report.route_stats("fronted"),
+            Some(RouteStats {
+                successes: 1,
+                failures: 0,
+                ..
+            })
+        );
     }
 
     #[tokio::test(start_paused = true)]
@@ -1084,6 +1130,7 @@ mod test {
             service_level_attempts_record: ConnectionOutcomes::new(SUGGESTED_CONNECT_PARAMS),
             make_transport_connector: always_hangs_connector,
             route_provider_context: Default::default(),
+            telemetry: Default::default(),
         }
         .into();
 
@@ -1141,6 +1188,7 @@ mod test {
             service_level_attempts_record: ConnectionOutcomes::new(SUGGESTED_CONNECT_PARAMS),
             make_transport_connector: connector,
             route_provider_context: Default::default(),
+            telemetry: Default::default(),
         };
 
         assert_eq!(
@@ -1256,6 +1304,7 @@ mod test {
             service_level_attempts_record: ConnectionOutcomes::new(SUGGESTED_CONNECT_PARAMS),
             make_transport_connector: connector,
             route_provider_context: Default::default(),
+            telemetry: Default::default(),
         };
 
         assert_eq!(
@@ -1354,6 +1403,7 @@ mod test {
             service_level_attempts_record: ConnectionOutcomes::new(SUGGESTED_CONNECT_PARAMS),
             make_transport_connector: client_abort_connector,
             route_provider_context: Default::default(),
+            telemetry: Default::default(),
         }
         .into();
 
@@ -1425,6 +1475,7 @@ mod test {
             service_level_attempts_record: ConnectionOutcomes::new(SUGGESTED_CONNECT_PARAMS),
             make_transport_connector: transport_connector,
             route_provider_context: Default::default(),
+            telemetry: Default::default(),
         }
         .into();
 
@@ -1497,10 +1548,12 @@ mod test {
             service_level_attempts_record: ConnectionOutcomes::new(SUGGESTED_CONNECT_PARAMS),
             make_transport_connector: fake_transport_connector,
             route_provider_context: Default::default(),
+            telemetry: Default::default(),
         };
 
         let past_failure = AttemptOutcome {
             started: start,
+            finished: start,
             result: Err(UnsuccessfulOutcome::default()),
         };
         state.attempts_record.apply_outcome_updates(
@@ -1583,10 +1636,12 @@ mod test {
             service_level_attempts_record: ConnectionOutcomes::new(SUGGESTED_CONNECT_PARAMS),
             make_transport_connector: fake_transport_connector,
             route_provider_context: Default::default(),
+            telemetry: Default::default(),
         };
 
         let past_failure = AttemptOutcome {
             started: start,
+            finished: start,
             result: Err(UnsuccessfulOutcome::default()),
         };
         state.attempts_record.apply_outcome_updates(
@@ -1692,10 +1747,12 @@ mod test {
             service_level_attempts_record: ConnectionOutcomes::new(SUGGESTED_CONNECT_PARAMS),
             make_transport_connector: fake_transport_connector,
             route_provider_context: Default::default(),
+            telemetry: Default::default(),
         };
 
         let past_failure = AttemptOutcome {
             started: start,
+            finished: start,
             result: Err(UnsuccessfulOutcome::default()),
         };
         // We only record one failure for the route and one failure for the service.
@@ -1790,6 +1847,7 @@ mod test {
             service_level_attempts_record: ConnectionOutcomes::new(SUGGESTED_CONNECT_PARAMS),
             make_transport_connector: fake_transport_connector,
             route_provider_context: Default::default(),
+            telemetry: Default::default(),
         }
         .into();
 
@@ -1849,6 +1907,7 @@ mod test {
             service_level_attempts_record: ConnectionOutcomes::new(SUGGESTED_CONNECT_PARAMS),
             make_transport_connector: client_abort_connector,
             route_provider_context: Default::default(),
+            telemetry: Default::default(),
         }
         .into();
 
@@ -1932,6 +1991,7 @@ mod test {
                 };
                 let outcome = AttemptOutcome {
                     started,
+                    finished: started,
                     result: match self {
                         DirectSuccess | ProxySuccess => Ok(()),
                         DirectFailure | ProxyFailure => Err(UnsuccessfulOutcome::default()),
@@ -2001,6 +2061,7 @@ mod test {
             service_level_attempts_record: ConnectionOutcomes::new(SUGGESTED_CONNECT_PARAMS),
             make_transport_connector,
             route_provider_context: Default::default(),
+            telemetry: Default::default(),
         }
         .into();
 
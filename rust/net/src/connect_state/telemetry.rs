@@ -0,0 +1,268 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Aggregate, privacy-safe connection statistics.
+//!
+//! Unlike the per-route records kept for backoff, nothing here identifies a
+//! particular host, address, or proxy; routes are only grouped by *kind* (direct
+//! or domain-fronted, and what sort of proxy, if any).
+
+use std::time::Duration;
+
+use libsignal_net_infra::dns::{DnsLookupCounts, DnsResolver};
+use libsignal_net_infra::route::{AttemptOutcome, ConnectionProxyKind, UnresolvedRouteDescription};
+use tokio::time::Instant;
+
+/// Upper bounds (inclusive) of the buckets in a [`LatencyHistogram`].
+///
+/// Latencies longer than the last bound go into an extra overflow bucket.
+pub const LATENCY_BUCKET_UPPER_BOUNDS: [Duration; 7] = [
+    Duration::from_millis(100),
+    Duration::from_millis(250),
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_millis(2500),
+    Duration::from_secs(5),
+    Duration::from_secs(10),
+];
+
+/// The number of buckets in a [`LatencyHistogram`], including the overflow bucket.
+pub const LATENCY_BUCKET_COUNT: usize = LATENCY_BUCKET_UPPER_BOUNDS.len() + 1;
+
+/// Counts of successful connection latencies, bucketed by
+/// [`LATENCY_BUCKET_UPPER_BOUNDS`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LatencyHistogram {
+    counts: [u64; LATENCY_BUCKET_COUNT],
+}
+
+impl LatencyHistogram {
+    pub fn record(&mut self, latency: Duration) {
+        let bucket = LATENCY_BUCKET_UPPER_BOUNDS.partition_point(|bound| *bound < latency);
+        self.counts[bucket] += 1;
+    }
+
+    pub fn counts(&self) -> &[u64; LATENCY_BUCKET_COUNT] {
+        &self.counts
+    }
+}
+
+/// The category a route is reported under.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RouteKind {
+    pub domain_fronted: bool,
+    pub proxy: Option<ConnectionProxyKind>,
+}
+
+impl From<&UnresolvedRouteDescription> for RouteKind {
+    fn from(value: &UnresolvedRouteDescription) -> Self {
+        let UnresolvedRouteDescription {
+            front,
+            proxy,
+            target: _,
+        } = value;
+        Self {
+            domain_fronted: front.is_some(),
+            proxy: *proxy,
+        }
+    }
+}
+
+impl std::fmt::Display for RouteKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            domain_fronted,
+            proxy,
+        } = self;
+        f.write_str(if *domain_fronted { "fronted" } else { "direct" })?;
+        if let Some(proxy) = proxy {
+            let proxy: &'static str = proxy.into();
+            write!(f, "+{}", proxy.to_ascii_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+/// Outcomes of connection attempts over a single [`RouteKind`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RouteStats {
+    pub successes: u64,
+    pub failures: u64,
+    /// Time from starting an attempt to having a usable connection, for successes only.
+    pub latency: LatencyHistogram,
+}
+
+/// Running totals kept by [`ConnectState`](super::ConnectState) between network changes.
+#[derive(Clone, Debug)]
+pub(crate) struct ConnectionTelemetry {
+    since: Instant,
+    routes: Vec<(RouteKind, RouteStats)>,
+}
+
+impl Default for ConnectionTelemetry {
+    fn default() -> Self {
+        Self {
+            since: Instant::now(),
+            routes: Vec::new(),
+        }
+    }
+}
+
+impl ConnectionTelemetry {
+    /// Records the outcomes of a single call to [`connect`](crate::infra::route::connect).
+    pub(crate) fn record(
+        &mut self,
+        outcomes: impl IntoIterator<Item = (RouteKind, AttemptOutcome)>,
+    ) {
+        for (kind, outcome) in outcomes {
+            let stats = match self.routes.iter_mut().find(|(k, _)| *k == kind) {
+                Some((_, stats)) => stats,
+                None => {
+                    self.routes.push((kind, RouteStats::default()));
+                    &mut self.routes.last_mut().expect("just pushed").1
+                }
+            };
+            match outcome.result {
+                Ok(()) => {
+                    stats.successes += 1;
+                    stats
+                        .latency
+                        .record(outcome.finished.saturating_duration_since(outcome.started));
+                }
+                Err(_) => stats.failures += 1,
+            }
+        }
+    }
+
+    pub(crate) fn reset(&mut self, now: Instant) {
+        self.since = now;
+        self.routes.clear();
+    }
+
+    pub(crate) fn report(&self, dns_resolver: &DnsResolver) -> ConnectivityReport {
+        let Self { since, routes } = self;
+        ConnectivityReport {
+            collection_period: since.elapsed(),
+            routes: routes.clone(),
+            dns_lookups: dns_resolver.lookup_counts(),
+        }
+    }
+}
+
+/// A snapshot of connection health since the last network change.
+///
+/// Contains only aggregate counts, so it is safe to upload as-is.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConnectivityReport {
+    /// How long statistics have been collected for.
+    pub collection_period: Duration,
+    /// Per-kind route statistics, in the order each kind was first attempted.
+    pub routes: Vec<(RouteKind, RouteStats)>,
+    /// Which DNS sources answered hostname lookups.
+    pub dns_lookups: DnsLookupCounts,
+}
+
+impl ConnectivityReport {
+    /// Looks up the statistics for the route kind whose [`Display`](std::fmt::Display)
+    /// representation is `kind`.
+    pub fn route_stats(&self, kind: &str) -> Option<&RouteStats> {
+        self.routes
+            .iter()
+            .find(|(k, _)| k.to_string() == kind)
+            .map(|(_, stats)| stats)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use libsignal_net_infra::route::UnsuccessfulOutcome;
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case(Duration::ZERO => 0)]
+    #[test_case(Duration::from_millis(100) => 0)]
+    #[test_case(Duration::from_millis(101) => 1)]
+    #[test_case(Duration::from_secs(1) => 3)]
+    #[test_case(Duration::from_secs(10) => 6)]
+    #[test_case(Duration::from_secs(60) => 7)]
+    fn latency_bucket(latency: Duration) -> usize {
+        let mut histogram = LatencyHistogram::default();
+        histogram.record(latency);
+        histogram
+            .counts()
+            .iter()
+            .position(|count| *count == 1)
+            .expect("recorded once")
+    }
+
+    #[test_case(false, None => "direct")]
+    #[test_case(true, None => "fronted")]
+    #[test_case(false, Some(ConnectionProxyKind::Socks) => "direct+socks")]
+    #[test_case(true, Some(ConnectionProxyKind::Tls) => "fronted+tls")]
+    fn route_kind_display(domain_fronted: bool, proxy: Option<ConnectionProxyKind>) -> String {
+        RouteKind {
+            domain_fronted,
+            proxy,
+        }
+        .to_string()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn record_and_reset() {
+        const DIRECT: RouteKind = RouteKind {
+            domain_fronted: false,
+            proxy: None,
+        };
+        const FRONTED: RouteKind = RouteKind {
+            domain_fronted: true,
+            proxy: None,
+        };
+
+        let mut telemetry = ConnectionTelemetry::default();
+        let start = Instant::now();
+        // The fronted attempt starts later than the direct one, so its latency must be measured
+        // from its own start rather than the start of the whole connect call.
+        let fronted_start = start + Duration::from_secs(1);
+        telemetry.record([
+            (
+                DIRECT,
+                AttemptOutcome {
+                    started: start,
+                    finished: start + Duration::from_secs(2),
+                    result: Err(UnsuccessfulOutcome::ShortTerm),
+                },
+            ),
+            (
+                FRONTED,
+                AttemptOutcome {
+                    started: fronted_start,
+                    finished: fronted_start + Duration::from_millis(300),
+                    result: Ok(()),
+                },
+            ),
+        ]);
+        tokio::time::advance(Duration::from_secs(5)).await;
+
+        let report = telemetry.report(&DnsResolver::default());
+        assert_eq!(report.collection_period, Duration::from_secs(5));
+        assert_eq!(
+            report.route_stats("direct"),
+            Some(&RouteStats {
+                successes: 0,
+                failures: 1,
+                latency: LatencyHistogram::default(),
+            })
+        );
+        let fronted = report.route_stats("fronted").expect("recorded");
+        assert_eq!((fronted.successes, fronted.failures), (1, 0));
+        assert_eq!(fronted.latency.counts()[2], 1);
+
+        telemetry.reset(Instant::now());
+        let report = telemetry.report(&DnsResolver::default());
+        assert_eq!(report.collection_period, Duration::ZERO);
+        assert_eq!(report.routes, vec![]);
+    }
+}
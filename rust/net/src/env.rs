@@ -13,7 +13,7 @@ use boring_signal::ssl::SslVersion;
 use const_str::{hex, ip_addr};
 use http::HeaderValue;
 use libsignal_keytrans::{DeploymentMode, PublicConfig, VerifyingKey, VerifyingKeys, VrfPublicKey};
-use libsignal_net_infra::certs::RootCertificates;
+use libsignal_net_infra::certs::{CertificatePolicy, RootCertificates};
 use libsignal_net_infra::dns::lookup_result::LookupResult;
 use libsignal_net_infra::host::Host;
 use libsignal_net_infra::route::{
@@ -618,6 +618,18 @@ impl ConnectionConfig {
         permissive_config.min_tls_version = None;
         permissive_config
     }
+
+    /// Returns a copy of `self` that also enforces `policy` on direct connections.
+    ///
+    /// Domain-fronting proxies present their own certificates, so their roots are left as is.
+    pub fn config_with_certificate_policy(&self, policy: Arc<CertificatePolicy>) -> Self {
+        let mut config = self.clone();
+        config.cert = RootCertificates::WithPolicy {
+            roots: Arc::new(self.cert.clone()),
+            policy,
+        };
+        config
+    }
 }
 
 #[derive(Clone)]
@@ -1035,6 +1047,7 @@ mod test {
                     sni: Host::Domain("host".into()),
                     alpn: Some(Alpn::Http1_1),
                     min_protocol_version: Some(SslVersion::TLS1_2),
+                    ech_config: None,
                 },
                 inner: TcpRoute {
                     address: UnresolvedHost::from(Arc::from("host")),
@@ -1056,6 +1069,35 @@ mod test {
         };
     }
 
+    #[test]
+    fn certificate_policy_applies_to_direct_routes_only() {
+        let policy = Arc::new(CertificatePolicy::default());
+        let config = DOMAIN_CONFIG_CHAT
+            .connect
+            .config_with_certificate_policy(Arc::clone(&policy));
+        let routes = config
+            .route_provider(
+                EnableDomainFronting::OneDomainPerProxy,
+                OverrideNagleAlgorithm::UseSystemDefault,
+            )
+            .routes(&mut FakeContext::new())
+            .collect_vec();
+
+        let (direct, proxied) = routes.split_first().expect("has routes");
+        assert_eq!(
+            direct.inner.fragment.root_certs,
+            RootCertificates::WithPolicy {
+                roots: Arc::new(SIGNAL_ROOT_CERTIFICATES),
+                policy,
+            }
+        );
+        assert!(!proxied.is_empty());
+        assert!(proxied.iter().all(|route| !matches!(
+            route.inner.fragment.root_certs,
+            RootCertificates::WithPolicy { .. }
+        )));
+    }
+
     #[tokio::test]
     #[test_matrix([&DOMAIN_CONFIG_CHAT, &DOMAIN_CONFIG_CHAT_STAGING, &DOMAIN_CONFIG_CDSI, &DOMAIN_CONFIG_CDSI_STAGING])]
     async fn live_resolve_eq_static_resolution(config: &DomainConfig) {
@@ -5,6 +5,7 @@
 
 #![warn(clippy::unwrap_used)]
 
+pub mod account_recovery;
 pub mod auth;
 pub mod cdsi;
 pub mod certs;
@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+pub(crate) mod account_recovery;
 pub(crate) mod cds2;
 pub(crate) mod chat_provisioning;
 pub mod chat_websocket;
@@ -10,10 +10,12 @@ use std::time::Duration;
 
 use const_str::ip_addr;
 use itertools::Itertools;
-use libsignal_net_infra::dns::build_custom_resolver_cloudflare_doh;
 use libsignal_net_infra::dns::custom_resolver::CustomDnsResolver;
 use libsignal_net_infra::dns::dns_lookup::{DnsLookup, DnsLookupRequest, SystemDnsLookup};
 use libsignal_net_infra::dns::dns_transport_udp::UdpTransportConnectorFactory;
+use libsignal_net_infra::dns::{
+    build_custom_resolver_cloudflare_doh, build_custom_resolver_cloudflare_dot,
+};
 use libsignal_net_infra::route::UdpRoute;
 use libsignal_net_infra::timeouts::DNS_LATER_RESPONSE_GRACE_PERIOD;
 use libsignal_net_infra::utils::no_network_change_events;
@@ -97,3 +99,21 @@ async fn dns_over_https_lookup() {
     assert!(!v4.is_empty());
     assert!(!v6.is_empty());
 }
+
+#[tokio::test]
+async fn dns_over_tls_lookup() {
+    skip_unless_nonhermetic!();
+    let dns = build_custom_resolver_cloudflare_dot(&no_network_change_events(), Duration::MAX);
+
+    let result = dns
+        .resolve(DnsLookupRequest {
+            hostname: "signal.org".into(),
+            ipv6_enabled: true,
+        })
+        .await
+        .expect("can look up");
+
+    let (v4, v6): (Vec<_>, Vec<_>) = result.into_iter().partition(IpAddr::is_ipv4);
+    assert!(!v4.is_empty());
+    assert!(!v6.is_empty());
+}
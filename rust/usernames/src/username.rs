@@ -56,7 +56,7 @@ impl Debug for Username {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct NicknameLimits(RangeInclusive<usize>);
 
 impl Default for NicknameLimits {
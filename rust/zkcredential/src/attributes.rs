@@ -14,6 +14,8 @@
 //!   well with [blind issuance](crate::issuance::blind).
 //! - [`RevealedAttribute`], which is hidden from the issuing server and then revealed to the
 //!   verifying server.
+//! - [`NumericAttribute`], which is known to the issuing server and hidden from the verifying
+//!   server, which instead learns that it lies in a particular range.
 
 use std::marker::PhantomData;
 
@@ -388,6 +390,36 @@ impl RevealedAttribute for RistrettoPoint {
     }
 }
 
+/// A number known to the issuing server, but hidden from the verifying server.
+///
+/// Instead of the value itself, the client proves that it lies in a particular range, using
+/// [`PresentationProofBuilder::add_numeric_attribute_in_range`]. This is useful for things like
+/// "the subscription level is at least N" or "the expiration is within the next week".
+///
+/// The range proofs rely on the attribute having been issued as a `u64`, using
+/// [`IssuanceProofBuilder::add_numeric_attribute`].
+///
+/// [`PresentationProofBuilder::add_numeric_attribute_in_range`]:
+///     crate::presentation::PresentationProofBuilder::add_numeric_attribute_in_range
+/// [`IssuanceProofBuilder::add_numeric_attribute`]:
+///     crate::issuance::IssuanceProofBuilder::add_numeric_attribute
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, PartialDefault)]
+pub struct NumericAttribute(pub u64);
+
+impl NumericAttribute {
+    /// The generator used to represent numeric attributes as points.
+    pub(crate) fn G_v() -> RistrettoPoint {
+        static STORAGE: std::sync::LazyLock<RistrettoPoint> = std::sync::LazyLock::new(|| {
+            poksho::ShoSha256::new(b"Signal_ZKCredential_NumericAttribute_G_v_20261019").get_point()
+        });
+        *STORAGE
+    }
+
+    pub(crate) fn as_point(&self) -> RistrettoPoint {
+        Scalar::from(self.0) * Self::G_v()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::OnceLock;
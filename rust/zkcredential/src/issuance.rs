@@ -22,7 +22,7 @@ use poksho::shoapi::ShoApiExt as _;
 use poksho::{ShoApi, ShoHmacSha256};
 use serde::{Deserialize, Serialize};
 
-use crate::attributes::{Attribute, PublicAttribute};
+use crate::attributes::{Attribute, NumericAttribute, PublicAttribute};
 use crate::credentials::{
     Credential, CredentialKeyPair, CredentialPublicKey, NUM_SUPPORTED_ATTRS, SystemParams,
 };
@@ -95,6 +95,22 @@ impl<'a> IssuanceProofBuilder<'a> {
         self
     }
 
+    /// Adds a numeric attribute to the credential.
+    ///
+    /// The client may later keep this hidden from the verifying server, proving only that it lies
+    /// in a particular range.
+    ///
+    /// This is order-sensitive.
+    pub fn add_numeric_attribute(mut self, attr: &NumericAttribute) -> Self {
+        self.attr_points.push(attr.as_point());
+        assert!(
+            self.attr_points.len() <= NUM_SUPPORTED_ATTRS,
+            "more than {} hidden attribute points not supported",
+            NUM_SUPPORTED_ATTRS - 1
+        );
+        self
+    }
+
     fn get_poksho_statement(&self) -> poksho::Statement {
         // See Chase-Perrin-Zaverucha section 3.2.
         let mut st = poksho::Statement::new();
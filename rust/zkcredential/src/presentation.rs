@@ -20,7 +20,12 @@
 //! requests over time.
 //!
 //! Credential presentation is defined in Chase-Perrin-Zaverucha section 3.2; proofs for verifiable
-//! encryption are defined in section 4.1.
+//! encryption are defined in section 4.1. Hidden [numeric attributes][NumericAttribute] are an
+//! extension, with range proofs described in the [`range`] module.
+
+mod range;
+
+use std::ops::RangeInclusive;
 
 use curve25519_dalek::Scalar;
 use curve25519_dalek::ristretto::RistrettoPoint;
@@ -30,7 +35,7 @@ use poksho::shoapi::ShoApiExt as _;
 use poksho::{ShoApi, ShoHmacSha256};
 use serde::{Deserialize, Serialize};
 
-use crate::attributes::{self, Attribute, PublicAttribute, RevealedAttribute};
+use crate::attributes::{self, Attribute, NumericAttribute, PublicAttribute, RevealedAttribute};
 use crate::credentials::{
     Credential, CredentialKeyPair, CredentialPrivateKey, CredentialPublicKey, NUM_SUPPORTED_ATTRS,
     SystemParams,
@@ -55,8 +60,20 @@ pub struct PresentationProof {
     poksho_proof: Vec<u8>,
 }
 
+/// A [`PresentationProof`] along with proofs that its hidden numeric attributes lie in the expected
+/// ranges.
+///
+/// Use [`PresentationProofVerifier::verify_with_ranges`] to validate the proof.
+#[derive(Clone, Serialize, Deserialize, PartialDefault)]
+pub struct PresentationProofWithRanges {
+    proof: PresentationProof,
+    range_proofs: Vec<range::AttributeRangeProof>,
+}
+
 struct AttributeRef {
     key_index: Option<usize>,
+    /// Present only for numeric attributes.
+    range: Option<RangeInclusive<u64>>,
     first_point_index: usize,
     second_point_index: usize,
 }
@@ -158,6 +175,16 @@ struct PresentationProofBuilderCore<'a, T: MayHavePublicKey> {
 /// See also [`PresentationProofVerifier`].
 pub struct PresentationProofBuilder<'a> {
     core: PresentationProofBuilderCore<'a, AnyKeyPair>,
+    /// The values of numeric attributes, in order.
+    numeric_values: Vec<u64>,
+}
+
+/// A [`PresentationProofBuilder`] that has had at least one numeric attribute added.
+///
+/// The resulting proof must include range proofs for those attributes, so this can only produce a
+/// [`PresentationProofWithRanges`].
+pub struct PresentationProofWithRangesBuilder<'a> {
+    inner: PresentationProofBuilder<'a>,
 }
 
 /// Used to verify presentation proofs.
@@ -214,11 +241,21 @@ impl<'a, T: MayHavePublicKey> PresentationProofBuilderCore<'a, T> {
         // If we ever support attributes longer than two points we'll have to change this.
         self.attributes.push(AttributeRef {
             key_index,
+            range: None,
             first_point_index: first_index,
             second_point_index: first_index + attr_points.len() - 1,
         });
     }
 
+    fn add_numeric_attribute(&mut self, attr_point: RistrettoPoint, range: RangeInclusive<u64>) {
+        self.add_attribute(&[attr_point], None);
+        self.attributes.last_mut().expect("just added").range = Some(range);
+    }
+
+    fn has_numeric_attributes(&self) -> bool {
+        self.attributes.iter().any(|attr| attr.range.is_some())
+    }
+
     fn get_poksho_statement(&self) -> poksho::Statement {
         let mut st = poksho::Statement::new();
         // These terms are from Chase-Perrin-Zaverucha section 3.2.
@@ -286,9 +323,19 @@ impl<'a, T: MayHavePublicKey> PresentationProofBuilderCore<'a, T> {
                         ),
                     ],
                 );
+            } else if attr.range.is_some() {
+                // A NumericAttribute is a hidden scalar multiple of G_v. This ties the scalar to
+                // the one in the separate range proof.
+                // C_y1 = z * G_y1 + v1 * G_v
+                st.add(
+                    &format!("C_y{}", attr.first_point_index),
+                    &[
+                        ("z", &format!("G_y{}", attr.first_point_index)),
+                        (&format!("v{}", attr.first_point_index), "G_v"),
+                    ],
+                );
             } else {
                 // If the attribute does not use a key, it's a RevealedAttribute.
-                // (We don't currently support hidden scalar attributes.)
                 // This is from section 3.2 again; C_y1 is otherwise unbound.
                 debug_assert_eq!(attr.first_point_index, attr.second_point_index);
                 // C_y1 = z * G_y1
@@ -312,6 +359,8 @@ impl<'a, T: MayHavePublicKey> PresentationProofBuilderCore<'a, T> {
     /// points necessary to prove the validity of encryption keys: `0`, `G_a1_{key}`, `G_a2_{key}`,
     /// and `sum(A)`.
     ///
+    /// If there are any numeric attributes, this also includes their generator `G_v`.
+    ///
     /// The caller is responsible for handling the presenter's one-off public point `Z` (which the
     /// verifier derives from the commitments and public attributes); the appropriate `C_y{i}` for
     /// all attributes besides public attributes (depending on whether or not attributes are
@@ -360,6 +409,10 @@ impl<'a, T: MayHavePublicKey> PresentationProofBuilderCore<'a, T> {
         point_args.add("C_y0", commitments.C_y[0]);
         // Other C_y depend on the form of the attribute.
 
+        if self.has_numeric_attributes() {
+            point_args.add("G_v", NumericAttribute::G_v());
+        }
+
         point_args
     }
 }
@@ -387,6 +440,7 @@ impl<'a> PresentationProofBuilder<'a> {
         _ = label;
         Self {
             core: PresentationProofBuilderCore::with_authenticated_message(message),
+            numeric_values: vec![],
         }
     }
 
@@ -442,6 +496,31 @@ impl<'a> PresentationProofBuilder<'a> {
         self
     }
 
+    /// Adds a numeric attribute to the proof, which will be hidden from the verifying server.
+    ///
+    /// Instead, the verifying server will be convinced that the attribute's value lies in `range`.
+    /// The returned builder generates the necessary range proofs along with the presentation.
+    ///
+    /// This is order-sensitive.
+    ///
+    /// # Panics
+    ///
+    /// If `attr` is not in `range`.
+    pub fn add_numeric_attribute_in_range(
+        mut self,
+        attr: &NumericAttribute,
+        range: RangeInclusive<u64>,
+    ) -> PresentationProofWithRangesBuilder<'a> {
+        self.push_numeric_attribute(attr, range);
+        PresentationProofWithRangesBuilder { inner: self }
+    }
+
+    fn push_numeric_attribute(&mut self, attr: &NumericAttribute, range: RangeInclusive<u64>) {
+        assert!(range.contains(&attr.0), "numeric attribute is out of range");
+        self.core.add_numeric_attribute(attr.as_point(), range);
+        self.numeric_values.push(attr.0);
+    }
+
     /// Generates the presentation of `credential` using the server-provided `public_key`.
     ///
     /// Note that this does not consume `credential`; indeed, it is recommended to use a new
@@ -460,6 +539,29 @@ impl<'a> PresentationProofBuilder<'a> {
         credential: &Credential,
         randomness: [u8; RANDOMNESS_LEN],
     ) -> PresentationProof {
+        debug_assert!(
+            !self.core.has_numeric_attributes(),
+            "numeric attributes produce a PresentationProofWithRangesBuilder"
+        );
+        self.present_impl(public_key, credential, randomness).0
+    }
+
+    /// Iterates over the point index, range, and value of every numeric attribute.
+    fn numeric_attributes(&self) -> impl Iterator<Item = (usize, &RangeInclusive<u64>, u64)> {
+        self.core
+            .attributes
+            .iter()
+            .filter_map(|attr| Some((attr.first_point_index, attr.range.as_ref()?)))
+            .zip(&self.numeric_values)
+            .map(|((index, range), value)| (index, range, *value))
+    }
+
+    fn present_impl(
+        self,
+        public_key: &CredentialPublicKey,
+        credential: &Credential,
+        randomness: [u8; RANDOMNESS_LEN],
+    ) -> (PresentationProof, Vec<range::AttributeRangeProof>) {
         let credentials_system = SystemParams::get_hardcoded();
 
         let mut sho = ShoHmacSha256::new(b"Signal_ZKCredential_Presentation_20230410");
@@ -506,12 +608,16 @@ impl<'a> PresentationProofBuilder<'a> {
             scalar_args.add(format!("a2_{key_id}"), key.a2);
             scalar_args.add(format!("z1_{key_id}"), -z * key.a1);
         }
+        for (index, _, value) in self.numeric_attributes() {
+            scalar_args.add(format!("v{index}"), Scalar::from(value));
+        }
 
         let mut point_args = self.core.prepare_non_attribute_point_args(I, &commitments);
         point_args.add("Z", Z);
         for attr in &self.core.attributes {
             let &AttributeRef {
                 key_index,
+                ref range,
                 first_point_index,
                 second_point_index,
             } = attr;
@@ -530,7 +636,7 @@ impl<'a> PresentationProofBuilder<'a> {
                     format!("C_y{second_point_index}-E_A{second_point_index}"),
                     commitments.C_y[second_point_index] - E_A2,
                 );
-            } else {
+            } else if range.is_none() {
                 debug_assert!(
                     self.core.attr_points[first_point_index] == RistrettoPoint::identity(),
                     "revealed attributes are incorporated by the server"
@@ -549,9 +655,91 @@ impl<'a> PresentationProofBuilder<'a> {
             )
             .expect("valid proof");
 
-        PresentationProof {
-            commitments,
-            poksho_proof,
+        let range_proofs = self
+            .numeric_attributes()
+            .map(|(index, range, value)| {
+                range::AttributeRangeProof::prove(
+                    range,
+                    value,
+                    z,
+                    range::Commitment {
+                        G_y: credentials_system.G_y[index],
+                        C_y: commitments.C_y[index],
+                    },
+                    self.core.authenticated_message,
+                    &mut sho,
+                )
+            })
+            .collect();
+
+        (
+            PresentationProof {
+                commitments,
+                poksho_proof,
+            },
+            range_proofs,
+        )
+    }
+}
+
+impl<'a> PresentationProofWithRangesBuilder<'a> {
+    /// See [`PresentationProofBuilder::add_attribute`].
+    pub fn add_attribute(
+        self,
+        attr: &dyn Attribute,
+        key: &attributes::KeyPair<impl attributes::Domain>,
+    ) -> Self {
+        Self {
+            inner: self.inner.add_attribute(attr, key),
+        }
+    }
+
+    /// See [`PresentationProofBuilder::add_attribute_without_verified_key`].
+    pub fn add_attribute_without_verified_key(
+        self,
+        attr: &dyn Attribute,
+        key: &attributes::KeyPair<impl attributes::Domain>,
+    ) -> Self {
+        Self {
+            inner: self.inner.add_attribute_without_verified_key(attr, key),
+        }
+    }
+
+    /// See [`PresentationProofBuilder::add_revealed_attribute`].
+    pub fn add_revealed_attribute(self, attr: &dyn RevealedAttribute) -> Self {
+        Self {
+            inner: self.inner.add_revealed_attribute(attr),
+        }
+    }
+
+    /// See [`PresentationProofBuilder::add_numeric_attribute_in_range`].
+    ///
+    /// # Panics
+    ///
+    /// If `attr` is not in `range`.
+    pub fn add_numeric_attribute_in_range(
+        mut self,
+        attr: &NumericAttribute,
+        range: RangeInclusive<u64>,
+    ) -> Self {
+        self.inner.push_numeric_attribute(attr, range);
+        self
+    }
+
+    /// Generates the presentation of `credential` using the server-provided `public_key`, along
+    /// with proofs that the numeric attributes lie in their ranges.
+    ///
+    /// The same guidelines for `randomness` apply as for [`PresentationProofBuilder::present`].
+    pub fn present_with_ranges(
+        self,
+        public_key: &CredentialPublicKey,
+        credential: &Credential,
+        randomness: [u8; RANDOMNESS_LEN],
+    ) -> PresentationProofWithRanges {
+        let (proof, range_proofs) = self.inner.present_impl(public_key, credential, randomness);
+        PresentationProofWithRanges {
+            proof,
+            range_proofs,
         }
     }
 }
@@ -631,13 +819,79 @@ impl<'a> PresentationProofVerifier<'a> {
         self
     }
 
+    /// Adds a hidden numeric attribute to check against the credential, which must lie in
+    /// `range`.
+    ///
+    /// Proofs with numeric attributes must be checked with
+    /// [`verify_with_ranges`](Self::verify_with_ranges).
+    ///
+    /// This is order-sensitive.
+    pub fn add_numeric_attribute_in_range(mut self, range: RangeInclusive<u64>) -> Self {
+        // The value is hidden; the range proof stands in for it.
+        self.core
+            .add_numeric_attribute(RistrettoPoint::identity(), range);
+        self
+    }
+
     fn finalize_public_attrs(&mut self) {
         debug_assert!(self.core.attr_points[0] == RistrettoPoint::identity());
         self.core.attr_points[0] = self.public_attrs.get_point();
     }
 
     /// Verifies the given `proof` over the accrued attributes using the given `key_pair`.
+    ///
+    /// Always fails if any numeric attributes have been added, since a plain [`PresentationProof`]
+    /// doesn't prove their ranges; use [`verify_with_ranges`](Self::verify_with_ranges) instead.
     pub fn verify(
+        self,
+        key_pair: &CredentialKeyPair,
+        proof: &PresentationProof,
+    ) -> Result<(), VerificationFailure> {
+        if self.core.has_numeric_attributes() {
+            return Err(VerificationFailure);
+        }
+        self.verify_impl(key_pair, proof)
+    }
+
+    /// Verifies the given `proof` over the accrued attributes using the given `key_pair`, including
+    /// that every numeric attribute lies in its range.
+    pub fn verify_with_ranges(
+        self,
+        key_pair: &CredentialKeyPair,
+        proof: &PresentationProofWithRanges,
+    ) -> Result<(), VerificationFailure> {
+        let PresentationProofWithRanges {
+            proof,
+            range_proofs,
+        } = proof;
+        let numeric_attributes = self
+            .core
+            .attributes
+            .iter()
+            .filter_map(|attr| Some((attr.first_point_index, attr.range.as_ref()?)))
+            .collect::<Vec<_>>();
+        if numeric_attributes.len() != range_proofs.len()
+            || proof.commitments.C_y.len() != self.core.attr_points.len()
+        {
+            return Err(VerificationFailure);
+        }
+
+        let credentials_system = SystemParams::get_hardcoded();
+        for ((index, range), range_proof) in numeric_attributes.into_iter().zip(range_proofs) {
+            range_proof.verify(
+                range,
+                range::Commitment {
+                    G_y: credentials_system.G_y[index],
+                    C_y: proof.commitments.C_y[index],
+                },
+                self.core.authenticated_message,
+            )?;
+        }
+
+        self.verify_impl(key_pair, proof)
+    }
+
+    fn verify_impl(
         mut self,
         key_pair: &CredentialKeyPair,
         proof: &PresentationProof,
@@ -675,6 +929,7 @@ impl<'a> PresentationProofVerifier<'a> {
                 first_point_index,
                 second_point_index,
                 key_index,
+                ref range,
             } = attr;
             point_args.add(format!("C_y{first_point_index}"), C_y[first_point_index]);
 
@@ -691,7 +946,7 @@ impl<'a> PresentationProofVerifier<'a> {
                     format!("C_y{second_point_index}-E_A{second_point_index}"),
                     C_y[second_point_index] - self.core.attr_points[second_point_index],
                 );
-            } else {
+            } else if range.is_none() {
                 // Check that the revealed attributes match the original issuance.
                 Z -= y[first_point_index] * self.core.attr_points[first_point_index];
             }
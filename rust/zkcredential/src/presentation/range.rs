@@ -0,0 +1,492 @@
+//
+// Copyright 2026 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Proofs that a hidden [`NumericAttribute`] lies in a range.
+//!
+//! In a presentation, a numeric attribute with value `v` appears only in the commitment
+//! `C_y = z * G_y + v * G_v`. To show that `v >= min`, the client writes `v - min` in binary as
+//! `sum(2^i * b_i)` and commits to each bit separately as `B_i = b_i * G_v + r_i * H`. It then
+//! proves, in a single [`poksho::Statement`]:
+//!
+//! - that it knows `z` and `v` for `C_y` (tying this proof to the credential presentation, since
+//!   a commitment can't be opened two different ways without knowing the discrete log of `G_v`
+//!   relative to `G_y`),
+//! - that `sum(2^i * B_i) + min * G_v = v * G_v + r * H` for some `r`, and
+//! - that each `b_i` is 0 or 1, by showing that `B_i = b_i * G_v + r_i * H` and also
+//!   `B_i = b_i * B_i + s_i * H`. Expanding the second equation gives `b_i^2 = b_i`.
+//!
+//! The upper bound `v <= max` works the same way with `max - v`.
+//!
+//! Because everything here happens modulo the group order, `v - min` being a small non-negative
+//! number only implies `v >= min` if `v` is known to be small to begin with. That's guaranteed by
+//! the issuing server only ever issuing numeric attributes as `u64` values.
+
+use std::ops::RangeInclusive;
+use std::sync::LazyLock;
+
+use curve25519_dalek::Scalar;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use partial_default::PartialDefault;
+use poksho::ShoApi;
+use serde::{Deserialize, Serialize};
+
+use crate::attributes::NumericAttribute;
+use crate::sho::ShoExt;
+use crate::{RANDOMNESS_LEN, VerificationFailure};
+
+/// The generator used to blind bit commitments, independent of [`NumericAttribute::G_v`].
+static H: LazyLock<RistrettoPoint> = LazyLock::new(|| {
+    poksho::ShoSha256::new(b"Signal_ZKCredential_RangeProof_H_20261019").get_point()
+});
+
+/// A proof that a single bound holds.
+#[derive(Clone, Serialize, Deserialize, PartialDefault)]
+struct BoundProof {
+    bit_commitments: Vec<RistrettoPoint>,
+    poksho_proof: Vec<u8>,
+}
+
+/// Proves that a hidden [`NumericAttribute`] lies in a particular range.
+///
+/// A bound is omitted when it's implied by the attribute being a `u64`, i.e. a lower bound of 0 or
+/// an upper bound of `u64::MAX`.
+#[derive(Clone, Serialize, Deserialize, PartialDefault)]
+pub(super) struct AttributeRangeProof {
+    lower: Option<BoundProof>,
+    upper: Option<BoundProof>,
+}
+
+/// The presentation's commitment to the attribute, `C_y = z * G_y + v * G_v`.
+#[derive(Clone, Copy)]
+pub(super) struct Commitment {
+    pub(super) G_y: RistrettoPoint,
+    pub(super) C_y: RistrettoPoint,
+}
+
+#[derive(Clone, Copy)]
+enum Bound {
+    Lower(u64),
+    Upper(u64),
+}
+
+impl Bound {
+    /// The bounds that need to be proven for `range`.
+    fn for_range(range: &RangeInclusive<u64>) -> [Option<Self>; 2] {
+        [
+            (*range.start() != 0).then_some(Self::Lower(*range.start())),
+            (*range.end() != u64::MAX).then_some(Self::Upper(*range.end())),
+        ]
+    }
+
+    /// The number of bits needed to write the distance from any value in `range` to the bound.
+    fn bit_count(range: &RangeInclusive<u64>) -> usize {
+        let width = range.end() - range.start();
+        usize::try_from(u64::BITS - width.leading_zeros()).expect("at most 64")
+    }
+
+    /// The distance from `value` to the bound, which must be non-negative.
+    fn distance(self, value: u64) -> u64 {
+        match self {
+            Self::Lower(min) => value - min,
+            Self::Upper(max) => max - value,
+        }
+    }
+
+    /// Computes `v * G_v + r * H` from the bit commitments, where `r` is the (signed) weighted sum
+    /// of the bits' blinding scalars.
+    fn combine(self, bit_commitments: &[RistrettoPoint]) -> RistrettoPoint {
+        let weighted_sum = bit_commitments
+            .iter()
+            .enumerate()
+            .map(|(i, B_i)| Scalar::from(1u64 << i) * B_i)
+            .sum::<RistrettoPoint>();
+        match self {
+            Self::Lower(min) => weighted_sum + Scalar::from(min) * NumericAttribute::G_v(),
+            Self::Upper(max) => Scalar::from(max) * NumericAttribute::G_v() - weighted_sum,
+        }
+    }
+
+    fn get_poksho_statement(bit_count: usize) -> poksho::Statement {
+        let mut st = poksho::Statement::new();
+        st.add("C_y", &[("z", "G_y"), ("v", "G_v")]);
+        st.add("L", &[("v", "G_v"), ("r", "H")]);
+        for i in 0..bit_count {
+            let (B_i, b_i) = (format!("B{i}"), format!("b{i}"));
+            st.add(&B_i, &[(&b_i, "G_v"), (&format!("r{i}"), "H")]);
+            st.add(&B_i, &[(&b_i, &B_i), (&format!("s{i}"), "H")]);
+        }
+        st
+    }
+
+    fn point_args(
+        commitment: Commitment,
+        L: RistrettoPoint,
+        bit_commitments: &[RistrettoPoint],
+    ) -> poksho::PointArgs {
+        let mut point_args = poksho::PointArgs::new();
+        point_args.add("G_y", commitment.G_y);
+        point_args.add("C_y", commitment.C_y);
+        point_args.add("G_v", NumericAttribute::G_v());
+        point_args.add("H", *H);
+        point_args.add("L", L);
+        for (i, B_i) in bit_commitments.iter().enumerate() {
+            point_args.add(format!("B{i}"), *B_i);
+        }
+        point_args
+    }
+
+    fn prove(
+        self,
+        bit_count: usize,
+        value: u64,
+        z: Scalar,
+        commitment: Commitment,
+        authenticated_message: &[u8],
+        sho: &mut dyn ShoApi,
+    ) -> BoundProof {
+        let distance = self.distance(value);
+
+        let mut scalar_args = poksho::ScalarArgs::new();
+        scalar_args.add("z", z);
+        scalar_args.add("v", Scalar::from(value));
+
+        let mut bit_commitments = Vec::with_capacity(bit_count);
+        let mut r = Scalar::ZERO;
+        for i in 0..bit_count {
+            let b_i = Scalar::from((distance >> i) & 1);
+            let r_i = sho.get_scalar();
+            bit_commitments.push(b_i * NumericAttribute::G_v() + r_i * *H);
+            r += Scalar::from(1u64 << i) * r_i;
+            scalar_args.add(format!("b{i}"), b_i);
+            scalar_args.add(format!("r{i}"), r_i);
+            scalar_args.add(format!("s{i}"), r_i * (Scalar::ONE - b_i));
+        }
+        scalar_args.add(
+            "r",
+            match self {
+                Self::Lower(_) => r,
+                Self::Upper(_) => -r,
+            },
+        );
+
+        let L = self.combine(&bit_commitments);
+        let point_args = Self::point_args(commitment, L, &bit_commitments);
+        let mut nonce = [0; RANDOMNESS_LEN];
+        sho.squeeze_and_ratchet_into(&mut nonce);
+        let poksho_proof = Self::get_poksho_statement(bit_count)
+            .prove(&scalar_args, &point_args, authenticated_message, &nonce)
+            .expect("valid proof");
+
+        BoundProof {
+            bit_commitments,
+            poksho_proof,
+        }
+    }
+
+    fn verify(
+        self,
+        bit_count: usize,
+        commitment: Commitment,
+        authenticated_message: &[u8],
+        proof: &BoundProof,
+    ) -> Result<(), VerificationFailure> {
+        if proof.bit_commitments.len() != bit_count {
+            return Err(VerificationFailure);
+        }
+        let L = self.combine(&proof.bit_commitments);
+        let point_args = Self::point_args(commitment, L, &proof.bit_commitments);
+        Self::get_poksho_statement(bit_count)
+            .verify_proof(&proof.poksho_proof, &point_args, authenticated_message)
+            .map_err(|_| VerificationFailure)
+    }
+}
+
+impl AttributeRangeProof {
+    /// Proves that `value` lies in `range`, where `z` is the randomness used for `commitment`.
+    ///
+    /// # Panics
+    ///
+    /// If `value` is not in `range`.
+    pub(super) fn prove(
+        range: &RangeInclusive<u64>,
+        value: u64,
+        z: Scalar,
+        commitment: Commitment,
+        authenticated_message: &[u8],
+        sho: &mut dyn ShoApi,
+    ) -> Self {
+        assert!(range.contains(&value), "numeric attribute is out of range");
+        let bit_count = Bound::bit_count(range);
+        let mut prove = |bound: Bound| {
+            bound.prove(
+                bit_count,
+                value,
+                z,
+                commitment,
+                authenticated_message,
+                &mut *sho,
+            )
+        };
+        let [lower, upper] = Bound::for_range(range);
+        Self {
+            lower: lower.map(&mut prove),
+            upper: upper.map(&mut prove),
+        }
+    }
+
+    /// Verifies that the attribute in `commitment` lies in `range`.
+    pub(super) fn verify(
+        &self,
+        range: &RangeInclusive<u64>,
+        commitment: Commitment,
+        authenticated_message: &[u8],
+    ) -> Result<(), VerificationFailure> {
+        if range.is_empty() {
+            return Err(VerificationFailure);
+        }
+        let bit_count = Bound::bit_count(range);
+        let [lower_bound, upper_bound] = Bound::for_range(range);
+        for (bound, proof) in [(lower_bound, &self.lower), (upper_bound, &self.upper)] {
+            match (bound, proof) {
+                (None, None) => {}
+                (Some(bound), Some(proof)) => {
+                    bound.verify(bit_count, commitment, authenticated_message, proof)?
+                }
+                (None, Some(_)) | (Some(_), None) => return Err(VerificationFailure),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use const_str::hex;
+
+    use super::*;
+    use crate::credentials::{Credential, CredentialKeyPair};
+    use crate::issuance::IssuanceProofBuilder;
+    use crate::presentation::{
+        PresentationProofBuilder, PresentationProofVerifier, PresentationProofWithRanges,
+    };
+
+    const LABEL: &[u8] = b"20261019_Test_RangeProof";
+    const EXPIRATION: u64 = 1_800_000_000;
+
+    fn issue(value: u64) -> (CredentialKeyPair, Credential) {
+        let key_pair = CredentialKeyPair::generate([0x10; RANDOMNESS_LEN]);
+        let proof = IssuanceProofBuilder::new(LABEL)
+            .add_public_attribute(&EXPIRATION)
+            .add_numeric_attribute(&NumericAttribute(value))
+            .issue(&key_pair, [0x11; RANDOMNESS_LEN]);
+        let credential = IssuanceProofBuilder::new(LABEL)
+            .add_public_attribute(&EXPIRATION)
+            .add_numeric_attribute(&NumericAttribute(value))
+            .verify(key_pair.public_key(), proof)
+            .expect("valid issuance");
+        (key_pair, credential)
+    }
+
+    fn check(
+        value: u64,
+        proven_range: RangeInclusive<u64>,
+        checked_range: RangeInclusive<u64>,
+    ) -> Result<(), VerificationFailure> {
+        let (key_pair, credential) = issue(value);
+        let proof = PresentationProofBuilder::new(LABEL)
+            .add_numeric_attribute_in_range(&NumericAttribute(value), proven_range)
+            .present_with_ranges(key_pair.public_key(), &credential, [0x12; RANDOMNESS_LEN]);
+        PresentationProofVerifier::new(LABEL)
+            .add_public_attribute(&EXPIRATION)
+            .add_numeric_attribute_in_range(checked_range)
+            .verify_with_ranges(&key_pair, &proof)
+    }
+
+    #[test]
+    fn test_vectors() {
+        const CASES: &[(u64, RangeInclusive<u64>, RangeInclusive<u64>, bool)] = &[
+            (5, 5..=5, 5..=5, true),
+            (0, 0..=10, 0..=10, true),
+            (10, 0..=10, 0..=10, true),
+            (18, 18..=u64::MAX, 18..=u64::MAX, true),
+            (u64::MAX, 3..=u64::MAX, 3..=u64::MAX, true),
+            (12345, 0..=u64::MAX, 0..=u64::MAX, true),
+            (1 << 40, 1000..=(1 << 41), 1000..=(1 << 41), true),
+            // The verifier must check the same range the client proved.
+            (5, 0..=10, 0..=5, false),
+            (5, 0..=10, 6..=10, false),
+            (20, 18..=u64::MAX, 21..=u64::MAX, false),
+            (5, 5..=5, 0..=u64::MAX, false),
+        ];
+        for (value, proven_range, checked_range, expected) in CASES {
+            assert_eq!(
+                check(*value, proven_range.clone(), checked_range.clone()).is_ok(),
+                *expected,
+                "{value} in {proven_range:?}, checked against {checked_range:?}"
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "numeric attribute is out of range")]
+    fn cannot_prove_out_of_range() {
+        _ = check(17, 18..=u64::MAX, 18..=u64::MAX);
+    }
+
+    #[test]
+    fn plain_verification_rejects_numeric_attributes() {
+        let (key_pair, credential) = issue(42);
+        let proof = PresentationProofBuilder::new(LABEL)
+            .add_numeric_attribute_in_range(&NumericAttribute(42), 10..=100)
+            .present_with_ranges(key_pair.public_key(), &credential, [0x12; RANDOMNESS_LEN]);
+        PresentationProofVerifier::new(LABEL)
+            .add_public_attribute(&EXPIRATION)
+            .add_numeric_attribute_in_range(10..=100)
+            .verify(&key_pair, &proof.proof)
+            .expect_err("needs range proofs");
+    }
+
+    #[test]
+    fn fixed_serialized_proof() {
+        // Generated once with fixed randomness. The range proofs draw their nonces from the same
+        // randomness as the presentation, so this also catches changes to how it's shared out.
+        const EXPECTED: &[u8] = &hex!(
+            "aa54f80c1d0187dc1c004d20e8874e6815b656293ed6927e55750eb2f22dd15dba282ce46be03dca
+            45206055c3b7023465e792cd3f48fce208a63f3a94937b61aa59124f3e4dace9d6bdc552ee619f8f
+            3d8cfff0ea673da9cab3682b995bc5070200000000000000ba6bacbb2fa60722bfebf7bec78f4b31
+            688e0aec5820c91b2254a2371793272c181697b0986f791033493d3166a14a6748a9eba7be9235ae
+            6330a587a2e7625ea0000000000000000fff31554a4add02d94ac253233e3f5e60506619be6c6ba1
+            fb3accebdd9ff808b3e7fdbf36b55f2f5b59e9a9fcf9059ae182e0d4f9ae1b9cfdffa76115161a03
+            9a1d9d1090bd923d02fe5469eda0ce82117ffaf5e710060dc7dd11c3676a6804433b4c529ddcce4a
+            ec5c35c624fcfbf0c59d433b34f39269a5bfc03a098642080ce71f21881ecc178525d628c85765b2
+            1412d7a3d0bad0ed6342657e029a37050100000000000000010700000000000000e6fb7f232771c1
+            c943bd780a98f6f8d445d4e90dc4b21e83c522bc62c2263d4504621c09a8be897ac412959f423b30
+            16ba2492e3cd3f210f21639394b696a12d226f82117ea63bbf00600a42c94c572cd82e14a6e12624
+            11ffe01426b98e2f16d826ef2d5dc0d1a2b6e493e1c39cf97f85083575b28534800fa45d0340a2b8
+            048421b9059720574f974d4dd60376f3c1996331212e804df156f4b5523fdd5f414408aed97859f7
+            55d91dbe2d65eb14a6495134fd34ad5f8d5f2cad94a3c35d69c64638a958b6cc9fd26603686f5a5b
+            fed79d3641d6ec19dc381953d96733383e2003000000000000294c7c90323952c2655c788694985b
+            f145a1e7c150867b5025b1a1f969e5140f56ca0798507aa61ebd968580eb20e335b1e1545bd86805
+            1a4f079b8a1b4ad50715c550a5251e15f1687cf4dcef027f9cd080314f2da2e5d0ca4184945a0a13
+            0cfde1886ea4639611dadb70b1bf4487b34057cf5133ac34a70c922fbfcb8d8a04a67028cad8be8e
+            30dd0abfc82a7d84a1daa01fe45b55c6235deba7af52f3f004e876c5bde520388a3a95b30ea78724
+            403505c14fead1f6f827aaad74b35129048925a733e632e968be17a55cd92b2d0cb8f5ed2ae5f385
+            9ce2a49ac97ba16d00c18ea4b9a8dbd3106fd6cd43c6db054fd0d85edc9bfcf1b4d8573994058b4e
+            07ba764ecb3683dc5fa8320a1742399877f24fca860b99601b99b248557f05b509bd5b6856ed92d1
+            a2a55043a7565bcbe204d232ee4a559da41cd15dd203983204e8b56b95a9b11c27a1bd033659d063
+            c1c719cbd851c63149330a7c1d1de2b00a11e8e52e1fded55e85fe606cd01d5e1a1d237275bb37c7
+            132062fda4978f050fe5c686900740ed5d3d2dd35afcf6e262086db351093cd097f64d8c3ea1297b
+            0662415345a57a724505560444386ced8ec7e38be2d8c3ffd7bfcabd036ed4b005ff861c8c20f99a
+            613463f2f5557c377d515a1cda29debda679bad8cd1430da0f0fd9f20c74544e1ce57e1f7ad053b6
+            6d3a806c01ff28f47ca166e7d4972bfe0e96954492419414d7d322ef77481e492befab44a2f347ab
+            65ca28e69d8235b109bde6cabedd9701131f7a9133964c75ec5f461a3030d02c7898dc236502fb94
+            08309437d95faa393c9a355fb42714f0a86635249426275cd2420869fc3ea47005c021d177a6db0d
+            8909675eef5cb9902bd0d906fb86cb290da65fee699b849808ad1a3a49182ed7cebe66877d76cd24
+            42cf62667e2faf529e874e677d5bd9cb096bcffd218974a55e481e48508082ab006e3f4261625990
+            f729db216d399beb058fce50bab3c8711c31c520a3ff0e57cbc4ed644e43c8b7de791357aae0f34c
+            033f05900ce3bca7e6e2227a9884f73db09044bb22e0095d98d24a037038cc560fbe46927289a8af
+            bdaec300456981fb6c8866e68fe22c09da88087ac36759b708010700000000000000c466d36a509c
+            5c67855672bbb9592877f7b9d2ef0a5d3ef8de7ff80510400c690878a6a07572a647ad9d97d122d8
+            8db87221bb37cc2420124faaded5ae22b25742d21a5579ac72f287167df8de7858a121df167c5f7c
+            82dff3052c2a824fe11aa893aca6f1cf6f331b7fb7b38dde47c2f21c6eeaf49fa9c2149adfd0f01a
+            c2755a65a9e65565c5103edcab5b403d6b2a2f447735ef539a16444b6bed25d00d473281cbfd15a8
+            523c9b722732223a4491e30b1b2d61752bbab3195209f4b63c33aa49c681bfbed730178ec24cf4ce
+            9224263425d1393e06493efe2a89311be2282003000000000000be94fa7451c7451eb6fcfee8b8d6
+            1a65f3b3bf38ddcf9a5e78e7f1fc5cbfc3054206775d27004d001aa79c3d989d9ebffd56b584e2e2
+            5dae60f1dc9c4271a6055a558cb467a74126d90b181943a7cd991c696c9cc543048417f6f08e5d89
+            62001a9978f02a74b6a11ddf94741adb342ceefee5e9f68b88f614c36561b52ad900262800ab528e
+            7c8c60306605013cb34dc4b1ed88b86f29ac4c7d7fda48d9d30d87cfcfef68ba17513e5c33158ac6
+            2d20808b72c6664b8f9bf66f2c875126d105c86ac8889ea615b522fc0555a895b39d471efff68516
+            dcca4eb7194f766c810627ada73301305930225ce57e5dd184007d003a0f37fe6c7b7291e4d5dad9
+            e702efa4f29ae7aa4218ce4a9d61dd90b5aaec0bc016cef31ee18c9c64fe64ee7600edd10cfdc674
+            b2fa773883a4859751e62a416092ad1622d03a70c5db4ade6c0ec708965b667de7137ac4026f2f2a
+            6463cf7db4ba21b1c423346fe70eb5fb62083ec75094f2c8b8f6e09e5513e5c33d3d0463f8e65f4b
+            d5efedab9e6bb6130a0c896011c17220110f8ae9f60e5073457dc6827b91804f0f23f140759e8569
+            1803e73e4a37bc2e3cdb8735fd579438f7f2b4190dadcfd5edf8d5c5c0e8d6bf530940333c9d69ba
+            1ab7c18f4d6ce9680d29eade7b0351af520c9dc4f26f08becb0e27e52b50baa851a10195b7f13d0e
+            9ac4c577080d5f57212d52e24cec854b350f698c9eb57ee2eaff9e4185aaa98b4865388e28e7c88b
+            ae3bc15a3736f06a920f72f303f58c24a541fc5a8f57fdac80910d072c725dd463d317acd5fe3242
+            760c6c9dfb8c07da869d794fdce5ca9c97dc7367835bf2e80b9989d3af76562a9208529093e02de4
+            fe18893e7f2a2de43ec8f8f7def89da3966f9909aa57f2c273029293c9e085a9f177546ac11ff338
+            bcca5f5bd4f3c10f341034cbf802e263c908f3d075226ec97ffbc6bbc441225d55394823ba28e02d
+            f7f6eac56d7c72f0720ef8d33cd522369f379039f9d7db2468a95cd8fd601f38007a4c9c831a74d4
+            a1097014542bfc396775ed430a8eea4c80b1b5a053f35109529153a15b13fe2a4c09936f2505556c
+            76041c676b211c0620e713ecac93779d939e8600091b4e8f630c"
+        );
+
+        let (key_pair, credential) = issue(42);
+        let proof = PresentationProofBuilder::new(LABEL)
+            .add_numeric_attribute_in_range(&NumericAttribute(42), 10..=100)
+            .present_with_ranges(key_pair.public_key(), &credential, [0x12; RANDOMNESS_LEN]);
+        let serialized = bincode::serialize(&proof).expect("can serialize");
+        assert_eq!(serialized, EXPECTED);
+
+        let deserialized: PresentationProofWithRanges =
+            bincode::deserialize(EXPECTED).expect("valid proof");
+        PresentationProofVerifier::new(LABEL)
+            .add_public_attribute(&EXPIRATION)
+            .add_numeric_attribute_in_range(10..=100)
+            .verify_with_ranges(&key_pair, &deserialized)
+            .expect("valid");
+    }
+
+    #[test]
+    fn rejects_tampered_proofs() {
+        let (key_pair, credential) = issue(42);
+        let proof = PresentationProofBuilder::new(LABEL)
+            .add_numeric_attribute_in_range(&NumericAttribute(42), 10..=100)
+            .present_with_ranges(key_pair.public_key(), &credential, [0x12; RANDOMNESS_LEN]);
+        let verify = |proof: &PresentationProofWithRanges| {
+            PresentationProofVerifier::new(LABEL)
+                .add_public_attribute(&EXPIRATION)
+                .add_numeric_attribute_in_range(10..=100)
+                .verify_with_ranges(&key_pair, proof)
+        };
+        verify(&proof).expect("valid");
+
+        let mut tampered = proof.clone();
+        let bound = tampered.range_proofs[0]
+            .lower
+            .as_mut()
+            .expect("has lower bound");
+        bound.bit_commitments.swap(0, 1);
+        verify(&tampered).expect_err("tampered bits");
+
+        let mut tampered = proof.clone();
+        tampered.range_proofs[0].upper = None;
+        verify(&tampered).expect_err("missing bound");
+
+        let mut tampered = proof;
+        tampered.range_proofs.clear();
+        verify(&tampered).expect_err("missing range proof");
+    }
+
+    #[test]
+    fn other_credentials_cannot_be_substituted() {
+        // Commitments to a different value from a different credential don't match the range
+        // proof, even though both values are in range.
+        let (key_pair, credential) = issue(42);
+        let (_, other_credential) = issue(50);
+        let proof = PresentationProofBuilder::new(LABEL)
+            .add_numeric_attribute_in_range(&NumericAttribute(42), 10..=100)
+            .present_with_ranges(key_pair.public_key(), &credential, [0x12; RANDOMNESS_LEN]);
+        let other_proof = PresentationProofBuilder::new(LABEL)
+            .add_numeric_attribute_in_range(&NumericAttribute(50), 10..=100)
+            .present_with_ranges(
+                key_pair.public_key(),
+                &other_credential,
+                [0x13; RANDOMNESS_LEN],
+            );
+        let mixed = PresentationProofWithRanges {
+            proof: proof.proof,
+            range_proofs: other_proof.range_proofs,
+        };
+        PresentationProofVerifier::new(LABEL)
+            .add_public_attribute(&EXPIRATION)
+            .add_numeric_attribute_in_range(10..=100)
+            .verify_with_ranges(&key_pair, &mixed)
+            .expect_err("mismatched range proof");
+    }
+}
@@ -11,9 +11,11 @@ pub mod donations;
 mod endorsement_expiration;
 pub mod groups;
 pub mod profiles;
+pub mod rate_limits;
 pub mod receipts;
 
 pub mod generic_server_params;
+pub mod key_rotation;
 pub mod server_params;
 pub mod zk_credential_key;
 
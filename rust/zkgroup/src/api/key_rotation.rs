@@ -0,0 +1,521 @@
+//
+// Copyright 2026 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Versioned server keys, so that credential keys can be rotated one credential type at a time.
+//!
+//! A [`ServerKeySet`] holds every generation of one kind of server key (for example
+//! [`GenericServerSecretParams`]) that is still in use, each with a [`ServerKeyId`] and the time it
+//! became current. The issuing server always issues with the current generation and tells clients
+//! which ID it used; clients hold the matching set of public keys and look up the right one by ID.
+//! Verifying servers accept presentations made with the current generation, or with the previous
+//! one for a grace period after rotation, so that clients can use up credentials they already have.
+//!
+//! # Protocol
+//!
+//! 1. The issuing server hands out [`public_key_set`][ServerKeySet::public_key_set] to clients,
+//!    which refresh it periodically. Rotating with a `valid_from` in the future gives clients time
+//!    to learn a new generation before anything is issued with it.
+//! 2. The issuing server issues with [`current`][ServerKeySet::current] and wraps the response in
+//!    a [`KeyedResponse`] carrying that generation's ID.
+//! 3. The client looks up the public key for [`KeyedResponse::key_id`] and receives the response
+//!    as usual. A client that doesn't know the ID should refresh its key set.
+//! 4. Presentations don't carry an ID. Verifying servers try each
+//!    [accepted][ServerKeySet::accepted] generation in turn, using [`ServerKeySet::verify`].
+
+use partial_default::PartialDefault;
+use serde::{Deserialize, Serialize};
+
+use crate::common::serialization::ReservedByte;
+use crate::common::simple_types::*;
+use crate::generic_server_params::{GenericServerPublicParams, GenericServerSecretParams};
+use crate::{
+    EndorsementPublicKey, EndorsementServerRootKeyPair, ServerPublicParams, ServerSecretParams,
+    ZkGroupVerificationFailure,
+};
+
+/// Identifies one generation within a [`ServerKeySet`].
+///
+/// IDs are assigned in increasing order as keys are rotated, starting from 0.
+#[derive(
+    Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize, PartialDefault,
+)]
+#[serde(transparent)]
+pub struct ServerKeyId(u32);
+
+impl ServerKeyId {
+    pub const fn new(id: u32) -> Self {
+        Self(id)
+    }
+
+    pub const fn get(self) -> u32 {
+        self.0
+    }
+}
+
+/// A secret server key with a corresponding public key that can be handed out to clients.
+pub trait RotatableServerKey {
+    type Public;
+
+    fn public_key(&self) -> Self::Public;
+}
+
+impl RotatableServerKey for ServerSecretParams {
+    type Public = ServerPublicParams;
+
+    fn public_key(&self) -> Self::Public {
+        self.get_public_params()
+    }
+}
+
+impl RotatableServerKey for GenericServerSecretParams {
+    type Public = GenericServerPublicParams;
+
+    fn public_key(&self) -> Self::Public {
+        self.get_public_params()
+    }
+}
+
+impl RotatableServerKey for EndorsementServerRootKeyPair {
+    type Public = EndorsementPublicKey;
+
+    fn public_key(&self) -> Self::Public {
+        EndorsementServerRootKeyPair::public_key(self)
+    }
+}
+
+/// Why [`ServerKeySet::rotate`] refused to add a generation.
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum KeyRotationError {
+    /// New key generation must become valid after every existing one
+    NotAfterExisting,
+    /// No more key generation IDs are available
+    IdsExhausted,
+}
+
+/// One generation of a server key.
+#[derive(Clone, Serialize, Deserialize, PartialDefault)]
+pub struct ServerKeyGeneration<K> {
+    reserved: ReservedByte,
+    id: ServerKeyId,
+    valid_from: Timestamp,
+    key: K,
+}
+
+impl<K> ServerKeyGeneration<K> {
+    pub fn id(&self) -> ServerKeyId {
+        self.id
+    }
+
+    /// When this generation became (or will become) the current one.
+    pub fn valid_from(&self) -> Timestamp {
+        self.valid_from
+    }
+
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+}
+
+/// Every generation of one kind of server key that is still in use.
+///
+/// The same type is used for the secret keys held by the server and the public keys held by
+/// clients; see [`public_key_set`][Self::public_key_set].
+///
+/// Generations are kept in order, with both IDs and `valid_from` times strictly increasing;
+/// deserializing a set that breaks this, or has no generations at all, fails.
+#[derive(Clone, Serialize, PartialDefault)]
+pub struct ServerKeySet<K> {
+    reserved: ReservedByte,
+    generations: Vec<ServerKeyGeneration<K>>,
+}
+
+impl<K> ServerKeySet<K> {
+    /// Creates a set whose only generation, with ID 0, is valid from `valid_from`.
+    pub fn new(key: K, valid_from: Timestamp) -> Self {
+        Self {
+            reserved: Default::default(),
+            generations: vec![ServerKeyGeneration {
+                reserved: Default::default(),
+                id: ServerKeyId(0),
+                valid_from,
+                key,
+            }],
+        }
+    }
+
+    /// Adds a new generation that becomes current at `valid_from`, returning its ID.
+    ///
+    /// Scheduling the new generation in the future lets clients learn its public key before any
+    /// credentials are issued with it. `valid_from` must be later than that of every existing
+    /// generation.
+    pub fn rotate(
+        &mut self,
+        key: K,
+        valid_from: Timestamp,
+    ) -> Result<ServerKeyId, KeyRotationError> {
+        let id = match self.generations.last() {
+            Some(latest) => {
+                if valid_from <= latest.valid_from {
+                    return Err(KeyRotationError::NotAfterExisting);
+                }
+                ServerKeyId(
+                    latest
+                        .id
+                        .0
+                        .checked_add(1)
+                        .ok_or(KeyRotationError::IdsExhausted)?,
+                )
+            }
+            None => ServerKeyId(0),
+        };
+        self.generations.push(ServerKeyGeneration {
+            reserved: Default::default(),
+            id,
+            valid_from,
+            key,
+        });
+        Ok(id)
+    }
+
+    /// Looks up a generation by ID.
+    pub fn get(&self, id: ServerKeyId) -> Option<&K> {
+        self.generations
+            .iter()
+            .find(|generation| generation.id == id)
+            .map(|generation| &generation.key)
+    }
+
+    /// The generation that should be used to issue credentials at `now`.
+    ///
+    /// Returns `None` if no generation is valid yet.
+    pub fn current(&self, now: Timestamp) -> Option<&ServerKeyGeneration<K>> {
+        self.generations
+            .iter()
+            .filter(|generation| generation.valid_from <= now)
+            .max_by_key(|generation| generation.valid_from)
+    }
+
+    /// The generations whose credentials should be accepted at `now`: the current one, plus the
+    /// one before it if it was replaced less than `grace_period_seconds` ago.
+    pub fn accepted(
+        &self,
+        now: Timestamp,
+        grace_period_seconds: u64,
+    ) -> impl Iterator<Item = &ServerKeyGeneration<K>> {
+        let current = self.current(now);
+        let previous = current
+            .filter(|current| {
+                current
+                    .valid_from
+                    .checked_add_seconds(grace_period_seconds)
+                    .is_none_or(|grace_period_end| now < grace_period_end)
+            })
+            .and_then(|current| {
+                self.generations
+                    .iter()
+                    .filter(|generation| generation.valid_from < current.valid_from)
+                    .max_by_key(|generation| generation.valid_from)
+            });
+        current.into_iter().chain(previous)
+    }
+
+    /// Runs `verify` with each [accepted][Self::accepted] key in turn, returning the first success.
+    ///
+    /// This is how a server should check presentations across a rotation, e.g.
+    ///
+    /// ```ignore
+    /// key_set.verify(now, GRACE_PERIOD, |params| presentation.verify(now, params))
+    /// ```
+    pub fn verify<T>(
+        &self,
+        now: Timestamp,
+        grace_period_seconds: u64,
+        mut verify: impl FnMut(&K) -> Result<T, ZkGroupVerificationFailure>,
+    ) -> Result<T, ZkGroupVerificationFailure> {
+        let mut result = Err(ZkGroupVerificationFailure);
+        for generation in self.accepted(now, grace_period_seconds) {
+            result = verify(&generation.key);
+            if result.is_ok() {
+                break;
+            }
+        }
+        result
+    }
+
+    /// Drops generations that will never be [accepted][Self::accepted] again after `now`.
+    pub fn prune(&mut self, now: Timestamp, grace_period_seconds: u64) {
+        let Some(oldest_needed) = self
+            .accepted(now, grace_period_seconds)
+            .map(|generation| generation.valid_from)
+            .min()
+        else {
+            return;
+        };
+        self.generations
+            .retain(|generation| generation.valid_from >= oldest_needed);
+    }
+}
+
+impl<'de, K: Deserialize<'de>> Deserialize<'de> for ServerKeySet<K> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Unchecked<K> {
+            reserved: ReservedByte,
+            generations: Vec<ServerKeyGeneration<K>>,
+        }
+
+        let Unchecked {
+            reserved,
+            generations,
+        } = Unchecked::deserialize(deserializer)?;
+        let in_order = !generations.is_empty()
+            && generations
+                .windows(2)
+                .all(|pair| pair[0].id < pair[1].id && pair[0].valid_from < pair[1].valid_from);
+        if !in_order {
+            return Err(<D::Error as serde::de::Error>::invalid_value(
+                serde::de::Unexpected::Seq,
+                &"a non-empty list of generations in increasing order",
+            ));
+        }
+        Ok(Self {
+            reserved,
+            generations,
+        })
+    }
+}
+
+impl<K: RotatableServerKey> ServerKeySet<K> {
+    /// The set of public keys to hand out to clients.
+    pub fn public_key_set(&self) -> ServerKeySet<K::Public> {
+        ServerKeySet {
+            reserved: Default::default(),
+            generations: self
+                .generations
+                .iter()
+                .map(|generation| ServerKeyGeneration {
+                    reserved: Default::default(),
+                    id: generation.id,
+                    valid_from: generation.valid_from,
+                    key: generation.key.public_key(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A credential response tagged with the [`ServerKeyId`] of the generation that issued it.
+///
+/// See the [module-level documentation](self) for how this fits into issuance.
+#[derive(Clone, Serialize, Deserialize, PartialDefault)]
+pub struct KeyedResponse<R> {
+    reserved: ReservedByte,
+    key_id: ServerKeyId,
+    response: R,
+}
+
+impl<R> KeyedResponse<R> {
+    pub fn new(key_id: ServerKeyId, response: R) -> Self {
+        Self {
+            reserved: Default::default(),
+            key_id,
+            response,
+        }
+    }
+
+    /// The generation the response was issued with.
+    pub fn key_id(&self) -> ServerKeyId {
+        self.key_id
+    }
+
+    pub fn into_response(self) -> R {
+        self.response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use assert_matches::assert_matches;
+
+    use super::*;
+    use crate::rate_limits::{RateLimitTokenRequestContext, RateLimitTokenResponse};
+    use crate::{RANDOMNESS_LEN, SECONDS_PER_DAY, common};
+
+    const START: Timestamp = Timestamp::from_epoch_seconds(1_600_000_000);
+    const GRACE_PERIOD: u64 = 2 * SECONDS_PER_DAY;
+
+    fn rotated_key_set() -> (ServerKeySet<GenericServerSecretParams>, Timestamp) {
+        let mut key_set = ServerKeySet::new(
+            GenericServerSecretParams::generate([0xA0; RANDOMNESS_LEN]),
+            START,
+        );
+        let rotation = START.add_seconds(30 * SECONDS_PER_DAY);
+        let id = key_set
+            .rotate(
+                GenericServerSecretParams::generate([0xA1; RANDOMNESS_LEN]),
+                rotation,
+            )
+            .expect("later generation");
+        assert_eq!(id, ServerKeyId::new(1));
+        (key_set, rotation)
+    }
+
+    fn accepted_ids<K>(key_set: &ServerKeySet<K>, now: Timestamp) -> Vec<u32> {
+        key_set
+            .accepted(now, GRACE_PERIOD)
+            .map(|generation| generation.id().get())
+            .collect()
+    }
+
+    #[test]
+    fn accepted_generations() {
+        let (key_set, rotation) = rotated_key_set();
+        assert!(key_set.current(START.sub_seconds(1)).is_none());
+        assert_eq!(
+            accepted_ids(&key_set, START.sub_seconds(1)),
+            Vec::<u32>::new()
+        );
+        assert_eq!(accepted_ids(&key_set, START), [0]);
+        assert_eq!(accepted_ids(&key_set, rotation.sub_seconds(1)), [0]);
+        assert_eq!(accepted_ids(&key_set, rotation), [1, 0]);
+        assert_eq!(
+            accepted_ids(&key_set, rotation.add_seconds(GRACE_PERIOD - 1)),
+            [1, 0]
+        );
+        assert_eq!(
+            accepted_ids(&key_set, rotation.add_seconds(GRACE_PERIOD)),
+            [1]
+        );
+    }
+
+    #[test]
+    fn rotation_must_move_forward() {
+        let (mut key_set, rotation) = rotated_key_set();
+        assert_matches!(
+            key_set.rotate(
+                GenericServerSecretParams::generate([0xA2; RANDOMNESS_LEN]),
+                rotation,
+            ),
+            Err(KeyRotationError::NotAfterExisting)
+        );
+        assert_eq!(accepted_ids(&key_set, rotation), [1, 0]);
+    }
+
+    #[test]
+    fn rotation_ids_exhausted() {
+        let mut key_set = ServerKeySet::new((), START);
+        key_set.generations[0].id = ServerKeyId::new(u32::MAX);
+        assert_matches!(
+            key_set.rotate((), START.add_seconds(1)),
+            Err(KeyRotationError::IdsExhausted)
+        );
+        assert_eq!(key_set.generations.len(), 1);
+    }
+
+    #[test]
+    fn rotating_an_empty_set_starts_at_zero() {
+        let mut key_set = ServerKeySet::<()>::partial_default();
+        assert_eq!(
+            key_set.rotate((), START).expect("first generation"),
+            ServerKeyId::new(0)
+        );
+        assert_eq!(
+            key_set
+                .rotate((), START.add_seconds(1))
+                .expect("later generation"),
+            ServerKeyId::new(1)
+        );
+    }
+
+    #[test]
+    fn deserialize_rejects_invalid_sets() {
+        let roundtrip = |key_set: &ServerKeySet<()>| {
+            common::serialization::deserialize::<ServerKeySet<()>>(
+                &common::serialization::serialize(key_set),
+            )
+        };
+
+        let mut key_set = ServerKeySet::new((), START);
+        key_set
+            .rotate((), START.add_seconds(1))
+            .expect("later generation");
+        assert!(roundtrip(&key_set).is_ok());
+
+        key_set.generations.swap(0, 1);
+        assert!(roundtrip(&key_set).is_err(), "out of order");
+
+        key_set.generations.swap(0, 1);
+        key_set.generations[1].id = ServerKeyId::new(0);
+        assert!(roundtrip(&key_set).is_err(), "repeated ID");
+
+        key_set.generations.clear();
+        assert!(roundtrip(&key_set).is_err(), "empty");
+    }
+
+    #[test]
+    fn prune() {
+        let (mut key_set, rotation) = rotated_key_set();
+        key_set.prune(rotation, GRACE_PERIOD);
+        assert!(key_set.get(ServerKeyId::new(0)).is_some());
+        key_set.prune(rotation.add_seconds(GRACE_PERIOD), GRACE_PERIOD);
+        assert!(key_set.get(ServerKeyId::new(0)).is_none());
+        assert!(key_set.get(ServerKeyId::new(1)).is_some());
+    }
+
+    #[test]
+    fn credentials_survive_rotation_during_grace_period() {
+        let (key_set, rotation) = rotated_key_set();
+        let public_key_set: ServerKeySet<GenericServerPublicParams> =
+            common::serialization::deserialize(&common::serialization::serialize(
+                &key_set.public_key_set(),
+            ))
+            .expect("valid key set");
+
+        // Issue just before the rotation, with generation 0.
+        let issued_at = rotation.sub_seconds(60);
+        let issuing = key_set.current(issued_at).expect("valid generation");
+        let context = RateLimitTokenRequestContext::new(NonZeroUsize::MIN, [0xB0; RANDOMNESS_LEN]);
+        let response = context
+            .get_request()
+            .issue(
+                RateLimitTokenResponse::default_expiration(issued_at),
+                1,
+                issuing.key(),
+                [0xB1; RANDOMNESS_LEN],
+            )
+            .expect("within allowance");
+        let response: KeyedResponse<RateLimitTokenResponse> = common::serialization::deserialize(
+            &common::serialization::serialize(&KeyedResponse::new(issuing.id(), response)),
+        )
+        .expect("valid response");
+
+        // The client uses the ID sent with the response to pick the right public key.
+        let public_params = public_key_set
+            .get(response.key_id())
+            .expect("known generation");
+        let token = context
+            .receive(response.into_response(), public_params, issued_at)
+            .expect("valid response")
+            .pop()
+            .expect("one token");
+        let presentation = token.present(public_params, [0xB2; RANDOMNESS_LEN]);
+
+        // After rotation, the old generation is still accepted.
+        let now = rotation.add_seconds(60);
+        key_set
+            .verify(now, GRACE_PERIOD, |params| presentation.verify(now, params))
+            .expect("accepted during grace period");
+
+        // ...but not once the grace period is over.
+        key_set
+            .verify(now, 0, |params| presentation.verify(now, params))
+            .expect_err("grace period over");
+    }
+}
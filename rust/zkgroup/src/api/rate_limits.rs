@@ -0,0 +1,11 @@
+//
+// Copyright 2026 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+mod rate_limit_token;
+
+pub use rate_limit_token::{
+    RateLimitNullifier, RateLimitToken, RateLimitTokenIssuanceError, RateLimitTokenPresentation,
+    RateLimitTokenRequest, RateLimitTokenRequestContext, RateLimitTokenResponse,
+};
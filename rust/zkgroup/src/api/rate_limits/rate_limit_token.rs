@@ -0,0 +1,610 @@
+//
+// Copyright 2026 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Provides RateLimitToken and related types.
+//!
+//! RateLimitToken is a single-use, unlinkable credential used to rate-limit access to
+//! unauthenticated endpoints. The issuing (chat) server hands out a fixed allowance of tokens per
+//! account per epoch; redeeming one reveals a nullifier, which the redeeming server records to
+//! reject double-spends.
+//!
+//! RateLimitToken is a MAC over:
+//! - a random nonce (chosen by the client, blinded at issuance, revealed for verification)
+//! - an expiration timestamp, truncated to day granularity (chosen by the issuing server, passed
+//!   publicly to the redeeming server for verification)
+
+use std::num::NonZeroUsize;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use partial_default::PartialDefault;
+use poksho::ShoApi;
+use poksho::shoapi::ShoApiExt as _;
+use serde::{Deserialize, Serialize};
+
+use crate::ZkGroupVerificationFailure;
+use crate::api::endorsement_expiration;
+use crate::common::serialization::ReservedByte;
+use crate::common::sho::Sho;
+use crate::common::simple_types::*;
+use crate::generic_server_params::{GenericServerPublicParams, GenericServerSecretParams};
+
+/// The length of a token nonce, in bytes.
+///
+/// Nonces determine the nullifier, so they need ample collision headroom across every token ever
+/// issued for an epoch.
+const NONCE_LEN: usize = 32;
+
+type NonceBytes = [u8; NONCE_LEN];
+
+/// The value a redeeming server records to enforce single use of a [`RateLimitToken`].
+pub type RateLimitNullifier = [u8; 32];
+
+/// Why a [`RateLimitTokenRequest`] was refused by [`issue`][RateLimitTokenRequest::issue].
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum RateLimitTokenIssuanceError {
+    /// Rate-limit token request asks for no tokens
+    EmptyRequest,
+    /// Rate-limit token request asks for {requested} tokens, but only {remaining} remain
+    OverAllowance { requested: usize, remaining: usize },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+struct RateLimitNoncePoint(RistrettoPoint);
+
+impl RateLimitNoncePoint {
+    fn new(nonce: &NonceBytes) -> Self {
+        Self(Sho::new(b"20261019_Signal_RateLimitTokenNonce", nonce).get_point())
+    }
+}
+
+impl zkcredential::attributes::RevealedAttribute for RateLimitNoncePoint {
+    fn as_point(&self) -> RistrettoPoint {
+        self.0
+    }
+}
+
+const CREDENTIAL_LABEL: &[u8] = b"20261019_Signal_RateLimitToken";
+
+/// Client-retained state for an in-flight token request.
+///
+/// This holds the nonces and the blinding key the client needs to unblind the eventual
+/// [`RateLimitTokenResponse`]. It must be persisted between sending the
+/// [`request`][Self::get_request] and calling [`receive`][Self::receive].
+#[derive(Clone, Serialize, Deserialize, PartialDefault)]
+pub struct RateLimitTokenRequestContext {
+    reserved: ReservedByte,
+    nonces: Vec<NonceBytes>,
+    blinded_nonces: Vec<zkcredential::issuance::blind::BlindedPoint>,
+    key_pair: zkcredential::issuance::blind::BlindingKeyPair,
+}
+
+impl RateLimitTokenRequestContext {
+    /// Samples `count` fresh nonces from `randomness` and blinds them for issuance.
+    pub fn new(count: NonZeroUsize, randomness: RandomnessBytes) -> Self {
+        let count = count.get();
+        let mut sho = poksho::ShoHmacSha256::new(b"20261019_Signal_RateLimitTokenRequest");
+        sho.absorb_and_ratchet(&randomness);
+
+        let key_pair = zkcredential::issuance::blind::BlindingKeyPair::generate(&mut sho);
+
+        let mut nonces = Vec::with_capacity(count);
+        let mut blinded_nonces = Vec::with_capacity(count);
+        for _ in 0..count {
+            let nonce: NonceBytes = sho.squeeze_and_ratchet_as_array();
+            let blinded_nonce = key_pair
+                .blind(&RateLimitNoncePoint::new(&nonce), &mut sho)
+                .into();
+            nonces.push(nonce);
+            blinded_nonces.push(blinded_nonce);
+        }
+
+        Self {
+            reserved: Default::default(),
+            nonces,
+            blinded_nonces,
+            key_pair,
+        }
+    }
+
+    pub fn get_request(&self) -> RateLimitTokenRequest {
+        RateLimitTokenRequest {
+            reserved: Default::default(),
+            blinded_nonces: self.blinded_nonces.clone(),
+            public_key: *self.key_pair.public_key(),
+        }
+    }
+
+    /// Validates the issuer's response and extracts the redeemable tokens.
+    ///
+    /// `now` is used to validate the response's expiration. The returned tokens are in the same
+    /// order as the nonces in this context.
+    pub fn receive(
+        self,
+        response: RateLimitTokenResponse,
+        params: &GenericServerPublicParams,
+        now: Timestamp,
+    ) -> Result<Vec<RateLimitToken>, ZkGroupVerificationFailure> {
+        endorsement_expiration::validate_expiration(response.expiration, now)?;
+        if response.blinded_credentials.len() != self.nonces.len() {
+            return Err(ZkGroupVerificationFailure);
+        }
+
+        self.nonces
+            .into_iter()
+            .zip(self.blinded_nonces)
+            .zip(response.blinded_credentials)
+            .map(|((nonce, blinded_nonce), blinded_credential)| {
+                let credential =
+                    zkcredential::issuance::IssuanceProofBuilder::new(CREDENTIAL_LABEL)
+                        .add_public_attribute(&response.expiration)
+                        .add_blinded_revealed_attribute(&blinded_nonce)
+                        .verify(&params.credential_key, &self.key_pair, blinded_credential)
+                        .map_err(|_| ZkGroupVerificationFailure)?;
+                Ok(RateLimitToken {
+                    reserved: Default::default(),
+                    expiration: response.expiration,
+                    nonce,
+                    credential,
+                })
+            })
+            .collect()
+    }
+}
+
+/// The wire message a client sends to the issuing server to request tokens.
+///
+/// It carries one blinded nonce per requested token. Like [`CreateCallLinkCredentialRequest`]
+/// there is no proof: the nonces are random, so the server's decision to issue never depends on
+/// them.
+///
+/// [`CreateCallLinkCredentialRequest`]: crate::call_links::CreateCallLinkCredentialRequest
+#[derive(Clone, Serialize, Deserialize, PartialDefault)]
+pub struct RateLimitTokenRequest {
+    reserved: ReservedByte,
+    blinded_nonces: Vec<zkcredential::issuance::blind::BlindedPoint>,
+    public_key: zkcredential::issuance::blind::BlindingPublicKey,
+}
+
+impl RateLimitTokenRequest {
+    /// The number of tokens requested.
+    pub fn len(&self) -> usize {
+        self.blinded_nonces.len()
+    }
+
+    /// Whether the request asks for zero tokens (which [`issue`][Self::issue] rejects).
+    pub fn is_empty(&self) -> bool {
+        self.blinded_nonces.is_empty()
+    }
+
+    /// Issues a token for each blinded nonce in this request, all expiring at `expiration`.
+    ///
+    /// The caller must have authenticated the requesting account. `tokens_remaining` is how many
+    /// more tokens that account may be issued for the current epoch; the request is rejected if it
+    /// is empty or asks for more than that. On success, the caller should deduct
+    /// [`len`][Self::len] from the account's allowance.
+    pub fn issue(
+        &self,
+        expiration: Timestamp,
+        tokens_remaining: usize,
+        params: &GenericServerSecretParams,
+        randomness: RandomnessBytes,
+    ) -> Result<RateLimitTokenResponse, RateLimitTokenIssuanceError> {
+        if self.is_empty() {
+            return Err(RateLimitTokenIssuanceError::EmptyRequest);
+        }
+        if self.len() > tokens_remaining {
+            return Err(RateLimitTokenIssuanceError::OverAllowance {
+                requested: self.len(),
+                remaining: tokens_remaining,
+            });
+        }
+
+        let mut sho = poksho::ShoHmacSha256::new(b"20261019_Signal_RateLimitTokenIssue");
+        sho.absorb_and_ratchet(&randomness);
+
+        let blinded_credentials = self
+            .blinded_nonces
+            .iter()
+            .map(|blinded_nonce| {
+                zkcredential::issuance::IssuanceProofBuilder::new(CREDENTIAL_LABEL)
+                    .add_public_attribute(&expiration)
+                    .add_blinded_revealed_attribute(blinded_nonce)
+                    .issue(
+                        &params.credential_key,
+                        &self.public_key,
+                        sho.squeeze_and_ratchet_as_array(),
+                    )
+            })
+            .collect();
+
+        Ok(RateLimitTokenResponse {
+            reserved: Default::default(),
+            expiration,
+            blinded_credentials,
+        })
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialDefault)]
+pub struct RateLimitTokenResponse {
+    reserved: ReservedByte,
+    expiration: Timestamp,
+    blinded_credentials: Vec<zkcredential::issuance::blind::BlindedIssuanceProof>,
+}
+
+impl RateLimitTokenResponse {
+    /// The expiration an issuing server should use for tokens issued at `current_time`.
+    ///
+    /// This is also the end of the epoch the tokens count against.
+    pub fn default_expiration(current_time: Timestamp) -> Timestamp {
+        endorsement_expiration::default_expiration(current_time)
+    }
+
+    /// The expiration shared by all tokens in this response.
+    pub fn expiration(&self) -> Timestamp {
+        self.expiration
+    }
+}
+
+/// A single unspent rate-limit token, held by the client.
+#[derive(Clone, Serialize, Deserialize, PartialDefault)]
+pub struct RateLimitToken {
+    reserved: ReservedByte,
+    expiration: Timestamp,
+    nonce: NonceBytes,
+    credential: zkcredential::credentials::Credential,
+}
+
+impl RateLimitToken {
+    /// The expiration after which this token can no longer be redeemed.
+    pub fn expiration(&self) -> Timestamp {
+        self.expiration
+    }
+
+    /// Generates the presentation sent to the redeeming server.
+    ///
+    /// Every presentation of the same token has the same
+    /// [`nullifier`][RateLimitTokenPresentation::nullifier], so a token can only ever be redeemed
+    /// once.
+    pub fn present(
+        &self,
+        server_params: &GenericServerPublicParams,
+        randomness: RandomnessBytes,
+    ) -> RateLimitTokenPresentation {
+        RateLimitTokenPresentation {
+            reserved: Default::default(),
+            expiration: self.expiration,
+            nonce: self.nonce,
+            proof: zkcredential::presentation::PresentationProofBuilder::new(CREDENTIAL_LABEL)
+                .add_revealed_attribute(&RateLimitNoncePoint::new(&self.nonce))
+                .present(&server_params.credential_key, &self.credential, randomness),
+        }
+    }
+}
+
+/// The value sent, over an unauthenticated connection, to redeem a [`RateLimitToken`].
+#[derive(Clone, Serialize, Deserialize, PartialDefault)]
+pub struct RateLimitTokenPresentation {
+    reserved: ReservedByte,
+    expiration: Timestamp,
+    nonce: NonceBytes,
+    proof: zkcredential::presentation::PresentationProof,
+}
+
+impl RateLimitTokenPresentation {
+    /// The expiration of the presented token.
+    ///
+    /// A redeeming server only needs to remember nullifiers until this time.
+    pub fn expiration(&self) -> Timestamp {
+        self.expiration
+    }
+
+    /// The key a redeeming server should use to detect double-spends.
+    ///
+    /// This is derived from the token's nonce and expiration, neither of which can be altered
+    /// without invalidating the proof, so replaying a token (even with a fresh presentation)
+    /// produces the same nullifier.
+    pub fn nullifier(&self) -> RateLimitNullifier {
+        let mut sho = Sho::new_seed(b"20261019_Signal_RateLimitTokenNullifier");
+        sho.absorb_and_ratchet(&self.expiration.to_be_bytes());
+        sho.absorb_and_ratchet(&self.nonce);
+        sho.squeeze_as_array()
+    }
+
+    /// Verifies that this presentation is for a token honestly issued under `server_params` that
+    /// has not yet expired.
+    ///
+    /// **This does not enforce single use.** Use [`redeem`][Self::redeem], or check
+    /// [`nullifier`][Self::nullifier] against a spent set separately.
+    pub fn verify(
+        &self,
+        current_time: Timestamp,
+        server_params: &GenericServerSecretParams,
+    ) -> Result<(), ZkGroupVerificationFailure> {
+        endorsement_expiration::validate_expiration(self.expiration, current_time)?;
+
+        zkcredential::presentation::PresentationProofVerifier::new(CREDENTIAL_LABEL)
+            .add_public_attribute(&self.expiration)
+            .add_revealed_attribute(&RateLimitNoncePoint::new(&self.nonce))
+            .verify(&server_params.credential_key, &self.proof)
+            .map_err(|_| ZkGroupVerificationFailure)
+    }
+
+    /// Verifies this presentation and marks its token as spent.
+    ///
+    /// `mark_spent` is called with the [`nullifier`][Self::nullifier] only if verification
+    /// succeeds. It should record the nullifier and return `false` if it had already been recorded,
+    /// in which case the token is rejected as a double-spend. `HashSet::insert` has exactly this
+    /// behavior.
+    pub fn redeem(
+        &self,
+        current_time: Timestamp,
+        server_params: &GenericServerSecretParams,
+        mark_spent: impl FnOnce(RateLimitNullifier) -> bool,
+    ) -> Result<(), ZkGroupVerificationFailure> {
+        self.verify(current_time, server_params)?;
+        if !mark_spent(self.nullifier()) {
+            return Err(ZkGroupVerificationFailure);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use assert_matches::assert_matches;
+    use const_str::hex;
+
+    use super::*;
+    use crate::{RANDOMNESS_LEN, SECONDS_PER_DAY, common};
+
+    const NOW: Timestamp = Timestamp::from_epoch_seconds(1_600_000_000);
+    const SERVER_SECRET_RAND: RandomnessBytes = [0xA0; RANDOMNESS_LEN];
+    const REQUEST_RAND: RandomnessBytes = [0xA1; RANDOMNESS_LEN];
+    const ISSUE_RAND: RandomnessBytes = [0xA2; RANDOMNESS_LEN];
+    const PRESENT_RAND: RandomnessBytes = [0xA3; RANDOMNESS_LEN];
+
+    fn server_secret_params() -> GenericServerSecretParams {
+        GenericServerSecretParams::generate(SERVER_SECRET_RAND)
+    }
+
+    fn issue_tokens(count: usize) -> Vec<RateLimitToken> {
+        let context = RateLimitTokenRequestContext::new(
+            NonZeroUsize::new(count).expect("non-zero"),
+            REQUEST_RAND,
+        );
+        let response = context
+            .get_request()
+            .issue(
+                RateLimitTokenResponse::default_expiration(NOW),
+                count,
+                &server_secret_params(),
+                ISSUE_RAND,
+            )
+            .expect("within allowance");
+        context
+            .receive(response, &server_secret_params().get_public_params(), NOW)
+            .expect("valid response")
+    }
+
+    #[test]
+    fn default_flow() {
+        let tokens = issue_tokens(3);
+        assert_eq!(tokens.len(), 3);
+
+        let server_params = server_secret_params();
+        let mut spent = HashSet::new();
+        for token in &tokens {
+            let presentation = token.present(&server_params.get_public_params(), PRESENT_RAND);
+            presentation
+                .redeem(NOW, &server_params, |nullifier| spent.insert(nullifier))
+                .expect("valid token");
+        }
+        assert_eq!(spent.len(), 3, "nullifiers should be distinct");
+    }
+
+    #[test]
+    fn double_spend_rejected() {
+        let token = issue_tokens(1).pop().expect("one token");
+        let server_params = server_secret_params();
+        let mut spent = HashSet::new();
+
+        let first = token.present(&server_params.get_public_params(), PRESENT_RAND);
+        first
+            .redeem(NOW, &server_params, |nullifier| spent.insert(nullifier))
+            .expect("first redemption");
+
+        // A fresh presentation is unlinkable, but still has the same nullifier.
+        let second = token.present(&server_params.get_public_params(), [0xB0; RANDOMNESS_LEN]);
+        assert_eq!(first.nullifier(), second.nullifier());
+        second
+            .verify(NOW, &server_params)
+            .expect("still a valid token");
+        second
+            .redeem(NOW, &server_params, |nullifier| spent.insert(nullifier))
+            .expect_err("already spent");
+    }
+
+    #[test]
+    fn allowance_enforced() {
+        let context =
+            RateLimitTokenRequestContext::new(NonZeroUsize::new(3).unwrap(), REQUEST_RAND);
+        let request = context.get_request();
+        let expiration = RateLimitTokenResponse::default_expiration(NOW);
+        assert_matches!(
+            request
+                .issue(expiration, 2, &server_secret_params(), ISSUE_RAND)
+                .err(),
+            Some(RateLimitTokenIssuanceError::OverAllowance {
+                requested: 3,
+                remaining: 2
+            })
+        );
+
+        let empty = RateLimitTokenRequest {
+            blinded_nonces: vec![],
+            ..request
+        };
+        assert!(empty.is_empty());
+        assert_matches!(
+            empty
+                .issue(expiration, 2, &server_secret_params(), ISSUE_RAND)
+                .err(),
+            Some(RateLimitTokenIssuanceError::EmptyRequest)
+        );
+    }
+
+    #[test]
+    fn wrong_key_fails() {
+        let token = issue_tokens(1).pop().expect("one token");
+        let other_params = GenericServerSecretParams::generate([0xAF; RANDOMNESS_LEN]);
+        let presentation = token.present(&other_params.get_public_params(), PRESENT_RAND);
+        presentation
+            .verify(NOW, &other_params)
+            .expect_err("issued under a different key");
+    }
+
+    #[test]
+    fn tampered_nonce_fails() {
+        let token = issue_tokens(1).pop().expect("one token");
+        let server_params = server_secret_params();
+        let mut presentation = token.present(&server_params.get_public_params(), PRESENT_RAND);
+        presentation.nonce[0] ^= 0xff;
+        presentation
+            .verify(NOW, &server_params)
+            .expect_err("tampered nonce");
+    }
+
+    #[test]
+    fn expired_token_fails() {
+        let token = issue_tokens(1).pop().expect("one token");
+        let server_params = server_secret_params();
+        let presentation = token.present(&server_params.get_public_params(), PRESENT_RAND);
+        presentation
+            .verify(token.expiration().add_seconds(1), &server_params)
+            .expect_err("expired token");
+    }
+
+    #[test]
+    fn non_dayaligned_expiration_rejected() {
+        let context = RateLimitTokenRequestContext::new(NonZeroUsize::MIN, REQUEST_RAND);
+        let expiration = RateLimitTokenResponse::default_expiration(NOW).add_seconds(1);
+        let response = context
+            .get_request()
+            .issue(expiration, 1, &server_secret_params(), ISSUE_RAND)
+            .expect("within allowance");
+        assert!(
+            context
+                .receive(response, &server_secret_params().get_public_params(), NOW)
+                .is_err(),
+            "non-day-aligned expiration"
+        );
+    }
+
+    #[test]
+    fn far_future_expiration_rejected() {
+        let context = RateLimitTokenRequestContext::new(NonZeroUsize::MIN, REQUEST_RAND);
+        let expiration =
+            RateLimitTokenResponse::default_expiration(NOW).add_seconds(7 * SECONDS_PER_DAY);
+        let response = context
+            .get_request()
+            .issue(expiration, 1, &server_secret_params(), ISSUE_RAND)
+            .expect("within allowance");
+        assert!(
+            context
+                .receive(response, &server_secret_params().get_public_params(), NOW)
+                .is_err(),
+            "expiration too far in the future"
+        );
+    }
+
+    #[test]
+    fn serialization_round_trip() {
+        let token = issue_tokens(1).pop().expect("one token");
+        let server_params = server_secret_params();
+        let token: RateLimitToken =
+            common::serialization::deserialize(&common::serialization::serialize(&token))
+                .expect("valid token");
+        let presentation = token.present(&server_params.get_public_params(), PRESENT_RAND);
+        let presentation: RateLimitTokenPresentation =
+            common::serialization::deserialize(&common::serialization::serialize(&presentation))
+                .expect("valid presentation");
+        presentation
+            .verify(NOW, &server_params)
+            .expect("still valid");
+    }
+
+    #[test]
+    fn fixed_serialized_vectors() {
+        // These pin the wire format and the deterministic derivations; any change to either means
+        // old and new clients and servers can no longer interoperate.
+        const REQUEST: &[u8] = &hex!(
+            "0001000000000000006cc4eabb840ac8ab3043f14f5f2d32a6dc5f3ca1d3abf19fe786aee60eb2a1
+            2bcac348dbd657b3dc826efa056cdd10c87f1ae0aa69affa5a05550d2a744712300c2a6f0ba71274
+            4bbf9754b1cd32b053c12bbdeeb57a127bbc2d0f7704caa301"
+        );
+        const RESPONSE: &[u8] = &hex!(
+            "000004605f000000000100000000000000b132ccc574b33359ae2c330a568286b64e01456502e45b
+            56e8b3198f7266a3062cbb8b5685b7e68adee5512be796272cbff37f0576242bbf67bf1f03781ac5
+            1b2015086299bb89d6039546ebdd6b0d0c5aa25e59a60803cbde15b23abf90953014daa66f53b96f
+            2d4aa1f169c230383689e240e334c03e5a59d14b8081a5a54700010000000000006b7ba21904d875
+            87826e5c98561a96f497c7713f393e118f880cbfaf68dbd60fab219c540f0c4c172fdd0e2e8df4db
+            c594a83daf2bfa89b27627962a89eaad0b61eee65cd5ca32778a059b4989f559a40dadf3832973d2
+            aab5ebd4c68a5c0905637dcbaaaaf817a6653d4f82cbf1e0e268461030dc4c2f558ff725ba908733
+            08e6a2047de640fdbb7edf68d17757dea59a6fa81de575d9182a55e83ee9a97706150f53d0f1619e
+            7ecba38548365aec21c4fc0941eeca83416deb7410bb8ba103a61acffc583184698998339c215f0f
+            d0ee329844325fc020532219d518aca206e62ccee05c0af1e8916c4c86d97e66d9008ad490f52478
+            bc16da40e1a96c8804"
+        );
+        const PRESENTATION: &[u8] = &hex!(
+            "000004605f000000005f12b0985f5e3971d799d54ce8b65aacab05bb8e13a0781ef80b680c1ca27e
+            98aa95b37efdcbaa40ab5f74467a0b9b08ae91c36fdd55266e5ed09d7f2e2ced464a6cfd66ef629f
+            c3654f4675a66164504e0b88a930578a7789dc320cb19a6103582889501095d90c6d00e277e83169
+            c8f495d6887c88ab01205d3d039f56772202000000000000002ae5549a5468a00133a722f871af9d
+            d05caedd1058750c6cb5da78ffe006ba0ce8d20b5ad3716fe4a795313ca3df45766a66c807fc8939
+            e8c232db104ebb9c1e800000000000000015421629ce8c4aa662bf35716e076f679bcebf3247432f
+            e426f124bb0df5b904cd548e74ac3506c45d08f45a94f840539f62ac19c38889d2daaaae675f6d09
+            0e70bac2436929d8803ea784841ddcc5fdbce29163e6593c01d00428617423ca0e574655da355dc3
+            6a8410b005862216ea1f049eeaa273e44c406c6f3e6bb3f50a"
+        );
+        const NULLIFIER: RateLimitNullifier =
+            hex!("95c706445c928fffe61f490f598c149731d417c0e0f79100386a444948cc8e43");
+
+        let server_params = server_secret_params();
+        let context = RateLimitTokenRequestContext::new(NonZeroUsize::MIN, REQUEST_RAND);
+        let request = context.get_request();
+        assert_eq!(common::serialization::serialize(&request), REQUEST);
+
+        let response = request
+            .issue(
+                RateLimitTokenResponse::default_expiration(NOW),
+                1,
+                &server_params,
+                ISSUE_RAND,
+            )
+            .expect("within allowance");
+        assert_eq!(common::serialization::serialize(&response), RESPONSE);
+
+        let token = context
+            .receive(response, &server_params.get_public_params(), NOW)
+            .expect("valid response")
+            .pop()
+            .expect("one token");
+        let presentation = token.present(&server_params.get_public_params(), PRESENT_RAND);
+        assert_eq!(
+            common::serialization::serialize(&presentation),
+            PRESENTATION
+        );
+
+        let presentation: RateLimitTokenPresentation =
+            common::serialization::deserialize(PRESENTATION).expect("valid presentation");
+        presentation
+            .verify(NOW, &server_params)
+            .expect("still valid");
+        assert_eq!(presentation.nullifier(), NULLIFIER);
+    }
+}
@@ -0,0 +1,116 @@
+//
+// Copyright 2025 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+import Foundation
+import SignalFfi
+
+/// A snapshot of connection health since the last network change.
+///
+/// Routes are only grouped by kind, such as `"direct"` or `"fronted+socks"`; nothing here identifies
+/// a particular host, address, or proxy.
+///
+/// See ``Net/connectivityReport()``.
+public class ConnectivityReport: NativeHandleOwner<SignalMutPointerConnectivityReport>, @unchecked Sendable {
+    override internal class func destroyNativeHandle(
+        _ handle: NonNull<SignalMutPointerConnectivityReport>
+    ) -> SignalFfiErrorRef? {
+        signal_connectivity_report_destroy(handle.pointer)
+    }
+
+    /// The number of buckets returned by ``latencyHistogram(kind:)``.
+    ///
+    /// Buckets are ordered from fastest to slowest; the last one has no upper bound.
+    public static var latencyBucketCount: Int {
+        Int(failOnError { try invokeFnReturningInteger(fn: signal_connectivity_report_latency_bucket_count) })
+    }
+
+    private func getCount(
+        _ fn: (UnsafeMutablePointer<UInt64>?, SignalConstPointerConnectivityReport) -> SignalFfiErrorRef?
+    ) -> UInt64 {
+        failOnError {
+            try self.withNativeHandle { report in
+                try invokeFnReturningInteger { fn($0, report.const()) }
+            }
+        }
+    }
+
+    /// How long statistics have been collected for, in seconds.
+    public var collectionPeriodSeconds: UInt64 {
+        self.getCount(signal_connectivity_report_collection_period_seconds)
+    }
+
+    /// The kinds of route that were attempted, in the order each was first tried.
+    public var routeKinds: [String] {
+        failOnError {
+            try self.withNativeHandle { report in
+                try invokeFnReturningStringArray {
+                    signal_connectivity_report_route_kinds($0, report.const())
+                }
+            }
+        }
+    }
+
+    /// The number of successful connection attempts over the given kind of route.
+    public func routeSuccesses(kind: String) -> UInt64 {
+        self.getCount { signal_connectivity_report_route_successes($0, $1, kind) }
+    }
+
+    /// The number of failed connection attempts over the given kind of route.
+    public func routeFailures(kind: String) -> UInt64 {
+        self.getCount { signal_connectivity_report_route_failures($0, $1, kind) }
+    }
+
+    /// The number of successful connections over the given kind of route that fell in each latency
+    /// bucket.
+    ///
+    /// See ``latencyBucketCount``.
+    public func latencyHistogram(kind: String) -> [UInt64] {
+        (0..<UInt32(Self.latencyBucketCount)).map { bucket in
+            self.getCount { signal_connectivity_report_latency_bucket($0, $1, kind, bucket) }
+        }
+    }
+
+    /// The number of hostname lookups answered by the system resolver.
+    public var dnsSystemLookups: UInt64 {
+        self.getCount(signal_connectivity_report_dns_system_lookups)
+    }
+
+    /// The number of hostname lookups answered over DNS-over-HTTPS.
+    public var dnsDohLookups: UInt64 {
+        self.getCount(signal_connectivity_report_dns_doh_lookups)
+    }
+
+    /// The number of hostname lookups answered over DNS-over-TLS.
+    public var dnsDotLookups: UInt64 {
+        self.getCount(signal_connectivity_report_dns_dot_lookups)
+    }
+
+    /// The number of hostname lookups answered from the built-in static fallback.
+    public var dnsStaticLookups: UInt64 {
+        self.getCount(signal_connectivity_report_dns_static_lookups)
+    }
+}
+
+extension SignalMutPointerConnectivityReport: SignalMutPointer {
+    public typealias ConstPointer = SignalConstPointerConnectivityReport
+
+    public init(untyped: OpaquePointer?) {
+        self.init(raw: untyped)
+    }
+
+    public func toOpaque() -> OpaquePointer? {
+        self.raw
+    }
+
+    public func const() -> Self.ConstPointer {
+        Self.ConstPointer(raw: self.raw)
+    }
+}
+
+extension SignalConstPointerConnectivityReport: SignalConstPointer {
+    public func toOpaque() -> OpaquePointer? {
+        self.raw
+    }
+}
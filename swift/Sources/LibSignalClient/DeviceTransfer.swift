@@ -12,6 +12,13 @@ public enum KeyFormat: UInt8, CaseIterable, Sendable {
     case keySpecific = 1
 }
 
+public enum DeviceTransferKeyType: UInt8, CaseIterable, Sendable {
+    /// RSA, with the same key size as ``DeviceTransferKey/generate(formattedAs:)``.
+    case rsa = 0
+    /// ECDSA over the NIST P-256 curve, much faster to generate than RSA.
+    case ecdsaP256 = 1
+}
+
 public struct DeviceTransferKey: Sendable {
     public let privateKey: Data
 
@@ -29,6 +36,40 @@ public struct DeviceTransferKey: Sendable {
         return Self(privateKey: privateKey)
     }
 
+    public static func generate(
+        type keyType: DeviceTransferKeyType,
+        formattedAs keyFormat: KeyFormat = .pkcs8
+    ) -> Self {
+        let privateKey = failOnError {
+            try invokeFnReturningData {
+                signal_device_transfer_generate_private_key_with_type($0, keyType.rawValue, keyFormat.rawValue)
+            }
+        }
+
+        return Self(privateKey: privateKey)
+    }
+
+    /// Computes the SHA-256 fingerprint of a DER-encoded certificate, to be exchanged out of band so
+    /// that the other device can pin it.
+    public static func certificateFingerprint(_ certificate: Data) throws -> Data {
+        return try certificate.withUnsafeBorrowedBuffer { certificateBuffer in
+            try invokeFnReturningFixedLengthArray {
+                signal_device_transfer_get_certificate_fingerprint($0, certificateBuffer)
+            }
+        }
+    }
+
+    /// Checks a peer's DER-encoded certificate against the fingerprint exchanged out of band.
+    public static func certificate(_ certificate: Data, matchesFingerprint fingerprint: Data) throws -> Bool {
+        return try certificate.withUnsafeBorrowedBuffer { certificateBuffer in
+            try fingerprint.withUnsafeBorrowedBuffer { fingerprintBuffer in
+                try invokeFnReturningBool {
+                    signal_device_transfer_certificate_matches_fingerprint($0, certificateBuffer, fingerprintBuffer)
+                }
+            }
+        }
+    }
+
     public func privateKeyMaterial() -> Data {
         return self.privateKey
     }
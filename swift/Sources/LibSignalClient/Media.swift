@@ -68,6 +68,48 @@ public func sanitizeWebp(input: SignalInputStream) throws {
     }
 }
 
+/// "Sanitize" an MP4 input, additionally reporting its duration, dimensions, and codecs.
+///
+/// This behaves like ``sanitizeMp4(input:len:)``, but the media information accessors on the result are populated as
+/// well.
+///
+/// - Parameters:
+///  - input: An MP4 format input stream.
+///  - length: The exact length of the input stream.
+///
+/// - Returns: The sanitized metadata.
+///
+/// - Throws:
+///  - `SignalError.ioError`: If an IO error on the input occurs.
+///  - `SignalError.invalidMediaInput` If the input could not be parsed because it was invalid.
+///  - `SignalError.unsupportedMediaInput` If the input could not be parsed because it's unsupported in some way.
+public func sanitizeMp4WithInfo(input: SignalInputStream, len: UInt64) throws -> SanitizedMetadata {
+    return try withInputStream(input) { ffiInput in
+        try invokeFnReturningNativeHandle {
+            signal_mp4_sanitizer_sanitize_with_info($0, ffiInput, len)
+        }
+    }
+}
+
+/// "Sanitize" a WebP input, additionally reporting its dimensions and animation.
+///
+/// - Parameters:
+///  - input: A WebP format input stream.
+///
+/// - Returns: The information found while sanitizing.
+///
+/// - Throws:
+///  - `SignalError.ioError`: If an IO error on the input occurs.
+///  - `SignalError.invalidMediaInput` If the input could not be parsed because it was invalid.
+///  - `SignalError.unsupportedMediaInput` If the input could not be parsed because it's unsupported in some way.
+public func sanitizeWebpWithInfo(input: SignalInputStream) throws -> SanitizedWebp {
+    return try withInputStream(input) { ffiInput in
+        try invokeFnReturningNativeHandle {
+            signal_webp_sanitizer_sanitize_with_info($0, ffiInput)
+        }
+    }
+}
+
 public class SanitizedMetadata: ClonableHandleOwner<OpaquePointer?> {
     override internal class func cloneNativeHandle(
         _ newHandle: inout OpaquePointer?,
@@ -114,6 +156,120 @@ public class SanitizedMetadata: ClonableHandleOwner<OpaquePointer?> {
             }
         }
     }
+
+    // The remaining accessors are only populated by ``sanitizeMp4WithInfo(input:len:)``.
+
+    /// The presentation duration of the movie in milliseconds, or nil if it wasn't specified.
+    public var durationMillis: UInt64? {
+        self.getOptionalInteger(signal_sanitized_metadata_get_duration_millis)
+    }
+
+    /// The display width of the first video track in pixels, or 0 if there is no video track.
+    public var videoWidth: UInt32 {
+        self.getInteger(signal_sanitized_metadata_get_video_width)
+    }
+
+    /// The display height of the first video track in pixels, or 0 if there is no video track.
+    public var videoHeight: UInt32 {
+        self.getInteger(signal_sanitized_metadata_get_video_height)
+    }
+
+    /// The clockwise rotation to apply when displaying the first video track, in degrees: 0, 90, 180, or 270.
+    public var videoRotation: UInt32 {
+        self.getInteger(signal_sanitized_metadata_get_video_rotation)
+    }
+
+    /// The codec of the first video track, such as `avc1`, or nil if there is no video track or its codec is unknown.
+    public var videoCodec: String? {
+        self.getCodec(signal_sanitized_metadata_get_video_codec)
+    }
+
+    /// The media duration of the first video track in milliseconds, or nil if it wasn't specified.
+    public var videoDurationMillis: UInt64? {
+        self.getOptionalInteger(signal_sanitized_metadata_get_video_duration_millis)
+    }
+
+    /// The codec of the first audio track, such as `mp4a`, or nil if there is no audio track or its codec is unknown.
+    public var audioCodec: String? {
+        self.getCodec(signal_sanitized_metadata_get_audio_codec)
+    }
+
+    /// The media duration of the first audio track in milliseconds, or nil if it wasn't specified.
+    public var audioDurationMillis: UInt64? {
+        self.getOptionalInteger(signal_sanitized_metadata_get_audio_duration_millis)
+    }
+
+    private func getInteger<Result: FixedWidthInteger>(
+        _ fn: (UnsafeMutablePointer<Result>?, OpaquePointer?) -> SignalFfiErrorRef?
+    ) -> Result {
+        return withNativeHandle { nativeHandle in
+            failOnError {
+                try invokeFnReturningInteger { fn($0, nativeHandle) }
+            }
+        }
+    }
+
+    private func getOptionalInteger(
+        _ fn: (UnsafeMutablePointer<UInt64>?, OpaquePointer?) -> SignalFfiErrorRef?
+    ) -> UInt64? {
+        // `nil` is bridged as the maximum value.
+        let result = self.getInteger(fn)
+        return if result == UInt64.max { nil } else { result }
+    }
+
+    private func getCodec(
+        _ fn: (UnsafeMutablePointer<UnsafePointer<CChar>?>?, OpaquePointer?) -> SignalFfiErrorRef?
+    ) -> String? {
+        let codec = withNativeHandle { nativeHandle in
+            failOnError {
+                try invokeFnReturningString { fn($0, nativeHandle) }
+            }
+        }
+        guard !codec.isEmpty else { return nil }
+        return codec
+    }
+}
+
+public class SanitizedWebp: NativeHandleOwner<OpaquePointer?> {
+    override internal class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_sanitized_webp_destroy(handle)
+    }
+
+    /// The canvas width of the image in pixels, or 0 if it could not be determined.
+    public var width: UInt32 {
+        self.getInteger(signal_sanitized_webp_get_width)
+    }
+
+    /// The canvas height of the image in pixels, or 0 if it could not be determined.
+    public var height: UInt32 {
+        self.getInteger(signal_sanitized_webp_get_height)
+    }
+
+    /// Whether the image is marked as animated.
+    public var isAnimated: Bool {
+        return withNativeHandle { nativeHandle in
+            failOnError {
+                try invokeFnReturningBool {
+                    signal_sanitized_webp_is_animated($0, nativeHandle)
+                }
+            }
+        }
+    }
+
+    /// The number of animation frames, or 1 for a still image.
+    public var frameCount: UInt32 {
+        self.getInteger(signal_sanitized_webp_get_frame_count)
+    }
+
+    private func getInteger(
+        _ fn: (UnsafeMutablePointer<UInt32>?, OpaquePointer?) -> SignalFfiErrorRef?
+    ) -> UInt32 {
+        return withNativeHandle { nativeHandle in
+            failOnError {
+                try invokeFnReturningInteger { fn($0, nativeHandle) }
+            }
+        }
+    }
 }
 
 #endif
@@ -318,3 +318,172 @@ extension SignalConstPointerMessageBackupValidationOutcome: SignalConstPointer {
         self.raw
     }
 }
+
+/// Summary statistics about the contents of a message backup file.
+///
+/// Collecting statistics only checks that each frame parses; it does not validate the backup the
+/// way ``validateMessageBackup(key:purpose:length:makeStream:)`` does. This makes it suitable for
+/// showing a user what's in a backup before restoring it.
+public class MessageBackupStatistics: NativeHandleOwner<SignalMutPointerMessageBackupStatistics>, @unchecked Sendable {
+    /// Reads a message backup file and summarizes its contents.
+    ///
+    /// - Parameters:
+    ///  - key: The key used to decrypt the backup file.
+    ///  - purpose: Whether the backup is intended for transfer or remote storage.
+    ///  - length: The exact length of the backup file, in bytes.
+    ///  - makeStream: A callback that produces InputStreams needed for backups.
+    ///
+    /// - Throws:
+    ///  - ``SignalError/ioError(_:)``: If an IO error on the input occurs.
+    ///  - ``MessageBackupValidationError``: If the input could not be parsed.
+    public convenience init(
+        key: MessageBackupKey,
+        purpose: MessageBackupPurpose,
+        length: UInt64,
+        makeStream: () throws -> SignalInputStream
+    ) throws {
+        let handle = try withInputStream(try makeStream()) { firstInput in
+            try withInputStream(try makeStream()) { secondInput in
+                try key.withNativeHandle { key in
+                    try invokeFnReturningValueByPointer(.init()) {
+                        signal_message_backup_statistics_collect(
+                            $0,
+                            key.const(),
+                            firstInput,
+                            secondInput,
+                            length,
+                            purpose.rawValue
+                        )
+                    }
+                }
+            }
+        }
+        self.init(owned: NonNull(handle)!)
+    }
+
+    internal required init(owned handle: NonNull<SignalMutPointerMessageBackupStatistics>) {
+        super.init(owned: handle)
+    }
+
+    override internal class func destroyNativeHandle(
+        _ handle: NonNull<SignalMutPointerMessageBackupStatistics>
+    ) -> SignalFfiErrorRef? {
+        signal_message_backup_statistics_destroy(handle.pointer)
+    }
+
+    private func getCount(
+        _ fn: (UnsafeMutablePointer<UInt64>?, SignalConstPointerMessageBackupStatistics) -> SignalFfiErrorRef?
+    ) -> UInt64 {
+        failOnError {
+            try self.withNativeHandle { stats in
+                try invokeFnReturningInteger { fn($0, stats.const()) }
+            }
+        }
+    }
+
+    /// The kinds of frames present in the backup, such as `"ChatItem.StandardMessage"`.
+    public var frameKinds: [String] {
+        failOnError {
+            try self.withNativeHandle { stats in
+                try invokeFnReturningStringArray {
+                    signal_message_backup_statistics_get_frame_kinds($0, stats.const())
+                }
+            }
+        }
+    }
+
+    /// The number of frames of the given kind.
+    public func frameCount(kind: String) -> UInt64 {
+        self.getCount { signal_message_backup_statistics_get_frame_count($0, $1, kind) }
+    }
+
+    /// The total size of all frames of the given kind, once decrypted and decompressed.
+    public func frameBytes(kind: String) -> UInt64 {
+        self.getCount { signal_message_backup_statistics_get_frame_bytes($0, $1, kind) }
+    }
+
+    /// The total size of all frames, once decrypted and decompressed.
+    public var totalFrameBytes: UInt64 {
+        self.getCount(signal_message_backup_statistics_get_total_frame_bytes)
+    }
+
+    /// The size of the backup file the statistics were collected from.
+    public var backupFileBytes: UInt64 {
+        self.getCount(signal_message_backup_statistics_get_backup_file_bytes)
+    }
+
+    /// The ratio of the decompressed frame data to the size of the backup file, or `nil` if the
+    /// file was empty.
+    public var compressionRatio: Double? {
+        let backupFileBytes = self.backupFileBytes
+        if backupFileBytes == 0 {
+            return nil
+        }
+        return Double(self.totalFrameBytes) / Double(backupFileBytes)
+    }
+
+    /// The number of attachments referenced anywhere in the backup.
+    public var attachmentCount: UInt64 {
+        self.getCount(signal_message_backup_statistics_get_attachment_count)
+    }
+
+    /// The sum of the sizes of all attachments, as recorded in the backup.
+    public var attachmentBytes: UInt64 {
+        self.getCount(signal_message_backup_statistics_get_attachment_bytes)
+    }
+
+    /// The number of chats that contain at least one message.
+    public var chatCount: UInt64 {
+        self.getCount(signal_message_backup_statistics_get_chat_count)
+    }
+
+    /// The total number of chat items across all chats.
+    public var messageCount: UInt64 {
+        self.getCount(signal_message_backup_statistics_get_message_count)
+    }
+
+    /// The number of chat items in each chat, keyed by the chat's ID within the backup.
+    public var messageCountsPerChat: [UInt64: UInt64] {
+        var counts: [UInt64: UInt64] = [:]
+        var index: UInt32 = 0
+        while true {
+            // `nil` is bridged as the maximum value.
+            let chatId = self.getCount { signal_message_backup_statistics_get_chat_id_at($0, $1, index) }
+            if chatId == UInt64.max {
+                break
+            }
+            counts[chatId] = self.getCount {
+                signal_message_backup_statistics_get_message_count_for_chat($0, $1, chatId)
+            }
+            index += 1
+        }
+        return counts
+    }
+
+    /// The number of unknown fields and enum values encountered while reading the backup.
+    public var unknownFieldCount: UInt64 {
+        self.getCount(signal_message_backup_statistics_get_unknown_field_count)
+    }
+}
+
+extension SignalMutPointerMessageBackupStatistics: SignalMutPointer {
+    public typealias ConstPointer = SignalConstPointerMessageBackupStatistics
+
+    public init(untyped: OpaquePointer?) {
+        self.init(raw: untyped)
+    }
+
+    public func toOpaque() -> OpaquePointer? {
+        self.raw
+    }
+
+    public func const() -> Self.ConstPointer {
+        Self.ConstPointer(raw: self.raw)
+    }
+}
+
+extension SignalConstPointerMessageBackupStatistics: SignalConstPointer {
+    public func toOpaque() -> OpaquePointer? {
+        self.raw
+    }
+}
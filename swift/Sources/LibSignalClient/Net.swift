@@ -134,6 +134,32 @@ public class Net {
         )
     }
 
+    /// Sets a pluggable transport bridge to be used for all new connections (until overridden).
+    ///
+    /// The app is responsible for running a Pluggable Transports client (such as an obfs4 library)
+    /// that listens for SOCKS5 connections on `localPort`. New connections will ask that client to
+    /// connect to the bridge at `bridgeHost`:`bridgePort`, passing along `bridgeArgs`: the bridge's
+    /// space-separated `key=value` arguments, as found at the end of a bridge line. The proxy can be
+    /// overridden by calling this method again or unset by calling ``Net/clearProxy()``.
+    ///
+    /// Existing connections and services will continue with the setting they were created with.
+    /// (In particular, changing this setting will not affect any existing ``ChatConnection``s.)
+    ///
+    /// - Throws: if the ports are not valid, or if the bridge arguments are malformed or too long.
+    public func setPluggableTransportProxy(
+        localPort: UInt16,
+        bridgeHost: String,
+        bridgePort: UInt16,
+        bridgeArgs: String
+    ) throws {
+        try self.connectionManager.setPluggableTransportProxy(
+            localPort: localPort,
+            bridgeHost: bridgeHost,
+            bridgePort: bridgePort,
+            bridgeArgs: bridgeArgs
+        )
+    }
+
     /// Refuses to make any new connections until a new proxy configuration is set or
     /// ``Net/clearProxy()`` is called.
     ///
@@ -208,6 +234,19 @@ public class Net {
         }
     }
 
+    /// Summarizes connection attempts made since the last network change.
+    ///
+    /// The report contains only aggregate counts, so it's suitable for uploading.
+    public func connectivityReport() -> ConnectivityReport {
+        failOnError {
+            try self.connectionManager.withNativeHandle { connectionManager in
+                try invokeFnReturningNativeHandle {
+                    signal_connection_manager_get_connectivity_report($0, connectionManager.const())
+                }
+            }
+        }
+    }
+
     /// Get the SVR-B (Secure Value Recovery for Backups) service for this network instance.
     ///
     /// SVR-B provides forward secrecy for Signal backups, ensuring that even if the user's
@@ -492,17 +531,44 @@ internal class ConnectionManager: NativeHandleOwner<SignalMutPointerConnectionMa
                 }
             }
 
-            try proxyConfig.withNativeHandle { proxyConfig in
-                try self.withNativeHandle {
-                    try checkError(signal_connection_manager_set_proxy($0.const(), proxyConfig.const()))
-                }
+            try self.setProxyConfig(proxyConfig)
+        } catch {
+            self.setInvalidProxy()
+            throw error
+        }
+    }
+
+    internal func setPluggableTransportProxy(
+        localPort: UInt16,
+        bridgeHost: String,
+        bridgePort: UInt16,
+        bridgeArgs: String
+    ) throws {
+        do {
+            let proxyConfig: ProxyConfig = try invokeFnReturningNativeHandle {
+                signal_connection_proxy_config_new_pluggable_transport(
+                    $0,
+                    Int32(localPort),
+                    bridgeHost,
+                    Int32(bridgePort),
+                    bridgeArgs
+                )
             }
+            try self.setProxyConfig(proxyConfig)
         } catch {
             self.setInvalidProxy()
             throw error
         }
     }
 
+    private func setProxyConfig(_ proxyConfig: ProxyConfig) throws {
+        try proxyConfig.withNativeHandle { proxyConfig in
+            try self.withNativeHandle {
+                try checkError(signal_connection_manager_set_proxy($0.const(), proxyConfig.const()))
+            }
+        }
+    }
+
     internal func setInvalidProxy() {
         self.withNativeHandle {
             failOnError(signal_connection_manager_set_invalid_proxy($0.const()))
@@ -583,6 +583,10 @@ typedef struct SignalConnectionManager SignalConnectionManager;
 typedef const SignalConnectionManager* SignalType_ConstPointer_SignalConnectionManager;
 static_assert_64bit(sizeof(SignalType_ConstPointer_SignalConnectionManager) == 8);
 static_assert_64bit(alignof(SignalType_ConstPointer_SignalConnectionManager) == 8);
+typedef struct SignalConnectivityReport SignalConnectivityReport;
+typedef const SignalConnectivityReport* SignalType_ConstPointer_SignalConnectivityReport;
+static_assert_64bit(sizeof(SignalType_ConstPointer_SignalConnectivityReport) == 8);
+static_assert_64bit(alignof(SignalType_ConstPointer_SignalConnectivityReport) == 8);
 typedef SignalType_ConstPointer_SignalConnectionManager (*SignalType_FunctionPointer_SignalType_ConstPointer_SignalConnectionManager_SignalType_MutPointer_void)(SignalType_MutPointer_void);
 static_assert_64bit(sizeof(SignalType_FunctionPointer_SignalType_ConstPointer_SignalConnectionManager_SignalType_MutPointer_void) == 8);
 static_assert_64bit(alignof(SignalType_FunctionPointer_SignalType_ConstPointer_SignalConnectionManager_SignalType_MutPointer_void) == 8);
@@ -644,6 +648,10 @@ typedef struct SignalMessageBackupValidationOutcome SignalMessageBackupValidatio
 typedef const SignalMessageBackupValidationOutcome* SignalType_ConstPointer_SignalMessageBackupValidationOutcome;
 static_assert_64bit(sizeof(SignalType_ConstPointer_SignalMessageBackupValidationOutcome) == 8);
 static_assert_64bit(alignof(SignalType_ConstPointer_SignalMessageBackupValidationOutcome) == 8);
+typedef struct SignalMessageBackupStatistics SignalMessageBackupStatistics;
+typedef const SignalMessageBackupStatistics* SignalType_ConstPointer_SignalMessageBackupStatistics;
+static_assert_64bit(sizeof(SignalType_ConstPointer_SignalMessageBackupStatistics) == 8);
+static_assert_64bit(alignof(SignalType_ConstPointer_SignalMessageBackupStatistics) == 8);
 typedef const SignalCdsiLookup* SignalType_ConstPointer_SignalCdsiLookup;
 static_assert_64bit(sizeof(SignalType_ConstPointer_SignalCdsiLookup) == 8);
 static_assert_64bit(alignof(SignalType_ConstPointer_SignalCdsiLookup) == 8);
@@ -1467,6 +1475,18 @@ static_assert_64bit(alignof(SignalMutPointerMessageBackupValidationOutcome) == 8
 typedef SignalMutPointerMessageBackupValidationOutcome* SignalType_MutPointer_SignalMutPointerMessageBackupValidationOutcome;
 static_assert_64bit(sizeof(SignalType_MutPointer_SignalMutPointerMessageBackupValidationOutcome) == 8);
 static_assert_64bit(alignof(SignalType_MutPointer_SignalMutPointerMessageBackupValidationOutcome) == 8);
+typedef SignalMessageBackupStatistics* SignalType_MutPointer_SignalMessageBackupStatistics;
+static_assert_64bit(sizeof(SignalType_MutPointer_SignalMessageBackupStatistics) == 8);
+static_assert_64bit(alignof(SignalType_MutPointer_SignalMessageBackupStatistics) == 8);
+typedef struct {
+  SignalMessageBackupStatistics* raw;
+} SignalMutPointerMessageBackupStatistics;
+static_assert_64bit(offsetof(SignalMutPointerMessageBackupStatistics, raw) == 0);
+static_assert_64bit(sizeof(SignalMutPointerMessageBackupStatistics) == 8);
+static_assert_64bit(alignof(SignalMutPointerMessageBackupStatistics) == 8);
+typedef SignalMutPointerMessageBackupStatistics* SignalType_MutPointer_SignalMutPointerMessageBackupStatistics;
+static_assert_64bit(sizeof(SignalType_MutPointer_SignalMutPointerMessageBackupStatistics) == 8);
+static_assert_64bit(alignof(SignalType_MutPointer_SignalMutPointerMessageBackupStatistics) == 8);
 typedef struct SignalOnlineBackupValidator SignalOnlineBackupValidator;
 typedef SignalOnlineBackupValidator* SignalType_MutPointer_SignalOnlineBackupValidator;
 static_assert_64bit(sizeof(SignalType_MutPointer_SignalOnlineBackupValidator) == 8);
@@ -1492,6 +1512,18 @@ static_assert_64bit(alignof(SignalMutPointerConnectionManager) == 8);
 typedef SignalMutPointerConnectionManager* SignalType_MutPointer_SignalMutPointerConnectionManager;
 static_assert_64bit(sizeof(SignalType_MutPointer_SignalMutPointerConnectionManager) == 8);
 static_assert_64bit(alignof(SignalType_MutPointer_SignalMutPointerConnectionManager) == 8);
+typedef SignalConnectivityReport* SignalType_MutPointer_SignalConnectivityReport;
+static_assert_64bit(sizeof(SignalType_MutPointer_SignalConnectivityReport) == 8);
+static_assert_64bit(alignof(SignalType_MutPointer_SignalConnectivityReport) == 8);
+typedef struct {
+  SignalConnectivityReport* raw;
+} SignalMutPointerConnectivityReport;
+static_assert_64bit(offsetof(SignalMutPointerConnectivityReport, raw) == 0);
+static_assert_64bit(sizeof(SignalMutPointerConnectivityReport) == 8);
+static_assert_64bit(alignof(SignalMutPointerConnectivityReport) == 8);
+typedef SignalMutPointerConnectivityReport* SignalType_MutPointer_SignalMutPointerConnectivityReport;
+static_assert_64bit(sizeof(SignalType_MutPointer_SignalMutPointerConnectivityReport) == 8);
+static_assert_64bit(alignof(SignalType_MutPointer_SignalMutPointerConnectivityReport) == 8);
 typedef SignalLookupRequest* SignalType_MutPointer_SignalLookupRequest;
 static_assert_64bit(sizeof(SignalType_MutPointer_SignalLookupRequest) == 8);
 static_assert_64bit(alignof(SignalType_MutPointer_SignalLookupRequest) == 8);
@@ -2559,12 +2591,24 @@ typedef struct {
 static_assert_64bit(offsetof(SignalConstPointerMessageBackupValidationOutcome, raw) == 0);
 static_assert_64bit(sizeof(SignalConstPointerMessageBackupValidationOutcome) == 8);
 static_assert_64bit(alignof(SignalConstPointerMessageBackupValidationOutcome) == 8);
+typedef struct {
+  const SignalMessageBackupStatistics* raw;
+} SignalConstPointerMessageBackupStatistics;
+static_assert_64bit(offsetof(SignalConstPointerMessageBackupStatistics, raw) == 0);
+static_assert_64bit(sizeof(SignalConstPointerMessageBackupStatistics) == 8);
+static_assert_64bit(alignof(SignalConstPointerMessageBackupStatistics) == 8);
 typedef struct {
   const SignalConnectionManager* raw;
 } SignalConstPointerConnectionManager;
 static_assert_64bit(offsetof(SignalConstPointerConnectionManager, raw) == 0);
 static_assert_64bit(sizeof(SignalConstPointerConnectionManager) == 8);
 static_assert_64bit(alignof(SignalConstPointerConnectionManager) == 8);
+typedef struct {
+  const SignalConnectivityReport* raw;
+} SignalConstPointerConnectivityReport;
+static_assert_64bit(offsetof(SignalConstPointerConnectivityReport, raw) == 0);
+static_assert_64bit(sizeof(SignalConstPointerConnectivityReport) == 8);
+static_assert_64bit(alignof(SignalConstPointerConnectivityReport) == 8);
 typedef struct {
   const SignalCdsiLookup* raw;
 } SignalConstPointerCdsiLookup;
@@ -3667,6 +3711,10 @@ SignalFfiError* signal_connection_manager_clear_proxy(
 SignalFfiError* signal_connection_manager_destroy(
   SignalMutPointerConnectionManager p
 );
+SignalFfiError* signal_connection_manager_get_connectivity_report(
+  SignalMutPointerConnectivityReport* out,
+  SignalConstPointerConnectionManager connection_manager
+);
 SignalFfiError* signal_connection_manager_new(
   SignalMutPointerConnectionManager* out,
   uint8_t environment,
@@ -3708,6 +3756,59 @@ SignalFfiError* signal_connection_proxy_config_new(
   const int8_t* username,
   const int8_t* password
 );
+SignalFfiError* signal_connection_proxy_config_new_pluggable_transport(
+  SignalMutPointerConnectionProxyConfig* out,
+  int32_t local_port,
+  const int8_t* bridge_host,
+  int32_t bridge_port,
+  const int8_t* bridge_args
+);
+SignalFfiError* signal_connectivity_report_collection_period_seconds(
+  uint64_t* out,
+  SignalConstPointerConnectivityReport report
+);
+SignalFfiError* signal_connectivity_report_destroy(
+  SignalMutPointerConnectivityReport p
+);
+SignalFfiError* signal_connectivity_report_dns_doh_lookups(
+  uint64_t* out,
+  SignalConstPointerConnectivityReport report
+);
+SignalFfiError* signal_connectivity_report_dns_dot_lookups(
+  uint64_t* out,
+  SignalConstPointerConnectivityReport report
+);
+SignalFfiError* signal_connectivity_report_dns_static_lookups(
+  uint64_t* out,
+  SignalConstPointerConnectivityReport report
+);
+SignalFfiError* signal_connectivity_report_dns_system_lookups(
+  uint64_t* out,
+  SignalConstPointerConnectivityReport report
+);
+SignalFfiError* signal_connectivity_report_latency_bucket(
+  uint64_t* out,
+  SignalConstPointerConnectivityReport report,
+  const int8_t* kind,
+  uint32_t bucket
+);
+SignalFfiError* signal_connectivity_report_latency_bucket_count(
+  uint32_t* out
+);
+SignalFfiError* signal_connectivity_report_route_failures(
+  uint64_t* out,
+  SignalConstPointerConnectivityReport report,
+  const int8_t* kind
+);
+SignalFfiError* signal_connectivity_report_route_kinds(
+  SignalBytestringArray* out,
+  SignalConstPointerConnectivityReport report
+);
+SignalFfiError* signal_connectivity_report_route_successes(
+  uint64_t* out,
+  SignalConstPointerConnectivityReport report,
+  const int8_t* kind
+);
 SignalFfiError* signal_copy_backup_media_stream_cancel(
   SignalConstPointerCopyBackupMediaStream stream
 );
@@ -3845,6 +3946,11 @@ SignalFfiError* signal_delete_backup_media_stream_next(
   SignalConstPointerTokioAsyncContext async_runtime,
   SignalConstPointerDeleteBackupMediaStream stream
 );
+SignalFfiError* signal_device_transfer_certificate_matches_fingerprint(
+  bool* out,
+  SignalBorrowedBuffer certificate,
+  SignalBorrowedBuffer fingerprint
+);
 SignalFfiError* signal_device_transfer_generate_certificate(
   SignalOwnedBuffer* out,
   SignalBorrowedBuffer private_key,
@@ -3858,6 +3964,15 @@ SignalFfiError* signal_device_transfer_generate_private_key_with_format(
   SignalOwnedBuffer* out,
   uint8_t key_format
 );
+SignalFfiError* signal_device_transfer_generate_private_key_with_type(
+  SignalOwnedBuffer* out,
+  uint8_t key_type,
+  uint8_t key_format
+);
+SignalFfiError* signal_device_transfer_get_certificate_fingerprint(
+  SignalType_FixedArray32_uint8_t* out,
+  SignalBorrowedBuffer certificate
+);
 SignalFfiError* signal_donation_permit_check_valid_contents(
   SignalBorrowedBuffer buffer
 );
@@ -4514,6 +4629,69 @@ SignalFfiError* signal_message_backup_key_get_hmac_key(
   SignalType_FixedArray32_uint8_t* out,
   SignalConstPointerMessageBackupKey key
 );
+SignalFfiError* signal_message_backup_statistics_collect(
+  SignalMutPointerMessageBackupStatistics* out,
+  SignalConstPointerMessageBackupKey key,
+  SignalConstPointerFfiSyncInputStreamStruct first_stream,
+  SignalConstPointerFfiSyncInputStreamStruct second_stream,
+  uint64_t len,
+  uint8_t purpose
+);
+SignalFfiError* signal_message_backup_statistics_destroy(
+  SignalMutPointerMessageBackupStatistics p
+);
+SignalFfiError* signal_message_backup_statistics_get_attachment_bytes(
+  uint64_t* out,
+  SignalConstPointerMessageBackupStatistics stats
+);
+SignalFfiError* signal_message_backup_statistics_get_attachment_count(
+  uint64_t* out,
+  SignalConstPointerMessageBackupStatistics stats
+);
+SignalFfiError* signal_message_backup_statistics_get_backup_file_bytes(
+  uint64_t* out,
+  SignalConstPointerMessageBackupStatistics stats
+);
+SignalFfiError* signal_message_backup_statistics_get_chat_count(
+  uint64_t* out,
+  SignalConstPointerMessageBackupStatistics stats
+);
+SignalFfiError* signal_message_backup_statistics_get_chat_id_at(
+  uint64_t* out,
+  SignalConstPointerMessageBackupStatistics stats,
+  uint32_t index
+);
+SignalFfiError* signal_message_backup_statistics_get_frame_bytes(
+  uint64_t* out,
+  SignalConstPointerMessageBackupStatistics stats,
+  const int8_t* kind
+);
+SignalFfiError* signal_message_backup_statistics_get_frame_count(
+  uint64_t* out,
+  SignalConstPointerMessageBackupStatistics stats,
+  const int8_t* kind
+);
+SignalFfiError* signal_message_backup_statistics_get_frame_kinds(
+  SignalBytestringArray* out,
+  SignalConstPointerMessageBackupStatistics stats
+);
+SignalFfiError* signal_message_backup_statistics_get_message_count(
+  uint64_t* out,
+  SignalConstPointerMessageBackupStatistics stats
+);
+SignalFfiError* signal_message_backup_statistics_get_message_count_for_chat(
+  uint64_t* out,
+  SignalConstPointerMessageBackupStatistics stats,
+  uint64_t chat_id
+);
+SignalFfiError* signal_message_backup_statistics_get_total_frame_bytes(
+  uint64_t* out,
+  SignalConstPointerMessageBackupStatistics stats
+);
+SignalFfiError* signal_message_backup_statistics_get_unknown_field_count(
+  uint64_t* out,
+  SignalConstPointerMessageBackupStatistics stats
+);
 SignalFfiError* signal_message_backup_validation_outcome_destroy(
   SignalMutPointerMessageBackupValidationOutcome p
 );
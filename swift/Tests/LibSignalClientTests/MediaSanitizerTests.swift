@@ -51,6 +51,24 @@ class Mp4SanitizerTests: TestCaseBase {
         )
     }
 
+    func testMinimalMp4WithInfo() throws {
+        let metadata = ftyp() + moov()
+        let input = ftyp() + mdat() + moov()
+
+        let sanitized = try sanitizeMp4WithInfo(input: SignalInputStreamAdapter(input), len: UInt64(input.count))
+        assertSanitizedMetadataEqual(
+            sanitized,
+            dataOffset: ftyp().count,
+            dataLen: input.count - metadata.count,
+            metadata: metadata
+        )
+        XCTAssertNil(sanitized.durationMillis)
+        XCTAssertEqual(sanitized.videoWidth, 0)
+        XCTAssertEqual(sanitized.videoHeight, 0)
+        XCTAssertNil(sanitized.videoCodec)
+        XCTAssertNil(sanitized.audioCodec)
+    }
+
     func testMp4IoError() throws {
         XCTAssertThrowsError(try sanitizeMp4(input: ErrorInputStream(), len: 1)) { error in
             if case SignalError.ioError = error {} else { XCTFail("\(error)") }
@@ -78,6 +96,15 @@ class WebpSanitizerTests: TestCaseBase {
         try sanitizeWebp(input: SignalInputStreamAdapter(input))
     }
 
+    func testMinimalWebpWithInfo() throws {
+        let input = webp()
+        let sanitized = try sanitizeWebpWithInfo(input: SignalInputStreamAdapter(input))
+        XCTAssertEqual(sanitized.width, 1)
+        XCTAssertEqual(sanitized.height, 1)
+        XCTAssertFalse(sanitized.isAnimated)
+        XCTAssertEqual(sanitized.frameCount, 1)
+    }
+
     func testWebpIoError() throws {
         XCTAssertThrowsError(try sanitizeWebp(input: ErrorInputStream(), len: 1)) { error in
             if case SignalError.ioError = error {} else { XCTFail("\(error)") }
@@ -25,6 +25,26 @@ class MessageBackupTests: TestCaseBase {
     }
     #endif
 
+    #if !os(iOS) || targetEnvironment(simulator)
+    func testCollectStatistics() throws {
+        let validBackupContents = readResource(forName: "new_account.binproto.encrypted")
+
+        let stats = try MessageBackupStatistics(
+            key: MessageBackupKey.testKey(),
+            purpose: .remoteBackup,
+            length: UInt64(validBackupContents.count),
+            makeStream: { SignalInputStreamAdapter(validBackupContents) }
+        )
+        XCTAssert(stats.frameKinds.contains("BackupInfo"))
+        XCTAssertEqual(stats.frameCount(kind: "BackupInfo"), 1)
+        XCTAssertEqual(stats.frameCount(kind: "not a frame kind"), 0)
+        XCTAssertEqual(stats.backupFileBytes, UInt64(validBackupContents.count))
+        XCTAssertNotNil(stats.compressionRatio)
+        XCTAssertEqual(UInt64(stats.messageCountsPerChat.count), stats.chatCount)
+        XCTAssertEqual(stats.unknownFieldCount, 0)
+    }
+    #endif
+
     func testDerivingKeyWithForwardSecrecyToken() {
         let accountEntropy = String(repeating: "m", count: 64)
         let uuid: uuid_t = (
@@ -484,6 +484,19 @@ class PublicAPITests: TestCaseBase {
         }
     }
 
+    func testDeviceTransferKeyTypes() throws {
+        for keyType in DeviceTransferKeyType.allCases {
+            let deviceKey = DeviceTransferKey.generate(type: keyType)
+            let cert = deviceKey.generateCertificate("name", 30)
+            XCTAssertEqual(cert[0], 0x30)
+
+            let fingerprint = try DeviceTransferKey.certificateFingerprint(cert)
+            XCTAssertEqual(fingerprint.count, 32)
+            XCTAssert(try DeviceTransferKey.certificate(cert, matchesFingerprint: fingerprint))
+            XCTAssertFalse(try DeviceTransferKey.certificate(cert, matchesFingerprint: Data(count: 32)))
+        }
+    }
+
     func testSignAlternateIdentity() {
         let primary = IdentityKeyPair.generate()
         let secondary = IdentityKeyPair.generate()